@@ -0,0 +1,595 @@
+//! Typed clients of the example contracts, wrapping their store, instantiate, execute & query over a node's tendermint
+//! RPC endpoint, so that tools & integrators driving the contracts from Rust share one surface.
+//!
+//! Txs are built with `cosmrs`, signed by the `SigningKey` of the connection, simulated to size their gas & broadcast
+//! to the node, returning once they are included in a block. Queries are ABCI queries of the same node. The clients
+//! block on a runtime owned by the connection, so they can be driven from synchronous code.
+//!
+//! ```ignore
+//! let conn = Conn::connect("http://localhost:26657", signing_key, GasPrice::new(0.025, "untrn"))?;
+//!
+//! let code_id = RoundtripClient::store(&conn, fs::read(Roundtrip::ARTIFACT)?)?;
+//!
+//! let roundtrip = RoundtripClient::instantiate(&conn, code_id, "roundtrip", &init_msg, &[])?;
+//!
+//! roundtrip.execute(&conn, &ExecuteMsg::SetupIca {}, &fee)?;
+//!
+//! let metadata = roundtrip.ica_metadata(&conn, conn.address())?;
+//! ```
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::missing_errors_doc)]
+
+use std::{marker::PhantomData, time::Duration};
+
+use anyhow::{anyhow, ensure, Result};
+use cosmrs::{
+    cosmwasm::{MsgExecuteContract, MsgInstantiateContract, MsgStoreCode},
+    crypto::secp256k1::SigningKey,
+    proto::{
+        cosmos::{
+            auth::v1beta1::{BaseAccount, QueryAccountRequest, QueryAccountResponse},
+            tx::v1beta1::{SimulateRequest, SimulateResponse},
+        },
+        cosmwasm::wasm::v1::{QuerySmartContractStateRequest, QuerySmartContractStateResponse},
+    },
+    rpc::{Client as _, HttpClient},
+    tendermint::{abci::Event, chain, Hash},
+    tx::{self, Fee, Msg, SignDoc, SignerInfo},
+    AccountId, Any, ErrorReport,
+};
+use cosmwasm_std::Coin;
+use prost::Message;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::runtime::Runtime;
+
+/// The bech32 prefix of neutron addresses
+const ACCOUNT_PREFIX: &str = "neutron";
+
+/// The gas limit of a tx is the gas used by its simulation scaled by this factor
+const GAS_ADJUSTMENT: f64 = 1.5;
+
+/// How long [`Conn::wait_for_tx`] waits between lookups of the tx, & how many times it looks it up
+const TX_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const TX_POLL_ATTEMPTS: u32 = 60;
+
+/// The price of gas paid by the txs of a connection, in `denom`
+#[derive(Debug, Clone)]
+pub struct GasPrice {
+    pub amount: f64,
+    pub denom: String,
+}
+
+impl GasPrice {
+    #[must_use]
+    pub fn new(amount: f64, denom: impl Into<String>) -> Self {
+        Self {
+            amount,
+            denom: denom.into(),
+        }
+    }
+}
+
+/// A connection to a node's tendermint RPC endpoint, signing txs with `signing_key`
+pub struct Conn {
+    rpc: HttpClient,
+    runtime: Runtime,
+    chain_id: chain::Id,
+    signing_key: SigningKey,
+    sender: AccountId,
+    gas_price: GasPrice,
+}
+
+/// `cosmrs` reports its errors as `eyre` reports, which are not `std` errors
+fn report(err: ErrorReport) -> anyhow::Error {
+    anyhow!("{err:?}")
+}
+
+fn cosmos_coins(coins: &[Coin]) -> Result<Vec<cosmrs::Coin>> {
+    coins
+        .iter()
+        .map(|coin| {
+            Ok(cosmrs::Coin {
+                denom: coin.denom.parse().map_err(report)?,
+                amount: coin.amount.u128(),
+            })
+        })
+        .collect()
+}
+
+/// The value of the attribute `key` of the first event of `kind` carrying it
+fn event_attribute(events: &[Event], kind: &str, key: &str) -> Option<String> {
+    events
+        .iter()
+        .filter(|event| event.kind == kind)
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.clone())
+}
+
+impl Conn {
+    /// Connect to the node serving RPC at `rpc_url`, the chain id being the one the node reports
+    pub fn connect(rpc_url: &str, signing_key: SigningKey, gas_price: GasPrice) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let rpc = HttpClient::new(rpc_url)?;
+
+        let chain_id = runtime.block_on(rpc.status())?.node_info.network;
+
+        let sender = signing_key
+            .public_key()
+            .account_id(ACCOUNT_PREFIX)
+            .map_err(report)?;
+
+        Ok(Self {
+            rpc,
+            runtime,
+            chain_id,
+            signing_key,
+            sender,
+            gas_price,
+        })
+    }
+
+    /// The address txs are sent from
+    #[must_use]
+    pub fn address(&self) -> &str {
+        self.sender.as_ref()
+    }
+
+    /// Query the gRPC method at `path` with `req` over ABCI
+    fn abci_query<Req: Message, Res: Message + Default>(
+        &self,
+        path: &str,
+        req: &Req,
+    ) -> Result<Res> {
+        let res = self.runtime.block_on(self.rpc.abci_query(
+            Some(path.to_owned()),
+            req.encode_to_vec(),
+            None,
+            false,
+        ))?;
+
+        ensure!(res.code.is_ok(), "{path} query failed: {}", res.log);
+
+        Ok(Res::decode(res.value.as_slice())?)
+    }
+
+    fn account(&self) -> Result<BaseAccount> {
+        let res: QueryAccountResponse = self.abci_query(
+            "/cosmos.auth.v1beta1.Query/Account",
+            &QueryAccountRequest {
+                address: self.sender.to_string(),
+            },
+        )?;
+
+        let account = res
+            .account
+            .ok_or_else(|| anyhow!("account {} not found", self.sender))?;
+
+        Ok(BaseAccount::decode(account.value.as_slice())?)
+    }
+
+    /// Sign `body` as the next tx of `account` with the fee of `gas_limit` at the gas price of the connection
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn sign(&self, body: &tx::Body, account: &BaseAccount, gas_limit: u64) -> Result<Vec<u8>> {
+        let fee = cosmrs::Coin {
+            denom: self.gas_price.denom.parse().map_err(report)?,
+            amount: (gas_limit as f64 * self.gas_price.amount).ceil() as u128,
+        };
+
+        let auth_info =
+            SignerInfo::single_direct(Some(self.signing_key.public_key()), account.sequence)
+                .auth_info(Fee::from_amount_and_gas(fee, gas_limit));
+
+        let sign_doc = SignDoc::new(body, &auth_info, &self.chain_id, account.account_number)
+            .map_err(report)?;
+
+        sign_doc
+            .sign(&self.signing_key)
+            .and_then(|tx| tx.to_bytes())
+            .map_err(report)
+    }
+
+    /// The gas limit of the tx carrying `body`, from the gas its simulation used
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn gas_limit(&self, body: &tx::Body, account: &BaseAccount) -> Result<u64> {
+        let res: SimulateResponse = self.abci_query(
+            "/cosmos.tx.v1beta1.Service/Simulate",
+            &SimulateRequest {
+                tx_bytes: self.sign(body, account, 0)?,
+                ..SimulateRequest::default()
+            },
+        )?;
+
+        let gas_used = res
+            .gas_info
+            .ok_or_else(|| anyhow!("the simulation reported no gas info"))?
+            .gas_used;
+
+        Ok((gas_used as f64 * GAS_ADJUSTMENT).ceil() as u64)
+    }
+
+    /// Sign & broadcast a tx of `msgs`, returning the events it emitted once included in a block
+    pub fn send(&self, msgs: Vec<Any>) -> Result<Vec<Event>> {
+        let account = self.account()?;
+
+        let body = tx::Body::new(msgs, "", 0u32);
+
+        let gas_limit = self.gas_limit(&body, &account)?;
+
+        let tx_bytes = self.sign(&body, &account, gas_limit)?;
+
+        let res = self
+            .runtime
+            .block_on(self.rpc.broadcast_tx_commit(tx_bytes))?;
+
+        ensure!(
+            res.check_tx.code.is_ok(),
+            "tx {} rejected: {}",
+            res.hash,
+            res.check_tx.log
+        );
+
+        ensure!(
+            res.tx_result.code.is_ok(),
+            "tx {} failed: {}",
+            res.hash,
+            res.tx_result.log
+        );
+
+        Ok(res.tx_result.events)
+    }
+
+    /// Sign a tx of each of `txs` as the next txs of the sender, in order, & broadcast them back to back without waiting
+    /// for their inclusion, so they can land in the same block. The txs cannot be simulated before the previous ones
+    /// are included, so each is given `gas_limit`. Returns the hashes of the txs once the node accepted them into its
+    /// mempool, see [`Conn::wait_for_tx`]
+    pub fn broadcast(&self, txs: Vec<Vec<Any>>, gas_limit: u64) -> Result<Vec<Hash>> {
+        let mut account = self.account()?;
+
+        let mut signed_txs = vec![];
+
+        for msgs in txs {
+            let body = tx::Body::new(msgs, "", 0u32);
+
+            signed_txs.push(self.sign(&body, &account, gas_limit)?);
+
+            account.sequence += 1;
+        }
+
+        let mut hashes = vec![];
+
+        for tx_bytes in signed_txs {
+            let res = self
+                .runtime
+                .block_on(self.rpc.broadcast_tx_sync(tx_bytes))?;
+
+            ensure!(res.code.is_ok(), "tx {} rejected: {}", res.hash, res.log);
+
+            hashes.push(res.hash);
+        }
+
+        Ok(hashes)
+    }
+
+    /// Wait for the tx `hash` to be included in a block, returning the height of the block
+    pub fn wait_for_tx(&self, hash: Hash) -> Result<u64> {
+        for _ in 0..TX_POLL_ATTEMPTS {
+            let Ok(res) = self.runtime.block_on(self.rpc.tx(hash, false)) else {
+                std::thread::sleep(TX_POLL_INTERVAL);
+
+                continue;
+            };
+
+            ensure!(
+                res.tx_result.code.is_ok(),
+                "tx {hash} failed: {}",
+                res.tx_result.log
+            );
+
+            return Ok(res.height.value());
+        }
+
+        Err(anyhow!("tx {hash} was not included in a block"))
+    }
+}
+
+/// The message types & build artifact of an example contract
+pub trait Contract {
+    /// The path of the optimized build of the contract, relative to the workspace root
+    const ARTIFACT: &'static str;
+
+    type InstantiateMsg: Serialize;
+    type ExecuteMsg: Serialize;
+    type QueryMsg: Serialize;
+}
+
+pub enum Roundtrip {}
+
+impl Contract for Roundtrip {
+    const ARTIFACT: &'static str = "artifacts/ibc_transfer_roundtrip.wasm";
+
+    type InstantiateMsg = ibc_transfer_roundtrip::msgs::InstantiateMsg;
+    type ExecuteMsg = ibc_transfer_roundtrip::msgs::ExecuteMsg;
+    type QueryMsg = ibc_transfer_roundtrip::msgs::QueryMsg;
+}
+
+pub enum MultiIca {}
+
+impl Contract for MultiIca {
+    const ARTIFACT: &'static str = "artifacts/multiple_ica_icq.wasm";
+
+    type InstantiateMsg = multiple_ica_icq::msgs::InstantiateMsg;
+    type ExecuteMsg = multiple_ica_icq::msgs::ExecuteMsg;
+    type QueryMsg = multiple_ica_icq::msgs::QueryMsg;
+}
+
+/// A client of an instance of the contract `C`
+pub struct Client<C> {
+    address: String,
+    contract: PhantomData<C>,
+}
+
+pub type RoundtripClient = Client<Roundtrip>;
+
+pub type MultiIcaClient = Client<MultiIca>;
+
+impl<C: Contract> Client<C> {
+    /// A client of the instance of `C` at `address`
+    #[must_use]
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            contract: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Store `wasm`, a build of `C` such as its [`Contract::ARTIFACT`], returning its code id
+    pub fn store(conn: &Conn, wasm: Vec<u8>) -> Result<u64> {
+        let msg = MsgStoreCode {
+            sender: conn.sender.clone(),
+            wasm_byte_code: wasm,
+            instantiate_permission: None,
+        };
+
+        let events = conn.send(vec![msg.to_any().map_err(report)?])?;
+
+        let code_id = event_attribute(&events, "store_code", "code_id")
+            .ok_or_else(|| anyhow!("no code id in the store code events"))?;
+
+        Ok(code_id.parse()?)
+    }
+
+    /// Instantiate the code `code_id` of `C` with `funds` attached, returning a client of the new instance. The
+    /// instance is administered by the sender of the connection, who may migrate it
+    pub fn instantiate(
+        conn: &Conn,
+        code_id: u64,
+        label: &str,
+        msg: &C::InstantiateMsg,
+        funds: &[Coin],
+    ) -> Result<Self> {
+        let msg = MsgInstantiateContract {
+            sender: conn.sender.clone(),
+            admin: Some(conn.sender.clone()),
+            code_id,
+            label: Some(label.to_owned()),
+            msg: cosmwasm_std::to_vec(msg)?,
+            funds: cosmos_coins(funds)?,
+        };
+
+        let events = conn.send(vec![msg.to_any().map_err(report)?])?;
+
+        let address = event_attribute(&events, "instantiate", "_contract_address")
+            .ok_or_else(|| anyhow!("no contract address in the instantiate events"))?;
+
+        Ok(Self::new(address))
+    }
+
+    /// The msg executing `msg` with `funds` attached, for txs sent with [`Conn::broadcast`]
+    pub fn execute_msg(&self, conn: &Conn, msg: &C::ExecuteMsg, funds: &[Coin]) -> Result<Any> {
+        let msg = MsgExecuteContract {
+            sender: conn.sender.clone(),
+            contract: self.address.parse().map_err(report)?,
+            msg: cosmwasm_std::to_vec(msg)?,
+            funds: cosmos_coins(funds)?,
+        };
+
+        msg.to_any().map_err(report)
+    }
+
+    /// Execute `msg` with `funds` attached
+    pub fn execute(&self, conn: &Conn, msg: &C::ExecuteMsg, funds: &[Coin]) -> Result<()> {
+        conn.send(vec![self.execute_msg(conn, msg, funds)?])?;
+
+        Ok(())
+    }
+
+    /// Query `msg`, the response type being the one the contract answers `msg` with
+    pub fn query<R: DeserializeOwned>(&self, conn: &Conn, msg: &C::QueryMsg) -> Result<R> {
+        let res: QuerySmartContractStateResponse = conn.abci_query(
+            "/cosmwasm.wasm.v1.Query/SmartContractState",
+            &QuerySmartContractStateRequest {
+                address: self.address.clone(),
+                query_data: cosmwasm_std::to_vec(msg)?,
+            },
+        )?;
+
+        Ok(cosmwasm_std::from_slice(&res.data)?)
+    }
+}
+
+impl RoundtripClient {
+    pub fn ica_metadata(
+        &self,
+        conn: &Conn,
+        owner: impl Into<String>,
+    ) -> Result<ibc_transfer_roundtrip::msgs::IcaMetadataResponse> {
+        self.query(
+            conn,
+            &ibc_transfer_roundtrip::msgs::QueryMsg::IcaMetadata {
+                owner: owner.into(),
+            },
+        )
+    }
+
+    pub fn ica_last_balance(
+        &self,
+        conn: &Conn,
+        owner: impl Into<String>,
+    ) -> Result<ibc_transfer_roundtrip::msgs::IcaLastBalanceResponse> {
+        self.query(
+            conn,
+            &ibc_transfer_roundtrip::msgs::QueryMsg::IcaLastBalance {
+                owner: owner.into(),
+            },
+        )
+    }
+
+    pub fn ica_tx_status(
+        &self,
+        conn: &Conn,
+        owner: impl Into<String>,
+    ) -> Result<ibc_transfer_roundtrip::msgs::IcaTxStatusResponse> {
+        self.query(
+            conn,
+            &ibc_transfer_roundtrip::msgs::QueryMsg::IcaTxStatus {
+                owner: owner.into(),
+            },
+        )
+    }
+
+    pub fn error_codes(
+        &self,
+        conn: &Conn,
+    ) -> Result<ibc_transfer_roundtrip::msgs::ErrorCodesResponse> {
+        self.query(conn, &ibc_transfer_roundtrip::msgs::QueryMsg::ErrorCodes {})
+    }
+}
+
+impl MultiIcaClient {
+    pub fn ica_set_size(&self, conn: &Conn) -> Result<multiple_ica_icq::msgs::IcaSetSizeResponse> {
+        self.query(conn, &multiple_ica_icq::msgs::QueryMsg::IcaSetSize {})
+    }
+
+    pub fn ica_metadata(
+        &self,
+        conn: &Conn,
+        ica_idx: u32,
+    ) -> Result<multiple_ica_icq::msgs::IcaMetadataResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::IcaMetadata { ica_idx },
+        )
+    }
+
+    pub fn ica_last_balance(
+        &self,
+        conn: &Conn,
+        ica_idx: u32,
+    ) -> Result<multiple_ica_icq::msgs::IcaLastBalanceResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::IcaLastBalance { ica_idx },
+        )
+    }
+
+    pub fn ica_last_delegation(
+        &self,
+        conn: &Conn,
+        ica_idx: u32,
+    ) -> Result<multiple_ica_icq::msgs::IcaLastDelegationResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::IcaLastDelegation { ica_idx },
+        )
+    }
+
+    pub fn ica_last_unbonding(
+        &self,
+        conn: &Conn,
+        ica_idx: u32,
+    ) -> Result<multiple_ica_icq::msgs::IcaLastUnbondingResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::IcaLastUnbonding { ica_idx },
+        )
+    }
+
+    pub fn ica_pending_rewards(
+        &self,
+        conn: &Conn,
+        ica_idx: u32,
+    ) -> Result<multiple_ica_icq::msgs::IcaPendingRewardsResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::IcaPendingRewards { ica_idx },
+        )
+    }
+
+    pub fn ica_staking_tx_status(
+        &self,
+        conn: &Conn,
+        ica_idx: u32,
+    ) -> Result<multiple_ica_icq::msgs::IcaStakingTxStatusResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::IcaStakingTxStatus { ica_idx },
+        )
+    }
+
+    pub fn all_last_balances(
+        &self,
+        conn: &Conn,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<multiple_ica_icq::msgs::AllLastBalancesResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::AllLastBalances { start_after, limit },
+        )
+    }
+
+    pub fn all_last_delegations(
+        &self,
+        conn: &Conn,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<multiple_ica_icq::msgs::AllLastDelegationsResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::AllLastDelegations { start_after, limit },
+        )
+    }
+
+    pub fn registration_status(
+        &self,
+        conn: &Conn,
+    ) -> Result<multiple_ica_icq::msgs::RegistrationStatusResponse> {
+        self.query(
+            conn,
+            &multiple_ica_icq::msgs::QueryMsg::RegistrationStatus {},
+        )
+    }
+
+    pub fn error_codes(&self, conn: &Conn) -> Result<multiple_ica_icq::msgs::ErrorCodesResponse> {
+        self.query(conn, &multiple_ica_icq::msgs::QueryMsg::ErrorCodes {})
+    }
+}
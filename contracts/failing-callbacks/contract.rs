@@ -0,0 +1,446 @@
+//! An ICA whose tx callbacks fail on demand, showing how neutron's contractmanager records failed sudo calls
+//! & how a contract resubmits them once the cause of the failure is fixed.
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::missing_panics_doc,
+    clippy::missing_errors_doc,
+    clippy::needless_pass_by_value
+)]
+
+pub mod msgs;
+
+use common::{
+    debug,
+    ibc::{ensure_connection_open, RawCoin},
+    ica_idx_from_port_id,
+    msg_builders::{self, IcaTxConfig},
+    IcaStatus, NeutronDeps, OpenAckVersion,
+};
+use cosmwasm_std::{
+    entry_point, from_slice, to_binary, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, Event,
+    MessageInfo, Response, Storage,
+};
+use neutron_sdk::{
+    bindings::{msg::NeutronMsg, query::NeutronQuery, types::ProtobufAny},
+    sudo::msg::SudoMsg,
+};
+use prost::Message;
+
+use crate::msgs::{
+    CallbacksResponse, ErrorCodesResponse, ExecuteMsg, FailureMode, FailureModeResponse,
+    IcaResponse, InstantiateMsg, QueryMsg,
+};
+
+/// The ICA is registered at instantiation under this id
+pub const ICA_ID: &str = "0";
+
+pub const TX_TIMEOUT_SECONDS: u64 = 60 * 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("[E0001] {0}")]
+    CosmwasmStd(#[from] cosmwasm_std::StdError),
+    #[error("[E0002] {0}")]
+    NeutronSdk(#[from] neutron_sdk::NeutronError),
+    #[error("[E0005] {0}")]
+    Validation(#[from] common::validate::ValidationError),
+    #[error("[E0006] {0}")]
+    IbcState(#[from] common::ibc::IbcStateError),
+    #[error("[E0104] unexpected open ack for port {port_id}")]
+    UnexpectedOpenAck { port_id: String },
+    #[error("[E0300] the ica is not active")]
+    IcaNotActive,
+    #[error("[E0301] {kind} callback failed on purpose")]
+    InjectedFailure { kind: &'static str },
+    #[error("[E0302] unauthorized")]
+    Unauthorized,
+}
+
+common::error_codes!(Error {
+    CosmwasmStd = 1,
+    NeutronSdk = 2,
+    Validation = 5,
+    IbcState = 6,
+    UnexpectedOpenAck = 104,
+    IcaNotActive = 300,
+    InjectedFailure = 301,
+    Unauthorized = 302,
+});
+
+pub mod state {
+    use common::json_item;
+    use cw_storage_macros::item;
+
+    use crate::msgs::{FailureMode, IcaStatus};
+
+    item!(connection_id!  : String);
+    // the instantiator, the only sender allowed to inject failures & send txs
+    item!(owner!          : String);
+    item!(ica_addr        : String);
+    item!(response_count  : u32);
+    item!(error_count     : u32);
+    item!(timeout_count   : u32);
+
+    json_item!(ica_status   : IcaStatus);
+    json_item!(failure_mode : FailureMode);
+}
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "handling instantiate msg");
+
+    msg.validate()?;
+
+    // the ICA registered below needs an open connection for its channel handshake
+    ensure_connection_open(deps.as_ref(), &msg.connection_id)?;
+
+    state::set_connection_id(deps.storage, &msg.connection_id);
+
+    state::set_owner(deps.storage, info.sender.as_str());
+
+    state::set_ica_status(deps.storage, &IcaStatus::Registering);
+
+    let register_ica_msg = NeutronMsg::RegisterInterchainAccount {
+        connection_id: msg.connection_id,
+        interchain_account_id: ICA_ID.to_owned(),
+    };
+
+    Ok(Response::default().add_message(register_ica_msg))
+}
+
+/// Build a `MsgSend` of `coin` from `from_address` to `to_address`
+#[must_use]
+pub fn make_bank_send_msg(from_address: String, to_address: String, coin: Coin) -> ProtobufAny {
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgSend {
+        #[prost(string, tag = "1")]
+        pub from_address: String,
+        #[prost(string, tag = "2")]
+        pub to_address: String,
+        #[prost(message, repeated, tag = "3")]
+        pub amount: Vec<RawCoin>,
+    }
+
+    let msg_send = MsgSend {
+        from_address,
+        to_address,
+        amount: vec![coin.into()],
+    };
+
+    ProtobufAny {
+        type_url: "/cosmos.bank.v1beta1.MsgSend".to_owned(),
+        value: msg_send.encode_to_vec().into(),
+    }
+}
+
+/// Build the contractmanager's `MsgResubmitFailure`, which re-runs a failed sudo call of `sender`
+#[must_use]
+pub fn make_resubmit_failure_msg(sender: String, failure_id: u64) -> CosmosMsg<NeutronMsg> {
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgResubmitFailure {
+        #[prost(string, tag = "1")]
+        pub sender: String,
+        #[prost(uint64, tag = "2")]
+        pub failure_id: u64,
+    }
+
+    let msg = MsgResubmitFailure { sender, failure_id };
+
+    CosmosMsg::Stargate {
+        type_url: "/neutron.contractmanager.MsgResubmitFailure".to_owned(),
+        value: msg.encode_to_vec().into(),
+    }
+}
+
+pub fn execute_send_tx(
+    deps: DepsMut<NeutronQuery>,
+    coin: Coin,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing send tx of {coin}");
+
+    if state::ica_status(deps.storage) != Some(IcaStatus::Active) {
+        return Err(Error::IcaNotActive);
+    }
+
+    let ica_addr = state::ica_addr(deps.storage).expect("an active ica has an address");
+
+    let fee = deps.as_ref().min_ibc_fee()?;
+
+    let ica_tx_config = IcaTxConfig {
+        connection_id: state::connection_id(deps.storage),
+        timeout_seconds: TX_TIMEOUT_SECONDS,
+    };
+
+    let submit_tx_msg = msg_builders::submit_tx(
+        &ica_tx_config,
+        ICA_ID,
+        vec![make_bank_send_msg(ica_addr.clone(), ica_addr, coin)],
+        fee,
+    );
+
+    Ok(Response::default().add_message(submit_tx_msg))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "handling execute msg");
+
+    // every msg changes how callbacks fail or triggers them, which only the owner may do
+    if info.sender.as_str() != state::owner(deps.storage) {
+        return Err(Error::Unauthorized);
+    }
+
+    match msg {
+        ExecuteMsg::SetFailureMode { mode } => {
+            state::set_failure_mode(deps.storage, &mode);
+
+            let event = Event::new("failure_mode_set").add_attribute("mode", format!("{mode:?}"));
+
+            Ok(Response::default().add_event(event))
+        }
+
+        ExecuteMsg::SendTx { coin } => execute_send_tx(deps, coin),
+
+        ExecuteMsg::ResubmitFailure { failure_id } => {
+            let resubmit_msg =
+                make_resubmit_failure_msg(env.contract.address.into_string(), failure_id);
+
+            let event = Event::new("failure_resubmitted")
+                .add_attribute("failure_id", failure_id.to_string());
+
+            Ok(Response::default()
+                .add_message(resubmit_msg)
+                .add_event(event))
+        }
+    }
+}
+
+/// Never returns, every iteration writes to storage so the sudo call is charged until it runs out of gas
+fn burn_gas(storage: &mut dyn Storage) -> ! {
+    let mut n: u64 = 0;
+
+    loop {
+        n = n.wrapping_add(1);
+
+        storage.set(b"gas_burner", &n.to_be_bytes());
+    }
+}
+
+/// Fail the `kind` callback if configured to
+pub fn inject_failure(storage: &mut dyn Storage, kind: &'static str) -> Result<(), Error> {
+    match state::failure_mode(storage).unwrap_or_default() {
+        FailureMode::None => Ok(()),
+        FailureMode::Error => Err(Error::InjectedFailure { kind }),
+        FailureMode::OutOfGas => burn_gas(storage),
+    }
+}
+
+#[must_use]
+pub fn callback_handled(kind: &str, count: u32) -> Response<NeutronMsg> {
+    let event = Event::new("callback_handled")
+        .add_attribute("kind", kind)
+        .add_attribute("count", count.to_string());
+
+    Response::default().add_event(event)
+}
+
+pub fn sudo_open_ack(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    port_id: String,
+    counterparty_version: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(
+        deps,
+        "received open ack for {port_id}: {counterparty_version}"
+    );
+
+    let parsed_version: OpenAckVersion =
+        from_slice(counterparty_version.as_bytes()).expect("valid counterparty_version");
+
+    ica_idx_from_port_id(&port_id, env.contract.address.as_str())
+        .filter(|_| state::ica_status(deps.storage) == Some(IcaStatus::Registering))
+        .ok_or(Error::UnexpectedOpenAck { port_id })?;
+
+    state::set_ica_status(deps.storage, &IcaStatus::Active);
+
+    state::set_ica_addr(deps.storage, &parsed_version.address);
+
+    Ok(Response::default())
+}
+
+#[entry_point]
+pub fn sudo(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "handling sudo msg");
+
+    match msg {
+        SudoMsg::OpenAck {
+            port_id,
+            counterparty_version,
+            ..
+        } => sudo_open_ack(deps, env, port_id, counterparty_version),
+
+        SudoMsg::Response { .. } => {
+            inject_failure(deps.storage, "response")?;
+
+            let count = state::response_count(deps.storage).unwrap_or_default() + 1;
+
+            state::set_response_count(deps.storage, count);
+
+            Ok(callback_handled("response", count))
+        }
+
+        SudoMsg::Error { .. } => {
+            inject_failure(deps.storage, "error")?;
+
+            let count = state::error_count(deps.storage).unwrap_or_default() + 1;
+
+            state::set_error_count(deps.storage, count);
+
+            Ok(callback_handled("error", count))
+        }
+
+        SudoMsg::Timeout { .. } => {
+            inject_failure(deps.storage, "timeout")?;
+
+            let count = state::timeout_count(deps.storage).unwrap_or_default() + 1;
+
+            state::set_timeout_count(deps.storage, count);
+
+            Ok(callback_handled("timeout", count))
+        }
+
+        _ => {
+            debug!(deps, "unexpected sudo msg: {msg:?}");
+            Ok(Response::default())
+        }
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
+    let res = match msg {
+        QueryMsg::Ica {} => to_binary(&IcaResponse {
+            address: state::ica_addr(deps.storage),
+            status: state::ica_status(deps.storage).unwrap_or_default(),
+        })?,
+
+        QueryMsg::FailureMode {} => to_binary(&FailureModeResponse {
+            mode: state::failure_mode(deps.storage).unwrap_or_default(),
+        })?,
+
+        QueryMsg::Callbacks {} => to_binary(&CallbacksResponse {
+            responses: state::response_count(deps.storage).unwrap_or_default(),
+            errors: state::error_count(deps.storage).unwrap_or_default(),
+            timeouts: state::timeout_count(deps.storage).unwrap_or_default(),
+        })?,
+
+        QueryMsg::ErrorCodes {} => to_binary(&ErrorCodesResponse {
+            codes: Error::catalogue(),
+        })?,
+    };
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{
+        testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+        OwnedDeps,
+    };
+
+    use super::*;
+
+    #[test]
+    fn only_owner_injects_failures() {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::<NeutronQuery>::new(&[]),
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        state::set_owner(deps.as_mut().storage, "owner");
+
+        let set_failure_mode = ExecuteMsg::SetFailureMode {
+            mode: FailureMode::OutOfGas,
+        };
+
+        for msg in [
+            set_failure_mode.clone(),
+            ExecuteMsg::SendTx {
+                coin: Coin::new(1, "uatom"),
+            },
+            ExecuteMsg::ResubmitFailure { failure_id: 0 },
+        ] {
+            assert!(matches!(
+                execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), msg),
+                Err(Error::Unauthorized)
+            ));
+        }
+
+        assert_eq!(state::failure_mode(deps.as_ref().storage), None);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            set_failure_mode,
+        )
+        .unwrap();
+
+        assert_eq!(
+            state::failure_mode(deps.as_ref().storage),
+            Some(FailureMode::OutOfGas)
+        );
+    }
+
+    #[test]
+    fn callbacks_fail_on_demand() {
+        let mut storage = MockStorage::default();
+
+        assert!(inject_failure(&mut storage, "response").is_ok());
+
+        state::set_failure_mode(&mut storage, &FailureMode::Error);
+
+        assert!(matches!(
+            inject_failure(&mut storage, "response"),
+            Err(Error::InjectedFailure { kind: "response" })
+        ));
+    }
+
+    #[test]
+    fn resubmit_failure_encoding() {
+        let CosmosMsg::Stargate { type_url, value } =
+            make_resubmit_failure_msg("neutron1contract".to_owned(), 300)
+        else {
+            panic!("expected a stargate msg");
+        };
+
+        assert_eq!(type_url, "/neutron.contractmanager.MsgResubmitFailure");
+
+        // field 1 (string) "neutron1contract", field 2 (varint) 300
+        let mut expected = vec![0x0a, 16];
+        expected.extend_from_slice(b"neutron1contract");
+        expected.extend_from_slice(&[0x10, 0xac, 0x02]);
+
+        assert_eq!(value.as_slice(), expected.as_slice());
+    }
+}
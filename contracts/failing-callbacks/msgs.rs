@@ -0,0 +1,84 @@
+use common::validate::{validate_connection_id, ValidationError};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Coin;
+
+pub use common::{error_codes::ErrorCode, IcaStatus};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The IBC connection ID on which to register the ICA
+    pub connection_id: String,
+}
+
+impl InstantiateMsg {
+    /// Validate the shape of the message without touching the chain
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate_connection_id(&self.connection_id)?;
+
+        Ok(())
+    }
+}
+
+/// How the sudo handlers of ICA tx callbacks behave
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum FailureMode {
+    /// Handle callbacks normally, counting them
+    #[default]
+    None,
+    /// Return an error from every callback
+    Error,
+    /// Loop until every callback runs out of gas
+    OutOfGas,
+}
+
+/// Every msg is only callable by the instantiator
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Change how the next callbacks are handled
+    SetFailureMode { mode: FailureMode },
+    /// Send `coin` from the ICA to itself, the attached `untrn` pays the IBC fee.
+    /// An unfunded ICA is acknowledged with an error, which is just as good a callback to fail.
+    SendTx { coin: Coin },
+    /// Ask the contractmanager module to re-run the failed callback with id `failure_id`
+    ResubmitFailure { failure_id: u64 },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Query the ICA address & status
+    Ica {},
+    /// Query the current failure mode
+    FailureMode {},
+    /// Query the number of callbacks handled without failing, by kind
+    Callbacks {},
+    /// Query the code of every error, surfaced in error messages as `[E0123]`
+    ErrorCodes {},
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct IcaResponse {
+    pub address: Option<String>,
+    pub status: IcaStatus,
+}
+
+#[cw_serde]
+pub struct FailureModeResponse {
+    pub mode: FailureMode,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct CallbacksResponse {
+    pub responses: u32,
+    pub errors: u32,
+    pub timeouts: u32,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ErrorCodesResponse {
+    /// The code of every error the contract can return, as prefixed to its message
+    pub codes: Vec<ErrorCode>,
+}
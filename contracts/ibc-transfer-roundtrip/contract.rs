@@ -6,98 +6,517 @@
     clippy::needless_pass_by_value
 )]
 
+pub mod fee_escrow;
 pub mod msgs;
 
+pub use common::funds::coins_attr;
 use common::{
-    combine_u32s, debug, icq_deposit_fee, query_balance_icq, split_u64, OpenAckVersion,
-    RemoteBalance,
+    alerts::{AlertHookMsg, AlertKind, ProbeAlert},
+    check_icq_deposit, combine_u32s, debug, foreign_deposit_coins,
+    hashing::HashScheme,
+    ibc::{
+        ensure_channel_open, ensure_connection_open, ibc_hooks_sender, ics20_voucher_denom,
+        make_ibc_transfer_via_hops_with_hook_msg, pfm_receiver, query_denom_trace, DenomTrace,
+        ForwardHop, RawCoin,
+    },
+    icq_deposit_fee_or,
+    indexes::rebuild_indexes,
+    is_query_type_mismatch, is_registered_kv_query,
+    msg_builders::{self, IcaTxConfig, TimeoutPolicy, TransferConfig},
+    query_balance_icq,
+    receipt::{self, ExecuteReceipt},
+    updated_registered_kv_query,
+    validate::{
+        validate_bech32_prefix, validate_channel_id, validate_denom, validate_icq_deposit,
+        validate_icq_update_period, validate_label, validate_note, validate_remote_address,
+        validate_transfer_memo, MemoLimits, MAX_IBC_MEMO_LEN,
+    },
+    IcqDepositError, NeutronDeps, OpenAckVersion, QueryBalanceIcqError, RemoteBalance,
 };
 use cosmwasm_std::{
-    entry_point, from_slice, to_binary, Addr, BankMsg, Binary, Coin, CustomQuery, Deps, DepsMut,
-    Env, MessageInfo, Reply, Response, SubMsg,
+    entry_point, from_slice, to_binary, to_vec, Addr, BankMsg, Binary, Coin, CustomQuery, Decimal,
+    Deps, DepsMut, Env, Event, MessageInfo, Reply, Response, Storage, SubMsg, Timestamp, Uint128,
+    WasmMsg,
 };
 use neutron_sdk::{
     bindings::{
         msg::{IbcFee, NeutronMsg},
         query::NeutronQuery,
-        types::ProtobufAny,
+        types::{Height, ProtobufAny},
     },
-    interchain_queries::v045::new_register_balance_query_msg,
-    query::min_ibc_fee::query_min_ibc_fee,
-    sudo::msg::{RequestPacket, RequestPacketTimeoutHeight, SudoMsg},
+    interchain_queries::v045::{
+        new_register_balance_query_msg, new_register_delegator_delegations_query_msg,
+        new_register_transfers_query_msg, types::Delegations,
+    },
+    sudo::msg::{RequestPacket, SudoMsg},
 };
 use prost::Message;
-use serde::Serialize;
 
 use crate::msgs::{
-    ExecuteMsg, IcaLastBalance, IcaLastBalanceResponse, IcaMetadata, IcaMetadataResponse,
-    IcaTxErrorResponse, IcaTxStatus, IcaTxStatusResponse, InstantiateMsg, QueryMsg,
+    ActivityEntry, ActivityResponse, AddressBookEntry, AddressBookResponse, AdminResponse,
+    AlertSubscribersResponse, ChannelVolume, CustomTx, DeadLetter, DeadLettersResponse,
+    DenomTransferLimits, DenomVolume, ErrorCodesResponse, ExecuteMsg, ExportResponse,
+    FeeHistoryResponse, FeeObservation, GlobalStatsResponse, GlobalTotals, HashMappingsResponse,
+    HookDeadLetter, HookDeadLettersResponse, IcaExport, IcaLastBalance, IcaLastBalanceResponse,
+    IcaLastDelegation, IcaLastDelegationResponse, IcaListEntry, IcaMetadata, IcaMetadataResponse,
+    IcaRemoteDepositsResponse, IcaStatus, IcaTxErrorResponse, IcaTxHistoryResponse, IcaTxStatus,
+    IcaTxStatusResponse, IcqInfoResponse, IndexerCheckpoint, IndexerCheckpointsResponse,
+    InstantiateMsg, IssuedTx, LeaderboardEntry, LeaderboardResponse, ListIcasResponse,
+    MemoLimitsResponse, MigrateMsg, OperatorGrant, OperatorsResponse, Owner, OwnersResponse,
+    PausedResponse, PendingTx, PendingTxsResponse, ProtobufAnyJson, QuarantinedIcqResult,
+    QuarantinedIcqResultResponse, QueryMsg, RemoteDeposit, RetrievalRoute,
+    RoundtripFeeEstimateResponse, StateCommitment, StateCommitmentResponse,
+    SupportedFeaturesResponse, TimeoutsResponse, TransferDenomsResponse, TransferDirection,
+    TransferLimitsResponse, TxHistoryEntry, TxKind, TxOutcome,
 };
 
+/// Version of the contract state layout, bumped whenever a migration is required
+pub const CONTRACT_VERSION: u32 = 6;
+
+/// The optional cargo features compiled into the contract, in lexical order
+pub const SUPPORTED_FEATURES: &[&str] = &[
+    #[cfg(feature = "camel-case")]
+    "camel-case",
+    #[cfg(feature = "ics20-check")]
+    "ics20-check",
+];
+
+/// The scheme of the tx & rx hashes issued from now on, its version is stored alongside each hash
+pub const HASH_SCHEME: HashScheme = HashScheme::V2;
+
+/// Hash domain of the (sequence number, source channel) of issued txs
+pub const TX_HASH_DOMAIN: &str = "tx";
+/// Hash domain of the (ICA address, amount, tx index) of retrievals
+pub const RX_HASH_DOMAIN: &str = "rx";
+/// Domains of the hashes making up state commitments
+pub const ACTIVITY_DOMAIN: &str = "activity";
+pub const STATS_DOMAIN: &str = "stats";
+pub const COMMITMENT_DOMAIN: &str = "commitment";
+
 pub const DEFAULT_TIMEOUT_SECONDS: u64 = 60 * 60 * 24 * 7 * 2; // 2 weeks
 pub const DEFAULT_TIMEOUT_HEIGHT: u64 = 10_000_000;
+pub const DEFAULT_TIMEOUT_REVISION_NUMBER: u64 = 2;
+
+/// The number of fee parameter changes kept in the fee history
+pub const FEE_HISTORY_LIMIT: usize = 16;
+
+/// The number of owners kept on the roundtrip leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// The number of blocks an ICA registration must be pending for before its owner can cancel it
+pub const REGISTRATION_CANCEL_BLOCKS: u64 = 1_000;
+
+/// The number of remote addresses an ICA's address book can hold
+pub const MAX_ADDRESS_BOOK_ENTRIES: usize = 20;
+
+/// The number of owners whose funds a single `RetrieveFundsFor` can retrieve
+pub const MAX_RETRIEVE_FOR_OWNERS: usize = 30;
+
+/// The number of contracts that can subscribe to alerts, each alert is executed on every subscriber
+/// within the gas limit of the sudo call acknowledging the failed tx
+pub const MAX_ALERT_SUBSCRIBERS: usize = 3;
+
+/// The gas an alert can use on each subscriber
+pub const ALERT_HOOK_GAS_LIMIT: u64 = 150_000;
+
+/// The unbonding period of the Cosmos Hub, assumed when the ack of an undelegation doesn't report its completion time
+pub const MAX_UNBONDING_SECONDS: u64 = 21 * 24 * 60 * 60;
+
+/// The gas an ICQ relayer is estimated to spend submitting a KV result along with its proofs
+pub const ICQ_RESULT_SUBMISSION_GAS: u64 = 400_000;
+
+/// The balance ICQ updates a roundtrip waits on: one observing the transfer on the ICA before it is retrieved,
+/// & one observing the ICA drained by the retrieval
+pub const ROUNDTRIP_ICQ_UPDATES: u64 = 2;
+
+/// The untrn gas price assumed by fee estimates when none is given, 0.0025untrn
+pub const DEFAULT_ESTIMATE_GAS_PRICE: Decimal = Decimal::raw(2_500_000_000_000_000);
+
+/// The number of ICA txs the messages of a `SubmitIcaTx` can be split across
+pub const MAX_CUSTOM_TXS: usize = 3;
 
-pub const REGISTER_ICQ_REPLY_KIND: u32 = 0;
+/// The number of intermediate chains a `RetrieveFundsVia` retrieval can be forwarded through
+pub const MAX_RETRIEVAL_HOPS: usize = 3;
+
+/// The number of operators an owner can grant an allowance to
+pub const MAX_OPERATORS: usize = 10;
+
+/// The number of activity entries kept, older entries are pruned & folded into the pruned activity digest
+pub const MAX_ACTIVITY_ENTRIES: u64 = 1_000;
+
+/// The bech32 prefix of the addresses ibc-hooks executes retrieval hooks as
+pub const HOOK_SENDER_BECH32_PREFIX: &str = "neutron";
+
+/// The number of blocks tx & rx hash mappings are kept for by default, outliving the default ICA tx timeout
+pub const DEFAULT_HASH_RETENTION_BLOCKS: u64 = 2_000_000;
+
+/// The number of hash mappings a single `Sweep` can visit
+pub const MAX_SWEEP_LIMIT: u32 = 100;
+
+pub const DEFAULT_PAGE_LIMIT: u32 = 10;
+pub const MAX_PAGE_LIMIT: u32 = 30;
+
+// the kinds of txs keying the last failed tx & sequence number of each kind per ICA, replies are dispatched from
+// the `PendingReply` recorded against their id
 pub const TRANSFER_TX_REPLY_KIND: u32 = 1;
 pub const RETRIEVE_TX_REPLY_KIND: u32 = 2;
+pub const DELEGATE_TX_REPLY_KIND: u32 = 4;
+pub const UNDELEGATE_TX_REPLY_KIND: u32 = 5;
+pub const CLAIM_REWARDS_TX_REPLY_KIND: u32 = 7;
+pub const CUSTOM_TX_REPLY_KIND: u32 = 10;
+
+/// The reply kinds of all txs issued by an ICA
+pub const TX_REPLY_KINDS: [u32; 6] = [
+    TRANSFER_TX_REPLY_KIND,
+    RETRIEVE_TX_REPLY_KIND,
+    DELEGATE_TX_REPLY_KIND,
+    UNDELEGATE_TX_REPLY_KIND,
+    CLAIM_REWARDS_TX_REPLY_KIND,
+    CUSTOM_TX_REPLY_KIND,
+];
 
 pub static IBC_FEE_DENOM: &str = "untrn";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error(transparent)]
+    #[error("[E0001] {0}")]
     CosmwasmStd(#[from] cosmwasm_std::StdError),
-    #[error(transparent)]
+    #[error("[E0002] {0}")]
     NeutronSdk(#[from] neutron_sdk::NeutronError),
-    #[error(transparent)]
+    #[error("[E0003] {0}")]
     ParseReply(#[from] common::ParseReplyError),
-    #[error(transparent)]
+    #[error("[E0004] {0}")]
     QueryBalanceIcq(#[from] common::QueryBalanceIcqError),
-    #[error("ica index {ica_idx} is out of bounds, ica set size is {ica_set_size}")]
+    #[error("[E0005] {0}")]
+    Validation(#[from] common::validate::ValidationError),
+    #[error("[E0006] {0}")]
+    IbcState(#[from] common::ibc::IbcStateError),
+    #[error("[E0100] ica index {ica_idx} is out of bounds, ica set size is {ica_set_size}")]
     IcaIndexOutOfBounds { ica_idx: u32, ica_set_size: u32 },
-    #[error("icq deposit missing")]
+    #[error("[E0101] icq deposit missing")]
     IcqDepositMissing,
-    #[error("incorrect icq deposit asset")]
+    #[error("[E0102] incorrect icq deposit asset")]
     IncorrectIcqDepositAsset,
-    #[error("insufficient icq deposit")]
+    #[error("[E0103] insufficient icq deposit")]
     InsufficientIcqDeposit,
-    #[error("insufficient ibc tx fee")]
-    InsufficientIbcTxFee,
-    #[error("no ica setup")]
+    #[error("[E0104] unexpected open ack for port {port_id}")]
+    UnexpectedOpenAck { port_id: String },
+    #[error("[E0200] insufficient ibc tx fee, missing {}", coins_attr(.shortfall))]
+    InsufficientIbcTxFee { shortfall: Vec<Coin> },
+    #[error("[E0201] insufficient fee balance, missing {}", coins_attr(.shortfall))]
+    InsufficientFeeBalance { shortfall: Vec<Coin> },
+    #[error("[E0202] staking amount must be non-zero")]
+    ZeroStakingAmount,
+    #[error("[E0203] the ica delegates to {expected}, only one validator is tracked")]
+    DelegationValidatorMismatch { expected: String },
+    #[error("[E0204] no delegations from the ica")]
+    NoDelegations,
+    #[error("[E0205] no ica setup")]
     NoIcaSetup,
-    #[error("no funds to transfer")]
+    #[error("[E0206] an ica is already setup")]
+    IcaAlreadySetup,
+    #[error("[E0207] the ica registration can be cancelled in {remaining_blocks} blocks")]
+    RegistrationPending { remaining_blocks: u64 },
+    #[error("[E0208] ica {ica_idx} is {status:?}, expected one of {expected:?}")]
+    UnexpectedIcaStatus {
+        ica_idx: u32,
+        status: IcaStatus,
+        expected: Vec<IcaStatus>,
+    },
+    #[error("[E0209] no funds to transfer")]
     NoFundsToTransfer,
-    #[error("no funds to retrieve")]
+    #[error("[E0210] the ibc fee denom cannot be a transfer denom")]
+    FeeDenomNotTransferable,
+    #[error("[E0211] {denom} is not accepted for transfer")]
+    DenomNotTransferable { denom: String },
+    #[error("[E0212] no funds to retrieve")]
     NoFundsToRetrieve,
-    #[error("no funds expected")]
+    #[error("[E0213] cannot retrieve {requested}, the last known ICA balance is {available}")]
+    RetrieveAmountExceedsBalance {
+        requested: Uint128,
+        available: Uint128,
+    },
+    #[error("[E0214] auto-retrieve is not enabled")]
+    AutoRetrieveNotEnabled,
+    #[error("[E0215] fee deposit missing")]
+    FeeDepositMissing,
+    #[error("[E0216] incorrect fee deposit asset, expected {IBC_FEE_DENOM}")]
+    IncorrectFeeDepositAsset,
+    #[error("[E0217] no fees to withdraw")]
+    NoFeesToWithdraw,
+    #[error("[E0218] no funds expected")]
     NoFundsExpected,
-    #[error("invalid rx hash")]
+    #[error("[E0219] invalid rx hash")]
     InvalidRxHash,
+    #[error("[E0220] retrieved {received} but {expected} was expected")]
+    RetrievedAmountMismatch {
+        expected: Uint128,
+        received: Uint128,
+    },
+    #[error("[E0221] no hook dead letter {idx}")]
+    NoHookDeadLetter { idx: u32 },
+    #[error("[E0222] a transfers ICQ is already registered")]
+    TransfersIcqAlreadyRegistered,
+    #[error("[E0223] the result of TX ICQ {icq_id} is not a valid tx")]
+    MalformedTxQueryResult { icq_id: u64 },
+    #[error("[E0224] acknowledged packet is missing its {field}")]
+    IncompletePacket { field: &'static str },
+    #[error("[E0225] no tx was issued with sequence number {seq_num} on {channel}")]
+    UnknownTx { seq_num: u64, channel: String },
+    #[error("[E0226] ica has {in_flight} txs in flight")]
+    TxsInFlight { in_flight: u32 },
+    #[error("[E0227] {indexer} is not an authorized indexer")]
+    UnauthorizedIndexer { indexer: String },
+    #[error("[E0228] indexer checkpoint cannot move back from activity {current} to {requested}")]
+    IndexerCheckpointRegression { current: u64, requested: u64 },
+    #[error("[E0229] the result of ICQ {icq_id} failed to decode & is quarantined")]
+    IcqResultQuarantined { icq_id: u64 },
+    #[error("[E0230] ICQ {icq_id} is no longer registered as a KV query")]
+    IcqInvalid { icq_id: u64 },
+    #[error(
+        "[E0231] the address book is full, it holds at most {MAX_ADDRESS_BOOK_ENTRIES} addresses"
+    )]
+    AddressBookFull,
+    #[error("[E0232] {address} is not in the address book")]
+    NoAddressBookEntry { address: String },
+    #[error("[E0233] no balance ICQ is registered for the ica")]
+    NoBalanceIcq,
+    #[error("[E0234] no failed tx to retry")]
+    NoFailedTx,
+    #[error("[E0235] unauthorized")]
+    Unauthorized,
+    #[error("[E0236] the contract is paused")]
+    Paused,
+    #[error("[E0237] timeouts must be non-zero")]
+    ZeroTimeout,
+    #[error("[E0238] cannot migrate from contract version {from} to {to}")]
+    InvalidMigration { from: u32, to: u32 },
+    #[error(
+        "[E0239] cannot transfer {amount}{denom}, the min transfer amount of {denom} is {min}"
+    )]
+    TransferBelowMinimum {
+        amount: Uint128,
+        denom: String,
+        min: Uint128,
+    },
+    #[error(
+        "[E0240] cannot transfer {amount}{denom}, the max transfer amount of {denom} is {max}"
+    )]
+    TransferAboveMaximum {
+        amount: Uint128,
+        denom: String,
+        max: Uint128,
+    },
+    #[error("[E0241] the min transfer amount {min} exceeds the max transfer amount {max}")]
+    InvalidTransferLimits { min: Uint128, max: Uint128 },
+    #[error("[E0242] funds can be retrieved for at most {max} owners at once")]
+    TooManyOwners { max: usize },
+    #[error("[E0243] only contracts can subscribe to alerts")]
+    AlertSubscriberNotContract,
+    #[error("[E0244] at most {MAX_ALERT_SUBSCRIBERS} contracts can subscribe to alerts")]
+    TooManyAlertSubscribers,
+    #[error("[E0245] retrievals only deliver {expected}, {received} was attached")]
+    UnexpectedRetrievedDenom { expected: String, received: String },
+    #[error("[E0246] retrieval hook of ICA {ica_idx} sent by {sender}, expected {expected}")]
+    UnexpectedHookSender {
+        ica_idx: u32,
+        sender: String,
+        expected: String,
+    },
+    #[error("[E0247] retrieval routes forward through 1 to {MAX_RETRIEVAL_HOPS} hops, {hops} were given")]
+    InvalidRetrievalHops { hops: usize },
+    #[error(
+        "[E0248] custom ICA txs execute 1 to {max} messages, split across at most {MAX_CUSTOM_TXS} ICA txs, {count} were given"
+    )]
+    InvalidCustomTxMsgs { count: usize, max: usize },
+    #[error("[E0249] custom ICA tx timeout must be non-zero")]
+    ZeroCustomTxTimeout,
+    #[error("[E0250] at most {MAX_OPERATORS} operators can be granted an allowance")]
+    TooManyOperators,
+    #[error("[E0251] {operator} is not an operator of {owner}")]
+    NoOperatorGrant { owner: String, operator: String },
+    #[error("[E0252] the allowance of operator {operator} does not cover {amount}")]
+    OperatorAllowanceExceeded { operator: String, amount: String },
+    #[error("[E0253] owners cannot grant themselves an allowance")]
+    SelfOperatorGrant,
+    #[error(
+        "[E0254] memo limits must be between 1 and {MAX_IBC_MEMO_LEN} bytes, the hook memo limit at most the memo limit"
+    )]
+    InvalidMemoLimits,
+    #[error("[E0255] the hash retention window must be non-zero")]
+    ZeroHashRetention,
+    #[error("[E0256] no pending reply recorded for reply {id}")]
+    UnexpectedReply { id: u64 },
+    #[error("[E0257] the interchaintxs module accepts no messages per ICA tx")]
+    ZeroSubmitTxMsgLimit,
+    #[error("[E0258] the ICS-20 receipt of {denom} could not be verified: {reason}")]
+    UnverifiedIcs20Receipt { denom: String, reason: String },
+    #[error("[E0259] no retrieval route is allowed under {name}")]
+    UnknownRetrievalRoute { name: String },
+    #[error(
+        "[E0260] the route's host denom {given} is not the voucher denom {expected} of its path"
+    )]
+    RetrievalRouteDenomMismatch { expected: String, given: String },
+    #[error("[E0261] custom ICA txs are not accepted while {in_flight} retrievals of the ica are in flight")]
+    RetrievalsInFlight { in_flight: usize },
+    #[error("[E0262] the failed {tx_kind} tx lacks the parameters it was issued with")]
+    IncompleteFailedTx { tx_kind: String },
+    #[error("[E0263] {subscriber} was not approved by the contract owner to subscribe to alerts")]
+    AlertSubscriberNotApproved { subscriber: String },
+    #[error("[E0264] the ICA still holds {balance}, retrieve it before tearing the ICA down")]
+    IcaNotDrained { balance: Coin },
+    #[error("[E0265] no balance ICQ result was submitted after the last tx of the ICA settled at height {settled_height}")]
+    IcaBalanceUnknown { settled_height: u64 },
+    #[error("[E0266] {undelivered} retrievals of the ICA are yet to be delivered to the contract")]
+    RetrievalsUndelivered { undelivered: u32 },
+    #[error("[E0267] the ICA still delegates {amount} to {validator}, undelegate it before tearing the ICA down")]
+    IcaDelegated { validator: String, amount: Coin },
+    #[error("[E0268] funds undelegated by the ICA are unbonding until {until} & are yet to be reported by the balance ICQ")]
+    IcaUnbonding { until: Timestamp },
+    #[error("[E0269] no delegations ICQ result was submitted after the last tx of the ICA settled at height {settled_height}")]
+    IcaDelegationsUnknown { settled_height: u64 },
+    #[error("[E0270] custom ICA txs execute at least one message")]
+    EmptyCustomTx,
 }
 
-macro_rules! hash {
-    ($($part:expr),+) => {{
-        let msg = [
-            $( $part.as_ref(), )*
-        ]
-        .concat();
-
-        let sha256 = hmac_sha256::Hash::hash(&msg);
+common::error_codes!(Error {
+    CosmwasmStd = 1,
+    NeutronSdk = 2,
+    ParseReply = 3,
+    QueryBalanceIcq = 4,
+    Validation = 5,
+    IbcState = 6,
+    IcaIndexOutOfBounds = 100,
+    IcqDepositMissing = 101,
+    IncorrectIcqDepositAsset = 102,
+    InsufficientIcqDeposit = 103,
+    UnexpectedOpenAck = 104,
+    InsufficientIbcTxFee = 200,
+    InsufficientFeeBalance = 201,
+    ZeroStakingAmount = 202,
+    DelegationValidatorMismatch = 203,
+    NoDelegations = 204,
+    NoIcaSetup = 205,
+    IcaAlreadySetup = 206,
+    RegistrationPending = 207,
+    UnexpectedIcaStatus = 208,
+    NoFundsToTransfer = 209,
+    FeeDenomNotTransferable = 210,
+    DenomNotTransferable = 211,
+    NoFundsToRetrieve = 212,
+    RetrieveAmountExceedsBalance = 213,
+    AutoRetrieveNotEnabled = 214,
+    FeeDepositMissing = 215,
+    IncorrectFeeDepositAsset = 216,
+    NoFeesToWithdraw = 217,
+    NoFundsExpected = 218,
+    InvalidRxHash = 219,
+    RetrievedAmountMismatch = 220,
+    NoHookDeadLetter = 221,
+    TransfersIcqAlreadyRegistered = 222,
+    MalformedTxQueryResult = 223,
+    IncompletePacket = 224,
+    UnknownTx = 225,
+    TxsInFlight = 226,
+    UnauthorizedIndexer = 227,
+    IndexerCheckpointRegression = 228,
+    IcqResultQuarantined = 229,
+    IcqInvalid = 230,
+    AddressBookFull = 231,
+    NoAddressBookEntry = 232,
+    NoBalanceIcq = 233,
+    NoFailedTx = 234,
+    Unauthorized = 235,
+    Paused = 236,
+    ZeroTimeout = 237,
+    InvalidMigration = 238,
+    TransferBelowMinimum = 239,
+    TransferAboveMaximum = 240,
+    InvalidTransferLimits = 241,
+    TooManyOwners = 242,
+    AlertSubscriberNotContract = 243,
+    TooManyAlertSubscribers = 244,
+    UnexpectedRetrievedDenom = 245,
+    UnexpectedHookSender = 246,
+    InvalidRetrievalHops = 247,
+    InvalidCustomTxMsgs = 248,
+    ZeroCustomTxTimeout = 249,
+    TooManyOperators = 250,
+    NoOperatorGrant = 251,
+    OperatorAllowanceExceeded = 252,
+    SelfOperatorGrant = 253,
+    InvalidMemoLimits = 254,
+    ZeroHashRetention = 255,
+    UnexpectedReply = 256,
+    ZeroSubmitTxMsgLimit = 257,
+    UnverifiedIcs20Receipt = 258,
+    UnknownRetrievalRoute = 259,
+    RetrievalRouteDenomMismatch = 260,
+    RetrievalsInFlight = 261,
+    IncompleteFailedTx = 262,
+    AlertSubscriberNotApproved = 263,
+    IcaNotDrained = 264,
+    IcaBalanceUnknown = 265,
+    RetrievalsUndelivered = 266,
+    IcaDelegated = 267,
+    IcaUnbonding = 268,
+    IcaDelegationsUnknown = 269,
+    EmptyCustomTx = 270,
+});
+
+impl From<IcqDepositError> for Error {
+    fn from(err: IcqDepositError) -> Self {
+        match err {
+            IcqDepositError::Missing => Self::IcqDepositMissing,
+            IcqDepositError::IncorrectAsset => Self::IncorrectIcqDepositAsset,
+            IcqDepositError::Insufficient => Self::InsufficientIcqDeposit,
+        }
+    }
+}
 
-        hex::encode_upper(sha256)
-    }};
+macro_rules! hash {
+    ($domain:expr; $($part:expr),+) => {
+        HASH_SCHEME.hash($domain, &[ $( AsRef::<[u8]>::as_ref(&$part), )* ])
+    };
 }
 
 pub mod state {
+    use common::{json_item, json_map};
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Coin, Timestamp, Uint128};
     use cw_storage_macros::{item, map};
 
+    use crate::msgs::{
+        ActivityEntry, ChannelVolume, DeadLetter, DenomTransferLimits, FeeObservation,
+        GlobalTotals, HookDeadLetter, IcaChannel, IcaStatus, IndexerCheckpoint, IssuedTx,
+        LeaderboardEntry, OperatorGrant, PendingTx, QuarantinedIcqResult, RemoteDeposit,
+        RetrievalRoute, StateCommitment, TxHistoryEntry, TxKind,
+    };
+
     item!(connection_id!        : String);
     item!(ibc_transfer_channel! : String);
     item!(remote_denom!         : String);
     item!(icq_update_period!    : u64);
     item!(host_ibc_denom!       : String);
 
-    item!(ica_count : u32);
+    item!(contract_version : u32);
+    item!(contract_owner   : String);
+    item!(pending_contract_owner : String);
+    item!(timeout_seconds  : u64);
+    item!(timeout_height   : u64);
+    item!(timeout_revision_number : u64);
+    item!(ica_count        : u32);
+    item!(dead_letter_count : u32);
+    item!(hook_dead_letter_count : u32);
+    // the ICA index the next `RebuildIndexes` resumes from, removed once every ICA has been visited
+    item!(rebuild_cursor   : u32);
+    item!(hash_retention_blocks : u64);
+    // the number of height-stamped tx & rx hash mappings kept
+    item!(tx_hash_count    : u64);
+    item!(rx_hash_count    : u64);
+    // the hash log is swept from its cursor, entries before it have been removed
+    item!(hash_log_len     : u64);
+    item!(hash_log_cursor  : u64);
 
     map!(owner       : str => ica_idx          : u32);
     map!(tx_hash     : str => ica_idx          : u32);
@@ -105,770 +524,10036 @@ pub mod state {
     map!(ica         : u32 => owner            : String);
     map!(ica         : u32 => addr             : String);
     map!(ica         : u32 => icq_id           : u64);
+    map!(ica         : u32 => status_height    : u64);
+    map!(ica         : u32 => delegations_icq_id    : u64);
+    map!(ica         : u32 => transfers_icq_id      : u64);
+    map!(ica         : u32 => remote_deposit_count  : u32);
+    map!(ica         : u32 => delegations_validator : String);
+    map!(ica         : u32 => label            : String);
+    map!(ica         : u32 => note             : String);
     map!(ica         : u32 => tx_issued_count  : u32);
     map!(ica         : u32 => tx_success_count : u32);
     map!(ica         : u32 => tx_error_count   : u32);
     map!(ica         : u32 => tx_timeout_count : u32);
     map!(ica         : u32 => round_trip_count : u32);
+    // the local height the last tx of each ICA was acknowledged or timed out at
+    map!(ica         : u32 => settled_height   : u64);
+    // the retrievals of each ICA whose rx hash is yet to be consumed by the hook, the failure of its tx or the sweep
+    map!(ica         : u32 => undelivered_retrievals : u32);
+    map!(ica         : u32 => tx_history_len   : u32);
+    map!(tx_hash     : str => history_idx      : u32);
+    // the local height tx & rx hash mappings were stamped at, entries of the hash log with another height are stale
+    map!(tx_hash     : str => height           : u64);
+    map!(rx_hash     : str => height           : u64);
+    // the owner who sent an outgoing transfer, refunded to them if the transfer fails
+    map!(tx_hash     : str => transfer_sender  : String);
     map!(ica_tx_kind : u64 => seq_num          : u64);
     map!(ica_err_idx : u64 => msg              : String);
     map!(icq         : u64 => ica_idx          : u32);
+
+    json_item!(fee_history     : Vec<FeeObservation>);
+    json_item!(leaderboard     : Vec<LeaderboardEntry>);
+    json_item!(channel_volumes : Vec<ChannelVolume>);
+    json_item!(additional_transfer_denoms : Vec<String>);
+    json_item!(alert_subscribers : Vec<String>);
+    json_item!(approved_alert_subscribers : Vec<String>);
+    json_item!(max_memo_len        : u32);
+    json_item!(max_hook_memo_len   : u32);
+    // charged as the ICQ deposit while the interchainqueries params cannot be queried
+    json_item!(icq_deposit_override : Vec<Coin>);
+    // set while new traffic is halted by the contract owner
+    json_item!(paused          : bool);
+    // set while the global stats & activity log are committed to, the commitment is kept while unset so that
+    // the activity digest carries on once enabled again
+    json_item!(state_commitments_enabled : bool);
+    json_item!(state_commitment : StateCommitment);
+    // the rolling hash of the activity entries pruned from the log, replaying the log starts from it
+    json_item!(pruned_activity_digest : String);
+    json_item!(global_totals   : GlobalTotals);
+
+    json_map!(ica         : u32 => round_trip_volume : Uint128);
+    json_map!(ica         : u32 => claimed_rewards   : Uint128);
+    // the latest completion time of the undelegations of each ICA, until a balance result is received after it
+    json_map!(ica         : u32 => unbonding_until   : Timestamp);
+    // txs issued in the current execution awaiting their reply, in submessage order
+    json_map!(ica         : u32 => pending_txs       : Vec<IssuedTx>);
+    json_map!(tx_hash     : str => issued_tx         : IssuedTx);
+    // txs in flight, from their reply until their acknowledgement or timeout
+    json_map!(tx_hash     : str => pending_tx        : PendingTx);
+    // the hashes of the txs in flight of each ICA, in issue order
+    json_map!(ica         : u32 => pending_tx_hashes : Vec<String>);
+    json_map!(ica         : u32 => channel           : IcaChannel);
+    // the version of the `HashScheme` each hash was issued under
+    json_map!(tx_hash     : str => scheme            : u8);
+    json_map!(rx_hash     : str => scheme            : u8);
+    json_map!(ica_tx_kind : u64 => failed_tx         : IssuedTx);
+    // deposits into each ICA observed by its transfers ICQ, keyed by (ICA index, deposit index)
+    json_map!(ica_deposit_idx : u64 => remote_deposit : RemoteDeposit);
+    // append-only log of the transfers & retrievals of each ICA, keyed by (ICA index, history index)
+    json_map!(ica_history_idx : u64 => tx_entry      : TxHistoryEntry);
+    json_map!(ica         : u32 => icq_refund        : IcqRefund);
+    // the ICQ deposit paid at setup, until the ICQ is registered on the open ack
+    json_map!(ica         : u32 => setup_deposit     : Vec<Coin>);
+    // iterable index of the ICAs currently setup
+    json_map!(ica         : u32 => listed_owner      : String);
+    json_map!(ica         : u32 => auto_retrieve     : bool);
+    // labelled remote addresses, ordered by address
+    json_map!(ica         : u32 => address_book      : Vec<AddressBookEntry>);
+    // absent for unregistered ICA slots, kept once an ICA is retired
+    json_map!(ica         : u32 => status            : IcaStatus);
+    json_map!(owner       : str => fee_balance       : Vec<Coin>);
+    // the operators granted an allowance by each owner, ordered by operator
+    json_map!(owner       : str => operators         : Vec<OperatorGrant>);
+    json_map!(icq         : u64 => quarantined_result : QuarantinedIcqResult);
+    // the bounds of the amount of each transfer denom transferred, absent if unbounded
+    json_map!(denom       : str => transfer_limits   : DenomTransferLimits);
+    // set when an ICQ is found to have been re-registered as another type of query
+    json_map!(icq         : u64 => invalid           : bool);
+    // acknowledged packets that could not be attributed to an issued tx
+    json_map!(dead_letter : u32 => packet            : DeadLetter);
+    // retrieval hooks that failed validation, removed once resolved
+    json_map!(dead_letter : u32 => hook              : HookDeadLetter);
+    // the amount & host denom of the transfer asset each retrieval is expected to deliver, removed with the
+    // ICA index of the rx hash once delivered
+    json_map!(rx_hash     : str => expected_amount   : Uint128);
+    json_map!(rx_hash     : str => expected_denom    : String);
+    // the hook sender of retrievals forwarded through intermediate chains, direct retrievals derive it from the ICA
+    json_map!(rx_hash     : str => hook_sender       : String);
+    // the trace path of the funds of retrievals forwarded through intermediate chains
+    json_map!(rx_hash     : str => expected_path     : String);
+    // the routes retrievals are allowed to be forwarded through, keyed by name
+    json_map!(retrieval   : str => route             : RetrievalRoute);
+    // changes to tx history entries logged while state commitments are enabled, keyed by activity id
+    json_map!(activity    : u64 => entry             : ActivityEntry);
+    // an indexer is authorized if it has a checkpoint entry
+    json_map!(indexer     : str => checkpoint        : IndexerCheckpoint);
+    // the tx & rx hash mappings in stamping order, hence in ascending height order
+    json_map!(hash_log    : u64 => entry             : HashLogEntry);
+
+    #[cw_serde]
+    pub enum HashKind {
+        Tx,
+        Rx,
+    }
+
+    #[cw_serde]
+    #[derive(Copy)]
+    pub enum ReplyKind {
+        RegisterIcq,
+        RegisterDelegationsIcq,
+        RegisterTransfersIcq,
+        RemoveIcq,
+        IssueTx(TxKind),
+        AlertHook,
+        RetrieveClaimedRewards,
+    }
+
+    /// The context of a submessage, recorded against its reply id until the reply is received
+    #[cw_serde]
+    pub struct PendingReply {
+        pub kind: ReplyKind,
+        pub ica_idx: u32,
+        /// The amount moved or staked by the tx, for the replies of txs other than claim rewards & custom txs
+        pub coin: Option<Coin>,
+    }
+
+    /// A tx or rx hash mapping stamped at the local `height`
+    #[cw_serde]
+    pub struct HashLogEntry {
+        pub kind: HashKind,
+        pub hash: String,
+        pub height: u64,
+    }
+
+    /// The ICQ deposit to refund once the ICQ of a torn down ICA is removed
+    #[cw_serde]
+    pub struct IcqRefund {
+        pub owner: String,
+        pub deposit: Vec<Coin>,
+    }
+
+    /// The state layout replaced in version 6, only read by the migration
+    pub mod v5 {
+        use common::json_item;
+        use cosmwasm_std::Uint128;
+
+        // the bounds of every transfer denom
+        json_item!(min_transfer_amount : Uint128);
+        json_item!(max_transfer_amount : Uint128);
+    }
+
+    /// The state layout replaced in version 4, only read by the migration
+    pub mod v3 {
+        use common::{json_item, json_map};
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::Coin;
+        use neutron_sdk::bindings::msg::IbcFee;
+
+        json_item!(fee_history     : Vec<FeeObservation>);
+        json_map!(ica : u32 => setup_deposit : Coin);
+
+        #[cw_serde]
+        pub struct FeeObservation {
+            pub height: u64,
+            pub min_ibc_fee: IbcFee,
+            pub icq_deposit: Coin,
+        }
+    }
 }
 
-#[entry_point]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "handling instantiate msg");
+/// The statuses in which the outcome of an issued tx can be received: transfers aren't sent over the ICA channel
+/// & can settle after it closes or while it is reopened, retired ICAs never have txs in flight
+pub const TX_OUTCOME_STATUSES: [IcaStatus; 3] = [
+    IcaStatus::Active,
+    IcaStatus::ChannelClosed,
+    IcaStatus::Reopening,
+];
 
-    // save configuration
-    state::set_connection_id(deps.storage, &msg.connection_id);
+#[must_use]
+pub fn ica_status(storage: &dyn Storage, ica_idx: u32) -> IcaStatus {
+    state::ica_status(storage, &ica_idx).unwrap_or_default()
+}
 
-    state::set_ibc_transfer_channel(deps.storage, &msg.ibc_transfer_channel);
+/// Transition the ICA at `ica_idx` to `status` at the local `height`
+pub fn set_ica_status(storage: &mut dyn Storage, ica_idx: u32, status: IcaStatus, height: u64) {
+    state::set_ica_status(storage, &ica_idx, &status);
+    state::set_ica_status_height(storage, &ica_idx, height);
+}
 
-    state::set_icq_update_period(deps.storage, msg.icq_update_period);
+/// Precondition of handlers acting on the ICA at `ica_idx`
+pub fn ensure_ica_status(
+    storage: &dyn Storage,
+    ica_idx: u32,
+    expected: &[IcaStatus],
+) -> Result<(), Error> {
+    let status = ica_status(storage, ica_idx);
 
-    state::set_remote_denom(deps.storage, &msg.remote_denom);
+    if !expected.contains(&status) {
+        return Err(Error::UnexpectedIcaStatus {
+            ica_idx,
+            status,
+            expected: expected.to_vec(),
+        });
+    }
 
-    state::set_host_ibc_denom(deps.storage, &msg.host_ibc_denom);
+    Ok(())
+}
 
-    Ok(Response::default())
+/// The ICA index of `owner`, which must be active to issue txs
+pub fn active_ica_idx(storage: &dyn Storage, owner: &str) -> Result<u32, Error> {
+    let ica_idx = state::owner_ica_idx(storage, owner).ok_or(Error::NoIcaSetup)?;
+
+    ensure_ica_status(storage, ica_idx, &[IcaStatus::Active])?;
+
+    Ok(ica_idx)
 }
 
-pub fn execute_setup_ica(
+/// An event for a state transition of the ICA at `ica_idx`, emitted as `wasm-{ty}`
+#[must_use]
+pub fn ica_event(ty: &str, ica_idx: u32) -> Event {
+    Event::new(ty).add_attribute("ica_idx", ica_idx.to_string())
+}
+
+pub fn save_timeouts(
     deps: DepsMut<impl CustomQuery>,
-    info: MessageInfo,
-) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "executing setup ica");
+    timeout_seconds: Option<u64>,
+    timeout_height: Option<u64>,
+    timeout_revision_number: Option<u64>,
+) -> Result<(), Error> {
+    if timeout_seconds == Some(0) || timeout_height == Some(0) {
+        return Err(Error::ZeroTimeout);
+    }
 
-    let owner = info.sender.into_string();
+    if let Some(timeout_seconds) = timeout_seconds {
+        state::set_timeout_seconds(deps.storage, timeout_seconds);
+    }
 
-    debug!(deps, "setting up ica for {owner}");
+    if let Some(timeout_height) = timeout_height {
+        state::set_timeout_height(deps.storage, timeout_height);
+    }
 
-    // get required ICQ deposit fee
-    let icq_deposit_fee = icq_deposit_fee(deps.as_ref())?;
+    // zero is a valid revision number, for chain ids without a revision suffix
+    if let Some(timeout_revision_number) = timeout_revision_number {
+        state::set_timeout_revision_number(deps.storage, timeout_revision_number);
+    }
 
-    // check sender has provided the required funds for a single balance ICQ deposit
-    let deposit = info.funds.first().ok_or(Error::IcqDepositMissing)?;
+    Ok(())
+}
 
-    if deposit.denom != icq_deposit_fee.denom {
-        return Err(Error::IncorrectIcqDepositAsset);
+/// Replace the transfer limits of `limits.denom`, which must be a transfer denom, removing them if both are unset
+pub fn save_transfer_limits(
+    storage: &mut dyn Storage,
+    limits: &DenomTransferLimits,
+) -> Result<(), Error> {
+    if !transfer_denoms(storage).contains(&limits.denom) {
+        return Err(Error::DenomNotTransferable {
+            denom: limits.denom.clone(),
+        });
     }
 
-    if deposit.amount < icq_deposit_fee.amount {
-        return Err(Error::InsufficientIcqDeposit);
+    if let (Some(min), Some(max)) = (limits.min_transfer_amount, limits.max_transfer_amount) {
+        if min > max {
+            return Err(Error::InvalidTransferLimits { min, max });
+        }
     }
 
-    let next_ica_idx = state::ica_count(deps.storage).unwrap_or_default();
+    if limits.min_transfer_amount.is_none() && limits.max_transfer_amount.is_none() {
+        state::remove_denom_transfer_limits(storage, &limits.denom);
+    } else {
+        state::set_denom_transfer_limits(storage, &limits.denom, limits);
+    }
 
-    state::set_ica_count(deps.storage, next_ica_idx + 1);
+    Ok(())
+}
 
-    state::set_owner_ica_idx(deps.storage, &owner, next_ica_idx);
+pub fn save_memo_limits(
+    storage: &mut dyn Storage,
+    max_memo_len: Option<u32>,
+    max_hook_memo_len: Option<u32>,
+) -> Result<(), Error> {
+    let limits = MemoLimits {
+        max_memo_len: max_memo_len.map_or(MAX_IBC_MEMO_LEN, |len| len as usize),
+        max_hook_memo_len: max_hook_memo_len.map_or(MAX_IBC_MEMO_LEN, |len| len as usize),
+    };
 
-    state::set_ica_owner(deps.storage, &next_ica_idx, &owner);
+    if !(1..=MAX_IBC_MEMO_LEN).contains(&limits.max_memo_len)
+        || !(1..=limits.max_memo_len).contains(&limits.max_hook_memo_len)
+    {
+        return Err(Error::InvalidMemoLimits);
+    }
 
-    let connection_id = state::connection_id(deps.storage);
+    match max_memo_len {
+        Some(len) => state::set_max_memo_len(storage, &len),
+        None => state::remove_max_memo_len(storage),
+    }
 
-    let registration_msg = NeutronMsg::RegisterInterchainAccount {
-        connection_id,
-        interchain_account_id: next_ica_idx.to_string(),
-    };
+    match max_hook_memo_len {
+        Some(len) => state::set_max_hook_memo_len(storage, &len),
+        None => state::remove_max_hook_memo_len(storage),
+    }
 
-    Ok(Response::default().add_message(registration_msg))
+    Ok(())
 }
 
+/// The limits the memos built for retrievals are checked against before submission
 #[must_use]
-pub fn is_ibc_fee_covered(info: &MessageInfo, ibc_fee: &IbcFee) -> bool {
-    assert_eq!(ibc_fee.ack_fee.len(), 1, "only a single ibc ack fee asset");
-    assert_eq!(
-        ibc_fee.timeout_fee.len(),
-        1,
-        "only a single ibc timeout fee asset"
-    );
+pub fn memo_limits(storage: &dyn Storage) -> MemoLimits {
+    let defaults = MemoLimits::default();
+
+    MemoLimits {
+        max_memo_len: state::max_memo_len(storage)
+            .map_or(defaults.max_memo_len, |len| len as usize),
+        max_hook_memo_len: state::max_hook_memo_len(storage)
+            .map_or(defaults.max_hook_memo_len, |len| len as usize),
+    }
+}
 
-    let Some(attached_fee_coin_amount) = info
-        .funds
-        .iter()
-        .find_map(|c| (c.denom == IBC_FEE_DENOM).then_some(c.amount.u128()))
-    else {
-        return false;
+/// Ensure the amount of `coin` is within the transfer limits of its denom
+pub fn ensure_transfer_amount_in_limits(storage: &dyn Storage, coin: &Coin) -> Result<(), Error> {
+    let Some(limits) = state::denom_transfer_limits(storage, &coin.denom) else {
+        return Ok(());
     };
 
-    let total_fee_amount: u128 = ibc_fee
-        .timeout_fee
-        .iter()
-        .chain(ibc_fee.ack_fee.iter())
-        .filter_map(|c| (c.denom == IBC_FEE_DENOM).then_some(c.amount.u128()))
-        .sum();
+    let amount = coin.amount;
+
+    if let Some(min) = limits.min_transfer_amount.filter(|min| amount < *min) {
+        return Err(Error::TransferBelowMinimum {
+            amount,
+            denom: limits.denom,
+            min,
+        });
+    }
+
+    if let Some(max) = limits.max_transfer_amount.filter(|max| amount > *max) {
+        return Err(Error::TransferAboveMaximum {
+            amount,
+            denom: limits.denom,
+            max,
+        });
+    }
 
-    attached_fee_coin_amount >= total_fee_amount
+    Ok(())
 }
 
-pub fn execute_transfer_funds(
-    deps: DepsMut<NeutronQuery>,
-    env: Env,
+#[entry_point]
+pub fn instantiate(
+    mut deps: DepsMut,
+    _env: Env,
     info: MessageInfo,
+    msg: InstantiateMsg,
 ) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "executing transfer funds");
+    debug!(deps, "handling instantiate msg");
+
+    msg.validate()?;
+
+    // reject configs pointing at connections & channels that cannot carry the contract's traffic
+    ensure_connection_open(deps.as_ref(), &msg.connection_id)?;
+
+    ensure_channel_open(deps.as_ref(), "transfer", &msg.ibc_transfer_channel)?;
+
+    let contract_owner = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?.into_string(),
+        None => info.sender.into_string(),
+    };
+
+    state::set_contract_owner(deps.storage, &contract_owner);
 
-    let min_ibc_fee = query_min_ibc_fee(deps.as_ref()).map(|res| res.min_fee)?;
+    save_timeouts(
+        deps.branch(),
+        msg.timeout_seconds,
+        msg.timeout_height,
+        msg.timeout_revision_number,
+    )?;
+
+    save_memo_limits(deps.storage, msg.max_memo_len, msg.max_hook_memo_len)?;
 
-    if !is_ibc_fee_covered(&info, &min_ibc_fee) {
-        return Err(Error::InsufficientIbcTxFee);
+    save_hash_retention(deps.storage, msg.hash_retention_blocks)?;
+
+    if let Some(deposit) = &msg.icq_deposit_override {
+        state::set_icq_deposit_override(deps.storage, deposit);
     }
 
-    let tx_denom = state::host_ibc_denom(deps.storage);
+    // save configuration
+    state::set_connection_id(deps.storage, &msg.connection_id);
 
-    let tx_coin = info
-        .funds
-        .into_iter()
-        .find(|c| c.denom == tx_denom)
-        .ok_or(Error::NoFundsToTransfer)?;
+    state::set_ibc_transfer_channel(deps.storage, &msg.ibc_transfer_channel);
 
-    let owner = info.sender.as_str();
+    state::set_icq_update_period(deps.storage, msg.icq_update_period);
 
-    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or(Error::NoIcaSetup)?;
+    state::set_remote_denom(deps.storage, &msg.remote_denom);
 
-    let ica_addr = state::ica_addr(deps.storage, &ica_idx).ok_or(Error::NoIcaSetup)?;
+    state::set_host_ibc_denom(deps.storage, &msg.host_ibc_denom);
 
-    let source_channel = state::ibc_transfer_channel(deps.storage);
+    let additional_transfer_denoms = msg.additional_transfer_denoms.unwrap_or_default();
 
-    debug!(
-        deps,
-        "transfering {tx_coin} to {ica_addr} on behalf of {owner}"
-    );
+    if additional_transfer_denoms
+        .iter()
+        .chain([&msg.host_ibc_denom])
+        .any(|denom| denom == IBC_FEE_DENOM)
+    {
+        return Err(Error::FeeDenomNotTransferable);
+    }
 
-    let ibc_transfer_msg = NeutronMsg::IbcTransfer {
-        source_port: "transfer".to_owned(),
-        source_channel,
-        sender: env.contract.address.into_string(),
-        receiver: ica_addr,
-        token: tx_coin,
-        timeout_height: RequestPacketTimeoutHeight {
-            revision_number: Some(2),
-            revision_height: Some(DEFAULT_TIMEOUT_HEIGHT),
-        },
-        timeout_timestamp: 0,
-        memo: String::new(),
-        fee: min_ibc_fee,
-    };
+    if !additional_transfer_denoms.is_empty() {
+        state::set_additional_transfer_denoms(deps.storage, &additional_transfer_denoms);
+    }
+
+    // the limits are checked against the transfer denoms saved above
+    for limits in msg.transfer_limits.iter().flatten() {
+        save_transfer_limits(deps.storage, limits)?;
+    }
 
-    let response = Response::default().add_submessage(SubMsg::reply_on_success(
-        ibc_transfer_msg,
-        combine_u32s(TRANSFER_TX_REPLY_KIND, ica_idx),
-    ));
+    state::set_contract_version(deps.storage, CONTRACT_VERSION);
 
-    Ok(response)
+    Ok(Response::default())
 }
 
-pub fn make_ibc_transfer_with_hook_msg<Msg: Serialize>(
-    source_channel: String,
-    token: Coin,
-    sender: String,
-    timeout_timestamp: u64,
-    recipient: Addr,
-    msg: Msg,
-) -> ProtobufAny {
-    #[derive(Clone, PartialEq, Message)]
-    struct RawCoin {
-        #[prost(string, tag = "1")]
-        pub denom: String,
-        #[prost(string, tag = "2")]
-        pub amount: String,
-    }
+/// Version 0 instances were deployed before the state marker existed,
+/// backfill the reverse lookup maps in case any are missing.
+fn migrate_v0_to_v1(deps: &mut DepsMut) {
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
+
+    for ica_idx in 0..ica_count {
+        if let Some(owner) = state::ica_owner(deps.storage, &ica_idx) {
+            if state::owner_ica_idx(deps.storage, &owner).is_none() {
+                debug!(deps, "backfilling owner {owner} for ICA {ica_idx}");
+                state::set_owner_ica_idx(deps.storage, &owner, ica_idx);
+            }
+        }
 
-    impl From<Coin> for RawCoin {
-        fn from(value: Coin) -> Self {
-            Self {
-                denom: value.denom,
-                amount: value.amount.to_string(),
+        if let Some(icq_id) = state::ica_icq_id(deps.storage, &ica_idx) {
+            if state::icq_ica_idx(deps.storage, &icq_id).is_none() {
+                debug!(deps, "backfilling ICQ {icq_id} for ICA {ica_idx}");
+                state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
             }
         }
     }
+}
 
-    #[derive(Clone, PartialEq, Message)]
-    struct Height {
-        #[prost(uint64, tag = "1")]
-        pub revision_number: u64,
-        #[prost(uint64, tag = "2")]
-        pub revision_height: u64,
+/// Version 1 instances have no iterable ICA index, build it from the owner map.
+fn migrate_v1_to_v2(deps: &mut DepsMut) {
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
+
+    for ica_idx in 0..ica_count {
+        if let Some(owner) = state::ica_owner(deps.storage, &ica_idx) {
+            state::set_ica_listed_owner(deps.storage, &ica_idx, &owner);
+        }
     }
+}
 
-    #[derive(Clone, PartialEq, Message)]
-    struct MsgTransfer {
-        #[prost(string, tag = "1")]
-        pub source_port: String,
-        #[prost(string, tag = "2")]
-        pub source_channel: String,
-        #[prost(message, optional, tag = "3")]
-        pub token: Option<RawCoin>,
-        #[prost(string, tag = "4")]
-        pub sender: String,
-        #[prost(string, tag = "5")]
-        pub receiver: String,
-        #[prost(message, optional, tag = "6")]
-        pub timeout_height: Option<Height>,
-        #[prost(uint64, tag = "7")]
-        pub timeout_timestamp: u64,
-        #[prost(string, tag = "8")]
-        pub memo: String,
-    }
-
-    #[derive(Serialize)]
-    struct IbcHookWasm<Msg> {
-        contract: String,
-        msg: Msg,
-    }
-
-    #[derive(Serialize)]
-    struct IbcHookMemo<Msg> {
-        wasm: IbcHookWasm<Msg>,
-    }
-
-    let ibc_hook = IbcHookMemo {
-        wasm: IbcHookWasm {
-            contract: recipient.clone().into_string(),
-            msg,
-        },
-    };
+/// Version 2 instances don't track the ICA lifecycle, infer the status of setup ICAs from their address.
+/// Torn down ICAs left no trace & stay unregistered, the migration height is recorded as the last status change.
+fn migrate_v2_to_v3(deps: &mut DepsMut, env: &Env) {
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
 
-    let memo = serde_json_wasm::to_string(&ibc_hook).expect("infallible serialization");
+    for ica_idx in 0..ica_count {
+        if state::ica_owner(deps.storage, &ica_idx).is_none() {
+            continue;
+        }
 
-    let transfer_msg = MsgTransfer {
-        source_port: "transfer".to_owned(),
-        source_channel,
-        token: Some(token.into()),
-        sender,
-        receiver: recipient.into_string(),
-        timeout_height: None,
-        timeout_timestamp,
-        memo,
-    };
+        let status = if state::ica_addr(deps.storage, &ica_idx).is_some() {
+            IcaStatus::Active
+        } else {
+            IcaStatus::Registering
+        };
 
-    ProtobufAny {
-        type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_owned(),
-        value: transfer_msg.encode_to_vec().into(),
+        set_ica_status(deps.storage, ica_idx, status, env.block.height);
     }
 }
 
-pub fn execute_retrieve_funds(
-    deps: DepsMut<NeutronQuery>,
-    env: Env,
-    info: MessageInfo,
-) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "executing retrieve funds");
+/// Version 3 instances only support single coin ICQ deposits, wrap the setup deposits & observed fees.
+fn migrate_v3_to_v4(deps: &mut DepsMut) {
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
 
-    let min_ibc_fee = query_min_ibc_fee(deps.as_ref()).map(|res| res.min_fee)?;
+    for ica_idx in 0..ica_count {
+        if let Some(deposit) = state::v3::ica_setup_deposit(deps.storage, &ica_idx) {
+            state::set_ica_setup_deposit(deps.storage, &ica_idx, &vec![deposit]);
+        }
+    }
 
-    if !is_ibc_fee_covered(&info, &min_ibc_fee) {
-        return Err(Error::InsufficientIbcTxFee);
+    if let Some(history) = state::v3::fee_history(deps.storage) {
+        let history: Vec<FeeObservation> = history
+            .into_iter()
+            .map(|observation| FeeObservation {
+                height: observation.height,
+                min_ibc_fee: observation.min_ibc_fee,
+                icq_deposit: vec![observation.icq_deposit],
+            })
+            .collect();
+
+        state::set_fee_history(deps.storage, &history);
     }
+}
 
-    let owner = info.sender.as_str();
+/// Version 4 instances only count txs & roundtrips per ICA, sum the counters of the ICAs still setup.
+/// Torn down ICAs left no trace & are missing from the totals.
+fn migrate_v4_to_v5(deps: &mut DepsMut) {
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
 
-    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or(Error::NoIcaSetup)?;
+    let mut totals = GlobalTotals::default();
 
-    let ica_balance_icq = state::ica_icq_id(deps.storage, &ica_idx).ok_or(Error::NoIcaSetup)?;
+    for ica_idx in 0..ica_count {
+        let count = |count: Option<u32>| u64::from(count.unwrap_or_default());
 
-    let non_zero_remote_balance = query_balance_icq(deps.as_ref(), ica_balance_icq)?
-        .and_then(|res| res.balance)
-        .filter(|remote_balance| !remote_balance.amount.is_zero())
-        .ok_or(Error::NoFundsToRetrieve)?;
+        totals.tx_issued += count(state::ica_tx_issued_count(deps.storage, &ica_idx));
+        totals.tx_success += count(state::ica_tx_success_count(deps.storage, &ica_idx));
+        totals.tx_error += count(state::ica_tx_error_count(deps.storage, &ica_idx));
+        totals.tx_timeout += count(state::ica_tx_timeout_count(deps.storage, &ica_idx));
+        totals.round_trips += count(state::ica_round_trip_count(deps.storage, &ica_idx));
+    }
 
-    let ica_addr = state::ica_addr(deps.storage, &ica_idx).ok_or(Error::NoIcaSetup)?;
+    state::set_global_totals(deps.storage, &totals);
+}
 
-    let connection_id = state::connection_id(deps.storage);
+/// Version 5 instances bound the amount of every transfer denom alike, scope the bounds to each transfer denom.
+fn migrate_v5_to_v6(deps: &mut DepsMut) {
+    let min_transfer_amount = state::v5::min_transfer_amount(deps.storage);
 
-    let source_channel = state::ibc_transfer_channel(deps.storage);
+    let max_transfer_amount = state::v5::max_transfer_amount(deps.storage);
 
-    let timeout_timestamp = env.block.time.plus_seconds(DEFAULT_TIMEOUT_SECONDS).nanos();
+    if min_transfer_amount.is_some() || max_transfer_amount.is_some() {
+        for denom in transfer_denoms(deps.storage) {
+            let limits = DenomTransferLimits {
+                denom,
+                min_transfer_amount,
+                max_transfer_amount,
+            };
 
-    let tx_idx = state::ica_tx_issued_count(deps.storage, &ica_idx).unwrap_or_default();
+            state::set_denom_transfer_limits(deps.storage, &limits.denom, &limits);
+        }
+    }
 
-    let rx_hash = hash!(
-        ica_addr,
-        non_zero_remote_balance.amount.u128().to_be_bytes(),
-        tx_idx.to_be_bytes()
-    );
+    state::v5::remove_min_transfer_amount(deps.storage);
 
-    // save the ICA idx against the rx hash
-    state::set_rx_hash_ica_idx(deps.storage, &rx_hash, ica_idx);
+    state::v5::remove_max_transfer_amount(deps.storage);
+}
 
-    let ibc_transfer_msg = make_ibc_transfer_with_hook_msg(
-        source_channel,
-        non_zero_remote_balance,
-        ica_addr,
-        timeout_timestamp,
-        env.contract.address,
-        // attach the rx hash to the callback message
-        ExecuteMsg::FundsRetrievedHook { rx_hash },
+#[entry_point]
+pub fn migrate(
+    mut deps: DepsMut,
+    env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    let from = state::contract_version(deps.storage).unwrap_or_default();
+
+    debug!(
+        deps,
+        "handling migrate msg: version {from} -> {CONTRACT_VERSION}"
     );
 
-    let ica_submit_tx_msg = NeutronMsg::SubmitTx {
-        connection_id,
-        interchain_account_id: ica_idx.to_string(),
-        msgs: vec![ibc_transfer_msg],
-        memo: String::new(),
-        timeout: DEFAULT_TIMEOUT_SECONDS,
-        fee: min_ibc_fee,
-    };
+    if from > CONTRACT_VERSION {
+        return Err(Error::InvalidMigration {
+            from,
+            to: CONTRACT_VERSION,
+        });
+    }
 
-    let response = Response::default().add_submessage(SubMsg::reply_on_success(
-        ica_submit_tx_msg,
-        combine_u32s(RETRIEVE_TX_REPLY_KIND, ica_idx),
-    ));
+    if from < 1 {
+        migrate_v0_to_v1(&mut deps);
+    }
 
-    Ok(response)
-}
+    if from < 2 {
+        migrate_v1_to_v2(&mut deps);
+    }
 
-pub fn execute_funds_retrieved_hook(
-    deps: DepsMut<impl CustomQuery>,
-    info: MessageInfo,
-    rx_hash: &str,
-) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "executing retrieve funds: rx_hash = {rx_hash}");
+    if from < 3 {
+        migrate_v2_to_v3(&mut deps, &env);
+    }
 
-    let ica_idx = state::rx_hash_ica_idx(deps.storage, rx_hash).ok_or(Error::InvalidRxHash)?;
+    if from < 4 {
+        migrate_v3_to_v4(&mut deps);
+    }
 
-    let current_round_trip_count =
-        state::ica_round_trip_count(deps.storage, &ica_idx).unwrap_or_default();
+    if from < 5 {
+        migrate_v4_to_v5(&mut deps);
 
-    state::set_ica_round_trip_count(deps.storage, &ica_idx, current_round_trip_count + 1);
+        // the totals summed by the migration are part of the committed stats
+        commit_stats(deps.storage, env.block.height);
+    }
 
-    let ica_owner = state::ica_owner(deps.storage, &ica_idx).expect("ica must have an owner");
+    if from < 6 {
+        migrate_v5_to_v6(&mut deps);
+    }
 
-    // forward the funds recieved from the ICA to it's owner
-    let msg = BankMsg::Send {
-        to_address: ica_owner,
-        amount: info.funds,
-    };
+    state::set_contract_version(deps.storage, CONTRACT_VERSION);
 
-    Ok(Response::default().add_message(msg))
+    Ok(Response::default())
 }
 
-#[entry_point]
-pub fn execute(
-    deps: DepsMut<NeutronQuery>,
+/// The deposit required to register an ICQ, the admin's override is charged if the interchainqueries params cannot be queried
+pub fn required_icq_deposit(
+    deps: Deps<impl CustomQuery + From<NeutronQuery>>,
+) -> Result<Vec<Coin>, Error> {
+    let deposit = icq_deposit_fee_or(deps, state::icq_deposit_override(deps.storage))?;
+
+    Ok(deposit)
+}
+
+pub fn execute_setup_ica(
+    deps: DepsMut<impl CustomQuery + From<NeutronQuery>>,
     env: Env,
     info: MessageInfo,
-    msg: ExecuteMsg,
 ) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "handling execute msg");
-
-    match msg {
-        ExecuteMsg::SetupIca {} => execute_setup_ica(deps, info),
+    debug!(deps, "executing setup ica");
 
-        ExecuteMsg::TransferFunds {} => execute_transfer_funds(deps, env, info),
+    let owner = info.sender.into_string();
 
-        ExecuteMsg::RetrieveFunds {} => execute_retrieve_funds(deps, env, info),
+    debug!(deps, "setting up ica for {owner}");
 
-        ExecuteMsg::FundsRetrievedHook { rx_hash } => {
-            execute_funds_retrieved_hook(deps, info, &rx_hash)
-        }
+    if state::owner_ica_idx(deps.storage, &owner).is_some() {
+        return Err(Error::IcaAlreadySetup);
     }
-}
 
-pub fn sudo_open_ack(
-    deps: DepsMut<NeutronQuery>,
-    port_id: String,
-    counterparty_version: String,
-) -> Result<Response<NeutronMsg>, Error> {
-    debug!(
-        deps,
-        "received open ack for {port_id}: {counterparty_version}"
-    );
-
-    // The version variable contains a JSON value with multiple fields,
-    // including the generated account address.
-    let parsed_version: OpenAckVersion =
-        from_slice(counterparty_version.as_bytes()).expect("valid counterparty_version");
+    // get required ICQ deposit fee
+    let icq_deposit_fee = required_icq_deposit(deps.as_ref())?;
 
-    let ica_idx = common::ica_idx_from_port_id(&port_id).expect("valid port id");
+    // check sender has provided the required funds for a single balance ICQ deposit
+    let deposit = check_icq_deposit(&info.funds, &icq_deposit_fee)?;
 
-    let ica_addr = parsed_version.address;
+    // coins of other denoms are not escrowed along with the deposit, so they are refunded rather than kept
+    let refund = foreign_deposit_coins(&info.funds, &icq_deposit_fee);
 
-    state::set_ica_addr(deps.storage, &ica_idx, &ica_addr);
+    let next_ica_idx = state::ica_count(deps.storage).unwrap_or_default();
 
-    let connection_id = state::connection_id(deps.storage);
+    state::set_ica_count(deps.storage, next_ica_idx + 1);
 
-    let icq_update_period = state::icq_update_period(deps.storage);
+    commit_stats(deps.storage, env.block.height);
 
-    let balance_icq_denom = state::remote_denom(deps.storage);
+    state::set_owner_ica_idx(deps.storage, &owner, next_ica_idx);
 
-    let balance_icq_register_msg = new_register_balance_query_msg(
-        connection_id.clone(),
-        ica_addr.clone(),
-        balance_icq_denom,
-        icq_update_period,
-    )?;
+    state::set_ica_owner(deps.storage, &next_ica_idx, &owner);
 
-    let response = Response::default().add_submessage(SubMsg::reply_on_success(
-        balance_icq_register_msg,
-        combine_u32s(REGISTER_ICQ_REPLY_KIND, ica_idx),
-    ));
+    state::set_ica_listed_owner(deps.storage, &next_ica_idx, &owner);
 
-    Ok(response)
-}
+    set_ica_status(
+        deps.storage,
+        next_ica_idx,
+        IcaStatus::Registering,
+        env.block.height,
+    );
 
-pub fn sudo_response(
-    deps: DepsMut<NeutronQuery>,
-    request: RequestPacket,
-) -> Result<Response<NeutronMsg>, Error> {
-    let tx_seq_num = request.sequence.expect("sequence number always set");
+    state::set_ica_setup_deposit(deps.storage, &next_ica_idx, &deposit);
 
-    let source_channel = request.source_channel.expect("source channel always set");
+    let connection_id = state::connection_id(deps.storage);
 
-    let tx_hash = hash!(tx_seq_num.to_be_bytes(), source_channel);
+    let registration_msg = NeutronMsg::RegisterInterchainAccount {
+        connection_id,
+        interchain_account_id: next_ica_idx.to_string(),
+    };
 
-    debug!(deps, "received sudo response for tx: {tx_hash}");
+    let event = ica_event("ica_setup", next_ica_idx)
+        .add_attribute("owner", owner.as_str())
+        .add_attribute("refund", coins_attr(&refund));
 
-    let ica_idx = state::tx_hash_ica_idx(deps.storage, &tx_hash)
-        .expect("a sequence number is always associated with an ica idx");
+    let mut response = Response::default()
+        .add_message(registration_msg)
+        .add_event(event);
 
-    let mut tx_success_count =
-        state::ica_tx_success_count(deps.storage, &ica_idx).unwrap_or_default();
+    if !refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: owner,
+            amount: refund,
+        });
+    }
 
-    tx_success_count += 1;
+    Ok(response)
+}
 
-    debug!(
-        deps,
-        "ICA {ica_idx} issued tx with sequence number {tx_seq_num} successfully, total success count: {tx_success_count}"
-    );
+/// The host denoms accepted by `TransferFunds`, the transfer asset first, the only one retrieved back
+#[must_use]
+pub fn transfer_denoms(storage: &dyn Storage) -> Vec<String> {
+    let mut denoms = vec![state::host_ibc_denom(storage)];
 
-    state::set_ica_tx_success_count(deps.storage, &ica_idx, tx_success_count);
+    denoms.extend(state::additional_transfer_denoms(storage).unwrap_or_default());
 
-    Ok(Response::default())
+    denoms
 }
 
-pub fn sudo_error(
-    deps: DepsMut<NeutronQuery>,
-    request: RequestPacket,
-    error: String,
+pub fn execute_reopen_ica(
+    deps: DepsMut<impl CustomQuery>,
+    env: Env,
+    info: MessageInfo,
 ) -> Result<Response<NeutronMsg>, Error> {
-    let tx_seq_num = request.sequence.expect("sequence number always set");
-
-    let source_channel = request.source_channel.expect("source channel always set");
-
-    let tx_hash = hash!(tx_seq_num.to_be_bytes(), source_channel);
+    debug!(deps, "executing reopen ica");
 
-    debug!(deps, "received sudo response for tx: {tx_hash}");
-
-    let ica_idx = state::tx_hash_ica_idx(deps.storage, &tx_hash)
-        .expect("a sequence number is always associated with an ica idx");
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
 
-    let mut tx_error_count = state::ica_tx_error_count(deps.storage, &ica_idx).unwrap_or_default();
+    let owner = info.sender.into_string();
 
-    let error_key = combine_u32s(ica_idx, tx_error_count);
+    let ica_idx = state::owner_ica_idx(deps.storage, &owner).ok_or(Error::NoIcaSetup)?;
 
-    tx_error_count += 1;
+    ensure_ica_status(deps.storage, ica_idx, &[IcaStatus::ChannelClosed])?;
 
-    debug!(
-        deps,
-        "ICA {ica_idx} issued tx with sequence number {tx_seq_num} failed: {error}, total error count: {tx_error_count}"
+    set_ica_status(
+        deps.storage,
+        ica_idx,
+        IcaStatus::Reopening,
+        env.block.height,
     );
 
-    state::set_ica_tx_error_count(deps.storage, &ica_idx, tx_error_count);
+    let connection_id = state::connection_id(deps.storage);
 
-    state::set_ica_err_idx_msg(deps.storage, &error_key, &error);
+    // the same interchain account id binds the new channel to the same ICA
+    let registration_msg = NeutronMsg::RegisterInterchainAccount {
+        connection_id,
+        interchain_account_id: ica_idx.to_string(),
+    };
 
-    Ok(Response::default())
+    let event = ica_event("ica_reopen_requested", ica_idx).add_attribute("owner", owner);
+
+    Ok(Response::default()
+        .add_message(registration_msg)
+        .add_event(event))
 }
 
-pub fn sudo_timeout(
-    deps: DepsMut<NeutronQuery>,
-    request: RequestPacket,
+pub fn execute_cancel_ica_registration(
+    deps: DepsMut<impl CustomQuery>,
+    env: Env,
+    info: MessageInfo,
 ) -> Result<Response<NeutronMsg>, Error> {
-    let tx_seq_num = request.sequence.expect("sequence number always set");
+    debug!(deps, "executing cancel ica registration");
 
-    let source_channel = request.source_channel.expect("source channel always set");
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
 
-    let tx_hash = hash!(tx_seq_num.to_be_bytes(), source_channel);
+    let owner = info.sender.into_string();
 
-    debug!(deps, "received sudo response for tx: {tx_hash}");
+    let ica_idx = state::owner_ica_idx(deps.storage, &owner).ok_or(Error::NoIcaSetup)?;
 
-    let ica_idx = state::tx_hash_ica_idx(deps.storage, &tx_hash)
-        .expect("a sequence number is always associated with an ica idx");
+    ensure_ica_status(deps.storage, ica_idx, &[IcaStatus::Registering])?;
 
-    let mut tx_timeout_count =
-        state::ica_tx_timeout_count(deps.storage, &ica_idx).unwrap_or_default();
+    let registering_since = state::ica_status_height(deps.storage, &ica_idx).unwrap_or_default();
 
-    tx_timeout_count += 1;
+    let cancellable_at = registering_since + REGISTRATION_CANCEL_BLOCKS;
+
+    if env.block.height < cancellable_at {
+        return Err(Error::RegistrationPending {
+            remaining_blocks: cancellable_at - env.block.height,
+        });
+    }
+
+    // registrations pending since before the deposit was recorded have nothing to refund
+    let deposit = state::ica_setup_deposit(deps.storage, &ica_idx);
 
     debug!(
         deps,
-        "ICA {ica_idx} issued tx with sequence number {tx_seq_num} timed out, total timeout count: {tx_timeout_count}"
+        "cancelling registration of ICA {ica_idx} of {owner}, pending since {registering_since}"
     );
 
-    state::set_ica_tx_timeout_count(deps.storage, &ica_idx, tx_timeout_count);
+    clear_ica_state(deps.storage, ica_idx, &owner);
 
-    Ok(Response::default())
-}
+    // a late open ack for the retired slot is rejected
+    set_ica_status(deps.storage, ica_idx, IcaStatus::Retired, env.block.height);
 
-pub fn sudo_kv_query_result(
-    deps: DepsMut<NeutronQuery>,
-    query_id: u64,
-) -> Result<Response<NeutronMsg>, Error> {
-    let ica_idx =
-        state::icq_ica_idx(deps.storage, &query_id).expect("the icq is associated with an ica");
+    let refund = deposit.unwrap_or_default();
 
-    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("the ica has an address");
+    let event = ica_event("ica_registration_cancelled", ica_idx)
+        .add_attribute("owner", owner.as_str())
+        .add_attribute("refund", coins_attr(&refund));
 
-    debug!(
-        deps,
-        "received balance ICQ {query_id} update for ICA {ica_idx} with address: {ica_addr}"
-    );
+    let mut response = Response::default().add_event(event);
 
-    Ok(Response::default())
+    if !refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: owner,
+            amount: refund,
+        });
+    }
+
+    Ok(response)
 }
 
-#[entry_point]
-pub fn sudo(
-    deps: DepsMut<NeutronQuery>,
-    _env: Env,
-    msg: SudoMsg,
+pub fn execute_transfer_funds(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    memo: Option<String>,
+    owner: Option<String>,
 ) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "handling sudo msg");
-
-    match msg {
-        SudoMsg::OpenAck {
-            port_id,
-            counterparty_version,
-            ..
-        } => sudo_open_ack(deps, port_id, counterparty_version),
+    debug!(deps, "executing transfer funds");
 
-        SudoMsg::Response { request, .. } => sudo_response(deps, request),
+    if let Some(memo) = &memo {
+        validate_transfer_memo(memo)?;
+    }
 
-        SudoMsg::Error { request, details } => sudo_error(deps, request, details),
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
 
-        SudoMsg::Timeout { request } => sudo_timeout(deps, request),
+    let tx_denoms = transfer_denoms(deps.storage);
 
-        SudoMsg::KVQueryResult { query_id } => sudo_kv_query_result(deps, query_id),
+    let (tx_coins, fee_deposit): (Vec<Coin>, Vec<Coin>) = info
+        .funds
+        .into_iter()
+        .partition(|c| tx_denoms.contains(&c.denom));
 
-        SudoMsg::TxQueryResult { .. } => unimplemented!("not expecting tx query results"),
+    if tx_coins.is_empty() {
+        return Err(Error::NoFundsToTransfer);
     }
-}
-
-pub fn reply_register_icq(deps: DepsMut, reply: Reply, ica_idx: u32) -> Result<Response, Error> {
-    debug!(
-        deps,
-        "received icq registation reply for ICA index {ica_idx}",
-    );
-
-    let icq_id = common::parse_icq_registration_reply(reply)?;
 
-    debug!(deps, "ICA {ica_idx} balance ICQ ID: {icq_id}",);
-
-    state::set_ica_icq_id(deps.storage, &ica_idx, icq_id);
+    for tx_coin in &tx_coins {
+        ensure_transfer_amount_in_limits(deps.storage, tx_coin)?;
+    }
 
-    state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
+    let sender = info.sender.as_str();
 
-    Ok(Response::default())
-}
+    let owner = owner.as_deref().unwrap_or(sender);
 
-pub fn reply_issue_tx(
-    deps: DepsMut,
-    reply: Reply,
-    tx_kind: u32,
-    ica_idx: u32,
-) -> Result<Response, Error> {
-    debug!(deps, "received issue tx reply for ICA index {ica_idx}");
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
 
-    let (tx_seq_num, channel) = common::parse_issue_tx_reply(reply)?;
+    spend_operator_allowance(deps.storage, owner, sender, &tx_coins)?;
 
-    let tx_hash = hash!(tx_seq_num.to_be_bytes(), channel);
+    fee_escrow::credit(deps.storage, sender, &fee_deposit);
 
-    state::set_tx_hash_ica_idx(deps.storage, &tx_hash, ica_idx);
+    let mut response = Response::default();
 
-    state::set_ica_tx_kind_seq_num(deps.storage, &combine_u32s(ica_idx, tx_kind), tx_seq_num);
+    // one transfer per coin, each resulting sequence number is tracked against the ICA
+    for tx_coin in tx_coins {
+        fee_escrow::debit(deps.storage, sender, &min_ibc_fee)?;
 
-    let mut tx_issue_count = state::ica_tx_issued_count(deps.storage, &ica_idx).unwrap_or_default();
+        debug!(deps, "transfering {tx_coin} on behalf of {owner}");
 
-    tx_issue_count += 1;
+        let event = ica_event("transfer_requested", ica_idx)
+            .add_attribute("owner", owner)
+            .add_attribute("sender", sender)
+            .add_attribute("amount", tx_coin.to_string());
 
-    debug!(
-        deps,
-        "ICA {ica_idx} issued tx {tx_issue_count} with sequence number {tx_seq_num}"
-    );
+        let transfer_msg = issue_transfer_tx(
+            deps.branch(),
+            &env,
+            ica_idx,
+            tx_coin,
+            memo.clone(),
+            min_ibc_fee.clone(),
+            sender,
+        )?;
 
-    state::set_ica_tx_issued_count(deps.storage, &ica_idx, tx_issue_count);
+        response = response.add_submessage(transfer_msg).add_event(event);
+    }
 
-    Ok(Response::default())
+    Ok(response)
 }
 
-#[entry_point]
-pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, Error> {
-    let (reply_kind, ica_idx) = split_u64(reply.id);
+/// Spend `amounts` from the allowance granted by `owner` to `operator`, removing the grant once its allowance is
+/// spent. Owners acting for themselves spend no allowance
+pub fn spend_operator_allowance(
+    storage: &mut dyn Storage,
+    owner: &str,
+    operator: &str,
+    amounts: &[Coin],
+) -> Result<(), Error> {
+    if owner == operator {
+        return Ok(());
+    }
 
-    debug!(
-        deps,
-        "received reply of kind {reply_kind} for ICA {ica_idx}"
-    );
+    let mut operators = state::owner_operators(storage, owner).unwrap_or_default();
 
-    match reply_kind {
-        REGISTER_ICQ_REPLY_KIND => reply_register_icq(deps, reply, ica_idx),
+    let pos = operators
+        .binary_search_by(|g| g.operator.as_str().cmp(operator))
+        .map_err(|_| Error::NoOperatorGrant {
+            owner: owner.to_owned(),
+            operator: operator.to_owned(),
+        })?;
 
-        TRANSFER_TX_REPLY_KIND | RETRIEVE_TX_REPLY_KIND => {
-            reply_issue_tx(deps, reply, reply_kind, ica_idx)
-        }
+    let allowance =
+        fee_escrow::subtract_coins(&operators[pos].allowance, amounts).ok_or_else(|| {
+            Error::OperatorAllowanceExceeded {
+                operator: operator.to_owned(),
+                amount: coins_attr(amounts),
+            }
+        })?;
 
-        _ => unreachable!("unexpected reply kind: {reply_kind}"),
+    if allowance.is_empty() {
+        operators.remove(pos);
+    } else {
+        operators[pos].allowance = allowance;
     }
-}
 
-pub fn owner_is_valid_addr(deps: Deps<impl CustomQuery>, owner: &str) -> Result<bool, Error> {
-    deps.api.addr_validate(owner)?;
+    if operators.is_empty() {
+        state::remove_owner_operators(storage, owner);
+    } else {
+        state::set_owner_operators(storage, owner, &operators);
+    }
 
-    Ok(true)
+    Ok(())
 }
 
-pub fn query_ica_metadata(
-    deps: Deps<NeutronQuery>,
-    owner: String,
-) -> Result<IcaMetadataResponse, Error> {
-    owner_is_valid_addr(deps, &owner)?;
+/// Queue a tx issued in the current execution, its parameters are consumed by the reply in submessage order
+pub fn push_pending_tx(storage: &mut dyn Storage, ica_idx: u32, issued_tx: IssuedTx) {
+    let mut pending_txs = state::ica_pending_txs(storage, &ica_idx).unwrap_or_default();
 
-    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
-        return Ok(IcaMetadataResponse::default());
-    };
+    pending_txs.push(issued_tx);
 
-    let address = state::ica_addr(deps.storage, &ica_idx);
+    state::set_ica_pending_txs(storage, &ica_idx, &pending_txs);
+}
 
-    let balance_icq_id = state::ica_icq_id(deps.storage, &ica_idx);
+/// Track the tx of `pending_tx` as in flight, until its acknowledgement or timeout is received
+pub fn track_pending_tx(storage: &mut dyn Storage, ica_idx: u32, pending_tx: PendingTx) {
+    let mut tx_hashes = state::ica_pending_tx_hashes(storage, &ica_idx).unwrap_or_default();
 
-    Ok(IcaMetadataResponse {
-        metadata: Some(IcaMetadata {
-            ica_idx,
-            address,
-            balance_icq_id,
-        }),
-    })
+    tx_hashes.push(pending_tx.tx_hash.clone());
+
+    state::set_ica_pending_tx_hashes(storage, &ica_idx, &tx_hashes);
+
+    state::set_tx_hash_pending_tx(storage, &pending_tx.tx_hash, &pending_tx);
 }
 
-pub fn query_last_ica_balance(
-    deps: Deps<NeutronQuery>,
-    owner: String,
-) -> Result<IcaLastBalanceResponse, Error> {
-    owner_is_valid_addr(deps, &owner)?;
+/// Stop tracking the tx of `tx_hash` as in flight, once its outcome is known
+pub fn settle_pending_tx(storage: &mut dyn Storage, ica_idx: u32, tx_hash: &str) {
+    state::remove_tx_hash_pending_tx(storage, tx_hash);
 
-    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
-        return Ok(IcaLastBalanceResponse::default());
+    let Some(mut tx_hashes) = state::ica_pending_tx_hashes(storage, &ica_idx) else {
+        return;
     };
 
-    let Some(icq_id) = state::ica_icq_id(deps.storage, &ica_idx) else {
-        return Ok(IcaLastBalanceResponse::default());
-    };
+    tx_hashes.retain(|h| h != tx_hash);
 
-    debug!(deps, "querying balance ICQ {icq_id} for ICA {ica_idx}");
+    if tx_hashes.is_empty() {
+        state::remove_ica_pending_tx_hashes(storage, &ica_idx);
+    } else {
+        state::set_ica_pending_tx_hashes(storage, &ica_idx, &tx_hashes);
+    }
+}
 
-    let Some(RemoteBalance {
-        last_submitted_result_local_height,
-        balance,
-    }) = query_balance_icq(deps, icq_id)?
-    else {
-        return Ok(IcaLastBalanceResponse::default());
-    };
+/// Take the parameters of the earliest issued tx awaiting its reply
+pub fn pop_pending_tx(storage: &mut dyn Storage, ica_idx: u32) -> Option<IssuedTx> {
+    let mut pending_txs = state::ica_pending_txs(storage, &ica_idx)?;
 
-    let address =
-        state::ica_addr(deps.storage, &ica_idx).expect("a registered ica has an address set");
+    let issued_tx = (!pending_txs.is_empty()).then(|| pending_txs.remove(0));
 
-    let last_balance = IcaLastBalance {
-        balance,
-        address,
-        last_submitted_result_local_height,
-    };
+    if pending_txs.is_empty() {
+        state::remove_ica_pending_txs(storage, &ica_idx);
+    } else {
+        state::set_ica_pending_txs(storage, &ica_idx, &pending_txs);
+    }
 
-    Ok(IcaLastBalanceResponse {
-        last_balance: Some(last_balance),
-    })
+    issued_tx
 }
 
-pub fn query_ica_tx_status(
-    deps: Deps<impl CustomQuery>,
-    owner: String,
-) -> Result<IcaTxStatusResponse, Error> {
-    owner_is_valid_addr(deps, &owner)?;
+/// The connection & timeout of the ICA txs issued from now on
+#[must_use]
+pub fn ica_tx_config(storage: &dyn Storage) -> IcaTxConfig {
+    IcaTxConfig {
+        connection_id: state::connection_id(storage),
+        timeout_seconds: state::timeout_seconds(storage).unwrap_or(DEFAULT_TIMEOUT_SECONDS),
+    }
+}
 
-    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
-        return Ok(IcaTxStatusResponse::default());
+/// Build the IBC transfer of `tx_coin` to the ICA, keeping the tx parameters in case it needs to be retried
+pub fn issue_transfer_tx(
+    deps: DepsMut<impl CustomQuery>,
+    env: &Env,
+    ica_idx: u32,
+    tx_coin: Coin,
+    memo: Option<String>,
+    fee: IbcFee,
+    payer: &str,
+) -> Result<SubMsg<NeutronMsg>, Error> {
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("active icas have an address");
+
+    let transfer_config = TransferConfig {
+        source_channel: state::ibc_transfer_channel(deps.storage),
+        sender: env.contract.address.to_string(),
     };
 
-    let issued = state::ica_tx_issued_count(deps.storage, &ica_idx).unwrap_or_default();
+    let timeout_height = state::timeout_height(deps.storage).unwrap_or(DEFAULT_TIMEOUT_HEIGHT);
+
+    let timeout_revision_number =
+        state::timeout_revision_number(deps.storage).unwrap_or(DEFAULT_TIMEOUT_REVISION_NUMBER);
+
+    push_pending_tx(
+        deps.storage,
+        ica_idx,
+        IssuedTx {
+            kind: TxKind::Transfer,
+            coin: tx_coin.clone(),
+            fee: Some(fee.clone()),
+            payer: Some(payer.to_owned()),
+            validator: None,
+            memo: memo.clone(),
+            route: None,
+            custom: None,
+            rx_hash: None,
+        },
+    );
 
-    let success = state::ica_tx_success_count(deps.storage, &ica_idx).unwrap_or_default();
+    let ibc_transfer_msg = msg_builders::ibc_transfer(
+        &transfer_config,
+        ica_addr,
+        tx_coin.clone(),
+        TimeoutPolicy::Height {
+            revision_number: timeout_revision_number,
+            revision_height: timeout_height,
+        },
+        memo,
+        fee,
+    );
 
-    let error = state::ica_tx_error_count(deps.storage, &ica_idx).unwrap_or_default();
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::IssueTx(TxKind::Transfer),
+        ica_idx,
+        Some(tx_coin),
+    );
 
-    let timeout = state::ica_tx_timeout_count(deps.storage, &ica_idx).unwrap_or_default();
+    // a failed submission is refunded by the reply, so it must not revert the whole execution
+    Ok(SubMsg::reply_always(ibc_transfer_msg, reply_id))
+}
 
-    let roundtrips = state::ica_round_trip_count(deps.storage, &ica_idx).unwrap_or_default();
+/// Build a `MsgWithdrawDelegatorReward` claiming the rewards of `delegator` from `validator`
+pub fn make_withdraw_delegator_reward_msg(delegator: String, validator: String) -> ProtobufAny {
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgWithdrawDelegatorReward {
+        #[prost(string, tag = "1")]
+        pub delegator_address: String,
+        #[prost(string, tag = "2")]
+        pub validator_address: String,
+    }
 
-    let last_transfer_seq_num =
-        state::ica_tx_kind_seq_num(deps.storage, &combine_u32s(ica_idx, TRANSFER_TX_REPLY_KIND));
+    let withdraw_msg = MsgWithdrawDelegatorReward {
+        delegator_address: delegator,
+        validator_address: validator,
+    };
 
-    let last_retrieve_seq_num =
-        state::ica_tx_kind_seq_num(deps.storage, &combine_u32s(ica_idx, RETRIEVE_TX_REPLY_KIND));
+    ProtobufAny {
+        type_url: "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward".to_owned(),
+        value: withdraw_msg.encode_to_vec().into(),
+    }
+}
 
-    let status = IcaTxStatus {
-        issued,
-        success,
-        error,
-        timeout,
-        roundtrips,
-        last_transfer_seq_num,
-        last_retrieve_seq_num,
-    };
+/// The encoded response of the first message of an ICA tx from its acknowledgement data,
+/// `None` if the ack can't be decoded or holds no response
+fn first_msg_response(ack_data: &[u8]) -> Option<Vec<u8>> {
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgData {
+        #[prost(string, tag = "1")]
+        pub msg_type: String,
+        #[prost(bytes = "vec", tag = "2")]
+        pub data: Vec<u8>,
+    }
 
-    Ok(IcaTxStatusResponse {
-        status: Some(status),
-    })
+    #[derive(Clone, PartialEq, Message)]
+    struct Any {
+        #[prost(string, tag = "1")]
+        pub type_url: String,
+        #[prost(bytes = "vec", tag = "2")]
+        pub value: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct TxMsgData {
+        // pre cosmos-sdk v0.46 hosts
+        #[prost(message, repeated, tag = "1")]
+        pub data: Vec<MsgData>,
+        #[prost(message, repeated, tag = "2")]
+        pub msg_responses: Vec<Any>,
+    }
+
+    let tx_msg_data = TxMsgData::decode(ack_data).ok()?;
+
+    tx_msg_data
+        .msg_responses
+        .into_iter()
+        .map(|any| any.value)
+        .chain(tx_msg_data.data.into_iter().map(|msg_data| msg_data.data))
+        .next()
 }
 
-pub fn query_ica_tx_error(
-    deps: Deps<impl CustomQuery>,
-    owner: String,
-    error_idx: u32,
-) -> Result<IcaTxErrorResponse, Error> {
-    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
-        return Ok(IcaTxErrorResponse::default());
+/// Parse the rewards claimed by a `MsgWithdrawDelegatorReward` from the ICA tx acknowledgement data,
+/// `None` if the ack can't be decoded or the host chain doesn't report the claimed amount
+#[must_use]
+pub fn parse_claimed_rewards(ack_data: &[u8]) -> Option<Vec<Coin>> {
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgWithdrawDelegatorRewardResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub amount: Vec<RawCoin>,
+    }
+
+    let response = first_msg_response(ack_data)?;
+
+    let MsgWithdrawDelegatorRewardResponse { amount } =
+        MsgWithdrawDelegatorRewardResponse::decode(response.as_slice()).ok()?;
+
+    amount.into_iter().map(RawCoin::to_coin).collect()
+}
+
+/// Parse the time the funds undelegated by a `MsgUndelegate` finish unbonding from the ICA tx acknowledgement data,
+/// `None` if the ack can't be decoded
+#[must_use]
+pub fn parse_unbonding_completion(ack_data: &[u8]) -> Option<Timestamp> {
+    #[derive(Clone, PartialEq, Message)]
+    struct ProtoTimestamp {
+        #[prost(int64, tag = "1")]
+        pub seconds: i64,
+        #[prost(int32, tag = "2")]
+        pub nanos: i32,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgUndelegateResponse {
+        #[prost(message, optional, tag = "1")]
+        pub completion_time: Option<ProtoTimestamp>,
+    }
+
+    let response = first_msg_response(ack_data)?;
+
+    let completion_time = MsgUndelegateResponse::decode(response.as_slice())
+        .ok()?
+        .completion_time?;
+
+    let seconds = u64::try_from(completion_time.seconds).ok()?;
+
+    let nanos = u64::try_from(completion_time.nanos).ok()?;
+
+    Some(Timestamp::from_seconds(seconds).plus_nanos(nanos))
+}
+
+/// Parse the bank sends to `recipient` out of a raw tx submitted as a TX ICQ result,
+/// `None` if the tx can't be decoded
+#[must_use]
+pub fn parse_bank_sends_to(tx_data: &[u8], recipient: &str) -> Option<Vec<(String, Vec<Coin>)>> {
+    #[derive(Clone, PartialEq, Message)]
+    struct TxRaw {
+        #[prost(bytes = "vec", tag = "1")]
+        pub body_bytes: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Any {
+        #[prost(string, tag = "1")]
+        pub type_url: String,
+        #[prost(bytes = "vec", tag = "2")]
+        pub value: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct TxBody {
+        #[prost(message, repeated, tag = "1")]
+        pub messages: Vec<Any>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgSend {
+        #[prost(string, tag = "1")]
+        pub from_address: String,
+        #[prost(string, tag = "2")]
+        pub to_address: String,
+        #[prost(message, repeated, tag = "3")]
+        pub amount: Vec<RawCoin>,
+    }
+
+    let tx = TxRaw::decode(tx_data).ok()?;
+
+    let body = TxBody::decode(tx.body_bytes.as_slice()).ok()?;
+
+    body.messages
+        .into_iter()
+        .filter(|any| any.type_url == "/cosmos.bank.v1beta1.MsgSend")
+        .map(|any| MsgSend::decode(any.value.as_slice()).ok())
+        // the relayer submitting TX ICQ results is untrusted, only sends to the recipient are kept
+        .filter(|msg_send| {
+            msg_send
+                .as_ref()
+                .map_or(true, |msg| msg.to_address == recipient)
+        })
+        .map(|msg_send| {
+            let MsgSend {
+                from_address,
+                amount,
+                ..
+            } = msg_send?;
+
+            let coins = amount
+                .into_iter()
+                .map(RawCoin::to_coin)
+                .collect::<Option<Vec<_>>>()?;
+
+            Some((from_address, coins))
+        })
+        .collect()
+}
+
+pub fn execute_retrieve_funds(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+    route: Option<RetrievalRoute>,
+    owner: Option<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing retrieve funds");
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let sender = info.sender.as_str();
+
+    let owner = owner.as_deref().unwrap_or(sender);
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    fee_escrow::credit(deps.storage, sender, &info.funds);
+
+    fee_escrow::debit(deps.storage, sender, &min_ibc_fee)?;
+
+    let ica_balance_icq = state::ica_icq_id(deps.storage, &ica_idx).ok_or(Error::NoIcaSetup)?;
+
+    if state::icq_invalid(deps.storage, &ica_balance_icq).is_some() {
+        return Err(Error::IcqInvalid {
+            icq_id: ica_balance_icq,
+        });
+    }
+
+    if state::icq_quarantined_result(deps.storage, &ica_balance_icq).is_some() {
+        return Err(Error::IcqResultQuarantined {
+            icq_id: ica_balance_icq,
+        });
+    }
+
+    let non_zero_remote_balance = query_balance_icq(deps.as_ref(), ica_balance_icq)?
+        .and_then(|res| res.balance)
+        .filter(|remote_balance| !remote_balance.amount.is_zero())
+        .ok_or(Error::NoFundsToRetrieve)?;
+
+    let retrieve_coin = match amount {
+        None => non_zero_remote_balance,
+
+        Some(amount) if amount.is_zero() => return Err(Error::NoFundsToRetrieve),
+
+        Some(amount) if amount > non_zero_remote_balance.amount => {
+            return Err(Error::RetrieveAmountExceedsBalance {
+                requested: amount,
+                available: non_zero_remote_balance.amount,
+            })
+        }
+
+        Some(amount) => Coin {
+            denom: non_zero_remote_balance.denom,
+            amount,
+        },
     };
 
-    let error_key = combine_u32s(ica_idx, error_idx);
+    spend_operator_allowance(
+        deps.storage,
+        owner,
+        sender,
+        std::slice::from_ref(&retrieve_coin),
+    )?;
 
-    let error = state::ica_err_idx_msg(deps.storage, &error_key);
+    let event = ica_event("retrieve_requested", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("sender", sender)
+        .add_attribute("amount", retrieve_coin.to_string());
 
-    Ok(IcaTxErrorResponse { error })
+    let retrieve_msg = issue_retrieve_tx(
+        deps,
+        &env,
+        ica_idx,
+        retrieve_coin,
+        min_ibc_fee,
+        sender,
+        route,
+    )?;
+
+    Ok(Response::default()
+        .add_submessage(retrieve_msg)
+        .add_event(event))
 }
 
-#[entry_point]
-pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
-    let res = match msg {
-        QueryMsg::IcaMetadata { owner } => {
-            let ica_metadata = query_ica_metadata(deps, owner)?;
+/// The ICA of `owner` & its entire last known balance if it can be retrieved on the owner's behalf,
+/// otherwise the reason it is skipped
+pub fn retrievable_balance(
+    deps: Deps<NeutronQuery>,
+    owner: &str,
+) -> Result<(u32, Coin), &'static str> {
+    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or("no_ica")?;
 
-            to_binary(&ica_metadata)?
+    if ica_status(deps.storage, ica_idx) != IcaStatus::Active {
+        return Err("ica_not_active");
+    }
+
+    // the balance may already be on its way back
+    if ica_txs_in_flight(deps.storage, ica_idx) > 0 {
+        return Err("txs_in_flight");
+    }
+
+    let icq_id = state::ica_icq_id(deps.storage, &ica_idx)
+        .filter(|icq_id| {
+            state::icq_invalid(deps.storage, icq_id).is_none()
+                && state::icq_quarantined_result(deps.storage, icq_id).is_none()
+        })
+        .ok_or("balance_icq_unavailable")?;
+
+    query_balance_icq(deps, icq_id)
+        .ok()
+        .flatten()
+        .and_then(|res| res.balance)
+        .filter(|remote_balance| !remote_balance.amount.is_zero())
+        .map(|remote_balance| (ica_idx, remote_balance))
+        .ok_or("no_funds")
+}
+
+pub fn execute_retrieve_funds_for(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    mut owners: Vec<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing retrieve funds for {} owners", owners.len());
+
+    if owners.len() > MAX_RETRIEVE_FOR_OWNERS {
+        return Err(Error::TooManyOwners {
+            max: MAX_RETRIEVE_FOR_OWNERS,
+        });
+    }
+
+    // a second retrieval of the same balance would fail on the remote chain
+    owners.sort_unstable();
+    owners.dedup();
+
+    let sender = info.sender.as_str();
+
+    fee_escrow::credit(deps.storage, sender, &info.funds);
+
+    let mut response = Response::default();
+
+    let mut retrievals = vec![];
+
+    for owner in owners {
+        match retrievable_balance(deps.as_ref(), &owner) {
+            Ok((ica_idx, retrieve_coin)) => retrievals.push((owner, ica_idx, retrieve_coin)),
+
+            Err(reason) => {
+                let event = Event::new("retrieve_skipped")
+                    .add_attribute("owner", owner)
+                    .add_attribute("reason", reason);
+
+                response = response.add_event(event);
+            }
         }
+    }
 
-        QueryMsg::IcaLastBalance { owner } => {
-            let last_ica_balance = query_last_ica_balance(deps, owner)?;
+    if retrievals.is_empty() {
+        return Ok(response);
+    }
 
-            to_binary(&last_ica_balance)?
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    for (owner, ica_idx, retrieve_coin) in retrievals {
+        fee_escrow::debit(deps.storage, sender, &min_ibc_fee)?;
+
+        let event = ica_event("retrieve_requested", ica_idx)
+            .add_attribute("owner", owner)
+            .add_attribute("sender", sender)
+            .add_attribute("amount", retrieve_coin.to_string());
+
+        let retrieve_msg = issue_retrieve_tx(
+            deps.branch(),
+            &env,
+            ica_idx,
+            retrieve_coin,
+            min_ibc_fee.clone(),
+            sender,
+            None,
+        )?;
+
+        response = response.add_submessage(retrieve_msg).add_event(event);
+    }
+
+    Ok(response)
+}
+
+/// Build the ICA tx transferring `retrieve_coin` back to the contract, directly or through the intermediate chains
+/// of `route`, keeping the tx parameters in case it needs to be retried
+pub fn issue_retrieve_tx(
+    deps: DepsMut<impl CustomQuery>,
+    env: &Env,
+    ica_idx: u32,
+    retrieve_coin: Coin,
+    fee: IbcFee,
+    payer: &str,
+    route: Option<RetrievalRoute>,
+) -> Result<SubMsg<NeutronMsg>, Error> {
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("active icas have an address");
+
+    let ica_tx_config = ica_tx_config(deps.storage);
+
+    let source_channel = match &route {
+        Some(route) => route.source_channel.clone(),
+        None => state::ibc_transfer_channel(deps.storage),
+    };
+
+    let timeout_timestamp = env
+        .block
+        .time
+        .plus_seconds(ica_tx_config.timeout_seconds)
+        .nanos();
+
+    let tx_idx = state::ica_tx_issued_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    let rx_hash = hash!(
+        RX_HASH_DOMAIN;
+        ica_addr,
+        retrieve_coin.amount.u128().to_be_bytes(),
+        tx_idx.to_be_bytes()
+    );
+
+    // save the ICA idx against the rx hash
+    state::set_rx_hash_ica_idx(deps.storage, &rx_hash, ica_idx);
+
+    let undelivered = state::ica_undelivered_retrievals(deps.storage, &ica_idx).unwrap_or_default();
+
+    state::set_ica_undelivered_retrievals(deps.storage, &ica_idx, undelivered + 1);
+
+    state::set_rx_hash_scheme(deps.storage, &rx_hash, &HASH_SCHEME.version);
+
+    state::set_rx_hash_expected_amount(deps.storage, &rx_hash, &retrieve_coin.amount);
+
+    let host_ibc_denom = match &route {
+        Some(route) => route.host_denom.clone(),
+        None => state::host_ibc_denom(deps.storage),
+    };
+
+    state::set_rx_hash_expected_denom(deps.storage, &rx_hash, &host_ibc_denom);
+
+    stamp_hash(
+        deps.storage,
+        state::HashKind::Rx,
+        &rx_hash,
+        env.block.height,
+    );
+
+    let mut hops = vec![];
+
+    // routed retrievals arrive from the address packet-forward-middleware forwards them from on the last hop,
+    // each hop overriding the receiver with the address derived from the funds' sender on the previous one
+    if let Some(route) = &route {
+        let mut sender = ica_addr.clone();
+
+        for hop in &route.hops {
+            sender = pfm_receiver(&hop.receive_channel, &sender, &hop.bech32_prefix);
+
+            hops.push(ForwardHop {
+                receiver: sender.clone(),
+                channel: hop.channel.clone(),
+            });
         }
 
-        QueryMsg::IcaTxStatus { owner } => {
-            let ica_tx_status = query_ica_tx_status(deps, owner)?;
+        let hook_sender = ibc_hooks_sender(&route.host_channel, &sender, HOOK_SENDER_BECH32_PREFIX);
 
-            to_binary(&ica_tx_status)?
+        state::set_rx_hash_hook_sender(deps.storage, &rx_hash, &hook_sender);
+
+        state::set_rx_hash_expected_path(deps.storage, &rx_hash, &retrieval_route_path(route));
+    }
+
+    let ibc_transfer_msg = make_ibc_transfer_via_hops_with_hook_msg(
+        source_channel,
+        retrieve_coin.clone(),
+        ica_addr,
+        timeout_timestamp,
+        &hops,
+        env.contract.address.clone(),
+        // attach the rx hash to the callback message
+        ExecuteMsg::FundsRetrievedHook {
+            rx_hash: rx_hash.clone(),
+        },
+        &memo_limits(deps.storage),
+    )?;
+
+    push_pending_tx(
+        deps.storage,
+        ica_idx,
+        IssuedTx {
+            kind: TxKind::Retrieve,
+            coin: retrieve_coin.clone(),
+            fee: Some(fee.clone()),
+            payer: Some(payer.to_owned()),
+            validator: None,
+            memo: None,
+            route,
+            custom: None,
+            rx_hash: Some(rx_hash),
+        },
+    );
+
+    let ica_submit_tx_msg = msg_builders::submit_tx(
+        &ica_tx_config,
+        ica_idx.to_string(),
+        vec![ibc_transfer_msg],
+        fee,
+    );
+
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::IssueTx(TxKind::Retrieve),
+        ica_idx,
+        Some(retrieve_coin),
+    );
+
+    Ok(SubMsg::reply_on_success(ica_submit_tx_msg, reply_id))
+}
+
+#[must_use]
+pub fn tx_kind_reply_kind(tx_kind: TxKind) -> u32 {
+    match tx_kind {
+        TxKind::Transfer => TRANSFER_TX_REPLY_KIND,
+        TxKind::Retrieve => RETRIEVE_TX_REPLY_KIND,
+        TxKind::Delegate => DELEGATE_TX_REPLY_KIND,
+        TxKind::Undelegate => UNDELEGATE_TX_REPLY_KIND,
+        TxKind::ClaimRewards => CLAIM_REWARDS_TX_REPLY_KIND,
+        TxKind::Custom => CUSTOM_TX_REPLY_KIND,
+    }
+}
+
+pub fn execute_retry_last_tx(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    tx_kind: TxKind,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing retry last tx: {tx_kind:?}");
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    let failed_tx_key = combine_u32s(ica_idx, tx_kind_reply_kind(tx_kind));
+
+    let IssuedTx {
+        coin,
+        validator,
+        memo,
+        route,
+        custom,
+        ..
+    } = state::ica_tx_kind_failed_tx(deps.storage, &failed_tx_key).ok_or(Error::NoFailedTx)?;
+
+    state::remove_ica_tx_kind_failed_tx(deps.storage, &failed_tx_key);
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    fee_escrow::debit(deps.storage, owner, &min_ibc_fee)?;
+
+    debug!(deps, "retrying {tx_kind:?} of {coin} for ICA {ica_idx}");
+
+    let event = ica_event("tx_retry_requested", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("tx_kind", format!("{tx_kind:?}"))
+        .add_attribute("amount", coin.to_string());
+
+    let retry_msg = match tx_kind {
+        TxKind::Transfer => issue_transfer_tx(deps, &env, ica_idx, coin, memo, min_ibc_fee, owner)?,
+        TxKind::Retrieve => {
+            issue_retrieve_tx(deps, &env, ica_idx, coin, min_ibc_fee, owner, route)?
         }
+        TxKind::Delegate | TxKind::Undelegate | TxKind::ClaimRewards => {
+            let validator = validator.ok_or_else(|| Error::IncompleteFailedTx {
+                tx_kind: format!("{tx_kind:?}"),
+            })?;
 
-        QueryMsg::IcaTxError { owner, error_idx } => {
-            let ica_tx_status = query_ica_tx_error(deps, owner, error_idx)?;
+            issue_staking_tx(deps, ica_idx, tx_kind, validator, coin, min_ibc_fee, owner)?
+        }
+        TxKind::Custom => {
+            let custom = custom.ok_or_else(|| Error::IncompleteFailedTx {
+                tx_kind: format!("{tx_kind:?}"),
+            })?;
 
-            to_binary(&ica_tx_status)?
+            issue_custom_tx(deps, ica_idx, custom, min_ibc_fee, owner)?
         }
     };
 
-    Ok(res)
+    Ok(Response::default()
+        .add_submessage(retry_msg)
+        .add_event(event))
+}
+
+/// The number of txs issued by the ICA that have yet to be acknowledged or timed out
+#[must_use]
+pub fn ica_txs_in_flight(storage: &dyn Storage, ica_idx: u32) -> u32 {
+    let issued = state::ica_tx_issued_count(storage, &ica_idx).unwrap_or_default();
+
+    let settled = state::ica_tx_success_count(storage, &ica_idx).unwrap_or_default()
+        + state::ica_tx_error_count(storage, &ica_idx).unwrap_or_default()
+        + state::ica_tx_timeout_count(storage, &ica_idx).unwrap_or_default();
+
+    issued.saturating_sub(settled)
+}
+
+/// The number of retrievals issued by the ICA that have yet to be acknowledged or timed out
+#[must_use]
+pub fn ica_retrievals_in_flight(storage: &dyn Storage, ica_idx: u32) -> usize {
+    state::ica_pending_tx_hashes(storage, &ica_idx)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tx_hash| state::tx_hash_pending_tx(storage, tx_hash))
+        .filter(|pending_tx| pending_tx.kind == TxKind::Retrieve)
+        .count()
+}
+
+/// Remove all state indexed by `ica_idx`, except for any tx/rx hashes which can't be enumerated
+pub fn clear_ica_state(storage: &mut dyn Storage, ica_idx: u32, owner: &str) {
+    let tx_error_count = state::ica_tx_error_count(storage, &ica_idx).unwrap_or_default();
+
+    for error_idx in 0..tx_error_count {
+        state::remove_ica_err_idx_msg(storage, &combine_u32s(ica_idx, error_idx));
+    }
+
+    let tx_history_len = state::ica_tx_history_len(storage, &ica_idx).unwrap_or_default();
+
+    for history_idx in 0..tx_history_len {
+        state::remove_ica_history_idx_tx_entry(storage, &combine_u32s(ica_idx, history_idx));
+    }
+
+    state::remove_ica_tx_history_len(storage, &ica_idx);
+
+    for tx_hash in state::ica_pending_tx_hashes(storage, &ica_idx).unwrap_or_default() {
+        state::remove_tx_hash_pending_tx(storage, &tx_hash);
+    }
+
+    state::remove_ica_pending_tx_hashes(storage, &ica_idx);
+
+    state::remove_ica_channel(storage, &ica_idx);
+
+    let remote_deposit_count =
+        state::ica_remote_deposit_count(storage, &ica_idx).unwrap_or_default();
+
+    for deposit_idx in 0..remote_deposit_count {
+        state::remove_ica_deposit_idx_remote_deposit(storage, &combine_u32s(ica_idx, deposit_idx));
+    }
+
+    state::remove_ica_remote_deposit_count(storage, &ica_idx);
+
+    for tx_kind in TX_REPLY_KINDS {
+        let ica_tx_kind = combine_u32s(ica_idx, tx_kind);
+
+        state::remove_ica_tx_kind_seq_num(storage, &ica_tx_kind);
+
+        state::remove_ica_tx_kind_failed_tx(storage, &ica_tx_kind);
+    }
+
+    let icq_ids = [
+        state::ica_icq_id(storage, &ica_idx),
+        state::ica_delegations_icq_id(storage, &ica_idx),
+        state::ica_transfers_icq_id(storage, &ica_idx),
+    ];
+
+    for icq_id in icq_ids.into_iter().flatten() {
+        state::remove_icq_ica_idx(storage, &icq_id);
+        state::remove_icq_quarantined_result(storage, &icq_id);
+        state::remove_icq_invalid(storage, &icq_id);
+    }
+
+    state::remove_owner_ica_idx(storage, owner);
+    state::remove_ica_owner(storage, &ica_idx);
+    state::remove_ica_addr(storage, &ica_idx);
+    state::remove_ica_icq_id(storage, &ica_idx);
+    state::remove_ica_delegations_icq_id(storage, &ica_idx);
+    state::remove_ica_delegations_validator(storage, &ica_idx);
+    state::remove_ica_transfers_icq_id(storage, &ica_idx);
+    state::remove_ica_tx_issued_count(storage, &ica_idx);
+    state::remove_ica_tx_success_count(storage, &ica_idx);
+    state::remove_ica_tx_error_count(storage, &ica_idx);
+    state::remove_ica_tx_timeout_count(storage, &ica_idx);
+    state::remove_ica_round_trip_count(storage, &ica_idx);
+    state::remove_ica_round_trip_volume(storage, &ica_idx);
+    state::remove_ica_settled_height(storage, &ica_idx);
+    state::remove_ica_undelivered_retrievals(storage, &ica_idx);
+    state::remove_ica_pending_txs(storage, &ica_idx);
+    state::remove_ica_listed_owner(storage, &ica_idx);
+    state::remove_ica_auto_retrieve(storage, &ica_idx);
+    state::remove_ica_claimed_rewards(storage, &ica_idx);
+    state::remove_ica_unbonding_until(storage, &ica_idx);
+    state::remove_ica_setup_deposit(storage, &ica_idx);
+    state::remove_ica_label(storage, &ica_idx);
+    state::remove_ica_note(storage, &ica_idx);
+    state::remove_ica_address_book(storage, &ica_idx);
+
+    let mut leaderboard = state::leaderboard(storage).unwrap_or_default();
+
+    leaderboard.retain(|e| e.ica_idx != ica_idx);
+
+    state::set_leaderboard(storage, &leaderboard);
+}
+
+/// Ensure the ICA at `ica_idx` holds none of the remote denom & delegates none of it according to ICQ results
+/// submitted after its last tx settled, & that none of its retrievals or undelegations is still on its way, so that
+/// tearing the ICA down orphans none of its funds
+pub fn ensure_ica_drained(deps: Deps<NeutronQuery>, ica_idx: u32) -> Result<(), Error> {
+    let undelivered = state::ica_undelivered_retrievals(deps.storage, &ica_idx).unwrap_or_default();
+
+    if undelivered > 0 {
+        return Err(Error::RetrievalsUndelivered { undelivered });
+    }
+
+    if let Some(until) = state::ica_unbonding_until(deps.storage, &ica_idx) {
+        return Err(Error::IcaUnbonding { until });
+    }
+
+    let settled_height = state::ica_settled_height(deps.storage, &ica_idx).unwrap_or_default();
+
+    if let Some(icq_id) = state::ica_delegations_icq_id(deps.storage, &ica_idx) {
+        let fresh = state::icq_invalid(deps.storage, &icq_id).is_none()
+            && updated_registered_kv_query(deps, icq_id)?.is_some_and(|registered_query| {
+                registered_query.last_submitted_result_local_height > settled_height
+            });
+
+        if !fresh {
+            return Err(Error::IcaDelegationsUnknown { settled_height });
+        }
+
+        let delegations: Delegations = deps.kv_result(icq_id)?;
+
+        if let Some(delegation) = delegations
+            .delegations
+            .into_iter()
+            .find(|delegation| !delegation.amount.amount.is_zero())
+        {
+            return Err(Error::IcaDelegated {
+                validator: delegation.validator,
+                amount: delegation.amount,
+            });
+        }
+    }
+
+    let last_balance = ica_last_balance(deps, ica_idx)?
+        .last_balance
+        .filter(|last_balance| last_balance.last_submitted_result_local_height > settled_height)
+        .ok_or(Error::IcaBalanceUnknown { settled_height })?;
+
+    if let Some(balance) = last_balance.balance.filter(|b| !b.amount.is_zero()) {
+        return Err(Error::IcaNotDrained { balance });
+    }
+
+    Ok(())
+}
+
+pub fn execute_teardown_ica(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing teardown ica");
+
+    let owner = info.sender.into_string();
+
+    let ica_idx = state::owner_ica_idx(deps.storage, &owner).ok_or(Error::NoIcaSetup)?;
+
+    // an ICA with a closed channel can still be torn down to reclaim its ICQ deposits
+    ensure_ica_status(
+        deps.storage,
+        ica_idx,
+        &[IcaStatus::Active, IcaStatus::ChannelClosed],
+    )?;
+
+    let icq_id = state::ica_icq_id(deps.storage, &ica_idx).ok_or(Error::NoIcaSetup)?;
+
+    let in_flight = ica_txs_in_flight(deps.storage, ica_idx);
+
+    // rx/tx hashes of in-flight txs would point at the cleared ICA
+    if in_flight > 0 {
+        return Err(Error::TxsInFlight { in_flight });
+    }
+
+    ensure_ica_drained(deps.as_ref(), ica_idx)?;
+
+    let mut deposit = deps.as_ref().registered_query(icq_id)?.deposit;
+
+    let extra_icq_ids = [
+        state::ica_delegations_icq_id(deps.storage, &ica_idx),
+        state::ica_transfers_icq_id(deps.storage, &ica_idx),
+    ];
+
+    let mut response = Response::default();
+
+    // the delegations & transfers ICQ deposits are refunded along with the balance ICQ deposit
+    for extra_icq_id in extra_icq_ids.into_iter().flatten() {
+        let extra_deposit = deps.as_ref().registered_query(extra_icq_id)?.deposit;
+
+        fee_escrow::add_coins(&mut deposit, &extra_deposit);
+
+        response = response.add_message(NeutronMsg::RemoveInterchainQuery {
+            query_id: extra_icq_id,
+        });
+    }
+
+    debug!(
+        deps,
+        "tearing down ICA {ica_idx} of {owner}, removing ICQ {icq_id}"
+    );
+
+    clear_ica_state(deps.storage, ica_idx, &owner);
+
+    set_ica_status(deps.storage, ica_idx, IcaStatus::Retired, env.block.height);
+
+    let event = ica_event("ica_torn_down", ica_idx)
+        .add_attribute("owner", owner.as_str())
+        .add_attribute("icq_id", icq_id.to_string());
+
+    state::set_ica_icq_refund(deps.storage, &ica_idx, &state::IcqRefund { owner, deposit });
+
+    let remove_icq_msg = NeutronMsg::RemoveInterchainQuery { query_id: icq_id };
+
+    let reply_id = save_pending_reply(deps.storage, state::ReplyKind::RemoveIcq, ica_idx, None);
+
+    Ok(response
+        .add_submessage(SubMsg::reply_on_success(remove_icq_msg, reply_id))
+        .add_event(event))
+}
+
+/// Build the ICA tx (un)delegating `coin` to `validator` or claiming the rewards from `validator`,
+/// keeping the tx parameters in case it needs to be retried
+pub fn issue_staking_tx(
+    deps: DepsMut<impl CustomQuery>,
+    ica_idx: u32,
+    kind: TxKind,
+    validator: String,
+    coin: Coin,
+    fee: IbcFee,
+    payer: &str,
+) -> Result<SubMsg<NeutronMsg>, Error> {
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("active icas have an address");
+
+    let ica_tx_config = ica_tx_config(deps.storage);
+
+    // claimed rewards are only known once the tx is acknowledged
+    let reply_coin = (kind != TxKind::ClaimRewards).then(|| coin.clone());
+
+    push_pending_tx(
+        deps.storage,
+        ica_idx,
+        IssuedTx {
+            kind,
+            coin: coin.clone(),
+            fee: Some(fee.clone()),
+            payer: Some(payer.to_owned()),
+            validator: Some(validator.clone()),
+            memo: None,
+            route: None,
+            custom: None,
+            rx_hash: None,
+        },
+    );
+
+    let staking_msg = match kind {
+        TxKind::Delegate => msg_builders::staking_msg(
+            msg_builders::MSG_DELEGATE_TYPE_URL,
+            ica_addr,
+            validator,
+            coin,
+        ),
+        TxKind::Undelegate => msg_builders::staking_msg(
+            msg_builders::MSG_UNDELEGATE_TYPE_URL,
+            ica_addr,
+            validator,
+            coin,
+        ),
+        TxKind::ClaimRewards => make_withdraw_delegator_reward_msg(ica_addr, validator),
+        TxKind::Transfer | TxKind::Retrieve | TxKind::Custom => {
+            unreachable!("not a staking tx: {kind:?}")
+        }
+    };
+
+    let ica_submit_tx_msg =
+        msg_builders::submit_tx(&ica_tx_config, ica_idx.to_string(), vec![staking_msg], fee);
+
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::IssueTx(kind),
+        ica_idx,
+        reply_coin,
+    );
+
+    Ok(SubMsg::reply_on_success(ica_submit_tx_msg, reply_id))
+}
+
+/// Split `msgs` into batches of at most `max_msgs`, the most messages the interchaintxs module executes per ICA tx
+pub fn submit_tx_batches<T>(msgs: Vec<T>, max_msgs: u64) -> Result<Vec<Vec<T>>, Error> {
+    if max_msgs == 0 {
+        return Err(Error::ZeroSubmitTxMsgLimit);
+    }
+
+    let max_msgs = usize::try_from(max_msgs).unwrap_or(usize::MAX);
+
+    let mut batches = vec![];
+
+    let mut msgs = msgs.into_iter().peekable();
+
+    while msgs.peek().is_some() {
+        batches.push(msgs.by_ref().take(max_msgs).collect());
+    }
+
+    Ok(batches)
+}
+
+pub fn execute_submit_ica_tx(
+    mut deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    msgs: Vec<ProtobufAnyJson>,
+    timeout: Option<u64>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing submit ica tx of {} msgs", msgs.len());
+
+    let count = msgs.len();
+
+    if count == 0 {
+        return Err(Error::EmptyCustomTx);
+    }
+
+    if timeout == Some(0) {
+        return Err(Error::ZeroCustomTxTimeout);
+    }
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    // the msgs could move the funds a retrieval is delivering, or reach the retrieval hook as the ICA
+    let in_flight = ica_retrievals_in_flight(deps.storage, ica_idx);
+
+    if in_flight > 0 {
+        return Err(Error::RetrievalsInFlight { in_flight });
+    }
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let max_msgs = deps.as_ref().ica_tx_params()?.msg_submit_tx_max_messages;
+
+    let type_urls: Vec<&str> = msgs.iter().map(|msg| msg.type_url.as_str()).collect();
+
+    let event = ica_event("custom_tx_requested", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("type_urls", type_urls.join(","));
+
+    let batches = submit_tx_batches(msgs, max_msgs.u64())?;
+
+    if batches.len() > MAX_CUSTOM_TXS {
+        let max = usize::try_from(max_msgs.u64())
+            .unwrap_or(usize::MAX)
+            .saturating_mul(MAX_CUSTOM_TXS);
+
+        return Err(Error::InvalidCustomTxMsgs { count, max });
+    }
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    // every batch is submitted as its own ICA tx, paying its own IBC fee
+    for _ in &batches {
+        fee_escrow::debit(deps.storage, owner, &min_ibc_fee)?;
+    }
+
+    let timeout = timeout
+        .unwrap_or_else(|| state::timeout_seconds(deps.storage).unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+
+    let event = event.add_attribute("txs", batches.len().to_string());
+
+    let mut response = Response::default().add_event(event);
+
+    for msgs in batches {
+        let custom_tx_msg = issue_custom_tx(
+            deps.branch(),
+            ica_idx,
+            CustomTx { msgs, timeout },
+            min_ibc_fee.clone(),
+            owner,
+        )?;
+
+        response = response.add_submessage(custom_tx_msg);
+    }
+
+    Ok(response)
+}
+
+/// Build the ICA tx executing the messages of `custom`, keeping the tx parameters in case it needs to be retried
+pub fn issue_custom_tx(
+    deps: DepsMut<impl CustomQuery>,
+    ica_idx: u32,
+    custom: CustomTx,
+    fee: IbcFee,
+    payer: &str,
+) -> Result<SubMsg<NeutronMsg>, Error> {
+    // custom txs carry their own timeout
+    let ica_tx_config = IcaTxConfig {
+        timeout_seconds: custom.timeout,
+        ..ica_tx_config(deps.storage)
+    };
+
+    let msgs = custom
+        .msgs
+        .iter()
+        .map(|msg| ProtobufAny {
+            type_url: msg.type_url.clone(),
+            value: msg.value.clone(),
+        })
+        .collect();
+
+    push_pending_tx(
+        deps.storage,
+        ica_idx,
+        IssuedTx {
+            kind: TxKind::Custom,
+            coin: Coin::default(),
+            fee: Some(fee.clone()),
+            payer: Some(payer.to_owned()),
+            validator: None,
+            memo: None,
+            route: None,
+            custom: Some(custom),
+            rx_hash: None,
+        },
+    );
+
+    let ica_submit_tx_msg = msg_builders::submit_tx(&ica_tx_config, ica_idx.to_string(), msgs, fee);
+
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::IssueTx(TxKind::Custom),
+        ica_idx,
+        None,
+    );
+
+    Ok(SubMsg::reply_on_success(ica_submit_tx_msg, reply_id))
+}
+
+pub fn execute_delegate_from_ica(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing delegate from ica: {amount} to {validator}");
+
+    if amount.is_zero() {
+        return Err(Error::ZeroStakingAmount);
+    }
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("active icas have an address");
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    let mut response = Response::default();
+
+    match state::ica_delegations_validator(deps.storage, &ica_idx) {
+        Some(expected) if expected != validator => {
+            return Err(Error::DelegationValidatorMismatch { expected });
+        }
+
+        Some(_) => {}
+
+        None => {
+            let icq_deposit = required_icq_deposit(deps.as_ref())?;
+
+            fee_escrow::debit_coins(deps.storage, owner, &icq_deposit)?;
+
+            state::set_ica_delegations_validator(deps.storage, &ica_idx, &validator);
+
+            let delegations_icq_register_msg = new_register_delegator_delegations_query_msg(
+                state::connection_id(deps.storage),
+                ica_addr,
+                vec![validator.clone()],
+                state::icq_update_period(deps.storage),
+            )?;
+
+            let reply_id = save_pending_reply(
+                deps.storage,
+                state::ReplyKind::RegisterDelegationsIcq,
+                ica_idx,
+                None,
+            );
+
+            response = response.add_submessage(SubMsg::reply_on_success(
+                delegations_icq_register_msg,
+                reply_id,
+            ));
+        }
+    }
+
+    fee_escrow::debit(deps.storage, owner, &min_ibc_fee)?;
+
+    let coin = Coin {
+        denom: state::remote_denom(deps.storage),
+        amount,
+    };
+
+    let event = ica_event("delegate_requested", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("validator", validator.as_str())
+        .add_attribute("amount", coin.to_string());
+
+    let delegate_msg = issue_staking_tx(
+        deps,
+        ica_idx,
+        TxKind::Delegate,
+        validator,
+        coin,
+        min_ibc_fee,
+        owner,
+    )?;
+
+    Ok(response.add_submessage(delegate_msg).add_event(event))
+}
+
+pub fn execute_set_ica_label(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    label: Option<String>,
+    note: Option<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set ica label");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or(Error::NoIcaSetup)?;
+
+    if let Some(label) = &label {
+        validate_label(label)?;
+
+        state::set_ica_label(deps.storage, &ica_idx, label);
+    } else {
+        state::remove_ica_label(deps.storage, &ica_idx);
+    }
+
+    if let Some(note) = &note {
+        validate_note(note)?;
+
+        state::set_ica_note(deps.storage, &ica_idx, note);
+    } else {
+        state::remove_ica_note(deps.storage, &ica_idx);
+    }
+
+    let event = ica_event("ica_labelled", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("label", label.unwrap_or_default());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_set_address_book_entry(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    address: String,
+    label: String,
+    note: Option<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set address book entry");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    validate_remote_address(&address)?;
+
+    validate_label(&label)?;
+
+    if let Some(note) = &note {
+        validate_note(note)?;
+    }
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or(Error::NoIcaSetup)?;
+
+    let mut address_book = state::ica_address_book(deps.storage, &ica_idx).unwrap_or_default();
+
+    let event = ica_event("address_book_entry_set", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("address", address.as_str())
+        .add_attribute("label", label.as_str());
+
+    let entry = AddressBookEntry {
+        address,
+        label,
+        note,
+    };
+
+    match address_book.binary_search_by(|e| e.address.cmp(&entry.address)) {
+        Ok(pos) => address_book[pos] = entry,
+        Err(_) if address_book.len() >= MAX_ADDRESS_BOOK_ENTRIES => {
+            return Err(Error::AddressBookFull)
+        }
+        Err(pos) => address_book.insert(pos, entry),
+    }
+
+    state::set_ica_address_book(deps.storage, &ica_idx, &address_book);
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_grant_operator(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    operator: String,
+    allowance: Vec<Coin>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing grant operator");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    let operator = deps.api.addr_validate(&operator)?.into_string();
+
+    let owner = info.sender.as_str();
+
+    if operator == owner {
+        return Err(Error::SelfOperatorGrant);
+    }
+
+    // merge duplicate denoms & drop zero amounts, leaving the allowance empty if nothing is granted
+    let mut merged = vec![];
+
+    fee_escrow::add_coins(&mut merged, &allowance);
+
+    merged.retain(|c| !c.amount.is_zero());
+
+    let mut operators = state::owner_operators(deps.storage, owner).unwrap_or_default();
+
+    let event = Event::new("operator_granted")
+        .add_attribute("owner", owner)
+        .add_attribute("operator", operator.as_str())
+        .add_attribute("allowance", coins_attr(&merged));
+
+    let grant = OperatorGrant {
+        operator,
+        allowance: merged,
+    };
+
+    match operators.binary_search_by(|g| g.operator.cmp(&grant.operator)) {
+        Ok(pos) => operators[pos] = grant,
+        Err(_) if operators.len() >= MAX_OPERATORS => return Err(Error::TooManyOperators),
+        Err(pos) => operators.insert(pos, grant),
+    }
+
+    operators.retain(|g| !g.allowance.is_empty());
+
+    if operators.is_empty() {
+        state::remove_owner_operators(deps.storage, owner);
+    } else {
+        state::set_owner_operators(deps.storage, owner, &operators);
+    }
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_revoke_operator(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing revoke operator");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    let owner = info.sender.as_str();
+
+    let mut operators = state::owner_operators(deps.storage, owner).unwrap_or_default();
+
+    let pos = operators
+        .binary_search_by(|g| g.operator.cmp(&operator))
+        .map_err(|_| Error::NoOperatorGrant {
+            owner: owner.to_owned(),
+            operator: operator.clone(),
+        })?;
+
+    operators.remove(pos);
+
+    if operators.is_empty() {
+        state::remove_owner_operators(deps.storage, owner);
+    } else {
+        state::set_owner_operators(deps.storage, owner, &operators);
+    }
+
+    let event = Event::new("operator_revoked")
+        .add_attribute("owner", owner)
+        .add_attribute("operator", operator);
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_remove_address_book_entry(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing remove address book entry");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or(Error::NoIcaSetup)?;
+
+    let mut address_book = state::ica_address_book(deps.storage, &ica_idx).unwrap_or_default();
+
+    let pos = address_book
+        .binary_search_by(|e| e.address.cmp(&address))
+        .map_err(|_| Error::NoAddressBookEntry {
+            address: address.clone(),
+        })?;
+
+    address_book.remove(pos);
+
+    if address_book.is_empty() {
+        state::remove_ica_address_book(deps.storage, &ica_idx);
+    } else {
+        state::set_ica_address_book(deps.storage, &ica_idx, &address_book);
+    }
+
+    let event = ica_event("address_book_entry_removed", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("address", address);
+
+    Ok(Response::default().add_event(event))
+}
+
+/// The remote height of the last result of the balance ICQ of the ICA at `ica_idx`, a lower bound of the current
+/// remote height, `None` until the ICQ has a result
+fn last_remote_height(deps: Deps<NeutronQuery>, ica_idx: u32) -> Option<u64> {
+    let icq_id = state::ica_icq_id(deps.storage, &ica_idx)?;
+
+    let res = deps.registered_query_result(icq_id).ok()?;
+
+    (res.result.height > 0).then_some(res.result.height)
+}
+
+pub fn execute_register_transfers_query(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing register transfers query");
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    if state::ica_transfers_icq_id(deps.storage, &ica_idx).is_some() {
+        return Err(Error::TransfersIcqAlreadyRegistered);
+    }
+
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("active icas have an address");
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    let icq_deposit = required_icq_deposit(deps.as_ref())?;
+
+    fee_escrow::debit_coins(deps.storage, owner, &icq_deposit)?;
+
+    // deposits made before now are of no interest, the filter is matched against the remote chain's tx heights
+    let transfers_icq_register_msg = new_register_transfers_query_msg(
+        state::connection_id(deps.storage),
+        ica_addr,
+        state::icq_update_period(deps.storage),
+        last_remote_height(deps.as_ref(), ica_idx),
+    )?;
+
+    let event = ica_event("transfers_icq_requested", ica_idx).add_attribute("owner", owner);
+
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::RegisterTransfersIcq,
+        ica_idx,
+        None,
+    );
+
+    Ok(Response::default()
+        .add_submessage(SubMsg::reply_on_success(
+            transfers_icq_register_msg,
+            reply_id,
+        ))
+        .add_event(event))
+}
+
+pub fn execute_undelegate_from_ica(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(
+        deps,
+        "executing undelegate from ica: {amount} from {validator}"
+    );
+
+    if amount.is_zero() {
+        return Err(Error::ZeroStakingAmount);
+    }
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    let expected =
+        state::ica_delegations_validator(deps.storage, &ica_idx).ok_or(Error::NoDelegations)?;
+
+    if expected != validator {
+        return Err(Error::DelegationValidatorMismatch { expected });
+    }
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    fee_escrow::debit(deps.storage, owner, &min_ibc_fee)?;
+
+    let coin = Coin {
+        denom: state::remote_denom(deps.storage),
+        amount,
+    };
+
+    let event = ica_event("undelegate_requested", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("validator", validator.as_str())
+        .add_attribute("amount", coin.to_string());
+
+    let undelegate_msg = issue_staking_tx(
+        deps,
+        ica_idx,
+        TxKind::Undelegate,
+        validator,
+        coin,
+        min_ibc_fee,
+        owner,
+    )?;
+
+    Ok(Response::default()
+        .add_submessage(undelegate_msg)
+        .add_event(event))
+}
+
+pub fn execute_claim_rewards(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing claim rewards");
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    let validator =
+        state::ica_delegations_validator(deps.storage, &ica_idx).ok_or(Error::NoDelegations)?;
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    fee_escrow::debit(deps.storage, owner, &min_ibc_fee)?;
+
+    // the claimed amount is only known once the claim is acknowledged
+    let coin = Coin {
+        denom: state::remote_denom(deps.storage),
+        amount: Uint128::zero(),
+    };
+
+    let event = ica_event("claim_rewards_requested", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("validator", validator.as_str());
+
+    let claim_msg = issue_staking_tx(
+        deps,
+        ica_idx,
+        TxKind::ClaimRewards,
+        validator,
+        coin,
+        min_ibc_fee,
+        owner,
+    )?;
+
+    Ok(Response::default()
+        .add_submessage(claim_msg)
+        .add_event(event))
+}
+
+/// Record the rewards claimed by the ICA & retrieve them in a submessage executing the contract itself, so that a
+/// failing retrieval is reverted & reported without failing the handling of the claim's acknowledgement
+pub fn record_claimed_rewards(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    ica_idx: u32,
+    ack_data: &[u8],
+) -> Result<Response<NeutronMsg>, Error> {
+    let remote_denom = state::remote_denom(deps.storage);
+
+    let claimed = parse_claimed_rewards(ack_data)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|c| c.denom == remote_denom && !c.amount.is_zero());
+
+    let Some(claimed) = claimed else {
+        debug!(deps, "no claimed rewards reported for ICA {ica_idx}");
+
+        return Ok(Response::default());
+    };
+
+    let claimed_rewards = state::ica_claimed_rewards(deps.storage, &ica_idx).unwrap_or_default();
+
+    state::set_ica_claimed_rewards(deps.storage, &ica_idx, &(claimed_rewards + claimed.amount));
+
+    let owner = state::ica_owner(deps.storage, &ica_idx).expect("ica has an owner");
+
+    let event = ica_event("rewards_claimed", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("amount", claimed.to_string());
+
+    let retrieve_msg = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::RetrieveClaimedRewards {
+            ica_idx,
+            amount: claimed.amount,
+        })?,
+        funds: vec![],
+    };
+
+    // replies on success too, so that the pending reply is always taken
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::RetrieveClaimedRewards,
+        ica_idx,
+        Some(claimed),
+    );
+
+    Ok(Response::default()
+        .add_submessage(SubMsg::reply_always(retrieve_msg, reply_id))
+        .add_event(event))
+}
+
+/// Record when the funds undelegated by the ICA finish unbonding, `MAX_UNBONDING_SECONDS` from now if the ack doesn't
+/// report it, the ICA cannot be torn down until then
+pub fn record_unbonding(storage: &mut dyn Storage, env: &Env, ica_idx: u32, ack_data: &[u8]) {
+    let completion = parse_unbonding_completion(ack_data)
+        .unwrap_or_else(|| env.block.time.plus_seconds(MAX_UNBONDING_SECONDS));
+
+    let until = state::ica_unbonding_until(storage, &ica_idx)
+        .map_or(completion, |until| until.max(completion));
+
+    state::set_ica_unbonding_until(storage, &ica_idx, &until);
+}
+
+/// Retrieve the rewards claimed by the ICA, paying the IBC fee from the owner's fee balance,
+/// only callable by the contract itself
+pub fn execute_retrieve_claimed_rewards(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    info: &MessageInfo,
+    ica_idx: u32,
+    amount: Uint128,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing retrieve claimed rewards of ICA {ica_idx}");
+
+    if info.sender != env.contract.address {
+        return Err(Error::Unauthorized);
+    }
+
+    let claimed = Coin::new(amount.u128(), state::remote_denom(deps.storage));
+
+    let owner = state::ica_owner(deps.storage, &ica_idx).expect("ica has an owner");
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    if let Err(Error::InsufficientIbcTxFee { shortfall }) =
+        fee_escrow::debit(deps.storage, &owner, &min_ibc_fee)
+    {
+        debug!(
+            deps,
+            "fee balance of {owner} cannot cover claimed rewards retrieve fees"
+        );
+
+        let skipped_event = ica_event("rewards_retrieve_skipped", ica_idx)
+            .add_attribute("owner", owner)
+            .add_attribute("shortfall", coins_attr(&shortfall));
+
+        return Ok(Response::default().add_event(skipped_event));
+    }
+
+    debug!(deps, "retrieving claimed {claimed} from ICA {ica_idx}");
+
+    let retrieve_msg = issue_retrieve_tx(deps, env, ica_idx, claimed, min_ibc_fee, &owner, None)?;
+
+    Ok(Response::default().add_submessage(retrieve_msg))
+}
+
+pub fn execute_enable_auto_retrieve(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing enable auto retrieve");
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    fee_escrow::credit(deps.storage, owner, &info.funds);
+
+    state::set_ica_auto_retrieve(deps.storage, &ica_idx, &true);
+
+    let event = ica_event("auto_retrieve_enabled", ica_idx).add_attribute("owner", owner);
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_disable_auto_retrieve(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing disable auto retrieve");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = state::owner_ica_idx(deps.storage, owner).ok_or(Error::NoIcaSetup)?;
+
+    if state::ica_auto_retrieve(deps.storage, &ica_idx).is_none() {
+        return Err(Error::AutoRetrieveNotEnabled);
+    }
+
+    state::remove_ica_auto_retrieve(deps.storage, &ica_idx);
+
+    let event = ica_event("auto_retrieve_disabled", ica_idx).add_attribute("owner", owner);
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_update_icq_period(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    new_period: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update icq period");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    validate_icq_update_period(new_period)?;
+
+    let owner = info.sender.as_str();
+
+    let ica_idx = active_ica_idx(deps.storage, owner)?;
+
+    let icq_id = state::ica_icq_id(deps.storage, &ica_idx).ok_or(Error::NoBalanceIcq)?;
+
+    if state::icq_invalid(deps.storage, &icq_id).is_some() {
+        return Err(Error::IcqInvalid { icq_id });
+    }
+
+    let update_msg = NeutronMsg::UpdateInterchainQuery {
+        query_id: icq_id,
+        new_keys: None,
+        new_update_period: Some(new_period),
+        new_transactions_filter: None,
+    };
+
+    let event = ica_event("icq_update_period_changed", ica_idx)
+        .add_attribute("owner", owner)
+        .add_attribute("icq_id", icq_id.to_string())
+        .add_attribute("update_period", new_period.to_string());
+
+    Ok(Response::default().add_message(update_msg).add_event(event))
+}
+
+pub fn ensure_contract_owner(storage: &dyn Storage, sender: &Addr) -> Result<(), Error> {
+    if state::contract_owner(storage).as_deref() != Some(sender.as_str()) {
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(())
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set paused: {paused}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    if paused {
+        state::set_paused(deps.storage, &true);
+    } else {
+        state::remove_paused(deps.storage);
+    }
+
+    let event = Event::new(if paused { "paused" } else { "unpaused" })
+        .add_attribute("owner", info.sender.as_str());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_set_state_commitments(
+    deps: DepsMut<impl CustomQuery>,
+    env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set state commitments: {enabled}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    if enabled {
+        state::set_state_commitments_enabled(deps.storage, &true);
+
+        // commit to the current global stats right away rather than on their next change
+        commit_stats(deps.storage, env.block.height);
+    } else {
+        state::remove_state_commitments_enabled(deps.storage);
+    }
+
+    let event = Event::new("state_commitments_set")
+        .add_attribute("owner", info.sender.as_str())
+        .add_attribute("enabled", enabled.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+#[must_use]
+pub fn is_paused(storage: &dyn Storage) -> bool {
+    state::paused(storage).unwrap_or_default()
+}
+
+pub fn execute_rebuild_indexes(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    limit: u32,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing rebuild indexes: limit {limit}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    let start = state::rebuild_cursor(deps.storage).unwrap_or_default();
+
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
+
+    let pass = rebuild_indexes(deps.storage, start, ica_count, limit, rebuild_ica_indexes);
+
+    match pass.next_ica_idx {
+        Some(next_ica_idx) => state::set_rebuild_cursor(deps.storage, next_ica_idx),
+        None => state::remove_rebuild_cursor(deps.storage),
+    }
+
+    Ok(Response::default().add_event(pass.event()))
+}
+
+pub fn execute_update_hash_retention(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    retention_blocks: Option<u64>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update hash retention");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    save_hash_retention(deps.storage, retention_blocks)?;
+
+    let event = Event::new("hash_retention_updated").add_attribute(
+        "retention_blocks",
+        hash_retention_blocks(deps.storage).to_string(),
+    );
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_update_icq_deposit_override(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    deposit: Option<Vec<Coin>>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update icq deposit override");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    match &deposit {
+        Some(deposit) => {
+            validate_icq_deposit(deposit)?;
+
+            state::set_icq_deposit_override(deps.storage, deposit);
+        }
+        None => state::remove_icq_deposit_override(deps.storage),
+    }
+
+    let event = Event::new("icq_deposit_override_updated")
+        .add_attribute("deposit", coins_attr(&deposit.unwrap_or_default()));
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_sweep(
+    deps: DepsMut<impl CustomQuery>,
+    env: Env,
+    limit: u32,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing sweep: limit {limit}");
+
+    let pass = sweep_hashes(deps.storage, env.block.height, limit.min(MAX_SWEEP_LIMIT));
+
+    let event = Event::new("hashes_swept")
+        .add_attribute("tx_hashes", pass.tx_hashes.to_string())
+        .add_attribute("rx_hashes", pass.rx_hashes.to_string())
+        .add_attribute("restamped", pass.restamped.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Restore the owner, ICQ & pending tx reverse lookups of the ICA at `ica_idx`, returning the number restored
+fn rebuild_ica_indexes(storage: &mut dyn Storage, ica_idx: u32) -> u32 {
+    let mut restored = 0;
+
+    if let Some(owner) = state::ica_owner(storage, &ica_idx) {
+        if state::owner_ica_idx(storage, &owner).is_none() {
+            state::set_owner_ica_idx(storage, &owner, ica_idx);
+            restored += 1;
+        }
+
+        if state::ica_listed_owner(storage, &ica_idx).is_none() {
+            state::set_ica_listed_owner(storage, &ica_idx, &owner);
+            restored += 1;
+        }
+    }
+
+    let icq_ids = [
+        state::ica_icq_id(storage, &ica_idx),
+        state::ica_delegations_icq_id(storage, &ica_idx),
+        state::ica_transfers_icq_id(storage, &ica_idx),
+    ];
+
+    for icq_id in icq_ids.into_iter().flatten() {
+        if state::icq_ica_idx(storage, &icq_id).is_none() {
+            state::set_icq_ica_idx(storage, &icq_id, ica_idx);
+            restored += 1;
+        }
+    }
+
+    for tx_hash in state::ica_pending_tx_hashes(storage, &ica_idx).unwrap_or_default() {
+        if state::tx_hash_ica_idx(storage, &tx_hash).is_none() {
+            state::set_tx_hash_ica_idx(storage, &tx_hash, ica_idx);
+            restored += 1;
+        }
+    }
+
+    restored
+}
+
+pub fn execute_transfer_ownership(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing transfer ownership to {new_owner}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    deps.api.addr_validate(&new_owner)?;
+
+    // proposing a new owner replaces any previous proposal
+    state::set_pending_contract_owner(deps.storage, &new_owner);
+
+    let event = Event::new("ownership_transfer_proposed")
+        .add_attribute("owner", info.sender.as_str())
+        .add_attribute("new_owner", new_owner);
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing accept ownership");
+
+    if state::pending_contract_owner(deps.storage).as_deref() != Some(info.sender.as_str()) {
+        return Err(Error::Unauthorized);
+    }
+
+    let previous_owner = state::contract_owner(deps.storage).unwrap_or_default();
+
+    state::set_contract_owner(deps.storage, info.sender.as_str());
+
+    state::remove_pending_contract_owner(deps.storage);
+
+    let event = Event::new("ownership_transferred")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", info.sender.as_str());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_update_indexers(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update indexers");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    for indexer in remove {
+        state::remove_indexer_checkpoint(deps.storage, &indexer);
+    }
+
+    for indexer in add {
+        deps.api.addr_validate(&indexer)?;
+
+        // keep the checkpoint of an already authorized indexer
+        if state::indexer_checkpoint(deps.storage, &indexer).is_some() {
+            continue;
+        }
+
+        let checkpoint = IndexerCheckpoint {
+            indexer,
+            activity_id: None,
+            height: None,
+        };
+
+        state::set_indexer_checkpoint(deps.storage, &checkpoint.indexer, &checkpoint);
+    }
+
+    Ok(Response::default())
+}
+
+pub fn execute_subscribe_alerts(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing subscribe alerts");
+
+    let subscriber = info.sender.into_string();
+
+    if deps.querier.query_wasm_contract_info(&subscriber).is_err() {
+        return Err(Error::AlertSubscriberNotContract);
+    }
+
+    let approved = state::approved_alert_subscribers(deps.storage).unwrap_or_default();
+
+    if !approved.contains(&subscriber) {
+        return Err(Error::AlertSubscriberNotApproved { subscriber });
+    }
+
+    let mut subscribers = state::alert_subscribers(deps.storage).unwrap_or_default();
+
+    if subscribers.contains(&subscriber) {
+        return Ok(Response::default());
+    }
+
+    if subscribers.len() >= MAX_ALERT_SUBSCRIBERS {
+        return Err(Error::TooManyAlertSubscribers);
+    }
+
+    subscribers.push(subscriber.clone());
+
+    state::set_alert_subscribers(deps.storage, &subscribers);
+
+    let event = Event::new("alerts_subscribed").add_attribute("subscriber", subscriber);
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Allow `subscriber` to subscribe to alerts, at most `MAX_ALERT_SUBSCRIBERS` contracts can be approved
+pub fn execute_approve_alert_subscriber(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    subscriber: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing approve alert subscriber: {subscriber}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    let subscriber = deps.api.addr_validate(&subscriber)?.into_string();
+
+    let mut approved = state::approved_alert_subscribers(deps.storage).unwrap_or_default();
+
+    if approved.contains(&subscriber) {
+        return Ok(Response::default());
+    }
+
+    if approved.len() >= MAX_ALERT_SUBSCRIBERS {
+        return Err(Error::TooManyAlertSubscribers);
+    }
+
+    approved.push(subscriber.clone());
+
+    state::set_approved_alert_subscribers(deps.storage, &approved);
+
+    let event = Event::new("alert_subscriber_approved").add_attribute("subscriber", subscriber);
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Unsubscribe `subscriber` from alerts & revoke its approval
+pub fn revoke_alert_subscriber(
+    storage: &mut dyn Storage,
+    subscriber: &str,
+) -> Response<NeutronMsg> {
+    let mut approved = state::approved_alert_subscribers(storage).unwrap_or_default();
+
+    approved.retain(|s| s != subscriber);
+
+    state::set_approved_alert_subscribers(storage, &approved);
+
+    remove_alert_subscriber(storage, subscriber)
+        .add_event(Event::new("alert_subscriber_revoked").add_attribute("subscriber", subscriber))
+}
+
+pub fn remove_alert_subscriber(
+    storage: &mut dyn Storage,
+    subscriber: &str,
+) -> Response<NeutronMsg> {
+    let mut subscribers = state::alert_subscribers(storage).unwrap_or_default();
+
+    subscribers.retain(|s| s != subscriber);
+
+    state::set_alert_subscribers(storage, &subscribers);
+
+    let event = Event::new("alerts_unsubscribed").add_attribute("subscriber", subscriber);
+
+    Response::default().add_event(event)
+}
+
+/// Execute `alert` on every alert subscriber, ignoring failures
+pub fn alert_hook_msgs(
+    storage: &mut dyn Storage,
+    alert: ProbeAlert,
+) -> Result<Vec<SubMsg<NeutronMsg>>, Error> {
+    let ica_idx = alert.ica_idx;
+
+    let msg = to_binary(&AlertHookMsg::ProbeAlert(alert))?;
+
+    let msgs = state::alert_subscribers(storage)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|contract_addr| {
+            let hook_msg = WasmMsg::Execute {
+                contract_addr,
+                msg: msg.clone(),
+                funds: vec![],
+            };
+
+            // replies on success too, so that the pending reply is always taken
+            let reply_id = save_pending_reply(storage, state::ReplyKind::AlertHook, ica_idx, None);
+
+            SubMsg::reply_always(hook_msg, reply_id).with_gas_limit(ALERT_HOOK_GAS_LIMIT)
+        })
+        .collect();
+
+    Ok(msgs)
+}
+
+pub fn execute_checkpoint_indexer(
+    deps: DepsMut<impl CustomQuery>,
+    env: Env,
+    info: MessageInfo,
+    activity_id: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    let indexer = info.sender.into_string();
+
+    debug!(
+        deps,
+        "executing checkpoint indexer {indexer}: {activity_id}"
+    );
+
+    let mut checkpoint = state::indexer_checkpoint(deps.storage, &indexer).ok_or_else(|| {
+        Error::UnauthorizedIndexer {
+            indexer: indexer.clone(),
+        }
+    })?;
+
+    if let Some(current) = checkpoint
+        .activity_id
+        .filter(|current| *current > activity_id)
+    {
+        return Err(Error::IndexerCheckpointRegression {
+            current,
+            requested: activity_id,
+        });
+    }
+
+    checkpoint.activity_id = Some(activity_id);
+
+    checkpoint.height = Some(env.block.height);
+
+    state::set_indexer_checkpoint(deps.storage, &indexer, &checkpoint);
+
+    Ok(Response::default())
+}
+
+pub fn execute_clear_icq_quarantine(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    icq_id: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing clear icq quarantine: {icq_id}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    state::remove_icq_quarantined_result(deps.storage, &icq_id);
+
+    let event = Event::new("icq_quarantine_cleared").add_attribute("icq_id", icq_id.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_update_timeouts(
+    mut deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    timeout_seconds: Option<u64>,
+    timeout_height: Option<u64>,
+    timeout_revision_number: Option<u64>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update timeouts");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    save_timeouts(
+        deps.branch(),
+        timeout_seconds,
+        timeout_height,
+        timeout_revision_number,
+    )?;
+
+    let updated = timeouts(deps.storage);
+
+    let event = Event::new("timeouts_updated")
+        .add_attribute("timeout_seconds", updated.timeout_seconds.to_string())
+        .add_attribute("timeout_height", updated.timeout_height.to_string())
+        .add_attribute(
+            "timeout_revision_number",
+            updated.timeout_revision_number.to_string(),
+        );
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_update_transfer_limits(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    limits: DenomTransferLimits,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update transfer limits");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    save_transfer_limits(deps.storage, &limits)?;
+
+    // an unset bound is emitted empty
+    let bound = |amount: Option<Uint128>| {
+        amount
+            .as_ref()
+            .map_or_else(String::new, ToString::to_string)
+    };
+
+    let event = Event::new("transfer_limits_updated")
+        .add_attribute("denom", &limits.denom)
+        .add_attribute("min_transfer_amount", bound(limits.min_transfer_amount))
+        .add_attribute("max_transfer_amount", bound(limits.max_transfer_amount));
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_update_memo_limits(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    max_memo_len: Option<u32>,
+    max_hook_memo_len: Option<u32>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update memo limits");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    save_memo_limits(deps.storage, max_memo_len, max_hook_memo_len)?;
+
+    let limits = memo_limits(deps.storage);
+
+    let event = Event::new("memo_limits_updated")
+        .add_attribute("max_memo_len", limits.max_memo_len.to_string())
+        .add_attribute("max_hook_memo_len", limits.max_hook_memo_len.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Add to the cumulative volumes of `denom` over `channel`
+pub fn add_channel_volume(
+    storage: &mut dyn Storage,
+    denom: &str,
+    channel: &str,
+    outbound: Uint128,
+    inbound: Uint128,
+    height: u64,
+) {
+    let mut volumes = state::channel_volumes(storage).unwrap_or_default();
+
+    if let Some(volume) = volumes
+        .iter_mut()
+        .find(|v| v.denom == denom && v.channel == channel)
+    {
+        volume.outbound += outbound;
+        volume.inbound += inbound;
+    } else {
+        volumes.push(ChannelVolume {
+            denom: denom.to_owned(),
+            channel: channel.to_owned(),
+            outbound,
+            inbound,
+        });
+    }
+
+    state::set_channel_volumes(storage, &volumes);
+
+    commit_stats(storage, height);
+}
+
+/// Apply `update` to the counters summed across all ICAs at the local `height`
+pub fn update_global_totals(
+    storage: &mut dyn Storage,
+    height: u64,
+    update: impl FnOnce(&mut GlobalTotals),
+) {
+    let mut totals = state::global_totals(storage).unwrap_or_default();
+
+    update(&mut totals);
+
+    state::set_global_totals(storage, &totals);
+
+    commit_stats(storage, height);
+}
+
+/// The totals across all ICAs, as served by `GlobalStats` & committed to by state commitments
+#[must_use]
+pub fn global_stats(storage: &dyn Storage) -> GlobalStatsResponse {
+    let volumes = state::channel_volumes(storage).unwrap_or_default();
+
+    GlobalStatsResponse {
+        denom_volumes: denom_volumes(&volumes),
+        volumes,
+        ica_count: state::ica_count(storage).unwrap_or_default(),
+        totals: state::global_totals(storage).unwrap_or_default(),
+    }
+}
+
+/// Sum the channel volumes of each denom, in order of first appearance
+#[must_use]
+pub fn denom_volumes(volumes: &[ChannelVolume]) -> Vec<DenomVolume> {
+    let mut denom_volumes: Vec<DenomVolume> = vec![];
+
+    for volume in volumes {
+        if let Some(denom_volume) = denom_volumes.iter_mut().find(|v| v.denom == volume.denom) {
+            denom_volume.outbound += volume.outbound;
+            denom_volume.inbound += volume.inbound;
+        } else {
+            denom_volumes.push(DenomVolume {
+                denom: volume.denom.clone(),
+                outbound: volume.outbound,
+                inbound: volume.inbound,
+            });
+        }
+    }
+
+    denom_volumes
+}
+
+/// Insert or replace the ICA's entry, keeping only the top `LEADERBOARD_SIZE` entries
+pub fn update_leaderboard(storage: &mut dyn Storage, entry: LeaderboardEntry) {
+    let mut leaderboard = state::leaderboard(storage).unwrap_or_default();
+
+    leaderboard.retain(|e| e.ica_idx != entry.ica_idx);
+
+    leaderboard.push(entry);
+
+    leaderboard.sort_by(|a, b| {
+        b.roundtrips
+            .cmp(&a.roundtrips)
+            .then_with(|| b.volume.cmp(&a.volume))
+    });
+
+    leaderboard.truncate(LEADERBOARD_SIZE);
+
+    state::set_leaderboard(storage, &leaderboard);
+}
+
+/// Check the funds delivered by a retrieval hook against the retrieval identified by `rx_hash`
+pub fn validate_retrieved_funds(
+    storage: &dyn Storage,
+    rx_hash: &str,
+    funds: &[Coin],
+) -> Result<u32, Error> {
+    let ica_idx = state::rx_hash_ica_idx(storage, rx_hash).ok_or(Error::InvalidRxHash)?;
+
+    let tx_denom = state::rx_hash_expected_denom(storage, rx_hash)
+        .unwrap_or_else(|| state::host_ibc_denom(storage));
+
+    if let Some(coin) = funds.iter().find(|c| c.denom != tx_denom) {
+        return Err(Error::UnexpectedRetrievedDenom {
+            expected: tx_denom,
+            received: coin.denom.clone(),
+        });
+    }
+
+    // retrievals issued before amounts were recorded are not checked
+    let Some(expected) = state::rx_hash_expected_amount(storage, rx_hash) else {
+        return Ok(ica_idx);
+    };
+
+    let received = funds
+        .iter()
+        .filter(|c| c.denom == tx_denom)
+        .map(|c| c.amount)
+        .sum();
+
+    if expected != received {
+        return Err(Error::RetrievedAmountMismatch { expected, received });
+    }
+
+    Ok(ica_idx)
+}
+
+/// Ensure the voucher `denom` of the retrieval `rx_hash` traces back to the remote denom, received over the transfer
+/// channel or the path of the route the retrieval was forwarded through
+pub fn ensure_ics20_trace(
+    storage: &dyn Storage,
+    rx_hash: &str,
+    denom: &str,
+    trace: &DenomTrace,
+) -> Result<(), Error> {
+    let unverified = |reason: String| Error::UnverifiedIcs20Receipt {
+        denom: denom.to_owned(),
+        reason,
+    };
+
+    let remote_denom = state::remote_denom(storage);
+
+    if trace.base_denom != remote_denom {
+        return Err(unverified(format!(
+            "base denom {} is not the remote denom {remote_denom}",
+            trace.base_denom
+        )));
+    }
+
+    let expected_path = state::rx_hash_expected_path(storage, rx_hash)
+        .unwrap_or_else(|| format!("transfer/{}", state::ibc_transfer_channel(storage)));
+
+    if trace.path != expected_path {
+        return Err(unverified(format!(
+            "path {} is not {expected_path}",
+            trace.path
+        )));
+    }
+
+    Ok(())
+}
+
+/// Verify against the transfer module that the `funds` delivered for the retrieval `rx_hash` are vouchers of the
+/// remote denom, received over the path of the retrieval.
+///
+/// This is the only guarantee: the escrow or mint of a past packet cannot be queried, so the amounts are only
+/// checked against the retrieval by `validate_retrieved_funds`, & ibc-hooks has already sent the funds to the
+/// contract by the time the hook executes
+pub fn verify_ics20_receipt(
+    deps: Deps<impl CustomQuery>,
+    rx_hash: &str,
+    funds: &[Coin],
+) -> Result<(), Error> {
+    for coin in funds {
+        let trace =
+            query_denom_trace(deps, &coin.denom).map_err(|err| Error::UnverifiedIcs20Receipt {
+                denom: coin.denom.clone(),
+                reason: err.to_string(),
+            })?;
+
+        ensure_ics20_trace(deps.storage, rx_hash, &coin.denom, &trace)?;
+    }
+
+    Ok(())
+}
+
+/// Ensure `sender` is the address ibc-hooks executes the hook of the retrieval `rx_hash` of the ICA at `ica_idx` as,
+/// derived from the transfer channel the retrievals are received on & the ICA address they are sent from unless
+/// the retrieval was forwarded through intermediate chains
+pub fn ensure_hook_sender(
+    storage: &dyn Storage,
+    rx_hash: &str,
+    ica_idx: u32,
+    sender: &Addr,
+) -> Result<(), Error> {
+    let expected = state::rx_hash_hook_sender(storage, rx_hash).or_else(|| {
+        state::ica_addr(storage, &ica_idx).map(|ica_addr| {
+            let channel = state::ibc_transfer_channel(storage);
+
+            ibc_hooks_sender(&channel, &ica_addr, HOOK_SENDER_BECH32_PREFIX)
+        })
+    });
+
+    if expected.as_deref() != Some(sender.as_str()) {
+        return Err(Error::UnexpectedHookSender {
+            ica_idx,
+            sender: sender.to_string(),
+            expected: expected.unwrap_or_default(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Forget the retrieval identified by `rx_hash` once its funds are delivered, so that the hook cannot be replayed
+pub fn consume_rx_hash(storage: &mut dyn Storage, rx_hash: &str) {
+    if let Some(ica_idx) = state::rx_hash_ica_idx(storage, rx_hash) {
+        let undelivered = state::ica_undelivered_retrievals(storage, &ica_idx).unwrap_or_default();
+
+        state::set_ica_undelivered_retrievals(storage, &ica_idx, undelivered.saturating_sub(1));
+    }
+
+    state::remove_rx_hash_ica_idx(storage, rx_hash);
+    state::remove_rx_hash_scheme(storage, rx_hash);
+    state::remove_rx_hash_expected_amount(storage, rx_hash);
+    state::remove_rx_hash_expected_denom(storage, rx_hash);
+    state::remove_rx_hash_hook_sender(storage, rx_hash);
+    state::remove_rx_hash_expected_path(storage, rx_hash);
+
+    if state::rx_hash_height(storage, rx_hash).is_some() {
+        state::remove_rx_hash_height(storage, rx_hash);
+
+        let count = state::rx_hash_count(storage).unwrap_or_default();
+
+        state::set_rx_hash_count(storage, count.saturating_sub(1));
+    }
+}
+
+/// Forget the tx identified by `tx_hash`, its acknowledgement or timeout can no longer be attributed to the ICA
+pub fn forget_tx_hash(storage: &mut dyn Storage, tx_hash: &str) {
+    state::remove_tx_hash_ica_idx(storage, tx_hash);
+    state::remove_tx_hash_scheme(storage, tx_hash);
+    state::remove_tx_hash_history_idx(storage, tx_hash);
+    state::remove_tx_hash_transfer_sender(storage, tx_hash);
+    state::remove_tx_hash_issued_tx(storage, tx_hash);
+
+    if state::tx_hash_height(storage, tx_hash).is_some() {
+        state::remove_tx_hash_height(storage, tx_hash);
+
+        let count = state::tx_hash_count(storage).unwrap_or_default();
+
+        state::set_tx_hash_count(storage, count.saturating_sub(1));
+    }
+}
+
+/// Stamp the mapping of `hash` with the local `height` it is swept after the retention window of
+pub fn stamp_hash(storage: &mut dyn Storage, kind: state::HashKind, hash: &str, height: u64) {
+    let previous = match kind {
+        state::HashKind::Tx => state::tx_hash_height(storage, hash),
+        state::HashKind::Rx => state::rx_hash_height(storage, hash),
+    };
+
+    match kind {
+        state::HashKind::Tx => state::set_tx_hash_height(storage, hash, height),
+        state::HashKind::Rx => state::set_rx_hash_height(storage, hash, height),
+    }
+
+    if previous.is_none() {
+        match kind {
+            state::HashKind::Tx => {
+                let count = state::tx_hash_count(storage).unwrap_or_default();
+
+                state::set_tx_hash_count(storage, count + 1);
+            }
+            state::HashKind::Rx => {
+                let count = state::rx_hash_count(storage).unwrap_or_default();
+
+                state::set_rx_hash_count(storage, count + 1);
+            }
+        }
+    }
+
+    let idx = state::hash_log_len(storage).unwrap_or_default();
+
+    let entry = state::HashLogEntry {
+        kind,
+        hash: hash.to_owned(),
+        height,
+    };
+
+    state::set_hash_log_entry(storage, &idx, &entry);
+
+    state::set_hash_log_len(storage, idx + 1);
+}
+
+#[must_use]
+pub fn hash_retention_blocks(storage: &dyn Storage) -> u64 {
+    state::hash_retention_blocks(storage).unwrap_or(DEFAULT_HASH_RETENTION_BLOCKS)
+}
+
+pub fn save_hash_retention(
+    storage: &mut dyn Storage,
+    retention_blocks: Option<u64>,
+) -> Result<(), Error> {
+    match retention_blocks {
+        Some(0) => return Err(Error::ZeroHashRetention),
+        Some(blocks) => state::set_hash_retention_blocks(storage, blocks),
+        None => state::remove_hash_retention_blocks(storage),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SweepPass {
+    pub tx_hashes: u32,
+    pub rx_hashes: u32,
+    /// Mappings of txs & retrievals still in flight, stamped again at the current height
+    pub restamped: u32,
+}
+
+/// Prune at most `limit` hash mappings stamped before the retention window ending at the local `height`
+pub fn sweep_hashes(storage: &mut dyn Storage, height: u64, limit: u32) -> SweepPass {
+    let retention_blocks = hash_retention_blocks(storage);
+
+    let mut cursor = state::hash_log_cursor(storage).unwrap_or_default();
+
+    let mut pass = SweepPass::default();
+
+    for _ in 0..limit {
+        if cursor >= state::hash_log_len(storage).unwrap_or_default() {
+            break;
+        }
+
+        let entry =
+            state::hash_log_entry(storage, &cursor).expect("entries after the cursor are kept");
+
+        // entries are in ascending height order, every later entry is within the window too
+        if entry.height.saturating_add(retention_blocks) > height {
+            break;
+        }
+
+        state::remove_hash_log_entry(storage, &cursor);
+
+        cursor += 1;
+
+        let stamped_height = match entry.kind {
+            state::HashKind::Tx => state::tx_hash_height(storage, &entry.hash),
+            state::HashKind::Rx => state::rx_hash_height(storage, &entry.hash),
+        };
+
+        // already forgotten, or stamped again by a later entry
+        if stamped_height != Some(entry.height) {
+            continue;
+        }
+
+        match entry.kind {
+            state::HashKind::Tx if state::tx_hash_pending_tx(storage, &entry.hash).is_some() => {
+                stamp_hash(storage, state::HashKind::Tx, &entry.hash, height);
+
+                pass.restamped += 1;
+            }
+            state::HashKind::Tx => {
+                forget_tx_hash(storage, &entry.hash);
+
+                pass.tx_hashes += 1;
+            }
+            state::HashKind::Rx if rx_hash_in_flight(storage, &entry.hash) => {
+                stamp_hash(storage, state::HashKind::Rx, &entry.hash, height);
+
+                pass.restamped += 1;
+            }
+            state::HashKind::Rx => {
+                consume_rx_hash(storage, &entry.hash);
+
+                pass.rx_hashes += 1;
+            }
+        }
+    }
+
+    state::set_hash_log_cursor(storage, cursor);
+
+    pass
+}
+
+/// Whether the retrieval identified by `rx_hash` may still be in flight, its ICA having retrievals awaiting their
+/// acknowledgement
+fn rx_hash_in_flight(storage: &dyn Storage, rx_hash: &str) -> bool {
+    state::rx_hash_ica_idx(storage, rx_hash)
+        .is_some_and(|ica_idx| ica_retrievals_in_flight(storage, ica_idx) > 0)
+}
+
+#[must_use]
+pub fn hash_mappings(storage: &dyn Storage) -> HashMappingsResponse {
+    let cursor = state::hash_log_cursor(storage).unwrap_or_default();
+
+    HashMappingsResponse {
+        tx_hashes: state::tx_hash_count(storage).unwrap_or_default(),
+        rx_hashes: state::rx_hash_count(storage).unwrap_or_default(),
+        retention_blocks: hash_retention_blocks(storage),
+        oldest_height: state::hash_log_entry(storage, &cursor).map(|entry| entry.height),
+    }
+}
+
+/// The trace path of the vouchers retrieved through `route`, the channels they were received on most recent first
+pub fn retrieval_route_path(route: &RetrievalRoute) -> String {
+    std::iter::once(&route.host_channel)
+        .chain(route.hops.iter().rev().map(|hop| &hop.receive_channel))
+        .map(|channel| format!("transfer/{channel}"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn validate_retrieval_route(
+    storage: &dyn Storage,
+    route: &RetrievalRoute,
+) -> Result<(), Error> {
+    if route.hops.is_empty() || route.hops.len() > MAX_RETRIEVAL_HOPS {
+        return Err(Error::InvalidRetrievalHops {
+            hops: route.hops.len(),
+        });
+    }
+
+    validate_channel_id(&route.source_channel)?;
+
+    validate_channel_id(&route.host_channel)?;
+
+    validate_denom(&route.host_denom)?;
+
+    for hop in &route.hops {
+        validate_channel_id(&hop.receive_channel)?;
+
+        validate_channel_id(&hop.channel)?;
+
+        validate_bech32_prefix(&hop.bech32_prefix)?;
+    }
+
+    let expected = ics20_voucher_denom(&retrieval_route_path(route), &state::remote_denom(storage));
+
+    if route.host_denom != expected {
+        return Err(Error::RetrievalRouteDenomMismatch {
+            expected,
+            given: route.host_denom.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+pub fn execute_set_retrieval_route(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    name: String,
+    route: Option<RetrievalRoute>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set retrieval route: {name}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    validate_label(&name)?;
+
+    let event = match route {
+        Some(route) => {
+            validate_retrieval_route(deps.storage, &route)?;
+
+            state::set_retrieval_route(deps.storage, &name, &route);
+
+            Event::new("retrieval_route_set")
+                .add_attribute("name", name)
+                .add_attribute("host_channel", route.host_channel)
+                .add_attribute("host_denom", route.host_denom)
+        }
+        None => {
+            state::remove_retrieval_route(deps.storage, &name);
+
+            Event::new("retrieval_route_removed").add_attribute("name", name)
+        }
+    };
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Hold the funds of a retrieval hook that failed validation until the contract owner resolves it,
+/// erroring would leave them stranded in the IBC hooks module
+pub fn record_hook_dead_letter(
+    storage: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    rx_hash: &str,
+    reason: &Error,
+) -> Response<NeutronMsg> {
+    let idx = state::hook_dead_letter_count(storage).unwrap_or_default();
+
+    let dead_letter = HookDeadLetter {
+        idx,
+        height: env.block.height,
+        sender: info.sender.into_string(),
+        funds: info.funds,
+        rx_hash: rx_hash.to_owned(),
+        reason: reason.to_string(),
+    };
+
+    let event = Event::new("hook_dead_letter_recorded")
+        .add_attribute("idx", idx.to_string())
+        .add_attribute("rx_hash", rx_hash)
+        .add_attribute("amount", coins_attr(&dead_letter.funds))
+        .add_attribute("reason", dead_letter.reason.as_str());
+
+    state::set_dead_letter_hook(storage, &idx, &dead_letter);
+
+    state::set_hook_dead_letter_count(storage, idx + 1);
+
+    Response::default().add_event(event)
+}
+
+pub fn execute_resolve_hook_dead_letter(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+    idx: u32,
+    recipient: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing resolve hook dead letter {idx}");
+
+    ensure_contract_owner(deps.storage, &info.sender)?;
+
+    deps.api.addr_validate(&recipient)?;
+
+    let dead_letter =
+        state::dead_letter_hook(deps.storage, &idx).ok_or(Error::NoHookDeadLetter { idx })?;
+
+    state::remove_dead_letter_hook(deps.storage, &idx);
+
+    let event = Event::new("hook_dead_letter_resolved")
+        .add_attribute("idx", idx.to_string())
+        .add_attribute("recipient", recipient.as_str())
+        .add_attribute("amount", coins_attr(&dead_letter.funds));
+
+    let mut response = Response::default().add_event(event);
+
+    if !dead_letter.funds.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: recipient,
+            amount: dead_letter.funds,
+        });
+    }
+
+    Ok(response)
+}
+
+pub fn execute_funds_retrieved_hook(
+    deps: DepsMut<impl CustomQuery>,
+    env: Env,
+    info: MessageInfo,
+    rx_hash: &str,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing retrieve funds: rx_hash = {rx_hash}");
+
+    // only the retrievals the contract issued are held as dead letters, erroring returns the funds of any other
+    let ica_idx = state::rx_hash_ica_idx(deps.storage, rx_hash).ok_or(Error::InvalidRxHash)?;
+
+    // a caller other than ibc-hooks relaying the ICA's transfer can't claim the retrieval, erroring returns its funds
+    ensure_hook_sender(deps.storage, rx_hash, ica_idx, &info.sender)?;
+
+    let ica_idx = match validate_retrieved_funds(deps.storage, rx_hash, &info.funds) {
+        Ok(ica_idx) => ica_idx,
+        Err(err) => {
+            return Ok(record_hook_dead_letter(
+                deps.storage,
+                &env,
+                info,
+                rx_hash,
+                &err,
+            ))
+        }
+    };
+
+    if cfg!(feature = "ics20-check") {
+        if let Err(err) = verify_ics20_receipt(deps.as_ref(), rx_hash, &info.funds) {
+            return Ok(record_hook_dead_letter(
+                deps.storage,
+                &env,
+                info,
+                rx_hash,
+                &err,
+            ));
+        }
+    }
+
+    consume_rx_hash(deps.storage, rx_hash);
+
+    let current_round_trip_count =
+        state::ica_round_trip_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    state::set_ica_round_trip_count(deps.storage, &ica_idx, current_round_trip_count + 1);
+
+    update_global_totals(deps.storage, env.block.height, |totals| {
+        totals.round_trips += 1
+    });
+
+    let ica_owner = state::ica_owner(deps.storage, &ica_idx).expect("ica must have an owner");
+
+    let tx_denom = state::host_ibc_denom(deps.storage);
+
+    let transfer_channel = state::ibc_transfer_channel(deps.storage);
+
+    for coin in &info.funds {
+        add_channel_volume(
+            deps.storage,
+            &coin.denom,
+            &transfer_channel,
+            Uint128::zero(),
+            coin.amount,
+            env.block.height,
+        );
+    }
+
+    let retrieved_amount: Uint128 = info
+        .funds
+        .iter()
+        .filter(|c| c.denom == tx_denom)
+        .map(|c| c.amount)
+        .sum();
+
+    let volume =
+        state::ica_round_trip_volume(deps.storage, &ica_idx).unwrap_or_default() + retrieved_amount;
+
+    state::set_ica_round_trip_volume(deps.storage, &ica_idx, &volume);
+
+    update_leaderboard(
+        deps.storage,
+        LeaderboardEntry {
+            owner: ica_owner.clone(),
+            ica_idx,
+            roundtrips: current_round_trip_count + 1,
+            volume,
+        },
+    );
+
+    let event = ica_event("funds_retrieved", ica_idx)
+        .add_attribute("owner", ica_owner.as_str())
+        .add_attribute("amount", coins_attr(&info.funds))
+        .add_attribute("roundtrips", (current_round_trip_count + 1).to_string());
+
+    // forward the funds recieved from the ICA to it's owner
+    let msg = BankMsg::Send {
+        to_address: ica_owner,
+        amount: info.funds,
+    };
+
+    Ok(Response::default().add_message(msg).add_event(event))
+}
+
+/// Record the min IBC fee & ICQ deposit if they changed since they were last observed. The observation is skipped
+/// while either cannot be queried, the messages that do not issue txs must keep working without them.
+pub fn record_fee_params(deps: DepsMut<NeutronQuery>, env: &Env) {
+    let (Ok(min_ibc_fee), Ok(icq_deposit)) = (
+        deps.as_ref().min_ibc_fee(),
+        required_icq_deposit(deps.as_ref()),
+    ) else {
+        debug!(
+            deps,
+            "fee parameters unavailable at height {}, skipping their observation", env.block.height
+        );
+
+        return;
+    };
+
+    let mut history = state::fee_history(deps.storage).unwrap_or_default();
+
+    if history
+        .last()
+        .is_some_and(|last| last.min_ibc_fee == min_ibc_fee && last.icq_deposit == icq_deposit)
+    {
+        return;
+    }
+
+    debug!(
+        deps,
+        "fee parameters changed at height {}: min ibc fee = {min_ibc_fee:?}, icq deposit = {}",
+        env.block.height,
+        coins_attr(&icq_deposit)
+    );
+
+    history.push(FeeObservation {
+        height: env.block.height,
+        min_ibc_fee,
+        icq_deposit,
+    });
+
+    if history.len() > FEE_HISTORY_LIMIT {
+        history.drain(..history.len() - FEE_HISTORY_LIMIT);
+    }
+
+    state::set_fee_history(deps.storage, &history);
+}
+
+/// Set the receipt of every execute as its response data, for calling contracts to read from their reply
+#[entry_point]
+pub fn execute(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    let action = receipt::action_name(&msg)?;
+
+    let res = execute_msg(deps, env, info, msg)?;
+
+    let receipt = ExecuteReceipt::from_events(action, &res.events);
+
+    Ok(receipt::set_receipt(res, &receipt)?)
+}
+
+pub fn execute_msg(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "handling execute msg");
+
+    record_fee_params(deps.branch(), &env);
+
+    // the rewards retrieval issued on a claim ack is a new tx too, its failed reply leaves the rewards on the ICA
+    let issues_txs = matches!(
+        msg,
+        ExecuteMsg::SetupIca {}
+            | ExecuteMsg::ReopenIca {}
+            | ExecuteMsg::TransferFunds { .. }
+            | ExecuteMsg::RetrieveFunds { .. }
+            | ExecuteMsg::RetrieveFundsFor { .. }
+            | ExecuteMsg::RetrieveFundsVia { .. }
+            | ExecuteMsg::SubmitIcaTx { .. }
+            | ExecuteMsg::RetryLastTx { .. }
+            | ExecuteMsg::DelegateFromIca { .. }
+            | ExecuteMsg::UndelegateFromIca { .. }
+            | ExecuteMsg::ClaimRewards {}
+            | ExecuteMsg::RetrieveClaimedRewards { .. }
+    );
+
+    if issues_txs && is_paused(deps.storage) {
+        return Err(Error::Paused);
+    }
+
+    match msg {
+        ExecuteMsg::SetupIca {} => execute_setup_ica(deps, env, info),
+
+        ExecuteMsg::CancelIcaRegistration {} => execute_cancel_ica_registration(deps, env, info),
+
+        ExecuteMsg::ReopenIca {} => execute_reopen_ica(deps, env, info),
+
+        ExecuteMsg::DepositFees {} => fee_escrow::execute_deposit_fees(deps, info),
+
+        ExecuteMsg::WithdrawFees {} => fee_escrow::execute_withdraw_fees(deps, info),
+
+        ExecuteMsg::TransferFunds { memo, owner } => {
+            execute_transfer_funds(deps, env, info, memo, owner)
+        }
+
+        ExecuteMsg::RetrieveFunds { amount, owner } => {
+            execute_retrieve_funds(deps, env, info, amount, None, owner)
+        }
+
+        ExecuteMsg::GrantOperator {
+            operator,
+            allowance,
+        } => execute_grant_operator(deps, info, operator, allowance),
+
+        ExecuteMsg::RevokeOperator { operator } => execute_revoke_operator(deps, info, operator),
+
+        ExecuteMsg::SubmitIcaTx { msgs, timeout } => {
+            execute_submit_ica_tx(deps, info, msgs, timeout)
+        }
+
+        ExecuteMsg::RetrieveFundsVia { route } => {
+            let route = state::retrieval_route(deps.storage, &route)
+                .ok_or(Error::UnknownRetrievalRoute { name: route })?;
+
+            execute_retrieve_funds(deps, env, info, None, Some(route), None)
+        }
+
+        ExecuteMsg::RetrieveFundsFor { owners } => {
+            execute_retrieve_funds_for(deps, env, info, owners)
+        }
+
+        ExecuteMsg::RetryLastTx { tx_kind } => execute_retry_last_tx(deps, env, info, tx_kind),
+
+        ExecuteMsg::EnableAutoRetrieve {} => execute_enable_auto_retrieve(deps, info),
+
+        ExecuteMsg::DisableAutoRetrieve {} => execute_disable_auto_retrieve(deps, info),
+
+        ExecuteMsg::UpdateIcqPeriod { new_period } => {
+            execute_update_icq_period(deps, info, new_period)
+        }
+
+        ExecuteMsg::DelegateFromIca { validator, amount } => {
+            execute_delegate_from_ica(deps, info, validator, amount)
+        }
+
+        ExecuteMsg::UndelegateFromIca { validator, amount } => {
+            execute_undelegate_from_ica(deps, info, validator, amount)
+        }
+
+        ExecuteMsg::ClaimRewards {} => execute_claim_rewards(deps, info),
+
+        ExecuteMsg::SetIcaLabel { label, note } => execute_set_ica_label(deps, info, label, note),
+
+        ExecuteMsg::SetAddressBookEntry {
+            address,
+            label,
+            note,
+        } => execute_set_address_book_entry(deps, info, address, label, note),
+
+        ExecuteMsg::RemoveAddressBookEntry { address } => {
+            execute_remove_address_book_entry(deps, info, address)
+        }
+
+        ExecuteMsg::RegisterTransfersQuery {} => execute_register_transfers_query(deps, info),
+
+        ExecuteMsg::TeardownIca {} => execute_teardown_ica(deps, env, info),
+
+        ExecuteMsg::SetPaused { paused } => execute_set_paused(deps, info, paused),
+
+        ExecuteMsg::SetStateCommitments { enabled } => {
+            execute_set_state_commitments(deps, env, info, enabled)
+        }
+
+        ExecuteMsg::UpdateTimeouts {
+            timeout_seconds,
+            timeout_height,
+            timeout_revision_number,
+        } => execute_update_timeouts(
+            deps,
+            info,
+            timeout_seconds,
+            timeout_height,
+            timeout_revision_number,
+        ),
+
+        ExecuteMsg::UpdateTransferLimits { limits } => {
+            execute_update_transfer_limits(deps, info, limits)
+        }
+
+        ExecuteMsg::UpdateMemoLimits {
+            max_memo_len,
+            max_hook_memo_len,
+        } => execute_update_memo_limits(deps, info, max_memo_len, max_hook_memo_len),
+
+        ExecuteMsg::SetRetrievalRoute { name, route } => {
+            execute_set_retrieval_route(deps, info, name, route)
+        }
+
+        ExecuteMsg::UpdateIndexers { add, remove } => {
+            execute_update_indexers(deps, info, add, remove)
+        }
+
+        ExecuteMsg::ClearIcqQuarantine { icq_id } => {
+            execute_clear_icq_quarantine(deps, info, icq_id)
+        }
+
+        ExecuteMsg::CheckpointIndexer { activity_id } => {
+            execute_checkpoint_indexer(deps, env, info, activity_id)
+        }
+
+        ExecuteMsg::TransferOwnership { new_owner } => {
+            execute_transfer_ownership(deps, info, new_owner)
+        }
+
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, info),
+
+        ExecuteMsg::ResolveHookDeadLetter { idx, recipient } => {
+            execute_resolve_hook_dead_letter(deps, info, idx, recipient)
+        }
+
+        ExecuteMsg::SubscribeAlerts {} => execute_subscribe_alerts(deps, info),
+
+        ExecuteMsg::UnsubscribeAlerts {} => {
+            Ok(remove_alert_subscriber(deps.storage, info.sender.as_str()))
+        }
+
+        ExecuteMsg::ApproveAlertSubscriber { subscriber } => {
+            execute_approve_alert_subscriber(deps, info, subscriber)
+        }
+
+        ExecuteMsg::RemoveAlertSubscriber { subscriber } => {
+            ensure_contract_owner(deps.storage, &info.sender)?;
+
+            Ok(revoke_alert_subscriber(deps.storage, &subscriber))
+        }
+
+        ExecuteMsg::RebuildIndexes { limit } => execute_rebuild_indexes(deps, info, limit),
+
+        ExecuteMsg::UpdateHashRetention { retention_blocks } => {
+            execute_update_hash_retention(deps, info, retention_blocks)
+        }
+
+        ExecuteMsg::UpdateIcqDepositOverride { deposit } => {
+            execute_update_icq_deposit_override(deps, info, deposit)
+        }
+
+        ExecuteMsg::Sweep { limit } => execute_sweep(deps, env, limit),
+
+        ExecuteMsg::RetrieveClaimedRewards { ica_idx, amount } => {
+            execute_retrieve_claimed_rewards(deps, &env, &info, ica_idx, amount)
+        }
+
+        ExecuteMsg::FundsRetrievedHook { rx_hash } => {
+            execute_funds_retrieved_hook(deps, env, info, &rx_hash)
+        }
+    }
+}
+
+/// The ICA index of an open ack for `port_id`, which must be the port of an ICA registration issued by this contract
+/// that is still awaiting its ack - anything else could hijack the address of another ICA
+pub fn registering_ica_idx(
+    storage: &dyn Storage,
+    contract: &Addr,
+    port_id: &str,
+) -> Result<u32, Error> {
+    common::ica_idx_from_port_id(port_id, contract.as_str())
+        .filter(|ica_idx| {
+            matches!(
+                ica_status(storage, *ica_idx),
+                IcaStatus::Registering | IcaStatus::Reopening
+            )
+        })
+        .ok_or_else(|| Error::UnexpectedOpenAck {
+            port_id: port_id.to_owned(),
+        })
+}
+
+pub fn sudo_open_ack(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    channel: IcaChannel,
+    counterparty_version: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(
+        deps,
+        "received open ack for {}: {counterparty_version}", channel.port_id
+    );
+
+    // The version variable contains a JSON value with multiple fields,
+    // including the generated account address.
+    let parsed_version: OpenAckVersion =
+        from_slice(counterparty_version.as_bytes()).expect("valid counterparty_version");
+
+    let ica_idx = registering_ica_idx(deps.storage, &env.contract.address, &channel.port_id)?;
+
+    let reopened = ica_status(deps.storage, ica_idx) == IcaStatus::Reopening;
+
+    let ica_addr = parsed_version.address;
+
+    set_ica_status(deps.storage, ica_idx, IcaStatus::Active, env.block.height);
+
+    // a reopened ICA is served by the new channel
+    state::set_ica_channel(deps.storage, &ica_idx, &channel);
+
+    // a reopened ICA keeps the balance ICQ registered when it was first setup
+    if reopened && state::ica_icq_id(deps.storage, &ica_idx).is_some() {
+        state::set_ica_addr(deps.storage, &ica_idx, &ica_addr);
+
+        let event = ica_event("ica_reopened", ica_idx).add_attribute("address", ica_addr);
+
+        return Ok(Response::default().add_event(event));
+    }
+
+    // the deposit is spent on the balance ICQ registered below
+    state::remove_ica_setup_deposit(deps.storage, &ica_idx);
+
+    state::set_ica_addr(deps.storage, &ica_idx, &ica_addr);
+
+    let connection_id = state::connection_id(deps.storage);
+
+    let icq_update_period = state::icq_update_period(deps.storage);
+
+    let balance_icq_denom = state::remote_denom(deps.storage);
+
+    let balance_icq_register_msg = new_register_balance_query_msg(
+        connection_id.clone(),
+        ica_addr.clone(),
+        balance_icq_denom,
+        icq_update_period,
+    )?;
+
+    let event = ica_event("ica_registered", ica_idx).add_attribute("address", ica_addr);
+
+    let reply_id = save_pending_reply(deps.storage, state::ReplyKind::RegisterIcq, ica_idx, None);
+
+    let response = Response::default()
+        .add_submessage(SubMsg::reply_on_success(balance_icq_register_msg, reply_id))
+        .add_event(event);
+
+    Ok(response)
+}
+
+/// Append an issued transfer or retrieval to the ICA's tx history, other tx kinds are not logged
+pub fn append_tx_history(
+    storage: &mut dyn Storage,
+    ica_idx: u32,
+    tx_hash: &str,
+    issued_tx: &IssuedTx,
+    seq_num: u64,
+    height: u64,
+) {
+    let direction = match issued_tx.kind {
+        TxKind::Transfer => TransferDirection::ToIca,
+        TxKind::Retrieve => TransferDirection::FromIca,
+        TxKind::Delegate | TxKind::Undelegate | TxKind::ClaimRewards | TxKind::Custom => return,
+    };
+
+    let idx = state::ica_tx_history_len(storage, &ica_idx).unwrap_or_default();
+
+    let entry = TxHistoryEntry {
+        idx,
+        direction,
+        coin: issued_tx.coin.clone(),
+        seq_num,
+        height,
+        outcome: TxOutcome::Pending,
+    };
+
+    state::set_ica_history_idx_tx_entry(storage, &combine_u32s(ica_idx, idx), &entry);
+
+    state::set_ica_tx_history_len(storage, &ica_idx, idx + 1);
+
+    state::set_tx_hash_history_idx(storage, tx_hash, idx);
+
+    log_activity(storage, ica_idx, entry, height);
+}
+
+/// Settle the outcome of the tx history entry of `tx_hash`, if it has one, recording the height the ICA's last tx
+/// settled at
+pub fn record_tx_outcome(
+    storage: &mut dyn Storage,
+    ica_idx: u32,
+    tx_hash: &str,
+    outcome: TxOutcome,
+    height: u64,
+) {
+    state::set_ica_settled_height(storage, &ica_idx, height);
+
+    let Some(idx) = state::tx_hash_history_idx(storage, tx_hash) else {
+        return;
+    };
+
+    state::remove_tx_hash_history_idx(storage, tx_hash);
+
+    let history_key = combine_u32s(ica_idx, idx);
+
+    let Some(mut entry) = state::ica_history_idx_tx_entry(storage, &history_key) else {
+        return;
+    };
+
+    entry.outcome = outcome;
+
+    state::set_ica_history_idx_tx_entry(storage, &history_key, &entry);
+
+    log_activity(storage, ica_idx, entry, height);
+}
+
+#[must_use]
+pub fn state_commitments_enabled(storage: &dyn Storage) -> bool {
+    state::state_commitments_enabled(storage).unwrap_or_default()
+}
+
+/// Log a change to a tx history entry as activity, folding it into the activity digest & committing to it,
+/// if state commitments are enabled
+pub fn log_activity(storage: &mut dyn Storage, ica_idx: u32, entry: TxHistoryEntry, height: u64) {
+    if !state_commitments_enabled(storage) {
+        return;
+    }
+
+    let (activity_id, prev_digest) = state::state_commitment(storage)
+        .map(|c| (c.activity_count, c.activity_digest))
+        .unwrap_or_default();
+
+    let activity = ActivityEntry {
+        activity_id,
+        ica_idx,
+        entry,
+    };
+
+    let activity_json = to_vec(&activity).expect("infallible serialization");
+
+    let activity_digest =
+        HASH_SCHEME.hash(ACTIVITY_DOMAIN, &[prev_digest.as_bytes(), &activity_json]);
+
+    state::set_activity_entry(storage, &activity_id, &activity);
+
+    if let Some(pruned_id) = activity_id.checked_sub(MAX_ACTIVITY_ENTRIES) {
+        prune_activity(storage, pruned_id);
+    }
+
+    commit_state(storage, height, activity_id + 1, activity_digest);
+}
+
+/// Remove the activity entry `activity_id`, the oldest one logged, folding it into the pruned activity digest
+fn prune_activity(storage: &mut dyn Storage, activity_id: u64) {
+    let Some(activity) = state::activity_entry(storage, &activity_id) else {
+        return;
+    };
+
+    let activity_json = to_vec(&activity).expect("infallible serialization");
+
+    let prev_digest = state::pruned_activity_digest(storage).unwrap_or_default();
+
+    let pruned_digest =
+        HASH_SCHEME.hash(ACTIVITY_DOMAIN, &[prev_digest.as_bytes(), &activity_json]);
+
+    state::set_pruned_activity_digest(storage, &pruned_digest);
+
+    state::remove_activity_entry(storage, &activity_id);
+}
+
+/// Commit to the global stats after they changed, if state commitments are enabled
+pub fn commit_stats(storage: &mut dyn Storage, height: u64) {
+    if !state_commitments_enabled(storage) {
+        return;
+    }
+
+    let (activity_count, activity_digest) = state::state_commitment(storage)
+        .map(|c| (c.activity_count, c.activity_digest))
+        .unwrap_or_default();
+
+    commit_state(storage, height, activity_count, activity_digest);
+}
+
+/// Commit to the current global stats & the given state of the activity log
+pub fn commit_state(
+    storage: &mut dyn Storage,
+    height: u64,
+    activity_count: u64,
+    activity_digest: String,
+) {
+    let stats_json = to_vec(&global_stats(storage)).expect("infallible serialization");
+
+    let stats_digest = HASH_SCHEME.hash(STATS_DOMAIN, &[&stats_json]);
+
+    let commitment = HASH_SCHEME.hash(
+        COMMITMENT_DOMAIN,
+        &[
+            &height.to_be_bytes(),
+            &activity_count.to_be_bytes(),
+            activity_digest.as_bytes(),
+            stats_digest.as_bytes(),
+        ],
+    );
+
+    let state_commitment = StateCommitment {
+        height,
+        activity_count,
+        activity_digest,
+        stats_digest,
+        commitment,
+    };
+
+    state::set_state_commitment(storage, &state_commitment);
+}
+
+/// Consume the rx hash of the retrieve tx of `tx_hash` that failed or timed out, its funds never left the ICA so that
+/// no hook will deliver them
+pub fn consume_failed_retrieval(storage: &mut dyn Storage, tx_hash: &str) {
+    if let Some(IssuedTx {
+        rx_hash: Some(rx_hash),
+        ..
+    }) = state::tx_hash_issued_tx(storage, tx_hash)
+    {
+        consume_rx_hash(storage, &rx_hash);
+    }
+}
+
+/// Keep the parameters of a failed tx as the last failure of its kind, so that it can be retried
+pub fn record_failed_tx(storage: &mut dyn Storage, ica_idx: u32, tx_hash: &str) {
+    let Some(issued_tx) = state::tx_hash_issued_tx(storage, tx_hash) else {
+        return;
+    };
+
+    let failed_tx_key = combine_u32s(ica_idx, tx_kind_reply_kind(issued_tx.kind));
+
+    state::set_ica_tx_kind_failed_tx(storage, &failed_tx_key, &issued_tx);
+
+    state::remove_tx_hash_issued_tx(storage, tx_hash);
+}
+
+/// Forward the tokens of a failed outgoing transfer, refunded to the contract by the ICS-20 module, to the owner who sent them.
+///
+/// Consumes the issued tx entry, so a refunded transfer can't be retried.
+pub fn refund_failed_transfer(storage: &mut dyn Storage, tx_hash: &str) -> Option<BankMsg> {
+    let sender = state::tx_hash_transfer_sender(storage, tx_hash)?;
+
+    state::remove_tx_hash_transfer_sender(storage, tx_hash);
+
+    let Some(IssuedTx {
+        kind: TxKind::Transfer,
+        coin,
+        ..
+    }) = state::tx_hash_issued_tx(storage, tx_hash)
+    else {
+        return None;
+    };
+
+    state::remove_tx_hash_issued_tx(storage, tx_hash);
+
+    Some(BankMsg::Send {
+        to_address: sender,
+        amount: vec![coin],
+    })
+}
+
+/// An acknowledged packet attributed to the ICA tx that sent it
+pub struct AckedTx {
+    pub seq_num: u64,
+    pub source_channel: String,
+    pub tx_hash: String,
+    pub ica_idx: u32,
+}
+
+pub fn acked_tx(storage: &dyn Storage, request: &RequestPacket) -> Result<AckedTx, Error> {
+    let seq_num = request.sequence.ok_or(Error::IncompletePacket {
+        field: "sequence number",
+    })?;
+
+    let source_channel = request
+        .source_channel
+        .clone()
+        .ok_or(Error::IncompletePacket {
+            field: "source channel",
+        })?;
+
+    // txs in flight across an upgrade were issued under an older hash scheme
+    let (tx_hash, ica_idx) = std::iter::once(HASH_SCHEME)
+        .chain(HashScheme::ALL.into_iter().rev())
+        .find_map(|scheme| {
+            let tx_hash = scheme.hash(
+                TX_HASH_DOMAIN,
+                &[&seq_num.to_be_bytes(), source_channel.as_bytes()],
+            );
+
+            state::tx_hash_ica_idx(storage, &tx_hash).map(|ica_idx| (tx_hash, ica_idx))
+        })
+        .ok_or_else(|| Error::UnknownTx {
+            seq_num,
+            channel: source_channel.clone(),
+        })?;
+
+    Ok(AckedTx {
+        seq_num,
+        source_channel,
+        tx_hash,
+        ica_idx,
+    })
+}
+
+/// Keep a packet that could not be attributed to an issued tx for inspection, rather than failing the callback
+pub fn record_dead_letter(
+    storage: &mut dyn Storage,
+    env: &Env,
+    callback: &str,
+    request: &RequestPacket,
+    reason: &Error,
+) -> Response<NeutronMsg> {
+    let idx = state::dead_letter_count(storage).unwrap_or_default();
+
+    let dead_letter = DeadLetter {
+        idx,
+        height: env.block.height,
+        callback: callback.to_owned(),
+        sequence: request.sequence,
+        source_channel: request.source_channel.clone(),
+        reason: reason.to_string(),
+    };
+
+    state::set_dead_letter_packet(storage, &idx, &dead_letter);
+
+    state::set_dead_letter_count(storage, idx + 1);
+
+    let event = Event::new("dead_letter_recorded")
+        .add_attribute("idx", idx.to_string())
+        .add_attribute("callback", callback)
+        .add_attribute("reason", dead_letter.reason);
+
+    Response::default().add_event(event)
+}
+
+pub fn sudo_response(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    request: RequestPacket,
+    data: Binary,
+) -> Result<Response<NeutronMsg>, Error> {
+    let AckedTx {
+        seq_num: tx_seq_num,
+        source_channel,
+        tx_hash,
+        ica_idx,
+    } = match acked_tx(deps.storage, &request) {
+        Ok(acked_tx) => acked_tx,
+        Err(err) => {
+            return Ok(record_dead_letter(
+                deps.storage,
+                &env,
+                "response",
+                &request,
+                &err,
+            ))
+        }
+    };
+
+    debug!(deps, "received sudo response for tx: {tx_hash}");
+
+    ensure_ica_status(deps.storage, ica_idx, &TX_OUTCOME_STATUSES)?;
+
+    let mut tx_success_count =
+        state::ica_tx_success_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    tx_success_count += 1;
+
+    debug!(
+        deps,
+        "ICA {ica_idx} issued tx with sequence number {tx_seq_num} successfully, total success count: {tx_success_count}"
+    );
+
+    state::set_ica_tx_success_count(deps.storage, &ica_idx, tx_success_count);
+
+    update_global_totals(deps.storage, env.block.height, |totals| {
+        totals.tx_success += 1
+    });
+
+    let fee_refund = fee_escrow::credit_unused_fee(deps.storage, ica_idx, &tx_hash, false);
+
+    record_tx_outcome(
+        deps.storage,
+        ica_idx,
+        &tx_hash,
+        TxOutcome::Success,
+        env.block.height,
+    );
+
+    settle_pending_tx(deps.storage, ica_idx, &tx_hash);
+
+    let issued_tx = state::tx_hash_issued_tx(deps.storage, &tx_hash);
+
+    if let Some(IssuedTx {
+        kind: TxKind::Transfer,
+        coin,
+        ..
+    }) = &issued_tx
+    {
+        add_channel_volume(
+            deps.storage,
+            &coin.denom,
+            &source_channel,
+            coin.amount,
+            Uint128::zero(),
+            env.block.height,
+        );
+    }
+
+    state::remove_tx_hash_issued_tx(deps.storage, &tx_hash);
+
+    state::remove_tx_hash_transfer_sender(deps.storage, &tx_hash);
+
+    let event = ica_event("tx_success", ica_idx)
+        .add_attribute("seq_num", tx_seq_num.to_string())
+        .add_attribute("fee_refund", coins_attr(&fee_refund));
+
+    let response = match issued_tx {
+        Some(IssuedTx {
+            kind: TxKind::ClaimRewards,
+            ..
+        }) => record_claimed_rewards(deps, &env, ica_idx, data.as_slice())?,
+        Some(IssuedTx {
+            kind: TxKind::Undelegate,
+            ..
+        }) => {
+            record_unbonding(deps.storage, &env, ica_idx, data.as_slice());
+
+            Response::default()
+        }
+        _ => Response::default(),
+    };
+
+    Ok(response.add_event(event))
+}
+
+pub fn sudo_error(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    request: RequestPacket,
+    error: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    let AckedTx {
+        seq_num: tx_seq_num,
+        tx_hash,
+        ica_idx,
+        ..
+    } = match acked_tx(deps.storage, &request) {
+        Ok(acked_tx) => acked_tx,
+        Err(err) => {
+            return Ok(record_dead_letter(
+                deps.storage,
+                &env,
+                "error",
+                &request,
+                &err,
+            ))
+        }
+    };
+
+    debug!(deps, "received sudo error for tx: {tx_hash}");
+
+    ensure_ica_status(deps.storage, ica_idx, &TX_OUTCOME_STATUSES)?;
+
+    let mut tx_error_count = state::ica_tx_error_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    let error_key = combine_u32s(ica_idx, tx_error_count);
+
+    tx_error_count += 1;
+
+    debug!(
+        deps,
+        "ICA {ica_idx} issued tx with sequence number {tx_seq_num} failed: {error}, total error count: {tx_error_count}"
+    );
+
+    state::set_ica_tx_error_count(deps.storage, &ica_idx, tx_error_count);
+
+    update_global_totals(deps.storage, env.block.height, |totals| {
+        totals.tx_error += 1
+    });
+
+    state::set_ica_err_idx_msg(deps.storage, &error_key, &error);
+
+    // an error is still an acknowledgement
+    let fee_refund = fee_escrow::credit_unused_fee(deps.storage, ica_idx, &tx_hash, false);
+
+    record_tx_outcome(
+        deps.storage,
+        ica_idx,
+        &tx_hash,
+        TxOutcome::Error,
+        env.block.height,
+    );
+
+    settle_pending_tx(deps.storage, ica_idx, &tx_hash);
+
+    consume_failed_retrieval(deps.storage, &tx_hash);
+
+    let mut response = Response::default();
+
+    if let Some(refund_msg) = refund_failed_transfer(deps.storage, &tx_hash) {
+        response = response.add_message(refund_msg);
+    }
+
+    record_failed_tx(deps.storage, ica_idx, &tx_hash);
+
+    let alert = ProbeAlert {
+        ica_idx,
+        owner: state::ica_owner(deps.storage, &ica_idx).unwrap_or_default(),
+        kind: AlertKind::TxError,
+        seq_num: tx_seq_num,
+        error: Some(error.clone()),
+        channel_closed: false,
+    };
+
+    let event = ica_event("tx_error", ica_idx)
+        .add_attribute("seq_num", tx_seq_num.to_string())
+        .add_attribute("fee_refund", coins_attr(&fee_refund))
+        .add_attribute("error", error);
+
+    Ok(response
+        .add_submessages(alert_hook_msgs(deps.storage, alert)?)
+        .add_event(event))
+}
+
+pub fn sudo_timeout(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    request: RequestPacket,
+) -> Result<Response<NeutronMsg>, Error> {
+    let AckedTx {
+        seq_num: tx_seq_num,
+        tx_hash,
+        ica_idx,
+        ..
+    } = match acked_tx(deps.storage, &request) {
+        Ok(acked_tx) => acked_tx,
+        Err(err) => {
+            return Ok(record_dead_letter(
+                deps.storage,
+                &env,
+                "timeout",
+                &request,
+                &err,
+            ))
+        }
+    };
+
+    debug!(deps, "received sudo timeout for tx: {tx_hash}");
+
+    ensure_ica_status(deps.storage, ica_idx, &TX_OUTCOME_STATUSES)?;
+
+    let mut tx_timeout_count =
+        state::ica_tx_timeout_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    tx_timeout_count += 1;
+
+    debug!(
+        deps,
+        "ICA {ica_idx} issued tx with sequence number {tx_seq_num} timed out, total timeout count: {tx_timeout_count}"
+    );
+
+    state::set_ica_tx_timeout_count(deps.storage, &ica_idx, tx_timeout_count);
+
+    update_global_totals(deps.storage, env.block.height, |totals| {
+        totals.tx_timeout += 1
+    });
+
+    let fee_refund = fee_escrow::credit_unused_fee(deps.storage, ica_idx, &tx_hash, true);
+
+    record_tx_outcome(
+        deps.storage,
+        ica_idx,
+        &tx_hash,
+        TxOutcome::Timeout,
+        env.block.height,
+    );
+
+    settle_pending_tx(deps.storage, ica_idx, &tx_hash);
+
+    consume_failed_retrieval(deps.storage, &tx_hash);
+
+    // ICA txs are sent over an ordered channel, which is closed by a timeout
+    let ica_tx_timed_out = state::tx_hash_issued_tx(deps.storage, &tx_hash)
+        .is_some_and(|issued_tx| issued_tx.kind != TxKind::Transfer);
+
+    let mut response = Response::default();
+
+    if let Some(refund_msg) = refund_failed_transfer(deps.storage, &tx_hash) {
+        response = response.add_message(refund_msg);
+    }
+
+    record_failed_tx(deps.storage, ica_idx, &tx_hash);
+
+    let channel_closed = ica_tx_timed_out && ica_status(deps.storage, ica_idx) == IcaStatus::Active;
+
+    if channel_closed {
+        set_ica_status(
+            deps.storage,
+            ica_idx,
+            IcaStatus::ChannelClosed,
+            env.block.height,
+        );
+
+        response = response.add_event(ica_event("ica_channel_closed", ica_idx));
+    }
+
+    let alert = ProbeAlert {
+        ica_idx,
+        owner: state::ica_owner(deps.storage, &ica_idx).unwrap_or_default(),
+        kind: AlertKind::TxTimeout,
+        seq_num: tx_seq_num,
+        error: None,
+        channel_closed,
+    };
+
+    let event = ica_event("tx_timeout", ica_idx)
+        .add_attribute("seq_num", tx_seq_num.to_string())
+        .add_attribute("fee_refund", coins_attr(&fee_refund));
+
+    Ok(response
+        .add_submessages(alert_hook_msgs(deps.storage, alert)?)
+        .add_event(event))
+}
+
+/// Stop serving an ICQ that is no longer registered as a KV query, emitting an alert
+pub fn invalidate_icq(
+    storage: &mut dyn Storage,
+    ica_idx: u32,
+    query_id: u64,
+) -> Response<NeutronMsg> {
+    state::set_icq_invalid(storage, &query_id, &true);
+
+    let event = ica_event("icq_invalidated", ica_idx)
+        .add_attribute("icq_id", query_id.to_string())
+        .add_attribute("reason", "query type mismatch");
+
+    Response::default().add_event(event)
+}
+
+pub fn sudo_tx_query_result(
+    deps: DepsMut<NeutronQuery>,
+    query_id: u64,
+    height: Height,
+    data: Binary,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "received tx query result for ICQ {query_id}");
+
+    let Some(ica_idx) = state::icq_ica_idx(deps.storage, &query_id) else {
+        return Ok(Response::default());
+    };
+
+    if state::icq_invalid(deps.storage, &query_id).is_some() {
+        return Ok(Response::default());
+    }
+
+    // the transfers ICQ is the only TX query, any other tracked ICQ reporting tx results has been re-registered
+    if state::ica_transfers_icq_id(deps.storage, &ica_idx) != Some(query_id) {
+        return Ok(invalidate_icq(deps.storage, ica_idx, query_id));
+    }
+
+    let ica_addr =
+        state::ica_addr(deps.storage, &ica_idx).expect("icas with a transfers icq have an address");
+
+    let sends = parse_bank_sends_to(data.as_slice(), &ica_addr)
+        .ok_or(Error::MalformedTxQueryResult { icq_id: query_id })?;
+
+    let mut deposit_count =
+        state::ica_remote_deposit_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    let mut response = Response::default();
+
+    for (sender, coins) in sends {
+        let deposit = RemoteDeposit {
+            idx: deposit_count,
+            sender,
+            coins,
+            remote_height: height.revision_height,
+        };
+
+        let event = ica_event("remote_deposit_observed", ica_idx)
+            .add_attribute("sender", deposit.sender.as_str())
+            .add_attribute("amount", coins_attr(&deposit.coins))
+            .add_attribute("remote_height", deposit.remote_height.to_string());
+
+        state::set_ica_deposit_idx_remote_deposit(
+            deps.storage,
+            &combine_u32s(ica_idx, deposit_count),
+            &deposit,
+        );
+
+        deposit_count += 1;
+
+        response = response.add_event(event);
+    }
+
+    state::set_ica_remote_deposit_count(deps.storage, &ica_idx, deposit_count);
+
+    Ok(response)
+}
+
+/// Keep the raw result of an ICQ that failed to decode, it is no longer served until the quarantine is cleared
+pub fn quarantine_icq_result(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    ica_idx: u32,
+    query_id: u64,
+    error: &prost::DecodeError,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "quarantining ICQ {query_id} result: {error}");
+
+    let raw_result = deps.as_ref().registered_query_result(query_id)?;
+
+    let values = raw_result
+        .result
+        .kv_results
+        .into_iter()
+        .map(|kv| kv.value)
+        .collect();
+
+    state::set_icq_quarantined_result(
+        deps.storage,
+        &query_id,
+        &QuarantinedIcqResult {
+            height: env.block.height,
+            values,
+            error: error.to_string(),
+        },
+    );
+
+    let event = ica_event("icq_result_quarantined", ica_idx)
+        .add_attribute("icq_id", query_id.to_string())
+        .add_attribute("error", error.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn sudo_kv_query_result(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    query_id: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    let ica_idx =
+        state::icq_ica_idx(deps.storage, &query_id).expect("the icq is associated with an ica");
+
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("the ica has an address");
+
+    debug!(
+        deps,
+        "received ICQ {query_id} update for ICA {ica_idx} with address: {ica_addr}"
+    );
+
+    if state::icq_invalid(deps.storage, &query_id).is_some() {
+        return Ok(Response::default());
+    }
+
+    if !is_registered_kv_query(deps.as_ref(), query_id)? {
+        return Ok(invalidate_icq(deps.storage, ica_idx, query_id));
+    }
+
+    // nothing to act on for delegations ICQ results, they are only served from queries
+    if state::ica_icq_id(deps.storage, &ica_idx) != Some(query_id) {
+        return Ok(Response::default());
+    }
+
+    // the unbonded funds are only reported by the balance results submitted after this one, which may predate
+    // the unbonding completion on the remote chain
+    if state::ica_unbonding_until(deps.storage, &ica_idx)
+        .is_some_and(|until| until <= env.block.time)
+    {
+        state::remove_ica_unbonding_until(deps.storage, &ica_idx);
+
+        state::set_ica_settled_height(deps.storage, &ica_idx, env.block.height);
+    }
+
+    let remote_balance = match query_balance_icq(deps.as_ref(), query_id) {
+        Err(QueryBalanceIcqError::Protobuf(err)) => {
+            return quarantine_icq_result(deps, &env, ica_idx, query_id, &err);
+        }
+
+        res => res?,
+    };
+
+    if state::icq_quarantined_result(deps.storage, &query_id).is_some()
+        || state::ica_auto_retrieve(deps.storage, &ica_idx).is_none()
+        || is_paused(deps.storage)
+        || ica_status(deps.storage, ica_idx) != IcaStatus::Active
+    {
+        return Ok(Response::default());
+    }
+
+    // wait for the outcome of in-flight txs, the balance may already be on its way back
+    if ica_txs_in_flight(deps.storage, ica_idx) > 0 {
+        return Ok(Response::default());
+    }
+
+    let Some(RemoteBalance {
+        last_submitted_result_local_height,
+        balance,
+    }) = remote_balance
+    else {
+        return Ok(Response::default());
+    };
+
+    // a result submitted before the last tx settled may still report funds that are already on their way back
+    let settled_height = state::ica_settled_height(deps.storage, &ica_idx).unwrap_or_default();
+
+    if last_submitted_result_local_height <= settled_height {
+        debug!(
+            deps,
+            "balance of ICA {ica_idx} predates its last tx settling at {settled_height}"
+        );
+
+        let event = ica_event("auto_retrieve_deferred", ica_idx)
+            .add_attribute(
+                "last_submitted_result_local_height",
+                last_submitted_result_local_height.to_string(),
+            )
+            .add_attribute("settled_height", settled_height.to_string());
+
+        return Ok(Response::default().add_event(event));
+    }
+
+    let Some(retrieve_coin) = balance.filter(|remote_balance| !remote_balance.amount.is_zero())
+    else {
+        return Ok(Response::default());
+    };
+
+    let min_ibc_fee = deps.as_ref().min_ibc_fee()?;
+
+    let owner = state::ica_owner(deps.storage, &ica_idx).expect("ica must have an owner");
+
+    if let Err(Error::InsufficientIbcTxFee { shortfall }) =
+        fee_escrow::debit(deps.storage, &owner, &min_ibc_fee)
+    {
+        debug!(
+            deps,
+            "fee balance of {owner} cannot cover auto retrieve fees"
+        );
+
+        let event = ica_event("auto_retrieve_skipped", ica_idx)
+            .add_attribute("owner", owner)
+            .add_attribute("shortfall", coins_attr(&shortfall));
+
+        return Ok(Response::default().add_event(event));
+    }
+
+    debug!(deps, "auto retrieving {retrieve_coin} from ICA {ica_idx}");
+
+    let event = ica_event("auto_retrieve_requested", ica_idx)
+        .add_attribute("owner", owner.as_str())
+        .add_attribute("amount", retrieve_coin.to_string());
+
+    let retrieve_msg = issue_retrieve_tx(
+        deps,
+        &env,
+        ica_idx,
+        retrieve_coin,
+        min_ibc_fee,
+        &owner,
+        None,
+    )?;
+
+    Ok(Response::default()
+        .add_submessage(retrieve_msg)
+        .add_event(event))
+}
+
+#[entry_point]
+pub fn sudo(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "handling sudo msg");
+
+    match msg {
+        SudoMsg::OpenAck {
+            port_id,
+            channel_id,
+            counterparty_channel_id,
+            counterparty_version,
+        } => {
+            let channel = IcaChannel {
+                port_id,
+                channel_id,
+                counterparty_channel_id,
+            };
+
+            sudo_open_ack(deps, &env, channel, counterparty_version)
+        }
+
+        SudoMsg::Response { request, data } => sudo_response(deps, env, request, data),
+
+        SudoMsg::Error { request, details } => sudo_error(deps, env, request, details),
+
+        SudoMsg::Timeout { request } => sudo_timeout(deps, env, request),
+
+        SudoMsg::KVQueryResult { query_id } => sudo_kv_query_result(deps, env, query_id),
+
+        SudoMsg::TxQueryResult {
+            query_id,
+            height,
+            data,
+        } => sudo_tx_query_result(deps, query_id, height, data),
+    }
+}
+
+pub fn reply_register_icq(deps: DepsMut, reply: Reply, ica_idx: u32) -> Result<Response, Error> {
+    debug!(
+        deps,
+        "received icq registation reply for ICA index {ica_idx}",
+    );
+
+    let icq_id = common::parse_icq_registration_reply(reply)?;
+
+    debug!(deps, "ICA {ica_idx} balance ICQ ID: {icq_id}",);
+
+    state::set_ica_icq_id(deps.storage, &ica_idx, icq_id);
+
+    state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
+
+    let event = ica_event("icq_registered", ica_idx).add_attribute("icq_id", icq_id.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn reply_register_delegations_icq(
+    deps: DepsMut,
+    reply: Reply,
+    ica_idx: u32,
+) -> Result<Response, Error> {
+    debug!(
+        deps,
+        "received delegations icq registation reply for ICA index {ica_idx}",
+    );
+
+    let icq_id = common::parse_icq_registration_reply(reply)?;
+
+    state::set_ica_delegations_icq_id(deps.storage, &ica_idx, icq_id);
+
+    state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
+
+    let event = ica_event("delegations_icq_registered", ica_idx)
+        .add_attribute("icq_id", icq_id.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn reply_register_transfers_icq(
+    deps: DepsMut,
+    reply: Reply,
+    ica_idx: u32,
+) -> Result<Response, Error> {
+    debug!(
+        deps,
+        "received transfers icq registation reply for ICA index {ica_idx}",
+    );
+
+    let icq_id = common::parse_icq_registration_reply(reply)?;
+
+    state::set_ica_transfers_icq_id(deps.storage, &ica_idx, icq_id);
+
+    state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
+
+    let event =
+        ica_event("transfers_icq_registered", ica_idx).add_attribute("icq_id", icq_id.to_string());
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Track the tx issued by the ICA at `ica_idx`, `coin` being the amount it moves or stakes
+pub fn reply_issue_tx(
+    deps: DepsMut,
+    env: &Env,
+    reply: Reply,
+    kind: TxKind,
+    ica_idx: u32,
+    coin: Option<Coin>,
+) -> Result<Response, Error> {
+    debug!(deps, "received issue tx reply for ICA index {ica_idx}");
+
+    let (tx_seq_num, channel) = common::parse_issue_tx_reply(reply)?;
+
+    let tx_hash = hash!(TX_HASH_DOMAIN; tx_seq_num.to_be_bytes(), channel);
+
+    state::set_tx_hash_ica_idx(deps.storage, &tx_hash, ica_idx);
+
+    state::set_tx_hash_scheme(deps.storage, &tx_hash, &HASH_SCHEME.version);
+
+    stamp_hash(
+        deps.storage,
+        state::HashKind::Tx,
+        &tx_hash,
+        env.block.height,
+    );
+
+    let event_ty = match kind {
+        TxKind::Transfer => "transfer_issued",
+        TxKind::Retrieve => "funds_retrieve_issued",
+        TxKind::Delegate => "delegate_issued",
+        TxKind::Undelegate => "undelegate_issued",
+        TxKind::ClaimRewards => "claim_rewards_issued",
+        TxKind::Custom => "custom_tx_issued",
+    };
+
+    let mut event = ica_event(event_ty, ica_idx)
+        .add_attribute("seq_num", tx_seq_num.to_string())
+        .add_attribute("channel", channel.as_str());
+
+    if let Some(owner) = state::ica_owner(deps.storage, &ica_idx) {
+        if kind == TxKind::Transfer {
+            state::set_tx_hash_transfer_sender(deps.storage, &tx_hash, &owner);
+        }
+
+        event = event.add_attribute("owner", owner);
+    }
+
+    let issued_tx = pop_pending_tx(deps.storage, ica_idx);
+
+    let (timeout_height, timeout_timestamp) = if kind == TxKind::Transfer {
+        let timeout_height = state::timeout_height(deps.storage).unwrap_or(DEFAULT_TIMEOUT_HEIGHT);
+
+        (Some(timeout_height), None)
+    } else {
+        let timeout_seconds = match issued_tx.as_ref().and_then(|tx| tx.custom.as_ref()) {
+            Some(custom) => custom.timeout,
+            None => state::timeout_seconds(deps.storage).unwrap_or(DEFAULT_TIMEOUT_SECONDS),
+        };
+
+        (None, Some(env.block.time.plus_seconds(timeout_seconds)))
+    };
+
+    track_pending_tx(
+        deps.storage,
+        ica_idx,
+        PendingTx {
+            tx_hash: tx_hash.clone(),
+            kind,
+            coin: coin.clone(),
+            issued_height: env.block.height,
+            timeout_height,
+            timeout_timestamp,
+        },
+    );
+
+    if let Some(coin) = &coin {
+        event = event.add_attribute("amount", coin.to_string());
+    }
+
+    if let Some(issued_tx) = issued_tx {
+        append_tx_history(
+            deps.storage,
+            ica_idx,
+            &tx_hash,
+            &issued_tx,
+            tx_seq_num,
+            env.block.height,
+        );
+
+        state::set_tx_hash_issued_tx(deps.storage, &tx_hash, &issued_tx);
+    }
+
+    state::set_ica_tx_kind_seq_num(
+        deps.storage,
+        &combine_u32s(ica_idx, tx_kind_reply_kind(kind)),
+        tx_seq_num,
+    );
+
+    let mut tx_issue_count = state::ica_tx_issued_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    tx_issue_count += 1;
+
+    debug!(
+        deps,
+        "ICA {ica_idx} issued tx {tx_issue_count} with sequence number {tx_seq_num}"
+    );
+
+    state::set_ica_tx_issued_count(deps.storage, &ica_idx, tx_issue_count);
+
+    update_global_totals(deps.storage, env.block.height, |totals| {
+        totals.tx_issued += 1
+    });
+
+    // replaces the receipt of the execute issuing the tx, the last tx issued wins if there are several
+    let receipt = ExecuteReceipt {
+        action: "issue_tx".to_owned(),
+        ica_idx: Some(ica_idx),
+        seq_num: Some(tx_seq_num),
+    };
+
+    Ok(receipt::set_receipt(
+        Response::default().add_event(event),
+        &receipt,
+    )?)
+}
+
+/// Refund the tokens & fees of an IBC transfer whose submission failed before a packet was created
+pub fn reply_transfer_failed(deps: DepsMut, reply: Reply, ica_idx: u32) -> Result<Response, Error> {
+    debug!(
+        deps,
+        "received failed transfer reply for ICA index {ica_idx}"
+    );
+
+    let error = reply.result.unwrap_err();
+
+    let mut event = ica_event("transfer_failed", ica_idx).add_attribute("error", error);
+
+    let Some(issued_tx) = pop_pending_tx(deps.storage, ica_idx) else {
+        return Ok(Response::default().add_event(event));
+    };
+
+    let owner = state::ica_owner(deps.storage, &ica_idx).expect("active icas have an owner");
+
+    // the coin & fee were paid by the sender of the transfer, who may be an operator of the owner
+    let payer = issued_tx.payer.unwrap_or_else(|| owner.clone());
+
+    let mut refund = vec![issued_tx.coin];
+
+    if let Some(fee) = &issued_tx.fee {
+        fee_escrow::add_coins(&mut refund, &fee_escrow::ibc_fee_coins(fee));
+    }
+
+    event = event
+        .add_attribute("owner", owner)
+        .add_attribute("payer", payer.as_str())
+        .add_attribute("refund", coins_attr(&refund));
+
+    let refund_msg = BankMsg::Send {
+        to_address: payer,
+        amount: refund,
+    };
+
+    Ok(Response::default().add_message(refund_msg).add_event(event))
+}
+
+pub fn reply_remove_icq(deps: DepsMut, ica_idx: u32) -> Result<Response, Error> {
+    debug!(deps, "received icq removal reply for ICA index {ica_idx}");
+
+    let state::IcqRefund { owner, deposit } = state::ica_icq_refund(deps.storage, &ica_idx)
+        .expect("a removed icq always has a pending refund");
+
+    state::remove_ica_icq_refund(deps.storage, &ica_idx);
+
+    let event = ica_event("icq_deposit_refunded", ica_idx)
+        .add_attribute("owner", owner.as_str())
+        .add_attribute("amount", coins_attr(&deposit));
+
+    let refund_msg = BankMsg::Send {
+        to_address: owner,
+        amount: deposit,
+    };
+
+    Ok(Response::default().add_message(refund_msg).add_event(event))
+}
+
+/// Alerts are best effort, a subscriber failing to handle one only leaves a trace
+pub fn reply_alert_hook_failed(
+    deps: DepsMut,
+    reply: Reply,
+    ica_idx: u32,
+) -> Result<Response, Error> {
+    debug!(
+        deps,
+        "received failed alert hook reply for ICA index {ica_idx}"
+    );
+
+    let error = reply.result.unwrap_err();
+
+    let event = ica_event("alert_hook_failed", ica_idx).add_attribute("error", error);
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Report the failed retrieval of claimed rewards, which stay on the ICA until retrieved by the owner
+pub fn reply_retrieve_claimed_rewards_failed(
+    deps: DepsMut,
+    reply: Reply,
+    ica_idx: u32,
+    claimed: Option<Coin>,
+) -> Result<Response, Error> {
+    debug!(
+        deps,
+        "received failed claimed rewards retrieval reply for ICA index {ica_idx}"
+    );
+
+    let error = reply.result.unwrap_err();
+
+    let claimed = claimed.expect("claimed rewards retrievals record the claimed coin");
+
+    let event = ica_event("rewards_retrieve_failed", ica_idx)
+        .add_attribute("amount", claimed.to_string())
+        .add_attribute("error", error);
+
+    Ok(Response::default().add_event(event))
+}
+
+/// Record the context of a submessage, returning the id its reply is dispatched from
+pub fn save_pending_reply(
+    storage: &mut dyn Storage,
+    kind: state::ReplyKind,
+    ica_idx: u32,
+    coin: Option<Coin>,
+) -> u64 {
+    let pending_reply = state::PendingReply {
+        kind,
+        ica_idx,
+        coin,
+    };
+
+    common::save_reply_payload(storage, &pending_reply)
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, Error> {
+    let state::PendingReply {
+        kind,
+        ica_idx,
+        coin,
+    } = common::take_reply_payload(deps.storage, reply.id)
+        .ok_or(Error::UnexpectedReply { id: reply.id })?;
+
+    debug!(
+        deps,
+        "received reply {} of kind {kind:?} for ICA {ica_idx}", reply.id
+    );
+
+    match kind {
+        state::ReplyKind::RegisterIcq => reply_register_icq(deps, reply, ica_idx),
+
+        state::ReplyKind::IssueTx(TxKind::Transfer) if reply.result.is_err() => {
+            reply_transfer_failed(deps, reply, ica_idx)
+        }
+
+        state::ReplyKind::IssueTx(tx_kind) => {
+            reply_issue_tx(deps, &env, reply, tx_kind, ica_idx, coin)
+        }
+
+        state::ReplyKind::RegisterDelegationsIcq => {
+            reply_register_delegations_icq(deps, reply, ica_idx)
+        }
+
+        state::ReplyKind::RegisterTransfersIcq => {
+            reply_register_transfers_icq(deps, reply, ica_idx)
+        }
+
+        state::ReplyKind::RemoveIcq => reply_remove_icq(deps, ica_idx),
+
+        state::ReplyKind::AlertHook if reply.result.is_ok() => Ok(Response::default()),
+
+        state::ReplyKind::AlertHook => reply_alert_hook_failed(deps, reply, ica_idx),
+
+        state::ReplyKind::RetrieveClaimedRewards if reply.result.is_ok() => Ok(Response::default()),
+
+        state::ReplyKind::RetrieveClaimedRewards => {
+            reply_retrieve_claimed_rewards_failed(deps, reply, ica_idx, coin)
+        }
+    }
+}
+
+pub fn owner_is_valid_addr(deps: Deps<impl CustomQuery>, owner: &str) -> Result<bool, Error> {
+    deps.api.addr_validate(owner)?;
+
+    Ok(true)
+}
+
+pub fn query_ica_metadata(
+    deps: Deps<NeutronQuery>,
+    owner: String,
+) -> Result<IcaMetadataResponse, Error> {
+    owner_is_valid_addr(deps, &owner)?;
+
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaMetadataResponse::default());
+    };
+
+    Ok(IcaMetadataResponse {
+        metadata: Some(ica_metadata(deps.storage, ica_idx)),
+    })
+}
+
+#[must_use]
+pub fn ica_metadata(storage: &dyn Storage, ica_idx: u32) -> IcaMetadata {
+    let address = state::ica_addr(storage, &ica_idx);
+
+    let balance_icq_id = state::ica_icq_id(storage, &ica_idx);
+
+    let delegations_icq_id = state::ica_delegations_icq_id(storage, &ica_idx);
+
+    let transfers_icq_id = state::ica_transfers_icq_id(storage, &ica_idx);
+
+    let auto_retrieve = state::ica_auto_retrieve(storage, &ica_idx).unwrap_or_default();
+
+    let status = ica_status(storage, ica_idx);
+
+    let last_status_change_height = state::ica_status_height(storage, &ica_idx).unwrap_or_default();
+
+    let label = state::ica_label(storage, &ica_idx);
+
+    let note = state::ica_note(storage, &ica_idx);
+
+    let channel = state::ica_channel(storage, &ica_idx);
+
+    IcaMetadata {
+        ica_idx,
+        address,
+        balance_icq_id,
+        delegations_icq_id,
+        transfers_icq_id,
+        auto_retrieve,
+        status,
+        last_status_change_height,
+        label,
+        note,
+        channel,
+    }
+}
+
+pub fn query_last_ica_balance(
+    deps: Deps<NeutronQuery>,
+    owner: String,
+) -> Result<IcaLastBalanceResponse, Error> {
+    owner_is_valid_addr(deps, &owner)?;
+
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaLastBalanceResponse::default());
+    };
+
+    ica_last_balance(deps, ica_idx)
+}
+
+pub fn ica_last_balance(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+) -> Result<IcaLastBalanceResponse, Error> {
+    let Some(icq_id) = state::ica_icq_id(deps.storage, &ica_idx) else {
+        return Ok(IcaLastBalanceResponse::default());
+    };
+
+    debug!(deps, "querying balance ICQ {icq_id} for ICA {ica_idx}");
+
+    let decode_failed = IcaLastBalanceResponse {
+        decode_failed: true,
+        ..Default::default()
+    };
+
+    let icq_invalid = IcaLastBalanceResponse {
+        icq_invalid: true,
+        ..Default::default()
+    };
+
+    if state::icq_invalid(deps.storage, &icq_id).is_some() {
+        return Ok(icq_invalid);
+    }
+
+    if state::icq_quarantined_result(deps.storage, &icq_id).is_some() {
+        return Ok(decode_failed);
+    }
+
+    let remote_balance = match query_balance_icq(deps, icq_id) {
+        Err(QueryBalanceIcqError::Protobuf(_)) => return Ok(decode_failed),
+        Err(err) if err.is_query_type_mismatch() => return Ok(icq_invalid),
+        res => res?,
+    };
+
+    let Some(RemoteBalance {
+        last_submitted_result_local_height,
+        balance,
+    }) = remote_balance
+    else {
+        return Ok(IcaLastBalanceResponse::default());
+    };
+
+    let address =
+        state::ica_addr(deps.storage, &ica_idx).expect("a registered ica has an address set");
+
+    let last_balance = IcaLastBalance {
+        balance,
+        address,
+        last_submitted_result_local_height,
+    };
+
+    Ok(IcaLastBalanceResponse {
+        last_balance: Some(last_balance),
+        ..Default::default()
+    })
+}
+
+pub fn query_last_ica_delegation(
+    deps: Deps<NeutronQuery>,
+    owner: String,
+) -> Result<IcaLastDelegationResponse, Error> {
+    owner_is_valid_addr(deps, &owner)?;
+
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaLastDelegationResponse::default());
+    };
+
+    let Some(icq_id) = state::ica_delegations_icq_id(deps.storage, &ica_idx) else {
+        return Ok(IcaLastDelegationResponse::default());
+    };
+
+    debug!(deps, "querying delegations ICQ {icq_id} for ICA {ica_idx}");
+
+    let icq_invalid = IcaLastDelegationResponse {
+        last_delegation: None,
+        icq_invalid: true,
+    };
+
+    if state::icq_invalid(deps.storage, &icq_id).is_some() {
+        return Ok(icq_invalid);
+    }
+
+    let registered_query = match updated_registered_kv_query(deps, icq_id) {
+        Err(err) if is_query_type_mismatch(&err) => return Ok(icq_invalid),
+        res => res?,
+    };
+
+    let Some(registered_query) = registered_query else {
+        return Ok(IcaLastDelegationResponse::default());
+    };
+
+    let delegations: Delegations = deps.kv_result(icq_id)?;
+
+    let validator = state::ica_delegations_validator(deps.storage, &ica_idx);
+
+    // the ICQ only queries the validator the ICA delegates to, the result is submitted by the relayer though
+    let delegation = delegations
+        .delegations
+        .into_iter()
+        .find(|delegation| validator.as_ref() == Some(&delegation.validator));
+
+    let last_submitted_result_local_height = registered_query.last_submitted_result_local_height;
+
+    Ok(IcaLastDelegationResponse {
+        last_delegation: Some(IcaLastDelegation {
+            delegation,
+            last_submitted_result_local_height,
+        }),
+        icq_invalid: false,
+    })
+}
+
+pub fn query_ica_tx_status(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+) -> Result<IcaTxStatusResponse, Error> {
+    owner_is_valid_addr(deps, &owner)?;
+
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaTxStatusResponse::default());
+    };
+
+    Ok(IcaTxStatusResponse {
+        status: Some(ica_tx_status(deps.storage, ica_idx)),
+    })
+}
+
+#[must_use]
+pub fn ica_tx_status(storage: &dyn Storage, ica_idx: u32) -> IcaTxStatus {
+    let issued = state::ica_tx_issued_count(storage, &ica_idx).unwrap_or_default();
+
+    let success = state::ica_tx_success_count(storage, &ica_idx).unwrap_or_default();
+
+    let error = state::ica_tx_error_count(storage, &ica_idx).unwrap_or_default();
+
+    let timeout = state::ica_tx_timeout_count(storage, &ica_idx).unwrap_or_default();
+
+    let roundtrips = state::ica_round_trip_count(storage, &ica_idx).unwrap_or_default();
+
+    let last_transfer_seq_num =
+        state::ica_tx_kind_seq_num(storage, &combine_u32s(ica_idx, TRANSFER_TX_REPLY_KIND));
+
+    let last_retrieve_seq_num =
+        state::ica_tx_kind_seq_num(storage, &combine_u32s(ica_idx, RETRIEVE_TX_REPLY_KIND));
+
+    let last_claim_rewards_seq_num =
+        state::ica_tx_kind_seq_num(storage, &combine_u32s(ica_idx, CLAIM_REWARDS_TX_REPLY_KIND));
+
+    let claimed_rewards = state::ica_claimed_rewards(storage, &ica_idx).unwrap_or_default();
+
+    IcaTxStatus {
+        issued,
+        success,
+        error,
+        timeout,
+        roundtrips,
+        last_transfer_seq_num,
+        last_retrieve_seq_num,
+        last_claim_rewards_seq_num,
+        claimed_rewards,
+    }
+}
+
+pub fn query_ica_tx_error(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+    error_idx: u32,
+) -> Result<IcaTxErrorResponse, Error> {
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaTxErrorResponse::default());
+    };
+
+    let error_key = combine_u32s(ica_idx, error_idx);
+
+    let error = state::ica_err_idx_msg(deps.storage, &error_key);
+
+    Ok(IcaTxErrorResponse { error })
+}
+
+pub fn query_operators(deps: Deps<impl CustomQuery>, owner: String) -> OperatorsResponse {
+    OperatorsResponse {
+        operators: state::owner_operators(deps.storage, &owner).unwrap_or_default(),
+    }
+}
+
+pub fn query_address_book(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<AddressBookResponse, Error> {
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(AddressBookResponse::default());
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+    let entries = state::ica_address_book(deps.storage, &ica_idx)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|e| {
+            start_after
+                .as_ref()
+                .map_or(true, |start| &e.address > start)
+        })
+        .take(limit)
+        .collect();
+
+    Ok(AddressBookResponse { entries })
+}
+
+pub fn query_icq_info(deps: Deps<NeutronQuery>, owner: String) -> Result<IcqInfoResponse, Error> {
+    owner_is_valid_addr(deps, &owner)?;
+
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcqInfoResponse::default());
+    };
+
+    let registered_query = |icq_id: Option<u64>| -> Result<_, Error> {
+        let Some(icq_id) = icq_id else {
+            return Ok(None);
+        };
+
+        Ok(Some(deps.registered_query(icq_id)?))
+    };
+
+    Ok(IcqInfoResponse {
+        balance: registered_query(state::ica_icq_id(deps.storage, &ica_idx))?,
+        delegations: registered_query(state::ica_delegations_icq_id(deps.storage, &ica_idx))?,
+        transfers: registered_query(state::ica_transfers_icq_id(deps.storage, &ica_idx))?,
+    })
+}
+
+pub fn query_ica_remote_deposits(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<IcaRemoteDepositsResponse, Error> {
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaRemoteDepositsResponse::default());
+    };
+
+    let deposit_count = state::ica_remote_deposit_count(deps.storage, &ica_idx).unwrap_or_default();
+
+    let start = start_after
+        .map_or(0, |idx| idx.saturating_add(1))
+        .min(deposit_count);
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    let deposits = (start..start.saturating_add(limit).min(deposit_count))
+        .filter_map(|idx| {
+            state::ica_deposit_idx_remote_deposit(deps.storage, &combine_u32s(ica_idx, idx))
+        })
+        .collect();
+
+    Ok(IcaRemoteDepositsResponse { deposits })
+}
+
+pub fn query_pending_txs(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+) -> Result<PendingTxsResponse, Error> {
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(PendingTxsResponse::default());
+    };
+
+    let pending_txs = state::ica_pending_tx_hashes(deps.storage, &ica_idx)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tx_hash| state::tx_hash_pending_tx(deps.storage, tx_hash))
+        .collect();
+
+    Ok(PendingTxsResponse { pending_txs })
+}
+
+pub fn query_ica_tx_history(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<IcaTxHistoryResponse, Error> {
+    let Some(ica_idx) = state::owner_ica_idx(deps.storage, &owner) else {
+        return Ok(IcaTxHistoryResponse::default());
+    };
+
+    let tx_history_len = state::ica_tx_history_len(deps.storage, &ica_idx).unwrap_or_default();
+
+    let start = start_after
+        .map_or(0, |idx| idx.saturating_add(1))
+        .min(tx_history_len);
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    let history = (start..start.saturating_add(limit).min(tx_history_len))
+        .filter_map(|idx| {
+            state::ica_history_idx_tx_entry(deps.storage, &combine_u32s(ica_idx, idx))
+        })
+        .collect();
+
+    Ok(IcaTxHistoryResponse { history })
+}
+
+/// Returns the range of ICA indices covered by a page
+pub fn page_range(
+    deps: Deps<impl CustomQuery>,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> std::ops::Range<u32> {
+    let ica_count = state::ica_count(deps.storage).unwrap_or_default();
+
+    let start = start_after
+        .map_or(0, |idx| idx.saturating_add(1))
+        .min(ica_count);
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    start..start.saturating_add(limit).min(ica_count)
+}
+
+#[must_use]
+pub fn timeouts(storage: &dyn Storage) -> TimeoutsResponse {
+    TimeoutsResponse {
+        timeout_seconds: state::timeout_seconds(storage).unwrap_or(DEFAULT_TIMEOUT_SECONDS),
+        timeout_height: state::timeout_height(storage).unwrap_or(DEFAULT_TIMEOUT_HEIGHT),
+        timeout_revision_number: state::timeout_revision_number(storage)
+            .unwrap_or(DEFAULT_TIMEOUT_REVISION_NUMBER),
+    }
+}
+
+/// The transfer limits of the transfer denoms which are bounded
+#[must_use]
+pub fn transfer_limits(storage: &dyn Storage) -> TransferLimitsResponse {
+    let limits = transfer_denoms(storage)
+        .iter()
+        .filter_map(|denom| state::denom_transfer_limits(storage, denom))
+        .collect();
+
+    TransferLimitsResponse { limits }
+}
+
+pub fn query_estimate_roundtrip_fee(
+    deps: Deps<NeutronQuery>,
+    coin: Coin,
+    gas_price: Option<Decimal>,
+) -> Result<RoundtripFeeEstimateResponse, Error> {
+    if !transfer_denoms(deps.storage).contains(&coin.denom) {
+        return Err(Error::DenomNotTransferable { denom: coin.denom });
+    }
+
+    let leg_fee = deps.min_ibc_fee()?;
+
+    let mut required_fee_balance = vec![];
+
+    let mut max_cost = vec![];
+
+    // the transfer & the retrieval both pay the min IBC fee
+    for _ in 0..2 {
+        fee_escrow::add_coins(
+            &mut required_fee_balance,
+            &fee_escrow::ibc_fee_coins(&leg_fee),
+        );
+
+        fee_escrow::add_coins(&mut max_cost, &fee_escrow::max_ibc_fee_cost(&leg_fee));
+    }
+
+    let gas_price = gas_price.unwrap_or(DEFAULT_ESTIMATE_GAS_PRICE);
+
+    // relayers pay the gas of submitting the ICQ results the roundtrip waits on, rounded up to a whole untrn
+    let overhead = Decimal::from_ratio(ROUNDTRIP_ICQ_UPDATES * ICQ_RESULT_SUBMISSION_GAS, 1u128)
+        .checked_mul(gas_price)
+        .map_err(cosmwasm_std::StdError::from)?
+        .ceil();
+
+    let icq_relayer_overhead = Coin::new(
+        (overhead.atomics() / Decimal::one().atomics()).u128(),
+        IBC_FEE_DENOM,
+    );
+
+    let total_untrn = max_cost
+        .iter()
+        .filter(|c| c.denom == IBC_FEE_DENOM)
+        .fold(icq_relayer_overhead.amount, |total, c| total + c.amount);
+
+    Ok(RoundtripFeeEstimateResponse {
+        coin,
+        leg_fee,
+        required_fee_balance,
+        max_cost,
+        icq_relayer_overhead,
+        total_untrn,
+        icq_deposit: required_icq_deposit(deps)?,
+        icq_update_period: state::icq_update_period(deps.storage),
+    })
+}
+
+pub fn query_export(
+    deps: Deps<NeutronQuery>,
+    cursor: Option<u32>,
+    limit: Option<u32>,
+) -> Result<ExportResponse, Error> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+    let listed = state::ica_listed_owner_range(deps.storage, cursor.as_ref(), limit);
+
+    let next_cursor = (listed.len() == limit)
+        .then(|| listed.last().map(|(ica_idx, _)| *ica_idx))
+        .flatten();
+
+    let icas = listed
+        .into_iter()
+        .map(|(ica_idx, owner)| {
+            Ok(IcaExport {
+                metadata: ica_metadata(deps.storage, ica_idx),
+                tx_status: ica_tx_status(deps.storage, ica_idx),
+                last_balance: ica_last_balance(deps, ica_idx)?,
+                round_trip_volume: state::ica_round_trip_volume(deps.storage, &ica_idx)
+                    .unwrap_or_default(),
+                fee_balance: state::owner_fee_balance(deps.storage, &owner).unwrap_or_default(),
+                owner,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(ExportResponse {
+        icas,
+        next_cursor,
+        timeouts: timeouts(deps.storage),
+        transfer_denoms: transfer_denoms(deps.storage),
+        transfer_limits: transfer_limits(deps.storage),
+        paused: is_paused(deps.storage),
+    })
+}
+
+pub fn query_owners(
+    deps: Deps<impl CustomQuery>,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<OwnersResponse, Error> {
+    let owners = page_range(deps, start_after, limit)
+        .filter_map(|ica_idx| {
+            state::ica_owner(deps.storage, &ica_idx).map(|owner| Owner { owner, ica_idx })
+        })
+        .collect();
+
+    Ok(OwnersResponse { owners })
+}
+
+pub fn query_list_icas(
+    deps: Deps<impl CustomQuery>,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<ListIcasResponse, Error> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+    let icas = state::ica_listed_owner_range(deps.storage, start_after.as_ref(), limit)
+        .into_iter()
+        .map(|(ica_idx, owner)| IcaListEntry {
+            ica_idx,
+            owner,
+            address: state::ica_addr(deps.storage, &ica_idx),
+            icq_id: state::ica_icq_id(deps.storage, &ica_idx),
+            label: state::ica_label(deps.storage, &ica_idx),
+        })
+        .collect();
+
+    Ok(ListIcasResponse { icas })
+}
+
+#[entry_point]
+pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
+    let res = match msg {
+        QueryMsg::IcaMetadata { owner } => {
+            let ica_metadata = query_ica_metadata(deps, owner)?;
+
+            to_binary(&ica_metadata)?
+        }
+
+        QueryMsg::IcaLastBalance { owner } => {
+            let last_ica_balance = query_last_ica_balance(deps, owner)?;
+
+            to_binary(&last_ica_balance)?
+        }
+
+        QueryMsg::IcaLastDelegation { owner } => {
+            let last_ica_delegation = query_last_ica_delegation(deps, owner)?;
+
+            to_binary(&last_ica_delegation)?
+        }
+
+        QueryMsg::IcaTxStatus { owner } => {
+            let ica_tx_status = query_ica_tx_status(deps, owner)?;
+
+            to_binary(&ica_tx_status)?
+        }
+
+        QueryMsg::IcaTxError { owner, error_idx } => {
+            let ica_tx_status = query_ica_tx_error(deps, owner, error_idx)?;
+
+            to_binary(&ica_tx_status)?
+        }
+
+        QueryMsg::AddressBook {
+            owner,
+            start_after,
+            limit,
+        } => {
+            let address_book = query_address_book(deps, owner, start_after, limit)?;
+
+            to_binary(&address_book)?
+        }
+
+        QueryMsg::Operators { owner } => to_binary(&query_operators(deps, owner))?,
+
+        QueryMsg::IcqInfo { owner } => {
+            let icq_info = query_icq_info(deps, owner)?;
+
+            to_binary(&icq_info)?
+        }
+
+        QueryMsg::IcaRemoteDeposits {
+            owner,
+            start_after,
+            limit,
+        } => {
+            let ica_remote_deposits = query_ica_remote_deposits(deps, owner, start_after, limit)?;
+
+            to_binary(&ica_remote_deposits)?
+        }
+
+        QueryMsg::IcaTxHistory {
+            owner,
+            start_after,
+            limit,
+        } => {
+            let ica_tx_history = query_ica_tx_history(deps, owner, start_after, limit)?;
+
+            to_binary(&ica_tx_history)?
+        }
+
+        QueryMsg::PendingTxs { owner } => {
+            let pending_txs = query_pending_txs(deps, owner)?;
+
+            to_binary(&pending_txs)?
+        }
+
+        QueryMsg::Owners { start_after, limit } => {
+            let owners = query_owners(deps, start_after, limit)?;
+
+            to_binary(&owners)?
+        }
+
+        QueryMsg::Leaderboard { limit } => {
+            let limit = limit.map_or(LEADERBOARD_SIZE, |l| l as usize);
+
+            let entries = state::leaderboard(deps.storage)
+                .unwrap_or_default()
+                .into_iter()
+                .take(limit)
+                .collect();
+
+            to_binary(&LeaderboardResponse { entries })?
+        }
+
+        QueryMsg::ListIcas { start_after, limit } => {
+            let icas = query_list_icas(deps, start_after, limit)?;
+
+            to_binary(&icas)?
+        }
+
+        QueryMsg::Timeouts {} => to_binary(&timeouts(deps.storage))?,
+
+        QueryMsg::Export { cursor, limit } => {
+            let export = query_export(deps, cursor, limit)?;
+
+            to_binary(&export)?
+        }
+
+        QueryMsg::TransferLimits {} => to_binary(&transfer_limits(deps.storage))?,
+
+        QueryMsg::RetrievalRoute { name } => {
+            to_binary(&state::retrieval_route(deps.storage, &name))?
+        }
+
+        QueryMsg::HashMappings {} => to_binary(&hash_mappings(deps.storage))?,
+
+        QueryMsg::MemoLimits {} => {
+            let limits = memo_limits(deps.storage);
+
+            to_binary(&MemoLimitsResponse {
+                max_memo_len: u32::try_from(limits.max_memo_len)
+                    .expect("bounded by ibc-go's limit"),
+                max_hook_memo_len: u32::try_from(limits.max_hook_memo_len)
+                    .expect("bounded by ibc-go's limit"),
+            })?
+        }
+
+        QueryMsg::TransferDenoms {} => {
+            let denoms = transfer_denoms(deps.storage);
+
+            to_binary(&TransferDenomsResponse { denoms })?
+        }
+
+        QueryMsg::GlobalStats {} => to_binary(&global_stats(deps.storage))?,
+
+        QueryMsg::IndexerCheckpoints { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+            let checkpoints =
+                state::indexer_checkpoint_range(deps.storage, start_after.as_deref(), limit)
+                    .into_iter()
+                    .map(|(_, checkpoint)| checkpoint)
+                    .collect();
+
+            to_binary(&IndexerCheckpointsResponse { checkpoints })?
+        }
+
+        QueryMsg::DeadLetters { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+            let dead_letters =
+                state::dead_letter_packet_range(deps.storage, start_after.as_ref(), limit)
+                    .into_iter()
+                    .map(|(_, dead_letter)| dead_letter)
+                    .collect();
+
+            to_binary(&DeadLettersResponse { dead_letters })?
+        }
+
+        QueryMsg::Admin {} => {
+            let admin = state::contract_owner(deps.storage).unwrap_or_default();
+
+            let pending_admin = state::pending_contract_owner(deps.storage);
+
+            to_binary(&AdminResponse {
+                admin,
+                pending_admin,
+            })?
+        }
+
+        QueryMsg::Paused {} => to_binary(&PausedResponse {
+            paused: is_paused(deps.storage),
+        })?,
+
+        QueryMsg::StateCommitment {} => to_binary(&StateCommitmentResponse {
+            commitment: state_commitments_enabled(deps.storage)
+                .then(|| state::state_commitment(deps.storage))
+                .flatten(),
+            stats: global_stats(deps.storage),
+            pruned_activity_digest: state::pruned_activity_digest(deps.storage).unwrap_or_default(),
+        })?,
+
+        QueryMsg::Activity { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+            let entries = state::activity_entry_range(deps.storage, start_after.as_ref(), limit)
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect();
+
+            to_binary(&ActivityResponse { entries })?
+        }
+
+        QueryMsg::SupportedFeatures {} => to_binary(&SupportedFeaturesResponse {
+            features: SUPPORTED_FEATURES.iter().map(|&f| f.to_owned()).collect(),
+        })?,
+
+        QueryMsg::ErrorCodes {} => to_binary(&ErrorCodesResponse {
+            codes: Error::catalogue(),
+        })?,
+
+        QueryMsg::AlertSubscribers {} => to_binary(&AlertSubscribersResponse {
+            subscribers: state::alert_subscribers(deps.storage).unwrap_or_default(),
+            approved: state::approved_alert_subscribers(deps.storage).unwrap_or_default(),
+        })?,
+
+        QueryMsg::HookDeadLetters { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+
+            let dead_letters =
+                state::dead_letter_hook_range(deps.storage, start_after.as_ref(), limit)
+                    .into_iter()
+                    .map(|(_, dead_letter)| dead_letter)
+                    .collect();
+
+            to_binary(&HookDeadLettersResponse { dead_letters })?
+        }
+
+        QueryMsg::QuarantinedIcqResult { icq_id } => {
+            let result = state::icq_quarantined_result(deps.storage, &icq_id);
+
+            to_binary(&QuarantinedIcqResultResponse { result })?
+        }
+
+        QueryMsg::FeeBalance { owner } => {
+            let fee_balance = fee_escrow::query_fee_balance(deps, owner)?;
+
+            to_binary(&fee_balance)?
+        }
+
+        QueryMsg::FeeHistory {} => {
+            let history = state::fee_history(deps.storage).unwrap_or_default();
+
+            to_binary(&FeeHistoryResponse { history })?
+        }
+
+        QueryMsg::EstimateRoundtripFee { coin, gas_price } => {
+            let estimate = query_estimate_roundtrip_fee(deps, coin, gas_price)?;
+
+            to_binary(&estimate)?
+        }
+    };
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use common::validate::{ValidationError, MAX_LABEL_LEN, MAX_NOTE_LEN};
+    use cosmwasm_std::{
+        from_binary,
+        testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+        ContractInfoResponse, ContractResult, CosmosMsg, Empty, OwnedDeps, Querier, QuerierResult,
+        QuerierWrapper, QueryRequest, SystemError, SystemResult, WasmQuery,
+    };
+    use test_consts::{CONNECTION_ID, HOST_IBC_DENOM, REMOTE_DENOM, TRANSFER_CHANNEL};
+
+    use crate::msgs::RetrievalHop;
+
+    use super::*;
+
+    /// Mock deps for the handlers taking neutron's custom queries, which are left unanswered
+    fn mock_neutron_dependencies(
+    ) -> OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::<NeutronQuery>::new(&[]),
+            custom_query_type: std::marker::PhantomData,
+        }
+    }
+
+    /// A querier answering Stargate queries with `handler` given their path & request, any other query with `base`
+    struct StargateQuerier<F> {
+        base: MockQuerier<NeutronQuery>,
+        handler: F,
+    }
+
+    impl<F> Querier for StargateQuerier<F>
+    where
+        F: Fn(&str, &[u8]) -> ContractResult<Binary>,
+    {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<NeutronQuery> = from_slice(bin_request).unwrap();
+
+            let QueryRequest::Stargate { path, data } = request else {
+                return self.base.raw_query(bin_request);
+            };
+
+            SystemResult::Ok((self.handler)(&path, data.as_slice()))
+        }
+    }
+
+    fn valid_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            connection_id: CONNECTION_ID.to_owned(),
+            ibc_transfer_channel: TRANSFER_CHANNEL.to_owned(),
+            icq_update_period: 6,
+            remote_denom: REMOTE_DENOM.to_owned(),
+            host_ibc_denom: HOST_IBC_DENOM.to_owned(),
+            additional_transfer_denoms: None,
+            admin: None,
+            timeout_seconds: None,
+            timeout_height: None,
+            timeout_revision_number: None,
+            transfer_limits: None,
+            max_memo_len: None,
+            max_hook_memo_len: None,
+            hash_retention_blocks: None,
+            icq_deposit_override: None,
+        }
+    }
+
+    #[test]
+    fn instantiate_msg_validation() {
+        assert_eq!(valid_instantiate_msg().validate(), Ok(()));
+
+        let msg = InstantiateMsg {
+            connection_id: "conn-0".to_owned(),
+            ..valid_instantiate_msg()
+        };
+
+        assert_eq!(
+            msg.validate(),
+            Err(ValidationError::InvalidConnectionId("conn-0".to_owned()))
+        );
+
+        let msg = InstantiateMsg {
+            ibc_transfer_channel: "channel-".to_owned(),
+            ..valid_instantiate_msg()
+        };
+
+        assert_eq!(
+            msg.validate(),
+            Err(ValidationError::InvalidChannelId("channel-".to_owned()))
+        );
+
+        let msg = InstantiateMsg {
+            icq_update_period: 0,
+            ..valid_instantiate_msg()
+        };
+
+        assert_eq!(
+            msg.validate(),
+            Err(ValidationError::IcqUpdatePeriodOutOfBounds(0))
+        );
+
+        for bad_denom in ["u", "1atom", "u atom", "ibc/NOTAHASH"] {
+            let msg = InstantiateMsg {
+                remote_denom: bad_denom.to_owned(),
+                ..valid_instantiate_msg()
+            };
+
+            assert!(
+                matches!(msg.validate(), Err(ValidationError::InvalidDenom { .. })),
+                "{bad_denom} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn transfer_memo_validation() {
+        assert_eq!(validate_transfer_memo("invoice #42"), Ok(()));
+
+        assert!(matches!(
+            validate_transfer_memo(r#" {"wasm":{"contract":"neutron1..","msg":{}}}"#),
+            Err(ValidationError::InvalidMemo(_))
+        ));
+
+        let too_long = validate_transfer_memo(&"x".repeat(common::validate::MAX_MEMO_LEN + 1));
+
+        assert_eq!(
+            too_long.unwrap_err().to_string(),
+            format!(
+                "memo of {} bytes exceeds the limit of {} bytes",
+                common::validate::MAX_MEMO_LEN + 1,
+                common::validate::MAX_MEMO_LEN
+            )
+        );
+    }
+
+    #[test]
+    fn label_and_remote_address_validation() {
+        assert_eq!(validate_label("savings"), Ok(()));
+
+        for bad_label in ["", "   ", "two\nlines"] {
+            assert!(
+                matches!(
+                    validate_label(bad_label),
+                    Err(ValidationError::InvalidLabel(_))
+                ),
+                "{bad_label:?} should be invalid"
+            );
+        }
+
+        let too_long = "x".repeat(MAX_LABEL_LEN + 1);
+
+        assert_eq!(
+            validate_label(&too_long).unwrap_err().to_string(),
+            format!(
+                "label of {} bytes exceeds the limit of {MAX_LABEL_LEN} bytes",
+                too_long.len()
+            )
+        );
+
+        assert_eq!(validate_note("first line\nsecond line"), Ok(()));
+
+        assert_eq!(
+            validate_note(&"x".repeat(MAX_NOTE_LEN + 1)),
+            Err(ValidationError::NoteTooLong(MAX_NOTE_LEN + 1))
+        );
+
+        assert_eq!(
+            validate_remote_address("cosmos1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu"),
+            Ok(())
+        );
+
+        for bad_address in [
+            "cosmos",
+            "1qypqxpq9qcrs",
+            "Cosmos1qypqxpq9qcrs",
+            "cosmos1qypqxpq9bcrs",
+        ] {
+            assert!(
+                matches!(
+                    validate_remote_address(bad_address),
+                    Err(ValidationError::InvalidRemoteAddress(_))
+                ),
+                "{bad_address} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn address_book_kept_sorted_and_bounded() {
+        let mut deps = mock_dependencies();
+
+        state::set_owner_ica_idx(deps.as_mut().storage, "owner", 0);
+
+        let address = |n: usize| format!("cosmos1{}", "q".repeat(6 + n));
+
+        for n in (0..MAX_ADDRESS_BOOK_ENTRIES).rev() {
+            execute_set_address_book_entry(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                address(n),
+                format!("entry {n}"),
+                None,
+            )
+            .unwrap();
+        }
+
+        assert!(matches!(
+            execute_set_address_book_entry(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                address(MAX_ADDRESS_BOOK_ENTRIES),
+                "one too many".to_owned(),
+                None,
+            ),
+            Err(Error::AddressBookFull)
+        ));
+
+        // updating an existing entry is allowed when full
+        execute_set_address_book_entry(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            address(0),
+            "relabelled".to_owned(),
+            Some("a note".to_owned()),
+        )
+        .unwrap();
+
+        execute_remove_address_book_entry(deps.as_mut(), mock_info("owner", &[]), address(1))
+            .unwrap();
+
+        let AddressBookResponse { entries } =
+            query_address_book(deps.as_ref(), "owner".to_owned(), None, Some(2)).unwrap();
+
+        assert_eq!(
+            entries,
+            [
+                AddressBookEntry {
+                    address: address(0),
+                    label: "relabelled".to_owned(),
+                    note: Some("a note".to_owned()),
+                },
+                AddressBookEntry {
+                    address: address(2),
+                    label: "entry 2".to_owned(),
+                    note: None,
+                },
+            ]
+        );
+
+        let AddressBookResponse { entries } =
+            query_address_book(deps.as_ref(), "owner".to_owned(), Some(address(2)), Some(1))
+                .unwrap();
+
+        assert_eq!(entries[0].address, address(3));
+    }
+
+    #[test]
+    fn migrate_v0_backfills_reverse_maps() {
+        let mut deps = mock_dependencies();
+
+        let (owner_a, owner_b) = ("owner_a".to_owned(), "owner_b".to_owned());
+
+        let storage = deps.as_mut().storage;
+
+        // a version 0 instance: no version marker & missing reverse maps
+        state::set_ica_count(storage, 2);
+        state::set_ica_owner(storage, &0, &owner_a);
+        state::set_ica_owner(storage, &1, &owner_b);
+        state::set_owner_ica_idx(storage, &owner_a, 0);
+        state::set_ica_icq_id(storage, &0, 7);
+        state::set_ica_icq_id(storage, &1, 8);
+        state::set_icq_ica_idx(storage, &7, 0);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::contract_version(storage), Some(CONTRACT_VERSION));
+        assert_eq!(state::owner_ica_idx(storage, &owner_a), Some(0));
+        assert_eq!(state::owner_ica_idx(storage, &owner_b), Some(1));
+        assert_eq!(state::icq_ica_idx(storage, &7), Some(0));
+        assert_eq!(state::icq_ica_idx(storage, &8), Some(1));
+    }
+
+    #[test]
+    fn migrate_v1_builds_iterable_ica_index() {
+        let mut deps = mock_dependencies();
+
+        let (owner_a, owner_b) = ("owner_a".to_owned(), "owner_b".to_owned());
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_version(storage, 1);
+        state::set_ica_count(storage, 3);
+        state::set_ica_owner(storage, &0, &owner_a);
+        state::set_ica_owner(storage, &2, &owner_b);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let listed = state::ica_listed_owner_range(deps.as_ref().storage, None, 10);
+
+        assert_eq!(listed, vec![(0, owner_a), (2, owner_b.clone())]);
+
+        let listed = state::ica_listed_owner_range(deps.as_ref().storage, Some(&0), 10);
+
+        assert_eq!(listed, vec![(2, owner_b)]);
+    }
+
+    #[test]
+    fn migrate_v2_infers_ica_status() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_version(storage, 2);
+        state::set_ica_count(storage, 2);
+        state::set_ica_owner(storage, &0, &"owner_a".to_owned());
+        state::set_ica_addr(storage, &0, &"ica_a".to_owned());
+        state::set_ica_owner(storage, &1, &"owner_b".to_owned());
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(ica_status(storage, 0), IcaStatus::Active);
+        assert_eq!(ica_status(storage, 1), IcaStatus::Registering);
+        assert_eq!(
+            state::ica_status_height(storage, &1),
+            Some(mock_env().block.height)
+        );
+    }
+
+    #[test]
+    fn migrate_v3_wraps_single_coin_deposits() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        let deposit = Coin::new(1_000_000, IBC_FEE_DENOM);
+
+        state::set_contract_version(storage, 3);
+        state::set_ica_count(storage, 2);
+        state::set_ica_owner(storage, &0, &"owner_a".to_owned());
+        state::set_ica_owner(storage, &1, &"owner_b".to_owned());
+        state::v3::set_ica_setup_deposit(storage, &1, &deposit);
+        state::v3::set_fee_history(
+            storage,
+            &vec![state::v3::FeeObservation {
+                height: 10,
+                min_ibc_fee: IbcFee {
+                    recv_fee: vec![],
+                    ack_fee: vec![],
+                    timeout_fee: vec![],
+                },
+                icq_deposit: deposit.clone(),
+            }],
+        );
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::ica_setup_deposit(storage, &0), None);
+        assert_eq!(
+            state::ica_setup_deposit(storage, &1),
+            Some(vec![deposit.clone()])
+        );
+
+        let history = state::fee_history(storage).unwrap();
+
+        assert_eq!(history[0].height, 10);
+        assert_eq!(history[0].icq_deposit, vec![deposit]);
+    }
+
+    #[test]
+    fn operator_allowances_spent_until_revoked() {
+        let mut deps = mock_dependencies();
+
+        let allowance = vec![
+            Coin::new(100, HOST_IBC_DENOM),
+            Coin::new(0, REMOTE_DENOM),
+            Coin::new(50, HOST_IBC_DENOM),
+        ];
+
+        assert!(matches!(
+            execute_grant_operator(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                "owner".to_owned(),
+                allowance.clone()
+            ),
+            Err(Error::SelfOperatorGrant)
+        ));
+
+        execute_grant_operator(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            "operator".to_owned(),
+            allowance,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query_operators(deps.as_ref(), "owner".to_owned()).operators,
+            vec![OperatorGrant {
+                operator: "operator".to_owned(),
+                allowance: vec![Coin::new(150, HOST_IBC_DENOM)],
+            }]
+        );
+
+        let storage = deps.as_mut().storage;
+
+        // owners acting for themselves spend no allowance
+        spend_operator_allowance(storage, "owner", "owner", &[Coin::new(1000, REMOTE_DENOM)])
+            .unwrap();
+
+        assert!(matches!(
+            spend_operator_allowance(storage, "owner", "someone", &[Coin::new(1, HOST_IBC_DENOM)]),
+            Err(Error::NoOperatorGrant { .. })
+        ));
+
+        assert!(matches!(
+            spend_operator_allowance(storage, "owner", "operator", &[Coin::new(1, REMOTE_DENOM)]),
+            Err(Error::OperatorAllowanceExceeded { .. })
+        ));
+
+        spend_operator_allowance(
+            storage,
+            "owner",
+            "operator",
+            &[Coin::new(100, HOST_IBC_DENOM)],
+        )
+        .unwrap();
+
+        assert!(matches!(
+            spend_operator_allowance(
+                storage,
+                "owner",
+                "operator",
+                &[Coin::new(51, HOST_IBC_DENOM)]
+            ),
+            Err(Error::OperatorAllowanceExceeded { .. })
+        ));
+
+        // the grant is removed once its allowance is spent
+        spend_operator_allowance(
+            storage,
+            "owner",
+            "operator",
+            &[Coin::new(50, HOST_IBC_DENOM)],
+        )
+        .unwrap();
+
+        assert_eq!(state::owner_operators(storage, "owner"), None);
+
+        for n in 0..MAX_OPERATORS {
+            execute_grant_operator(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                format!("operator{n}"),
+                vec![Coin::new(1, HOST_IBC_DENOM)],
+            )
+            .unwrap();
+        }
+
+        assert!(matches!(
+            execute_grant_operator(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                "operator".to_owned(),
+                vec![Coin::new(1, HOST_IBC_DENOM)],
+            ),
+            Err(Error::TooManyOperators)
+        ));
+
+        execute_revoke_operator(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            "operator0".to_owned(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            execute_revoke_operator(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                "operator0".to_owned()
+            ),
+            Err(Error::NoOperatorGrant { .. })
+        ));
+
+        assert_eq!(
+            query_operators(deps.as_ref(), "owner".to_owned())
+                .operators
+                .len(),
+            MAX_OPERATORS - 1
+        );
+    }
+
+    #[test]
+    fn open_ack_only_accepted_for_pending_registrations() {
+        let mut deps = mock_dependencies();
+
+        let contract = mock_env().contract.address;
+
+        set_ica_status(deps.as_mut().storage, 1, IcaStatus::Registering, 1);
+        set_ica_status(deps.as_mut().storage, 2, IcaStatus::Active, 1);
+        set_ica_status(deps.as_mut().storage, 3, IcaStatus::Retired, 1);
+
+        let storage = deps.as_ref().storage;
+
+        let port_id = format!("icacontroller-{contract}.1");
+
+        assert_eq!(
+            registering_ica_idx(storage, &contract, &port_id).unwrap(),
+            1
+        );
+
+        for port_id in [
+            format!("icacontroller-{contract}.0"),
+            format!("icacontroller-{contract}.2"),
+            format!("icacontroller-{contract}.3"),
+            format!("icacontroller-{contract}.01"),
+            format!("icacontroller-{contract}.+1"),
+            format!("icacontroller-{contract}1.1"),
+            "icacontroller-neutron1attacker.1".to_owned(),
+            "transfer".to_owned(),
+        ] {
+            assert!(
+                matches!(
+                    registering_ica_idx(storage, &contract, &port_id),
+                    Err(Error::UnexpectedOpenAck { .. })
+                ),
+                "{port_id} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn only_active_icas_issue_txs() {
+        let mut deps = mock_dependencies();
+
+        let owner = "owner".to_owned();
+
+        let storage = deps.as_mut().storage;
+
+        assert!(matches!(
+            active_ica_idx(storage, &owner),
+            Err(Error::NoIcaSetup)
+        ));
+
+        state::set_owner_ica_idx(storage, &owner, 0);
+
+        for status in [
+            IcaStatus::Registering,
+            IcaStatus::ChannelClosed,
+            IcaStatus::Reopening,
+        ] {
+            set_ica_status(storage, 0, status, 1);
+
+            assert!(matches!(
+                active_ica_idx(storage, &owner),
+                Err(Error::UnexpectedIcaStatus { ica_idx: 0, .. })
+            ));
+        }
+
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        assert_eq!(active_ica_idx(storage, &owner).unwrap(), 0);
+    }
+
+    #[test]
+    fn closed_ica_reopened_keeping_its_icq() {
+        let mut deps = mock_neutron_dependencies();
+
+        let env = mock_env();
+
+        let owner = "owner".to_owned();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, "connection-0");
+        state::set_owner_ica_idx(storage, &owner, 0);
+        state::set_ica_icq_id(storage, &0, 7);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        assert!(matches!(
+            execute_reopen_ica(deps.as_mut(), env.clone(), mock_info(&owner, &[])),
+            Err(Error::UnexpectedIcaStatus { ica_idx: 0, .. })
+        ));
+
+        set_ica_status(deps.as_mut().storage, 0, IcaStatus::ChannelClosed, 2);
+
+        let res = execute_reopen_ica(deps.as_mut(), env.clone(), mock_info(&owner, &[])).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            NeutronMsg::RegisterInterchainAccount {
+                connection_id: "connection-0".to_owned(),
+                interchain_account_id: "0".to_owned(),
+            }
+            .into()
+        );
+
+        assert_eq!(ica_status(deps.as_ref().storage, 0), IcaStatus::Reopening);
+
+        state::set_ica_channel(
+            deps.as_mut().storage,
+            &0,
+            &IcaChannel {
+                port_id: format!("icacontroller-{}.0", env.contract.address),
+                channel_id: "channel-1".to_owned(),
+                counterparty_channel_id: "channel-2".to_owned(),
+            },
+        );
+
+        let channel = IcaChannel {
+            port_id: format!("icacontroller-{}.0", env.contract.address),
+            channel_id: "channel-3".to_owned(),
+            counterparty_channel_id: "channel-4".to_owned(),
+        };
+
+        let counterparty_version = r#"{"version":"ics27-1","controller_connection_id":"connection-0","host_connection_id":"connection-0","address":"cosmos1ica","encoding":"proto3","tx_type":"sdk_multi_msg"}"#;
+
+        let res = sudo_open_ack(
+            deps.as_mut(),
+            &env,
+            channel.clone(),
+            counterparty_version.to_owned(),
+        )
+        .unwrap();
+
+        assert!(
+            res.messages.is_empty(),
+            "the balance ICQ is not re-registered"
+        );
+
+        assert_eq!(ica_status(deps.as_ref().storage, 0), IcaStatus::Active);
+        assert_eq!(
+            state::ica_addr(deps.as_ref().storage, &0),
+            Some("cosmos1ica".to_owned())
+        );
+        assert_eq!(
+            ica_metadata(deps.as_ref().storage, 0).channel,
+            Some(channel),
+            "the ICA is served by the new channel"
+        );
+    }
+
+    #[test]
+    fn stale_registration_cancelled_with_refund() {
+        use cosmwasm_std::testing::mock_info;
+
+        let mut deps = mock_dependencies();
+
+        let owner = "owner".to_owned();
+
+        let deposit = vec![Coin::new(1_000_000, IBC_FEE_DENOM)];
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, &owner, 0);
+        state::set_ica_owner(storage, &0, &owner);
+        state::set_ica_setup_deposit(storage, &0, &deposit);
+        set_ica_status(storage, 0, IcaStatus::Registering, 100);
+
+        let mut env = mock_env();
+
+        env.block.height = 100 + REGISTRATION_CANCEL_BLOCKS - 1;
+
+        let err =
+            execute_cancel_ica_registration(deps.as_mut(), env.clone(), mock_info(&owner, &[]))
+                .unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::RegistrationPending {
+                remaining_blocks: 1
+            }
+        ));
+
+        env.block.height += 1;
+
+        let res =
+            execute_cancel_ica_registration(deps.as_mut(), env, mock_info(&owner, &[])).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: owner.clone(),
+                amount: deposit,
+            }
+            .into()
+        );
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::owner_ica_idx(storage, &owner), None);
+        assert_eq!(ica_status(storage, 0), IcaStatus::Retired);
+    }
+
+    #[test]
+    fn failed_transfer_submission_refunded() {
+        let mut deps = mock_dependencies();
+
+        let owner = "owner".to_owned();
+
+        let fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![Coin::new(1000, IBC_FEE_DENOM)],
+            timeout_fee: vec![Coin::new(1000, IBC_FEE_DENOM)],
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_owner(storage, &0, &owner);
+
+        push_pending_tx(
+            storage,
+            0,
+            IssuedTx {
+                kind: TxKind::Transfer,
+                coin: Coin::new(500, HOST_IBC_DENOM),
+                fee: Some(fee),
+                payer: None,
+                validator: None,
+                memo: None,
+                route: None,
+                custom: None,
+                rx_hash: None,
+            },
+        );
+
+        let reply_id = save_pending_reply(
+            storage,
+            state::ReplyKind::IssueTx(TxKind::Transfer),
+            0,
+            Some(Coin::new(500, HOST_IBC_DENOM)),
+        );
+
+        let failed_reply = Reply {
+            id: reply_id,
+            result: cosmwasm_std::SubMsgResult::Err("channel closed".to_owned()),
+        };
+
+        let res = reply(deps.as_mut(), mock_env(), failed_reply.clone()).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: owner,
+                amount: vec![
+                    Coin::new(500, HOST_IBC_DENOM),
+                    Coin::new(2000, IBC_FEE_DENOM)
+                ],
+            }
+            .into()
+        );
+
+        assert_eq!(state::ica_pending_txs(deps.as_ref().storage, &0), None);
+
+        // the pending reply is taken by its reply
+        assert!(matches!(
+            reply(deps.as_mut(), mock_env(), failed_reply),
+            Err(Error::UnexpectedReply { id }) if id == reply_id
+        ));
+    }
+
+    #[test]
+    fn pending_txs_consumed_in_issue_order() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        let issued_tx = |amount| IssuedTx {
+            kind: TxKind::Transfer,
+            coin: Coin::new(amount, HOST_IBC_DENOM),
+            fee: None,
+            payer: None,
+            validator: None,
+            memo: None,
+            route: None,
+            custom: None,
+            rx_hash: None,
+        };
+
+        push_pending_tx(storage, 0, issued_tx(1));
+        push_pending_tx(storage, 0, issued_tx(2));
+
+        assert_eq!(pop_pending_tx(storage, 0), Some(issued_tx(1)));
+        assert_eq!(pop_pending_tx(storage, 0), Some(issued_tx(2)));
+        assert_eq!(pop_pending_tx(storage, 0), None);
+        assert_eq!(state::ica_pending_txs(storage, &0), None);
+    }
+
+    #[test]
+    fn tx_history_logs_transfers_with_outcomes() {
+        let mut deps = mock_dependencies();
+
+        let owner = "owner".to_owned();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, &owner, 0);
+
+        let issued_tx = |kind| IssuedTx {
+            kind,
+            coin: Coin::new(10, HOST_IBC_DENOM),
+            fee: None,
+            payer: None,
+            validator: None,
+            memo: None,
+            route: None,
+            custom: None,
+            rx_hash: None,
+        };
+
+        append_tx_history(storage, 0, "TX0", &issued_tx(TxKind::Transfer), 1, 100);
+        append_tx_history(storage, 0, "TX1", &issued_tx(TxKind::Delegate), 2, 101);
+        append_tx_history(storage, 0, "TX2", &issued_tx(TxKind::Retrieve), 3, 102);
+
+        record_tx_outcome(storage, 0, "TX0", TxOutcome::Success, 103);
+        record_tx_outcome(storage, 0, "TX2", TxOutcome::Timeout, 104);
+
+        let history = query_ica_tx_history(deps.as_ref(), owner.clone(), None, None)
+            .unwrap()
+            .history;
+
+        let summary: Vec<_> = history
+            .iter()
+            .map(|entry| (entry.idx, entry.direction, entry.seq_num, entry.outcome))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                (0, TransferDirection::ToIca, 1, TxOutcome::Success),
+                (1, TransferDirection::FromIca, 3, TxOutcome::Timeout),
+            ]
+        );
+
+        let page = query_ica_tx_history(deps.as_ref(), owner, Some(0), Some(1))
+            .unwrap()
+            .history;
+
+        assert_eq!(page, history[1..]);
+    }
+
+    #[test]
+    fn activity_pruned_into_its_digest() {
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_state_commitments_enabled(storage, &true);
+
+        let entry = |idx: u32| TxHistoryEntry {
+            idx,
+            direction: TransferDirection::ToIca,
+            coin: Coin::new(10, HOST_IBC_DENOM),
+            seq_num: idx.into(),
+            height: 100,
+            outcome: TxOutcome::Pending,
+        };
+
+        let logged = u32::try_from(MAX_ACTIVITY_ENTRIES).unwrap() + 2;
+
+        for idx in 0..logged {
+            log_activity(storage, 0, entry(idx), 100);
+        }
+
+        let kept: Vec<ActivityEntry> = (0..u64::from(logged))
+            .filter_map(|activity_id| state::activity_entry(storage, &activity_id))
+            .collect();
+
+        // the oldest entries are pruned
+        assert_eq!(kept.len() as u64, MAX_ACTIVITY_ENTRIES);
+        assert_eq!(kept[0].activity_id, 2);
+
+        let fold = |digest: String, entry: &ActivityEntry| {
+            HASH_SCHEME.hash(
+                ACTIVITY_DOMAIN,
+                &[digest.as_bytes(), &to_vec(entry).unwrap()],
+            )
+        };
+
+        let pruned_digest = state::pruned_activity_digest(storage).unwrap();
+
+        let pruned = (0..2).map(|idx| ActivityEntry {
+            activity_id: idx.into(),
+            ica_idx: 0,
+            entry: entry(idx),
+        });
+
+        assert_eq!(
+            pruned_digest,
+            pruned.fold(String::new(), |digest, entry| fold(digest, &entry))
+        );
+
+        let commitment = state::state_commitment(storage).unwrap();
+
+        assert_eq!(commitment.activity_count, u64::from(logged));
+        assert_eq!(
+            commitment.activity_digest,
+            kept.iter().fold(pruned_digest, fold)
+        );
+    }
+
+    #[test]
+    fn state_commitments_replayable_from_activity_and_stats() {
+        let mut deps = mock_neutron_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        let issued_tx = IssuedTx {
+            kind: TxKind::Transfer,
+            coin: Coin::new(10, HOST_IBC_DENOM),
+            fee: None,
+            payer: None,
+            validator: None,
+            memo: None,
+            route: None,
+            custom: None,
+            rx_hash: None,
+        };
+
+        // nothing is logged until enabled
+        append_tx_history(deps.as_mut().storage, 0, "TX0", &issued_tx, 1, 100);
+
+        assert!(matches!(
+            execute_set_state_commitments(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("someone", &[]),
+                true
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        execute_set_state_commitments(deps.as_mut(), mock_env(), mock_info("owner", &[]), true)
+            .unwrap();
+
+        let storage = deps.as_mut().storage;
+
+        append_tx_history(storage, 1, "TX1", &issued_tx, 2, 101);
+        record_tx_outcome(storage, 1, "TX1", TxOutcome::Success, 102);
+        record_tx_outcome(storage, 0, "TX0", TxOutcome::Error, 102);
+        add_channel_volume(
+            storage,
+            HOST_IBC_DENOM,
+            TRANSFER_CHANNEL,
+            10u128.into(),
+            Uint128::zero(),
+            102,
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::StateCommitment {}).unwrap();
+
+        let StateCommitmentResponse {
+            commitment: Some(commitment),
+            stats,
+            pruned_activity_digest,
+        } = cosmwasm_std::from_binary(&res).unwrap()
+        else {
+            panic!("state commitments are enabled");
+        };
+
+        // nothing was pruned yet
+        assert_eq!(pruned_activity_digest, "");
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Activity {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+
+        let ActivityResponse { entries } = cosmwasm_std::from_binary(&res).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(commitment.activity_count, 3);
+        assert_eq!(commitment.height, 102);
+
+        // an off-chain verifier replays the activity log & hashes the stats to recompute the commitment
+        let activity_digest = entries
+            .iter()
+            .fold(pruned_activity_digest, |digest, entry| {
+                HASH_SCHEME.hash(
+                    ACTIVITY_DOMAIN,
+                    &[digest.as_bytes(), &to_vec(entry).unwrap()],
+                )
+            });
+
+        assert_eq!(stats.volumes.len(), 1);
+
+        let stats_digest = HASH_SCHEME.hash(STATS_DOMAIN, &[&to_vec(&stats).unwrap()]);
+
+        let expected = HASH_SCHEME.hash(
+            COMMITMENT_DOMAIN,
+            &[
+                &102u64.to_be_bytes(),
+                &3u64.to_be_bytes(),
+                activity_digest.as_bytes(),
+                stats_digest.as_bytes(),
+            ],
+        );
+
+        assert_eq!(commitment.activity_digest, activity_digest);
+        assert_eq!(commitment.stats_digest, stats_digest);
+        assert_eq!(commitment.commitment, expected);
+
+        // the totals are committed to along with the volumes
+        update_global_totals(deps.as_mut().storage, 103, |totals| totals.round_trips += 1);
+
+        let recommitted = state::state_commitment(deps.as_ref().storage).unwrap();
+
+        assert_eq!(recommitted.height, 103);
+        assert_eq!(recommitted.activity_digest, commitment.activity_digest);
+        assert_ne!(recommitted.stats_digest, commitment.stats_digest);
+
+        execute_set_state_commitments(deps.as_mut(), mock_env(), mock_info("owner", &[]), false)
+            .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::StateCommitment {}).unwrap();
+
+        let StateCommitmentResponse { commitment, .. } = cosmwasm_std::from_binary(&res).unwrap();
+
+        assert_eq!(commitment, None);
+    }
+
+    #[test]
+    fn pending_txs_tracked_until_settled() {
+        let mut deps = mock_dependencies();
+
+        let owner = "owner".to_owned();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, &owner, 0);
+
+        let pending_tx = |tx_hash: &str| PendingTx {
+            tx_hash: tx_hash.to_owned(),
+            kind: TxKind::Transfer,
+            coin: Some(Coin::new(10, HOST_IBC_DENOM)),
+            issued_height: 100,
+            timeout_height: Some(DEFAULT_TIMEOUT_HEIGHT),
+            timeout_timestamp: None,
+        };
+
+        track_pending_tx(storage, 0, pending_tx("TX0"));
+        track_pending_tx(storage, 0, pending_tx("TX1"));
+
+        settle_pending_tx(storage, 0, "TX0");
+
+        let res = query_pending_txs(deps.as_ref(), owner.clone()).unwrap();
+
+        assert_eq!(res.pending_txs, [pending_tx("TX1")]);
+
+        let storage = deps.as_mut().storage;
+
+        settle_pending_tx(storage, 0, "TX1");
+
+        assert_eq!(state::ica_pending_tx_hashes(storage, &0), None);
+        assert_eq!(state::tx_hash_pending_tx(storage, "TX1"), None);
+    }
+
+    #[test]
+    fn custom_txs_submit_their_msgs() {
+        let mut neutron_deps = mock_neutron_dependencies();
+
+        let msg = ProtobufAnyJson {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_owned(),
+            value: Binary::from(b"msg".to_vec()),
+        };
+
+        let err =
+            execute_submit_ica_tx(neutron_deps.as_mut(), mock_info("owner", &[]), vec![], None)
+                .unwrap_err();
+
+        assert!(matches!(err, Error::EmptyCustomTx));
+        assert_eq!(
+            err.to_string(),
+            "[E0270] custom ICA txs execute at least one message"
+        );
+
+        assert!(matches!(
+            execute_submit_ica_tx(
+                neutron_deps.as_mut(),
+                mock_info("owner", &[]),
+                vec![msg.clone()],
+                Some(0)
+            ),
+            Err(Error::ZeroCustomTxTimeout)
+        ));
+
+        let mut deps = mock_dependencies();
+
+        state::set_connection_id(deps.as_mut().storage, CONNECTION_ID);
+
+        let fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![Coin::new(1, IBC_FEE_DENOM)],
+            timeout_fee: vec![Coin::new(1, IBC_FEE_DENOM)],
+        };
+
+        let custom = CustomTx {
+            msgs: vec![msg.clone()],
+            timeout: 60,
+        };
+
+        let sub_msg =
+            issue_custom_tx(deps.as_mut(), 3, custom.clone(), fee.clone(), "owner").unwrap();
+
+        assert_eq!(
+            common::take_reply_payload(deps.as_mut().storage, sub_msg.id),
+            Some(state::PendingReply {
+                kind: state::ReplyKind::IssueTx(TxKind::Custom),
+                ica_idx: 3,
+                coin: None,
+            })
+        );
+        assert_eq!(
+            sub_msg.msg,
+            CosmosMsg::Custom(NeutronMsg::SubmitTx {
+                connection_id: CONNECTION_ID.to_owned(),
+                interchain_account_id: "3".to_owned(),
+                msgs: vec![ProtobufAny {
+                    type_url: msg.type_url,
+                    value: msg.value,
+                }],
+                memo: String::new(),
+                timeout: 60,
+                fee,
+            })
+        );
+
+        let pending = state::ica_pending_txs(deps.as_ref().storage, &3).unwrap();
+
+        assert_eq!(pending[0].kind, TxKind::Custom);
+        assert_eq!(pending[0].custom, Some(custom));
+    }
+
+    #[test]
+    fn custom_txs_split_by_interchaintxs_params() {
+        let min_fee = r#"{"min_fee":{"recv_fee":[],"ack_fee":[{"denom":"untrn","amount":"1000"}],"timeout_fee":[{"denom":"untrn","amount":"1000"}]}}"#;
+
+        let base = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let NeutronQuery::MinIbcFee {} = query else {
+                unimplemented!("unexpected query: {query:?}");
+            };
+
+            SystemResult::Ok(ContractResult::Ok(min_fee.as_bytes().into()))
+        });
+
+        let querier = StargateQuerier {
+            base,
+            handler: |path: &str, _: &[u8]| {
+                assert_eq!(path, "/neutron.interchaintxs.Query/Params");
+
+                ContractResult::Ok(
+                    br#"{"params":{"msg_submit_tx_max_messages":"2"}}"#.as_slice().into(),
+                )
+            },
+        };
+
+        let mut storage = MockStorage::default();
+
+        let api = MockApi::default();
+
+        state::set_connection_id(&mut storage, CONNECTION_ID);
+        state::set_owner_ica_idx(&mut storage, "owner", 0);
+        state::set_ica_owner(&mut storage, &0, "owner");
+        state::set_ica_addr(&mut storage, &0, "ica");
+        set_ica_status(&mut storage, 0, IcaStatus::Active, 1);
+
+        let deps: Deps<NeutronQuery> = Deps {
+            storage: &storage,
+            api: &api,
+            querier: QuerierWrapper::new(&querier),
+        };
+
+        let params = deps.ica_tx_params().unwrap();
+
+        assert_eq!(params.msg_submit_tx_max_messages.u64(), 2);
+
+        let msg = |n: u8| ProtobufAnyJson {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_owned(),
+            value: Binary::from(vec![n]),
+        };
+
+        let too_many = (0..=2 * MAX_CUSTOM_TXS as u8).map(msg).collect();
+
+        assert!(matches!(
+            execute_submit_ica_tx(
+                DepsMut {
+                    storage: &mut storage,
+                    api: &api,
+                    querier: QuerierWrapper::new(&querier),
+                },
+                mock_info("owner", &[Coin::new(10_000, IBC_FEE_DENOM)]),
+                too_many,
+                None
+            ),
+            Err(Error::InvalidCustomTxMsgs { count: 7, max: 6 })
+        ));
+
+        let res = execute_submit_ica_tx(
+            DepsMut {
+                storage: &mut storage,
+                api: &api,
+                querier: QuerierWrapper::new(&querier),
+            },
+            mock_info("owner", &[Coin::new(6000, IBC_FEE_DENOM)]),
+            (0..5).map(msg).collect(),
+            None,
+        )
+        .unwrap();
+
+        let submitted: Vec<Vec<Binary>> = res
+            .messages
+            .iter()
+            .map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Custom(NeutronMsg::SubmitTx { msgs, .. }) => {
+                    msgs.iter().map(|msg| msg.value.clone()).collect()
+                }
+                msg => panic!("unexpected msg: {msg:?}"),
+            })
+            .collect();
+
+        assert_eq!(
+            submitted,
+            [
+                vec![Binary::from(vec![0]), Binary::from(vec![1])],
+                vec![Binary::from(vec![2]), Binary::from(vec![3])],
+                vec![Binary::from(vec![4])],
+            ]
+        );
+
+        // each ICA tx pays its own fee, leaving nothing of the deposit
+        assert_eq!(state::owner_fee_balance(&storage, "owner"), Some(vec![]));
+        assert_eq!(state::ica_pending_txs(&storage, &0).unwrap().len(), 3);
+
+        // a failed custom tx recorded without its msgs cannot be retried
+        state::set_ica_tx_kind_failed_tx(
+            &mut storage,
+            &combine_u32s(0, CUSTOM_TX_REPLY_KIND),
+            &IssuedTx {
+                kind: TxKind::Custom,
+                coin: Coin::new(0, ""),
+                fee: None,
+                payer: None,
+                validator: None,
+                memo: None,
+                route: None,
+                custom: None,
+                rx_hash: None,
+            },
+        );
+
+        assert!(matches!(
+            execute_retry_last_tx(
+                DepsMut {
+                    storage: &mut storage,
+                    api: &api,
+                    querier: QuerierWrapper::new(&querier),
+                },
+                mock_env(),
+                mock_info("owner", &[Coin::new(2000, IBC_FEE_DENOM)]),
+                TxKind::Custom
+            ),
+            Err(Error::IncompleteFailedTx { .. })
+        ));
+    }
+
+    #[test]
+    fn custom_txs_rejected_while_retrievals_in_flight() {
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, "owner", 0);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        let pending_tx = |tx_hash: &str, kind| PendingTx {
+            tx_hash: tx_hash.to_owned(),
+            kind,
+            coin: Some(Coin::new(10, HOST_IBC_DENOM)),
+            issued_height: 100,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        track_pending_tx(storage, 0, pending_tx("TX0", TxKind::Transfer));
+        track_pending_tx(storage, 0, pending_tx("TX1", TxKind::Retrieve));
+
+        assert_eq!(ica_retrievals_in_flight(storage, 0), 1);
+
+        let msg = ProtobufAnyJson {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_owned(),
+            value: Binary::from(b"msg".to_vec()),
+        };
+
+        assert!(matches!(
+            execute_submit_ica_tx(deps.as_mut(), mock_info("owner", &[]), vec![msg], None),
+            Err(Error::RetrievalsInFlight { in_flight: 1 })
+        ));
+
+        settle_pending_tx(deps.as_mut().storage, 0, "TX1");
+
+        assert_eq!(ica_retrievals_in_flight(deps.as_ref().storage, 0), 0);
+    }
+
+    #[test]
+    fn custom_tx_msgs_batched_by_submit_tx_limit() {
+        assert!(matches!(
+            submit_tx_batches(vec![1, 2, 3], 0),
+            Err(Error::ZeroSubmitTxMsgLimit)
+        ));
+
+        assert_eq!(
+            submit_tx_batches(vec![1, 2, 3], 5).unwrap(),
+            [vec![1, 2, 3]]
+        );
+
+        assert_eq!(
+            submit_tx_batches(vec![1, 2, 3, 4, 5], 2).unwrap(),
+            [vec![1, 2], vec![3, 4], vec![5]]
+        );
+
+        assert_eq!(
+            submit_tx_batches(vec![1, 2, 3, 4], u64::MAX).unwrap(),
+            [vec![1, 2, 3, 4]]
+        );
+    }
+
+    #[test]
+    fn hash_schemes_encode_the_same_digest() {
+        // SHA-256("abc")
+        let expected = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD";
+
+        assert_eq!(
+            HashScheme::V0.hash(TX_HASH_DOMAIN, &[b"a", b"bc"]),
+            expected
+        );
+
+        let compact = HashScheme::V1.hash(TX_HASH_DOMAIN, &[b"abc"]);
+
+        assert_eq!(compact.len(), 44);
+        assert_eq!(
+            hex::encode_upper(Binary::from_base64(&compact).unwrap().0),
+            expected
+        );
+
+        assert_eq!(
+            HashScheme::from_version(HASH_SCHEME.version),
+            Some(HASH_SCHEME)
+        );
+    }
+
+    #[test]
+    fn framed_hashes_never_collide_across_splits_or_domains() {
+        let input = b"neutron1abc";
+
+        let mut legacy = HashSet::new();
+        let mut framed = HashSet::new();
+
+        // every way of splitting the same bytes into two parts, in both domains
+        for domain in [TX_HASH_DOMAIN, RX_HASH_DOMAIN] {
+            for split in 0..=input.len() {
+                let (a, b) = input.split_at(split);
+
+                legacy.insert(HashScheme::V0.hash(domain, &[a, b]));
+                framed.insert(HashScheme::V2.hash(domain, &[a, b]));
+            }
+        }
+
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(framed.len(), 2 * (input.len() + 1));
+
+        // the domain cannot be smuggled into the first part
+        assert_ne!(
+            HashScheme::V2.hash("t", &[b"x", b"7"]),
+            HashScheme::V2.hash("", &[b"tx", b"7"]),
+        );
+
+        // tx & rx hashes of generated inputs are all distinct
+        let mut hashes = HashSet::new();
+
+        for seq_num in 0..64u64 {
+            for channel in ["channel-0", "channel-1", "channel-10"] {
+                hashes.insert(hash!(TX_HASH_DOMAIN; seq_num.to_be_bytes(), channel));
+                hashes.insert(hash!(RX_HASH_DOMAIN; seq_num.to_be_bytes(), channel));
+            }
+        }
+
+        assert_eq!(hashes.len(), 64 * 3 * 2);
+    }
+
+    #[test]
+    fn acked_txs_found_under_legacy_hash_scheme() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        let legacy_hash = HashScheme::V0.hash(TX_HASH_DOMAIN, &[&5u64.to_be_bytes(), b"channel-1"]);
+
+        state::set_tx_hash_ica_idx(storage, &legacy_hash, 2);
+
+        let request = RequestPacket {
+            sequence: Some(5),
+            source_port: None,
+            source_channel: Some("channel-1".to_owned()),
+            destination_port: None,
+            destination_channel: None,
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        let acked = acked_tx(storage, &request).unwrap();
+
+        assert_eq!((acked.tx_hash, acked.ica_idx), (legacy_hash, 2));
+    }
+
+    #[test]
+    fn tx_outcomes_received_while_reopening() {
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        let packet = |sequence: u64| RequestPacket {
+            sequence: Some(sequence),
+            source_port: Some("transfer".to_owned()),
+            source_channel: Some("channel-1".to_owned()),
+            destination_port: None,
+            destination_channel: None,
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        for seq_num in [5u64, 6] {
+            let tx_hash = HASH_SCHEME.hash(TX_HASH_DOMAIN, &[&seq_num.to_be_bytes(), b"channel-1"]);
+
+            state::set_tx_hash_ica_idx(storage, &tx_hash, 0);
+        }
+
+        set_ica_status(storage, 0, IcaStatus::Reopening, 1);
+
+        // the transfers issued before the channel closed settle while the ICA is reopened
+        sudo_response(deps.as_mut(), mock_env(), packet(5), Binary::default()).unwrap();
+
+        sudo_timeout(deps.as_mut(), mock_env(), packet(6)).unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::ica_tx_success_count(storage, &0), Some(1));
+        assert_eq!(state::ica_tx_timeout_count(storage, &0), Some(1));
+        assert_eq!(ica_status(storage, 0), IcaStatus::Reopening);
+    }
+
+    #[test]
+    fn unattributed_packets_become_dead_letters() {
+        let mut deps = mock_dependencies();
+
+        let env = mock_env();
+
+        let packet = |sequence: Option<u64>| RequestPacket {
+            sequence,
+            source_port: Some("icacontroller-contract.0".to_owned()),
+            source_channel: Some("channel-1".to_owned()),
+            destination_port: None,
+            destination_channel: None,
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        let tx_hash = hash!(TX_HASH_DOMAIN; 7u64.to_be_bytes(), "channel-1");
+
+        state::set_tx_hash_ica_idx(storage, &tx_hash, 3);
+
+        assert!(matches!(
+            acked_tx(storage, &packet(Some(7))),
+            Ok(AckedTx { ica_idx: 3, .. })
+        ));
+
+        for (request, callback) in [(packet(None), "error"), (packet(Some(8)), "timeout")] {
+            let err = acked_tx(storage, &request).unwrap_err();
+
+            record_dead_letter(storage, &env, callback, &request, &err);
+        }
+
+        let dead_letters: Vec<_> = state::dead_letter_packet_range(storage, None, 10)
+            .into_iter()
+            .map(|(_, dead_letter)| (dead_letter.callback, dead_letter.reason))
+            .collect();
+
+        assert_eq!(
+            dead_letters,
+            [
+                (
+                    "error".to_owned(),
+                    Error::IncompletePacket {
+                        field: "sequence number"
+                    }
+                    .to_string()
+                ),
+                (
+                    "timeout".to_owned(),
+                    Error::UnknownTx {
+                        seq_num: 8,
+                        channel: "channel-1".to_owned()
+                    }
+                    .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_transfers_refunded_to_sender() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        let transfer = IssuedTx {
+            kind: TxKind::Transfer,
+            coin: Coin::new(500, HOST_IBC_DENOM),
+            fee: None,
+            payer: None,
+            validator: None,
+            memo: None,
+            route: None,
+            custom: None,
+            rx_hash: None,
+        };
+
+        state::set_tx_hash_issued_tx(storage, "TX0", &transfer);
+        state::set_tx_hash_transfer_sender(storage, "TX0", &"owner".to_owned());
+
+        assert_eq!(
+            refund_failed_transfer(storage, "TX0"),
+            Some(BankMsg::Send {
+                to_address: "owner".to_owned(),
+                amount: vec![Coin::new(500, HOST_IBC_DENOM)],
+            })
+        );
+
+        // refunded transfers are not retryable
+        assert_eq!(state::tx_hash_issued_tx(storage, "TX0"), None);
+
+        assert_eq!(refund_failed_transfer(storage, "TX0"), None);
+
+        // transfers issued before senders were tracked are kept for a retry
+        state::set_tx_hash_issued_tx(storage, "TX1", &transfer);
+
+        assert_eq!(refund_failed_transfer(storage, "TX1"), None);
+
+        assert_eq!(state::tx_hash_issued_tx(storage, "TX1"), Some(transfer));
+    }
+
+    #[test]
+    fn icq_update_period_changed_by_ica_owner() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, "owner", 0);
+        state::set_ica_icq_id(storage, &0, 7);
+        set_ica_status(storage, 0, IcaStatus::Active, 100);
+
+        assert!(matches!(
+            execute_update_icq_period(deps.as_mut(), mock_info("owner", &[]), 0),
+            Err(Error::Validation(
+                ValidationError::IcqUpdatePeriodOutOfBounds(0)
+            ))
+        ));
+
+        assert!(matches!(
+            execute_update_icq_period(deps.as_mut(), mock_info("someone", &[]), 100),
+            Err(Error::NoIcaSetup)
+        ));
+
+        let res = execute_update_icq_period(deps.as_mut(), mock_info("owner", &[]), 100).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            NeutronMsg::UpdateInterchainQuery {
+                query_id: 7,
+                new_keys: None,
+                new_update_period: Some(100),
+                new_transactions_filter: None,
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn export_paginates_listed_icas() {
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        for (ica_idx, owner) in [(0, "owner_a"), (2, "owner_b")] {
+            state::set_ica_owner(storage, &ica_idx, &owner.to_owned());
+            state::set_ica_listed_owner(storage, &ica_idx, &owner.to_owned());
+            set_ica_status(storage, ica_idx, IcaStatus::Active, 100);
+        }
+
+        state::set_owner_fee_balance(storage, "owner_b", &vec![Coin::new(10, IBC_FEE_DENOM)]);
+
+        let first_page = query_export(deps.as_ref(), None, Some(1)).unwrap();
+
+        assert_eq!(first_page.icas[0].owner, "owner_a");
+        assert_eq!(first_page.next_cursor, Some(0));
+
+        let last_page = query_export(deps.as_ref(), first_page.next_cursor, Some(1)).unwrap();
+
+        assert_eq!(last_page.icas[0].metadata.ica_idx, 2);
+        assert_eq!(
+            last_page.icas[0].fee_balance,
+            vec![Coin::new(10, IBC_FEE_DENOM)]
+        );
+
+        let done = query_export(deps.as_ref(), last_page.next_cursor, Some(1)).unwrap();
+
+        assert!(done.icas.is_empty());
+        assert_eq!(done.next_cursor, None);
+    }
+
+    #[test]
+    fn tx_query_results_checked_against_the_filter() {
+        #[derive(Clone, PartialEq, Message)]
+        struct MsgSend {
+            #[prost(string, tag = "1")]
+            pub from_address: String,
+            #[prost(string, tag = "2")]
+            pub to_address: String,
+            #[prost(message, repeated, tag = "3")]
+            pub amount: Vec<RawCoin>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        struct Any {
+            #[prost(string, tag = "1")]
+            pub type_url: String,
+            #[prost(bytes = "vec", tag = "2")]
+            pub value: Vec<u8>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        struct TxBody {
+            #[prost(message, repeated, tag = "1")]
+            pub messages: Vec<Any>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        struct TxRaw {
+            #[prost(bytes = "vec", tag = "1")]
+            pub body_bytes: Vec<u8>,
+        }
+
+        let send = |from: &str, to: &str, amount: u128| Any {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_owned(),
+            value: MsgSend {
+                from_address: from.to_owned(),
+                to_address: to.to_owned(),
+                amount: vec![Coin::new(amount, REMOTE_DENOM).into()],
+            }
+            .encode_to_vec(),
+        };
+
+        let body = TxBody {
+            messages: vec![
+                send("alice", "ica", 10),
+                send("bob", "someone_else", 20),
+                Any {
+                    type_url: "/cosmos.staking.v1beta1.MsgDelegate".to_owned(),
+                    value: vec![],
+                },
+            ],
+        };
+
+        let tx_data = TxRaw {
+            body_bytes: body.encode_to_vec(),
+        }
+        .encode_to_vec();
+
+        assert_eq!(
+            parse_bank_sends_to(&tx_data, "ica"),
+            Some(vec![(
+                "alice".to_owned(),
+                vec![Coin::new(10, REMOTE_DENOM)]
+            )])
+        );
+
+        assert_eq!(parse_bank_sends_to(b"not a tx", "ica"), None);
+
+        // the module only proves the tx was included on the remote chain, a relayer can submit any tx as a result of
+        // the transfers ICQ & only the sends matching its filter are recorded
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_icq_ica_idx(storage, &7, 0);
+        state::set_ica_transfers_icq_id(storage, &0, 7);
+
+        let height = || Height {
+            revision_number: 0,
+            revision_height: 42,
+        };
+
+        let res = sudo_tx_query_result(deps.as_mut(), 7, height(), Binary::from(tx_data)).unwrap();
+
+        assert_eq!(res.events.len(), 1);
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::ica_remote_deposit_count(storage, &0), Some(1));
+        assert_eq!(
+            state::ica_deposit_idx_remote_deposit(storage, &combine_u32s(0, 0)),
+            Some(RemoteDeposit {
+                idx: 0,
+                sender: "alice".to_owned(),
+                coins: vec![Coin::new(10, REMOTE_DENOM)],
+                remote_height: 42,
+            })
+        );
+
+        assert!(matches!(
+            sudo_tx_query_result(
+                deps.as_mut(),
+                7,
+                height(),
+                Binary::from(b"not a tx".as_slice())
+            ),
+            Err(Error::MalformedTxQueryResult { icq_id: 7 })
+        ));
+    }
+
+    #[test]
+    fn ics20_traces_checked_against_retrieval_path() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_remote_denom(storage, REMOTE_DENOM);
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+        state::set_rx_hash_expected_path(
+            storage,
+            "ROUTED",
+            &"transfer/channel-9/transfer/channel-3".to_owned(),
+        );
+
+        let trace = |path: &str, base_denom: &str| DenomTrace {
+            path: path.to_owned(),
+            base_denom: base_denom.to_owned(),
+        };
+
+        let direct_path = format!("transfer/{TRANSFER_CHANNEL}");
+
+        ensure_ics20_trace(
+            storage,
+            "DIRECT",
+            HOST_IBC_DENOM,
+            &trace(&direct_path, REMOTE_DENOM),
+        )
+        .unwrap();
+
+        // forwarded retrievals arrive over the route's path
+        ensure_ics20_trace(
+            storage,
+            "ROUTED",
+            "ibc/FORWARDED",
+            &trace("transfer/channel-9/transfer/channel-3", REMOTE_DENOM),
+        )
+        .unwrap();
+
+        for (rx_hash, trace) in [
+            ("DIRECT", trace("transfer/channel-9", REMOTE_DENOM)),
+            ("DIRECT", trace(&direct_path, "uother")),
+            (
+                "DIRECT",
+                trace(&format!("{direct_path}/transfer/channel-3"), REMOTE_DENOM),
+            ),
+            (
+                "ROUTED",
+                trace("transfer/channel-9/transfer/channel-3", "uother"),
+            ),
+            (
+                "ROUTED",
+                trace("transfer/channel-7/transfer/channel-3", REMOTE_DENOM),
+            ),
+            ("ROUTED", trace("transfer/channel-9", REMOTE_DENOM)),
+            (
+                "ROUTED",
+                trace("transfer/channel-9/transfer/channel-4", REMOTE_DENOM),
+            ),
+        ] {
+            assert!(matches!(
+                ensure_ics20_trace(storage, rx_hash, "ibc/FORWARDED", &trace),
+                Err(Error::UnverifiedIcs20Receipt { .. })
+            ));
+        }
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    struct DenomTraceResponse {
+        denom_trace: DenomTrace,
+    }
+
+    #[test]
+    fn ics20_receipts_verified_against_transfer_module() {
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_remote_denom(storage, REMOTE_DENOM);
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+
+        let trace = |path: String| DenomTrace {
+            path,
+            base_denom: REMOTE_DENOM.to_owned(),
+        };
+
+        let traces = [
+            (
+                HOST_IBC_DENOM.trim_start_matches("ibc/").to_owned(),
+                trace(format!("transfer/{TRANSFER_CHANNEL}")),
+            ),
+            ("OTHER".to_owned(), trace("transfer/channel-9".to_owned())),
+        ];
+
+        let querier = StargateQuerier {
+            base: MockQuerier::new(&[]),
+            handler: |path: &str, data: &[u8]| {
+                assert_eq!(path, "/ibc.applications.transfer.v1.Query/DenomTrace");
+
+                // the request only holds the hash, a length-delimited string at field 1
+                let hash = std::str::from_utf8(&data[2..]).unwrap();
+
+                match traces.iter().find(|(h, _)| h == hash) {
+                    Some((_, trace)) => ContractResult::Ok(
+                        to_binary(&DenomTraceResponse {
+                            denom_trace: trace.clone(),
+                        })
+                        .unwrap(),
+                    ),
+                    None => ContractResult::Err(format!("no trace for {hash}")),
+                }
+            },
+        };
+
+        let deps: Deps<NeutronQuery> = Deps {
+            storage: &deps.storage,
+            api: &deps.api,
+            querier: QuerierWrapper::new(&querier),
+        };
+
+        verify_ics20_receipt(deps, "RX", &[Coin::new(10, HOST_IBC_DENOM)]).unwrap();
+
+        for denom in ["ibc/OTHER", "ibc/UNKNOWN", REMOTE_DENOM] {
+            assert!(matches!(
+                verify_ics20_receipt(deps, "RX", &[Coin::new(10, HOST_IBC_DENOM), Coin::new(10, denom)]),
+                Err(Error::UnverifiedIcs20Receipt { denom: unverified, .. }) if unverified == denom
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_retrieval_hooks_held_as_dead_letters() {
+        let mut deps = mock_dependencies();
+
+        let env = mock_env();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_rx_hash_ica_idx(storage, "RX", 0);
+        state::set_rx_hash_expected_amount(storage, "RX", &Uint128::new(10));
+
+        let funds = |amount| vec![Coin::new(amount, HOST_IBC_DENOM)];
+
+        assert_eq!(
+            validate_retrieved_funds(storage, "RX", &funds(10)).unwrap(),
+            0
+        );
+
+        let err = validate_retrieved_funds(storage, "RX", &funds(9)).unwrap_err();
+
+        record_hook_dead_letter(storage, &env, mock_info("hooks", &funds(9)), "RX", &err);
+
+        // the hooks of rx hashes the contract never issued are not held, their funds are returned
+        assert!(matches!(
+            execute_funds_retrieved_hook(
+                deps.as_mut(),
+                env,
+                mock_info("hooks", &funds(10)),
+                "UNKNOWN"
+            ),
+            Err(Error::InvalidRxHash)
+        ));
+
+        let reasons: Vec<_> = state::dead_letter_hook_range(deps.as_ref().storage, None, 10)
+            .into_iter()
+            .map(|(_, dead_letter)| dead_letter.reason)
+            .collect();
+
+        assert_eq!(
+            reasons,
+            [Error::RetrievedAmountMismatch {
+                expected: Uint128::new(10),
+                received: Uint128::new(9)
+            }
+            .to_string()]
+        );
+    }
+
+    #[test]
+    fn retrieval_hooks_consumed_once() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_rx_hash_ica_idx(storage, "RX", 0);
+        state::set_rx_hash_expected_amount(storage, "RX", &Uint128::new(10));
+        state::set_rx_hash_expected_denom(storage, "RX", HOST_IBC_DENOM);
+
+        let hooks = ibc_hooks_sender(TRANSFER_CHANNEL, "ica", HOOK_SENDER_BECH32_PREFIX);
+
+        let expected_funds = vec![Coin::new(10, HOST_IBC_DENOM)];
+
+        let mut unexpected_funds = expected_funds.clone();
+        unexpected_funds.push(Coin::new(1, "uother"));
+
+        let hook = |deps: DepsMut, funds: &[Coin]| {
+            execute_funds_retrieved_hook(deps, mock_env(), mock_info(&hooks, funds), "RX").unwrap()
+        };
+
+        let res = hook(deps.as_mut(), &unexpected_funds);
+
+        assert!(res.messages.is_empty(), "held as a dead letter");
+        assert_eq!(
+            state::rx_hash_ica_idx(deps.as_ref().storage, "RX"),
+            Some(0),
+            "a rejected hook does not consume the retrieval"
+        );
+
+        let res = hook(deps.as_mut(), &expected_funds);
+
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_owned(),
+                amount: expected_funds.clone(),
+            }
+            .into()
+        );
+        assert_eq!(state::rx_hash_ica_idx(deps.as_ref().storage, "RX"), None);
+
+        // a replayed hook errors, returning its funds
+        assert!(matches!(
+            execute_funds_retrieved_hook(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(&hooks, &expected_funds),
+                "RX"
+            ),
+            Err(Error::InvalidRxHash)
+        ));
+
+        let reasons: Vec<_> = state::dead_letter_hook_range(deps.as_ref().storage, None, 10)
+            .into_iter()
+            .map(|(_, dead_letter)| dead_letter.reason)
+            .collect();
+
+        assert_eq!(
+            reasons,
+            [Error::UnexpectedRetrievedDenom {
+                expected: HOST_IBC_DENOM.to_owned(),
+                received: "uother".to_owned()
+            }
+            .to_string()]
+        );
+    }
+
+    #[test]
+    fn spoofed_hook_callers_rejected() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_rx_hash_ica_idx(storage, "RX", 0);
+        state::set_rx_hash_expected_amount(storage, "RX", &Uint128::new(10));
+        state::set_rx_hash_expected_denom(storage, "RX", HOST_IBC_DENOM);
+
+        let funds = vec![Coin::new(10, HOST_IBC_DENOM)];
+
+        for sender in [
+            "attacker".to_owned(),
+            // the same ICA address relayed over another channel
+            ibc_hooks_sender("channel-99", "ica", HOOK_SENDER_BECH32_PREFIX),
+            // another remote sender relayed over the transfer channel
+            ibc_hooks_sender(TRANSFER_CHANNEL, "other_ica", HOOK_SENDER_BECH32_PREFIX),
+        ] {
+            let err = execute_funds_retrieved_hook(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(&sender, &funds),
+                "RX",
+            )
+            .unwrap_err();
+
+            assert!(matches!(
+                err,
+                Error::UnexpectedHookSender { ica_idx: 0, .. }
+            ));
+        }
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::rx_hash_ica_idx(storage, "RX"), Some(0));
+        assert_eq!(state::hook_dead_letter_count(storage), None);
+
+        let hooks = ibc_hooks_sender(TRANSFER_CHANNEL, "ica", HOOK_SENDER_BECH32_PREFIX);
+
+        execute_funds_retrieved_hook(deps.as_mut(), mock_env(), mock_info(&hooks, &funds), "RX")
+            .unwrap();
+
+        assert_eq!(state::rx_hash_ica_idx(deps.as_ref().storage, "RX"), None);
+    }
+
+    #[test]
+    fn routed_retrievals_expect_the_last_hop_as_hook_sender() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, CONNECTION_ID);
+        state::set_remote_denom(storage, REMOTE_DENOM);
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+        state::set_contract_owner(storage, "admin");
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+
+        let routed_denom =
+            ics20_voucher_denom("transfer/channel-3/transfer/channel-5", REMOTE_DENOM);
+
+        let route = RetrievalRoute {
+            source_channel: "channel-1".to_owned(),
+            hops: vec![RetrievalHop {
+                receive_channel: "channel-5".to_owned(),
+                channel: "channel-2".to_owned(),
+                bech32_prefix: "osmo".to_owned(),
+            }],
+            host_channel: "channel-3".to_owned(),
+            host_denom: routed_denom.clone(),
+        };
+
+        assert_eq!(
+            retrieval_route_path(&route),
+            "transfer/channel-3/transfer/channel-5"
+        );
+
+        assert!(matches!(
+            validate_retrieval_route(
+                storage,
+                &RetrievalRoute {
+                    hops: vec![],
+                    ..route.clone()
+                }
+            ),
+            Err(Error::InvalidRetrievalHops { hops: 0 })
+        ));
+
+        // the host denom must be the voucher denom of the route's path
+        assert!(matches!(
+            validate_retrieval_route(
+                storage,
+                &RetrievalRoute {
+                    host_denom: HOST_IBC_DENOM.to_owned(),
+                    ..route.clone()
+                }
+            ),
+            Err(Error::RetrievalRouteDenomMismatch { .. })
+        ));
+
+        // only the contract owner allows routes
+        assert!(matches!(
+            execute_set_retrieval_route(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                "osmosis".to_owned(),
+                Some(route.clone()),
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        let res = execute_set_retrieval_route(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            "osmosis".to_owned(),
+            Some(route.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(res.events[0].ty, "retrieval_route_set");
+        assert_eq!(
+            state::retrieval_route(deps.as_ref().storage, "osmosis"),
+            Some(route.clone())
+        );
+
+        let fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![Coin::new(1, IBC_FEE_DENOM)],
+            timeout_fee: vec![Coin::new(1, IBC_FEE_DENOM)],
+        };
+
+        issue_retrieve_tx(
+            deps.as_mut(),
+            &mock_env(),
+            0,
+            Coin::new(10, REMOTE_DENOM),
+            fee,
+            "owner",
+            Some(route.clone()),
+        )
+        .unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        let (rx_hash, hook_sender) = state::rx_hash_hook_sender_range(storage, None, 10)
+            .pop()
+            .unwrap();
+
+        // the funds are forwarded from the address packet-forward-middleware derives for the ICA on the hop
+        assert_eq!(
+            hook_sender,
+            ibc_hooks_sender(
+                "channel-3",
+                &pfm_receiver("channel-5", "ica", "osmo"),
+                HOOK_SENDER_BECH32_PREFIX
+            )
+        );
+        assert_eq!(
+            state::rx_hash_expected_path(storage, &rx_hash).as_deref(),
+            Some("transfer/channel-3/transfer/channel-5")
+        );
+
+        let pending = state::ica_pending_txs(storage, &0).unwrap();
+
+        assert_eq!(pending[0].route, Some(route));
+
+        let funds = vec![Coin::new(10, routed_denom)];
+
+        let direct_sender = ibc_hooks_sender(TRANSFER_CHANNEL, "ica", HOOK_SENDER_BECH32_PREFIX);
+
+        assert!(matches!(
+            execute_funds_retrieved_hook(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(&direct_sender, &funds),
+                &rx_hash,
+            ),
+            Err(Error::UnexpectedHookSender { .. })
+        ));
+
+        let res = execute_funds_retrieved_hook(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&hook_sender, &funds),
+            &rx_hash,
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_owned(),
+                amount: funds,
+            }
+            .into()
+        );
+        assert_eq!(
+            state::rx_hash_hook_sender(deps.as_ref().storage, &rx_hash),
+            None
+        );
+    }
+
+    #[test]
+    fn ownership_handed_over_in_two_steps() {
+        let mut deps = mock_dependencies();
+
+        let (owner, new_owner) = (Addr::unchecked("owner"), Addr::unchecked("new_owner"));
+
+        state::set_contract_owner(deps.as_mut().storage, owner.as_str());
+
+        assert!(matches!(
+            execute_transfer_ownership(deps.as_mut(), mock_info("new_owner", &[]), "x".to_owned()),
+            Err(Error::Unauthorized)
+        ));
+
+        execute_transfer_ownership(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            "new_owner".to_owned(),
+        )
+        .unwrap();
+
+        // the proposal alone doesn't hand over ownership
+        assert!(ensure_contract_owner(deps.as_ref().storage, &owner).is_ok());
+
+        assert!(matches!(
+            execute_accept_ownership(deps.as_mut(), mock_info("someone", &[])),
+            Err(Error::Unauthorized)
+        ));
+
+        execute_accept_ownership(deps.as_mut(), mock_info("new_owner", &[])).unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        assert!(ensure_contract_owner(storage, &new_owner).is_ok());
+        assert!(ensure_contract_owner(storage, &owner).is_err());
+        assert_eq!(state::pending_contract_owner(storage), None);
+    }
+
+    #[test]
+    fn rebuild_indexes_resumes_until_every_ica_visited() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_owner(storage, "owner");
+        state::set_ica_count(storage, 3);
+        state::set_ica_owner(storage, &0, &"owner_a".to_owned());
+        state::set_ica_icq_id(storage, &0, 7);
+        state::set_ica_owner(storage, &2, &"owner_c".to_owned());
+        state::set_ica_transfers_icq_id(storage, &2, 9);
+        state::set_ica_pending_tx_hashes(storage, &2, &vec!["hash".to_owned()]);
+        state::set_ica_listed_owner(storage, &2, &"owner_c".to_owned());
+
+        assert!(matches!(
+            execute_rebuild_indexes(deps.as_mut(), mock_info("someone", &[]), 2),
+            Err(Error::Unauthorized)
+        ));
+
+        let res = execute_rebuild_indexes(deps.as_mut(), mock_info("owner", &[]), 2).unwrap();
+
+        assert!(res.events[0]
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "restored" && attr.value == "3"));
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::owner_ica_idx(storage, "owner_a"), Some(0));
+        assert_eq!(state::icq_ica_idx(storage, &7), Some(0));
+        assert_eq!(state::owner_ica_idx(storage, "owner_c"), None);
+        assert_eq!(state::rebuild_cursor(storage), Some(2));
+
+        let res = execute_rebuild_indexes(deps.as_mut(), mock_info("owner", &[]), 2).unwrap();
+
+        assert!(res.events[0]
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "restored" && attr.value == "3"));
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::owner_ica_idx(storage, "owner_c"), Some(2));
+        assert_eq!(state::icq_ica_idx(storage, &9), Some(2));
+        assert_eq!(state::tx_hash_ica_idx(storage, "hash"), Some(2));
+        assert_eq!(state::rebuild_cursor(storage), None);
+    }
+
+    #[test]
+    fn only_the_owner_pauses() {
+        let mut deps = mock_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        assert!(matches!(
+            execute_set_paused(deps.as_mut(), mock_info("someone", &[]), true),
+            Err(Error::Unauthorized)
+        ));
+
+        execute_set_paused(deps.as_mut(), mock_info("owner", &[]), true).unwrap();
+
+        assert!(is_paused(deps.as_ref().storage));
+
+        execute_set_paused(deps.as_mut(), mock_info("owner", &[]), false).unwrap();
+
+        assert!(!is_paused(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn paused_rejects_every_tx_issuing_msg() {
+        let min_fee = r#"{"min_fee":{"recv_fee":[],"ack_fee":[{"denom":"untrn","amount":"1000"}],"timeout_fee":[{"denom":"untrn","amount":"1000"}]}}"#;
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let NeutronQuery::MinIbcFee {} = query else {
+                unimplemented!("unexpected query: {query:?}");
+            };
+
+            SystemResult::Ok(ContractResult::Ok(min_fee.as_bytes().into()))
+        });
+
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_owner(storage, "owner");
+        state::set_icq_deposit_override(storage, &vec![Coin::new(1000, IBC_FEE_DENOM)]);
+        state::set_paused(storage, &true);
+
+        let msgs = [
+            ExecuteMsg::SetupIca {},
+            ExecuteMsg::ReopenIca {},
+            ExecuteMsg::TransferFunds {
+                memo: None,
+                owner: None,
+            },
+            ExecuteMsg::RetrieveFunds {
+                amount: None,
+                owner: None,
+            },
+            ExecuteMsg::RetrieveFundsFor {
+                owners: vec!["owner".to_owned()],
+            },
+            ExecuteMsg::RetrieveFundsVia {
+                route: "route".to_owned(),
+            },
+            ExecuteMsg::SubmitIcaTx {
+                msgs: vec![],
+                timeout: None,
+            },
+            ExecuteMsg::RetryLastTx {
+                tx_kind: TxKind::Transfer,
+            },
+            ExecuteMsg::DelegateFromIca {
+                validator: "validator".to_owned(),
+                amount: Uint128::new(10),
+            },
+            ExecuteMsg::UndelegateFromIca {
+                validator: "validator".to_owned(),
+                amount: Uint128::new(10),
+            },
+            ExecuteMsg::ClaimRewards {},
+            ExecuteMsg::RetrieveClaimedRewards {
+                ica_idx: 0,
+                amount: Uint128::new(10),
+            },
+        ];
+
+        for msg in msgs {
+            assert!(
+                matches!(
+                    execute_msg(
+                        deps.as_mut(),
+                        mock_env(),
+                        mock_info("owner", &[]),
+                        msg.clone()
+                    ),
+                    Err(Error::Paused)
+                ),
+                "{msg:?} was not paused"
+            );
+        }
+
+        // managing fee balances issues no tx
+        assert!(execute_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[Coin::new(1000, IBC_FEE_DENOM)]),
+            ExecuteMsg::DepositFees {},
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn retrieve_for_skips_owners_without_retrievable_funds() {
+        let mut deps = mock_neutron_dependencies();
+
+        let env = mock_env();
+
+        let too_many_owners = (0..=MAX_RETRIEVE_FOR_OWNERS)
+            .map(|i| format!("owner{i}"))
+            .collect();
+
+        assert!(matches!(
+            execute_retrieve_funds_for(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("keeper", &[]),
+                too_many_owners
+            ),
+            Err(Error::TooManyOwners { .. })
+        ));
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, "registering", 0);
+        set_ica_status(storage, 0, IcaStatus::Registering, 1);
+
+        state::set_owner_ica_idx(storage, "no_icq", 1);
+        set_ica_status(storage, 1, IcaStatus::Active, 1);
+
+        state::set_owner_ica_idx(storage, "in_flight", 2);
+        set_ica_status(storage, 2, IcaStatus::Active, 1);
+        state::set_ica_tx_issued_count(storage, &2, 1);
+
+        let owners = ["unknown", "registering", "no_icq", "in_flight", "no_icq"]
+            .map(str::to_owned)
+            .to_vec();
+
+        let res = execute_retrieve_funds_for(deps.as_mut(), env, mock_info("keeper", &[]), owners)
+            .unwrap();
+
+        assert!(res.messages.is_empty());
+
+        let skipped: Vec<_> = res
+            .events
+            .iter()
+            .map(|event| {
+                assert_eq!(event.ty, "retrieve_skipped");
+
+                (
+                    event.attributes[0].value.as_str(),
+                    event.attributes[1].value.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            skipped,
+            [
+                ("in_flight", "txs_in_flight"),
+                ("no_icq", "balance_icq_unavailable"),
+                ("registering", "ica_not_active"),
+                ("unknown", "no_ica"),
+            ]
+        );
+    }
+
+    #[test]
+    fn retrieve_for_charges_the_keeper() {
+        let balance = RawCoin {
+            denom: REMOTE_DENOM.to_owned(),
+            amount: "10".to_owned(),
+        };
+
+        let registered_query = format!(
+            r#"{{"registered_query":{{"id":7,"owner":"contract","query_type":"kv","keys":[],"transactions_filter":"","connection_id":"{CONNECTION_ID}","update_period":6,"last_submitted_result_local_height":12}}}}"#
+        );
+
+        let query_result = format!(
+            r#"{{"result":{{"kv_results":[{{"storage_prefix":"bank","key":"","value":"{}"}}],"height":12,"revision":0}}}}"#,
+            Binary::from(balance.encode_to_vec()).to_base64()
+        );
+
+        let min_fee = r#"{"min_fee":{"recv_fee":[],"ack_fee":[{"denom":"untrn","amount":"1000"}],"timeout_fee":[{"denom":"untrn","amount":"1000"}]}}"#;
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let res = match query {
+                NeutronQuery::MinIbcFee {} => min_fee.to_owned(),
+                NeutronQuery::RegisteredInterchainQuery { query_id: 7 } => registered_query.clone(),
+                NeutronQuery::InterchainQueryResult { query_id: 7 } => query_result.clone(),
+                _ => unimplemented!("unexpected query: {query:?}"),
+            };
+
+            SystemResult::Ok(ContractResult::Ok(res.into_bytes().into()))
+        });
+
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, CONNECTION_ID);
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_owner_ica_idx(storage, "owner", 0);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_ica_icq_id(storage, &0, 7);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        let res = execute_retrieve_funds_for(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[Coin::new(2000, IBC_FEE_DENOM)]),
+            vec!["owner".to_owned()],
+        )
+        .unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+
+        let storage = deps.as_ref().storage;
+
+        // the keeper paid the fee from its own deposit & is credited the unused part of it
+        let pending = state::ica_pending_txs(storage, &0).unwrap();
+
+        assert_eq!(pending[0].payer.as_deref(), Some("keeper"));
+        assert_eq!(pending[0].coin, Coin::new(10, REMOTE_DENOM));
+        assert_eq!(state::owner_fee_balance(storage, "keeper"), Some(vec![]));
+        assert_eq!(state::owner_fee_balance(storage, "owner"), None);
+    }
+
+    #[test]
+    fn auto_retrieve_deferred_until_balance_submitted_after_settlement() {
+        let balance = RawCoin {
+            denom: REMOTE_DENOM.to_owned(),
+            amount: "10".to_owned(),
+        };
+
+        let registered_query = format!(
+            r#"{{"registered_query":{{"id":7,"owner":"contract","query_type":"kv","keys":[],"transactions_filter":"","connection_id":"{CONNECTION_ID}","update_period":6,"last_submitted_result_local_height":12}}}}"#
+        );
+
+        let query_result = format!(
+            r#"{{"result":{{"kv_results":[{{"storage_prefix":"bank","key":"","value":"{}"}}],"height":12,"revision":0}}}}"#,
+            Binary::from(balance.encode_to_vec()).to_base64()
+        );
+
+        let min_fee = r#"{"min_fee":{"recv_fee":[],"ack_fee":[{"denom":"untrn","amount":"1000"}],"timeout_fee":[{"denom":"untrn","amount":"1000"}]}}"#;
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let res = match query {
+                NeutronQuery::MinIbcFee {} => min_fee.to_owned(),
+                NeutronQuery::RegisteredInterchainQuery { query_id: 7 } => registered_query.clone(),
+                NeutronQuery::InterchainQueryResult { query_id: 7 } => query_result.clone(),
+                _ => unimplemented!("unexpected query: {query:?}"),
+            };
+
+            SystemResult::Ok(ContractResult::Ok(res.into_bytes().into()))
+        });
+
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_owner_ica_idx(storage, "owner", 0);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_ica_icq_id(storage, &0, 7);
+        state::set_icq_ica_idx(storage, &7, 0);
+        state::set_ica_auto_retrieve(storage, &0, &true);
+        state::set_ica_settled_height(storage, &0, 12);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        // the balance may still include funds retrieved by the tx settled at the same height
+        let res = sudo_kv_query_result(deps.as_mut(), mock_env(), 7).unwrap();
+
+        assert!(res.messages.is_empty());
+        assert_eq!(res.events[0].ty, "auto_retrieve_deferred");
+
+        state::set_ica_settled_height(deps.as_mut().storage, &0, 11);
+
+        // the fresh balance is acted on, though the owner has no fee balance to pay for the retrieval
+        let res = sudo_kv_query_result(deps.as_mut(), mock_env(), 7).unwrap();
+
+        assert!(res.messages.is_empty());
+        assert_eq!(res.events[0].ty, "auto_retrieve_skipped");
+    }
+
+    #[test]
+    fn transfers_icq_filtered_from_the_last_remote_height() {
+        let query_result = r#"{"result":{"kv_results":[],"height":12,"revision":0}}"#;
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let res = match query {
+                NeutronQuery::InterchainQueryResult { query_id: 7 } => query_result,
+                _ => unimplemented!("unexpected query: {query:?}"),
+            };
+
+            SystemResult::Ok(ContractResult::Ok(res.as_bytes().into()))
+        });
+
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, CONNECTION_ID);
+        state::set_icq_update_period(storage, 6);
+        state::set_icq_deposit_override(storage, &vec![Coin::new(1000, IBC_FEE_DENOM)]);
+        state::set_owner_ica_idx(storage, "owner", 0);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_ica_icq_id(storage, &0, 7);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        let res = execute_register_transfers_query(
+            deps.as_mut(),
+            mock_info("owner", &[Coin::new(1000, IBC_FEE_DENOM)]),
+        )
+        .unwrap();
+
+        // the remote height of the balance result, not the local height
+        let expected = new_register_transfers_query_msg(
+            CONNECTION_ID.to_owned(),
+            "ica".to_owned(),
+            6,
+            Some(12),
+        )
+        .unwrap();
+
+        assert_eq!(res.messages[0].msg, CosmosMsg::Custom(expected));
+    }
+
+    /// Mock deps of the active ICA of "owner", its balance ICQ 7 reporting `balance` of the remote denom in a result
+    /// submitted at the local `submitted_height`
+    fn teardown_deps(
+        balance: u128,
+        submitted_height: u64,
+    ) -> OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery> {
+        let balance = RawCoin {
+            denom: REMOTE_DENOM.to_owned(),
+            amount: balance.to_string(),
+        };
+
+        let registered_query = format!(
+            r#"{{"registered_query":{{"id":7,"owner":"contract","query_type":"kv","keys":[],"transactions_filter":"","connection_id":"{CONNECTION_ID}","update_period":6,"last_submitted_result_local_height":{submitted_height},"deposit":[{{"denom":"untrn","amount":"1000"}}]}}}}"#
+        );
+
+        let query_result = format!(
+            r#"{{"result":{{"kv_results":[{{"storage_prefix":"bank","key":"","value":"{}"}}],"height":12,"revision":0}}}}"#,
+            Binary::from(balance.encode_to_vec()).to_base64()
+        );
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let res = match query {
+                NeutronQuery::RegisteredInterchainQuery { query_id: 7 } => registered_query.clone(),
+                NeutronQuery::InterchainQueryResult { query_id: 7 } => query_result.clone(),
+                // a delegations ICQ without any result
+                NeutronQuery::RegisteredInterchainQuery { query_id: 8 } => registered_query
+                    .replace(
+                        &format!(r#""last_submitted_result_local_height":{submitted_height}"#),
+                        r#""last_submitted_result_local_height":0"#,
+                    ),
+                _ => unimplemented!("unexpected query: {query:?}"),
+            };
+
+            SystemResult::Ok(ContractResult::Ok(res.into_bytes().into()))
+        });
+
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, CONNECTION_ID);
+        state::set_icq_ica_idx(storage, &7, 0);
+        state::set_owner_ica_idx(storage, "owner", 0);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_ica_addr(storage, &0, "ica");
+        state::set_ica_icq_id(storage, &0, 7);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        deps
+    }
+
+    #[test]
+    fn setup_refunds_coins_of_other_denoms_than_the_deposit() {
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, CONNECTION_ID);
+        state::set_icq_deposit_override(storage, &vec![Coin::new(1000, IBC_FEE_DENOM)]);
+
+        let funds = [Coin::new(1500, IBC_FEE_DENOM), Coin::new(7, HOST_IBC_DENOM)];
+
+        let res = execute_setup_ica(deps.as_mut(), mock_env(), mock_info("owner", &funds)).unwrap();
+
+        assert_eq!(
+            res.messages[1].msg,
+            BankMsg::Send {
+                to_address: "owner".to_owned(),
+                amount: vec![Coin::new(7, HOST_IBC_DENOM)],
+            }
+            .into()
+        );
+
+        // the whole deposit attached is escrowed until the ICQ is registered
+        assert_eq!(
+            state::ica_setup_deposit(deps.as_ref().storage, &0),
+            Some(vec![Coin::new(1500, IBC_FEE_DENOM)])
+        );
+    }
+
+    #[test]
+    fn roundtrip_fee_estimate_covers_both_legs_and_icq_relaying() {
+        let min_fee = r#"{"min_fee":{"recv_fee":[],"ack_fee":[{"denom":"untrn","amount":"1000"}],"timeout_fee":[{"denom":"untrn","amount":"1500"}]}}"#;
+
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+                let NeutronQuery::MinIbcFee {} = query else {
+                    unimplemented!("unexpected query: {query:?}");
+                };
+
+                SystemResult::Ok(ContractResult::Ok(min_fee.as_bytes().into()))
+            }),
+            custom_query_type: std::marker::PhantomData,
+        };
+
+        let storage = deps.as_mut().storage;
+
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_icq_update_period(storage, 6);
+        state::set_icq_deposit_override(storage, &vec![Coin::new(1_000_000, IBC_FEE_DENOM)]);
+
+        let estimate = |coin: Coin, gas_price: Option<Decimal>| {
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::EstimateRoundtripFee { coin, gas_price },
+            )
+            .map(|res| from_binary::<RoundtripFeeEstimateResponse>(&res).unwrap())
+        };
+
+        let res = estimate(Coin::new(100, HOST_IBC_DENOM), None).unwrap();
+
+        assert_eq!(
+            res.required_fee_balance,
+            vec![Coin::new(5000, IBC_FEE_DENOM)]
+        );
+        assert_eq!(res.max_cost, vec![Coin::new(3000, IBC_FEE_DENOM)]);
+        assert_eq!(res.icq_deposit, vec![Coin::new(1_000_000, IBC_FEE_DENOM)]);
+        assert_eq!(res.icq_update_period, 6);
+
+        // 2 ICQ results of 400k gas at 0.0025untrn
+        assert_eq!(res.icq_relayer_overhead, Coin::new(2000, IBC_FEE_DENOM));
+        assert_eq!(res.total_untrn, Uint128::new(5000));
+
+        // the overhead is rounded up to a whole untrn
+        let res = estimate(
+            Coin::new(100, HOST_IBC_DENOM),
+            Some(Decimal::from_ratio(1u128, 10_000_000u128)),
+        )
+        .unwrap();
+
+        assert_eq!(res.icq_relayer_overhead, Coin::new(1, IBC_FEE_DENOM));
+        assert_eq!(res.total_untrn, Uint128::new(3001));
+
+        assert!(matches!(
+            estimate(Coin::new(100, REMOTE_DENOM), None),
+            Err(Error::DenomNotTransferable { denom }) if denom == REMOTE_DENOM
+        ));
+    }
+
+    #[test]
+    fn teardown_refused_until_ica_drained() {
+        let mut deps = teardown_deps(10, 12);
+
+        assert!(matches!(
+            execute_teardown_ica(deps.as_mut(), mock_env(), mock_info("owner", &[])),
+            Err(Error::IcaNotDrained { balance }) if balance == Coin::new(10, REMOTE_DENOM)
+        ));
+
+        let mut deps = teardown_deps(0, 12);
+
+        let storage = deps.as_mut().storage;
+
+        state::set_rx_hash_ica_idx(storage, "RX", 0);
+        state::set_ica_undelivered_retrievals(storage, &0, 1);
+
+        assert!(matches!(
+            execute_teardown_ica(deps.as_mut(), mock_env(), mock_info("owner", &[])),
+            Err(Error::RetrievalsUndelivered { undelivered: 1 })
+        ));
+
+        // the retrieval is delivered by the hook
+        consume_rx_hash(deps.as_mut().storage, "RX");
+
+        // but the last balance result predates the retrieval's settlement
+        state::set_ica_settled_height(deps.as_mut().storage, &0, 12);
+
+        assert!(matches!(
+            execute_teardown_ica(deps.as_mut(), mock_env(), mock_info("owner", &[])),
+            Err(Error::IcaBalanceUnknown { settled_height: 12 })
+        ));
+
+        state::set_ica_settled_height(deps.as_mut().storage, &0, 11);
+
+        let res = execute_teardown_ica(deps.as_mut(), mock_env(), mock_info("owner", &[])).unwrap();
+
+        assert_eq!(res.events[0].ty, "ica_torn_down");
+
+        assert_eq!(ica_status(deps.as_ref().storage, 0), IcaStatus::Retired);
+    }
+
+    #[test]
+    fn failed_retrieval_leaves_teardown_unblocked() {
+        let mut deps = teardown_deps(0, 12);
+
+        let mut env = mock_env();
+
+        // the retrieval settles before the last balance result
+        env.block.height = 10;
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ibc_transfer_channel(storage, TRANSFER_CHANNEL);
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+
+        let fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![Coin::new(1, IBC_FEE_DENOM)],
+            timeout_fee: vec![Coin::new(1, IBC_FEE_DENOM)],
+        };
+
+        let retrieve_msg = issue_retrieve_tx(
+            deps.as_mut(),
+            &env,
+            0,
+            Coin::new(10, REMOTE_DENOM),
+            fee,
+            "owner",
+            None,
+        )
+        .unwrap();
+
+        let issued_reply = Reply {
+            id: retrieve_msg.id,
+            result: cosmwasm_std::SubMsgResult::Ok(cosmwasm_std::SubMsgResponse {
+                events: vec![],
+                data: Some(Binary::from(
+                    br#"{"sequence_id":5,"channel":"channel-1"}"#.as_slice(),
+                )),
+            }),
+        };
+
+        reply(
+            DepsMut {
+                storage: &mut deps.storage,
+                api: &deps.api,
+                querier: QuerierWrapper::new(&MockQuerier::<Empty>::new(&[])),
+            },
+            env.clone(),
+            issued_reply,
+        )
+        .unwrap();
+
+        assert_eq!(
+            state::ica_undelivered_retrievals(deps.as_ref().storage, &0),
+            Some(1)
+        );
+
+        let packet = RequestPacket {
+            sequence: Some(5),
+            source_port: Some("icacontroller-contract.0".to_owned()),
+            source_channel: Some("channel-1".to_owned()),
+            destination_port: None,
+            destination_channel: None,
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        sudo_error(
+            deps.as_mut(),
+            env.clone(),
+            packet,
+            "insufficient funds".to_owned(),
+        )
+        .unwrap();
+
+        // the funds never left the ICA, so no hook will deliver them
+        assert_eq!(
+            state::ica_undelivered_retrievals(deps.as_ref().storage, &0),
+            Some(0)
+        );
+        assert_eq!(state::rx_hash_count(deps.as_ref().storage), Some(0));
+
+        let res = execute_teardown_ica(deps.as_mut(), env, mock_info("owner", &[])).unwrap();
+
+        assert_eq!(res.events[0].ty, "ica_torn_down");
+    }
+
+    #[test]
+    fn teardown_refused_while_staked() {
+        let mut deps = teardown_deps(0, 12);
+
+        let mut env = mock_env();
+
+        // the undelegation ack reports no completion time
+        record_unbonding(deps.as_mut().storage, &env, 0, &[]);
+
+        let until = env.block.time.plus_seconds(MAX_UNBONDING_SECONDS);
+
+        assert!(matches!(
+            execute_teardown_ica(deps.as_mut(), env.clone(), mock_info("owner", &[])),
+            Err(Error::IcaUnbonding { until: unbonding_until }) if unbonding_until == until
+        ));
+
+        // the first balance result once the unbonding completed may not report the unbonded funds yet
+        env.block.time = until;
+
+        sudo_kv_query_result(deps.as_mut(), env.clone(), 7).unwrap();
+
+        assert_eq!(state::ica_unbonding_until(deps.as_ref().storage, &0), None);
+
+        assert!(matches!(
+            execute_teardown_ica(deps.as_mut(), env.clone(), mock_info("owner", &[])),
+            Err(Error::IcaBalanceUnknown { settled_height }) if settled_height == env.block.height
+        ));
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_settled_height(storage, &0, 11);
+        state::set_ica_delegations_icq_id(storage, &0, 8);
+
+        assert!(matches!(
+            execute_teardown_ica(deps.as_mut(), env, mock_info("owner", &[])),
+            Err(Error::IcaDelegationsUnknown { settled_height: 11 })
+        ));
+    }
+
+    #[test]
+    fn unbonding_completion_parsed_from_ack_data() {
+        #[derive(Clone, PartialEq, Message)]
+        struct ProtoTimestamp {
+            #[prost(int64, tag = "1")]
+            pub seconds: i64,
+            #[prost(int32, tag = "2")]
+            pub nanos: i32,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        struct MsgUndelegateResponse {
+            #[prost(message, optional, tag = "1")]
+            pub completion_time: Option<ProtoTimestamp>,
+            #[prost(message, optional, tag = "2")]
+            pub amount: Option<RawCoin>,
+        }
+
+        let response = MsgUndelegateResponse {
+            completion_time: Some(ProtoTimestamp {
+                seconds: 1_700_000_000,
+                nanos: 5,
+            }),
+            amount: Some(Coin::new(1234, REMOTE_DENOM).into()),
+        };
+
+        let ack_data = msg_response_ack_data(
+            "/cosmos.staking.v1beta1.MsgUndelegateResponse",
+            response.encode_to_vec(),
+        );
+
+        assert_eq!(
+            parse_unbonding_completion(&ack_data),
+            Some(Timestamp::from_seconds(1_700_000_000).plus_nanos(5))
+        );
+
+        assert_eq!(parse_unbonding_completion(&[]), None);
+    }
+
+    #[test]
+    fn alerts_pushed_to_subscribed_contracts() {
+        let mut deps = mock_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr.starts_with("contract") => {
+                let info = ContractInfoResponse::new(1, "creator");
+
+                SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+            }
+
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "not a contract".to_owned(),
+            }),
+        });
+
+        assert!(matches!(
+            execute_subscribe_alerts(deps.as_mut(), mock_info("someone", &[])),
+            Err(Error::AlertSubscriberNotContract)
+        ));
+
+        // only the contracts approved by the owner can subscribe
+        assert!(matches!(
+            execute_subscribe_alerts(deps.as_mut(), mock_info("contract0", &[])),
+            Err(Error::AlertSubscriberNotApproved { subscriber }) if subscriber == "contract0"
+        ));
+
+        assert!(matches!(
+            execute_approve_alert_subscriber(
+                deps.as_mut(),
+                mock_info("contract0", &[]),
+                "contract0".to_owned()
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        for i in 0..MAX_ALERT_SUBSCRIBERS {
+            execute_approve_alert_subscriber(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                format!("contract{i}"),
+            )
+            .unwrap();
+
+            execute_subscribe_alerts(deps.as_mut(), mock_info(&format!("contract{i}"), &[]))
+                .unwrap();
+        }
+
+        // subscribing again is a no-op
+        execute_subscribe_alerts(deps.as_mut(), mock_info("contract0", &[])).unwrap();
+
+        assert!(matches!(
+            execute_approve_alert_subscriber(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                "contract_extra".to_owned()
+            ),
+            Err(Error::TooManyAlertSubscribers)
+        ));
+
+        revoke_alert_subscriber(deps.as_mut().storage, "contract1");
+
+        assert!(matches!(
+            execute_subscribe_alerts(deps.as_mut(), mock_info("contract1", &[])),
+            Err(Error::AlertSubscriberNotApproved { .. })
+        ));
+
+        let alert = ProbeAlert {
+            ica_idx: 3,
+            owner: "owner".to_owned(),
+            kind: AlertKind::TxTimeout,
+            seq_num: 7,
+            error: None,
+            channel_closed: true,
+        };
+
+        let msgs = alert_hook_msgs(deps.as_mut().storage, alert.clone()).unwrap();
+
+        let subscribers: Vec<_> = msgs
+            .iter()
+            .map(|sub_msg| {
+                assert_eq!(
+                    common::take_reply_payload(deps.as_mut().storage, sub_msg.id),
+                    Some(state::PendingReply {
+                        kind: state::ReplyKind::AlertHook,
+                        ica_idx: 3,
+                        coin: None,
+                    })
+                );
+                assert_eq!(sub_msg.gas_limit, Some(ALERT_HOOK_GAS_LIMIT));
+
+                let CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) = &sub_msg.msg
+                else {
+                    panic!("expected a wasm execute msg");
+                };
+
+                assert_eq!(
+                    from_binary::<AlertHookMsg>(msg).unwrap(),
+                    AlertHookMsg::ProbeAlert(alert.clone())
+                );
+
+                contract_addr.as_str()
+            })
+            .collect();
+
+        assert_eq!(subscribers, ["contract0", "contract2"]);
+    }
+
+    #[test]
+    fn transfer_amounts_bounded_by_limits_of_their_denom() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_owner(storage, "owner");
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_additional_transfer_denoms(storage, &vec!["uusdc".to_owned()]);
+
+        let limits = |denom: &str, min: Option<u128>, max: Option<u128>| DenomTransferLimits {
+            denom: denom.to_owned(),
+            min_transfer_amount: min.map(Uint128::new),
+            max_transfer_amount: max.map(Uint128::new),
+        };
+
+        let bounded = limits(HOST_IBC_DENOM, Some(10), Some(100));
+
+        assert!(matches!(
+            execute_update_transfer_limits(
+                deps.as_mut(),
+                mock_info("someone", &[]),
+                bounded.clone()
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        assert!(matches!(
+            execute_update_transfer_limits(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                limits(HOST_IBC_DENOM, Some(100), Some(10))
+            ),
+            Err(Error::InvalidTransferLimits { .. })
+        ));
+
+        assert!(matches!(
+            execute_update_transfer_limits(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                limits(REMOTE_DENOM, Some(10), None)
+            ),
+            Err(Error::DenomNotTransferable { denom }) if denom == REMOTE_DENOM
+        ));
+
+        let res =
+            execute_update_transfer_limits(deps.as_mut(), mock_info("owner", &[]), bounded.clone())
+                .unwrap();
+
+        assert_eq!(
+            res.events,
+            [Event::new("transfer_limits_updated")
+                .add_attribute("denom", HOST_IBC_DENOM)
+                .add_attribute("min_transfer_amount", "10")
+                .add_attribute("max_transfer_amount", "100")]
+        );
+
+        let storage = deps.as_ref().storage;
+
+        assert!(matches!(
+            ensure_transfer_amount_in_limits(storage, &Coin::new(9, HOST_IBC_DENOM)),
+            Err(Error::TransferBelowMinimum { .. })
+        ));
+        assert!(matches!(
+            ensure_transfer_amount_in_limits(storage, &Coin::new(101, HOST_IBC_DENOM)),
+            Err(Error::TransferAboveMaximum { .. })
+        ));
+        assert!(ensure_transfer_amount_in_limits(storage, &Coin::new(10, HOST_IBC_DENOM)).is_ok());
+        assert!(ensure_transfer_amount_in_limits(storage, &Coin::new(100, HOST_IBC_DENOM)).is_ok());
+
+        // the limits of one denom don't bound the others
+        assert!(ensure_transfer_amount_in_limits(storage, &Coin::new(1, "uusdc")).is_ok());
+        assert!(ensure_transfer_amount_in_limits(storage, &Coin::new(u128::MAX, "uusdc")).is_ok());
+
+        assert_eq!(
+            transfer_limits(storage),
+            TransferLimitsResponse {
+                limits: vec![bounded]
+            }
+        );
+
+        execute_update_transfer_limits(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            limits(HOST_IBC_DENOM, None, None),
+        )
+        .unwrap();
+
+        assert_eq!(
+            transfer_limits(deps.as_ref().storage),
+            TransferLimitsResponse::default()
+        );
+        assert!(ensure_transfer_amount_in_limits(
+            deps.as_ref().storage,
+            &Coin::new(u128::MAX, HOST_IBC_DENOM)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn migrate_v5_scopes_transfer_limits_to_each_transfer_denom() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_version(storage, 5);
+        state::set_host_ibc_denom(storage, HOST_IBC_DENOM);
+        state::set_additional_transfer_denoms(storage, &vec!["uusdc".to_owned()]);
+        state::v5::set_min_transfer_amount(storage, &Uint128::new(10));
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let storage = deps.as_ref().storage;
+
+        let limits = |denom: &str| DenomTransferLimits {
+            denom: denom.to_owned(),
+            min_transfer_amount: Some(Uint128::new(10)),
+            max_transfer_amount: None,
+        };
+
+        assert_eq!(
+            transfer_limits(storage).limits,
+            vec![limits(HOST_IBC_DENOM), limits("uusdc")]
+        );
+        assert_eq!(state::v5::min_transfer_amount(storage), None);
+    }
+
+    #[test]
+    fn memo_limits_bounded_by_ibc_go_limit() {
+        let mut deps = mock_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        assert_eq!(memo_limits(deps.as_ref().storage), MemoLimits::default());
+
+        assert!(matches!(
+            execute_update_memo_limits(deps.as_mut(), mock_info("someone", &[]), Some(512), None),
+            Err(Error::Unauthorized)
+        ));
+
+        for (max_memo_len, max_hook_memo_len) in [
+            (Some(0), Some(0)),
+            (Some(512), Some(1024)),
+            (Some(512), None),
+            (Some(u32::MAX), None),
+        ] {
+            assert!(matches!(
+                execute_update_memo_limits(
+                    deps.as_mut(),
+                    mock_info("owner", &[]),
+                    max_memo_len,
+                    max_hook_memo_len
+                ),
+                Err(Error::InvalidMemoLimits)
+            ));
+        }
+
+        let res = execute_update_memo_limits(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            Some(512),
+            Some(256),
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.events,
+            [Event::new("memo_limits_updated")
+                .add_attribute("max_memo_len", "512")
+                .add_attribute("max_hook_memo_len", "256")]
+        );
+
+        assert_eq!(
+            memo_limits(deps.as_ref().storage),
+            MemoLimits {
+                max_memo_len: 512,
+                max_hook_memo_len: 256,
+            }
+        );
+
+        execute_update_memo_limits(deps.as_mut(), mock_info("owner", &[]), None, Some(256))
+            .unwrap();
+
+        assert_eq!(
+            memo_limits(deps.as_ref().storage).max_memo_len,
+            MAX_IBC_MEMO_LEN
+        );
+    }
+
+    #[test]
+    fn icq_deposit_override_updated_by_owner() {
+        let mut deps = mock_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        let deposit = vec![Coin::new(1_000_000, "untrn")];
+
+        let update = |deps: DepsMut, sender: &str, deposit: Option<Vec<Coin>>| {
+            execute_update_icq_deposit_override(deps, mock_info(sender, &[]), deposit)
+        };
+
+        assert!(matches!(
+            update(deps.as_mut(), "someone", Some(deposit.clone())),
+            Err(Error::Unauthorized)
+        ));
+
+        assert!(matches!(
+            update(deps.as_mut(), "owner", Some(vec![])),
+            Err(Error::Validation(ValidationError::InvalidIcqDeposit(_)))
+        ));
+
+        let res = update(deps.as_mut(), "owner", Some(deposit.clone())).unwrap();
+
+        assert_eq!(res.events[0].ty, "icq_deposit_override_updated");
+        assert_eq!(res.events[0].attributes[0].value, "1000000untrn");
+        assert_eq!(
+            state::icq_deposit_override(deps.as_ref().storage),
+            Some(deposit)
+        );
+
+        update(deps.as_mut(), "owner", None).unwrap();
+
+        assert_eq!(state::icq_deposit_override(deps.as_ref().storage), None);
+    }
+
+    #[test]
+    fn owner_msgs_executed_while_fee_params_unavailable() {
+        // neither the min IBC fee nor the interchainqueries params can be queried, no ICQ deposit override is set
+        let mut deps = mock_neutron_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        let deposit = vec![Coin::new(1_000_000, IBC_FEE_DENOM)];
+
+        for msg in [
+            ExecuteMsg::SetPaused { paused: true },
+            ExecuteMsg::UpdateIcqDepositOverride {
+                deposit: Some(deposit.clone()),
+            },
+        ] {
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        }
+
+        let storage = deps.as_ref().storage;
+
+        assert!(is_paused(storage));
+        assert_eq!(state::icq_deposit_override(storage), Some(deposit));
+
+        // the min IBC fee still cannot be queried, so nothing was observed
+        assert_eq!(state::fee_history(storage), None);
+    }
+
+    #[test]
+    fn hash_mappings_swept_after_retention_window() {
+        use state::HashKind::{Rx, Tx};
+
+        let mut deps = mock_dependencies();
+
+        state::set_contract_owner(deps.as_mut().storage, "owner");
+
+        assert!(matches!(
+            execute_update_hash_retention(deps.as_mut(), mock_info("owner", &[]), Some(0)),
+            Err(Error::ZeroHashRetention)
+        ));
+
+        let res = execute_update_hash_retention(deps.as_mut(), mock_info("owner", &[]), Some(100))
+            .unwrap();
+
+        assert_eq!(res.events[0].ty, "hash_retention_updated");
+        assert_eq!(res.events[0].attributes[0].value, "100");
+
+        let storage = deps.as_mut().storage;
+
+        state::set_tx_hash_ica_idx(storage, "TX0", 0);
+        stamp_hash(storage, Tx, "TX0", 10);
+
+        state::set_rx_hash_ica_idx(storage, "RX0", 0);
+        stamp_hash(storage, Rx, "RX0", 20);
+
+        state::set_tx_hash_ica_idx(storage, "TX1", 0);
+        stamp_hash(storage, Tx, "TX1", 30);
+
+        track_pending_tx(
+            storage,
+            0,
+            PendingTx {
+                tx_hash: "TX1".to_owned(),
+                kind: TxKind::Transfer,
+                coin: Some(Coin::new(10, HOST_IBC_DENOM)),
+                issued_height: 30,
+                timeout_height: None,
+                timeout_timestamp: None,
+            },
+        );
+
+        // the retrieval of another ICA, still awaiting its acknowledgement
+        state::set_rx_hash_ica_idx(storage, "RX2", 1);
+        stamp_hash(storage, Rx, "RX2", 40);
+
+        track_pending_tx(
+            storage,
+            1,
+            PendingTx {
+                tx_hash: "TX2".to_owned(),
+                kind: TxKind::Retrieve,
+                coin: Some(Coin::new(10, HOST_IBC_DENOM)),
+                issued_height: 40,
+                timeout_height: None,
+                timeout_timestamp: None,
+            },
+        );
+
+        state::set_rx_hash_ica_idx(storage, "RX1", 0);
+        stamp_hash(storage, Rx, "RX1", 200);
+
+        // delivered retrievals are forgotten before they are swept
+        consume_rx_hash(storage, "RX0");
+
+        assert_eq!(
+            hash_mappings(storage),
+            HashMappingsResponse {
+                tx_hashes: 2,
+                rx_hashes: 2,
+                retention_blocks: 100,
+                oldest_height: Some(10),
+            }
+        );
+
+        assert_eq!(sweep_hashes(storage, 109, 10), SweepPass::default());
+
+        assert_eq!(
+            sweep_hashes(storage, 150, 10),
+            SweepPass {
+                tx_hashes: 1,
+                rx_hashes: 0,
+                restamped: 2,
+            }
+        );
+
+        assert_eq!(state::tx_hash_ica_idx(storage, "TX0"), None);
+        assert_eq!(state::tx_hash_ica_idx(storage, "TX1"), Some(0));
+        assert_eq!(state::rx_hash_ica_idx(storage, "RX2"), Some(1));
+
+        // the tx & retrieval in flight are swept once settled & the window has passed again
+        settle_pending_tx(storage, 0, "TX1");
+
+        settle_pending_tx(storage, 1, "TX2");
+
+        assert_eq!(
+            sweep_hashes(storage, 300, 10),
+            SweepPass {
+                tx_hashes: 1,
+                rx_hashes: 2,
+                restamped: 0,
+            }
+        );
+
+        assert_eq!(
+            hash_mappings(storage),
+            HashMappingsResponse {
+                tx_hashes: 0,
+                rx_hashes: 0,
+                retention_blocks: 100,
+                oldest_height: None,
+            }
+        );
+    }
+
+    #[test]
+    fn supported_features_follow_cargo_features() {
+        let deps = mock_neutron_dependencies();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::SupportedFeatures {}).unwrap();
+
+        let SupportedFeaturesResponse { features } = cosmwasm_std::from_binary(&res).unwrap();
+
+        assert_eq!(
+            features.contains(&"camel-case".to_owned()),
+            cfg!(feature = "camel-case")
+        );
+
+        assert_eq!(
+            features.contains(&"ics20-check".to_owned()),
+            cfg!(feature = "ics20-check")
+        );
+
+        assert!(features.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn response_field_casing_follows_cargo_feature() {
+        let res = to_binary(&TimeoutsResponse {
+            timeout_seconds: 1,
+            timeout_height: 2,
+            timeout_revision_number: 3,
+        })
+        .unwrap();
+
+        let expected = if cfg!(feature = "camel-case") {
+            r#"{"timeoutSeconds":1,"timeoutHeight":2,"timeoutRevisionNumber":3}"#
+        } else {
+            r#"{"timeout_seconds":1,"timeout_height":2,"timeout_revision_number":3}"#
+        };
+
+        assert_eq!(res.as_slice(), expected.as_bytes());
+
+        // stored types keep their casing, whichever features the contract is built with
+        let stored = to_binary(&RemoteDeposit {
+            idx: 0,
+            sender: "sender".to_owned(),
+            coins: vec![],
+            remote_height: 1,
+        })
+        .unwrap();
+
+        assert_eq!(
+            stored.as_slice(),
+            br#"{"idx":0,"sender":"sender","coins":[],"remote_height":1}"#
+        );
+    }
+
+    #[test]
+    fn error_codes_are_unique_and_prefix_messages() {
+        let deps = mock_neutron_dependencies();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ErrorCodes {}).unwrap();
+
+        let ErrorCodesResponse { codes } = cosmwasm_std::from_binary(&res).unwrap();
+
+        let unique: HashSet<_> = codes.iter().map(|c| c.code).collect();
+
+        assert_eq!(unique.len(), codes.len());
+
+        for err in [
+            Error::NoIcaSetup,
+            Error::Paused,
+            Error::UnexpectedOpenAck {
+                port_id: "transfer".to_owned(),
+            },
+            ValidationError::InvalidLabel("must not be blank").into(),
+        ] {
+            assert!(
+                err.to_string()
+                    .starts_with(&format!("[E{:04}] ", err.code())),
+                "{err} is not prefixed with its code"
+            );
+        }
+    }
+
+    /// The ack data of an ICA tx claiming `amount` of rewards
+    fn claim_rewards_ack_data(amount: Vec<Coin>) -> Vec<u8> {
+        #[derive(Clone, PartialEq, Message)]
+        struct MsgWithdrawDelegatorRewardResponse {
+            #[prost(message, repeated, tag = "1")]
+            pub amount: Vec<RawCoin>,
+        }
+
+        let response = MsgWithdrawDelegatorRewardResponse {
+            amount: amount.into_iter().map(Into::into).collect(),
+        };
+
+        msg_response_ack_data(
+            "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorRewardResponse",
+            response.encode_to_vec(),
+        )
+    }
+
+    /// The ack data of an ICA tx of a single message responding with the encoded `value`
+    fn msg_response_ack_data(type_url: &str, value: Vec<u8>) -> Vec<u8> {
+        #[derive(Clone, PartialEq, Message)]
+        struct Any {
+            #[prost(string, tag = "1")]
+            pub type_url: String,
+            #[prost(bytes = "vec", tag = "2")]
+            pub value: Vec<u8>,
+        }
+
+        #[derive(Clone, PartialEq, Message)]
+        struct TxMsgData {
+            #[prost(message, repeated, tag = "2")]
+            pub msg_responses: Vec<Any>,
+        }
+
+        TxMsgData {
+            msg_responses: vec![Any {
+                type_url: type_url.to_owned(),
+                value,
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn claimed_rewards_parsed_from_ack_data() {
+        let ack_data = claim_rewards_ack_data(vec![Coin::new(1234, REMOTE_DENOM)]);
+
+        assert_eq!(
+            parse_claimed_rewards(&ack_data),
+            Some(vec![Coin::new(1234, REMOTE_DENOM)])
+        );
+
+        assert_eq!(parse_claimed_rewards(&[]), None);
+
+        assert_eq!(parse_claimed_rewards(b"not a tx msg data"), None);
+    }
+
+    #[test]
+    fn failed_claimed_rewards_retrieval_isolated_from_the_claim_ack() {
+        let mut deps = mock_neutron_dependencies();
+
+        let env = mock_env();
+
+        let packet = RequestPacket {
+            sequence: Some(5),
+            source_port: Some("icacontroller-contract.0".to_owned()),
+            source_channel: Some("channel-1".to_owned()),
+            destination_port: None,
+            destination_channel: None,
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        let tx_hash = HASH_SCHEME.hash(TX_HASH_DOMAIN, &[&5u64.to_be_bytes(), b"channel-1"]);
+
+        let storage = deps.as_mut().storage;
+
+        state::set_remote_denom(storage, REMOTE_DENOM);
+        state::set_ica_owner(storage, &0, "owner");
+        state::set_tx_hash_ica_idx(storage, &tx_hash, 0);
+
+        state::set_tx_hash_issued_tx(
+            storage,
+            &tx_hash,
+            &IssuedTx {
+                kind: TxKind::ClaimRewards,
+                coin: Coin::new(0, REMOTE_DENOM),
+                fee: None,
+                payer: None,
+                validator: Some("validator".to_owned()),
+                memo: None,
+                route: None,
+                custom: None,
+                rx_hash: None,
+            },
+        );
+
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        let ack_data = claim_rewards_ack_data(vec![Coin::new(1234, REMOTE_DENOM)]);
+
+        let res = sudo_response(deps.as_mut(), env.clone(), packet, ack_data.into()).unwrap();
+
+        // the claim is recorded by the ack & only its retrieval is left to the submessage
+        assert_eq!(
+            state::ica_claimed_rewards(deps.as_ref().storage, &0),
+            Some(Uint128::new(1234))
+        );
+
+        assert_eq!(
+            state::ica_tx_success_count(deps.as_ref().storage, &0),
+            Some(1)
+        );
+
+        let retrieve_msg = &res.messages[0];
+
+        assert_eq!(retrieve_msg.reply_on, cosmwasm_std::ReplyOn::Always);
+
+        assert_eq!(
+            retrieve_msg.msg,
+            WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::RetrieveClaimedRewards {
+                    ica_idx: 0,
+                    amount: Uint128::new(1234),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into()
+        );
+
+        assert!(matches!(
+            execute_retrieve_claimed_rewards(
+                deps.as_mut(),
+                &env,
+                &mock_info("owner", &[]),
+                0,
+                Uint128::new(1234)
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        let failed_reply = Reply {
+            id: retrieve_msg.id,
+            result: cosmwasm_std::SubMsgResult::Err("codespace: wasm, code: 5".to_owned()),
+        };
+
+        let res = reply(
+            DepsMut {
+                storage: &mut deps.storage,
+                api: &deps.api,
+                querier: QuerierWrapper::new(&MockQuerier::<Empty>::new(&[])),
+            },
+            env,
+            failed_reply,
+        )
+        .unwrap();
+
+        assert_eq!(res.events[0].ty, "rewards_retrieve_failed");
+
+        assert_eq!(
+            state::ica_claimed_rewards(deps.as_ref().storage, &0),
+            Some(Uint128::new(1234))
+        );
+    }
+
+    #[test]
+    fn migrate_v4_sums_ica_counters() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_contract_version(storage, 4);
+        state::set_ica_count(storage, 3);
+        state::set_ica_tx_issued_count(storage, &0, 3);
+        state::set_ica_tx_success_count(storage, &0, 2);
+        state::set_ica_tx_timeout_count(storage, &0, 1);
+        state::set_ica_round_trip_count(storage, &0, 1);
+        state::set_ica_tx_issued_count(storage, &2, 1);
+        state::set_ica_tx_error_count(storage, &2, 1);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let storage = deps.as_mut().storage;
+
+        assert_eq!(
+            state::global_totals(storage),
+            Some(GlobalTotals {
+                tx_issued: 4,
+                tx_success: 2,
+                tx_error: 1,
+                tx_timeout: 1,
+                round_trips: 1,
+            })
+        );
+
+        update_global_totals(storage, 1, |totals| totals.round_trips += 1);
+
+        assert_eq!(state::global_totals(storage).unwrap().round_trips, 2);
+    }
+
+    #[test]
+    fn denom_volumes_summed_across_channels() {
+        let volume = |denom: &str, channel: &str, outbound: u128, inbound: u128| ChannelVolume {
+            denom: denom.to_owned(),
+            channel: channel.to_owned(),
+            outbound: outbound.into(),
+            inbound: inbound.into(),
+        };
+
+        let volumes = [
+            volume(HOST_IBC_DENOM, "channel-0", 10, 5),
+            volume("untrn", "channel-0", 1, 0),
+            volume(HOST_IBC_DENOM, "channel-1", 20, 15),
+        ];
+
+        assert_eq!(
+            denom_volumes(&volumes),
+            vec![
+                DenomVolume {
+                    denom: HOST_IBC_DENOM.to_owned(),
+                    outbound: Uint128::new(30),
+                    inbound: Uint128::new(20),
+                },
+                DenomVolume {
+                    denom: "untrn".to_owned(),
+                    outbound: Uint128::new(1),
+                    inbound: Uint128::zero(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_current_version_is_noop() {
+        let mut deps = mock_dependencies();
+
+        state::set_contract_version(deps.as_mut().storage, CONTRACT_VERSION);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        assert_eq!(
+            state::contract_version(deps.as_ref().storage),
+            Some(CONTRACT_VERSION)
+        );
+    }
+
+    #[test]
+    fn migrate_from_newer_version_fails() {
+        let mut deps = mock_dependencies();
+
+        state::set_contract_version(deps.as_mut().storage, CONTRACT_VERSION + 1);
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidMigration { .. }));
+    }
 }
@@ -0,0 +1,307 @@
+//! Owners deposit IBC fees up front, each issued tx is paid for out of the owner's fee balance
+//! and the fees refunded by neutron once the outcome of a tx is known are credited back.
+
+pub use common::funds::{add_coins, coins_shortfall, subtract_coins};
+
+use common::debug;
+use cosmwasm_std::{
+    BankMsg, Coin, CustomQuery, Deps, DepsMut, Event, MessageInfo, Response, Storage,
+};
+use neutron_sdk::bindings::msg::{IbcFee, NeutronMsg};
+
+use crate::{coins_attr, msgs::FeeBalanceResponse, state, Error, IBC_FEE_DENOM};
+
+/// The ack & timeout fees, the recv fee is always zero on neutron
+#[must_use]
+pub fn ibc_fee_coins(ibc_fee: &IbcFee) -> Vec<Coin> {
+    ibc_fee
+        .ack_fee
+        .iter()
+        .chain(&ibc_fee.timeout_fee)
+        .cloned()
+        .collect()
+}
+
+/// The amounts of each fee denom missing from `funds` to cover the ack & timeout fees
+#[must_use]
+pub fn ibc_fee_shortfall(funds: &[Coin], ibc_fee: &IbcFee) -> Vec<Coin> {
+    coins_shortfall(funds, &ibc_fee_coins(ibc_fee))
+}
+
+/// The most a tx costs once its outcome is known, the larger of the ack & timeout fees of each denom
+#[must_use]
+pub fn max_ibc_fee_cost(ibc_fee: &IbcFee) -> Vec<Coin> {
+    let mut cost = ibc_fee.ack_fee.clone();
+
+    for timeout_fee in &ibc_fee.timeout_fee {
+        match cost.iter_mut().find(|c| c.denom == timeout_fee.denom) {
+            Some(c) => c.amount = c.amount.max(timeout_fee.amount),
+            None => cost.push(timeout_fee.clone()),
+        }
+    }
+
+    cost
+}
+
+/// Debit the ack & timeout fees from the merged `coins`, `None` if they don't cover the fees
+#[must_use]
+pub fn debit_ibc_fee(coins: &[Coin], ibc_fee: &IbcFee) -> Option<Vec<Coin>> {
+    subtract_coins(coins, &ibc_fee_coins(ibc_fee))
+}
+
+/// Add `funds` to the fee balance of `owner`
+pub fn credit(storage: &mut dyn Storage, owner: &str, funds: &[Coin]) {
+    if funds.is_empty() {
+        return;
+    }
+
+    let mut balance = state::owner_fee_balance(storage, owner).unwrap_or_default();
+
+    add_coins(&mut balance, funds);
+
+    state::set_owner_fee_balance(storage, owner, &balance);
+}
+
+/// Pay the ack & timeout fees out of the fee balance of `owner`
+pub fn debit(storage: &mut dyn Storage, owner: &str, ibc_fee: &IbcFee) -> Result<(), Error> {
+    let balance = state::owner_fee_balance(storage, owner).unwrap_or_default();
+
+    let Some(remaining) = debit_ibc_fee(&balance, ibc_fee) else {
+        return Err(Error::InsufficientIbcTxFee {
+            shortfall: ibc_fee_shortfall(&balance, ibc_fee),
+        });
+    };
+
+    state::set_owner_fee_balance(storage, owner, &remaining);
+
+    Ok(())
+}
+
+/// Pay `amounts` other than IBC fees, such as ICQ deposits, out of the fee balance of `owner`
+pub fn debit_coins(storage: &mut dyn Storage, owner: &str, amounts: &[Coin]) -> Result<(), Error> {
+    let balance = state::owner_fee_balance(storage, owner).unwrap_or_default();
+
+    let Some(remaining) = subtract_coins(&balance, amounts) else {
+        return Err(Error::InsufficientFeeBalance {
+            shortfall: coins_shortfall(&balance, amounts),
+        });
+    };
+
+    state::set_owner_fee_balance(storage, owner, &remaining);
+
+    Ok(())
+}
+
+/// Credit the fee neutron refunds once the outcome of the tx at `tx_hash` is known to the account that paid it, the
+/// ICA owner for txs issued before payers were recorded: the timeout fee when the tx is acknowledged or the ack fee
+/// when it times out.
+///
+/// Must be called before the issued tx entry is consumed, returns the credited fee.
+pub fn credit_unused_fee(
+    storage: &mut dyn Storage,
+    ica_idx: u32,
+    tx_hash: &str,
+    timed_out: bool,
+) -> Vec<Coin> {
+    let Some(issued_tx) = state::tx_hash_issued_tx(storage, tx_hash) else {
+        return vec![];
+    };
+
+    let Some(fee) = issued_tx.fee else {
+        return vec![];
+    };
+
+    let Some(payer) = issued_tx
+        .payer
+        .or_else(|| state::ica_owner(storage, &ica_idx))
+    else {
+        return vec![];
+    };
+
+    let unused = if timed_out {
+        fee.ack_fee
+    } else {
+        fee.timeout_fee
+    };
+
+    credit(storage, &payer, &unused);
+
+    unused
+}
+
+pub fn execute_deposit_fees(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing deposit fees");
+
+    if info.funds.is_empty() {
+        return Err(Error::FeeDepositMissing);
+    }
+
+    if info.funds.iter().any(|c| c.denom != IBC_FEE_DENOM) {
+        return Err(Error::IncorrectFeeDepositAsset);
+    }
+
+    let owner = info.sender.as_str();
+
+    credit(deps.storage, owner, &info.funds);
+
+    let event = Event::new("fees_deposited")
+        .add_attribute("owner", owner)
+        .add_attribute("amount", coins_attr(&info.funds));
+
+    Ok(Response::default().add_event(event))
+}
+
+pub fn execute_withdraw_fees(
+    deps: DepsMut<impl CustomQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing withdraw fees");
+
+    if !info.funds.is_empty() {
+        return Err(Error::NoFundsExpected);
+    }
+
+    let owner = info.sender.into_string();
+
+    let balance = state::owner_fee_balance(deps.storage, &owner)
+        .filter(|balance| !balance.is_empty())
+        .ok_or(Error::NoFeesToWithdraw)?;
+
+    state::remove_owner_fee_balance(deps.storage, &owner);
+
+    let event = Event::new("fees_withdrawn")
+        .add_attribute("owner", owner.as_str())
+        .add_attribute("amount", coins_attr(&balance));
+
+    let msg = BankMsg::Send {
+        to_address: owner,
+        amount: balance,
+    };
+
+    Ok(Response::default().add_message(msg).add_event(event))
+}
+
+pub fn query_fee_balance(
+    deps: Deps<impl CustomQuery>,
+    owner: String,
+) -> Result<FeeBalanceResponse, Error> {
+    deps.api.addr_validate(&owner)?;
+
+    let balance = state::owner_fee_balance(deps.storage, &owner).unwrap_or_default();
+
+    Ok(FeeBalanceResponse { balance })
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{coin, testing::MockStorage};
+
+    use super::*;
+    use crate::msgs::{IssuedTx, TxKind};
+
+    #[test]
+    fn ibc_fee_shortfall_is_aggregated_per_denom() {
+        let ibc_fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![coin(1000, "untrn"), coin(5, "uatom")],
+            timeout_fee: vec![coin(1000, "untrn")],
+        };
+
+        assert!(ibc_fee_shortfall(&[coin(2000, "untrn"), coin(5, "uatom")], &ibc_fee).is_empty());
+
+        assert_eq!(
+            ibc_fee_shortfall(&[coin(1500, "untrn")], &ibc_fee),
+            vec![coin(5, "uatom"), coin(500, "untrn")]
+        );
+    }
+
+    #[test]
+    fn max_ibc_fee_cost_per_denom() {
+        let ibc_fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![coin(1000, "untrn")],
+            timeout_fee: vec![coin(1500, "untrn"), coin(5, "uatom")],
+        };
+
+        assert_eq!(
+            max_ibc_fee_cost(&ibc_fee),
+            vec![coin(1500, "untrn"), coin(5, "uatom")]
+        );
+    }
+
+    #[test]
+    fn debit_ibc_fee_from_escrow() {
+        let ibc_fee = IbcFee {
+            recv_fee: vec![],
+            ack_fee: vec![coin(1000, "untrn")],
+            timeout_fee: vec![coin(1000, "untrn")],
+        };
+
+        let mut escrow = vec![coin(1500, "untrn")];
+
+        assert_eq!(debit_ibc_fee(&escrow, &ibc_fee), None);
+
+        add_coins(&mut escrow, &[coin(3000, "untrn"), coin(5, "uatom")]);
+
+        assert_eq!(escrow, vec![coin(4500, "untrn"), coin(5, "uatom")]);
+
+        assert_eq!(
+            debit_ibc_fee(&escrow, &ibc_fee),
+            Some(vec![coin(2500, "untrn"), coin(5, "uatom")])
+        );
+
+        assert_eq!(
+            debit_ibc_fee(&[coin(2000, "untrn")], &ibc_fee),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn unused_fee_credited_to_payer() {
+        let mut storage = MockStorage::default();
+
+        let issued_tx = |payer: Option<&str>| IssuedTx {
+            kind: TxKind::Retrieve,
+            coin: coin(10, "uatom"),
+            fee: Some(IbcFee {
+                recv_fee: vec![],
+                ack_fee: vec![coin(1000, "untrn")],
+                timeout_fee: vec![coin(2000, "untrn")],
+            }),
+            payer: payer.map(str::to_owned),
+            validator: None,
+            memo: None,
+            route: None,
+            custom: None,
+            rx_hash: None,
+        };
+
+        state::set_ica_owner(&mut storage, &0, "owner");
+        state::set_tx_hash_issued_tx(&mut storage, "OPERATOR_TX", &issued_tx(Some("operator")));
+        state::set_tx_hash_issued_tx(&mut storage, "LEGACY_TX", &issued_tx(None));
+
+        // an operator paying for the owner's tx is credited its unused fee
+        assert_eq!(
+            credit_unused_fee(&mut storage, 0, "OPERATOR_TX", false),
+            vec![coin(2000, "untrn")]
+        );
+        assert_eq!(
+            state::owner_fee_balance(&storage, "operator"),
+            Some(vec![coin(2000, "untrn")])
+        );
+        assert_eq!(state::owner_fee_balance(&storage, "owner"), None);
+
+        // txs issued before payers were recorded credit the owner
+        assert_eq!(
+            credit_unused_fee(&mut storage, 0, "LEGACY_TX", true),
+            vec![coin(1000, "untrn")]
+        );
+        assert_eq!(
+            state::owner_fee_balance(&storage, "owner"),
+            Some(vec![coin(1000, "untrn")])
+        );
+    }
+}
@@ -1,5 +1,12 @@
+use common::validate::{
+    validate_channel_id, validate_connection_id, validate_denom, validate_icq_deposit,
+    validate_icq_update_period, ValidationError,
+};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Binary, Coin, Decimal, Delegation, Timestamp, Uint128};
+use neutron_sdk::bindings::{msg::IbcFee, types::RegisteredQuery};
+
+pub use common::{error_codes::ErrorCode, IcaChannel, IcaStatus};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -13,50 +20,471 @@ pub struct InstantiateMsg {
     pub remote_denom: String,
     /// The ICS-20 denom of the transfer asset on the host chain
     pub host_ibc_denom: String,
+    /// Host denoms accepted by `TransferFunds` in addition to `host_ibc_denom`, these are only transferred to the ICA.
+    /// Retrievals only ever retrieve `remote_denom`, so the ICA's balance of these is left on it until its owner moves
+    /// it with `SubmitIcaTx`, e.g. by an ICS-20 transfer back to the host chain
+    pub additional_transfer_denoms: Option<Vec<String>>,
+    /// The timeout in seconds of ICA txs, defaults to 2 weeks
+    pub timeout_seconds: Option<u64>,
+    /// The remote timeout height of IBC transfers, defaults to 10,000,000
+    pub timeout_height: Option<u64>,
+    /// The revision number of the remote chain for IBC transfer timeout heights, defaults to 2
+    pub timeout_revision_number: Option<u64>,
+    /// The contract owner, permitted to update the configuration & take emergency actions, defaults to the instantiator
+    pub admin: Option<String>,
+    /// The bounds of the amount of the coins of each transfer denom transferred by `TransferFunds`,
+    /// the denoms without limits are unbounded
+    pub transfer_limits: Option<Vec<DenomTransferLimits>>,
+    /// The max length in bytes of the memo of retrieval transfers, defaults to ibc-go's limit
+    pub max_memo_len: Option<u32>,
+    /// The max length in bytes of the IBC hook memo of retrieval transfers, defaults to ibc-go's limit
+    pub max_hook_memo_len: Option<u32>,
+    /// The number of blocks tx & rx hash mappings are kept for before they can be swept,
+    /// defaults to `DEFAULT_HASH_RETENTION_BLOCKS`
+    pub hash_retention_blocks: Option<u64>,
+    /// The ICQ deposit charged if the interchainqueries params cannot be queried, e.g. once a chain upgrade moved the
+    /// params query, the live deposit is charged whenever it can be queried
+    pub icq_deposit_override: Option<Vec<Coin>>,
+}
+
+impl InstantiateMsg {
+    /// Validate the shape of the message without touching the chain
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate_connection_id(&self.connection_id)?;
+        validate_channel_id(&self.ibc_transfer_channel)?;
+        validate_icq_update_period(self.icq_update_period)?;
+        validate_denom(&self.remote_denom)?;
+        validate_denom(&self.host_ibc_denom)?;
+
+        for denom in self.additional_transfer_denoms.iter().flatten() {
+            validate_denom(denom)?;
+        }
+
+        for limits in self.transfer_limits.iter().flatten() {
+            validate_denom(&limits.denom)?;
+        }
+
+        if let Some(deposit) = &self.icq_deposit_override {
+            validate_icq_deposit(deposit)?;
+        }
+
+        Ok(())
+    }
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
+/// Every execute sets a `common::receipt::ExecuteReceipt` as its response data, naming the msg & the ICA acted upon.
+/// Once an ICA tx issued by the execute is submitted, the receipt is replaced by one of the `issue_tx` action
+/// carrying the tx's sequence number.
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Setup an ICA for the sender to transfer assets to
     SetupIca {},
-    /// Transfer attached funds to the ICA if one has been setup
-    TransferFunds {},
-    /// Retrieve funds from the ICA if one has been setup and it has a non-zero balance
-    RetrieveFunds {},
-    /// Callback for when funds are retrieved from the ICA
+    /// Cancel the sender's ICA registration once it has been pending for at least `REGISTRATION_CANCEL_BLOCKS`,
+    /// refunding the ICQ deposit paid at setup & allowing the sender to setup a new ICA
+    CancelIcaRegistration {},
+    /// Re-register the sender's ICA once its channel was closed by a timed out ICA tx, opening a new channel for
+    /// the same ICA & keeping its ICQs
+    ReopenIca {},
+    /// Deposit the attached `untrn` into the sender's fee balance, which pays for the IBC fees of their txs
+    DepositFees {},
+    /// Withdraw the sender's entire fee balance
+    WithdrawFees {},
+    /// Transfer the attached coins of the accepted transfer denoms to the ICA if one has been setup, one IBC transfer per coin,
+    /// any other attached funds are deposited into the sender's fee balance before paying the IBC fee of each transfer
+    TransferFunds {
+        /// Plain text memo forwarded with each IBC transfer, JSON object memos are rejected
+        memo: Option<String>,
+        /// Transfer to the ICA of this owner instead of the sender's, the sender must be an operator granted an
+        /// allowance covering the transferred coins by the owner
+        owner: Option<String>,
+    },
+    /// Retrieve funds from the ICA if one has been setup and it has a non-zero balance of `remote_denom`,
+    /// attached funds are deposited into the sender's fee balance before paying the IBC fee. Coins of the additional
+    /// transfer denoms are not retrieved, see `InstantiateMsg::additional_transfer_denoms`
+    RetrieveFunds {
+        /// The amount to retrieve, defaults to the entire last known ICA balance
+        amount: Option<Uint128>,
+        /// Retrieve from the ICA of this owner instead of the sender's, the sender must be an operator granted an
+        /// allowance covering the retrieved coin by the owner
+        owner: Option<String>,
+    },
+    /// Allow `operator` to `TransferFunds` & `RetrieveFunds` on the sender's behalf, up to a cumulative `allowance`
+    /// of each denom, replacing any previous grant. The operator pays the IBC fees, at most `MAX_OPERATORS` operators
+    /// can be granted, a grant is removed once its allowance is spent.
+    GrantOperator {
+        operator: String,
+        allowance: Vec<Coin>,
+    },
+    /// Revoke the allowance granted to `operator` by the sender
+    RevokeOperator { operator: String },
+    /// Retrieve the entire last known balance of the ICAs of `owners`, at most `MAX_RETRIEVE_FOR_OWNERS`, callable by anyone.
+    /// The IBC fees are paid from the sender's fee balance & attached funds are deposited into it, unused fees are
+    /// refunded to the ICA owners. Owners without an active ICA holding funds are skipped.
+    RetrieveFundsFor { owners: Vec<String> },
+    /// Submit an ICA tx executing `msgs` as the sender's ICA, timing out after `timeout` seconds or the contract's ICA
+    /// tx timeout. Messages beyond the interchaintxs module's per tx limit are split across several ICA txs, at most
+    /// `MAX_CUSTOM_TXS`, each paying the IBC fee from the sender's fee balance & attached funds are deposited into it,
+    /// the outcome of each is tracked as for any other ICA tx. Rejected while a retrieval of the ICA is in flight.
+    ///
+    /// Split txs are not atomic: each is executed, fails or times out on its own, so a failed tx does not revert the
+    /// messages of the others.
+    SubmitIcaTx {
+        msgs: Vec<ProtobufAnyJson>,
+        timeout: Option<u64>,
+    },
+    /// Retrieve the entire last known balance of the sender's ICA through the intermediate chains of the retrieval
+    /// route named `route`, as `RetrieveFunds` otherwise
+    RetrieveFundsVia { route: String },
+    /// Re-issue the last failed or timed out tx of the given kind, the IBC fee is paid again.
+    /// Failed transfers are refunded to the owner instead, unless issued before refunds were tracked
+    RetryLastTx { tx_kind: TxKind },
+    /// Automatically retrieve funds whenever the balance ICQ reports a non-zero ICA balance in a result submitted after
+    /// the ICA's last tx settled, the IBC fees are paid from the sender's fee balance & attached funds are deposited into it
+    EnableAutoRetrieve {},
+    /// Stop automatically retrieving funds
+    DisableAutoRetrieve {},
+    /// Change the update period in blocks of the sender's balance ICQ, e.g. to poll less often once roundtrips are complete
+    UpdateIcqPeriod { new_period: u64 },
+    /// Delegate `amount` of the remote denom held by the sender's ICA to `validator`.
+    /// The first delegation registers a delegations ICQ for the validator, paying the ICQ deposit from the fee balance,
+    /// later delegations must be to the same validator.
+    DelegateFromIca { validator: String, amount: Uint128 },
+    /// Undelegate `amount` of the remote denom delegated by the sender's ICA to `validator`
+    UndelegateFromIca { validator: String, amount: Uint128 },
+    /// Claim the staking rewards of the sender's ICA & retrieve the claimed rewards once the claim is acknowledged,
+    /// the IBC fees of both txs are paid from the sender's fee balance & attached funds are deposited into it
+    ClaimRewards {},
+    /// Set or clear the label & note of the sender's ICA, shown in the metadata & list queries
+    SetIcaLabel {
+        label: Option<String>,
+        note: Option<String>,
+    },
+    /// Add or update a labelled remote address in the address book of the sender's ICA,
+    /// holding at most `MAX_ADDRESS_BOOK_ENTRIES` addresses
+    SetAddressBookEntry {
+        address: String,
+        label: String,
+        note: Option<String>,
+    },
+    /// Remove a remote address from the address book of the sender's ICA
+    RemoveAddressBookEntry { address: String },
+    /// Register a TX ICQ observing the bank sends to the sender's ICA, the ICQ deposit is paid from the sender's
+    /// fee balance & attached funds are deposited into it
+    RegisterTransfersQuery {},
+    /// Remove the sender's ICA balance ICQ, clear all of the ICA's state & refund the ICQ deposit.
+    /// Refused until the ICA is settled & drained: no tx in flight, every retrieval delivered to the contract, no
+    /// undelegation still unbonding & balance & delegations ICQ results submitted after the last tx settled showing
+    /// none of the remote denom left on or delegated by the ICA.
+    /// The rx hash of a failed or timed out retrieval counts as undelivered until it is pruned by `Sweep`.
+    TeardownIca {},
+    /// Start or stop committing to the global stats & activity log whenever they change, see `QueryMsg::StateCommitment`.
+    /// Activity is only logged while enabled, only callable by the contract owner
+    SetStateCommitments { enabled: bool },
+    /// Halt or resume every msg issuing txs: `SetupIca`, `ReopenIca`, `TransferFunds`, `RetrieveFunds`, `RetrieveFundsFor`,
+    /// `RetrieveFundsVia`, `SubmitIcaTx`, `RetryLastTx`, `DelegateFromIca`, `UndelegateFromIca`, `ClaimRewards`, the
+    /// retrieval of claimed rewards & auto-retrieval. Acknowledgements of txs already in flight are still processed while
+    /// paused, only callable by the contract owner
+    SetPaused { paused: bool },
+    /// Update the IBC timeouts, only callable by the contract owner
+    UpdateTimeouts {
+        timeout_seconds: Option<u64>,
+        timeout_height: Option<u64>,
+        timeout_revision_number: Option<u64>,
+    },
+    /// Replace the bounds of the amount of the coins of `limits.denom`, a transfer denom, transferred by
+    /// `TransferFunds`, an unset bound is removed, only callable by the contract owner
+    UpdateTransferLimits { limits: DenomTransferLimits },
+    /// Replace the byte size limits of the memos built for retrievals, an unset limit is reset to ibc-go's limit,
+    /// only callable by the contract owner
+    UpdateMemoLimits {
+        max_memo_len: Option<u32>,
+        max_hook_memo_len: Option<u32>,
+    },
+    /// Allow retrievals through `route` under `name`, or disallow the route named `name` if unset, only callable by
+    /// the contract owner. The route's host denom must be the voucher denom of the remote denom over its path
+    SetRetrievalRoute {
+        name: String,
+        route: Option<RetrievalRoute>,
+    },
+    /// Add or remove authorized indexers, only callable by the contract owner
+    UpdateIndexers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Resume serving the results of an ICQ quarantined after failing to decode, only callable by the contract owner
+    ClearIcqQuarantine { icq_id: u64 },
+    /// Record that the sender, an authorized indexer, has indexed up to & including `activity_id`
+    CheckpointIndexer { activity_id: u64 },
+    /// Propose `new_owner` as the contract owner, who must accept it for the handover to complete,
+    /// only callable by the contract owner
+    TransferOwnership { new_owner: String },
+    /// Become the contract owner, only callable by the proposed owner
+    AcceptOwnership {},
+    /// Send the funds of a retrieval hook that failed validation to `recipient`, only callable by the contract owner
+    ResolveHookDeadLetter { idx: u32, recipient: String },
+    /// Subscribe the sender, a contract approved by the contract owner, to the alerts of failed ICA txs.
+    /// Alerts are executed on subscribers as `AlertHookMsg` with a gas limit of `ALERT_HOOK_GAS_LIMIT`,
+    /// an alert failing on a subscriber is ignored.
+    SubscribeAlerts {},
+    /// Unsubscribe the sender from the alerts of failed ICA txs, it stays approved to subscribe again
+    UnsubscribeAlerts {},
+    /// Approve the contract `subscriber` to subscribe to the alerts of failed ICA txs, at most `MAX_ALERT_SUBSCRIBERS`
+    /// contracts can be approved, only callable by the contract owner
+    ApproveAlertSubscriber { subscriber: String },
+    /// Unsubscribe `subscriber` from the alerts of failed ICA txs & revoke its approval, only callable by the
+    /// contract owner
+    RemoveAlertSubscriber { subscriber: String },
+    /// Update the number of blocks tx & rx hash mappings are kept for, reset to `DEFAULT_HASH_RETENTION_BLOCKS` if
+    /// unset, only callable by the contract owner
+    UpdateHashRetention { retention_blocks: Option<u64> },
+    /// Replace the ICQ deposit charged while the interchainqueries params cannot be queried, removed if unset,
+    /// only callable by the contract owner
+    UpdateIcqDepositOverride { deposit: Option<Vec<Coin>> },
+    /// Prune at most `limit` tx & rx hash mappings older than the retention window, oldest first.
+    /// Mappings of txs & retrievals still in flight are kept & swept once the window has passed again.
+    Sweep { limit: u32 },
+    /// Restore the missing reverse lookup maps of at most `limit` ICAs from their primary state, resuming after the
+    /// ICAs visited by the previous call until every ICA has been visited, only callable by the contract owner
+    RebuildIndexes { limit: u32 },
+    /// Retrieve the `amount` of the remote denom claimed as staking rewards by the ICA `ica_idx`, only callable by the
+    /// contract itself once a claim is acknowledged, so that a failing retrieval does not fail the acknowledgement
+    RetrieveClaimedRewards { ica_idx: u32, amount: Uint128 },
+    /// Callback for when funds are retrieved from the ICA, only accepted from the address ibc-hooks derives from the
+    /// transfer channel & the address of the ICA the rx hash was issued for
     FundsRetrievedHook {
         /// IBC hook sender cannot be trusted - this has is used to identify the sender ICA
         rx_hash: String,
     },
 }
 
+#[cw_serde]
+#[derive(Copy)]
+pub enum TxKind {
+    /// IBC transfer from the contract to the ICA
+    Transfer,
+    /// ICA tx transferring funds from the ICA back to the contract
+    Retrieve,
+    /// ICA tx delegating funds held by the ICA
+    Delegate,
+    /// ICA tx undelegating funds delegated by the ICA
+    Undelegate,
+    /// ICA tx withdrawing the staking rewards of the ICA
+    ClaimRewards,
+    /// ICA tx of arbitrary messages submitted by the ICA owner
+    Custom,
+}
+
+/// The parameters of an issued tx, kept so that it can be re-issued on failure
+#[cw_serde]
+pub struct IssuedTx {
+    pub kind: TxKind,
+    pub coin: Coin,
+    /// The IBC fee paid from the payer's fee balance
+    pub fee: Option<IbcFee>,
+    /// The owner or operator whose fee balance paid the IBC fee & is credited the unused part of it,
+    /// the ICA owner if unset
+    pub payer: Option<String>,
+    /// The validator of delegate, undelegate & claim rewards txs
+    pub validator: Option<String>,
+    /// The memo of transfer txs
+    pub memo: Option<String>,
+    /// The route of retrieve txs forwarded through intermediate chains
+    pub route: Option<RetrievalRoute>,
+    /// The messages of custom txs, which carry a zero coin without a denom
+    pub custom: Option<CustomTx>,
+    /// The rx hash of retrieve txs, consumed if the tx fails or times out as its funds never leave the ICA
+    pub rx_hash: Option<String>,
+}
+
+/// A protobuf message executed by the ICA, as its type URL & encoded value
+#[cw_serde]
+pub struct ProtobufAnyJson {
+    pub type_url: String,
+    pub value: Binary,
+}
+
+#[cw_serde]
+pub struct CustomTx {
+    pub msgs: Vec<ProtobufAnyJson>,
+    /// The ICA tx timeout in seconds
+    pub timeout: u64,
+}
+
+/// The path of a retrieval forwarded through intermediate chains by packet-forward-middleware
+#[cw_serde]
+pub struct RetrievalRoute {
+    /// The channel on the remote chain the retrieval leaves the ICA through, towards the first hop
+    pub source_channel: String,
+    /// The intermediate chains in order
+    pub hops: Vec<RetrievalHop>,
+    /// The channel on neutron the funds arrive through from the last hop
+    pub host_channel: String,
+    /// The denom of the retrieved funds on neutron, tracing their path through the hops
+    pub host_denom: String,
+}
+
+/// An intermediate chain of a retrieval route. packet-forward-middleware receives & forwards the funds as an address
+/// derived from the channel they arrive through & their sender, from which the hook sender is derived in turn
+#[cw_serde]
+pub struct RetrievalHop {
+    /// The channel on the intermediate chain the funds arrive through
+    pub receive_channel: String,
+    /// The channel on the intermediate chain the funds are forwarded through, towards the next hop
+    pub channel: String,
+    /// The bech32 prefix of the intermediate chain's addresses
+    pub bech32_prefix: String,
+}
+
 #[cw_serde]
 pub enum QueryMsg {
     /// Query the metadata for the ICA setup by the `owner` address, if any
     IcaMetadata { owner: String },
     /// Query the last transfer asset balance for the ICA setup by the `owner` address, if any
     IcaLastBalance { owner: String },
+    /// Query the last delegation of the ICA setup by the `owner` address, if any
+    IcaLastDelegation { owner: String },
     /// Query the ICA Tx status data for the `owner` address, if any
     IcaTxStatus { owner: String },
     /// Query the error message for the `error_idx` and `owner` address, if any
     IcaTxError { owner: String, error_idx: u32 },
+    /// Query the address book of the ICA setup by the `owner` address, ordered by address
+    AddressBook {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Query the operators granted an allowance by the `owner` address, ordered by operator
+    Operators { owner: String },
+    /// Query the registrations of the ICQs of the ICA setup by the `owner` address, as held by the ICQ module
+    IcqInfo { owner: String },
+    /// Query the deposits into the ICA setup by the `owner` address observed by its transfers ICQ, oldest first
+    IcaRemoteDeposits {
+        owner: String,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Query the transfers & retrievals issued by the ICA setup by the `owner` address, oldest first
+    IcaTxHistory {
+        owner: String,
+        /// Start listing from the history entry after this one
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Query the txs issued by the ICA setup by the `owner` address still awaiting their outcome, in issue order
+    PendingTxs { owner: String },
+    /// Query the owners of all ICAs setup so far, ordered by ICA index
+    Owners {
+        /// Start listing from the ICA index after this one
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Query the owners with the most completed roundtrips, ties broken by retrieved volume
+    Leaderboard { limit: Option<u32> },
+    /// Query the ICAs that are currently setup, ordered by ICA index
+    ListIcas {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Query the IBC timeouts in effect
+    Timeouts {},
+    /// Query the host denoms accepted by `TransferFunds`
+    TransferDenoms {},
+    /// Query the bounds of the amount of the coins of each transfer denom transferred by `TransferFunds`, ordered as
+    /// the transfer denoms
+    TransferLimits {},
+    /// Query the retrieval route allowed under `name`, if any
+    RetrievalRoute { name: String },
+    /// Query the byte size limits of the memos built for retrievals
+    MemoLimits {},
+    /// Query the number of tx & rx hash mappings kept & the retention window they are swept after
+    HashMappings {},
+    /// Query contract wide statistics, aggregated across all ICAs
+    GlobalStats {},
+    /// Query a denormalized dump of the ICAs currently setup & the contract config, for periodic full exports
+    Export {
+        /// Continue from the `next_cursor` of the previous page
+        cursor: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Query the checkpoints of the authorized indexers, ordered by indexer address
+    IndexerCheckpoints {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Query the raw result of an ICQ quarantined after failing to decode, if any
+    QuarantinedIcqResult { icq_id: u64 },
+    /// Query the fee balance deposited by the `owner` address
+    FeeBalance { owner: String },
+    /// Query the history of min IBC fee & ICQ deposit changes observed when executing, oldest first
+    FeeHistory {},
+    /// Query the fees of a roundtrip of `coin` at the current fee parameters: transferring it to an ICA & retrieving it,
+    /// along with the gas ICQ relayers spend observing it at `gas_price` untrn, `DEFAULT_ESTIMATE_GAS_PRICE` if unset
+    EstimateRoundtripFee {
+        coin: Coin,
+        gas_price: Option<Decimal>,
+    },
+    /// Query the contract owner & the proposed owner, if any
+    Admin {},
+    /// Query whether new traffic is halted
+    Paused {},
+    /// Query the latest commitment to the global stats & activity log along with the global stats it covers,
+    /// the commitment is unset unless state commitments are enabled
+    StateCommitment {},
+    /// Query the activity logged while state commitments are enabled, oldest first, at most the last
+    /// `MAX_ACTIVITY_ENTRIES` being kept
+    Activity {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Query the optional subsystems compiled into this instance
+    SupportedFeatures {},
+    /// Query the code of every error, surfaced in error messages as `[E0123]`
+    ErrorCodes {},
+    /// Query the contracts subscribed & approved to subscribe to the alerts of failed ICA txs
+    AlertSubscribers {},
+    /// Query the acknowledged packets that could not be attributed to an issued tx, oldest first
+    DeadLetters {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Query the unresolved retrieval hooks that failed validation, oldest first
+    HookDeadLetters {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaMetadata {
     pub ica_idx: u32,
     pub address: Option<String>,
     pub balance_icq_id: Option<u64>,
+    pub delegations_icq_id: Option<u64>,
+    pub transfers_icq_id: Option<u64>,
+    pub auto_retrieve: bool,
+    pub status: IcaStatus,
+    /// The local height at which the status last changed
+    pub last_status_change_height: u64,
+    pub label: Option<String>,
+    pub note: Option<String>,
+    /// Set once the ICA channel is open, replaced when the ICA is reopened
+    pub channel: Option<IcaChannel>,
 }
 
 #[cw_serde]
 #[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaMetadataResponse {
     pub metadata: Option<IcaMetadata>,
 }
 
 #[cw_serde]
 #[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaLastBalance {
     pub balance: Option<Coin>,
     pub address: String,
@@ -65,11 +493,33 @@ pub struct IcaLastBalance {
 
 #[cw_serde]
 #[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaLastBalanceResponse {
     pub last_balance: Option<IcaLastBalance>,
+    /// Set if the last balance result could not be decoded & is quarantined
+    pub decode_failed: bool,
+    /// Set if the balance ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcaLastDelegation {
+    pub delegation: Option<Delegation>,
+    pub last_submitted_result_local_height: u64,
 }
 
 #[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcaLastDelegationResponse {
+    pub last_delegation: Option<IcaLastDelegation>,
+    /// Set if the delegations ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaTxStatus {
     pub issued: u32,
     pub success: u32,
@@ -78,16 +528,501 @@ pub struct IcaTxStatus {
     pub roundtrips: u32,
     pub last_transfer_seq_num: Option<u64>,
     pub last_retrieve_seq_num: Option<u64>,
+    pub last_claim_rewards_seq_num: Option<u64>,
+    /// The total amount of the remote denom claimed as staking rewards
+    pub claimed_rewards: Uint128,
 }
 
 #[cw_serde]
 #[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaTxStatusResponse {
     pub status: Option<IcaTxStatus>,
 }
 
 #[cw_serde]
 #[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IcaTxErrorResponse {
     pub error: Option<String>,
 }
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum TransferDirection {
+    /// A transfer from the contract to the ICA
+    ToIca,
+    /// A retrieval from the ICA back to the contract
+    FromIca,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum TxOutcome {
+    /// Awaiting the acknowledgement
+    Pending,
+    Success,
+    Error,
+    Timeout,
+}
+
+#[cw_serde]
+pub struct TxHistoryEntry {
+    pub idx: u32,
+    pub direction: TransferDirection,
+    pub coin: Coin,
+    pub seq_num: u64,
+    /// The local height at which the tx was issued
+    pub height: u64,
+    pub outcome: TxOutcome,
+}
+
+/// A change to a tx history entry, logged while state commitments are enabled
+#[cw_serde]
+pub struct ActivityEntry {
+    pub activity_id: u64,
+    pub ica_idx: u32,
+    /// The tx history entry as of the change
+    pub entry: TxHistoryEntry,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ActivityResponse {
+    pub entries: Vec<ActivityEntry>,
+}
+
+/// The hashes binding the global stats & activity log at `height`, all hex encoded under the contract's hash scheme
+#[cw_serde]
+pub struct StateCommitment {
+    /// The local height of the last change committed to
+    pub height: u64,
+    /// The number of activity entries logged
+    pub activity_count: u64,
+    /// The rolling hash of every activity entry logged, each folded into the previous digest in order
+    pub activity_digest: String,
+    /// The hash of the JSON encoded global stats
+    pub stats_digest: String,
+    /// The hash of the height, activity count, activity digest & stats digest
+    pub commitment: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct StateCommitmentResponse {
+    pub commitment: Option<StateCommitment>,
+    /// The global stats, as committed to
+    pub stats: GlobalStatsResponse,
+    /// The rolling hash of the activity entries pruned from the log beyond `MAX_ACTIVITY_ENTRIES`, the activity digest
+    /// is recomputed by folding the entries still logged into it
+    pub pruned_activity_digest: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcaTxHistoryResponse {
+    pub history: Vec<TxHistoryEntry>,
+}
+
+/// A tx whose acknowledgement or timeout has not been received yet
+#[cw_serde]
+pub struct PendingTx {
+    pub tx_hash: String,
+    pub kind: TxKind,
+    /// The amount moved or staked, absent for claim rewards txs
+    pub coin: Option<Coin>,
+    /// The local height at which the tx was issued
+    pub issued_height: u64,
+    /// The remote height at which a transfer times out
+    pub timeout_height: Option<u64>,
+    /// The time at which an ICA tx times out
+    pub timeout_timestamp: Option<Timestamp>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct PendingTxsResponse {
+    pub pending_txs: Vec<PendingTx>,
+}
+
+#[cw_serde]
+pub struct RemoteDeposit {
+    pub idx: u32,
+    pub sender: String,
+    pub coins: Vec<Coin>,
+    /// The remote height of the tx the deposit was made in
+    pub remote_height: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcaRemoteDepositsResponse {
+    pub deposits: Vec<RemoteDeposit>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AdminResponse {
+    pub admin: String,
+    /// The owner proposed by `TransferOwnership` until they accept
+    pub pending_admin: Option<String>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct PausedResponse {
+    pub paused: bool,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SupportedFeaturesResponse {
+    /// The enabled cargo features of the contract, in lexical order
+    pub features: Vec<String>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AlertSubscribersResponse {
+    pub subscribers: Vec<String>,
+    /// The contracts approved by the contract owner to subscribe, whether subscribed or not
+    pub approved: Vec<String>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ErrorCodesResponse {
+    /// The code of every error the contract can return, as prefixed to its message
+    pub codes: Vec<ErrorCode>,
+}
+
+#[cw_serde]
+pub struct DeadLetter {
+    pub idx: u32,
+    /// The local height at which the packet was received
+    pub height: u64,
+    /// The sudo callback that received the packet: `response`, `error` or `timeout`
+    pub callback: String,
+    pub sequence: Option<u64>,
+    pub source_channel: Option<String>,
+    /// Why the packet could not be attributed to an issued tx
+    pub reason: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DeadLettersResponse {
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+#[cw_serde]
+pub struct HookDeadLetter {
+    pub idx: u32,
+    /// The local height at which the hook was executed
+    pub height: u64,
+    pub sender: String,
+    /// The funds held by the contract until the dead letter is resolved
+    pub funds: Vec<Coin>,
+    pub rx_hash: String,
+    /// Why the hook failed validation
+    pub reason: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct HookDeadLettersResponse {
+    pub dead_letters: Vec<HookDeadLetter>,
+}
+
+#[cw_serde]
+pub struct QuarantinedIcqResult {
+    /// The local height at which the result was quarantined
+    pub height: u64,
+    /// The raw values of the KV result
+    pub values: Vec<Binary>,
+    pub error: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct QuarantinedIcqResultResponse {
+    pub result: Option<QuarantinedIcqResult>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FeeBalanceResponse {
+    pub balance: Vec<Coin>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct RoundtripFeeEstimateResponse {
+    pub coin: Coin,
+    /// The min IBC fee of each leg, the transfer to the ICA & the retrieval back to the contract
+    pub leg_fee: IbcFee,
+    /// The fee balance needed to issue both legs, the ack & timeout fees of each leg are escrowed
+    pub required_fee_balance: Vec<Coin>,
+    /// The most both legs cost once their outcome is known, the unused fee of each leg being refunded
+    pub max_cost: Vec<Coin>,
+    /// The gas relayers are estimated to spend submitting the `ROUNDTRIP_ICQ_UPDATES` balance ICQ results the
+    /// roundtrip waits on, paid by the relayers rather than the sender
+    pub icq_relayer_overhead: Coin,
+    /// The untrn of `max_cost` & `icq_relayer_overhead`, the whole cost of a roundtrip when the IBC fees are in untrn.
+    /// The refunded ICQ deposit is not included
+    pub total_untrn: Uint128,
+    /// The ICQ deposit paid to setup an ICA, refunded when it is torn down
+    pub icq_deposit: Vec<Coin>,
+    /// The blocks between the updates of the balance ICQ, the longest relayers take to observe the
+    /// transfer before the retrieval can be issued
+    pub icq_update_period: u64,
+}
+
+#[cw_serde]
+pub struct FeeObservation {
+    /// The local height at which the fee parameters were first observed
+    pub height: u64,
+    pub min_ibc_fee: IbcFee,
+    pub icq_deposit: Vec<Coin>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FeeHistoryResponse {
+    pub history: Vec<FeeObservation>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TimeoutsResponse {
+    pub timeout_seconds: u64,
+    pub timeout_height: u64,
+    pub timeout_revision_number: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TransferDenomsResponse {
+    pub denoms: Vec<String>,
+}
+
+/// The bounds of the amount of the coins of a transfer denom transferred by `TransferFunds`, unbounded if unset
+#[cw_serde]
+pub struct DenomTransferLimits {
+    pub denom: String,
+    pub min_transfer_amount: Option<Uint128>,
+    pub max_transfer_amount: Option<Uint128>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TransferLimitsResponse {
+    /// The limits of the bounded transfer denoms
+    pub limits: Vec<DenomTransferLimits>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct MemoLimitsResponse {
+    /// The limit of the memo of the submitted transfer, nesting the memo of every forwarded transfer
+    pub max_memo_len: u32,
+    /// The limit of the IBC hook memo executed on arrival of the transfer
+    pub max_hook_memo_len: u32,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct HashMappingsResponse {
+    /// The tx hash mappings kept, those recorded before mappings were height-stamped are neither counted nor swept
+    pub tx_hashes: u64,
+    /// The rx hash mappings kept, until their retrieval is delivered or they are swept
+    pub rx_hashes: u64,
+    pub retention_blocks: u64,
+    /// The local height of the oldest mapping not yet swept
+    pub oldest_height: Option<u64>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct Owner {
+    pub owner: String,
+    pub ica_idx: u32,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct OwnersResponse {
+    pub owners: Vec<Owner>,
+}
+
+#[cw_serde]
+pub struct LeaderboardEntry {
+    pub owner: String,
+    pub ica_idx: u32,
+    pub roundtrips: u32,
+    /// The total amount of the transfer asset retrieved from the ICA
+    pub volume: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct LeaderboardResponse {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[cw_serde]
+pub struct ChannelVolume {
+    pub denom: String,
+    pub channel: String,
+    /// Total amount successfully transferred out of the contract over the channel
+    pub outbound: Uint128,
+    /// Total amount received by the contract over the channel
+    pub inbound: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct GlobalStatsResponse {
+    pub volumes: Vec<ChannelVolume>,
+    /// The volumes of each denom summed across channels
+    pub denom_volumes: Vec<DenomVolume>,
+    /// The number of ICAs setup so far, including those since torn down
+    pub ica_count: u32,
+    pub totals: GlobalTotals,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DenomVolume {
+    pub denom: String,
+    /// Total amount successfully transferred out of the contract
+    pub outbound: Uint128,
+    /// Total amount received by the contract
+    pub inbound: Uint128,
+}
+
+/// The tx & roundtrip counters of every ICA summed, counters of ICAs torn down before they were tracked are missing
+#[cw_serde]
+#[derive(Default)]
+pub struct GlobalTotals {
+    pub tx_issued: u64,
+    pub tx_success: u64,
+    pub tx_error: u64,
+    pub tx_timeout: u64,
+    pub round_trips: u64,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcaListEntry {
+    pub ica_idx: u32,
+    pub owner: String,
+    pub address: Option<String>,
+    pub icq_id: Option<u64>,
+    pub label: Option<String>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ListIcasResponse {
+    pub icas: Vec<IcaListEntry>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcqInfoResponse {
+    pub balance: Option<RegisteredQuery>,
+    pub delegations: Option<RegisteredQuery>,
+    pub transfers: Option<RegisteredQuery>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IcaExport {
+    pub owner: String,
+    pub metadata: IcaMetadata,
+    pub tx_status: IcaTxStatus,
+    pub last_balance: IcaLastBalanceResponse,
+    /// The total amount of the transfer asset retrieved in completed roundtrips
+    pub round_trip_volume: Uint128,
+    pub fee_balance: Vec<Coin>,
+}
+
+#[cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ExportResponse {
+    pub icas: Vec<IcaExport>,
+    /// The cursor of the next page, `None` once all ICAs are exported
+    pub next_cursor: Option<u32>,
+    pub timeouts: TimeoutsResponse,
+    pub transfer_denoms: Vec<String>,
+    pub transfer_limits: TransferLimitsResponse,
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub struct AddressBookEntry {
+    /// A bech32 address on the remote chain
+    pub address: String,
+    pub label: String,
+    pub note: Option<String>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AddressBookResponse {
+    pub entries: Vec<AddressBookEntry>,
+}
+
+#[cw_serde]
+pub struct OperatorGrant {
+    pub operator: String,
+    /// The amount of each denom the operator can still transfer or retrieve
+    pub allowance: Vec<Coin>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct OperatorsResponse {
+    pub operators: Vec<OperatorGrant>,
+}
+
+#[cw_serde]
+pub struct IndexerCheckpoint {
+    pub indexer: String,
+    /// The last activity id indexed, if any
+    pub activity_id: Option<u64>,
+    /// The local height at which the checkpoint was recorded
+    pub height: Option<u64>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct IndexerCheckpointsResponse {
+    pub checkpoints: Vec<IndexerCheckpoint>,
+}
@@ -9,77 +9,290 @@
 pub mod helper;
 pub mod msgs;
 
+use std::ops::Range;
+
 use cosmwasm_std::{
-    entry_point, from_slice, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    SubMsg,
+    entry_point, from_slice, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, Event,
+    MessageInfo, Reply, Response, StdError, Storage, SubMsg, SubMsgResult, Uint128,
 };
 use msgs::IcaLastDelegationResponse;
 use neutron_sdk::{
     bindings::{msg::NeutronMsg, query::NeutronQuery},
     interchain_queries::v045::{
-        new_register_balance_query_msg, new_register_delegator_delegations_query_msg,
+        new_register_balances_query_msg, new_register_delegator_delegations_query_msg,
+        new_register_delegator_unbonding_delegations_query_msg,
     },
-    sudo::msg::SudoMsg,
+    sudo::msg::{RequestPacket, SudoMsg},
 };
 
 use crate::msgs::{
-    ExecuteMsg, IcaLastBalance, IcaLastBalanceResponse, IcaMetadata, IcaMetadataResponse,
-    IcaSetSizeResponse, InstantiateMsg, QueryMsg,
+    AllLastBalancesResponse, AllLastDelegationsResponse, BalanceSnapshot, ErrorCodesResponse,
+    ExecuteMsg, IcaBalanceAtResponse, IcaChannel, IcaLastBalance, IcaLastBalanceEntry,
+    IcaLastBalanceResponse, IcaLastDelegationEntry, IcaLastUnbondingResponse, IcaMetadata,
+    IcaMetadataResponse, IcaOwner, IcaPendingRewardsResponse, IcaRegistrationStatus,
+    IcaSetSizeResponse, IcaStakingTxStatusResponse, IcaStatus, IcqRefundAddressResponse,
+    InstantiateMsg, MigrateMsg, QueryMsg, RegistrationStatus, RegistrationStatusResponse,
+    StakingTx, StakingTxKind, StakingTxOutcome,
 };
 
 use common::{
-    combine_u32s, debug, ica_idx_from_port_id, icq_deposit_fee, parse_icq_registration_reply,
-    query_balance_icq, split_u64, OpenAckVersion, RemoteBalance,
+    check_icq_deposit, debug, foreign_deposit_coins,
+    funds::{add_coins, coins_attr, coins_shortfall, subtract_coins},
+    ibc::ensure_connection_open,
+    ica_idx_from_port_id, icq_deposit_fee_or,
+    indexes::rebuild_indexes,
+    is_query_type_mismatch, is_registered_kv_query,
+    msg_builders::{self, IcaTxConfig},
+    parse_icq_registration_reply, parse_issue_tx_reply, query_balances_icq,
+    updated_registered_kv_query,
+    validate::{validate_icq_update_period, validate_remote_address},
+    IcqDepositError, NeutronDeps, OpenAckVersion, RemoteBalances,
 };
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error(transparent)]
+    #[error("[E0001] {0}")]
     CosmwasmStd(#[from] cosmwasm_std::StdError),
-    #[error(transparent)]
+    #[error("[E0002] {0}")]
     NeutronSdk(#[from] neutron_sdk::NeutronError),
-    #[error(transparent)]
+    #[error("[E0003] {0}")]
     ParseReply(#[from] common::ParseReplyError),
-    #[error(transparent)]
+    #[error("[E0004] {0}")]
     QueryBalanceIcq(#[from] common::QueryBalanceIcqError),
-    #[error("ica index {ica_idx} is out of bounds, ica set size is {ica_set_size}")]
+    #[error("[E0005] {0}")]
+    Validation(#[from] common::validate::ValidationError),
+    #[error("[E0006] {0}")]
+    IbcState(#[from] common::ibc::IbcStateError),
+    #[error("[E0100] ica index {ica_idx} is out of bounds, ica set size is {ica_set_size}")]
     IcaIndexOutOfBounds { ica_idx: u32, ica_set_size: u32 },
-    #[error("icq deposit missing")]
+    #[error("[E0101] icq deposit missing")]
     IcqDepositMissing,
-    #[error("incorrect icq deposit asset")]
+    #[error("[E0102] incorrect icq deposit asset")]
     IncorrectIcqDepositAsset,
-    #[error("insufficient icq deposit")]
+    #[error("[E0103] insufficient icq deposit")]
     InsufficientIcqDeposit,
+    #[error("[E0104] unexpected open ack for port {port_id}")]
+    UnexpectedOpenAck { port_id: String },
+    #[error("[E0105] unauthorized")]
+    Unauthorized,
+    #[error("[E0106] at most {MAX_BALANCE_ICQ_DENOMS} denoms can be queried by the balance ICQ")]
+    TooManyBalanceIcqDenoms,
+    #[error("[E0107] the ica set must grow by at least one ica")]
+    EmptySetExpansion,
+    #[error("[E0108] no icqs are registered for ica {ica_idx}")]
+    NoIcqsRegistered { ica_idx: u32 },
+    #[error("[E0109] between 1 and {MAX_DELEGATIONS_ICQ_VALIDATORS} validators can be queried by the delegations ICQ")]
+    InvalidDelegationsIcqValidatorCount,
+    #[error("[E0110] staking amount must be greater than zero")]
+    ZeroStakingAmount,
+    #[error("[E0111] the attached funds are short of the ibc fee by {shortfall}")]
+    InsufficientIbcFee { shortfall: String },
+    #[error("[E0112] ica {ica_idx} is not active")]
+    IcaNotActive { ica_idx: u32 },
+    #[error("[E0113] ica {ica_idx} is already owned by {owner}")]
+    IcaAlreadyOwned { ica_idx: u32, owner: String },
+    #[error("[E0114] only the owner of ica {ica_idx} can act through it")]
+    NotIcaOwner { ica_idx: u32 },
+    #[error("[E0115] the sender is not approved to claim ica {ica_idx}")]
+    IcaClaimNotApproved { ica_idx: u32 },
+    #[error("[E0116] cannot migrate from contract version {from} to {to}")]
+    InvalidMigration { from: u32, to: u32 },
+    #[error("[E0117] no pending reply recorded for reply {id}")]
+    UnexpectedReply { id: u64 },
+}
+
+common::error_codes!(Error {
+    CosmwasmStd = 1,
+    NeutronSdk = 2,
+    ParseReply = 3,
+    QueryBalanceIcq = 4,
+    Validation = 5,
+    IbcState = 6,
+    IcaIndexOutOfBounds = 100,
+    IcqDepositMissing = 101,
+    IncorrectIcqDepositAsset = 102,
+    InsufficientIcqDeposit = 103,
+    UnexpectedOpenAck = 104,
+    Unauthorized = 105,
+    TooManyBalanceIcqDenoms = 106,
+    EmptySetExpansion = 107,
+    NoIcqsRegistered = 108,
+    InvalidDelegationsIcqValidatorCount = 109,
+    ZeroStakingAmount = 110,
+    InsufficientIbcFee = 111,
+    IcaNotActive = 112,
+    IcaAlreadyOwned = 113,
+    NotIcaOwner = 114,
+    IcaClaimNotApproved = 115,
+    InvalidMigration = 116,
+    UnexpectedReply = 117,
+});
+
+impl From<IcqDepositError> for Error {
+    fn from(err: IcqDepositError) -> Self {
+        match err {
+            IcqDepositError::Missing => Self::IcqDepositMissing,
+            IcqDepositError::IncorrectAsset => Self::IncorrectIcqDepositAsset,
+            IcqDepositError::Insufficient => Self::InsufficientIcqDeposit,
+        }
+    }
 }
 
+/// Version of the contract state layout, bumped whenever a migration is required
+pub const CONTRACT_VERSION: u32 = 1;
+
+// the kinds of ICQ recorded against each ICQ id
 const BALANCE_ICQ_KIND: u32 = 1;
 const DELEGATIONS_ICQ_KIND: u32 = 2;
+const UNBONDING_ICQ_KIND: u32 = 4;
+const REWARDS_ICQ_KIND: u32 = 5;
+
+/// The balance, delegations, unbonding delegations & rewards ICQs registered per ICA, each holding a deposit
+pub const ICQS_PER_ICA: u32 = 4;
+
+/// The number of keys a KV ICQ can query, as set by the interchainqueries module
+pub const MAX_KV_QUERY_KEYS: usize = 32;
+
+/// The number of denoms a balance ICQ can query, each being a storage key of the ICQ
+pub const MAX_BALANCE_ICQ_DENOMS: usize = 10;
+
+/// The number of validators a delegations ICQ can query, each being two storage keys of the ICQ, the unbonding
+/// delegations ICQ querying the same validators
+pub const MAX_DELEGATIONS_ICQ_VALIDATORS: usize = 10;
+
+/// The number of validators the rewards ICQ can query, bounded by its keys per validator, the rewards ICQ querying the
+/// first of the delegations ICQ validators
+pub const MAX_REWARDS_ICQ_VALIDATORS: usize =
+    MAX_KV_QUERY_KEYS / helper::REWARDS_ICQ_KEYS_PER_VALIDATOR;
+
+/// The number of blocks the registration of an ICA can await its channel open ack before `RegisterMissing` issues it
+/// again, the channel handshake having timed out
+pub const ICA_REGISTRATION_TIMEOUT_BLOCKS: u64 = 1_000;
+
+/// The number of ICAs whose ICQs have their update period set per `SetIcqUpdatePeriod` call, bounding the
+/// `UpdateInterchainQuery` msgs of a tx to `ICQS_PER_ICA` times as many
+pub const ICQ_UPDATE_PERIOD_BATCH_ICAS: u32 = 25;
+
+/// The seconds after which the staking txs of the ICAs time out
+pub const STAKING_TX_TIMEOUT_SECONDS: u64 = 60 * 60 * 24 * 7 * 2; // 2 weeks
+
+pub const DEFAULT_PAGE_LIMIT: u32 = 10;
+pub const MAX_PAGE_LIMIT: u32 = 30;
+
+/// The number of balance snapshots kept per ICA, the oldest are evicted first
+pub const BALANCE_HISTORY_LIMIT: usize = 32;
 
 pub mod state {
+    use common::{json_item, json_map};
     use cw_storage_macros::{item, map};
 
-    item!(delegations_icq_validator! : String);
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Coin;
+
+    use crate::msgs::{
+        BalanceSnapshot, IcaChannel, IcaLastDelegation, IcaLastUnbonding, IcaPendingRewards,
+        IcaStakingTxStatus, IcaStatus, StakingTxKind,
+    };
+
+    /// A staking tx submitted by an ICA, awaiting the reply carrying its sequence number
+    #[cw_serde]
+    pub struct PendingStakingTx {
+        pub validator: String,
+        pub amount: Coin,
+    }
+
+    #[cw_serde]
+    #[derive(Copy)]
+    pub enum ReplyKind {
+        RegisterIca,
+        /// The registration of an ICQ of the given ICQ kind
+        RegisterIcq(u32),
+        IssueStakingTx(StakingTxKind),
+    }
+
+    /// The context of a submessage, recorded against its reply id until the reply is received
+    #[cw_serde]
+    pub struct PendingReply {
+        pub kind: ReplyKind,
+        pub ica_idx: u32,
+    }
+
+    item!(contract_version : u32);
     item!(connection_id!             : String);
     item!(balance_icq_denom!         : String);
+    json_item!(additional_balance_icq_denoms : Vec<String>);
+    json_item!(delegations_icq_validators    : Vec<String>);
+    // charged as the ICQ deposit while the interchainqueries params cannot be queried
+    json_item!(icq_deposit_override : Vec<Coin>);
+    // receives the deposits of removed ICQs, the admin removing them if unset
+    item!(icq_refund_address : String);
     item!(ica_set_size!              : u32);
     item!(icq_update_period!         : u64);
 
     map!(ica: u32 => addr               : String);
+    map!(ica: u32 => owner              : String);
+    // the address the admin approved to claim the ICA, removed once the ICA is owned
+    map!(ica: u32 => approved_claimant  : String);
     map!(icq: u64 => ica_idx            : u32);
     map!(icq: u64 => kind               : u32);
     map!(ica: u32 => balance_icq_id     : u64);
     map!(ica: u32 => delegations_icq_id : u64);
+    map!(ica: u32 => unbonding_icq_id   : u64);
+    map!(ica: u32 => rewards_icq_id     : u64);
+    map!(ica: u32 => status_height      : u64);
+
+    // why the registration of the ICA or one of its ICQs failed, cleared when the ICA is registered again
+    map!(ica: u32 => registration_failure : String);
+
+    // the ICA index the next `RebuildIndexes` resumes from, removed once every ICA has been visited
+    item!(rebuild_cursor : u32);
+
+    // the ICA index the next `SetIcqUpdatePeriod` resumes from, removed once every ICA has been updated
+    item!(icq_update_period_cursor : u32);
+
+    // set when an ICQ is found to have been re-registered as another type of query
+    json_map!(icq: u64 => invalid : bool);
+    json_map!(ica: u32 => status  : IcaStatus);
+    json_map!(ica: u32 => channel : IcaChannel);
+
+    // ring buffer of the balances reported by the balance ICQ, in ascending local height order
+    json_map!(ica: u32 => balance_history : Vec<BalanceSnapshot>);
+    // the delegations reported by the last delegations ICQ result
+    json_map!(ica: u32 => last_delegation : IcaLastDelegation);
+    // the unbonding delegations reported by the last unbonding delegations ICQ result
+    json_map!(ica: u32 => last_unbonding  : IcaLastUnbonding);
+    // the pending rewards decoded from the last rewards ICQ result
+    json_map!(ica: u32 => pending_rewards : IcaPendingRewards);
+
+    json_map!(ica: u32 => pending_staking_tx : PendingStakingTx);
+    json_map!(ica: u32 => staking_tx_status  : IcaStakingTxStatus);
+
+    // the ICA that issued a staking tx, keyed by `{channel}/{seq_num}`, removed once the tx is acknowledged
+    map!(staking_tx: String => ica_idx : u32);
+
+    /// The state layout of version 0 instances, the initial release, read by their migration
+    pub mod v0 {
+        use cw_storage_macros::item;
+
+        // the only validator queried by the delegations ICQ
+        item!(delegations_icq_validator : String);
+    }
 }
 
 #[entry_point]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response<NeutronMsg>, Error> {
     debug!(deps, "handling instantiate msg");
 
+    msg.validate()?;
+
+    // reject configs pointing at connections & channels that cannot carry the contract's traffic
+    ensure_connection_open(deps.as_ref(), &msg.connection_id)?;
+
     // save configuration
     state::set_connection_id(deps.storage, &msg.connection_id);
 
@@ -89,340 +302,3342 @@ pub fn instantiate(
 
     state::set_balance_icq_denom(deps.storage, &msg.balance_icq_denom);
 
-    state::set_delegations_icq_validator(deps.storage, &msg.delegations_icq_validator);
+    let additional_balance_icq_denoms = dedup_balance_icq_denoms(
+        &msg.balance_icq_denom,
+        msg.additional_balance_icq_denoms.unwrap_or_default(),
+    );
 
-    // get required ICQ deposit fee
-    let icq_deposit_fee = icq_deposit_fee(deps.as_ref())?;
+    if additional_balance_icq_denoms.len() + 1 > MAX_BALANCE_ICQ_DENOMS {
+        return Err(Error::TooManyBalanceIcqDenoms);
+    }
 
-    // check instantiator has provided the required funds for an ICQ per ICA
-    let deposit = info.funds.first().ok_or(Error::IcqDepositMissing)?;
+    if !additional_balance_icq_denoms.is_empty() {
+        state::set_additional_balance_icq_denoms(deps.storage, &additional_balance_icq_denoms);
+    }
+
+    let delegations_icq_validators = dedup_validators(msg.delegations_icq_validators);
+
+    ensure_delegations_icq_validator_count(&delegations_icq_validators)?;
+
+    state::set_delegations_icq_validators(deps.storage, &delegations_icq_validators);
+
+    if let Some(deposit) = &msg.icq_deposit_override {
+        state::set_icq_deposit_override(deps.storage, deposit);
+    }
+
+    if let Some(address) = &msg.icq_refund_address {
+        let address = deps.api.addr_validate(address)?;
 
-    if deposit.denom != icq_deposit_fee.denom {
-        return Err(Error::IncorrectIcqDepositAsset);
+        state::set_icq_refund_address(deps.storage, address.as_str());
     }
 
-    let number_of_icqs = msg.ica_set_size * 2;
+    for IcaOwner { ica_idx, owner } in msg.ica_owners.into_iter().flatten() {
+        ica_idx_in_bounds(deps.as_ref(), ica_idx)?;
 
-    let required_deposit_amount = icq_deposit_fee.amount.u128() * u128::from(number_of_icqs);
+        let owner = deps.api.addr_validate(&owner)?;
 
-    if deposit.amount.u128() < required_deposit_amount {
-        return Err(Error::InsufficientIcqDeposit);
+        assign_ica_owner(deps.storage, ica_idx, owner.as_str())?;
     }
 
-    // Generate ICA registration messages
-    let register_ica_msgs =
-        (0..msg.ica_set_size).map(|idx| NeutronMsg::RegisterInterchainAccount {
-            connection_id: msg.connection_id.clone(),
-            interchain_account_id: idx.to_string(),
-        });
+    // check instantiator has provided the required funds for an ICQ per ICA
+    let required_deposit = required_icq_deposit(deps.as_ref(), msg.ica_set_size)?;
 
-    Ok(Response::default().add_messages(register_ica_msgs))
+    check_icq_deposit(&info.funds, &required_deposit)?;
+
+    let register_ica_msgs = register_icas(deps.storage, 0..msg.ica_set_size, env.block.height);
+
+    state::set_contract_version(deps.storage, CONTRACT_VERSION);
+
+    Ok(refund_foreign_deposit_coins(
+        Response::default().add_submessages(register_ica_msgs),
+        &info,
+        &required_deposit,
+    ))
 }
 
-#[entry_point]
-pub fn execute(
-    _deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    _msg: ExecuteMsg,
-) -> Result<Response, Error> {
-    Ok(Response::default())
+/// Refund the attached coins of none of the deposit denoms to the sender, they are not held as ICQ deposits
+fn refund_foreign_deposit_coins(
+    response: Response<NeutronMsg>,
+    info: &MessageInfo,
+    required_deposit: &[Coin],
+) -> Response<NeutronMsg> {
+    let refund = foreign_deposit_coins(&info.funds, required_deposit);
+
+    if refund.is_empty() {
+        return response;
+    }
+
+    let event = Event::new("foreign_deposit_coins_refunded")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("amount", coins_attr(&refund));
+
+    response
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund,
+        })
+        .add_event(event)
 }
 
-pub fn sudo_open_ack(
-    deps: DepsMut<NeutronQuery>,
-    _env: Env,
-    port_id: String,
-    _channel_id: String,
-    _counterparty_channel_id: String,
-    counterparty_version: String,
-) -> Result<Response<NeutronMsg>, Error> {
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, Error> {
+    let from = state::contract_version(deps.storage).unwrap_or_default();
+
     debug!(
         deps,
-        "received open ack for {port_id}: {counterparty_version}"
+        "handling migrate msg: version {from} -> {CONTRACT_VERSION}"
     );
 
-    // The version variable contains a JSON value with multiple fields,
-    // including the generated account address.
-    let parsed_version: OpenAckVersion =
-        from_slice(counterparty_version.as_bytes()).expect("valid counterparty_version");
+    if from > CONTRACT_VERSION {
+        return Err(Error::InvalidMigration {
+            from,
+            to: CONTRACT_VERSION,
+        });
+    }
 
-    let ica_idx = ica_idx_from_port_id(&port_id).expect("valid port id");
+    if from < 1 {
+        migrate_v0_to_v1(deps.storage, env.block.height);
+    }
 
-    state::set_ica_addr(deps.storage, &ica_idx, &parsed_version.address);
+    state::set_contract_version(deps.storage, CONTRACT_VERSION);
 
-    let connection_id = state::connection_id(deps.storage);
+    Ok(Response::default())
+}
 
-    let icq_update_period = state::icq_update_period(deps.storage);
+/// Version 0 instances query a single validator & do not track the status of their ICAs, those with an address have
+/// had their channel opened & the others are still registering, `RegisterMissing` re-issuing the registrations that
+/// timed out. Their channels were not recorded & their unbonding delegations & rewards ICQs were never registered, the
+/// latter are recorded as a registration failure for `RegisterMissing` to register them once the admin has sent their
+/// deposit to the contract.
+fn migrate_v0_to_v1(storage: &mut dyn Storage, height: u64) {
+    if let Some(validator) = state::v0::delegations_icq_validator(storage) {
+        state::set_delegations_icq_validators(storage, &vec![validator]);
+        state::v0::remove_delegations_icq_validator(storage);
+    }
 
-    let balance_icq_denom = state::balance_icq_denom(deps.storage);
+    for ica_idx in 0..state::ica_set_size(storage) {
+        if state::ica_addr(storage, &ica_idx).is_none() {
+            set_ica_status(storage, ica_idx, IcaStatus::Registering, height);
+            continue;
+        }
 
-    let delegations_icq_validator = state::delegations_icq_validator(deps.storage);
+        set_ica_status(storage, ica_idx, IcaStatus::Active, height);
 
-    let balance_icq_register_msg = new_register_balance_query_msg(
-        connection_id.clone(),
-        parsed_version.address.clone(),
-        balance_icq_denom,
-        icq_update_period,
-    )?;
+        state::set_ica_registration_failure(
+            storage,
+            &ica_idx,
+            "unbonding delegations & rewards icqs not registered by contract version 0",
+        );
+    }
+}
 
-    let delegations_icq_register_msg = new_register_delegator_delegations_query_msg(
-        connection_id,
-        parsed_version.address,
-        vec![delegations_icq_validator],
-        icq_update_period,
-    )?;
+/// The deposit of the balance, delegations, unbonding delegations & rewards ICQs of `ica_count` ICAs, the instantiator's override is charged
+/// if the interchainqueries params cannot be queried
+pub fn required_icq_deposit(deps: Deps<NeutronQuery>, ica_count: u32) -> Result<Vec<Coin>, Error> {
+    let icq_deposit_fee = icq_deposit_fee_or(deps, state::icq_deposit_override(deps.storage))?;
 
-    let response = Response::default()
-        .add_submessage(SubMsg::reply_on_success(
-            balance_icq_register_msg,
-            combine_u32s(BALANCE_ICQ_KIND, ica_idx),
-        ))
-        .add_submessage(SubMsg::reply_on_success(
-            delegations_icq_register_msg,
-            combine_u32s(DELEGATIONS_ICQ_KIND, ica_idx),
-        ));
+    let number_of_icqs = Uint128::from(ica_count) * Uint128::from(ICQS_PER_ICA);
 
-    Ok(response)
+    let required_deposit = icq_deposit_fee
+        .into_iter()
+        .map(|coin| Coin {
+            amount: coin.amount * number_of_icqs,
+            denom: coin.denom,
+        })
+        .collect();
+
+    Ok(required_deposit)
 }
 
-pub fn sudo_kv_query_result(
-    deps: DepsMut<NeutronQuery>,
-    _env: Env,
-    query_id: u64,
-) -> Result<Response<NeutronMsg>, Error> {
-    let ica_idx =
-        state::icq_ica_idx(deps.storage, &query_id).expect("the icq is associated with an ica");
+/// Issue the registration of the ICAs at `ica_idxs`, each awaiting its channel open ack from the local `height`.
+/// A failed registration is recorded as the registration failure of the ICA rather than failing the whole tx.
+fn register_icas(
+    storage: &mut dyn Storage,
+    ica_idxs: impl IntoIterator<Item = u32>,
+    height: u64,
+) -> Vec<SubMsg<NeutronMsg>> {
+    let connection_id = state::connection_id(storage);
 
-    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("the ica has an address");
+    ica_idxs
+        .into_iter()
+        .map(|ica_idx| {
+            set_ica_status(storage, ica_idx, IcaStatus::Registering, height);
 
-    let ica_kind = state::icq_kind(deps.storage, &query_id).expect("the icq has a kind");
+            state::remove_ica_registration_failure(storage, &ica_idx);
 
-    let kind_str = match ica_kind {
-        BALANCE_ICQ_KIND => stringify!(BALANCE_ICQ_KIND),
-        DELEGATIONS_ICQ_KIND => stringify!(DELEGATIONS_ICQ_KIND),
-        _ => unreachable!(),
-    };
+            let msg = NeutronMsg::RegisterInterchainAccount {
+                connection_id: connection_id.clone(),
+                interchain_account_id: ica_idx.to_string(),
+            };
 
-    debug!(
-        deps,
-        "received {kind_str} ICQ {query_id} update for ICA {ica_idx} with address: {ica_addr}"
-    );
+            let reply_id = save_pending_reply(storage, state::ReplyKind::RegisterIca, ica_idx);
 
-    Ok(Response::default())
+            SubMsg::reply_on_error(msg, reply_id)
+        })
+        .collect()
+}
+
+/// Whether every ICQ of the ICA at `ica_idx` is registered
+fn icqs_registered(storage: &dyn Storage, ica_idx: u32) -> bool {
+    state::ica_balance_icq_id(storage, &ica_idx).is_some()
+        && state::ica_delegations_icq_id(storage, &ica_idx).is_some()
+        && state::ica_unbonding_icq_id(storage, &ica_idx).is_some()
+        && state::ica_rewards_icq_id(storage, &ica_idx).is_some()
+}
+
+/// The progress of the registration of the ICA at `ica_idx` & of its ICQs
+#[must_use]
+pub fn registration_status(storage: &dyn Storage, ica_idx: u32) -> RegistrationStatus {
+    if let Some(reason) = state::ica_registration_failure(storage, &ica_idx) {
+        return RegistrationStatus::Failed { reason };
+    }
+
+    if icqs_registered(storage, ica_idx) {
+        return RegistrationStatus::IcqRegistered;
+    }
+
+    if state::ica_channel(storage, &ica_idx).is_some() {
+        return RegistrationStatus::ChannelOpen;
+    }
+
+    RegistrationStatus::Pending
+}
+
+/// Assign `owner` to the ICA at `ica_idx` if it has no owner yet, only the admin replaces owners
+fn assign_ica_owner(storage: &mut dyn Storage, ica_idx: u32, owner: &str) -> Result<(), Error> {
+    if let Some(owner) = state::ica_owner(storage, &ica_idx) {
+        return Err(Error::IcaAlreadyOwned { ica_idx, owner });
+    }
+
+    state::set_ica_owner(storage, &ica_idx, owner);
+
+    state::remove_ica_approved_claimant(storage, &ica_idx);
+
+    Ok(())
+}
+
+/// Only the owner of the ICA at `ica_idx` can act through it, the contract admin acting through the ICAs without one
+fn ensure_ica_operator(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    info: &MessageInfo,
+    ica_idx: u32,
+) -> Result<(), Error> {
+    match state::ica_owner(deps.storage, &ica_idx) {
+        Some(owner) if owner == info.sender.as_str() => Ok(()),
+        Some(_) => Err(Error::NotIcaOwner { ica_idx }),
+        None => ensure_contract_admin(deps, env, info),
+    }
+}
+
+fn ensure_contract_admin(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    info: &MessageInfo,
+) -> Result<(), Error> {
+    let contract_info = deps
+        .querier
+        .query_wasm_contract_info(env.contract.address.as_str())?;
+
+    if contract_info.admin.as_deref() != Some(info.sender.as_str()) {
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(())
 }
 
 #[entry_point]
-pub fn sudo(
+pub fn execute(
     deps: DepsMut<NeutronQuery>,
     env: Env,
-    msg: SudoMsg,
+    info: MessageInfo,
+    msg: ExecuteMsg,
 ) -> Result<Response<NeutronMsg>, Error> {
-    debug!(deps, "handling sudo msg");
-
     match msg {
-        SudoMsg::OpenAck {
-            port_id,
-            channel_id,
-            counterparty_channel_id,
-            counterparty_version,
-        } => sudo_open_ack(
+        ExecuteMsg::RebuildIndexes { limit } => execute_rebuild_indexes(deps, env, info, limit),
+        ExecuteMsg::RegisterMissing {} => execute_register_missing(deps, env, info),
+        ExecuteMsg::ExpandSet { additional } => execute_expand_set(deps, env, info, additional),
+        ExecuteMsg::RemoveIcqs { ica_idx } => execute_remove_icqs(deps, env, info, ica_idx),
+        ExecuteMsg::UpdateIcqRefundAddress { address } => {
+            execute_update_icq_refund_address(deps, env, info, address)
+        }
+        ExecuteMsg::UpdateDelegationsIcqValidators { validators } => {
+            execute_update_delegations_icq_validators(deps, env, info, validators)
+        }
+        ExecuteMsg::SetIcqUpdatePeriod { period } => {
+            execute_set_icq_update_period(deps, env, info, period)
+        }
+        ExecuteMsg::ApproveIcaClaim { ica_idx, claimant } => {
+            execute_approve_ica_claim(deps, env, info, ica_idx, claimant)
+        }
+        ExecuteMsg::ClaimIca { ica_idx } => execute_claim_ica(deps, info, ica_idx),
+        ExecuteMsg::SetIcaOwner { ica_idx, owner } => {
+            execute_set_ica_owner(deps, env, info, ica_idx, Some(owner))
+        }
+        ExecuteMsg::RevokeIcaOwner { ica_idx } => {
+            execute_set_ica_owner(deps, env, info, ica_idx, None)
+        }
+        ExecuteMsg::Delegate {
+            ica_idx,
+            validator,
+            amount,
+        } => execute_staking_tx(
             deps,
             env,
-            port_id,
-            channel_id,
-            counterparty_channel_id,
-            counterparty_version,
+            info,
+            StakingTxKind::Delegate,
+            ica_idx,
+            validator,
+            amount,
+        ),
+        ExecuteMsg::Undelegate {
+            ica_idx,
+            validator,
+            amount,
+        } => execute_staking_tx(
+            deps,
+            env,
+            info,
+            StakingTxKind::Undelegate,
+            ica_idx,
+            validator,
+            amount,
         ),
+    }
+}
 
-        SudoMsg::KVQueryResult { query_id } => sudo_kv_query_result(deps, env, query_id),
+pub fn execute_claim_ica(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    ica_idx: u32,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing claim ica {ica_idx} for {}", info.sender);
 
-        _ => {
-            debug!(deps, "unexpected sudo msg: {msg:?}");
-            Ok(Response::default())
+    ica_idx_in_bounds(deps.as_ref(), ica_idx)?;
+
+    if state::ica_approved_claimant(deps.storage, &ica_idx).as_deref() != Some(info.sender.as_str())
+    {
+        // an owned ICA has no approved claimant left, report who owns it
+        if let Some(owner) = state::ica_owner(deps.storage, &ica_idx) {
+            return Err(Error::IcaAlreadyOwned { ica_idx, owner });
         }
+
+        return Err(Error::IcaClaimNotApproved { ica_idx });
     }
+
+    assign_ica_owner(deps.storage, ica_idx, info.sender.as_str())?;
+
+    let event = Event::new("ica_claimed")
+        .add_attribute("ica_idx", ica_idx.to_string())
+        .add_attribute("owner", info.sender);
+
+    Ok(Response::default().add_event(event))
 }
 
-#[entry_point]
-pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, Error> {
-    let reply_id = reply.id;
+pub fn execute_approve_ica_claim(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    ica_idx: u32,
+    claimant: Option<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing approve ica {ica_idx} claim: {claimant:?}");
 
-    let (icq_kind, ica_idx) = split_u64(reply_id);
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
 
-    debug!(
-        deps,
-        "received reply with id {}, split into ICQ kind {icq_kind} and ICA index {ica_idx}",
-        reply.id
-    );
+    ica_idx_in_bounds(deps.as_ref(), ica_idx)?;
 
-    let icq_id = parse_icq_registration_reply(reply)?;
+    let mut event = Event::new("ica_claim_approved").add_attribute("ica_idx", ica_idx.to_string());
 
-    state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
+    match claimant {
+        Some(claimant) => {
+            let claimant = deps.api.addr_validate(&claimant)?;
 
-    state::set_icq_kind(deps.storage, &icq_id, icq_kind);
+            if let Some(owner) = state::ica_owner(deps.storage, &ica_idx) {
+                return Err(Error::IcaAlreadyOwned { ica_idx, owner });
+            }
 
-    match icq_kind {
-        BALANCE_ICQ_KIND => {
-            debug!(deps, "Got balance ICQ with id {icq_id} for ICA {ica_idx}");
-            state::set_ica_balance_icq_id(deps.storage, &ica_idx, icq_id);
+            state::set_ica_approved_claimant(deps.storage, &ica_idx, claimant.as_str());
+
+            event = event.add_attribute("claimant", claimant);
         }
+        None => state::remove_ica_approved_claimant(deps.storage, &ica_idx),
+    }
 
-        DELEGATIONS_ICQ_KIND => {
-            debug!(
-                deps,
-                "Got delegations ICQ with id {icq_id} for ICA {ica_idx}"
-            );
-            state::set_ica_delegations_icq_id(deps.storage, &ica_idx, icq_id);
+    Ok(Response::default().add_event(event))
+}
+
+/// Assign `owner` to the ICA at `ica_idx` or revoke its owner, whether the ICA is owned or not
+pub fn execute_set_ica_owner(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    ica_idx: u32,
+    owner: Option<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set ica {ica_idx} owner: {owner:?}");
+
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
+
+    ica_idx_in_bounds(deps.as_ref(), ica_idx)?;
+
+    state::remove_ica_approved_claimant(deps.storage, &ica_idx);
+
+    let event = match owner {
+        Some(owner) => {
+            let owner = deps.api.addr_validate(&owner)?;
+
+            state::set_ica_owner(deps.storage, &ica_idx, owner.as_str());
+
+            Event::new("ica_owner_set")
+                .add_attribute("ica_idx", ica_idx.to_string())
+                .add_attribute("owner", owner)
         }
+        None => {
+            state::remove_ica_owner(deps.storage, &ica_idx);
 
-        _ => {
-            debug!(
-                deps,
-                "received reply with id {reply_id} that has unknown ICQ kind: {icq_kind}",
-            );
+            Event::new("ica_owner_revoked").add_attribute("ica_idx", ica_idx.to_string())
         }
     };
 
-    Ok(Response::default())
+    Ok(Response::default().add_event(event))
 }
 
-pub fn ica_idx_in_bounds(deps: Deps<NeutronQuery>, ica_idx: u32) -> Result<bool, Error> {
+pub fn execute_rebuild_indexes(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    limit: u32,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing rebuild indexes: limit {limit}");
+
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
+
+    let start = state::rebuild_cursor(deps.storage).unwrap_or_default();
+
     let ica_set_size = state::ica_set_size(deps.storage);
 
-    if ica_idx >= ica_set_size {
-        return Err(Error::IcaIndexOutOfBounds {
-            ica_idx,
-            ica_set_size,
-        });
+    let pass = rebuild_indexes(
+        deps.storage,
+        start,
+        ica_set_size,
+        limit,
+        rebuild_ica_indexes,
+    );
+
+    match pass.next_ica_idx {
+        Some(next_ica_idx) => state::set_rebuild_cursor(deps.storage, next_ica_idx),
+        None => state::remove_rebuild_cursor(deps.storage),
     }
 
-    Ok(true)
+    Ok(Response::default().add_event(pass.event()))
 }
 
-pub fn query_ica_metadata(
-    deps: Deps<NeutronQuery>,
-    ica_idx: u32,
-) -> Result<IcaMetadataResponse, Error> {
-    ica_idx_in_bounds(deps, ica_idx)?;
+/// The ICAs of the set whose channel was closed by a timed out tx, or still registering whose registration failed or
+/// has awaited its channel open ack for more than `ICA_REGISTRATION_TIMEOUT_BLOCKS` at the local `height`,
+/// registrations still in flight being left alone
+#[must_use]
+pub fn missing_icas(storage: &dyn Storage, height: u64) -> Vec<u32> {
+    (0..state::ica_set_size(storage))
+        .filter(|ica_idx| match state::ica_status(storage, ica_idx) {
+            // registering the ICA again opens a new channel to the same address
+            Some(IcaStatus::ChannelClosed) => true,
+
+            Some(IcaStatus::Registering) | None => {
+                if state::ica_registration_failure(storage, ica_idx).is_some() {
+                    return true;
+                }
+
+                let registering_since =
+                    state::ica_status_height(storage, ica_idx).unwrap_or_default();
+
+                height.saturating_sub(registering_since) > ICA_REGISTRATION_TIMEOUT_BLOCKS
+            }
+
+            _ => false,
+        })
+        .collect()
+}
 
-    let maybe_ica_addr = state::ica_addr(deps.storage, &ica_idx);
+pub fn execute_register_missing(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing register missing");
 
-    let maybe_balance_icq_id = state::ica_balance_icq_id(deps.storage, &ica_idx);
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
 
-    let maybe_delegations_icq_id = state::ica_delegations_icq_id(deps.storage, &ica_idx);
+    let missing = missing_icas(deps.storage, env.block.height);
 
-    let metadata = maybe_ica_addr
-        .zip(maybe_balance_icq_id)
-        .zip(maybe_delegations_icq_id)
-        .map(
-            |((address, balance_icq_id), delegations_icq_id)| IcaMetadata {
-                address,
-                balance_icq_id,
-                delegation_icq_id: delegations_icq_id,
-            },
-        );
+    let ica_idxs: Vec<String> = missing.iter().map(ToString::to_string).collect();
+
+    let mut event =
+        Event::new("ica_registrations_reissued").add_attribute("ica_idxs", ica_idxs.join(","));
+
+    // the ICQs of the missing ICAs were never registered, so their deposit is still held by the contract, or are kept
+    // registered while the closed channel of their ICA is reopened
+    let register_ica_msgs = register_icas(deps.storage, missing, env.block.height);
+
+    let mut response = Response::default().add_submessages(register_ica_msgs);
+
+    // as is the deposit of the ICQs whose registration failed once the channel of their ICA opened
+    let mut icq_ica_idxs = vec![];
+
+    for ica_idx in 0..state::ica_set_size(deps.storage) {
+        let Some(ica_addr) = state::ica_addr(deps.storage, &ica_idx) else {
+            continue;
+        };
+
+        if state::ica_registration_failure(deps.storage, &ica_idx).is_none() {
+            continue;
+        }
+
+        let register_icq_msgs = register_missing_icqs(deps.storage, ica_idx, &ica_addr)?;
+
+        if !register_icq_msgs.is_empty() {
+            icq_ica_idxs.push(ica_idx.to_string());
+        }
 
-    Ok(IcaMetadataResponse { metadata })
+        response = response.add_submessages(register_icq_msgs);
+    }
+
+    event = event.add_attribute("icq_ica_idxs", icq_ica_idxs.join(","));
+
+    Ok(response.add_event(event))
 }
 
-pub fn query_last_ica_balance(
-    deps: Deps<NeutronQuery>,
-    ica_idx: u32,
-) -> Result<IcaLastBalanceResponse, Error> {
-    ica_idx_in_bounds(deps, ica_idx)?;
+pub fn execute_expand_set(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    additional: u32,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing expand set: {additional} additional icas");
 
-    let Some(icq_id) = state::ica_balance_icq_id(deps.storage, &ica_idx) else {
-        return Ok(IcaLastBalanceResponse::default());
-    };
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
 
-    debug!(deps, "querying balance ICQ {icq_id} for ICA {ica_idx}");
+    if additional == 0 {
+        return Err(Error::EmptySetExpansion);
+    }
 
-    let Some(RemoteBalance {
-        last_submitted_result_local_height,
-        balance,
-    }) = query_balance_icq(deps, icq_id)?
-    else {
-        return Ok(IcaLastBalanceResponse::default());
-    };
+    let required_deposit = required_icq_deposit(deps.as_ref(), additional)?;
 
-    let address =
-        state::ica_addr(deps.storage, &ica_idx).expect("a registered ica has an address set");
+    check_icq_deposit(&info.funds, &required_deposit)?;
 
-    let last_balance = IcaLastBalance {
-        balance,
-        address,
-        last_submitted_result_local_height,
-    };
+    let ica_set_size = state::ica_set_size(deps.storage);
 
-    Ok(IcaLastBalanceResponse {
-        last_balance: Some(last_balance),
-    })
+    let new_ica_set_size = ica_set_size
+        .checked_add(additional)
+        .ok_or_else(|| StdError::generic_err("ica set size overflow"))?;
+
+    state::set_ica_set_size(deps.storage, new_ica_set_size);
+
+    let event = Event::new("ica_set_expanded")
+        .add_attribute("from", ica_set_size.to_string())
+        .add_attribute("to", new_ica_set_size.to_string());
+
+    let register_ica_msgs = register_icas(
+        deps.storage,
+        ica_set_size..new_ica_set_size,
+        env.block.height,
+    );
+
+    let response = Response::default()
+        .add_submessages(register_ica_msgs)
+        .add_event(event);
+
+    Ok(refund_foreign_deposit_coins(
+        response,
+        &info,
+        &required_deposit,
+    ))
 }
 
-pub fn query_last_ica_delegation(
-    deps: Deps<NeutronQuery>,
+/// The ids of the ICQs registered for the ICA at `ica_idx`
+fn ica_icq_ids(storage: &dyn Storage, ica_idx: u32) -> Vec<u64> {
+    [
+        state::ica_balance_icq_id(storage, &ica_idx),
+        state::ica_delegations_icq_id(storage, &ica_idx),
+        state::ica_unbonding_icq_id(storage, &ica_idx),
+        state::ica_rewards_icq_id(storage, &ica_idx),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Clear the ICQ ids of the ICA at `ica_idx` & their reverse lookups, returning the ids cleared
+fn clear_ica_icqs(storage: &mut dyn Storage, ica_idx: u32) -> Vec<u64> {
+    let icq_ids = ica_icq_ids(storage, ica_idx);
+
+    state::remove_ica_balance_icq_id(storage, &ica_idx);
+
+    state::remove_ica_delegations_icq_id(storage, &ica_idx);
+
+    state::remove_ica_unbonding_icq_id(storage, &ica_idx);
+
+    state::remove_ica_rewards_icq_id(storage, &ica_idx);
+
+    state::remove_ica_last_delegation(storage, &ica_idx);
+
+    state::remove_ica_last_unbonding(storage, &ica_idx);
+
+    state::remove_ica_pending_rewards(storage, &ica_idx);
+
+    for icq_id in &icq_ids {
+        state::remove_icq_ica_idx(storage, icq_id);
+        state::remove_icq_kind(storage, icq_id);
+        state::remove_icq_invalid(storage, icq_id);
+    }
+
+    icq_ids
+}
+
+pub fn execute_remove_icqs(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
     ica_idx: u32,
-) -> Result<IcaLastDelegationResponse, Error> {
-    ica_idx_in_bounds(deps, ica_idx)?;
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing remove icqs of ICA {ica_idx}");
 
-    let Some(icq_id) = state::ica_delegations_icq_id(deps.storage, &ica_idx) else {
-        return Ok(IcaLastDelegationResponse::default());
-    };
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
+
+    ica_idx_in_bounds(deps.as_ref(), ica_idx)?;
+
+    let icq_ids = clear_ica_icqs(deps.storage, ica_idx);
+
+    if icq_ids.is_empty() {
+        return Err(Error::NoIcqsRegistered { ica_idx });
+    }
+
+    let mut deposit = vec![];
+
+    let mut response = Response::default();
+
+    for &query_id in &icq_ids {
+        add_coins(
+            &mut deposit,
+            &deps.as_ref().registered_query(query_id)?.deposit,
+        );
+
+        response = response.add_message(NeutronMsg::RemoveInterchainQuery { query_id });
+    }
 
-    debug!(deps, "querying delegation ICQ {icq_id} for ICA {ica_idx}");
+    let refund_address =
+        state::icq_refund_address(deps.storage).unwrap_or(info.sender.into_string());
 
-    let last_delegation = helper::query_delegation_icq(deps, icq_id)?;
+    let icq_ids: Vec<String> = icq_ids.iter().map(ToString::to_string).collect();
 
-    Ok(IcaLastDelegationResponse { last_delegation })
+    let event = Event::new("icqs_removed")
+        .add_attribute("ica_idx", ica_idx.to_string())
+        .add_attribute("icq_ids", icq_ids.join(","))
+        .add_attribute("refund_address", refund_address.as_str())
+        .add_attribute("deposit", coins_attr(&deposit));
+
+    // the module refunds the deposits to the contract as each ICQ is removed, before the refund is forwarded
+    if !deposit.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: refund_address,
+            amount: deposit,
+        });
+    }
+
+    Ok(response.add_event(event))
 }
 
-#[entry_point]
-pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
-    let res = match msg {
-        QueryMsg::IcaSetSize {} => {
-            let ica_set_size = state::ica_set_size(deps.storage);
+pub fn execute_update_icq_refund_address(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing update icq refund address: {address:?}");
 
-            to_binary(&IcaSetSizeResponse { ica_set_size })?
-        }
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
 
-        QueryMsg::IcaMetadata { ica_idx } => {
-            let ica_metadata = query_ica_metadata(deps, ica_idx)?;
+    match address {
+        Some(address) => {
+            let address = deps.api.addr_validate(&address)?;
 
-            to_binary(&ica_metadata)?
+            state::set_icq_refund_address(deps.storage, address.as_str());
         }
+        None => state::remove_icq_refund_address(deps.storage),
+    }
 
-        QueryMsg::IcaLastBalance { ica_idx } => {
-            let last_ica_balance = query_last_ica_balance(deps, ica_idx)?;
+    Ok(Response::default())
+}
 
-            to_binary(&last_ica_balance)?
+pub fn execute_update_delegations_icq_validators(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    validators: Vec<String>,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(
+        deps,
+        "executing update delegations icq validators: {validators:?}"
+    );
+
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
+
+    let validators = dedup_validators(validators);
+
+    ensure_delegations_icq_validator_count(&validators)?;
+
+    for validator in &validators {
+        validate_remote_address(validator)?;
+    }
+
+    state::set_delegations_icq_validators(deps.storage, &validators);
+
+    let connection_id = state::connection_id(deps.storage);
+
+    let icq_update_period = state::icq_update_period(deps.storage);
+
+    let mut response = Response::default();
+
+    // ICQs registered from now on query the new validators, the registered ones have their keys replaced
+    for ica_idx in 0..state::ica_set_size(deps.storage) {
+        let Some(ica_addr) = state::ica_addr(deps.storage, &ica_idx) else {
+            continue;
+        };
+
+        let icqs = [
+            (
+                state::ica_delegations_icq_id(deps.storage, &ica_idx),
+                new_register_delegator_delegations_query_msg(
+                    connection_id.clone(),
+                    ica_addr.clone(),
+                    validators.clone(),
+                    icq_update_period,
+                )?,
+            ),
+            (
+                state::ica_unbonding_icq_id(deps.storage, &ica_idx),
+                new_register_delegator_unbonding_delegations_query_msg(
+                    connection_id.clone(),
+                    ica_addr.clone(),
+                    validators.clone(),
+                    icq_update_period,
+                )?,
+            ),
+            (
+                state::ica_rewards_icq_id(deps.storage, &ica_idx),
+                new_register_rewards_query_msg(
+                    connection_id.clone(),
+                    &ica_addr,
+                    &rewards_icq_validators(deps.storage),
+                    icq_update_period,
+                )?,
+            ),
+        ];
+
+        for (query_id, register_msg) in icqs {
+            let Some(query_id) = query_id else {
+                continue;
+            };
+
+            // the keys are those the registration of the ICQ for the ICA would query
+            let NeutronMsg::RegisterInterchainQuery { keys, .. } = register_msg else {
+                unreachable!("delegator ICQs are registered by a RegisterInterchainQuery msg");
+            };
+
+            response = response.add_message(NeutronMsg::UpdateInterchainQuery {
+                query_id,
+                new_keys: Some(keys),
+                new_update_period: None,
+                new_transactions_filter: None,
+            });
         }
+    }
 
-        QueryMsg::IcaLastDelegation { ica_idx } => {
-            let last_ica_delegation = query_last_ica_delegation(deps, ica_idx)?;
+    let event = Event::new("delegations_icq_validators_updated")
+        .add_attribute("validators", validators.join(","));
 
-            to_binary(&last_ica_delegation)?
+    Ok(response.add_event(event))
+}
+
+/// The msgs setting the update period of the ICQs of at most `ICQ_UPDATE_PERIOD_BATCH_ICAS` ICAs from `start` to
+/// `period`, along with the ICA index the next batch starts from, `None` once every ICA has been visited
+fn icq_update_period_batch(
+    storage: &dyn Storage,
+    start: u32,
+    period: u64,
+) -> (Vec<NeutronMsg>, Option<u32>) {
+    let ica_set_size = state::ica_set_size(storage);
+
+    let end = start
+        .saturating_add(ICQ_UPDATE_PERIOD_BATCH_ICAS)
+        .min(ica_set_size);
+
+    let msgs = (start..end)
+        .flat_map(|ica_idx| ica_icq_ids(storage, ica_idx))
+        .map(|query_id| NeutronMsg::UpdateInterchainQuery {
+            query_id,
+            new_keys: None,
+            new_update_period: Some(period),
+            new_transactions_filter: None,
+        })
+        .collect();
+
+    (msgs, (end < ica_set_size).then_some(end))
+}
+
+pub fn execute_set_icq_update_period(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    period: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "executing set icq update period: {period} blocks");
+
+    ensure_contract_admin(deps.as_ref(), &env, &info)?;
+
+    validate_icq_update_period(period)?;
+
+    // a new period restarts the updates from the first ICA, the same period resumes the updates in progress
+    let start = if period == state::icq_update_period(deps.storage) {
+        match state::icq_update_period_cursor(deps.storage) {
+            Some(cursor) => cursor,
+            None => state::ica_set_size(deps.storage),
         }
+    } else {
+        0
     };
 
-    Ok(res)
+    // ICQs registered from now on are registered with the new period
+    state::set_icq_update_period(deps.storage, period);
+
+    let (msgs, next_ica_idx) = icq_update_period_batch(deps.storage, start, period);
+
+    match next_ica_idx {
+        Some(next_ica_idx) => state::set_icq_update_period_cursor(deps.storage, next_ica_idx),
+        None => state::remove_icq_update_period_cursor(deps.storage),
+    }
+
+    let event = Event::new("icq_update_period_set")
+        .add_attribute("period", period.to_string())
+        .add_attribute("start", start.to_string())
+        .add_attribute("updated_icqs", msgs.len().to_string())
+        .add_attribute("complete", next_ica_idx.is_none().to_string());
+
+    Ok(Response::default().add_messages(msgs).add_event(event))
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// The key of the staking tx with sequence number `seq_num` on `channel`
+fn staking_tx_key(channel: &str, seq_num: u64) -> String {
+    format!("{channel}/{seq_num}")
+}
 
-    #[test]
-    fn icq_reply_id_round_trip() {
-        for i in 0..100 {
-            assert_eq!(
-                (BALANCE_ICQ_KIND, i),
-                split_u64(combine_u32s(BALANCE_ICQ_KIND, i))
-            );
-            assert_eq!(
-                (DELEGATIONS_ICQ_KIND, i),
-                split_u64(combine_u32s(DELEGATIONS_ICQ_KIND, i))
-            );
+/// Submit a tx (un)delegating `amount` of the balance ICQ denom through the ICA at `ica_idx`, the attached funds
+/// must cover the min IBC fee of the tx
+pub fn execute_staking_tx(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    kind: StakingTxKind,
+    ica_idx: u32,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(
+        deps,
+        "executing {kind:?} of {amount} from ICA {ica_idx} to {validator}"
+    );
+
+    ica_idx_in_bounds(deps.as_ref(), ica_idx)?;
+
+    ensure_ica_operator(deps.as_ref(), &env, &info, ica_idx)?;
+
+    if amount.is_zero() {
+        return Err(Error::ZeroStakingAmount);
+    }
+
+    validate_remote_address(&validator)?;
+
+    if state::ica_status(deps.storage, &ica_idx) != Some(IcaStatus::Active) {
+        return Err(Error::IcaNotActive { ica_idx });
+    }
+
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("active icas have an address");
+
+    let fee = deps.as_ref().min_ibc_fee()?;
+
+    let mut required_fee = fee.ack_fee.clone();
+
+    add_coins(&mut required_fee, &fee.timeout_fee);
+
+    let shortfall = coins_shortfall(&info.funds, &required_fee);
+
+    if !shortfall.is_empty() {
+        return Err(Error::InsufficientIbcFee {
+            shortfall: coins_attr(&shortfall),
+        });
+    }
+
+    // only the required fee is escrowed by the submission, the rest of the funds is sent back
+    let fee_refund =
+        subtract_coins(&info.funds, &required_fee).expect("the funds cover the required fee");
+
+    let coin = Coin {
+        denom: state::balance_icq_denom(deps.storage),
+        amount,
+    };
+
+    let type_url = match kind {
+        StakingTxKind::Delegate => msg_builders::MSG_DELEGATE_TYPE_URL,
+        StakingTxKind::Undelegate => msg_builders::MSG_UNDELEGATE_TYPE_URL,
+    };
+
+    let staking_msg =
+        msg_builders::staking_msg(type_url, ica_addr, validator.clone(), coin.clone());
+
+    let ica_tx_config = IcaTxConfig {
+        connection_id: state::connection_id(deps.storage),
+        timeout_seconds: STAKING_TX_TIMEOUT_SECONDS,
+    };
+
+    let submit_tx_msg =
+        msg_builders::submit_tx(&ica_tx_config, ica_idx.to_string(), vec![staking_msg], fee);
+
+    let event = Event::new("staking_tx_requested")
+        .add_attribute("ica_idx", ica_idx.to_string())
+        .add_attribute("kind", format!("{kind:?}").to_lowercase())
+        .add_attribute("validator", validator.as_str())
+        .add_attribute("amount", coin.to_string())
+        .add_attribute("fee_refund", coins_attr(&fee_refund));
+
+    state::set_ica_pending_staking_tx(
+        deps.storage,
+        &ica_idx,
+        &state::PendingStakingTx {
+            validator,
+            amount: coin,
+        },
+    );
+
+    let reply_id = save_pending_reply(
+        deps.storage,
+        state::ReplyKind::IssueStakingTx(kind),
+        ica_idx,
+    );
+
+    let mut response = Response::default()
+        .add_submessage(SubMsg::reply_on_success(submit_tx_msg, reply_id))
+        .add_event(event);
+
+    if !fee_refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.into_string(),
+            amount: fee_refund,
+        });
+    }
+
+    Ok(response)
+}
+
+/// Record the staking tx of the ICA at `ica_idx` issued with sequence number `seq_num` on `channel` at the local
+/// `height`, its acknowledgement is attributed to the ICA by the sequence number & channel
+fn record_staking_tx_issued(
+    storage: &mut dyn Storage,
+    kind: StakingTxKind,
+    ica_idx: u32,
+    seq_num: u64,
+    channel: String,
+    height: u64,
+) {
+    let state::PendingStakingTx { validator, amount } =
+        state::ica_pending_staking_tx(storage, &ica_idx).expect("set by the staking tx execution");
+
+    state::remove_ica_pending_staking_tx(storage, &ica_idx);
+
+    state::set_staking_tx_ica_idx(storage, &staking_tx_key(&channel, seq_num), ica_idx);
+
+    let staking_tx = StakingTx {
+        kind,
+        validator,
+        amount,
+        seq_num,
+        channel,
+        issued_height: height,
+        outcome: StakingTxOutcome::Pending,
+    };
+
+    let mut status = state::ica_staking_tx_status(storage, &ica_idx).unwrap_or_default();
+
+    match kind {
+        StakingTxKind::Delegate => status.last_delegate = Some(staking_tx),
+        StakingTxKind::Undelegate => status.last_undelegate = Some(staking_tx),
+    }
+
+    state::set_ica_staking_tx_status(storage, &ica_idx, &status);
+}
+
+/// Record the `outcome` of the staking tx with sequence number `seq_num` on `channel`, returning the ICA that issued
+/// it, `None` if the tx was not issued as a staking tx
+fn record_staking_tx_outcome(
+    storage: &mut dyn Storage,
+    seq_num: u64,
+    channel: &str,
+    outcome: &StakingTxOutcome,
+) -> Option<u32> {
+    let key = staking_tx_key(channel, seq_num);
+
+    let ica_idx = state::staking_tx_ica_idx(storage, &key)?;
+
+    state::remove_staking_tx_ica_idx(storage, &key);
+
+    let mut status = state::ica_staking_tx_status(storage, &ica_idx).unwrap_or_default();
+
+    match outcome {
+        StakingTxOutcome::Pending => {}
+        StakingTxOutcome::Succeeded => status.success_count += 1,
+        StakingTxOutcome::Failed { .. } => status.error_count += 1,
+        StakingTxOutcome::TimedOut => status.timeout_count += 1,
+    }
+
+    // only the last tx of each kind is kept, the outcomes of earlier ones are only counted
+    for tx in [&mut status.last_delegate, &mut status.last_undelegate]
+        .into_iter()
+        .flatten()
+        .filter(|tx| tx.seq_num == seq_num && tx.channel == channel)
+    {
+        tx.outcome = outcome.clone();
+    }
+
+    state::set_ica_staking_tx_status(storage, &ica_idx, &status);
+
+    Some(ica_idx)
+}
+
+fn ensure_delegations_icq_validator_count(validators: &[String]) -> Result<(), Error> {
+    if !(1..=MAX_DELEGATIONS_ICQ_VALIDATORS).contains(&validators.len()) {
+        return Err(Error::InvalidDelegationsIcqValidatorCount);
+    }
+
+    Ok(())
+}
+
+/// The validators queried by the delegations & unbonding delegations ICQs of each ICA
+#[must_use]
+pub fn delegations_icq_validators(storage: &dyn Storage) -> Vec<String> {
+    state::delegations_icq_validators(storage).expect("set during instantiation")
+}
+
+/// The validators queried by the rewards ICQ of each ICA, the first `MAX_REWARDS_ICQ_VALIDATORS` of the delegations
+/// ICQ validators
+#[must_use]
+pub fn rewards_icq_validators(storage: &dyn Storage) -> Vec<String> {
+    let mut validators = delegations_icq_validators(storage);
+
+    validators.truncate(MAX_REWARDS_ICQ_VALIDATORS);
+
+    validators
+}
+
+/// Restore the ICQ reverse lookups of the ICA at `ica_idx`, returning the number restored
+fn rebuild_ica_indexes(storage: &mut dyn Storage, ica_idx: u32) -> u32 {
+    let icq_ids = [
+        (
+            BALANCE_ICQ_KIND,
+            state::ica_balance_icq_id(storage, &ica_idx),
+        ),
+        (
+            DELEGATIONS_ICQ_KIND,
+            state::ica_delegations_icq_id(storage, &ica_idx),
+        ),
+        (
+            UNBONDING_ICQ_KIND,
+            state::ica_unbonding_icq_id(storage, &ica_idx),
+        ),
+        (
+            REWARDS_ICQ_KIND,
+            state::ica_rewards_icq_id(storage, &ica_idx),
+        ),
+    ];
+
+    let mut restored = 0;
+
+    for (kind, icq_id) in icq_ids {
+        let Some(icq_id) = icq_id else {
+            continue;
+        };
+
+        if state::icq_ica_idx(storage, &icq_id).is_none() {
+            state::set_icq_ica_idx(storage, &icq_id, ica_idx);
+            restored += 1;
+        }
+
+        if state::icq_kind(storage, &icq_id).is_none() {
+            state::set_icq_kind(storage, &icq_id, kind);
+            restored += 1;
         }
     }
+
+    restored
+}
+
+/// Transition the ICA at `ica_idx` to `status` at the local `height`
+pub fn set_ica_status(storage: &mut dyn Storage, ica_idx: u32, status: IcaStatus, height: u64) {
+    state::set_ica_status(storage, &ica_idx, &status);
+    state::set_ica_status_height(storage, &ica_idx, height);
+}
+
+pub fn sudo_open_ack(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    port_id: String,
+    channel_id: String,
+    counterparty_channel_id: String,
+    counterparty_version: String,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(
+        deps,
+        "received open ack for {port_id}: {counterparty_version}"
+    );
+
+    // The version variable contains a JSON value with multiple fields,
+    // including the generated account address.
+    let parsed_version: OpenAckVersion =
+        from_slice(counterparty_version.as_bytes()).expect("valid counterparty_version");
+
+    // only the acks of the ICAs of the set that are still registering are expected
+    let ica_idx = ica_idx_from_port_id(&port_id, env.contract.address.as_str())
+        .filter(|ica_idx| state::ica_status(deps.storage, ica_idx) == Some(IcaStatus::Registering))
+        .ok_or_else(|| Error::UnexpectedOpenAck {
+            port_id: port_id.clone(),
+        })?;
+
+    set_ica_status(deps.storage, ica_idx, IcaStatus::Active, env.block.height);
+
+    state::set_ica_addr(deps.storage, &ica_idx, &parsed_version.address);
+
+    state::set_ica_channel(
+        deps.storage,
+        &ica_idx,
+        &IcaChannel {
+            port_id,
+            channel_id,
+            counterparty_channel_id,
+        },
+    );
+
+    let register_icq_msgs = register_missing_icqs(deps.storage, ica_idx, &parsed_version.address)?;
+
+    Ok(Response::default().add_submessages(register_icq_msgs))
+}
+
+/// Issue the registration of the ICQs of the ICA at `ica_idx` holding the address `ica_addr` that are not registered,
+/// all of them once its channel opens or those whose registration failed
+pub fn register_missing_icqs(
+    storage: &mut dyn Storage,
+    ica_idx: u32,
+    ica_addr: &str,
+) -> Result<Vec<SubMsg<NeutronMsg>>, Error> {
+    let connection_id = state::connection_id(storage);
+
+    let icq_update_period = state::icq_update_period(storage);
+
+    let mut register_icq_msgs = vec![];
+
+    if state::ica_balance_icq_id(storage, &ica_idx).is_none() {
+        let msg = new_register_balances_query_msg(
+            connection_id.clone(),
+            ica_addr.to_owned(),
+            balance_icq_denoms(storage),
+            icq_update_period,
+        )?;
+
+        let reply_id = save_pending_reply(
+            storage,
+            state::ReplyKind::RegisterIcq(BALANCE_ICQ_KIND),
+            ica_idx,
+        );
+
+        register_icq_msgs.push(SubMsg::reply_always(msg, reply_id));
+    }
+
+    if state::ica_delegations_icq_id(storage, &ica_idx).is_none() {
+        let msg = new_register_delegator_delegations_query_msg(
+            connection_id.clone(),
+            ica_addr.to_owned(),
+            delegations_icq_validators(storage),
+            icq_update_period,
+        )?;
+
+        let reply_id = save_pending_reply(
+            storage,
+            state::ReplyKind::RegisterIcq(DELEGATIONS_ICQ_KIND),
+            ica_idx,
+        );
+
+        register_icq_msgs.push(SubMsg::reply_always(msg, reply_id));
+    }
+
+    if state::ica_unbonding_icq_id(storage, &ica_idx).is_none() {
+        let msg = new_register_delegator_unbonding_delegations_query_msg(
+            connection_id.clone(),
+            ica_addr.to_owned(),
+            delegations_icq_validators(storage),
+            icq_update_period,
+        )?;
+
+        let reply_id = save_pending_reply(
+            storage,
+            state::ReplyKind::RegisterIcq(UNBONDING_ICQ_KIND),
+            ica_idx,
+        );
+
+        register_icq_msgs.push(SubMsg::reply_always(msg, reply_id));
+    }
+
+    if state::ica_rewards_icq_id(storage, &ica_idx).is_none() {
+        let msg = new_register_rewards_query_msg(
+            connection_id,
+            ica_addr,
+            &rewards_icq_validators(storage),
+            icq_update_period,
+        )?;
+
+        let reply_id = save_pending_reply(
+            storage,
+            state::ReplyKind::RegisterIcq(REWARDS_ICQ_KIND),
+            ica_idx,
+        );
+
+        register_icq_msgs.push(SubMsg::reply_always(msg, reply_id));
+    }
+
+    Ok(register_icq_msgs)
+}
+
+/// The registration of the rewards ICQ of the ICA at `ica_addr`, the reward periods of each validator are added to
+/// its keys once its first result is submitted
+pub fn new_register_rewards_query_msg(
+    connection_id: String,
+    ica_addr: &str,
+    validators: &[String],
+    update_period: u64,
+) -> Result<NeutronMsg, Error> {
+    let validators: Vec<_> = validators
+        .iter()
+        .map(|validator| (validator.clone(), None))
+        .collect();
+
+    Ok(NeutronMsg::RegisterInterchainQuery {
+        query_type: "kv".to_owned(),
+        keys: helper::rewards_icq_keys(ica_addr, &validators)?,
+        transactions_filter: String::new(),
+        connection_id,
+        update_period,
+    })
+}
+
+/// Drop the additional balance ICQ `denoms` repeating `balance_icq_denom` or an earlier denom, each denom being queried
+/// by a single key of the balance ICQ
+#[must_use]
+pub fn dedup_balance_icq_denoms(balance_icq_denom: &str, denoms: Vec<String>) -> Vec<String> {
+    let mut deduped: Vec<String> = vec![];
+
+    for denom in denoms {
+        if denom != balance_icq_denom && !deduped.contains(&denom) {
+            deduped.push(denom);
+        }
+    }
+
+    deduped
+}
+
+/// `validators` without repeats, in the order they were first given
+#[must_use]
+pub fn dedup_validators(validators: Vec<String>) -> Vec<String> {
+    let mut deduped: Vec<String> = vec![];
+
+    for validator in validators {
+        if !deduped.contains(&validator) {
+            deduped.push(validator);
+        }
+    }
+
+    deduped
+}
+
+/// The denoms queried by the balance ICQ of each ICA, `balance_icq_denom` first
+#[must_use]
+pub fn balance_icq_denoms(storage: &dyn Storage) -> Vec<String> {
+    let mut denoms = vec![state::balance_icq_denom(storage)];
+
+    denoms.extend(state::additional_balance_icq_denoms(storage).unwrap_or_default());
+
+    denoms
+}
+
+/// Handle the update of a KV ICQ result.
+///
+/// The submitter of the result is not known to the contract: the sudo msg only carries the query id & the
+/// interchainqueries module offers no option to restrict who may submit the results of a query. Anyone can relay
+/// a KV result, which is trusted as the module verifies its proofs against the remote chain's light client.
+pub fn sudo_kv_query_result(
+    deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    query_id: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    let ica_idx =
+        state::icq_ica_idx(deps.storage, &query_id).expect("the icq is associated with an ica");
+
+    let ica_addr = state::ica_addr(deps.storage, &ica_idx).expect("the ica has an address");
+
+    let ica_kind = state::icq_kind(deps.storage, &query_id).expect("the icq has a kind");
+
+    let kind_str = match ica_kind {
+        BALANCE_ICQ_KIND => stringify!(BALANCE_ICQ_KIND),
+        DELEGATIONS_ICQ_KIND => stringify!(DELEGATIONS_ICQ_KIND),
+        UNBONDING_ICQ_KIND => stringify!(UNBONDING_ICQ_KIND),
+        REWARDS_ICQ_KIND => stringify!(REWARDS_ICQ_KIND),
+        _ => unreachable!(),
+    };
+
+    debug!(
+        deps,
+        "received {kind_str} ICQ {query_id} update for ICA {ica_idx} with address: {ica_addr}"
+    );
+
+    if state::icq_invalid(deps.storage, &query_id).is_none()
+        && !is_registered_kv_query(deps.as_ref(), query_id)?
+    {
+        return Ok(invalidate_icq(deps.storage, ica_idx, query_id));
+    }
+
+    if state::icq_invalid(deps.storage, &query_id).is_some() {
+        return Ok(Response::default());
+    }
+
+    // the results are decoded once as they are submitted, queries are served from storage
+    if ica_kind == BALANCE_ICQ_KIND {
+        if let Some(RemoteBalances {
+            last_submitted_result_local_height,
+            balances,
+        }) = query_balances_icq(deps.as_ref(), query_id)?
+        {
+            let snapshot = BalanceSnapshot {
+                balances,
+                local_height: last_submitted_result_local_height,
+            };
+
+            record_balance_snapshot(deps.storage, ica_idx, snapshot);
+        }
+    }
+
+    if ica_kind == DELEGATIONS_ICQ_KIND {
+        if let Some(last_delegation) = helper::query_delegation_icq(deps.as_ref(), query_id)? {
+            state::set_ica_last_delegation(deps.storage, &ica_idx, &last_delegation);
+        }
+    }
+
+    if ica_kind == UNBONDING_ICQ_KIND {
+        if let Some(last_unbonding) = helper::query_unbonding_icq(deps.as_ref(), query_id)? {
+            state::set_ica_last_unbonding(deps.storage, &ica_idx, &last_unbonding);
+        }
+    }
+
+    if ica_kind == REWARDS_ICQ_KIND {
+        return record_rewards(deps, ica_idx, &ica_addr, query_id);
+    }
+
+    Ok(Response::default())
+}
+
+/// Record the pending rewards of a rewards ICQ result, moving the keys of the ICQ to the reward periods of the
+/// result as delegations start over & validators complete periods. The rewards of a validator whose periods moved
+/// past the queried ones are kept from the previous result & marked stale until the next result.
+pub fn record_rewards(
+    deps: DepsMut<NeutronQuery>,
+    ica_idx: u32,
+    ica_addr: &str,
+    query_id: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    let validators = rewards_icq_validators(deps.storage);
+
+    let Some(result) = helper::query_rewards_icq(deps.as_ref(), query_id, ica_addr, &validators)?
+    else {
+        return Ok(Response::default());
+    };
+
+    let previous = state::ica_pending_rewards(deps.storage, &ica_idx);
+
+    let pending_rewards =
+        helper::keep_stale_rewards(result.pending_rewards, previous, &result.periods);
+
+    state::set_ica_pending_rewards(deps.storage, &ica_idx, &pending_rewards);
+
+    let keys = helper::rewards_icq_keys(ica_addr, &result.periods)?;
+
+    if keys == deps.as_ref().registered_query(query_id)?.keys {
+        return Ok(Response::default());
+    }
+
+    Ok(
+        Response::default().add_message(NeutronMsg::UpdateInterchainQuery {
+            query_id,
+            new_keys: Some(keys),
+            new_update_period: None,
+            new_transactions_filter: None,
+        }),
+    )
+}
+
+/// Append a snapshot to the ICA's balance history, replacing one recorded at the same height & evicting the oldest
+/// once the history is full
+pub fn record_balance_snapshot(storage: &mut dyn Storage, ica_idx: u32, snapshot: BalanceSnapshot) {
+    let mut history = state::ica_balance_history(storage, &ica_idx).unwrap_or_default();
+
+    if history
+        .last()
+        .is_some_and(|last| last.local_height >= snapshot.local_height)
+    {
+        history.pop();
+    }
+
+    history.push(snapshot);
+
+    if history.len() > BALANCE_HISTORY_LIMIT {
+        history.drain(..history.len() - BALANCE_HISTORY_LIMIT);
+    }
+
+    state::set_ica_balance_history(storage, &ica_idx, &history);
+}
+
+/// Stop serving an ICQ that is no longer registered as a KV query, emitting an alert
+pub fn invalidate_icq(
+    storage: &mut dyn Storage,
+    ica_idx: u32,
+    query_id: u64,
+) -> Response<NeutronMsg> {
+    state::set_icq_invalid(storage, &query_id, &true);
+
+    let event = Event::new("icq_invalidated")
+        .add_attribute("ica_idx", ica_idx.to_string())
+        .add_attribute("icq_id", query_id.to_string())
+        .add_attribute("reason", "query type mismatch");
+
+    Response::default().add_event(event)
+}
+
+pub fn sudo_tx_query_result(
+    deps: DepsMut<NeutronQuery>,
+    query_id: u64,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "received tx query result for ICQ {query_id}");
+
+    // only KV queries are ever registered, a tracked ICQ reporting tx results has been re-registered
+    let Some(ica_idx) = state::icq_ica_idx(deps.storage, &query_id) else {
+        return Ok(Response::default());
+    };
+
+    if state::icq_invalid(deps.storage, &query_id).is_some() {
+        return Ok(Response::default());
+    }
+
+    Ok(invalidate_icq(deps.storage, ica_idx, query_id))
+}
+
+/// Handle the acknowledgement or timeout of a staking tx, a timed out tx closing the ordered channel of the ICA
+pub fn sudo_staking_tx_outcome(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    request: RequestPacket,
+    outcome: StakingTxOutcome,
+) -> Result<Response<NeutronMsg>, Error> {
+    let (Some(seq_num), Some(channel)) = (request.sequence, request.source_channel) else {
+        debug!(
+            deps,
+            "received outcome {outcome:?} for an incomplete packet"
+        );
+
+        return Ok(Response::default());
+    };
+
+    let Some(ica_idx) = record_staking_tx_outcome(deps.storage, seq_num, &channel, &outcome) else {
+        debug!(
+            deps,
+            "received outcome {outcome:?} for unknown tx {seq_num} on {channel}"
+        );
+
+        return Ok(Response::default());
+    };
+
+    debug!(
+        deps,
+        "ICA {ica_idx} staking tx {seq_num} on {channel}: {outcome:?}"
+    );
+
+    let event_ty = match &outcome {
+        StakingTxOutcome::Pending => unreachable!("acknowledged txs are no longer pending"),
+        StakingTxOutcome::Succeeded => "staking_tx_succeeded",
+        StakingTxOutcome::Failed { .. } => "staking_tx_failed",
+        StakingTxOutcome::TimedOut => "staking_tx_timed_out",
+    };
+
+    // the timeout closed the ordered channel of the ICA, until `RegisterMissing` reopens it
+    if outcome == StakingTxOutcome::TimedOut {
+        set_ica_status(
+            deps.storage,
+            ica_idx,
+            IcaStatus::ChannelClosed,
+            env.block.height,
+        );
+    }
+
+    let mut event = Event::new(event_ty)
+        .add_attribute("ica_idx", ica_idx.to_string())
+        .add_attribute("seq_num", seq_num.to_string())
+        .add_attribute("channel", channel);
+
+    if let StakingTxOutcome::Failed { error } = outcome {
+        event = event.add_attribute("error", error);
+    }
+
+    Ok(Response::default().add_event(event))
+}
+
+#[entry_point]
+pub fn sudo(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<NeutronMsg>, Error> {
+    debug!(deps, "handling sudo msg");
+
+    match msg {
+        SudoMsg::OpenAck {
+            port_id,
+            channel_id,
+            counterparty_channel_id,
+            counterparty_version,
+        } => sudo_open_ack(
+            deps,
+            env,
+            port_id,
+            channel_id,
+            counterparty_channel_id,
+            counterparty_version,
+        ),
+
+        SudoMsg::KVQueryResult { query_id } => sudo_kv_query_result(deps, env, query_id),
+
+        SudoMsg::TxQueryResult { query_id, .. } => sudo_tx_query_result(deps, query_id),
+
+        SudoMsg::Response { request, .. } => {
+            sudo_staking_tx_outcome(deps, env, request, StakingTxOutcome::Succeeded)
+        }
+
+        SudoMsg::Error { request, details } => sudo_staking_tx_outcome(
+            deps,
+            env,
+            request,
+            StakingTxOutcome::Failed { error: details },
+        ),
+
+        SudoMsg::Timeout { request } => {
+            sudo_staking_tx_outcome(deps, env, request, StakingTxOutcome::TimedOut)
+        }
+
+        _ => {
+            debug!(deps, "unexpected sudo msg: {msg:?}");
+            Ok(Response::default())
+        }
+    }
+}
+
+/// Record why the registration of the ICA at `ica_idx` or of one of its ICQs failed
+fn record_registration_failure(
+    storage: &mut dyn Storage,
+    kind: state::ReplyKind,
+    ica_idx: u32,
+    error: &str,
+) -> Response {
+    let registration = match kind {
+        state::ReplyKind::RegisterIca => "ica",
+        state::ReplyKind::RegisterIcq(BALANCE_ICQ_KIND) => "balance icq",
+        state::ReplyKind::RegisterIcq(DELEGATIONS_ICQ_KIND) => "delegations icq",
+        state::ReplyKind::RegisterIcq(UNBONDING_ICQ_KIND) => "unbonding delegations icq",
+        state::ReplyKind::RegisterIcq(REWARDS_ICQ_KIND) => "rewards icq",
+        _ => "unknown",
+    };
+
+    let reason = format!("{registration} registration failed: {error}");
+
+    state::set_ica_registration_failure(storage, &ica_idx, &reason);
+
+    let event = Event::new("registration_failed")
+        .add_attribute("ica_idx", ica_idx.to_string())
+        .add_attribute("reason", reason);
+
+    Response::default().add_event(event)
+}
+
+/// Record the context of a submessage, returning the id its reply is dispatched from
+fn save_pending_reply(storage: &mut dyn Storage, kind: state::ReplyKind, ica_idx: u32) -> u64 {
+    common::save_reply_payload(storage, &state::PendingReply { kind, ica_idx })
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, Error> {
+    let reply_id = reply.id;
+
+    let state::PendingReply { kind, ica_idx } = common::take_reply_payload(deps.storage, reply_id)
+        .ok_or(Error::UnexpectedReply { id: reply_id })?;
+
+    debug!(
+        deps,
+        "received reply {reply_id} of kind {kind:?} for ICA {ica_idx}"
+    );
+
+    // staking txs are submitted on success only, a failed submission reverting the execution
+    if let state::ReplyKind::IssueStakingTx(kind) = kind {
+        let (seq_num, channel) = parse_issue_tx_reply(reply)?;
+
+        let event = Event::new("staking_tx_issued")
+            .add_attribute("ica_idx", ica_idx.to_string())
+            .add_attribute("seq_num", seq_num.to_string())
+            .add_attribute("channel", channel.as_str());
+
+        record_staking_tx_issued(
+            deps.storage,
+            kind,
+            ica_idx,
+            seq_num,
+            channel,
+            env.block.height,
+        );
+
+        return Ok(Response::default().add_event(event));
+    }
+
+    // wasmd redacts the error to its codespace & code, e.g. "codespace: wasm, code: 5", the full error is only logged
+    if let SubMsgResult::Err(error) = reply.result {
+        return Ok(record_registration_failure(
+            deps.storage,
+            kind,
+            ica_idx,
+            &error,
+        ));
+    }
+
+    // ICA registrations only reply on error
+    let state::ReplyKind::RegisterIcq(icq_kind) = kind else {
+        return Ok(Response::default());
+    };
+
+    let icq_id = parse_icq_registration_reply(reply)?;
+
+    state::set_icq_ica_idx(deps.storage, &icq_id, ica_idx);
+
+    state::set_icq_kind(deps.storage, &icq_id, icq_kind);
+
+    match icq_kind {
+        BALANCE_ICQ_KIND => {
+            debug!(deps, "Got balance ICQ with id {icq_id} for ICA {ica_idx}");
+            state::set_ica_balance_icq_id(deps.storage, &ica_idx, icq_id);
+        }
+
+        DELEGATIONS_ICQ_KIND => {
+            debug!(
+                deps,
+                "Got delegations ICQ with id {icq_id} for ICA {ica_idx}"
+            );
+            state::set_ica_delegations_icq_id(deps.storage, &ica_idx, icq_id);
+        }
+
+        UNBONDING_ICQ_KIND => {
+            debug!(
+                deps,
+                "Got unbonding delegations ICQ with id {icq_id} for ICA {ica_idx}"
+            );
+            state::set_ica_unbonding_icq_id(deps.storage, &ica_idx, icq_id);
+        }
+
+        REWARDS_ICQ_KIND => {
+            debug!(deps, "Got rewards ICQ with id {icq_id} for ICA {ica_idx}");
+            state::set_ica_rewards_icq_id(deps.storage, &ica_idx, icq_id);
+        }
+
+        _ => {
+            debug!(
+                deps,
+                "received reply with id {reply_id} that has unknown ICQ kind: {icq_kind}",
+            );
+        }
+    };
+
+    // a failed ICQ registration is only behind the ICA once all of its ICQs are registered, another may have failed
+    if icqs_registered(deps.storage, ica_idx) {
+        state::remove_ica_registration_failure(deps.storage, &ica_idx);
+    }
+
+    Ok(Response::default())
+}
+
+pub fn ica_idx_in_bounds(deps: Deps<NeutronQuery>, ica_idx: u32) -> Result<bool, Error> {
+    let ica_set_size = state::ica_set_size(deps.storage);
+
+    if ica_idx >= ica_set_size {
+        return Err(Error::IcaIndexOutOfBounds {
+            ica_idx,
+            ica_set_size,
+        });
+    }
+
+    Ok(true)
+}
+
+pub fn query_ica_metadata(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+) -> Result<IcaMetadataResponse, Error> {
+    ica_idx_in_bounds(deps, ica_idx)?;
+
+    let address = state::ica_addr(deps.storage, &ica_idx);
+
+    let owner = state::ica_owner(deps.storage, &ica_idx);
+
+    let balance_icq_id = state::ica_balance_icq_id(deps.storage, &ica_idx);
+
+    let delegation_icq_id = state::ica_delegations_icq_id(deps.storage, &ica_idx);
+
+    let unbonding_icq_id = state::ica_unbonding_icq_id(deps.storage, &ica_idx);
+
+    let rewards_icq_id = state::ica_rewards_icq_id(deps.storage, &ica_idx);
+
+    let status = state::ica_status(deps.storage, &ica_idx).unwrap_or_default();
+
+    let last_status_change_height =
+        state::ica_status_height(deps.storage, &ica_idx).unwrap_or_default();
+
+    let channel = state::ica_channel(deps.storage, &ica_idx);
+
+    Ok(IcaMetadataResponse {
+        metadata: Some(IcaMetadata {
+            address,
+            owner,
+            balance_icq_id,
+            delegation_icq_id,
+            unbonding_icq_id,
+            rewards_icq_id,
+            status,
+            last_status_change_height,
+            channel,
+        }),
+    })
+}
+
+pub fn query_last_ica_balance(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+) -> Result<IcaLastBalanceResponse, Error> {
+    ica_idx_in_bounds(deps, ica_idx)?;
+
+    let Some(icq_id) = state::ica_balance_icq_id(deps.storage, &ica_idx) else {
+        return Ok(IcaLastBalanceResponse::default());
+    };
+
+    debug!(deps, "querying balance ICQ {icq_id} for ICA {ica_idx}");
+
+    let icq_invalid = IcaLastBalanceResponse {
+        icq_invalid: true,
+        ..IcaLastBalanceResponse::default()
+    };
+
+    if state::icq_invalid(deps.storage, &icq_id).is_some() {
+        return Ok(icq_invalid);
+    }
+
+    let registered_query = match updated_registered_kv_query(deps, icq_id) {
+        Err(err) if is_query_type_mismatch(&err) => return Ok(icq_invalid),
+        res => res?,
+    };
+
+    let Some(registered_query) = registered_query else {
+        return Ok(IcaLastBalanceResponse::default());
+    };
+
+    let address =
+        state::ica_addr(deps.storage, &ica_idx).expect("a registered ica has an address set");
+
+    // the balance recorded by the last result handled, only missing if the result failed to be handled
+    let cached =
+        state::ica_balance_history(deps.storage, &ica_idx).and_then(|mut history| history.pop());
+
+    let Some(BalanceSnapshot {
+        balances,
+        local_height,
+    }) = cached
+    else {
+        let remote_balances = match query_balances_icq(deps, icq_id) {
+            Err(err) if err.is_query_type_mismatch() => return Ok(icq_invalid),
+            res => res?,
+        };
+
+        let Some(RemoteBalances {
+            last_submitted_result_local_height,
+            balances,
+        }) = remote_balances
+        else {
+            return Ok(IcaLastBalanceResponse::default());
+        };
+
+        return Ok(IcaLastBalanceResponse {
+            last_balance: Some(IcaLastBalance {
+                balances,
+                address,
+                last_submitted_result_local_height,
+            }),
+            icq_invalid: false,
+            fresh: true,
+        });
+    };
+
+    let last_balance = IcaLastBalance {
+        balances,
+        address,
+        last_submitted_result_local_height: local_height,
+    };
+
+    Ok(IcaLastBalanceResponse {
+        last_balance: Some(last_balance),
+        icq_invalid: false,
+        fresh: local_height >= registered_query.last_submitted_result_local_height,
+    })
+}
+
+/// The closest snapshot of the ICA's balance history at or before the local `height`
+#[must_use]
+pub fn balance_at(storage: &dyn Storage, ica_idx: u32, height: u64) -> Option<BalanceSnapshot> {
+    let mut history = state::ica_balance_history(storage, &ica_idx).unwrap_or_default();
+
+    // snapshots are in ascending height order, the closest one at or before `height` precedes the partition point
+    let at_or_before = history.partition_point(|snapshot| snapshot.local_height <= height);
+
+    history.truncate(at_or_before);
+
+    history.pop()
+}
+
+pub fn query_ica_balance_at(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+    height: u64,
+) -> Result<IcaBalanceAtResponse, Error> {
+    ica_idx_in_bounds(deps, ica_idx)?;
+
+    Ok(IcaBalanceAtResponse {
+        snapshot: balance_at(deps.storage, ica_idx, height),
+    })
+}
+
+/// The last result of an ICA's ICQ, as recorded when it was handled
+struct LastIcqResult<T> {
+    value: Option<T>,
+    icq_invalid: bool,
+    fresh: bool,
+}
+
+impl<T> Default for LastIcqResult<T> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            icq_invalid: false,
+            fresh: false,
+        }
+    }
+}
+
+/// The last result of the ICQ `icq_id` recorded as `recorded` at the local height given by `recorded_height`, queried
+/// with `query` if the result failed to be handled. Stale if a newer result was submitted but failed to be handled,
+/// invalid if the ICQ is no longer registered as a KV query.
+fn last_icq_result<T>(
+    deps: Deps<NeutronQuery>,
+    icq_id: Option<u64>,
+    recorded: Option<T>,
+    recorded_height: impl FnOnce(&T) -> u64,
+    query: impl FnOnce(u64) -> Result<Option<T>, neutron_sdk::NeutronError>,
+) -> Result<LastIcqResult<T>, Error> {
+    let Some(icq_id) = icq_id else {
+        return Ok(LastIcqResult::default());
+    };
+
+    let icq_invalid = LastIcqResult {
+        icq_invalid: true,
+        ..LastIcqResult::default()
+    };
+
+    if state::icq_invalid(deps.storage, &icq_id).is_some() {
+        return Ok(icq_invalid);
+    }
+
+    let registered_query = match updated_registered_kv_query(deps, icq_id) {
+        Err(err) if is_query_type_mismatch(&err) => return Ok(icq_invalid),
+        res => res?,
+    };
+
+    let Some(registered_query) = registered_query else {
+        return Ok(LastIcqResult::default());
+    };
+
+    let Some(recorded) = recorded else {
+        let value = match query(icq_id) {
+            Err(err) if is_query_type_mismatch(&err) => return Ok(icq_invalid),
+            res => res?,
+        };
+
+        return Ok(LastIcqResult {
+            value,
+            icq_invalid: false,
+            fresh: true,
+        });
+    };
+
+    let fresh = recorded_height(&recorded) >= registered_query.last_submitted_result_local_height;
+
+    Ok(LastIcqResult {
+        value: Some(recorded),
+        icq_invalid: false,
+        fresh,
+    })
+}
+
+pub fn query_last_ica_delegation(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+) -> Result<IcaLastDelegationResponse, Error> {
+    ica_idx_in_bounds(deps, ica_idx)?;
+
+    debug!(deps, "querying delegation ICQ for ICA {ica_idx}");
+
+    let last = last_icq_result(
+        deps,
+        state::ica_delegations_icq_id(deps.storage, &ica_idx),
+        state::ica_last_delegation(deps.storage, &ica_idx),
+        |last| last.last_submitted_result_local_height,
+        |icq_id| helper::query_delegation_icq(deps, icq_id),
+    )?;
+
+    Ok(IcaLastDelegationResponse {
+        last_delegation: last.value,
+        icq_invalid: last.icq_invalid,
+        fresh: last.fresh,
+    })
+}
+
+pub fn query_last_ica_unbonding(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+) -> Result<IcaLastUnbondingResponse, Error> {
+    ica_idx_in_bounds(deps, ica_idx)?;
+
+    debug!(deps, "querying unbonding delegations ICQ for ICA {ica_idx}");
+
+    let last = last_icq_result(
+        deps,
+        state::ica_unbonding_icq_id(deps.storage, &ica_idx),
+        state::ica_last_unbonding(deps.storage, &ica_idx),
+        |last| last.last_submitted_result_local_height,
+        |icq_id| helper::query_unbonding_icq(deps, icq_id),
+    )?;
+
+    Ok(IcaLastUnbondingResponse {
+        last_unbonding: last.value,
+        icq_invalid: last.icq_invalid,
+        fresh: last.fresh,
+    })
+}
+
+pub fn query_ica_pending_rewards(
+    deps: Deps<NeutronQuery>,
+    ica_idx: u32,
+) -> Result<IcaPendingRewardsResponse, Error> {
+    ica_idx_in_bounds(deps, ica_idx)?;
+
+    debug!(deps, "querying rewards ICQ for ICA {ica_idx}");
+
+    let last = last_icq_result(
+        deps,
+        state::ica_rewards_icq_id(deps.storage, &ica_idx),
+        state::ica_pending_rewards(deps.storage, &ica_idx),
+        |last| last.last_submitted_result_local_height,
+        |icq_id| {
+            let ica_addr = state::ica_addr(deps.storage, &ica_idx)
+                .expect("a registered ica has an address set");
+
+            let validators = rewards_icq_validators(deps.storage);
+
+            let result = helper::query_rewards_icq(deps, icq_id, &ica_addr, &validators)?;
+
+            Ok(result.map(|result| result.pending_rewards))
+        },
+    )?;
+
+    Ok(IcaPendingRewardsResponse {
+        pending_rewards: last.value,
+        icq_invalid: last.icq_invalid,
+        fresh: last.fresh,
+    })
+}
+
+/// The indexes of the ICAs of a page of at most `limit` ICAs, starting after `start_after`
+fn ica_page(storage: &dyn Storage, start_after: Option<u32>, limit: Option<u32>) -> Range<u32> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    let start = start_after.map_or(0, |ica_idx| ica_idx.saturating_add(1));
+
+    let end = start
+        .saturating_add(limit)
+        .min(state::ica_set_size(storage));
+
+    start..end
+}
+
+pub fn query_all_last_balances(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<AllLastBalancesResponse, Error> {
+    let mut balances = vec![];
+
+    for ica_idx in ica_page(deps.storage, start_after, limit) {
+        let IcaLastBalanceResponse {
+            last_balance,
+            icq_invalid,
+            fresh,
+        } = query_last_ica_balance(deps, ica_idx)?;
+
+        balances.push(IcaLastBalanceEntry {
+            ica_idx,
+            last_balance,
+            icq_invalid,
+            fresh,
+        });
+    }
+
+    Ok(AllLastBalancesResponse { balances })
+}
+
+pub fn query_all_last_delegations(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<AllLastDelegationsResponse, Error> {
+    let mut delegations = vec![];
+
+    for ica_idx in ica_page(deps.storage, start_after, limit) {
+        let IcaLastDelegationResponse {
+            last_delegation,
+            icq_invalid,
+            fresh,
+        } = query_last_ica_delegation(deps, ica_idx)?;
+
+        delegations.push(IcaLastDelegationEntry {
+            ica_idx,
+            last_delegation,
+            icq_invalid,
+            fresh,
+        });
+    }
+
+    Ok(AllLastDelegationsResponse { delegations })
+}
+
+#[entry_point]
+pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
+    let res = match msg {
+        QueryMsg::IcaSetSize {} => {
+            let ica_set_size = state::ica_set_size(deps.storage);
+
+            to_binary(&IcaSetSizeResponse { ica_set_size })?
+        }
+
+        QueryMsg::IcaMetadata { ica_idx } => {
+            let ica_metadata = query_ica_metadata(deps, ica_idx)?;
+
+            to_binary(&ica_metadata)?
+        }
+
+        QueryMsg::IcaLastBalance { ica_idx } => {
+            let last_ica_balance = query_last_ica_balance(deps, ica_idx)?;
+
+            to_binary(&last_ica_balance)?
+        }
+
+        QueryMsg::IcaBalanceAt { ica_idx, height } => {
+            let ica_balance_at = query_ica_balance_at(deps, ica_idx, height)?;
+
+            to_binary(&ica_balance_at)?
+        }
+
+        QueryMsg::IcaLastDelegation { ica_idx } => {
+            let last_ica_delegation = query_last_ica_delegation(deps, ica_idx)?;
+
+            to_binary(&last_ica_delegation)?
+        }
+
+        QueryMsg::IcaLastUnbonding { ica_idx } => {
+            let last_ica_unbonding = query_last_ica_unbonding(deps, ica_idx)?;
+
+            to_binary(&last_ica_unbonding)?
+        }
+
+        QueryMsg::IcaPendingRewards { ica_idx } => {
+            let ica_pending_rewards = query_ica_pending_rewards(deps, ica_idx)?;
+
+            to_binary(&ica_pending_rewards)?
+        }
+
+        QueryMsg::IcaStakingTxStatus { ica_idx } => {
+            ica_idx_in_bounds(deps, ica_idx)?;
+
+            to_binary(&IcaStakingTxStatusResponse {
+                status: state::ica_staking_tx_status(deps.storage, &ica_idx),
+            })?
+        }
+
+        QueryMsg::AllLastBalances { start_after, limit } => {
+            let all_last_balances = query_all_last_balances(deps, start_after, limit)?;
+
+            to_binary(&all_last_balances)?
+        }
+
+        QueryMsg::AllLastDelegations { start_after, limit } => {
+            let all_last_delegations = query_all_last_delegations(deps, start_after, limit)?;
+
+            to_binary(&all_last_delegations)?
+        }
+
+        QueryMsg::RegistrationStatus {} => {
+            let icas = (0..state::ica_set_size(deps.storage))
+                .map(|ica_idx| IcaRegistrationStatus {
+                    ica_idx,
+                    status: registration_status(deps.storage, ica_idx),
+                })
+                .collect();
+
+            to_binary(&RegistrationStatusResponse { icas })?
+        }
+
+        QueryMsg::IcqRefundAddress {} => to_binary(&IcqRefundAddressResponse {
+            address: state::icq_refund_address(deps.storage),
+        })?,
+
+        QueryMsg::ErrorCodes {} => to_binary(&ErrorCodesResponse {
+            codes: Error::catalogue(),
+        })?,
+    };
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::{
+        testing::{
+            mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+            MOCK_CONTRACT_ADDR,
+        },
+        ContractInfoResponse, ContractResult, CosmosMsg, Empty, OwnedDeps, QuerierWrapper,
+        SubMsgResponse, SystemError, SystemResult, WasmQuery,
+    };
+
+    use super::*;
+    use crate::msgs::IcaStakingTxStatus;
+
+    const ADMIN: &str = "admin";
+
+    const V0_VALIDATOR: &str = "cosmosvaloper1qgpqyqszqgpqyqszqgpqyqszqgpqyqszxrnw2e";
+
+    const V0_ICA_ADDR: &str = "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du";
+
+    /// Mock deps for the handlers taking neutron's custom queries, the contract's admin being `ADMIN`
+    fn mock_neutron_dependencies(
+    ) -> OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery> {
+        mock_neutron_dependencies_with(MockQuerier::<NeutronQuery>::new(&[]))
+    }
+
+    /// Mock deps answering neutron's custom queries with `querier`, the contract's admin being `ADMIN`
+    fn mock_neutron_dependencies_with(
+        querier: MockQuerier<NeutronQuery>,
+    ) -> OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery> {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: PhantomData,
+        };
+
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { .. } => {
+                let mut info = ContractInfoResponse::new(1, "creator");
+
+                info.admin = Some(ADMIN.to_owned());
+
+                SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+            }
+
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "not a contract info query".to_owned(),
+            }),
+        });
+
+        deps
+    }
+
+    /// Handle the successful reply `id` to the submission of the ICA tx `seq_num` on `channel-3`, the reply taking no
+    /// custom queries
+    fn reply_tx_issued(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery>,
+        id: u64,
+        seq_num: u64,
+    ) -> Response {
+        let querier = MockQuerier::<Empty>::new(&[]);
+
+        let deps = DepsMut {
+            storage: &mut deps.storage,
+            api: &deps.api,
+            querier: QuerierWrapper::new(&querier),
+        };
+
+        let data = format!(r#"{{"sequence_id":{seq_num},"channel":"channel-3"}}"#);
+
+        let reply_msg = Reply {
+            id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(data.into_bytes().into()),
+            }),
+        };
+
+        reply(deps, mock_env(), reply_msg).unwrap()
+    }
+
+    #[test]
+    fn rebuild_restores_missing_icq_lookups() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_balance_icq_id(storage, &1, 4);
+        state::set_ica_delegations_icq_id(storage, &1, 5);
+        state::set_icq_ica_idx(storage, &4, 1);
+        state::set_icq_kind(storage, &4, BALANCE_ICQ_KIND);
+
+        let pass = rebuild_indexes(storage, 0, 2, 10, rebuild_ica_indexes);
+
+        assert_eq!(pass.visited, 2);
+        assert_eq!(pass.restored, 2);
+        assert_eq!(pass.next_ica_idx, None);
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::icq_ica_idx(storage, &5), Some(1));
+        assert_eq!(state::icq_kind(storage, &5), Some(DELEGATIONS_ICQ_KIND));
+    }
+
+    #[test]
+    fn balance_at_returns_closest_snapshot_at_or_before_height() {
+        let mut deps = mock_dependencies();
+
+        let snapshot = |local_height: u64| BalanceSnapshot {
+            balances: vec![Coin::new(local_height.into(), "untrn")],
+            local_height,
+        };
+
+        for height in 1..=u64::try_from(BALANCE_HISTORY_LIMIT).unwrap() + 2 {
+            record_balance_snapshot(deps.as_mut().storage, 0, snapshot(height * 10));
+        }
+
+        // a result resubmitted at the same height replaces the previous snapshot
+        record_balance_snapshot(deps.as_mut().storage, 0, snapshot(340));
+
+        let history = state::ica_balance_history(deps.as_ref().storage, &0).unwrap();
+
+        assert_eq!(history.len(), BALANCE_HISTORY_LIMIT);
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(balance_at(storage, 0, 25), None, "evicted from the history");
+        assert_eq!(balance_at(storage, 0, 30), Some(snapshot(30)));
+        assert_eq!(balance_at(storage, 0, 109), Some(snapshot(100)));
+        assert_eq!(balance_at(storage, 0, u64::MAX), Some(snapshot(340)));
+        assert_eq!(balance_at(storage, 1, u64::MAX), None);
+    }
+
+    #[test]
+    fn balance_icq_denoms_primary_denom_first() {
+        let mut deps = mock_dependencies();
+
+        state::set_balance_icq_denom(deps.as_mut().storage, "uatom");
+
+        assert_eq!(balance_icq_denoms(deps.as_ref().storage), ["uatom"]);
+
+        state::set_additional_balance_icq_denoms(
+            deps.as_mut().storage,
+            &vec!["uosmo".to_owned(), "stuatom".to_owned()],
+        );
+
+        assert_eq!(
+            balance_icq_denoms(deps.as_ref().storage),
+            ["uatom", "uosmo", "stuatom"]
+        );
+
+        let denoms = ["uosmo", "uatom", "stuatom", "uosmo"]
+            .map(str::to_owned)
+            .to_vec();
+
+        assert_eq!(
+            dedup_balance_icq_denoms("uatom", denoms),
+            ["uosmo", "stuatom"]
+        );
+    }
+
+    /// Seed the state of a version 0 instance of 3 ICAs, the first 2 having opened their channel & registered their
+    /// balance & delegations ICQs
+    fn seed_v0_state(storage: &mut dyn Storage) {
+        state::set_connection_id(storage, "connection-0");
+        state::set_balance_icq_denom(storage, "uatom");
+        state::set_ica_set_size(storage, 3);
+        state::set_icq_update_period(storage, 10);
+        state::v0::set_delegations_icq_validator(storage, V0_VALIDATOR);
+
+        for ica_idx in 0..2 {
+            let balance_icq_id = u64::from(ica_idx) * 2;
+            let delegations_icq_id = balance_icq_id + 1;
+
+            state::set_ica_addr(storage, &ica_idx, V0_ICA_ADDR);
+            state::set_ica_balance_icq_id(storage, &ica_idx, balance_icq_id);
+            state::set_ica_delegations_icq_id(storage, &ica_idx, delegations_icq_id);
+            state::set_icq_ica_idx(storage, &balance_icq_id, ica_idx);
+            state::set_icq_ica_idx(storage, &delegations_icq_id, ica_idx);
+            state::set_icq_kind(storage, &balance_icq_id, BALANCE_ICQ_KIND);
+            state::set_icq_kind(storage, &delegations_icq_id, DELEGATIONS_ICQ_KIND);
+        }
+    }
+
+    #[test]
+    fn v0_icas_activated() {
+        let mut deps = mock_dependencies();
+
+        seed_v0_state(deps.as_mut().storage);
+
+        let mut env = mock_env();
+
+        env.block.height = 100;
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        let storage = deps.as_mut().storage;
+
+        assert_eq!(state::contract_version(storage), Some(CONTRACT_VERSION));
+        assert_eq!(state::ica_status(storage, &0), Some(IcaStatus::Active));
+        assert_eq!(state::ica_status(storage, &1), Some(IcaStatus::Active));
+        assert_eq!(state::ica_status(storage, &2), Some(IcaStatus::Registering));
+        assert_eq!(state::ica_status_height(storage, &0), Some(100));
+        assert_eq!(
+            state::delegations_icq_validators(storage).unwrap(),
+            [V0_VALIDATOR]
+        );
+        assert_eq!(state::v0::delegations_icq_validator(storage), None);
+
+        // the ICAs with an open channel have their unbonding delegations & rewards ICQs registered by `RegisterMissing`
+        assert!(matches!(
+            registration_status(storage, 0),
+            RegistrationStatus::Failed { .. }
+        ));
+        assert_eq!(registration_status(storage, 2), RegistrationStatus::Pending);
+
+        let msgs = register_missing_icqs(storage, 0, V0_ICA_ADDR).unwrap();
+
+        assert_eq!(msgs.len(), 2);
+
+        for (msg, icq_kind) in msgs.iter().zip([UNBONDING_ICQ_KIND, REWARDS_ICQ_KIND]) {
+            assert_eq!(
+                common::take_reply_payload(storage, msg.id),
+                Some(state::PendingReply {
+                    kind: state::ReplyKind::RegisterIcq(icq_kind),
+                    ica_idx: 0,
+                })
+            );
+        }
+
+        // the ICA still registering is re-registered once its registration timed out
+        assert!(missing_icas(storage, 100 + ICA_REGISTRATION_TIMEOUT_BLOCKS).is_empty());
+        assert_eq!(
+            missing_icas(storage, 101 + ICA_REGISTRATION_TIMEOUT_BLOCKS),
+            [2]
+        );
+
+        // migrating again leaves the state as is
+        env.block.height = 200;
+
+        migrate(deps.as_mut(), env, MigrateMsg {}).unwrap();
+
+        assert_eq!(
+            state::ica_status_height(deps.as_ref().storage, &0),
+            Some(100)
+        );
+
+        state::set_contract_version(deps.as_mut().storage, CONTRACT_VERSION + 1);
+
+        assert!(matches!(
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}),
+            Err(Error::InvalidMigration { .. })
+        ));
+    }
+
+    #[test]
+    fn delegations_icq_validator_count_bounded() {
+        let validator = "cosmosvaloper1qqqqqqqq".to_owned();
+
+        assert!(matches!(
+            ensure_delegations_icq_validator_count(&[]),
+            Err(Error::InvalidDelegationsIcqValidatorCount)
+        ));
+
+        assert!(ensure_delegations_icq_validator_count(&vec![
+            validator.clone();
+            MAX_DELEGATIONS_ICQ_VALIDATORS
+        ])
+        .is_ok());
+
+        assert!(matches!(
+            ensure_delegations_icq_validator_count(&vec![
+                validator;
+                MAX_DELEGATIONS_ICQ_VALIDATORS + 1
+            ]),
+            Err(Error::InvalidDelegationsIcqValidatorCount)
+        ));
+    }
+
+    #[test]
+    fn ica_pages_bounded_by_set_size() {
+        let mut deps = mock_dependencies();
+
+        state::set_ica_set_size(deps.as_mut().storage, 45);
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(ica_page(storage, None, None), 0..DEFAULT_PAGE_LIMIT);
+        assert_eq!(ica_page(storage, Some(9), Some(100)), 10..40);
+        assert_eq!(ica_page(storage, Some(39), None), 40..45);
+        assert!(ica_page(storage, Some(44), None).is_empty());
+        assert!(ica_page(storage, Some(u32::MAX), None).is_empty());
+    }
+
+    #[test]
+    fn missing_icas_registered_again() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, "connection-0");
+        state::set_ica_set_size(storage, 4);
+
+        register_icas(storage, 0..4, 10);
+
+        state::set_ica_addr(storage, &1, "cosmos1ica");
+        set_ica_status(storage, 1, IcaStatus::Active, 10);
+
+        // registrations awaiting their open ack are left alone until they time out
+        assert_eq!(
+            missing_icas(storage, 10 + ICA_REGISTRATION_TIMEOUT_BLOCKS),
+            Vec::<u32>::new()
+        );
+
+        record_registration_failure(
+            storage,
+            state::ReplyKind::RegisterIca,
+            0,
+            "connection closed",
+        );
+
+        assert_eq!(missing_icas(storage, 11), [0]);
+
+        set_ica_status(storage, 3, IcaStatus::Registering, 11);
+
+        assert_eq!(
+            missing_icas(storage, 11 + ICA_REGISTRATION_TIMEOUT_BLOCKS),
+            [0, 2]
+        );
+
+        let msgs = register_icas(
+            storage,
+            missing_icas(storage, 11 + ICA_REGISTRATION_TIMEOUT_BLOCKS),
+            42,
+        );
+
+        assert_eq!(
+            msgs,
+            [
+                SubMsg::reply_on_error(
+                    NeutronMsg::RegisterInterchainAccount {
+                        connection_id: "connection-0".to_owned(),
+                        interchain_account_id: "0".to_owned(),
+                    },
+                    msgs[0].id,
+                ),
+                SubMsg::reply_on_error(
+                    NeutronMsg::RegisterInterchainAccount {
+                        connection_id: "connection-0".to_owned(),
+                        interchain_account_id: "2".to_owned(),
+                    },
+                    msgs[1].id,
+                ),
+            ]
+        );
+
+        // each reply is dispatched from the context recorded for its ICA
+        for (msg, ica_idx) in msgs.iter().zip([0, 2]) {
+            assert_eq!(
+                common::take_reply_payload(storage, msg.id),
+                Some(state::PendingReply {
+                    kind: state::ReplyKind::RegisterIca,
+                    ica_idx,
+                })
+            );
+        }
+
+        assert_eq!(state::ica_status(storage, &2), Some(IcaStatus::Registering));
+        assert_eq!(state::ica_status_height(storage, &2), Some(42));
+        assert_eq!(state::ica_status_height(storage, &3), Some(11));
+    }
+
+    #[test]
+    fn registration_status_follows_progress() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, "connection-0");
+
+        assert_eq!(registration_status(storage, 0), RegistrationStatus::Pending);
+
+        state::set_ica_channel(
+            storage,
+            &0,
+            &IcaChannel {
+                port_id: "icacontroller-contract.0".to_owned(),
+                channel_id: "channel-1".to_owned(),
+                counterparty_channel_id: "channel-2".to_owned(),
+            },
+        );
+
+        assert_eq!(
+            registration_status(storage, 0),
+            RegistrationStatus::ChannelOpen
+        );
+
+        state::set_ica_balance_icq_id(storage, &0, 4);
+
+        assert_eq!(
+            registration_status(storage, 0),
+            RegistrationStatus::ChannelOpen
+        );
+
+        state::set_ica_delegations_icq_id(storage, &0, 5);
+
+        assert_eq!(
+            registration_status(storage, 0),
+            RegistrationStatus::ChannelOpen
+        );
+
+        state::set_ica_unbonding_icq_id(storage, &0, 6);
+
+        assert_eq!(
+            registration_status(storage, 0),
+            RegistrationStatus::ChannelOpen
+        );
+
+        state::set_ica_rewards_icq_id(storage, &0, 7);
+
+        assert_eq!(
+            registration_status(storage, 0),
+            RegistrationStatus::IcqRegistered
+        );
+
+        record_registration_failure(
+            storage,
+            state::ReplyKind::RegisterIca,
+            1,
+            "connection closed",
+        );
+
+        assert_eq!(
+            registration_status(storage, 1),
+            RegistrationStatus::Failed {
+                reason: "ica registration failed: connection closed".to_owned()
+            }
+        );
+
+        // registering the ICA again clears the failure
+        register_icas(storage, [1], 42);
+
+        assert_eq!(registration_status(storage, 1), RegistrationStatus::Pending);
+    }
+
+    #[test]
+    fn failed_icq_registrations_reissued() {
+        const ICA_ADDR: &str = "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du";
+        const VALIDATOR: &str = "cosmosvaloper1qgpqyqszqgpqyqszqgpqyqszqgpqyqszxrnw2e";
+
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_connection_id(storage, "connection-0");
+        state::set_icq_update_period(storage, 6);
+        state::set_delegations_icq_validators(storage, &vec![VALIDATOR.to_owned()]);
+        state::set_ica_addr(storage, &0, ICA_ADDR);
+        state::set_ica_balance_icq_id(storage, &0, 4);
+        state::set_ica_delegations_icq_id(storage, &0, 5);
+        state::set_ica_unbonding_icq_id(storage, &0, 6);
+
+        let msgs = register_missing_icqs(storage, 0, ICA_ADDR).unwrap();
+
+        assert_eq!(msgs.len(), 1);
+
+        let failed_reply_id = msgs[0].id;
+
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: failed_reply_id,
+                result: SubMsgResult::Err("codespace: interchainqueries, code: 1".to_owned()),
+            },
+        )
+        .unwrap();
+
+        let storage = deps.as_mut().storage;
+
+        assert!(matches!(
+            registration_status(storage, 0),
+            RegistrationStatus::Failed { .. }
+        ));
+
+        // only the ICQ whose registration failed is issued again
+        let msgs = register_missing_icqs(storage, 0, ICA_ADDR).unwrap();
+
+        assert_eq!(msgs.len(), 1);
+        assert_ne!(msgs[0].id, failed_reply_id);
+
+        let reply_id = msgs[0].id;
+
+        // the context of the failed registration was taken with its reply
+        assert!(matches!(
+            reply(
+                deps.as_mut(),
+                mock_env(),
+                Reply {
+                    id: failed_reply_id,
+                    result: SubMsgResult::Err("codespace: interchainqueries, code: 1".to_owned()),
+                },
+            ),
+            Err(Error::UnexpectedReply { id }) if id == failed_reply_id
+        ));
+
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: reply_id,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: Some(Binary::from(br#"{"id":7}"#.as_slice())),
+                }),
+            },
+        )
+        .unwrap();
+
+        let storage = deps.as_mut().storage;
+
+        assert_eq!(state::ica_rewards_icq_id(storage, &0), Some(7));
+        assert_eq!(
+            registration_status(storage, 0),
+            RegistrationStatus::IcqRegistered
+        );
+    }
+
+    #[test]
+    fn removed_icqs_cleared_with_their_lookups() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_balance_icq_id(storage, &0, 4);
+        state::set_ica_delegations_icq_id(storage, &0, 5);
+        state::set_ica_unbonding_icq_id(storage, &0, 8);
+        state::set_ica_rewards_icq_id(storage, &0, 9);
+        state::set_ica_balance_icq_id(storage, &1, 6);
+
+        for (icq_id, kind) in [
+            (4, BALANCE_ICQ_KIND),
+            (5, DELEGATIONS_ICQ_KIND),
+            (8, UNBONDING_ICQ_KIND),
+            (9, REWARDS_ICQ_KIND),
+        ] {
+            state::set_icq_ica_idx(storage, &icq_id, 0);
+            state::set_icq_kind(storage, &icq_id, kind);
+        }
+
+        state::set_icq_invalid(storage, &5, &true);
+
+        state::set_ica_last_delegation(
+            storage,
+            &0,
+            &msgs::IcaLastDelegation {
+                delegations: vec![],
+                last_submitted_result_local_height: 7,
+            },
+        );
+
+        state::set_ica_last_unbonding(
+            storage,
+            &0,
+            &msgs::IcaLastUnbonding {
+                unbondings: vec![],
+                last_submitted_result_local_height: 7,
+            },
+        );
+
+        state::set_ica_pending_rewards(
+            storage,
+            &0,
+            &msgs::IcaPendingRewards {
+                rewards: vec![],
+                last_submitted_result_local_height: 7,
+            },
+        );
+
+        assert_eq!(clear_ica_icqs(storage, 0), [4, 5, 8, 9]);
+        assert_eq!(clear_ica_icqs(storage, 0), Vec::<u64>::new());
+
+        assert_eq!(state::ica_balance_icq_id(storage, &0), None);
+        assert_eq!(state::icq_ica_idx(storage, &4), None);
+        assert_eq!(state::icq_kind(storage, &5), None);
+        assert_eq!(state::icq_invalid(storage, &5), None);
+        assert_eq!(state::ica_last_delegation(storage, &0), None);
+        assert_eq!(state::ica_unbonding_icq_id(storage, &0), None);
+        assert_eq!(state::icq_kind(storage, &8), None);
+        assert_eq!(state::ica_last_unbonding(storage, &0), None);
+        assert_eq!(state::ica_rewards_icq_id(storage, &0), None);
+        assert_eq!(state::ica_pending_rewards(storage, &0), None);
+        assert_eq!(state::ica_balance_icq_id(storage, &1), Some(6));
+    }
+
+    #[test]
+    fn icq_update_period_set_in_batches() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_set_size(storage, ICQ_UPDATE_PERIOD_BATCH_ICAS + 2);
+
+        // the first ICA only has its balance ICQ registered, the second none
+        state::set_ica_balance_icq_id(storage, &0, 4);
+        state::set_ica_balance_icq_id(storage, &2, 6);
+        state::set_ica_delegations_icq_id(storage, &2, 7);
+        state::set_ica_unbonding_icq_id(storage, &2, 8);
+        state::set_ica_rewards_icq_id(storage, &2, 9);
+        state::set_ica_balance_icq_id(storage, &ICQ_UPDATE_PERIOD_BATCH_ICAS, 10);
+
+        let update = |query_id| NeutronMsg::UpdateInterchainQuery {
+            query_id,
+            new_keys: None,
+            new_update_period: Some(12),
+            new_transactions_filter: None,
+        };
+
+        let (msgs, next_ica_idx) = icq_update_period_batch(storage, 0, 12);
+
+        assert_eq!(msgs, [4, 6, 7, 8, 9].map(update));
+        assert_eq!(next_ica_idx, Some(ICQ_UPDATE_PERIOD_BATCH_ICAS));
+
+        let (msgs, next_ica_idx) =
+            icq_update_period_batch(storage, ICQ_UPDATE_PERIOD_BATCH_ICAS, 12);
+
+        assert_eq!(msgs, [update(10)]);
+        assert_eq!(next_ica_idx, None);
+
+        let (msgs, next_ica_idx) =
+            icq_update_period_batch(storage, ICQ_UPDATE_PERIOD_BATCH_ICAS + 2, 12);
+
+        assert!(msgs.is_empty());
+        assert_eq!(next_ica_idx, None);
+    }
+
+    #[test]
+    fn staking_tx_outcomes_recorded_per_ica() {
+        let mut deps = mock_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        let pending = |amount| state::PendingStakingTx {
+            validator: "cosmosvaloper1".to_owned(),
+            amount: Coin::new(amount, "uatom"),
+        };
+
+        state::set_ica_pending_staking_tx(storage, &2, &pending(10));
+
+        record_staking_tx_issued(
+            storage,
+            StakingTxKind::Delegate,
+            2,
+            1,
+            "channel-3".to_owned(),
+            42,
+        );
+
+        state::set_ica_pending_staking_tx(storage, &2, &pending(4));
+
+        record_staking_tx_issued(
+            storage,
+            StakingTxKind::Undelegate,
+            2,
+            2,
+            "channel-3".to_owned(),
+            43,
+        );
+
+        assert_eq!(state::ica_pending_staking_tx(storage, &2), None);
+
+        let failed = StakingTxOutcome::Failed {
+            error: "insufficient delegation".to_owned(),
+        };
+
+        assert_eq!(
+            record_staking_tx_outcome(storage, 1, "channel-3", &StakingTxOutcome::Succeeded),
+            Some(2)
+        );
+        assert_eq!(
+            record_staking_tx_outcome(storage, 2, "channel-3", &failed),
+            Some(2)
+        );
+
+        // each tx is only acknowledged once, unknown txs are not attributed to any ICA
+        assert_eq!(
+            record_staking_tx_outcome(storage, 1, "channel-3", &StakingTxOutcome::Succeeded),
+            None
+        );
+        assert_eq!(
+            record_staking_tx_outcome(storage, 1, "channel-4", &StakingTxOutcome::TimedOut),
+            None
+        );
+
+        let status = state::ica_staking_tx_status(storage, &2).unwrap();
+
+        assert_eq!(
+            status,
+            IcaStakingTxStatus {
+                success_count: 1,
+                error_count: 1,
+                timeout_count: 0,
+                last_delegate: Some(StakingTx {
+                    kind: StakingTxKind::Delegate,
+                    validator: "cosmosvaloper1".to_owned(),
+                    amount: Coin::new(10, "uatom"),
+                    seq_num: 1,
+                    channel: "channel-3".to_owned(),
+                    issued_height: 42,
+                    outcome: StakingTxOutcome::Succeeded,
+                }),
+                last_undelegate: Some(StakingTx {
+                    kind: StakingTxKind::Undelegate,
+                    validator: "cosmosvaloper1".to_owned(),
+                    amount: Coin::new(4, "uatom"),
+                    seq_num: 2,
+                    channel: "channel-3".to_owned(),
+                    issued_height: 43,
+                    outcome: failed,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn icas_act_for_their_owner_only() {
+        let mut deps = mock_neutron_dependencies();
+
+        state::set_ica_set_size(deps.as_mut().storage, 2);
+
+        let env = mock_env();
+
+        let exec = |deps: &mut OwnedDeps<_, _, _, NeutronQuery>, sender: &str, msg| {
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+        };
+
+        let claim = ExecuteMsg::ClaimIca { ica_idx: 1 };
+
+        assert!(matches!(
+            exec(&mut deps, "claimant", claim.clone()),
+            Err(Error::IcaClaimNotApproved { ica_idx: 1 })
+        ));
+
+        let approve = ExecuteMsg::ApproveIcaClaim {
+            ica_idx: 1,
+            claimant: Some("claimant".to_owned()),
+        };
+
+        assert!(matches!(
+            exec(&mut deps, "claimant", approve.clone()),
+            Err(Error::Unauthorized)
+        ));
+
+        exec(&mut deps, ADMIN, approve.clone()).unwrap();
+
+        // only the approved claimant can claim
+        assert!(matches!(
+            exec(&mut deps, "someone", claim.clone()),
+            Err(Error::IcaClaimNotApproved { ica_idx: 1 })
+        ));
+
+        exec(&mut deps, "claimant", claim.clone()).unwrap();
+
+        assert_eq!(
+            state::ica_owner(deps.as_ref().storage, &1).as_deref(),
+            Some("claimant")
+        );
+        assert_eq!(
+            state::ica_approved_claimant(deps.as_ref().storage, &1),
+            None
+        );
+
+        assert!(matches!(
+            exec(&mut deps, "claimant", claim.clone()),
+            Err(Error::IcaAlreadyOwned { ica_idx: 1, owner }) if owner == "claimant"
+        ));
+
+        assert!(matches!(
+            exec(&mut deps, ADMIN, approve),
+            Err(Error::IcaAlreadyOwned { ica_idx: 1, .. })
+        ));
+
+        // the admin no longer acts through a claimed ICA
+        let delegate = ExecuteMsg::Delegate {
+            ica_idx: 1,
+            validator: "cosmosvaloper1qqqqqqqq".to_owned(),
+            amount: Uint128::new(10),
+        };
+
+        assert!(matches!(
+            exec(&mut deps, ADMIN, delegate.clone()),
+            Err(Error::NotIcaOwner { ica_idx: 1 })
+        ));
+
+        assert!(ensure_ica_operator(deps.as_ref(), &env, &mock_info("claimant", &[]), 1).is_ok());
+
+        assert!(ensure_ica_operator(deps.as_ref(), &env, &mock_info(ADMIN, &[]), 0).is_ok());
+
+        // until the admin revokes or replaces the owner
+        assert!(matches!(
+            exec(
+                &mut deps,
+                "claimant",
+                ExecuteMsg::RevokeIcaOwner { ica_idx: 1 }
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        exec(&mut deps, ADMIN, ExecuteMsg::RevokeIcaOwner { ica_idx: 1 }).unwrap();
+
+        assert_eq!(state::ica_owner(deps.as_ref().storage, &1), None);
+
+        assert!(ensure_ica_operator(deps.as_ref(), &env, &mock_info(ADMIN, &[]), 1).is_ok());
+
+        let set_owner = ExecuteMsg::SetIcaOwner {
+            ica_idx: 1,
+            owner: "other".to_owned(),
+        };
+
+        exec(&mut deps, ADMIN, set_owner).unwrap();
+
+        assert!(matches!(
+            exec(&mut deps, "claimant", delegate),
+            Err(Error::NotIcaOwner { ica_idx: 1 })
+        ));
+    }
+
+    #[test]
+    fn removed_icq_deposits_forwarded_to_the_refund_address() {
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(|query| {
+            let NeutronQuery::RegisteredInterchainQuery { query_id } = query else {
+                unimplemented!("unexpected query: {query:?}");
+            };
+
+            let res = format!(
+                r#"{{"registered_query":{{"id":{query_id},"owner":"contract","query_type":"kv","keys":[],"transactions_filter":"","connection_id":"connection-0","update_period":6,"deposit":[{{"denom":"untrn","amount":"1000000"}}]}}}}"#
+            );
+
+            SystemResult::Ok(ContractResult::Ok(res.into_bytes().into()))
+        });
+
+        let mut deps = mock_neutron_dependencies_with(querier);
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_set_size(storage, 2);
+
+        for (ica_idx, icq_id) in [(0, 4), (1, 5)] {
+            state::set_ica_balance_icq_id(storage, &ica_idx, icq_id);
+            state::set_icq_ica_idx(storage, &icq_id, ica_idx);
+            state::set_icq_kind(storage, &icq_id, BALANCE_ICQ_KIND);
+        }
+
+        state::set_ica_delegations_icq_id(storage, &0, 6);
+        state::set_icq_ica_idx(storage, &6, 0);
+        state::set_icq_kind(storage, &6, DELEGATIONS_ICQ_KIND);
+
+        let remove_icqs = |deps: &mut OwnedDeps<_, _, _, NeutronQuery>, sender: &str, ica_idx| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::RemoveIcqs { ica_idx },
+            )
+        };
+
+        assert!(matches!(
+            remove_icqs(&mut deps, "someone", 0),
+            Err(Error::Unauthorized)
+        ));
+
+        // without a refund address the deposits are forwarded to the admin removing the ICQs
+        let res = remove_icqs(&mut deps, ADMIN, 0).unwrap();
+
+        assert_eq!(
+            res.messages,
+            [
+                SubMsg::new(NeutronMsg::RemoveInterchainQuery { query_id: 4 }),
+                SubMsg::new(NeutronMsg::RemoveInterchainQuery { query_id: 6 }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: ADMIN.to_owned(),
+                    amount: vec![Coin::new(2_000_000, "untrn")],
+                }),
+            ]
+        );
+
+        assert!(matches!(
+            remove_icqs(&mut deps, ADMIN, 0),
+            Err(Error::NoIcqsRegistered { ica_idx: 0 })
+        ));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::UpdateIcqRefundAddress {
+                address: Some("refund".to_owned()),
+            },
+        )
+        .unwrap();
+
+        let res = remove_icqs(&mut deps, ADMIN, 1).unwrap();
+
+        assert_eq!(
+            res.messages,
+            [
+                SubMsg::new(NeutronMsg::RemoveInterchainQuery { query_id: 5 }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "refund".to_owned(),
+                    amount: vec![Coin::new(1_000_000, "untrn")],
+                }),
+            ]
+        );
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::icq_ica_idx(storage, &5), None);
+        assert_eq!(state::ica_balance_icq_id(storage, &1), None);
+    }
+
+    #[test]
+    fn delegations_icq_validators_deduped_on_update() {
+        const ICA_ADDR: &str = "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du";
+        const VALIDATOR_1: &str = "cosmosvaloper1qgpqyqszqgpqyqszqgpqyqszqgpqyqszxrnw2e";
+        const VALIDATOR_2: &str = "cosmosvaloper1qvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcr8nj0qc";
+
+        let mut deps = mock_neutron_dependencies();
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_set_size(storage, 1);
+        state::set_connection_id(storage, "connection-0");
+        state::set_icq_update_period(storage, 6);
+        state::set_delegations_icq_validators(storage, &vec![VALIDATOR_1.to_owned()]);
+        state::set_ica_addr(storage, &0, ICA_ADDR);
+        state::set_ica_delegations_icq_id(storage, &0, 5);
+
+        let validators = [VALIDATOR_2, VALIDATOR_1, VALIDATOR_2]
+            .map(str::to_owned)
+            .to_vec();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::UpdateDelegationsIcqValidators { validators },
+        )
+        .unwrap();
+
+        assert_eq!(
+            delegations_icq_validators(deps.as_ref().storage),
+            [VALIDATOR_2, VALIDATOR_1]
+        );
+
+        assert_eq!(
+            res.events,
+            [Event::new("delegations_icq_validators_updated")
+                .add_attribute("validators", format!("{VALIDATOR_2},{VALIDATOR_1}"))]
+        );
+
+        // the registered delegations ICQ queries each validator once
+        let [SubMsg {
+            msg:
+                CosmosMsg::Custom(NeutronMsg::UpdateInterchainQuery {
+                    query_id: 5,
+                    new_keys: Some(keys),
+                    ..
+                }),
+            ..
+        }] = res.messages.as_slice()
+        else {
+            panic!(
+                "expected the delegations ICQ update, got {:?}",
+                res.messages
+            );
+        };
+
+        let NeutronMsg::RegisterInterchainQuery {
+            keys: expected_keys,
+            ..
+        } = new_register_delegator_delegations_query_msg(
+            "connection-0".to_owned(),
+            ICA_ADDR.to_owned(),
+            vec![VALIDATOR_2.to_owned(), VALIDATOR_1.to_owned()],
+            6,
+        )
+        .unwrap()
+        else {
+            unreachable!();
+        };
+
+        assert_eq!(keys, &expected_keys);
+    }
+
+    #[test]
+    fn staking_txs_executed_issued_then_acknowledged() {
+        const ICA_ADDR: &str = "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du";
+        const VALIDATOR: &str = "cosmosvaloper1qgpqyqszqgpqyqszqgpqyqszqgpqyqszxrnw2e";
+
+        let min_fee = r#"{"min_fee":{"recv_fee":[],"ack_fee":[{"denom":"untrn","amount":"1000"}],"timeout_fee":[{"denom":"untrn","amount":"1000"}]}}"#;
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let NeutronQuery::MinIbcFee {} = query else {
+                unimplemented!("unexpected query: {query:?}");
+            };
+
+            SystemResult::Ok(ContractResult::Ok(min_fee.as_bytes().into()))
+        });
+
+        let mut deps = mock_neutron_dependencies_with(querier);
+
+        let storage = deps.as_mut().storage;
+
+        state::set_ica_set_size(storage, 1);
+        state::set_connection_id(storage, "connection-0");
+        state::set_balance_icq_denom(storage, "uatom");
+        state::set_ica_addr(storage, &0, ICA_ADDR);
+        set_ica_status(storage, 0, IcaStatus::Active, 1);
+
+        let exec = |deps: &mut OwnedDeps<_, _, _, NeutronQuery>, funds: u128, msg| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(ADMIN, &[Coin::new(funds, "untrn")]),
+                msg,
+            )
+        };
+
+        let packet = |seq_num| RequestPacket {
+            sequence: Some(seq_num),
+            source_port: Some("icacontroller-contract.0".to_owned()),
+            source_channel: Some("channel-3".to_owned()),
+            destination_port: None,
+            destination_channel: None,
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        };
+
+        let delegate = ExecuteMsg::Delegate {
+            ica_idx: 0,
+            validator: VALIDATOR.to_owned(),
+            amount: Uint128::new(10),
+        };
+
+        assert!(matches!(
+            exec(&mut deps, 1500, delegate.clone()),
+            Err(Error::InsufficientIbcFee { shortfall }) if shortfall == "500untrn"
+        ));
+
+        // the funds in excess of the fee are refunded
+        let res = exec(&mut deps, 2500, delegate.clone()).unwrap();
+
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(BankMsg::Send {
+                to_address: ADMIN.to_owned(),
+                amount: vec![Coin::new(500, "untrn")],
+            })
+        );
+
+        reply_tx_issued(&mut deps, res.messages[0].id, 1);
+
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::Response {
+                request: packet(1),
+                data: Binary::default(),
+            },
+        )
+        .unwrap();
+
+        let status = state::ica_staking_tx_status(deps.as_ref().storage, &0).unwrap();
+
+        assert_eq!(status.success_count, 1);
+        assert_eq!(
+            status.last_delegate.map(|tx| tx.outcome),
+            Some(StakingTxOutcome::Succeeded)
+        );
+
+        let undelegate = ExecuteMsg::Undelegate {
+            ica_idx: 0,
+            validator: VALIDATOR.to_owned(),
+            amount: Uint128::new(4),
+        };
+
+        let res = exec(&mut deps, 2000, undelegate).unwrap();
+
+        assert_eq!(res.messages.len(), 1, "no refund of exact funds");
+
+        reply_tx_issued(&mut deps, res.messages[0].id, 2);
+
+        // a timed out tx closes the ordered channel of the ICA
+        let res = sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::Timeout { request: packet(2) },
+        )
+        .unwrap();
+
+        assert_eq!(res.events[0].ty, "staking_tx_timed_out");
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(
+            state::ica_status(storage, &0),
+            Some(IcaStatus::ChannelClosed)
+        );
+
+        let status = state::ica_staking_tx_status(storage, &0).unwrap();
+
+        assert_eq!(status.timeout_count, 1);
+        assert_eq!(
+            status.last_undelegate.map(|tx| tx.outcome),
+            Some(StakingTxOutcome::TimedOut)
+        );
+
+        assert!(matches!(
+            exec(&mut deps, 2000, delegate.clone()),
+            Err(Error::IcaNotActive { ica_idx: 0 })
+        ));
+
+        let storage = deps.as_mut().storage;
+
+        for (kind, icq_id) in [
+            (BALANCE_ICQ_KIND, 1),
+            (DELEGATIONS_ICQ_KIND, 2),
+            (UNBONDING_ICQ_KIND, 3),
+            (REWARDS_ICQ_KIND, 4),
+        ] {
+            state::set_icq_ica_idx(storage, &icq_id, 0);
+            state::set_icq_kind(storage, &icq_id, kind);
+        }
+
+        state::set_icq_update_period(storage, 10);
+        state::set_ica_balance_icq_id(storage, &0, 1);
+        state::set_ica_delegations_icq_id(storage, &0, 2);
+        state::set_ica_unbonding_icq_id(storage, &0, 3);
+        state::set_ica_rewards_icq_id(storage, &0, 4);
+
+        // registering the ICA again reopens its channel
+        let res = exec(&mut deps, 0, ExecuteMsg::RegisterMissing {}).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Custom(NeutronMsg::RegisterInterchainAccount {
+                connection_id: "connection-0".to_owned(),
+                interchain_account_id: "0".to_owned(),
+            })
+        );
+        assert_eq!(
+            state::ica_status(deps.as_ref().storage, &0),
+            Some(IcaStatus::Registering)
+        );
+
+        let counterparty_version = format!(
+            r#"{{"version":"ics27-1","controller_connection_id":"connection-0","host_connection_id":"connection-0","address":"{ICA_ADDR}","encoding":"proto3","tx_type":"sdk_multi_msg"}}"#
+        );
+
+        let res = sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::OpenAck {
+                port_id: format!("icacontroller-{MOCK_CONTRACT_ADDR}.0"),
+                channel_id: "channel-4".to_owned(),
+                counterparty_channel_id: "channel-5".to_owned(),
+                counterparty_version,
+            },
+        )
+        .unwrap();
+
+        assert!(res.messages.is_empty(), "the ICQs are kept registered");
+
+        let storage = deps.as_ref().storage;
+
+        assert_eq!(state::ica_status(storage, &0), Some(IcaStatus::Active));
+        assert_eq!(
+            state::ica_channel(storage, &0).map(|channel| channel.channel_id),
+            Some("channel-4".to_owned())
+        );
+
+        exec(&mut deps, 2000, delegate).unwrap();
+    }
+
+    #[test]
+    fn unrecorded_replies_rejected() {
+        let mut deps = mock_dependencies();
+
+        let reply_msg = Reply {
+            id: 1,
+            result: SubMsgResult::Err("codespace: interchainqueries, code: 1".to_owned()),
+        };
+
+        assert!(matches!(
+            reply(deps.as_mut(), mock_env(), reply_msg),
+            Err(Error::UnexpectedReply { id: 1 })
+        ));
+    }
 }
@@ -1,34 +1,610 @@
-use common::updated_registered_kv_query;
-use cosmwasm_std::Deps;
+use std::{collections::BTreeMap, str::FromStr};
+
+use common::{updated_registered_kv_query, NeutronDeps};
+use cosmwasm_std::{Binary, Coin, Decimal256, StdError, Uint128, Uint256};
 use neutron_sdk::{
-    bindings::query::NeutronQuery,
-    interchain_queries::{query_kv_result, v045::types::Delegations},
-    NeutronError,
+    bindings::types::{KVKey, StorageValue},
+    interchain_queries::{
+        helpers::{decode_and_convert, length_prefix},
+        v045::types::{Delegations, UnbondingDelegations},
+    },
+    NeutronError, NeutronResult,
+};
+use prost::Message;
+
+use crate::msgs::{
+    IcaLastDelegation, IcaLastUnbonding, IcaPendingRewards, UnbondingDelegation, UnbondingEntry,
+    ValidatorRewards,
 };
 
-use crate::msgs::IcaLastDelegation;
+const DISTRIBUTION_STORE_KEY: &str = "distribution";
+const STAKING_STORE_KEY: &str = "staking";
+
+const DELEGATOR_STARTING_INFO_PREFIX: u8 = 0x04;
+const VALIDATOR_HISTORICAL_REWARDS_PREFIX: u8 = 0x05;
+const VALIDATOR_CURRENT_REWARDS_PREFIX: u8 = 0x06;
+const VALIDATOR_PREFIX: u8 = 0x21;
+
+/// The keys of the rewards ICQ per validator, once the periods the rewards accumulate over are known
+pub const REWARDS_ICQ_KEYS_PER_VALIDATOR: usize = 5;
 
 pub fn query_delegation_icq(
-    deps: Deps<NeutronQuery>,
+    deps: impl NeutronDeps,
     query_id: u64,
 ) -> Result<Option<IcaLastDelegation>, NeutronError> {
     let Some(registered_query) = updated_registered_kv_query(deps, query_id)? else {
         return Ok(None);
     };
 
-    let delegations: Delegations = query_kv_result(deps, query_id)?;
+    let delegations: Delegations = deps.kv_result(query_id)?;
+
+    let last_submitted_result_local_height = registered_query.last_submitted_result_local_height;
+
+    Ok(Some(IcaLastDelegation {
+        delegations: delegations.delegations,
+        last_submitted_result_local_height,
+    }))
+}
+
+pub fn query_unbonding_icq(
+    deps: impl NeutronDeps,
+    query_id: u64,
+) -> Result<Option<IcaLastUnbonding>, NeutronError> {
+    let Some(registered_query) = updated_registered_kv_query(deps, query_id)? else {
+        return Ok(None);
+    };
 
-    assert!(
-        delegations.delegations.len() < 2,
-        "only one validator is ever delegated to"
-    );
+    let unbonding_delegations: UnbondingDelegations = deps.kv_result(query_id)?;
 
-    let delegation = delegations.delegations.into_iter().next();
+    let unbondings = unbonding_delegations
+        .unbonding_responses
+        .into_iter()
+        .map(|unbonding| UnbondingDelegation {
+            validator: unbonding.validator_address,
+            entries: unbonding
+                .entries
+                .into_iter()
+                .map(|entry| UnbondingEntry {
+                    creation_height: entry.creation_height,
+                    completion_time: entry.completion_time,
+                    initial_balance: entry.initial_balance,
+                    balance: entry.balance,
+                })
+                .collect(),
+        })
+        .collect();
 
     let last_submitted_result_local_height = registered_query.last_submitted_result_local_height;
 
-    Ok(Some(IcaLastDelegation {
-        delegation,
+    Ok(Some(IcaLastUnbonding {
+        unbondings,
         last_submitted_result_local_height,
     }))
 }
+
+/// The periods of a validator's rewards the rewards of a delegation accumulated over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RewardPeriods {
+    /// The period the delegation started after
+    pub starting: u64,
+    /// The last period the validator completed
+    pub ending: u64,
+}
+
+/// The pending rewards decoded from a rewards ICQ result & the reward periods of each validator delegated to
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardsIcqResult {
+    pub pending_rewards: IcaPendingRewards,
+    pub periods: Vec<(String, Option<RewardPeriods>)>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct DecCoin {
+    #[prost(string, tag = "1")]
+    denom: String,
+    #[prost(string, tag = "2")]
+    amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct DelegatorStartingInfo {
+    #[prost(uint64, tag = "1")]
+    previous_period: u64,
+    #[prost(string, tag = "2")]
+    stake: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ValidatorCurrentRewards {
+    #[prost(message, repeated, tag = "1")]
+    rewards: Vec<DecCoin>,
+    #[prost(uint64, tag = "2")]
+    period: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ValidatorHistoricalRewards {
+    #[prost(message, repeated, tag = "1")]
+    cumulative_reward_ratio: Vec<DecCoin>,
+}
+
+// only the tokens of the validator are read, the other fields are skipped
+#[derive(Clone, PartialEq, Message)]
+struct Validator {
+    #[prost(string, tag = "5")]
+    tokens: String,
+}
+
+/// The reward state of a validator & of the delegation to it, as queried by the rewards ICQ
+#[derive(Default)]
+struct ValidatorRewardState {
+    starting_info: Option<DelegatorStartingInfo>,
+    current_rewards: Option<ValidatorCurrentRewards>,
+    tokens: Option<Uint256>,
+    historical: BTreeMap<u64, Vec<DecCoin>>,
+}
+
+fn kv_key(path: &str, key: Vec<u8>) -> KVKey {
+    KVKey {
+        path: path.to_owned(),
+        key: Binary(key),
+    }
+}
+
+fn prefixed_key(prefix: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let mut key = vec![prefix];
+
+    for part in parts {
+        key.extend_from_slice(part);
+    }
+
+    key
+}
+
+/// The keys of the rewards ICQ of `delegator`: per validator the delegation's starting info, the validator's
+/// current rewards & tokens, and once known the validator's historical rewards at the reward periods
+pub fn rewards_icq_keys(
+    delegator: &str,
+    validators: &[(String, Option<RewardPeriods>)],
+) -> NeutronResult<Vec<KVKey>> {
+    let delegator = length_prefix(decode_and_convert(delegator)?)?;
+
+    let mut keys = vec![];
+
+    for (validator, periods) in validators {
+        let validator = length_prefix(decode_and_convert(validator)?)?;
+
+        keys.push(kv_key(
+            DISTRIBUTION_STORE_KEY,
+            prefixed_key(DELEGATOR_STARTING_INFO_PREFIX, &[&validator, &delegator]),
+        ));
+
+        keys.push(kv_key(
+            DISTRIBUTION_STORE_KEY,
+            prefixed_key(VALIDATOR_CURRENT_REWARDS_PREFIX, &[&validator]),
+        ));
+
+        keys.push(kv_key(
+            STAKING_STORE_KEY,
+            prefixed_key(VALIDATOR_PREFIX, &[&validator]),
+        ));
+
+        let Some(RewardPeriods { starting, ending }) = *periods else {
+            continue;
+        };
+
+        // historical rewards are keyed by their period in little endian
+        for period in [starting, ending] {
+            keys.push(kv_key(
+                DISTRIBUTION_STORE_KEY,
+                prefixed_key(
+                    VALIDATOR_HISTORICAL_REWARDS_PREFIX,
+                    &[&validator, &period.to_le_bytes()],
+                ),
+            ));
+        }
+    }
+
+    keys.dedup();
+
+    Ok(keys)
+}
+
+fn parse_dec(value: &str) -> Result<Decimal256, StdError> {
+    let atomics = Uint256::from_str(value)?;
+
+    // proto decimals are encoded as their atomics with 18 decimal places
+    Decimal256::from_atomics(atomics, 18).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+fn ratio_of(coins: &[DecCoin], denom: &str) -> Result<Decimal256, StdError> {
+    coins
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map_or(Ok(Decimal256::zero()), |coin| parse_dec(&coin.amount))
+}
+
+/// The rewards of a delegation of `stake` from after `starting` to the validator's current period, as the distribution
+/// module computes them, ignoring the slashes of the validator since the delegation started
+fn accumulated_rewards(
+    stake: Decimal256,
+    starting: &[DecCoin],
+    ending: &[DecCoin],
+    current: &ValidatorCurrentRewards,
+    tokens: Uint256,
+) -> Result<Vec<Coin>, StdError> {
+    let mut denoms: Vec<&str> = ending
+        .iter()
+        .chain(&current.rewards)
+        .map(|coin| coin.denom.as_str())
+        .collect();
+
+    denoms.sort_unstable();
+
+    denoms.dedup();
+
+    let mut rewards = vec![];
+
+    for denom in denoms {
+        let mut ending_ratio = ratio_of(ending, denom)?;
+
+        // the ratio of the period in progress, as it would be completed when the rewards are withdrawn
+        if !tokens.is_zero() {
+            let current_rewards = ratio_of(&current.rewards, denom)?;
+
+            ending_ratio += Decimal256::from_ratio(
+                current_rewards.atomics(),
+                tokens * Decimal256::one().atomics(),
+            );
+        }
+
+        let ratio = ending_ratio.saturating_sub(ratio_of(starting, denom)?);
+
+        let amount = (stake * ratio).atomics() / Decimal256::one().atomics();
+
+        let amount = Uint128::try_from(amount)?;
+
+        if !amount.is_zero() {
+            rewards.push(Coin::new(amount.u128(), denom));
+        }
+    }
+
+    Ok(rewards)
+}
+
+/// Decode the pending rewards of the delegations of `delegator` to `validators` from the `kv_results` of a rewards
+/// ICQ, validators not delegated to & those whose reward periods are not queried yet are left out of the rewards
+pub fn decode_rewards(
+    kv_results: &[StorageValue],
+    delegator: &str,
+    validators: &[String],
+    last_submitted_result_local_height: u64,
+) -> NeutronResult<RewardsIcqResult> {
+    let delegator_key = length_prefix(decode_and_convert(delegator)?)?;
+
+    let mut states: Vec<(Vec<u8>, ValidatorRewardState)> = validators
+        .iter()
+        .map(|validator| {
+            let key = length_prefix(decode_and_convert(validator)?)?;
+
+            Ok::<_, NeutronError>((key, ValidatorRewardState::default()))
+        })
+        .collect::<NeutronResult<_>>()?;
+
+    for kv in kv_results {
+        // keys missing on the remote chain are submitted with empty values
+        let (Some((&prefix, rest)), false) = (kv.key.split_first(), kv.value.is_empty()) else {
+            continue;
+        };
+
+        let Some((validator_key, state)) = states
+            .iter_mut()
+            .find(|(validator_key, _)| rest.starts_with(validator_key))
+        else {
+            continue;
+        };
+
+        let rest = &rest[validator_key.len()..];
+
+        match (kv.storage_prefix.as_str(), prefix) {
+            (DISTRIBUTION_STORE_KEY, DELEGATOR_STARTING_INFO_PREFIX) if rest == delegator_key => {
+                state.starting_info = Some(DelegatorStartingInfo::decode(kv.value.as_slice())?);
+            }
+            (DISTRIBUTION_STORE_KEY, VALIDATOR_CURRENT_REWARDS_PREFIX) => {
+                state.current_rewards = Some(ValidatorCurrentRewards::decode(kv.value.as_slice())?);
+            }
+            (DISTRIBUTION_STORE_KEY, VALIDATOR_HISTORICAL_REWARDS_PREFIX) => {
+                let Ok(period) = <[u8; 8]>::try_from(rest) else {
+                    continue;
+                };
+
+                let historical = ValidatorHistoricalRewards::decode(kv.value.as_slice())?;
+
+                state.historical.insert(
+                    u64::from_le_bytes(period),
+                    historical.cumulative_reward_ratio,
+                );
+            }
+            (STAKING_STORE_KEY, VALIDATOR_PREFIX) => {
+                let validator = Validator::decode(kv.value.as_slice())?;
+
+                state.tokens = Some(Uint256::from_str(&validator.tokens)?);
+            }
+            _ => {}
+        }
+    }
+
+    let mut rewards = vec![];
+
+    let mut periods = vec![];
+
+    for (validator, (_, state)) in validators.iter().zip(states) {
+        let (Some(starting_info), Some(current_rewards)) =
+            (state.starting_info, state.current_rewards)
+        else {
+            periods.push((validator.clone(), None));
+            continue;
+        };
+
+        let reward_periods = RewardPeriods {
+            starting: starting_info.previous_period,
+            ending: current_rewards.period.saturating_sub(1),
+        };
+
+        periods.push((validator.clone(), Some(reward_periods)));
+
+        let (Some(starting), Some(ending), Some(tokens)) = (
+            state.historical.get(&reward_periods.starting),
+            state.historical.get(&reward_periods.ending),
+            state.tokens,
+        ) else {
+            continue;
+        };
+
+        let stake = parse_dec(&starting_info.stake)?;
+
+        rewards.push(ValidatorRewards {
+            validator: validator.clone(),
+            rewards: accumulated_rewards(stake, starting, ending, &current_rewards, tokens)?,
+            stale: false,
+        });
+    }
+
+    Ok(RewardsIcqResult {
+        pending_rewards: IcaPendingRewards {
+            rewards,
+            last_submitted_result_local_height,
+        },
+        periods,
+    })
+}
+
+/// Keep the `previous` rewards of the validators still delegated to per `periods` whose rewards `current` is missing,
+/// their periods having moved past the queried ones, marked stale & in the order of `periods`
+#[must_use]
+pub fn keep_stale_rewards(
+    current: IcaPendingRewards,
+    previous: Option<IcaPendingRewards>,
+    periods: &[(String, Option<RewardPeriods>)],
+) -> IcaPendingRewards {
+    let Some(previous) = previous else {
+        return current;
+    };
+
+    let mut rewards = vec![];
+
+    for (validator, periods) in periods {
+        let computed = current
+            .rewards
+            .iter()
+            .find(|rewards| rewards.validator == *validator);
+
+        if let Some(computed) = computed {
+            rewards.push(computed.clone());
+            continue;
+        }
+
+        // validators no longer delegated to have no rewards left to keep
+        if periods.is_none() {
+            continue;
+        }
+
+        let kept = previous
+            .rewards
+            .iter()
+            .find(|rewards| rewards.validator == *validator);
+
+        if let Some(kept) = kept {
+            rewards.push(ValidatorRewards {
+                stale: true,
+                ..kept.clone()
+            });
+        }
+    }
+
+    IcaPendingRewards {
+        rewards,
+        last_submitted_result_local_height: current.last_submitted_result_local_height,
+    }
+}
+
+pub fn query_rewards_icq(
+    deps: impl NeutronDeps,
+    query_id: u64,
+    delegator: &str,
+    validators: &[String],
+) -> Result<Option<RewardsIcqResult>, NeutronError> {
+    let Some(registered_query) = updated_registered_kv_query(deps, query_id)? else {
+        return Ok(None);
+    };
+
+    let res = deps.registered_query_result(query_id)?;
+
+    let result = decode_rewards(
+        &res.result.kv_results,
+        delegator,
+        validators,
+        registered_query.last_submitted_result_local_height,
+    )?;
+
+    Ok(Some(result))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DELEGATOR: &str = "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du";
+    const VALIDATOR: &str = "cosmosvaloper1qgpqyqszqgpqyqszqgpqyqszqgpqyqszxrnw2e";
+
+    fn dec_coin(denom: &str, amount: &str) -> DecCoin {
+        DecCoin {
+            denom: denom.to_owned(),
+            // proto decimals carry 18 decimal places
+            amount: format!("{amount}000000000000000000"),
+        }
+    }
+
+    fn storage_value(key: &KVKey, value: Vec<u8>) -> StorageValue {
+        StorageValue {
+            storage_prefix: key.path.clone(),
+            key: key.key.clone(),
+            value: Binary(value),
+        }
+    }
+
+    #[test]
+    fn rewards_accumulated_over_queried_periods() {
+        let periods = RewardPeriods {
+            starting: 2,
+            ending: 4,
+        };
+
+        let keys = rewards_icq_keys(DELEGATOR, &[(VALIDATOR.to_owned(), None)]).unwrap();
+
+        assert_eq!(keys.len(), 3);
+
+        let mut kv_results = vec![
+            storage_value(
+                &keys[0],
+                DelegatorStartingInfo {
+                    previous_period: periods.starting,
+                    stake: Decimal256::from_str("100").unwrap().atomics().to_string(),
+                }
+                .encode_to_vec(),
+            ),
+            storage_value(
+                &keys[1],
+                ValidatorCurrentRewards {
+                    rewards: vec![dec_coin("uatom", "50")],
+                    period: periods.ending + 1,
+                }
+                .encode_to_vec(),
+            ),
+            storage_value(
+                &keys[2],
+                Validator {
+                    tokens: "1000".to_owned(),
+                }
+                .encode_to_vec(),
+            ),
+        ];
+
+        // the first result only reveals the periods the rewards accumulated over
+        let result = decode_rewards(&kv_results, DELEGATOR, &[VALIDATOR.to_owned()], 7).unwrap();
+
+        assert!(result.pending_rewards.rewards.is_empty());
+        assert_eq!(result.periods, [(VALIDATOR.to_owned(), Some(periods))]);
+
+        let keys = rewards_icq_keys(DELEGATOR, &result.periods).unwrap();
+
+        assert_eq!(keys.len(), REWARDS_ICQ_KEYS_PER_VALIDATOR);
+
+        for (key, ratio) in keys[3..].iter().zip(["0.1", "0.3"]) {
+            let ratio = Decimal256::from_str(ratio).unwrap();
+
+            let historical = ValidatorHistoricalRewards {
+                cumulative_reward_ratio: vec![DecCoin {
+                    denom: "uatom".to_owned(),
+                    amount: ratio.atomics().to_string(),
+                }],
+            };
+
+            kv_results.push(storage_value(key, historical.encode_to_vec()));
+        }
+
+        let result = decode_rewards(&kv_results, DELEGATOR, &[VALIDATOR.to_owned()], 8).unwrap();
+
+        // 100 staked * (0.3 + 50 / 1000 tokens - 0.1)
+        assert_eq!(
+            result.pending_rewards,
+            IcaPendingRewards {
+                rewards: vec![ValidatorRewards {
+                    validator: VALIDATOR.to_owned(),
+                    rewards: vec![Coin::new(25, "uatom")],
+                    stale: false,
+                }],
+                last_submitted_result_local_height: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn rewards_kept_stale_while_periods_move() {
+        const OTHER_VALIDATOR: &str = "other";
+
+        let periods = Some(RewardPeriods {
+            starting: 2,
+            ending: 4,
+        });
+
+        let rewards = |validator: &str, amount: u128, stale: bool| ValidatorRewards {
+            validator: validator.to_owned(),
+            rewards: vec![Coin::new(amount, "uatom")],
+            stale,
+        };
+
+        let previous = IcaPendingRewards {
+            rewards: vec![
+                rewards(VALIDATOR, 25, false),
+                rewards(OTHER_VALIDATOR, 5, false),
+            ],
+            last_submitted_result_local_height: 8,
+        };
+
+        // the periods of the first validator moved, the second was undelegated from
+        let current = IcaPendingRewards {
+            rewards: vec![],
+            last_submitted_result_local_height: 9,
+        };
+
+        let validator_periods = [
+            (VALIDATOR.to_owned(), periods),
+            (OTHER_VALIDATOR.to_owned(), None),
+        ];
+
+        assert_eq!(
+            keep_stale_rewards(current.clone(), Some(previous.clone()), &validator_periods),
+            IcaPendingRewards {
+                rewards: vec![rewards(VALIDATOR, 25, true)],
+                last_submitted_result_local_height: 9,
+            }
+        );
+
+        assert_eq!(
+            keep_stale_rewards(current.clone(), None, &validator_periods),
+            current
+        );
+
+        // rewards computed from the result replace the kept ones
+        let current = IcaPendingRewards {
+            rewards: vec![rewards(VALIDATOR, 30, false)],
+            last_submitted_result_local_height: 10,
+        };
+
+        assert_eq!(
+            keep_stale_rewards(current.clone(), Some(previous), &validator_periods),
+            current
+        );
+    }
+}
@@ -1,5 +1,11 @@
+use common::validate::{
+    validate_connection_id, validate_denom, validate_icq_deposit, validate_icq_update_period,
+    validate_remote_address, ValidationError,
+};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Coin, Delegation};
+use cosmwasm_std::{Coin, Delegation, Timestamp, Uint128};
+
+pub use common::{error_codes::ErrorCode, IcaChannel, IcaStatus};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -11,19 +17,185 @@ pub struct InstantiateMsg {
     pub icq_update_period: u64,
     /// The asset denomination of the balance ICQ
     pub balance_icq_denom: String,
-    /// The validator of the delegations ICQ
-    pub delegations_icq_validator: String,
+    /// Denoms queried by the balance ICQ in addition to `balance_icq_denom`, at most `MAX_BALANCE_ICQ_DENOMS` in total
+    /// once duplicates are dropped
+    pub additional_balance_icq_denoms: Option<Vec<String>>,
+    /// The validators of the delegations & unbonding delegations ICQs, at most `MAX_DELEGATIONS_ICQ_VALIDATORS`, the
+    /// rewards ICQ querying the first `MAX_REWARDS_ICQ_VALIDATORS` of them
+    pub delegations_icq_validators: Vec<String>,
+    /// The ICQ deposit charged if the interchainqueries params cannot be queried, e.g. once a chain upgrade moved the
+    /// params query, the live deposit is charged whenever it can be queried
+    pub icq_deposit_override: Option<Vec<Coin>>,
+    /// The address receiving the deposits of ICQs removed by `RemoveIcqs`, the admin removing them if unset
+    pub icq_refund_address: Option<String>,
+    /// The owners assigned to ICAs of the set, the ICAs left unassigned can be claimed by the claimants the admin
+    /// approves with `ApproveIcaClaim`
+    pub ica_owners: Option<Vec<IcaOwner>>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub struct IcaOwner {
+    pub ica_idx: u32,
+    pub owner: String,
+}
+
+impl InstantiateMsg {
+    /// Validate the shape of the message without touching the chain
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate_connection_id(&self.connection_id)?;
+        validate_icq_update_period(self.icq_update_period)?;
+        validate_denom(&self.balance_icq_denom)?;
+
+        for denom in self.additional_balance_icq_denoms.iter().flatten() {
+            validate_denom(denom)?;
+        }
+
+        for validator in &self.delegations_icq_validators {
+            validate_remote_address(validator)?;
+        }
+
+        if let Some(deposit) = &self.icq_deposit_override {
+            validate_icq_deposit(deposit)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cw_serde]
-pub enum ExecuteMsg {}
+pub enum ExecuteMsg {
+    /// Restore the missing ICQ reverse lookups of at most `limit` ICAs from their ICQ ids, resuming after the ICAs
+    /// visited by the previous call until every ICA has been visited, only callable by the contract admin
+    RebuildIndexes { limit: u32 },
+    /// Re-issue the registration of every ICA of the set whose registration failed or timed out, awaiting its channel
+    /// open ack for more than `ICA_REGISTRATION_TIMEOUT_BLOCKS`, or whose channel was closed by a timed out staking
+    /// tx, and of the ICQs of registered ICAs whose registration failed, only callable by the contract admin
+    RegisterMissing {},
+    /// Grow the ICA set by `additional` ICAs, registered as the ICAs registered at instantiation, the deposit of
+    /// their `ICQS_PER_ICA` ICQs must be attached, only callable by the contract admin
+    ExpandSet { additional: u32 },
+    /// Remove the balance, delegations, unbonding delegations & rewards ICQs of the ICA at `ica_idx`, forwarding their deposits to the ICQ refund
+    /// address, only callable by the contract admin
+    RemoveIcqs { ica_idx: u32 },
+    /// Set the address receiving the deposits of removed ICQs, unset to refund the admin removing them,
+    /// only callable by the contract admin
+    UpdateIcqRefundAddress { address: Option<String> },
+    /// Replace the validators of the delegations & unbonding delegations ICQs, and those of the rewards ICQ among them,
+    /// updating the keys of every registered one, only callable by the contract admin
+    UpdateDelegationsIcqValidators { validators: Vec<String> },
+    /// Set the update period in blocks of every ICQ, updating the registered ones of at most
+    /// `ICQ_UPDATE_PERIOD_BATCH_ICAS` ICAs per call, calling again with the same period resumes after the ICAs
+    /// updated by the previous call until every ICA has been updated, only callable by the contract admin
+    SetIcqUpdatePeriod { period: u64 },
+    /// Approve `claimant` to claim the ICA at `ica_idx`, unset to withdraw the approval, only callable by the
+    /// contract admin
+    ApproveIcaClaim {
+        ica_idx: u32,
+        claimant: Option<String>,
+    },
+    /// Claim the ownership of the ICA at `ica_idx` for the sender, only callable by the claimant approved by the
+    /// admin while the ICA has no owner
+    ClaimIca { ica_idx: u32 },
+    /// Assign `owner` to the ICA at `ica_idx`, replacing its owner if any, only callable by the contract admin
+    SetIcaOwner { ica_idx: u32, owner: String },
+    /// Remove the owner of the ICA at `ica_idx`, handing its staking txs back to the contract admin, only callable by
+    /// the contract admin
+    RevokeIcaOwner { ica_idx: u32 },
+    /// Delegate `amount` of the balance ICQ denom held by the ICA at `ica_idx` to `validator` through an ICA tx, the
+    /// min IBC fee of the tx must be attached & any excess is refunded, only callable by the owner of the ICA, or by the contract admin if the
+    /// ICA has no owner
+    Delegate {
+        ica_idx: u32,
+        validator: String,
+        amount: Uint128,
+    },
+    /// Undelegate `amount` of the balance ICQ denom delegated by the ICA at `ica_idx` from `validator` through an ICA
+    /// tx, the min IBC fee of the tx must be attached & any excess is refunded, only callable by the owner of the ICA, or by the contract admin
+    /// if the ICA has no owner
+    Undelegate {
+        ica_idx: u32,
+        validator: String,
+        amount: Uint128,
+    },
+}
 
 #[cw_serde]
 pub enum QueryMsg {
     IcaSetSize {},
-    IcaMetadata { ica_idx: u32 },
-    IcaLastBalance { ica_idx: u32 },
-    IcaLastDelegation { ica_idx: u32 },
+    IcaMetadata {
+        ica_idx: u32,
+    },
+    IcaLastBalance {
+        ica_idx: u32,
+    },
+    IcaLastDelegation {
+        ica_idx: u32,
+    },
+    /// The unbonding delegations of the ICA to the validators of the delegations ICQ
+    IcaLastUnbonding {
+        ica_idx: u32,
+    },
+    /// The staking rewards of the ICA not yet withdrawn from the first `MAX_REWARDS_ICQ_VALIDATORS` validators of the
+    /// delegations ICQ
+    IcaPendingRewards {
+        ica_idx: u32,
+    },
+    /// The outcomes of the staking txs issued by the ICA
+    IcaStakingTxStatus {
+        ica_idx: u32,
+    },
+    /// The last balance of each ICA, ordered by ICA index
+    AllLastBalances {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// The last delegations of each ICA, ordered by ICA index
+    AllLastDelegations {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// The latest recorded balance of the ICA at or before the given local height
+    IcaBalanceAt {
+        ica_idx: u32,
+        height: u64,
+    },
+    /// The progress of the registration of every ICA & of its ICQs, ordered by ICA index
+    RegistrationStatus {},
+    IcqRefundAddress {},
+    ErrorCodes {},
+}
+
+#[cw_serde]
+pub enum RegistrationStatus {
+    /// The ICA registration was issued & awaits its channel open ack
+    Pending,
+    /// The ICA channel is open & its ICQs await their registration
+    ChannelOpen,
+    /// The balance, delegations, unbonding delegations & rewards ICQs of the ICA are registered
+    IcqRegistered,
+    /// The registration of the ICA or one of its ICQs failed, `RegisterMissing` re-issues failed registrations. The
+    /// reason only holds the codespace & code of the error, CosmWasm 1.x redacting the errors of submessages
+    Failed { reason: String },
+}
+
+#[cw_serde]
+pub struct IcaRegistrationStatus {
+    pub ica_idx: u32,
+    pub status: RegistrationStatus,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RegistrationStatusResponse {
+    pub icas: Vec<IcaRegistrationStatus>,
+}
+
+#[cw_serde]
+pub struct IcqRefundAddressResponse {
+    pub address: Option<String>,
 }
 
 #[cw_serde]
@@ -33,9 +205,19 @@ pub struct IcaSetSizeResponse {
 
 #[cw_serde]
 pub struct IcaMetadata {
-    pub address: String,
-    pub balance_icq_id: u64,
-    pub delegation_icq_id: u64,
+    pub address: Option<String>,
+    /// Assigned at instantiation or by the admin, or claimed by an approved claimant, the owner alone can act
+    /// through the ICA
+    pub owner: Option<String>,
+    pub balance_icq_id: Option<u64>,
+    pub delegation_icq_id: Option<u64>,
+    pub unbonding_icq_id: Option<u64>,
+    pub rewards_icq_id: Option<u64>,
+    pub status: IcaStatus,
+    /// The local height at which the status last changed
+    pub last_status_change_height: u64,
+    /// Set once the ICA channel is open
+    pub channel: Option<IcaChannel>,
 }
 
 #[cw_serde]
@@ -46,7 +228,8 @@ pub struct IcaMetadataResponse {
 #[cw_serde]
 #[derive(Default)]
 pub struct IcaLastBalance {
-    pub balance: Option<Coin>,
+    /// The non-empty balances of the balance ICQ denoms
+    pub balances: Vec<Coin>,
     pub address: String,
     pub last_submitted_result_local_height: u64,
 }
@@ -55,11 +238,32 @@ pub struct IcaLastBalance {
 #[derive(Default)]
 pub struct IcaLastBalanceResponse {
     pub last_balance: Option<IcaLastBalance>,
+    /// Set if the balance ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+    /// Unset only if a newer result was submitted but failed to be recorded, the older recorded result being served
+    /// meanwhile. It says nothing about how old the recorded result is, see `last_submitted_result_local_height`
+    pub fresh: bool,
+}
+
+#[cw_serde]
+pub struct BalanceSnapshot {
+    /// The non-empty balances of the balance ICQ denoms
+    pub balances: Vec<Coin>,
+    /// The local height at which the balance ICQ result was submitted
+    pub local_height: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct IcaBalanceAtResponse {
+    /// None if no balance was recorded at or before the height, or if it has since been evicted from the history
+    pub snapshot: Option<BalanceSnapshot>,
 }
 
 #[cw_serde]
 pub struct IcaLastDelegation {
-    pub delegation: Option<Delegation>,
+    /// The delegations to the validators of the delegations ICQ, validators not delegated to are left out
+    pub delegations: Vec<Delegation>,
     pub last_submitted_result_local_height: u64,
 }
 
@@ -67,4 +271,159 @@ pub struct IcaLastDelegation {
 #[derive(Default)]
 pub struct IcaLastDelegationResponse {
     pub last_delegation: Option<IcaLastDelegation>,
+    /// Set if the delegations ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+    /// As `IcaLastBalanceResponse::fresh`
+    pub fresh: bool,
+}
+
+#[cw_serde]
+pub struct UnbondingEntry {
+    /// The remote height the unbonding started at
+    pub creation_height: u64,
+    /// The remote time the unbonded tokens are released at
+    pub completion_time: Option<Timestamp>,
+    pub initial_balance: Uint128,
+    /// The tokens still unbonding, less than `initial_balance` once slashed
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+pub struct UnbondingDelegation {
+    pub validator: String,
+    /// The unbondings from the validator, in the order they complete
+    pub entries: Vec<UnbondingEntry>,
+}
+
+#[cw_serde]
+pub struct IcaLastUnbonding {
+    /// The unbonding delegations from the validators of the ICQ, validators not unbonded from are left out
+    pub unbondings: Vec<UnbondingDelegation>,
+    pub last_submitted_result_local_height: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct IcaLastUnbondingResponse {
+    pub last_unbonding: Option<IcaLastUnbonding>,
+    /// Set if the unbonding delegations ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+    /// As `IcaLastBalanceResponse::fresh`
+    pub fresh: bool,
+}
+
+#[cw_serde]
+pub struct ValidatorRewards {
+    pub validator: String,
+    /// The rewards accumulated since the delegation last started or withdrew its rewards, truncated
+    pub rewards: Vec<Coin>,
+    /// Set if the rewards are kept from an earlier result, the periods of the validator having moved past the ones
+    /// queried by the last result
+    pub stale: bool,
+}
+
+#[cw_serde]
+pub struct IcaPendingRewards {
+    /// The rewards per validator delegated to, validators whose reward periods were never queried are left out
+    pub rewards: Vec<ValidatorRewards>,
+    pub last_submitted_result_local_height: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct IcaPendingRewardsResponse {
+    pub pending_rewards: Option<IcaPendingRewards>,
+    /// Set if the rewards ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+    /// As `IcaLastBalanceResponse::fresh`
+    pub fresh: bool,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum StakingTxKind {
+    Delegate,
+    Undelegate,
+}
+
+#[cw_serde]
+pub enum StakingTxOutcome {
+    /// The tx awaits its acknowledgement
+    Pending,
+    Succeeded,
+    Failed {
+        error: String,
+    },
+    /// The tx timed out, closing the ordered ICA channel
+    TimedOut,
+}
+
+#[cw_serde]
+pub struct StakingTx {
+    pub kind: StakingTxKind,
+    pub validator: String,
+    pub amount: Coin,
+    /// The sequence number of the ICA tx on `channel`
+    pub seq_num: u64,
+    pub channel: String,
+    /// The local height at which the tx was issued
+    pub issued_height: u64,
+    pub outcome: StakingTxOutcome,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct IcaStakingTxStatus {
+    pub success_count: u32,
+    pub error_count: u32,
+    pub timeout_count: u32,
+    /// The last delegation issued by the ICA
+    pub last_delegate: Option<StakingTx>,
+    /// The last undelegation issued by the ICA
+    pub last_undelegate: Option<StakingTx>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct IcaStakingTxStatusResponse {
+    pub status: Option<IcaStakingTxStatus>,
+}
+
+#[cw_serde]
+pub struct IcaLastBalanceEntry {
+    pub ica_idx: u32,
+    pub last_balance: Option<IcaLastBalance>,
+    /// Set if the balance ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+    /// As `IcaLastBalanceResponse::fresh`
+    pub fresh: bool,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AllLastBalancesResponse {
+    pub balances: Vec<IcaLastBalanceEntry>,
+}
+
+#[cw_serde]
+pub struct IcaLastDelegationEntry {
+    pub ica_idx: u32,
+    pub last_delegation: Option<IcaLastDelegation>,
+    /// Set if the delegations ICQ is no longer registered as a KV query & is no longer served
+    pub icq_invalid: bool,
+    /// As `IcaLastBalanceResponse::fresh`
+    pub fresh: bool,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AllLastDelegationsResponse {
+    pub delegations: Vec<IcaLastDelegationEntry>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ErrorCodesResponse {
+    /// The code of every error the contract can return, as prefixed to its message
+    pub codes: Vec<ErrorCode>,
 }
@@ -0,0 +1,459 @@
+//! Instantiates & tracks an `ibc-transfer-roundtrip` probe per IBC path, following the factory pattern: each probe
+//! is instantiated by a submessage whose reply carries the probe's address. The health of every probed path is then
+//! queried from the probes themselves & aggregated across the fleet.
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::missing_panics_doc,
+    clippy::missing_errors_doc,
+    clippy::needless_pass_by_value
+)]
+
+pub mod msgs;
+
+use common::{
+    debug,
+    factory::{self, Child, FactoryError},
+};
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, Event, MessageInfo, Reply, Response,
+    Storage, WasmMsg,
+};
+
+use crate::msgs::{
+    ErrorCodesResponse, ExecuteMsg, FleetHealthResponse, InstantiateMsg, Probe, ProbeGlobalStats,
+    ProbeHealth, ProbeInstantiateMsg, ProbePath, ProbePaused, ProbeQueryMsg, ProbeTotals,
+    ProbesResponse, QueryMsg,
+};
+
+/// The number of probes a coordinator can instantiate
+pub const MAX_PROBES: u32 = 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("[E0001] {0}")]
+    CosmwasmStd(#[from] cosmwasm_std::StdError),
+    #[error("[E0003] {0}")]
+    ParseReply(#[from] common::ParseReplyError),
+    #[error("[E0500] unauthorized")]
+    Unauthorized,
+    #[error("[E0501] at most {MAX_PROBES} probes can be instantiated")]
+    TooManyProbes,
+    #[error("[E0502] unexpected probe instantiation reply {id}")]
+    UnexpectedProbeReply { id: u64 },
+}
+
+common::error_codes!(Error {
+    CosmwasmStd = 1,
+    ParseReply = 3,
+    Unauthorized = 500,
+    TooManyProbes = 501,
+    UnexpectedProbeReply = 502,
+});
+
+impl From<FactoryError> for Error {
+    fn from(err: FactoryError) -> Self {
+        match err {
+            FactoryError::ParseReply(err) => Self::ParseReply(err),
+            FactoryError::UnexpectedReply { id } => Self::UnexpectedProbeReply { id },
+        }
+    }
+}
+
+// the probes are registered as the children of `common::factory`
+pub mod state {
+    use cosmwasm_schema::cw_serde;
+    use cw_storage_macros::item;
+
+    item!(owner!         : String);
+    item!(probe_code_id! : u64);
+
+    /// The context of a submessage, recorded against its reply id until the reply is received
+    #[cw_serde]
+    pub enum ReplyKind {
+        InstantiateProbe { idx: u32 },
+    }
+}
+
+#[entry_point]
+pub fn instantiate(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, Error> {
+    debug!(deps, "handling instantiate msg");
+
+    state::set_owner(deps.storage, info.sender.as_str());
+
+    state::set_probe_code_id(deps.storage, msg.probe_code_id);
+
+    let mut response = Response::default();
+
+    for path in msg.paths {
+        let res = add_path(deps.branch(), path)?;
+
+        response = response
+            .add_submessages(res.messages)
+            .add_events(res.events);
+    }
+
+    Ok(response)
+}
+
+/// Instantiate a probe of `path`, its address is captured by the reply. The coordinator owner administers the probe
+/// & owns it, the coordinator has no way to forward admin msgs to its probes
+pub fn add_path(deps: DepsMut, path: ProbePath) -> Result<Response, Error> {
+    let idx = factory::child_count(deps.storage);
+
+    if idx >= MAX_PROBES {
+        return Err(Error::TooManyProbes);
+    }
+
+    let owner = state::owner(deps.storage);
+
+    let probe_msg = ProbeInstantiateMsg {
+        connection_id: &path.connection_id,
+        ibc_transfer_channel: &path.ibc_transfer_channel,
+        icq_update_period: path.icq_update_period,
+        remote_denom: &path.remote_denom,
+        host_ibc_denom: &path.host_ibc_denom,
+        admin: &owner,
+    };
+
+    let instantiate_msg = WasmMsg::Instantiate {
+        admin: Some(owner.clone()),
+        code_id: state::probe_code_id(deps.storage),
+        msg: to_binary(&probe_msg)?,
+        funds: vec![],
+        label: format!(
+            "probe {idx}: {} over {}",
+            path.connection_id, path.ibc_transfer_channel
+        ),
+    };
+
+    let event = Event::new("probe_added")
+        .add_attribute("idx", idx.to_string())
+        .add_attribute("connection_id", path.connection_id.as_str())
+        .add_attribute("ibc_transfer_channel", path.ibc_transfer_channel.as_str());
+
+    let (_, instantiate_msg) =
+        factory::instantiate_child(deps.storage, instantiate_msg, &path, |idx| {
+            state::ReplyKind::InstantiateProbe { idx }
+        })?;
+
+    Ok(Response::default()
+        .add_submessage(instantiate_msg)
+        .add_event(event))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, Error> {
+    debug!(deps, "handling execute msg");
+
+    match msg {
+        ExecuteMsg::AddPath { .. } if info.sender.as_str() != state::owner(deps.storage) => {
+            Err(Error::Unauthorized)
+        }
+
+        ExecuteMsg::AddPath { path } => add_path(deps, path),
+    }
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, Error> {
+    debug!(deps, "handling reply msg: {}", msg.id);
+
+    let state::ReplyKind::InstantiateProbe { idx } =
+        common::take_reply_payload(deps.storage, msg.id)
+            .ok_or(Error::UnexpectedProbeReply { id: msg.id })?;
+
+    let probe: Child<ProbePath> = factory::register_child(deps.storage, idx, msg)?;
+
+    let event = Event::new("probe_instantiated")
+        .add_attribute("idx", probe.idx.to_string())
+        .add_attribute("address", probe.address.unwrap_or_default());
+
+    Ok(Response::default().add_event(event))
+}
+
+fn probes(storage: &dyn Storage) -> Vec<Probe> {
+    factory::children(storage, None, MAX_PROBES as usize)
+        .into_iter()
+        .map(|child: Child<ProbePath>| Probe {
+            idx: child.idx,
+            path: child.params,
+            address: child.address,
+        })
+        .collect()
+}
+
+fn add_totals(totals: &mut ProbeTotals, add: &ProbeTotals) {
+    totals.tx_issued += add.tx_issued;
+    totals.tx_success += add.tx_success;
+    totals.tx_error += add.tx_error;
+    totals.tx_timeout += add.tx_timeout;
+    totals.round_trips += add.round_trips;
+}
+
+/// Query the health of a probe, an unreachable probe is reported as such rather than failing the whole query
+#[must_use]
+pub fn probe_health(deps: Deps, idx: u32, address: String, path: ProbePath) -> ProbeHealth {
+    let stats: Option<ProbeGlobalStats> = deps
+        .querier
+        .query_wasm_smart(&address, &ProbeQueryMsg::GlobalStats {})
+        .ok();
+
+    let paused: Option<ProbePaused> = deps
+        .querier
+        .query_wasm_smart(&address, &ProbeQueryMsg::Paused {})
+        .ok();
+
+    let reachable = stats.is_some() && paused.is_some();
+
+    let (ica_count, totals) = stats.map_or((0, ProbeTotals::default()), |stats| {
+        (stats.ica_count, stats.totals)
+    });
+
+    ProbeHealth {
+        idx,
+        address,
+        connection_id: path.connection_id,
+        ibc_transfer_channel: path.ibc_transfer_channel,
+        reachable,
+        paused: paused.map_or(false, |p| p.paused),
+        ica_count,
+        totals,
+    }
+}
+
+#[must_use]
+pub fn fleet_health(deps: Deps) -> FleetHealthResponse {
+    let mut fleet = FleetHealthResponse::default();
+
+    for probe in probes(deps.storage) {
+        // only unset until the reply of the tx instantiating the probe
+        let Some(address) = probe.address else {
+            continue;
+        };
+
+        let health = probe_health(deps, probe.idx, address, probe.path);
+
+        if !health.is_healthy() {
+            fleet.unhealthy += 1;
+        }
+
+        add_totals(&mut fleet.totals, &health.totals);
+
+        fleet.probes.push(health);
+    }
+
+    fleet
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
+    let res = match msg {
+        QueryMsg::Probes {} => to_binary(&ProbesResponse {
+            probes: probes(deps.storage),
+        })?,
+
+        QueryMsg::FleetHealth {} => to_binary(&fleet_health(deps))?,
+
+        QueryMsg::ErrorCodes {} => to_binary(&ErrorCodesResponse {
+            codes: Error::catalogue(),
+        })?,
+    };
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{
+        from_binary,
+        testing::{mock_dependencies, mock_env, mock_info},
+        ContractResult, CosmosMsg, SystemError, SystemResult, WasmQuery,
+    };
+
+    use super::*;
+
+    fn path(n: u32) -> ProbePath {
+        ProbePath {
+            connection_id: format!("connection-{n}"),
+            ibc_transfer_channel: format!("channel-{n}"),
+            icq_update_period: 6,
+            remote_denom: "uatom".to_owned(),
+            host_ibc_denom: "ibc/atom".to_owned(),
+        }
+    }
+
+    fn instantiate_reply(reply_id: u64, address: &str) -> Reply {
+        common::testing::instantiate_reply(reply_id, address)
+    }
+
+    #[test]
+    fn probes_instantiated_for_the_coordinator_owner() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            probe_code_id: 7,
+            paths: vec![path(0)],
+        };
+
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        let CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin,
+            code_id,
+            msg,
+            ..
+        }) = &res.messages[0].msg
+        else {
+            panic!("probes are instantiated by a wasm msg");
+        };
+
+        // the owner administers & owns the probe, the coordinator cannot act on its behalf
+        assert_eq!(admin.as_deref(), Some("admin"));
+        assert_eq!(*code_id, 7);
+        assert_eq!(
+            std::str::from_utf8(msg).unwrap(),
+            r#"{"connection_id":"connection-0","ibc_transfer_channel":"channel-0","icq_update_period":6,"remote_denom":"uatom","host_ibc_denom":"ibc/atom","admin":"admin"}"#
+        );
+
+        let probes = |deps: Deps| -> Vec<Probe> {
+            from_binary::<ProbesResponse>(&query(deps, mock_env(), QueryMsg::Probes {}).unwrap())
+                .unwrap()
+                .probes
+        };
+
+        assert_eq!(probes(deps.as_ref())[0].address, None);
+
+        let reply_id = res.messages[0].id;
+
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(reply_id, "probe_a"),
+        )
+        .unwrap();
+
+        assert_eq!(res.events[0].ty, "probe_instantiated");
+        assert_eq!(
+            probes(deps.as_ref()),
+            vec![Probe {
+                idx: 0,
+                path: path(0),
+                address: Some("probe_a".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn fleet_health_aggregated_from_instantiated_probes() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            probe_code_id: 7,
+            paths: vec![path(0), path(1)],
+        };
+
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        assert_eq!(res.messages.len(), 2, "a probe is instantiated per path");
+
+        let reply_ids: Vec<u64> = res.messages.iter().map(|msg| msg.id).collect();
+
+        assert_eq!(reply_ids, [1, 2]);
+
+        assert!(matches!(
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("someone", &[]),
+                ExecuteMsg::AddPath { path: path(2) }
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        reply(deps.as_mut(), mock_env(), instantiate_reply(1, "probe_a")).unwrap();
+        reply(deps.as_mut(), mock_env(), instantiate_reply(2, "probe_b")).unwrap();
+
+        // the payload of a reply is taken once
+        assert!(matches!(
+            reply(deps.as_mut(), mock_env(), instantiate_reply(2, "probe_c")),
+            Err(Error::UnexpectedProbeReply { .. })
+        ));
+
+        // replies without a recorded payload are not taken for a probe's
+        assert!(matches!(
+            reply(deps.as_mut(), mock_env(), instantiate_reply(3, "probe_c")),
+            Err(Error::UnexpectedProbeReply { .. })
+        ));
+
+        // probe_b is unreachable
+        deps.querier.update_wasm(|query| {
+            let WasmQuery::Smart { contract_addr, msg } = query else {
+                unreachable!("only smart queries are made")
+            };
+
+            if contract_addr != "probe_a" {
+                return SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.clone(),
+                });
+            }
+
+            let res: &[u8] = match from_binary(msg).unwrap() {
+                ProbeQueryMsg::GlobalStats {} => {
+                    br#"{
+                    "volumes": [],
+                    "denom_volumes": [],
+                    "ica_count": 2,
+                    "totals": {
+                        "tx_issued": 4,
+                        "tx_success": 2,
+                        "tx_error": 1,
+                        "tx_timeout": 1,
+                        "round_trips": 1
+                    }
+                }"#
+                }
+                ProbeQueryMsg::Paused {} => br#"{ "paused": false }"#,
+            };
+
+            SystemResult::Ok(ContractResult::Ok(Binary::from(res)))
+        });
+
+        let FleetHealthResponse {
+            probes,
+            unhealthy,
+            totals,
+        } = fleet_health(deps.as_ref());
+
+        assert_eq!(unhealthy, 1);
+        assert_eq!(probes.len(), 2);
+
+        assert!(probes[0].is_healthy());
+        assert_eq!(probes[0].ica_count, 2);
+        assert_eq!(probes[0].connection_id, "connection-0");
+
+        assert!(!probes[1].reachable);
+
+        assert_eq!(
+            totals,
+            ProbeTotals {
+                tx_issued: 4,
+                tx_success: 2,
+                tx_error: 1,
+                tx_timeout: 1,
+                round_trips: 1,
+            }
+        );
+    }
+}
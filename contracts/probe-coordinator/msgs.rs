@@ -0,0 +1,141 @@
+use cosmwasm_schema::cw_serde;
+use serde::{Deserialize, Serialize};
+
+pub use common::error_codes::ErrorCode;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The code id of `ibc-transfer-roundtrip` each probe is instantiated from
+    pub probe_code_id: u64,
+    /// The IBC paths to probe, one probe is instantiated per path
+    pub paths: Vec<ProbePath>,
+}
+
+/// An IBC path probed by an `ibc-transfer-roundtrip` instance, sent as the instantiate msg of the probe along with
+/// the coordinator owner as the probe's owner. The other optional fields of the probe's instantiate msg are left to
+/// their defaults.
+#[cw_serde]
+pub struct ProbePath {
+    /// The IBC connection ID on which the probe registers ICAs/ICQs
+    pub connection_id: String,
+    /// The IBC channel over which the probe transfers the assets
+    pub ibc_transfer_channel: String,
+    /// The target update period for the probe's ICQs
+    pub icq_update_period: u64,
+    /// The denom of the transfer asset on the remote chain
+    pub remote_denom: String,
+    /// The ICS-20 denom of the transfer asset on the host chain
+    pub host_ibc_denom: String,
+}
+
+/// The instantiate msg of a probe, the fields of `ibc-transfer-roundtrip`'s instantiate msg set by the coordinator
+#[derive(Serialize)]
+pub struct ProbeInstantiateMsg<'a> {
+    pub connection_id: &'a str,
+    pub ibc_transfer_channel: &'a str,
+    pub icq_update_period: u64,
+    pub remote_denom: &'a str,
+    pub host_ibc_denom: &'a str,
+    /// The owner of the probe, able to pause it & update its config
+    pub admin: &'a str,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Instantiate a probe of `path`, at most `MAX_PROBES` probes can be instantiated,
+    /// only callable by the contract owner
+    AddPath { path: ProbePath },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Query the probes instantiated so far, ordered by probe index
+    Probes {},
+    /// Query the health of every probe along with the totals across the fleet
+    FleetHealth {},
+    /// Query the code of every error, surfaced in error messages as `[E0123]`
+    ErrorCodes {},
+}
+
+#[cw_serde]
+pub struct Probe {
+    pub idx: u32,
+    pub path: ProbePath,
+    /// Set once the probe's instantiation reply is received
+    pub address: Option<String>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ProbesResponse {
+    pub probes: Vec<Probe>,
+}
+
+/// The tx & roundtrip counters summed across the ICAs of a probe, or across the fleet
+#[cw_serde]
+#[derive(Default, Copy)]
+pub struct ProbeTotals {
+    pub tx_issued: u64,
+    pub tx_success: u64,
+    pub tx_error: u64,
+    pub tx_timeout: u64,
+    pub round_trips: u64,
+}
+
+#[cw_serde]
+pub struct ProbeHealth {
+    pub idx: u32,
+    pub address: String,
+    pub connection_id: String,
+    pub ibc_transfer_channel: String,
+    /// Unset if the probe could not be queried, e.g. once migrated to an incompatible version
+    pub reachable: bool,
+    pub paused: bool,
+    pub ica_count: u32,
+    pub totals: ProbeTotals,
+}
+
+impl ProbeHealth {
+    /// A healthy probe can be queried & accepts new traffic
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.reachable && !self.paused
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct FleetHealthResponse {
+    /// The health of each instantiated probe, ordered by probe index
+    pub probes: Vec<ProbeHealth>,
+    /// The number of probes that are unreachable or paused
+    pub unhealthy: u32,
+    pub totals: ProbeTotals,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ErrorCodesResponse {
+    /// The code of every error the contract can return, as prefixed to its message
+    pub codes: Vec<ErrorCode>,
+}
+
+/// The subset of the `ibc-transfer-roundtrip` queries answered by every probe
+#[cw_serde]
+pub enum ProbeQueryMsg {
+    GlobalStats {},
+    Paused {},
+}
+
+/// The fields of the probe's `GlobalStatsResponse` read by the coordinator, any others are ignored.
+/// Probes must be built without the `camel-case` feature.
+#[derive(Deserialize)]
+pub struct ProbeGlobalStats {
+    pub ica_count: u32,
+    pub totals: ProbeTotals,
+}
+
+#[derive(Deserialize)]
+pub struct ProbePaused {
+    pub paused: bool,
+}
@@ -0,0 +1,321 @@
+//! Aggregates the failure alerts pushed by many `ibc-transfer-roundtrip` instances, each probing the IBC path it
+//! transfers over, showing how the example contracts compose: the watchtower subscribes to each probe that approved
+//! it, which executes the watchtower whenever one of its ICA txs fails.
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::missing_panics_doc,
+    clippy::missing_errors_doc,
+    clippy::needless_pass_by_value
+)]
+
+pub mod msgs;
+
+use common::{alerts::AlertSubscriptionMsg, debug};
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, Event, MessageInfo, Response, WasmMsg,
+};
+
+use crate::msgs::{
+    AlertKind, AlertsResponse, ErrorCodesResponse, ExecuteMsg, InstantiateMsg, ProbeAlert,
+    ProbeAlerts, QueryMsg,
+};
+
+/// The number of probes a watchtower can watch
+pub const MAX_WATCHED_PROBES: usize = 30;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("[E0001] {0}")]
+    CosmwasmStd(#[from] cosmwasm_std::StdError),
+    #[error("[E0400] unauthorized")]
+    Unauthorized,
+    #[error("[E0401] {probe} is not watched")]
+    UnwatchedProbe { probe: String },
+    #[error("[E0402] at most {MAX_WATCHED_PROBES} probes can be watched")]
+    TooManyProbes,
+}
+
+common::error_codes!(Error {
+    CosmwasmStd = 1,
+    Unauthorized = 400,
+    UnwatchedProbe = 401,
+    TooManyProbes = 402,
+});
+
+pub mod state {
+    use common::json_map;
+    use cw_storage_macros::item;
+
+    use crate::msgs::ProbeAlerts;
+
+    item!(owner! : String);
+
+    // a probe is watched for as long as it has an entry
+    json_map!(probe : str => alerts : ProbeAlerts);
+}
+
+#[entry_point]
+pub fn instantiate(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, Error> {
+    debug!(deps, "handling instantiate msg");
+
+    state::set_owner(deps.storage, info.sender.as_str());
+
+    let mut response = Response::default();
+
+    for probe in msg.probes {
+        let res = watch(deps.branch(), probe)?;
+
+        response = response
+            .add_submessages(res.messages)
+            .add_events(res.events);
+    }
+
+    Ok(response)
+}
+
+fn subscription_msg(probe: String, msg: &AlertSubscriptionMsg) -> Result<WasmMsg, Error> {
+    Ok(WasmMsg::Execute {
+        contract_addr: probe,
+        msg: to_binary(msg)?,
+        funds: vec![],
+    })
+}
+
+pub fn watch(deps: DepsMut, probe: String) -> Result<Response, Error> {
+    let probe = deps.api.addr_validate(&probe)?.into_string();
+
+    if state::probe_alerts(deps.storage, &probe).is_some() {
+        return Ok(Response::default());
+    }
+
+    if state::probe_alerts_range(deps.storage, None, MAX_WATCHED_PROBES).len() >= MAX_WATCHED_PROBES
+    {
+        return Err(Error::TooManyProbes);
+    }
+
+    let alerts = ProbeAlerts {
+        probe: probe.clone(),
+        ..ProbeAlerts::default()
+    };
+
+    state::set_probe_alerts(deps.storage, &probe, &alerts);
+
+    let event = Event::new("probe_watched").add_attribute("probe", probe.as_str());
+
+    let subscribe_msg = subscription_msg(probe, &AlertSubscriptionMsg::SubscribeAlerts {})?;
+
+    Ok(Response::default()
+        .add_message(subscribe_msg)
+        .add_event(event))
+}
+
+pub fn unwatch(deps: DepsMut, probe: String) -> Result<Response, Error> {
+    if state::probe_alerts(deps.storage, &probe).is_none() {
+        return Err(Error::UnwatchedProbe { probe });
+    }
+
+    state::remove_probe_alerts(deps.storage, &probe);
+
+    let event = Event::new("probe_unwatched").add_attribute("probe", probe.as_str());
+
+    let unsubscribe_msg = subscription_msg(probe, &AlertSubscriptionMsg::UnsubscribeAlerts {})?;
+
+    Ok(Response::default()
+        .add_message(unsubscribe_msg)
+        .add_event(event))
+}
+
+pub fn execute_probe_alert(
+    deps: DepsMut,
+    info: MessageInfo,
+    alert: ProbeAlert,
+) -> Result<Response, Error> {
+    let probe = info.sender.into_string();
+
+    debug!(deps, "received {:?} alert from {probe}", alert.kind);
+
+    let mut alerts =
+        state::probe_alerts(deps.storage, &probe).ok_or_else(|| Error::UnwatchedProbe {
+            probe: probe.clone(),
+        })?;
+
+    match alert.kind {
+        AlertKind::TxError => alerts.tx_errors += 1,
+        AlertKind::TxTimeout => alerts.tx_timeouts += 1,
+    }
+
+    if alert.channel_closed {
+        alerts.channels_closed += 1;
+    }
+
+    let event = Event::new("probe_alert")
+        .add_attribute("probe", probe.as_str())
+        .add_attribute("ica_idx", alert.ica_idx.to_string())
+        .add_attribute("owner", alert.owner.as_str())
+        .add_attribute("seq_num", alert.seq_num.to_string());
+
+    alerts.last_alert = Some(alert);
+
+    state::set_probe_alerts(deps.storage, &probe, &alerts);
+
+    Ok(Response::default().add_event(event))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, Error> {
+    debug!(deps, "handling execute msg");
+
+    match msg {
+        ExecuteMsg::Watch { probe } | ExecuteMsg::Unwatch { probe }
+            if info.sender.as_str() != state::owner(deps.storage) =>
+        {
+            debug!(
+                deps,
+                "{} cannot change whether {probe} is watched", info.sender
+            );
+
+            Err(Error::Unauthorized)
+        }
+
+        ExecuteMsg::Watch { probe } => watch(deps, probe),
+
+        ExecuteMsg::Unwatch { probe } => unwatch(deps, probe),
+
+        ExecuteMsg::ProbeAlert(alert) => execute_probe_alert(deps, info, alert),
+    }
+}
+
+#[must_use]
+pub fn alerts(deps: Deps) -> AlertsResponse {
+    let probes: Vec<ProbeAlerts> =
+        state::probe_alerts_range(deps.storage, None, MAX_WATCHED_PROBES)
+            .into_iter()
+            .map(|(_, alerts)| alerts)
+            .collect();
+
+    let total = probes
+        .iter()
+        .map(|alerts| alerts.tx_errors + alerts.tx_timeouts)
+        .sum();
+
+    AlertsResponse { probes, total }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, Error> {
+    let res = match msg {
+        QueryMsg::Alerts {} => to_binary(&alerts(deps))?,
+
+        QueryMsg::ErrorCodes {} => to_binary(&ErrorCodesResponse {
+            codes: Error::catalogue(),
+        })?,
+    };
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    use super::*;
+
+    fn alert(kind: AlertKind, channel_closed: bool) -> ProbeAlert {
+        ProbeAlert {
+            ica_idx: 0,
+            owner: "owner".to_owned(),
+            kind,
+            seq_num: 1,
+            error: None,
+            channel_closed,
+        }
+    }
+
+    #[test]
+    fn alerts_aggregated_across_watched_probes() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            probes: vec!["probe_a".to_owned(), "probe_b".to_owned()],
+        };
+
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        assert_eq!(res.messages.len(), 2, "every probe is subscribed to");
+
+        assert!(matches!(
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("someone", &[]),
+                ExecuteMsg::Watch {
+                    probe: "probe_c".to_owned()
+                }
+            ),
+            Err(Error::Unauthorized)
+        ));
+
+        assert!(matches!(
+            execute_probe_alert(
+                deps.as_mut(),
+                mock_info("probe_c", &[]),
+                alert(AlertKind::TxError, false)
+            ),
+            Err(Error::UnwatchedProbe { .. })
+        ));
+
+        for (probe, kind, channel_closed) in [
+            ("probe_a", AlertKind::TxError, false),
+            ("probe_a", AlertKind::TxTimeout, true),
+            ("probe_b", AlertKind::TxTimeout, false),
+        ] {
+            execute_probe_alert(
+                deps.as_mut(),
+                mock_info(probe, &[]),
+                alert(kind, channel_closed),
+            )
+            .unwrap();
+        }
+
+        let AlertsResponse { probes, total } = alerts(deps.as_ref());
+
+        assert_eq!(total, 3);
+
+        assert_eq!(
+            probes[0],
+            ProbeAlerts {
+                probe: "probe_a".to_owned(),
+                tx_errors: 1,
+                tx_timeouts: 1,
+                channels_closed: 1,
+                last_alert: Some(alert(AlertKind::TxTimeout, true)),
+            }
+        );
+
+        assert_eq!(probes[1].tx_timeouts, 1);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::Unwatch {
+                probe: "probe_a".to_owned(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(alerts(deps.as_ref()).total, 1);
+    }
+}
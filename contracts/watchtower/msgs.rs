@@ -0,0 +1,60 @@
+use cosmwasm_schema::cw_serde;
+
+pub use common::{
+    alerts::{AlertKind, ProbeAlert},
+    error_codes::ErrorCode,
+};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The `ibc-transfer-roundtrip` instances to watch, subscribed to at instantiation, each probe's owner must have
+    /// approved the watchtower with `ApproveAlertSubscriber` beforehand
+    pub probes: Vec<String>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Subscribe to the alerts of `probe`, which owner must have approved the watchtower with `ApproveAlertSubscriber`,
+    /// only callable by the contract owner
+    Watch { probe: String },
+    /// Unsubscribe from the alerts of `probe` & drop its alerts, only callable by the contract owner
+    Unwatch { probe: String },
+    /// An alert pushed by a watched probe
+    ProbeAlert(ProbeAlert),
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Query the alerts aggregated across the watched probes
+    Alerts {},
+    /// Query the code of every error, surfaced in error messages as `[E0123]`
+    ErrorCodes {},
+}
+
+/// The alerts pushed by a probe
+#[cw_serde]
+#[derive(Default)]
+pub struct ProbeAlerts {
+    pub probe: String,
+    pub tx_errors: u32,
+    pub tx_timeouts: u32,
+    /// The number of timeouts that closed an ICA channel
+    pub channels_closed: u32,
+    pub last_alert: Option<ProbeAlert>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AlertsResponse {
+    /// The alerts of each watched probe, ordered by probe address
+    pub probes: Vec<ProbeAlerts>,
+    /// The number of alerts pushed by all watched probes
+    pub total: u32,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ErrorCodesResponse {
+    /// The code of every error the contract can return, as prefixed to its message
+    pub codes: Vec<ErrorCode>,
+}
@@ -0,0 +1,41 @@
+//! The failure alerts a probe contract pushes to the contracts subscribed to it, e.g. the watchtower example.
+//!
+//! A subscriber manages its subscription by executing [`AlertSubscriptionMsg`] on the probe & receives alerts as
+//! [`AlertHookMsg`], which it must accept as a variant of its own `ExecuteMsg`.
+
+/// The kind of failure of an ICA tx issued by a probe
+#[cosmwasm_schema::cw_serde]
+#[derive(Copy)]
+pub enum AlertKind {
+    /// The tx was acknowledged with an error
+    TxError,
+    /// The tx timed out
+    TxTimeout,
+}
+
+#[cosmwasm_schema::cw_serde]
+pub struct ProbeAlert {
+    pub ica_idx: u32,
+    /// The owner of the ICA that issued the tx
+    pub owner: String,
+    pub kind: AlertKind,
+    /// The sequence number of the failed tx
+    pub seq_num: u64,
+    /// The acknowledged error of failed txs
+    pub error: Option<String>,
+    /// Set if the timeout closed the ICA channel
+    pub channel_closed: bool,
+}
+
+/// The msg executed on the subscribers of a probe
+#[cosmwasm_schema::cw_serde]
+pub enum AlertHookMsg {
+    ProbeAlert(ProbeAlert),
+}
+
+/// The msgs of a probe's `ExecuteMsg` managing the subscription of the sender
+#[cosmwasm_schema::cw_serde]
+pub enum AlertSubscriptionMsg {
+    SubscribeAlerts {},
+    UnsubscribeAlerts {},
+}
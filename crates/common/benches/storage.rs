@@ -0,0 +1,110 @@
+//! Native benchmarks of the storage encodings, run with `cargo bench -p common`.
+//!
+//! Covers key formatting along with the generated get/set functions of both the `cw-storage-macros`
+//! primitive maps & the JSON maps, so changes to key encoding can be compared against a saved baseline
+//! (`cargo bench -p common -- --save-baseline before` then `--baseline before`).
+
+use common::storage::{map_key, namespaced_key};
+use cosmwasm_std::{testing::MockStorage, Coin};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+mod state {
+    use common::json_map;
+    use cosmwasm_std::Coin;
+    use cw_storage_macros::map;
+
+    map!(ica: u32 => icq_id : u64);
+    map!(owner: String => ica_idx : u32);
+
+    json_map!(ica: u32 => deposit : Coin);
+}
+
+const ENTRIES: u32 = 1_000;
+
+const OWNER: &str = "neutron1m9l358xunhhwds0568za49mzhvuxx9ux8xafx2";
+
+fn populated_storage() -> MockStorage {
+    let mut storage = MockStorage::new();
+
+    for idx in 0..ENTRIES {
+        state::set_ica_icq_id(&mut storage, &idx, u64::from(idx));
+        state::set_owner_ica_idx(&mut storage, &format!("{OWNER}{idx}"), idx);
+        state::set_ica_deposit(&mut storage, &idx, &Coin::new(idx.into(), "untrn"));
+    }
+
+    storage
+}
+
+fn key_formatting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("key_formatting");
+
+    group.bench_function("namespaced_key", |b| {
+        b.iter(|| namespaced_key(black_box("fee_history"), black_box(&[])));
+    });
+
+    group.bench_function("map_key/u32", |b| {
+        b.iter(|| map_key(black_box("ica_deposit"), black_box(&42u32)));
+    });
+
+    group.bench_function("map_key/str", |b| {
+        b.iter(|| map_key(black_box("owner_ica_idx"), black_box(OWNER)));
+    });
+
+    group.finish();
+}
+
+fn primitive_map_fns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("primitive_map");
+
+    let storage = populated_storage();
+
+    group.bench_function("get/u32", |b| {
+        b.iter(|| state::ica_icq_id(&storage, black_box(&(ENTRIES / 2))));
+    });
+
+    let owner = format!("{OWNER}{}", ENTRIES / 2);
+
+    group.bench_function("get/string", |b| {
+        b.iter(|| state::owner_ica_idx(&storage, black_box(&owner)));
+    });
+
+    group.bench_function("set/u32", |b| {
+        b.iter_batched_ref(
+            MockStorage::new,
+            |storage| state::set_ica_icq_id(storage, black_box(&7), black_box(7)),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
+fn json_map_fns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("json_map");
+
+    let storage = populated_storage();
+
+    group.bench_function("get", |b| {
+        b.iter(|| state::ica_deposit(&storage, black_box(&(ENTRIES / 2))));
+    });
+
+    group.bench_function("set", |b| {
+        let deposit = Coin::new(1_000, "untrn");
+
+        b.iter_batched_ref(
+            MockStorage::new,
+            |storage| state::set_ica_deposit(storage, black_box(&7), black_box(&deposit)),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("range/100", |b| {
+        b.iter(|| state::ica_deposit_range(&storage, black_box(Some(&10)), 100));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, key_formatting, primitive_map_fns, json_map_fns);
+
+criterion_main!(benches);
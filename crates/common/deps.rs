@@ -0,0 +1,128 @@
+//! The neutron queries made by the helpers of this crate, abstracted over the custom query type of the querier.
+//!
+//! `neutron_sdk`'s query functions take a `Deps<NeutronQuery>`, while helpers generic over `impl CustomQuery`
+//! cannot issue neutron's custom queries at all. [`NeutronDeps`] is implemented for the `Deps` of any custom
+//! query type that can carry a `NeutronQuery`, so the same helpers serve the production bindings, a
+//! `MockQuerier<NeutronQuery>` & multitest apps wrapping `NeutronQuery` in their own query type.
+
+use cosmwasm_std::{Binary, Coin, CustomQuery, Deps, QueryRequest, StdResult, Uint64};
+use neutron_sdk::{
+    bindings::{
+        msg::IbcFee,
+        query::{NeutronQuery, QueryRegisteredQueryResponse, QueryRegisteredQueryResultResponse},
+        types::RegisteredQuery,
+    },
+    interchain_queries::types::KVReconstruct,
+    query::min_ibc_fee::MinIbcFeeResponse,
+    NeutronResult,
+};
+
+/// The parameters of the interchainqueries module
+#[cosmwasm_schema::cw_serde]
+pub struct IcqParams {
+    /// The number of blocks after which an ICQ not updated can be removed by anyone
+    pub query_submit_timeout: Uint64,
+    /// The deposit required to register an ICQ, refunded once the ICQ is removed
+    pub query_deposit: Vec<Coin>,
+    /// The max number of tx ICQ results removed along with the ICQ
+    pub tx_query_removal_limit: Uint64,
+}
+
+/// The parameters of the interchaintxs module
+#[cosmwasm_schema::cw_serde]
+pub struct IcaTxParams {
+    /// The max number of messages a single `MsgSubmitTx` can execute
+    pub msg_submit_tx_max_messages: Uint64,
+}
+
+/// The neutron queries needed by the helpers of this crate
+pub trait NeutronDeps: Copy {
+    /// The min fee to attach to IBC transfers & ICA txs
+    fn min_ibc_fee(self) -> StdResult<IbcFee>;
+
+    fn icq_params(self) -> StdResult<IcqParams>;
+
+    fn ica_tx_params(self) -> StdResult<IcaTxParams>;
+
+    /// The registration of the ICQ `query_id`, as held by the ICQ module
+    fn registered_query(self, query_id: u64) -> StdResult<RegisteredQuery>;
+
+    /// The raw result last submitted for the KV ICQ `query_id`
+    fn registered_query_result(
+        self,
+        query_id: u64,
+    ) -> StdResult<QueryRegisteredQueryResultResponse>;
+
+    /// The result last submitted for the KV ICQ `query_id`, reconstructed as `T`
+    fn kv_result<T: KVReconstruct>(self, query_id: u64) -> NeutronResult<T> {
+        let res = self.registered_query_result(query_id)?;
+
+        T::reconstruct(&res.result.kv_results)
+    }
+}
+
+impl<Q> NeutronDeps for Deps<'_, Q>
+where
+    Q: CustomQuery + From<NeutronQuery>,
+{
+    fn min_ibc_fee(self) -> StdResult<IbcFee> {
+        let query = Q::from(NeutronQuery::MinIbcFee {});
+
+        let res: MinIbcFeeResponse = self.querier.query(&QueryRequest::Custom(query))?;
+
+        Ok(res.min_fee)
+    }
+
+    fn icq_params(self) -> StdResult<IcqParams> {
+        #[cosmwasm_schema::cw_serde]
+        struct QueryParamsResponse {
+            params: IcqParams,
+        }
+
+        let res: QueryParamsResponse = self.querier.query(&QueryRequest::Stargate {
+            path: "/neutron.interchainqueries.Query/Params".to_owned(),
+            data: Binary(vec![]),
+        })?;
+
+        Ok(res.params)
+    }
+
+    fn ica_tx_params(self) -> StdResult<IcaTxParams> {
+        // the module params include more than the ones read, such as the ICA registration fee
+        #[derive(serde::Deserialize)]
+        struct Params {
+            msg_submit_tx_max_messages: Uint64,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct QueryParamsResponse {
+            params: Params,
+        }
+
+        let res: QueryParamsResponse = self.querier.query(&QueryRequest::Stargate {
+            path: "/neutron.interchaintxs.Query/Params".to_owned(),
+            data: Binary(vec![]),
+        })?;
+
+        Ok(IcaTxParams {
+            msg_submit_tx_max_messages: res.params.msg_submit_tx_max_messages,
+        })
+    }
+
+    fn registered_query(self, query_id: u64) -> StdResult<RegisteredQuery> {
+        let query = Q::from(NeutronQuery::RegisteredInterchainQuery { query_id });
+
+        let res: QueryRegisteredQueryResponse = self.querier.query(&QueryRequest::Custom(query))?;
+
+        Ok(res.registered_query)
+    }
+
+    fn registered_query_result(
+        self,
+        query_id: u64,
+    ) -> StdResult<QueryRegisteredQueryResultResponse> {
+        let query = Q::from(NeutronQuery::InterchainQueryResult { query_id });
+
+        self.querier.query(&QueryRequest::Custom(query))
+    }
+}
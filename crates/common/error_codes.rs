@@ -0,0 +1,44 @@
+//! Stable numeric codes of contract errors, surfaced in their messages as `[E0123]` so that frontends can
+//! translate & branch on failures without matching on strings.
+//!
+//! Codes are never reused or renumbered. `E0001`-`E0099` are the errors of this crate wrapped by the contracts,
+//! each contract numbers its own errors in a range of its own & reuses the code of an error it shares with
+//! another contract.
+
+/// An entry of the error code catalogue of a contract
+#[cosmwasm_schema::cw_serde]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ErrorCode {
+    pub code: u16,
+    /// The name of the error variant
+    pub name: String,
+}
+
+/// Map the variants of the `$error` enum to their codes, which must match the `[E0123]` prefix of their messages.
+///
+/// Generates `$error::code` & `$error::catalogue`, the mapping must be exhaustive.
+#[macro_export]
+macro_rules! error_codes {
+    ($error:ident { $($variant:ident = $code:literal),+ $(,)? }) => {
+        impl $error {
+            /// The stable code of the error, the `[E0123]` prefix of its message
+            #[must_use]
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(Self::$variant { .. } => $code,)+
+                }
+            }
+
+            /// The code of every error, in declaration order
+            #[must_use]
+            pub fn catalogue() -> Vec<$crate::error_codes::ErrorCode> {
+                vec![
+                    $($crate::error_codes::ErrorCode {
+                        code: $code,
+                        name: stringify!($variant).to_owned(),
+                    },)+
+                ]
+            }
+        }
+    };
+}
@@ -0,0 +1,119 @@
+//! Instantiation of child contracts by submessage, capturing the address of each child from the instantiate reply.
+//!
+//! ```ignore
+//! let (idx, msg) = factory::instantiate_child(storage, instantiate_msg, &params, |idx| ReplyKind::Child { idx })?;
+//!
+//! // in the reply entry point, for replies with a `ReplyKind::Child { idx }` payload
+//! let child: Child<Params> = factory::register_child(storage, idx, reply)?;
+//! ```
+//!
+//! Children are stored with the parameters they were instantiated with, indexed in instantiation order.
+
+use cosmwasm_std::{Reply, StdError, SubMsg, WasmMsg};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    reply::{parse_instantiate_reply, save_reply_payload},
+    storage::{self, namespaced_key, Storage},
+    ParseReplyError,
+};
+
+const CHILDREN_NAMESPACE: &str = "factory_children";
+const CHILD_COUNT_NAMESPACE: &str = "factory_child_count";
+
+/// A contract instantiated by the factory
+#[cosmwasm_schema::cw_serde]
+pub struct Child<T> {
+    pub idx: u32,
+    pub params: T,
+    /// Set once the instantiate reply is received
+    pub address: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FactoryError {
+    #[error(transparent)]
+    ParseReply(#[from] ParseReplyError),
+    #[error("unexpected instantiate reply {id}")]
+    UnexpectedReply { id: u64 },
+}
+
+/// The number of children instantiated so far
+#[must_use]
+pub fn child_count(storage: &dyn Storage) -> u32 {
+    storage::load(storage, &namespaced_key(CHILD_COUNT_NAMESPACE, &[])).unwrap_or_default()
+}
+
+#[must_use]
+pub fn child<T: DeserializeOwned>(storage: &dyn Storage, idx: u32) -> Option<Child<T>> {
+    storage::load(storage, &storage::map_key(CHILDREN_NAMESPACE, &idx))
+}
+
+/// Iterate the children in instantiation order, starting after `start_after`
+#[must_use]
+pub fn children<T: DeserializeOwned>(
+    storage: &dyn Storage,
+    start_after: Option<u32>,
+    limit: usize,
+) -> Vec<Child<T>> {
+    storage::range(storage, CHILDREN_NAMESPACE, start_after.as_ref(), limit)
+        .into_iter()
+        .map(|(_, child)| child)
+        .collect()
+}
+
+/// Register a child instantiated with `params` & wrap `instantiate_msg` in a submessage replying on success,
+/// recording the payload `reply_payload` builds from the child's index against the reply id with [`save_reply_payload`].
+/// The reply must be passed to [`register_child`] with the index taken back from the payload
+pub fn instantiate_child<T: Serialize, P: Serialize>(
+    storage: &mut dyn Storage,
+    instantiate_msg: WasmMsg,
+    params: &T,
+    reply_payload: impl FnOnce(u32) -> P,
+) -> Result<(u32, SubMsg), StdError> {
+    if !matches!(instantiate_msg, WasmMsg::Instantiate { .. }) {
+        return Err(StdError::generic_err(
+            "only instantiate msgs create children",
+        ));
+    }
+
+    let idx = child_count(storage);
+
+    storage::save(
+        storage,
+        &namespaced_key(CHILD_COUNT_NAMESPACE, &[]),
+        &(idx + 1),
+    );
+
+    let child = Child {
+        idx,
+        params,
+        address: None,
+    };
+
+    storage::save(storage, &storage::map_key(CHILDREN_NAMESPACE, &idx), &child);
+
+    let reply_id = save_reply_payload(storage, &reply_payload(idx));
+
+    let msg = SubMsg::reply_on_success(instantiate_msg, reply_id);
+
+    Ok((idx, msg))
+}
+
+/// Record the address of the child `idx` instantiated by the submessage `reply` is for, which must have been sent by
+/// [`instantiate_child`]
+pub fn register_child<T: Serialize + DeserializeOwned>(
+    storage: &mut dyn Storage,
+    idx: u32,
+    reply: Reply,
+) -> Result<Child<T>, FactoryError> {
+    let mut child = child::<T>(storage, idx)
+        .filter(|child| child.address.is_none())
+        .ok_or(FactoryError::UnexpectedReply { id: reply.id })?;
+
+    child.address = Some(parse_instantiate_reply(reply)?);
+
+    storage::save(storage, &storage::map_key(CHILDREN_NAMESPACE, &idx), &child);
+
+    Ok(child)
+}
@@ -0,0 +1,77 @@
+//! Arithmetic over lists of coins holding at most one coin per denom.
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Coin, Uint128};
+
+/// Format `coins` as a comma separated event attribute value
+#[must_use]
+pub fn coins_attr(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The amounts of each denom missing from `funds` to cover the `required` coins
+#[must_use]
+pub fn coins_shortfall(funds: &[Coin], required: &[Coin]) -> Vec<Coin> {
+    let mut required_by_denom: BTreeMap<&str, Uint128> = BTreeMap::new();
+
+    for coin in required {
+        *required_by_denom.entry(coin.denom.as_str()).or_default() += coin.amount;
+    }
+
+    let required = required_by_denom;
+
+    required
+        .into_iter()
+        .filter_map(|(denom, required_amount)| {
+            let attached_amount: Uint128 = funds
+                .iter()
+                .filter(|c| c.denom == denom)
+                .map(|c| c.amount)
+                .sum();
+
+            (attached_amount < required_amount).then(|| Coin {
+                denom: denom.to_owned(),
+                amount: required_amount - attached_amount,
+            })
+        })
+        .collect()
+}
+
+/// Merge `add` into `coins`, keeping a single coin per denom
+pub fn add_coins(coins: &mut Vec<Coin>, add: &[Coin]) {
+    for coin in add {
+        if let Some(existing) = coins.iter_mut().find(|c| c.denom == coin.denom) {
+            existing.amount += coin.amount;
+        } else {
+            coins.push(coin.clone());
+        }
+    }
+}
+
+/// Subtract `amounts` from the merged `coins`, `None` if they don't cover the amounts
+#[must_use]
+pub fn subtract_coins(coins: &[Coin], amounts: &[Coin]) -> Option<Vec<Coin>> {
+    if !coins_shortfall(coins, amounts).is_empty() {
+        return None;
+    }
+
+    let mut remaining = coins.to_vec();
+
+    for amount in amounts {
+        let coin = remaining
+            .iter_mut()
+            .find(|c| c.denom == amount.denom)
+            .expect("denoms are covered");
+
+        coin.amount -= amount.amount;
+    }
+
+    remaining.retain(|c| !c.amount.is_zero());
+
+    Some(remaining)
+}
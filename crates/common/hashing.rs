@@ -0,0 +1,98 @@
+//! Hashing of the parts identifying issued txs & retrievals, the resulting hashes are used as storage keys.
+//!
+//! A [`HashScheme`] pairs a framing, a digest & an encoding under a version byte. The version is stored alongside
+//! persisted hashes, so the scheme in use can change without losing track of hashes issued under an older one.
+
+use cosmwasm_std::Binary;
+
+/// How the domain & parts are laid out before hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// The parts are concatenated as is & the domain is ignored, ambiguous if several parts vary in length
+    Concat,
+    /// The domain & each part are prefixed by their big-endian `u32` length
+    LengthPrefixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Digest {
+    Sha256,
+    /// SHA-512 truncated to its first 256 bits
+    Sha512Trunc256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    HexUpper,
+    /// Standard padded base64, 44 characters for a 256 bit digest rather than 64 hex characters
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashScheme {
+    pub version: u8,
+    pub framing: Framing,
+    pub digest: Digest,
+    pub encoding: Encoding,
+}
+
+impl HashScheme {
+    /// Plain SHA-256 encoded as upper case hex, the scheme of every hash stored before versioning
+    pub const V0: Self = Self {
+        version: 0,
+        framing: Framing::Concat,
+        digest: Digest::Sha256,
+        encoding: Encoding::HexUpper,
+    };
+
+    /// SHA-256 encoded as base64 for shorter keys & hook payloads
+    pub const V1: Self = Self {
+        version: 1,
+        framing: Framing::Concat,
+        digest: Digest::Sha256,
+        encoding: Encoding::Base64,
+    };
+
+    /// Domain separated, length prefixed SHA-256 encoded as upper case hex
+    pub const V2: Self = Self {
+        version: 2,
+        framing: Framing::LengthPrefixed,
+        digest: Digest::Sha256,
+        encoding: Encoding::HexUpper,
+    };
+
+    pub const ALL: [Self; 3] = [Self::V0, Self::V1, Self::V2];
+
+    #[must_use]
+    pub fn from_version(version: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.version == version)
+    }
+
+    /// Hash `parts` within `domain`, so that equal parts hashed for different purposes never collide
+    #[must_use]
+    pub fn hash(&self, domain: &str, parts: &[&[u8]]) -> String {
+        let msg = match self.framing {
+            Framing::Concat => parts.concat(),
+            Framing::LengthPrefixed => std::iter::once(domain.as_bytes())
+                .chain(parts.iter().copied())
+                .flat_map(|part| {
+                    let len = u32::try_from(part.len()).expect("parts shorter than u32::MAX");
+
+                    [len.to_be_bytes().as_slice(), part].concat()
+                })
+                .collect(),
+        };
+
+        let digest = match self.digest {
+            Digest::Sha256 => hmac_sha256::Hash::hash(&msg).to_vec(),
+            Digest::Sha512Trunc256 => hmac_sha512::Hash::hash(&msg)[..32].to_vec(),
+        };
+
+        match self.encoding {
+            Encoding::HexUpper => hex::encode_upper(digest),
+            Encoding::Base64 => Binary::from(digest).to_base64(),
+        }
+    }
+}
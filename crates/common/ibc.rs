@@ -0,0 +1,398 @@
+//! Protobuf encodings of IBC messages for use in ICA txs & checks of the IBC connections & channels they go through.
+
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{Addr, Coin, CustomQuery, Deps, QueryRequest, StdError, StdResult};
+use neutron_sdk::bindings::types::ProtobufAny;
+use prost::Message;
+use serde::Serialize;
+
+use crate::validate::{validate_built_memo, MemoLimits, ValidationError};
+
+/// The protobuf encoding of a `cosmos.base.v1beta1.Coin`
+#[derive(Clone, PartialEq, Message)]
+pub struct RawCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+impl From<Coin> for RawCoin {
+    fn from(value: Coin) -> Self {
+        Self {
+            denom: value.denom,
+            amount: value.amount.to_string(),
+        }
+    }
+}
+
+impl RawCoin {
+    /// `None` if the amount is not a valid integer
+    #[must_use]
+    pub fn to_coin(self) -> Option<Coin> {
+        Some(Coin::new(self.amount.parse().ok()?, self.denom))
+    }
+}
+
+/// An intermediate chain an ICS-20 transfer is forwarded through by packet-forward-middleware
+#[cosmwasm_schema::cw_serde]
+pub struct ForwardHop {
+    /// The address receiving the transfer on the intermediate chain, the forwarded transfer is sent from it
+    pub receiver: String,
+    /// The channel on the intermediate chain the transfer is forwarded through
+    pub channel: String,
+}
+
+/// Build an ICS-20 `MsgTransfer` of `token` to `recipient`, executing `msg` on it via an IBC hook on arrival
+pub fn make_ibc_transfer_with_hook_msg<Msg: Serialize>(
+    source_channel: String,
+    token: Coin,
+    sender: String,
+    timeout_timestamp: u64,
+    recipient: Addr,
+    msg: Msg,
+    limits: &MemoLimits,
+) -> Result<ProtobufAny, ValidationError> {
+    make_ibc_transfer_via_hops_with_hook_msg(
+        source_channel,
+        token,
+        sender,
+        timeout_timestamp,
+        &[],
+        recipient,
+        msg,
+        limits,
+    )
+}
+
+/// Build an ICS-20 `MsgTransfer` of `token` forwarded through each of `hops` in order by packet-forward-middleware
+/// before arriving at `recipient`, executing `msg` on it via an IBC hook on arrival.
+/// The hook memo & the memo of the transfer are checked against `limits` before the transfer is built.
+#[allow(clippy::too_many_arguments)]
+pub fn make_ibc_transfer_via_hops_with_hook_msg<Msg: Serialize>(
+    source_channel: String,
+    token: Coin,
+    sender: String,
+    timeout_timestamp: u64,
+    hops: &[ForwardHop],
+    recipient: Addr,
+    msg: Msg,
+    limits: &MemoLimits,
+) -> Result<ProtobufAny, ValidationError> {
+    #[derive(Clone, PartialEq, Message)]
+    struct Height {
+        #[prost(uint64, tag = "1")]
+        pub revision_number: u64,
+        #[prost(uint64, tag = "2")]
+        pub revision_height: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgTransfer {
+        #[prost(string, tag = "1")]
+        pub source_port: String,
+        #[prost(string, tag = "2")]
+        pub source_channel: String,
+        #[prost(message, optional, tag = "3")]
+        pub token: Option<RawCoin>,
+        #[prost(string, tag = "4")]
+        pub sender: String,
+        #[prost(string, tag = "5")]
+        pub receiver: String,
+        #[prost(message, optional, tag = "6")]
+        pub timeout_height: Option<Height>,
+        #[prost(uint64, tag = "7")]
+        pub timeout_timestamp: u64,
+        #[prost(string, tag = "8")]
+        pub memo: String,
+    }
+
+    #[derive(Serialize)]
+    struct IbcHookWasm<Msg> {
+        contract: String,
+        msg: Msg,
+    }
+
+    #[derive(Serialize)]
+    struct IbcHookMemo<Msg> {
+        wasm: IbcHookWasm<Msg>,
+    }
+
+    #[derive(Serialize)]
+    struct PfmForward<'a> {
+        receiver: &'a str,
+        port: &'a str,
+        channel: &'a str,
+        // packet-forward-middleware accepts the memo of the forwarded transfer as a JSON string
+        next: String,
+    }
+
+    #[derive(Serialize)]
+    struct PfmMemo<'a> {
+        forward: PfmForward<'a>,
+    }
+
+    let ibc_hook = IbcHookMemo {
+        wasm: IbcHookWasm {
+            contract: recipient.clone().into_string(),
+            msg,
+        },
+    };
+
+    let mut memo = serde_json_wasm::to_string(&ibc_hook).expect("infallible serialization");
+
+    validate_built_memo(&memo, limits.max_hook_memo_len)?;
+
+    let mut receiver = recipient.into_string();
+
+    // wrap the memo of each forwarded transfer, starting from the last hop
+    for hop in hops.iter().rev() {
+        let pfm = PfmMemo {
+            forward: PfmForward {
+                receiver: &receiver,
+                port: "transfer",
+                channel: &hop.channel,
+                next: memo,
+            },
+        };
+
+        memo = serde_json_wasm::to_string(&pfm).expect("infallible serialization");
+
+        receiver = hop.receiver.clone();
+    }
+
+    validate_built_memo(&memo, limits.max_memo_len)?;
+
+    let transfer_msg = MsgTransfer {
+        source_port: "transfer".to_owned(),
+        source_channel,
+        token: Some(token.into()),
+        sender,
+        receiver,
+        timeout_height: None,
+        timeout_timestamp,
+        memo,
+    };
+
+    Ok(ProtobufAny {
+        type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_owned(),
+        value: transfer_msg.encode_to_vec().into(),
+    })
+}
+
+/// The domain ibc-hooks hashes the channel & original sender of a packet under to derive its intermediary sender
+const IBC_HOOKS_SENDER_DOMAIN: &str = "ibc-wasm-hook-intermediary";
+
+/// The address ibc-hooks executes a wasm hook as for a packet received on the local `channel` from `original_sender`,
+/// `sha256(sha256(domain) || "{channel}/{original_sender}")` encoded under the local `bech32_prefix`
+#[must_use]
+pub fn ibc_hooks_sender(channel: &str, original_sender: &str, bech32_prefix: &str) -> String {
+    let mut hasher = hmac_sha256::Hash::new();
+
+    hasher.update(hmac_sha256::Hash::hash(IBC_HOOKS_SENDER_DOMAIN.as_bytes()));
+
+    hasher.update(format!("{channel}/{original_sender}"));
+
+    bech32::encode(
+        bech32_prefix,
+        hasher.finalize().to_base32(),
+        Variant::Bech32,
+    )
+    .expect("valid bech32 prefix")
+}
+
+/// The domain packet-forward-middleware hashes the channel & original sender of a packet under to derive the
+/// receiver it forwards the packet from
+const PFM_RECEIVER_DOMAIN: &str = "packetfwd";
+
+/// The address packet-forward-middleware receives & forwards a packet as, for a packet received on the intermediate
+/// chain's `channel` from `original_sender`, overriding the packet's receiver. The first 20 bytes of
+/// `sha256(sha256(domain) || "{channel}/{original_sender}")` encoded under the intermediate chain's `bech32_prefix`
+#[must_use]
+pub fn pfm_receiver(channel: &str, original_sender: &str, bech32_prefix: &str) -> String {
+    let mut hasher = hmac_sha256::Hash::new();
+
+    hasher.update(hmac_sha256::Hash::hash(PFM_RECEIVER_DOMAIN.as_bytes()));
+
+    hasher.update(format!("{channel}/{original_sender}"));
+
+    bech32::encode(
+        bech32_prefix,
+        hasher.finalize()[..20].to_base32(),
+        Variant::Bech32,
+    )
+    .expect("valid bech32 prefix")
+}
+
+/// The `ibc/{hash}` denom of the vouchers of `base_denom` received over the `{port}/{channel}` hops of `path`
+#[must_use]
+pub fn ics20_voucher_denom(path: &str, base_denom: &str) -> String {
+    let hash = hmac_sha256::Hash::hash(format!("{path}/{base_denom}").as_bytes());
+
+    format!("ibc/{}", hex::encode_upper(hash))
+}
+
+/// The ICS-20 trace of a voucher denom
+#[cosmwasm_schema::cw_serde]
+pub struct DenomTrace {
+    /// The `{port}/{channel}` hops the tokens took to reach this chain, most recent first
+    pub path: String,
+    /// The denom of the tokens on the chain they originate from
+    pub base_denom: String,
+}
+
+/// Query the ICS-20 trace of the `ibc/{hash}` voucher `denom` from the transfer module
+pub fn query_denom_trace(deps: Deps<impl CustomQuery>, denom: &str) -> StdResult<DenomTrace> {
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryDenomTraceRequest {
+        #[prost(string, tag = "1")]
+        pub hash: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct QueryDenomTraceResponse {
+        denom_trace: DenomTrace,
+    }
+
+    let hash = denom
+        .strip_prefix("ibc/")
+        .ok_or_else(|| StdError::generic_err(format!("{denom} is not an ICS-20 voucher")))?;
+
+    let request = QueryDenomTraceRequest {
+        hash: hash.to_owned(),
+    };
+
+    let res: QueryDenomTraceResponse = deps.querier.query(&QueryRequest::Stargate {
+        path: "/ibc.applications.transfer.v1.Query/DenomTrace".to_owned(),
+        data: request.encode_to_vec().into(),
+    })?;
+
+    Ok(res.denom_trace)
+}
+
+/// The state of IBC connections & channels, as named by `ibc-go`
+pub const STATE_OPEN: &str = "STATE_OPEN";
+
+#[derive(Debug, thiserror::Error)]
+pub enum IbcStateError {
+    #[error("connection {connection_id} could not be found: {source}")]
+    ConnectionNotFound {
+        connection_id: String,
+        source: StdError,
+    },
+    #[error("connection {connection_id} is not open, its state is {state}")]
+    ConnectionNotOpen {
+        connection_id: String,
+        state: String,
+    },
+    #[error("channel {channel_id} on port {port_id} could not be found: {source}")]
+    ChannelNotFound {
+        port_id: String,
+        channel_id: String,
+        source: StdError,
+    },
+    #[error("channel {channel_id} on port {port_id} is not open, its state is {state}")]
+    ChannelNotOpen {
+        port_id: String,
+        channel_id: String,
+        state: String,
+    },
+}
+
+/// Ensure the IBC connection `connection_id` exists on this chain & is open
+pub fn ensure_connection_open(
+    deps: Deps<impl CustomQuery>,
+    connection_id: &str,
+) -> Result<(), IbcStateError> {
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryConnectionRequest {
+        #[prost(string, tag = "1")]
+        pub connection_id: String,
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    struct ConnectionEnd {
+        state: String,
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    struct QueryConnectionResponse {
+        connection: ConnectionEnd,
+    }
+
+    let request = QueryConnectionRequest {
+        connection_id: connection_id.to_owned(),
+    };
+
+    let res: QueryConnectionResponse = deps
+        .querier
+        .query(&QueryRequest::Stargate {
+            path: "/ibc.core.connection.v1.Query/Connection".to_owned(),
+            data: request.encode_to_vec().into(),
+        })
+        .map_err(|source| IbcStateError::ConnectionNotFound {
+            connection_id: connection_id.to_owned(),
+            source,
+        })?;
+
+    if res.connection.state != STATE_OPEN {
+        return Err(IbcStateError::ConnectionNotOpen {
+            connection_id: connection_id.to_owned(),
+            state: res.connection.state,
+        });
+    }
+
+    Ok(())
+}
+
+/// Ensure the IBC channel `channel_id` bound to `port_id` exists on this chain & is open
+pub fn ensure_channel_open(
+    deps: Deps<impl CustomQuery>,
+    port_id: &str,
+    channel_id: &str,
+) -> Result<(), IbcStateError> {
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryChannelRequest {
+        #[prost(string, tag = "1")]
+        pub port_id: String,
+        #[prost(string, tag = "2")]
+        pub channel_id: String,
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    struct Channel {
+        state: String,
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    struct QueryChannelResponse {
+        channel: Channel,
+    }
+
+    let request = QueryChannelRequest {
+        port_id: port_id.to_owned(),
+        channel_id: channel_id.to_owned(),
+    };
+
+    let res: QueryChannelResponse = deps
+        .querier
+        .query(&QueryRequest::Stargate {
+            path: "/ibc.core.channel.v1.Query/Channel".to_owned(),
+            data: request.encode_to_vec().into(),
+        })
+        .map_err(|source| IbcStateError::ChannelNotFound {
+            port_id: port_id.to_owned(),
+            channel_id: channel_id.to_owned(),
+            source,
+        })?;
+
+    if res.channel.state != STATE_OPEN {
+        return Err(IbcStateError::ChannelNotOpen {
+            port_id: port_id.to_owned(),
+            channel_id: channel_id.to_owned(),
+            state: res.channel.state,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Interchain account registration helpers & the lifecycle shared by the example contracts.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct OpenAckVersion {
+    pub version: String,
+    pub controller_connection_id: String,
+    pub host_connection_id: String,
+    pub address: String,
+    pub encoding: String,
+    pub tx_type: String,
+}
+
+/// The lifecycle of an ICA slot
+#[cosmwasm_schema::cw_serde]
+#[derive(Copy, Default)]
+pub enum IcaStatus {
+    /// No ICA has been setup in the slot
+    #[default]
+    Unregistered,
+    /// The ICA registration has been issued & is awaiting the channel open ack
+    Registering,
+    /// The ICA channel is open & the ICA can issue txs
+    Active,
+    /// The ordered ICA channel was closed by a timed out ICA tx
+    ChannelClosed,
+    /// A new channel is being opened for the ICA after its channel was closed
+    Reopening,
+    /// The ICA has been torn down, the slot is never reused
+    Retired,
+}
+
+/// The channel of an ICA, as acknowledged by its open ack
+#[cosmwasm_schema::cw_serde]
+pub struct IcaChannel {
+    /// The `icacontroller-{contract}.{ica_idx}` port of the ICA
+    pub port_id: String,
+    pub channel_id: String,
+    /// The channel on the host chain
+    pub counterparty_channel_id: String,
+}
+
+/// The ICA index of `port_id` if it is exactly the `icacontroller-{contract}.{ica_idx}` port of an ICA registered by `contract`
+#[must_use]
+pub fn ica_idx_from_port_id(port_id: &str, contract: &str) -> Option<u32> {
+    let ica_idx: u32 = port_id
+        .strip_prefix("icacontroller-")?
+        .strip_prefix(contract)?
+        .strip_prefix('.')?
+        .parse()
+        .ok()?;
+
+    // reject non-canonical indices such as `+1` or `01`
+    (port_id == format!("icacontroller-{contract}.{ica_idx}")).then_some(ica_idx)
+}
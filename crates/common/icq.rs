@@ -0,0 +1,198 @@
+//! Interchain query deposits, registration checks & result decoding.
+
+use cosmwasm_std::{Coin, StdError};
+use neutron_sdk::{
+    bindings::types::RegisteredQuery,
+    interchain_queries::{check_query_type, types::QueryType},
+    NeutronError,
+};
+use prost::Message;
+
+use crate::{deps::NeutronDeps, funds::coins_shortfall, ibc::RawCoin};
+
+/// The deposit required to register an ICQ, refunded once the ICQ is removed
+pub fn icq_deposit_fee(deps: impl NeutronDeps) -> Result<Vec<Coin>, StdError> {
+    let coins = deps.icq_params()?.query_deposit;
+
+    assert!(!coins.is_empty(), "there should always be a deposit coin");
+
+    Ok(coins)
+}
+
+/// The deposit required to register an ICQ, falling back to `deposit_override` if the interchainqueries params
+/// cannot be queried, e.g. once a chain upgrade moved the params query. The live params are preferred when available.
+pub fn icq_deposit_fee_or(
+    deps: impl NeutronDeps,
+    deposit_override: Option<Vec<Coin>>,
+) -> Result<Vec<Coin>, StdError> {
+    match (icq_deposit_fee(deps), deposit_override) {
+        (Ok(coins), _) | (Err(_), Some(coins)) => Ok(coins),
+        (Err(err), None) => Err(err),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcqDepositError {
+    /// No funds were attached
+    Missing,
+    /// None of the attached funds are of a deposit denom
+    IncorrectAsset,
+    /// The attached funds don't cover every deposit coin
+    Insufficient,
+}
+
+/// Check the attached `funds` cover each of the `required` deposit coins,
+/// returning the attached coins of the deposit denoms to be escrowed.
+pub fn check_icq_deposit(funds: &[Coin], required: &[Coin]) -> Result<Vec<Coin>, IcqDepositError> {
+    if funds.is_empty() {
+        return Err(IcqDepositError::Missing);
+    }
+
+    let deposit: Vec<Coin> = funds
+        .iter()
+        .filter(|c| required.iter().any(|r| r.denom == c.denom))
+        .cloned()
+        .collect();
+
+    if deposit.is_empty() {
+        return Err(IcqDepositError::IncorrectAsset);
+    }
+
+    if !coins_shortfall(&deposit, required).is_empty() {
+        return Err(IcqDepositError::Insufficient);
+    }
+
+    Ok(deposit)
+}
+
+/// The attached `funds` of none of the `required` deposit denoms, which are not escrowed & must be refunded
+#[must_use]
+pub fn foreign_deposit_coins(funds: &[Coin], required: &[Coin]) -> Vec<Coin> {
+    funds
+        .iter()
+        .filter(|c| required.iter().all(|r| r.denom != c.denom))
+        .cloned()
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueryBalanceIcqError {
+    #[error(transparent)]
+    CosmwasmStd(#[from] cosmwasm_std::StdError),
+    #[error(transparent)]
+    NeutronSdk(#[from] NeutronError),
+    #[error(transparent)]
+    Protobuf(#[from] prost::DecodeError),
+}
+
+impl QueryBalanceIcqError {
+    /// Whether the ICQ is no longer registered as a KV query
+    #[must_use]
+    pub fn is_query_type_mismatch(&self) -> bool {
+        matches!(self, Self::NeutronSdk(err) if is_query_type_mismatch(err))
+    }
+}
+
+#[must_use]
+pub fn is_query_type_mismatch(err: &NeutronError) -> bool {
+    matches!(err, NeutronError::InvalidQueryType { .. })
+}
+
+/// Cross-check that the query registered as `query_id` is still a KV query
+pub fn is_registered_kv_query(deps: impl NeutronDeps, query_id: u64) -> Result<bool, NeutronError> {
+    let registered_query = deps.registered_query(query_id)?;
+
+    Ok(check_query_type(registered_query.query_type, QueryType::KV).is_ok())
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteBalance {
+    pub last_submitted_result_local_height: u64,
+    pub balance: Option<Coin>,
+}
+
+pub fn updated_registered_kv_query(
+    deps: impl NeutronDeps,
+    query_id: u64,
+) -> Result<Option<RegisteredQuery>, NeutronError> {
+    let registered_query = deps.registered_query(query_id)?;
+
+    let last_submitted_local_height = registered_query.last_submitted_result_local_height;
+
+    if last_submitted_local_height == 0 {
+        return Ok(None);
+    }
+
+    check_query_type(registered_query.query_type, QueryType::KV)?;
+
+    Ok(Some(registered_query))
+}
+
+/// Decode a balance storage entry, `None` if the balance is empty
+fn decode_balance(value: &[u8]) -> Result<Option<Coin>, QueryBalanceIcqError> {
+    let RawCoin { denom, amount } = RawCoin::decode(value)?;
+
+    if denom.is_empty() && amount.is_empty() {
+        return Ok(None);
+    }
+
+    let amount = amount.parse()?;
+
+    Ok(Some(Coin { denom, amount }))
+}
+
+pub fn query_balance_icq(
+    deps: impl NeutronDeps,
+    query_id: u64,
+) -> Result<Option<RemoteBalance>, QueryBalanceIcqError> {
+    let Some(registered_query) = updated_registered_kv_query(deps, query_id)? else {
+        return Ok(None);
+    };
+
+    let registered_query_result = deps.registered_query_result(query_id)?;
+
+    assert_eq!(
+        registered_query_result.result.kv_results.len(),
+        1,
+        "only a single balance key requested means exactly one storage entry submitted"
+    );
+
+    let storage_entry = registered_query_result.result.kv_results.first().unwrap();
+
+    Ok(Some(RemoteBalance {
+        last_submitted_result_local_height: registered_query.last_submitted_result_local_height,
+        balance: decode_balance(storage_entry.value.as_slice())?,
+    }))
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteBalances {
+    pub last_submitted_result_local_height: u64,
+    /// The non-empty balances of the requested denoms, in the order the balance keys were registered
+    pub balances: Vec<Coin>,
+}
+
+/// Query the result of a balances ICQ registered for any number of denoms, one storage entry being submitted per denom
+pub fn query_balances_icq(
+    deps: impl NeutronDeps,
+    query_id: u64,
+) -> Result<Option<RemoteBalances>, QueryBalanceIcqError> {
+    let Some(registered_query) = updated_registered_kv_query(deps, query_id)? else {
+        return Ok(None);
+    };
+
+    let registered_query_result = deps.registered_query_result(query_id)?;
+
+    let mut balances = vec![];
+
+    for storage_entry in &registered_query_result.result.kv_results {
+        if let Some(balance) = decode_balance(storage_entry.value.as_slice())? {
+            balances.push(balance);
+        }
+    }
+
+    Ok(Some(RemoteBalances {
+        last_submitted_result_local_height: registered_query.last_submitted_result_local_height,
+        balances,
+    }))
+}
@@ -0,0 +1,48 @@
+//! Incremental reconstruction of the reverse lookup maps derived from the primary per-ICA state.
+
+use cosmwasm_std::{Event, Storage};
+
+/// The outcome of a single rebuild pass over a range of ICAs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuildPass {
+    /// The first ICA index visited
+    pub start: u32,
+    /// The number of ICAs visited
+    pub visited: u32,
+    /// The number of missing reverse map entries restored
+    pub restored: u32,
+    /// The ICA index the next pass resumes from, `None` once every ICA has been visited
+    pub next_ica_idx: Option<u32>,
+}
+
+impl RebuildPass {
+    #[must_use]
+    pub fn event(&self) -> Event {
+        Event::new("indexes_rebuilt")
+            .add_attribute("start", self.start.to_string())
+            .add_attribute("visited", self.visited.to_string())
+            .add_attribute("restored", self.restored.to_string())
+            .add_attribute("complete", self.next_ica_idx.is_none().to_string())
+    }
+}
+
+/// Visit at most `limit` ICA indexes from `start` up to `ica_count`, calling `rebuild` on each to restore the
+/// missing reverse map entries derived from the ICA's primary state & return how many were restored.
+pub fn rebuild_indexes(
+    storage: &mut dyn Storage,
+    start: u32,
+    ica_count: u32,
+    limit: u32,
+    mut rebuild: impl FnMut(&mut dyn Storage, u32) -> u32,
+) -> RebuildPass {
+    let end = start.saturating_add(limit).min(ica_count);
+
+    let restored = (start..end).map(|ica_idx| rebuild(storage, ica_idx)).sum();
+
+    RebuildPass {
+        start,
+        visited: end.saturating_sub(start),
+        restored,
+        next_ica_idx: (end < ica_count).then_some(end),
+    }
+}
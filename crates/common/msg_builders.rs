@@ -0,0 +1,153 @@
+//! Builders of the `NeutronMsg`s the examples issue, filled in from the contract's config so that execute handlers
+//! only decide what is sent, to whom & how it is paid for.
+//!
+//! ```ignore
+//! let config = TransferConfig {
+//!     source_channel: state::ibc_transfer_channel(deps.storage),
+//!     sender: env.contract.address.to_string(),
+//! };
+//!
+//! let msg = msg_builders::ibc_transfer(&config, ica_addr, coin, TimeoutPolicy::Timestamp(nanos), None, fee);
+//! ```
+
+use cosmwasm_std::Coin;
+use neutron_sdk::{
+    bindings::{
+        msg::{IbcFee, NeutronMsg},
+        types::ProtobufAny,
+    },
+    sudo::msg::RequestPacketTimeoutHeight,
+};
+use prost::Message;
+
+use crate::ibc::RawCoin;
+
+/// The port of ICS-20 transfers
+pub const TRANSFER_PORT: &str = "transfer";
+
+pub const MSG_DELEGATE_TYPE_URL: &str = "/cosmos.staking.v1beta1.MsgDelegate";
+
+pub const MSG_UNDELEGATE_TYPE_URL: &str = "/cosmos.staking.v1beta1.MsgUndelegate";
+
+/// When an IBC packet times out if it has not been received yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPolicy {
+    /// At a height of the counterparty chain
+    Height {
+        revision_number: u64,
+        revision_height: u64,
+    },
+    /// At a block time of the counterparty chain, in nanoseconds since the unix epoch
+    Timestamp(u64),
+}
+
+impl TimeoutPolicy {
+    fn into_packet_timeout(self) -> (RequestPacketTimeoutHeight, u64) {
+        match self {
+            Self::Height {
+                revision_number,
+                revision_height,
+            } => (
+                RequestPacketTimeoutHeight {
+                    revision_number: Some(revision_number),
+                    revision_height: Some(revision_height),
+                },
+                0,
+            ),
+            Self::Timestamp(nanos) => (
+                RequestPacketTimeoutHeight {
+                    revision_number: None,
+                    revision_height: None,
+                },
+                nanos,
+            ),
+        }
+    }
+}
+
+/// The channel IBC transfers are sent over & the address sending them, usually the contract's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferConfig {
+    pub source_channel: String,
+    pub sender: String,
+}
+
+/// The connection ICA txs are submitted over & the seconds after which they time out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcaTxConfig {
+    pub connection_id: String,
+    pub timeout_seconds: u64,
+}
+
+/// An ICS-20 transfer of `token` to `receiver` over the configured channel
+#[must_use]
+pub fn ibc_transfer(
+    config: &TransferConfig,
+    receiver: impl Into<String>,
+    token: Coin,
+    timeout: TimeoutPolicy,
+    memo: Option<String>,
+    fee: IbcFee,
+) -> NeutronMsg {
+    let (timeout_height, timeout_timestamp) = timeout.into_packet_timeout();
+
+    NeutronMsg::IbcTransfer {
+        source_port: TRANSFER_PORT.to_owned(),
+        source_channel: config.source_channel.clone(),
+        sender: config.sender.clone(),
+        receiver: receiver.into(),
+        token,
+        timeout_height,
+        timeout_timestamp,
+        memo: memo.unwrap_or_default(),
+        fee,
+    }
+}
+
+/// A tx of `msgs` executed by the ICA registered as `interchain_account_id` over the configured connection
+#[must_use]
+pub fn submit_tx(
+    config: &IcaTxConfig,
+    interchain_account_id: impl Into<String>,
+    msgs: Vec<ProtobufAny>,
+    fee: IbcFee,
+) -> NeutronMsg {
+    NeutronMsg::SubmitTx {
+        connection_id: config.connection_id.clone(),
+        interchain_account_id: interchain_account_id.into(),
+        msgs,
+        memo: String::new(),
+        timeout: config.timeout_seconds,
+        fee,
+    }
+}
+
+/// A `MsgDelegate` or `MsgUndelegate` of `coin` from `delegator` to `validator`, both messages share a layout
+#[must_use]
+pub fn staking_msg(
+    type_url: &str,
+    delegator: String,
+    validator: String,
+    coin: Coin,
+) -> ProtobufAny {
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgStaking {
+        #[prost(string, tag = "1")]
+        pub delegator_address: String,
+        #[prost(string, tag = "2")]
+        pub validator_address: String,
+        #[prost(message, optional, tag = "3")]
+        pub amount: Option<RawCoin>,
+    }
+
+    let staking_msg = MsgStaking {
+        delegator_address: delegator,
+        validator_address: validator,
+        amount: Some(coin.into()),
+    };
+
+    ProtobufAny {
+        type_url: type_url.to_owned(),
+        value: staking_msg.encode_to_vec().into(),
+    }
+}
@@ -0,0 +1,94 @@
+//! Receipts of execute calls, set as the response data so that calling contracts read the outcome of the
+//! submessages they execute from the reply data rather than by parsing events.
+//!
+//! ```ignore
+//! // in the executed contract
+//! let action = receipt::action_name(&msg)?;
+//!
+//! let res = dispatch(msg)?;
+//!
+//! let receipt = ExecuteReceipt::from_events(action, &res.events);
+//!
+//! Ok(receipt::set_receipt(res, &receipt)?)
+//!
+//! // in the calling contract's reply entry point
+//! let receipt = receipt::parse_execute_receipt(reply)?;
+//! ```
+
+use cosmwasm_std::{from_slice, to_binary, to_vec, Event, Reply, Response, StdError, StdResult};
+
+use crate::reply::ParseReplyError;
+
+/// The outcome of an execute call
+#[cosmwasm_schema::cw_serde]
+pub struct ExecuteReceipt {
+    /// The snake case name of the executed msg, e.g. `transfer_funds`
+    pub action: String,
+    /// The ICA acted upon, if any
+    pub ica_idx: Option<u32>,
+    /// The sequence number of the ICA tx issued, only known once the tx is submitted
+    pub seq_num: Option<u64>,
+}
+
+impl ExecuteReceipt {
+    #[must_use]
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            ica_idx: None,
+            seq_num: None,
+        }
+    }
+
+    /// A receipt of `action` acting upon the ICA of the first `ica_idx` attribute of `events`
+    #[must_use]
+    pub fn from_events(action: impl Into<String>, events: &[Event]) -> Self {
+        let ica_idx = events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "ica_idx")
+            .and_then(|attr| attr.value.parse().ok());
+
+        Self {
+            ica_idx,
+            ..Self::new(action)
+        }
+    }
+}
+
+/// The name of an externally tagged msg, i.e. the key of the JSON object it serializes into
+pub fn action_name<T: serde::Serialize>(msg: &T) -> StdResult<String> {
+    let json = to_vec(msg)?;
+
+    let name = json
+        .strip_prefix(b"{\"")
+        .and_then(|rest| rest.split(|&b| b == b'"').next())
+        .ok_or_else(|| StdError::generic_err("msgs are serialized as JSON objects"))?;
+
+    Ok(String::from_utf8_lossy(name).into_owned())
+}
+
+/// Set `receipt` as the data of `res`
+pub fn set_receipt<T>(res: Response<T>, receipt: &ExecuteReceipt) -> StdResult<Response<T>> {
+    Ok(res.set_data(to_binary(receipt)?))
+}
+
+/// Parse the receipt of a contract executed by a submessage from the protobuf encoded reply data
+pub fn parse_execute_receipt(reply: Reply) -> Result<ExecuteReceipt, ParseReplyError> {
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct MsgExecuteContractResponse {
+        #[prost(bytes, tag = "1")]
+        data: Vec<u8>,
+    }
+
+    let res = reply
+        .result
+        .into_result()
+        .map_err(ParseReplyError::SubMsgFailure)?;
+
+    let data = res.data.ok_or(ParseReplyError::ReplyDataMissing)?;
+
+    let MsgExecuteContractResponse { data } = prost::Message::decode(data.as_slice())?;
+
+    Ok(from_slice(&data)?)
+}
@@ -0,0 +1,131 @@
+//! Payloads recorded against submessage reply ids, packing of `u32` pairs & parsing of the replies to neutron messages.
+
+use cosmwasm_std::{from_binary, Reply};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::storage::{self, namespaced_key, Storage};
+
+const PENDING_REPLY_NAMESPACE: &str = "pending_reply";
+const LAST_REPLY_ID_NAMESPACE: &str = "last_reply_id";
+
+/// Pack two `u32`s into a `u64`, typically an ICA index & a per ICA index or kind keying storage
+#[must_use]
+pub fn combine_u32s(a: u32, b: u32) -> u64 {
+    (u64::from(a) << 32) | u64::from(b)
+}
+
+#[must_use]
+pub fn split_u64(x: u64) -> (u32, u32) {
+    let a = u32::try_from(x >> 32).unwrap();
+    let b = u32::try_from(x.rotate_left(32) >> 32).unwrap();
+    (a, b)
+}
+
+/// Record `payload` for the reply of a submessage, returning the id to send the submessage with.
+/// Ids increase monotonically & are never reused, the payload is removed once taken by [`take_reply_payload`],
+/// so the submessage must reply unless it not replying reverts the whole execution
+pub fn save_reply_payload<T: Serialize>(storage: &mut dyn Storage, payload: &T) -> u64 {
+    let last_key = namespaced_key(LAST_REPLY_ID_NAMESPACE, &[]);
+
+    let reply_id = storage::load::<u64>(storage, &last_key).unwrap_or_default() + 1;
+
+    storage::save(storage, &last_key, &reply_id);
+
+    storage::save(
+        storage,
+        &storage::map_key(PENDING_REPLY_NAMESPACE, &reply_id),
+        payload,
+    );
+
+    reply_id
+}
+
+/// Take the payload recorded for the reply `reply_id`, `None` if there is none or it was already taken
+pub fn take_reply_payload<T: DeserializeOwned>(
+    storage: &mut dyn Storage,
+    reply_id: u64,
+) -> Option<T> {
+    let key = storage::map_key(PENDING_REPLY_NAMESPACE, &reply_id);
+
+    let payload = storage::load(storage, &key)?;
+
+    storage.remove(&key);
+
+    Some(payload)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseReplyError {
+    #[error("{0}")]
+    SubMsgFailure(String),
+    #[error("reply data missing")]
+    ReplyDataMissing,
+    #[error(transparent)]
+    CosmwasmStd(#[from] cosmwasm_std::StdError),
+    #[error(transparent)]
+    Decode(#[from] prost::DecodeError),
+}
+
+/// Tries to parse the query id of a newly registered ICQ from the reply data
+pub fn parse_icq_registration_reply(reply: Reply) -> Result<u64, ParseReplyError> {
+    #[cosmwasm_schema::cw_serde]
+    struct MsgRegisterInterchainQueryResponse {
+        id: u64,
+    }
+
+    let res = reply
+        .result
+        .into_result()
+        .map_err(ParseReplyError::SubMsgFailure)?;
+
+    let data = res.data.ok_or(ParseReplyError::ReplyDataMissing)?;
+
+    let msg: MsgRegisterInterchainQueryResponse = from_binary(&data)?;
+
+    Ok(msg.id)
+}
+
+/// Tries to parse the sequence number and channel id of a newly issued IBC tx from the reply data
+pub fn parse_issue_tx_reply(reply: Reply) -> Result<(u64, String), ParseReplyError> {
+    #[cosmwasm_schema::cw_serde]
+    struct MsgSubmitTxResponse {
+        sequence_id: u64,
+        channel: String,
+    }
+
+    let res = reply
+        .result
+        .into_result()
+        .map_err(ParseReplyError::SubMsgFailure)?;
+
+    let data = res.data.ok_or(ParseReplyError::ReplyDataMissing)?;
+
+    let MsgSubmitTxResponse {
+        sequence_id,
+        channel,
+    } = from_binary(&data)?;
+
+    Ok((sequence_id, channel))
+}
+
+/// Tries to parse the address of a newly instantiated contract from the protobuf encoded reply data
+pub fn parse_instantiate_reply(reply: Reply) -> Result<String, ParseReplyError> {
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct MsgInstantiateContractResponse {
+        #[prost(string, tag = "1")]
+        address: String,
+        #[prost(bytes, tag = "2")]
+        data: Vec<u8>,
+    }
+
+    let res = reply
+        .result
+        .into_result()
+        .map_err(ParseReplyError::SubMsgFailure)?;
+
+    let data = res.data.ok_or(ParseReplyError::ReplyDataMissing)?;
+
+    let MsgInstantiateContractResponse { address, .. } = prost::Message::decode(data.as_slice())?;
+
+    Ok(address)
+}
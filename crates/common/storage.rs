@@ -0,0 +1,220 @@
+//! JSON encoded storage for state that doesn't fit the primitive point lookups of `cw-storage-macros`.
+//!
+//! ```ignore
+//! pub mod state {
+//!     use common::{json_item, json_map};
+//!
+//!     json_item!(fee_history   : Vec<FeeObservation>);
+//!     json_map!(ica : u32 => pending_tx : PendingTx);
+//! }
+//! ```
+//!
+//! Generates `fee_history`, `set_fee_history` & `remove_fee_history` along with
+//! `ica_pending_tx`, `set_ica_pending_tx`, `remove_ica_pending_tx` & `ica_pending_tx_range`.
+
+use cosmwasm_std::{from_slice, to_vec, Order};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use cosmwasm_std::Storage;
+
+/// A type that can be used as a `json_map!` key, integers are big-endian encoded so they iterate in order
+pub trait Key {
+    type Owned;
+
+    fn key_bytes(&self) -> Vec<u8>;
+
+    fn from_key_bytes(bytes: &[u8]) -> Self::Owned;
+}
+
+impl Key for str {
+    type Owned = String;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> String {
+        String::from_utf8(bytes.to_vec()).expect("str keys are valid utf8")
+    }
+}
+
+impl Key for String {
+    type Owned = String;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> String {
+        str::from_key_bytes(bytes)
+    }
+}
+
+impl Key for u32 {
+    type Owned = u32;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes(bytes.try_into().expect("u32 keys are 4 bytes"))
+    }
+}
+
+impl Key for u64 {
+    type Owned = u64;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> u64 {
+        u64::from_be_bytes(bytes.try_into().expect("u64 keys are 8 bytes"))
+    }
+}
+
+/// Length prefix the namespace so that no namespace is ever a prefix of another
+#[must_use]
+pub fn namespaced_key(namespace: &str, key: &[u8]) -> Vec<u8> {
+    let len = u16::try_from(namespace.len()).expect("namespace shorter than u16::MAX");
+
+    [len.to_be_bytes().as_slice(), namespace.as_bytes(), key].concat()
+}
+
+#[must_use]
+pub fn map_key<K: Key + ?Sized>(namespace: &str, key: &K) -> Vec<u8> {
+    namespaced_key(namespace, &key.key_bytes())
+}
+
+#[must_use]
+pub fn load<T: DeserializeOwned>(storage: &dyn Storage, key: &[u8]) -> Option<T> {
+    storage
+        .get(key)
+        .map(|bytes| from_slice(&bytes).expect("stored value is valid json"))
+}
+
+pub fn save<T: Serialize + ?Sized>(storage: &mut dyn Storage, key: &[u8], value: &T) {
+    let bytes = to_vec(value).expect("infallible serialization");
+
+    storage.set(key, &bytes);
+}
+
+/// The first key that sorts after every key starting with `prefix`
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+    let mut end = prefix.to_vec();
+
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            break;
+        }
+    }
+
+    end
+}
+
+/// Iterate the entries of the map in `namespace` in ascending key order, starting after `start_after`
+#[must_use]
+pub fn range<K: Key + ?Sized, V: DeserializeOwned>(
+    storage: &dyn Storage,
+    namespace: &str,
+    start_after: Option<&K>,
+    limit: usize,
+) -> Vec<(K::Owned, V)> {
+    let prefix = namespaced_key(namespace, &[]);
+
+    let start = start_after.map_or_else(
+        || prefix.clone(),
+        |key| {
+            // the smallest key greater than `start_after`
+            let mut start = map_key(namespace, key);
+            start.push(0);
+            start
+        },
+    );
+
+    let end = prefix_end(&prefix);
+
+    storage
+        .range(Some(&start), Some(&end), Order::Ascending)
+        .take(limit)
+        .map(|(key, value)| {
+            let key = K::from_key_bytes(&key[prefix.len()..]);
+            let value = from_slice(&value).expect("stored value is valid json");
+            (key, value)
+        })
+        .collect()
+}
+
+#[macro_export]
+macro_rules! json_item {
+    ($name:ident : $t:ty) => {
+        $crate::paste::paste! {
+            pub fn $name(storage: &dyn $crate::storage::Storage) -> Option<$t> {
+                $crate::storage::load(
+                    storage,
+                    &$crate::storage::namespaced_key(stringify!($name), &[]),
+                )
+            }
+
+            pub fn [<set_ $name>](storage: &mut dyn $crate::storage::Storage, value: &$t) {
+                $crate::storage::save(
+                    storage,
+                    &$crate::storage::namespaced_key(stringify!($name), &[]),
+                    value,
+                );
+            }
+
+            pub fn [<remove_ $name>](storage: &mut dyn $crate::storage::Storage) {
+                storage.remove(&$crate::storage::namespaced_key(stringify!($name), &[]));
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! json_map {
+    ($ns:ident : $k:ty => $name:ident : $v:ty) => {
+        $crate::paste::paste! {
+            pub fn [<$ns _ $name>](storage: &dyn $crate::storage::Storage, key: &$k) -> Option<$v> {
+                $crate::storage::load(
+                    storage,
+                    &$crate::storage::map_key(concat!(stringify!($ns), "_", stringify!($name)), key),
+                )
+            }
+
+            pub fn [<set_ $ns _ $name>](
+                storage: &mut dyn $crate::storage::Storage,
+                key: &$k,
+                value: &$v,
+            ) {
+                $crate::storage::save(
+                    storage,
+                    &$crate::storage::map_key(concat!(stringify!($ns), "_", stringify!($name)), key),
+                    value,
+                );
+            }
+
+            pub fn [<remove_ $ns _ $name>](storage: &mut dyn $crate::storage::Storage, key: &$k) {
+                storage.remove(&$crate::storage::map_key(
+                    concat!(stringify!($ns), "_", stringify!($name)),
+                    key,
+                ));
+            }
+
+            pub fn [<$ns _ $name _range>](
+                storage: &dyn $crate::storage::Storage,
+                start_after: Option<&$k>,
+                limit: usize,
+            ) -> Vec<(<$k as $crate::storage::Key>::Owned, $v)> {
+                $crate::storage::range(
+                    storage,
+                    concat!(stringify!($ns), "_", stringify!($name)),
+                    start_after,
+                    limit,
+                )
+            }
+        }
+    };
+}
@@ -0,0 +1,37 @@
+//! Fixtures for the unit tests of contracts built on these building blocks, unavailable to wasm builds.
+
+use cosmwasm_std::{Binary, Reply, SubMsgResponse, SubMsgResult};
+
+/// The layout shared by `MsgInstantiateContractResponse` & `MsgExecuteContractResponse` when only their first field
+/// is set, respectively the address of the instantiated contract & the data returned by the executed contract
+#[derive(Clone, PartialEq, prost::Message)]
+struct FirstFieldOnly {
+    #[prost(bytes, tag = "1")]
+    field: Vec<u8>,
+}
+
+fn first_field_reply(id: u64, field: &[u8]) -> Reply {
+    let data = prost::Message::encode_to_vec(&FirstFieldOnly {
+        field: field.to_vec(),
+    });
+
+    Reply {
+        id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(data)),
+        }),
+    }
+}
+
+/// The successful reply `id` to a submessage instantiating the contract at `address`
+#[must_use]
+pub fn instantiate_reply(id: u64, address: &str) -> Reply {
+    first_field_reply(id, address.as_bytes())
+}
+
+/// The successful reply `id` to a submessage executing a contract which returned `data`
+#[must_use]
+pub fn execute_reply(id: u64, data: &[u8]) -> Reply {
+    first_field_reply(id, data)
+}
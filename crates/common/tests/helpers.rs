@@ -0,0 +1,713 @@
+use std::marker::PhantomData;
+
+use common::{
+    check_icq_deposit,
+    factory::{self, Child, FactoryError},
+    foreign_deposit_coins,
+    funds::{add_coins, coins_shortfall, subtract_coins},
+    ibc::{
+        ibc_hooks_sender, ics20_voucher_denom, make_ibc_transfer_via_hops_with_hook_msg,
+        pfm_receiver, ForwardHop,
+    },
+    ica::ica_idx_from_port_id,
+    icq_deposit_fee_or,
+    msg_builders::{self, IcaTxConfig, TimeoutPolicy, TransferConfig},
+    receipt::{action_name, parse_execute_receipt, ExecuteReceipt},
+    reply::{
+        combine_u32s, parse_instantiate_reply, save_reply_payload, split_u64, take_reply_payload,
+    },
+    testing,
+    validate::{validate_icq_deposit, validate_transfer_memo, MemoLimits, ValidationError},
+    IcqDepositError, NeutronDeps,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin,
+    testing::{MockApi, MockQuerier, MockStorage},
+    to_binary, to_vec, Binary, ContractResult, CustomQuery, Event, OwnedDeps, Reply,
+    SubMsgResponse, SubMsgResult, SystemResult, WasmMsg,
+};
+use neutron_sdk::bindings::{
+    msg::{IbcFee, NeutronMsg},
+    query::NeutronQuery,
+    types::ProtobufAny,
+};
+
+#[test]
+fn reply_id_roundtrip() {
+    let id = combine_u32s(7, u32::MAX);
+
+    assert_eq!(split_u64(id), (7, u32::MAX));
+}
+
+#[test]
+fn reply_payloads_taken_once_under_increasing_ids() {
+    let mut storage = MockStorage::default();
+
+    let first = save_reply_payload(&mut storage, &("transfer", 3_u32));
+    let second = save_reply_payload(&mut storage, &("retrieve", 3_u32));
+
+    assert!(second > first);
+
+    assert_eq!(
+        take_reply_payload(&mut storage, second),
+        Some(("retrieve".to_owned(), 3_u32))
+    );
+    assert_eq!(
+        take_reply_payload::<(String, u32)>(&mut storage, second),
+        None
+    );
+
+    // ids are not reused once taken
+    assert!(save_reply_payload(&mut storage, &()) > second);
+
+    assert_eq!(
+        take_reply_payload(&mut storage, first),
+        Some(("transfer".to_owned(), 3_u32))
+    );
+}
+
+#[test]
+fn instantiated_address_parsed_from_reply_data() {
+    let reply = |result| Reply { id: 0, result };
+
+    let address = "neutron1probe";
+
+    let ok = testing::instantiate_reply(0, address);
+
+    assert_eq!(parse_instantiate_reply(ok).unwrap(), address);
+
+    let no_data = reply(SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: None,
+    }));
+
+    assert!(parse_instantiate_reply(no_data).is_err());
+
+    let failed = reply(SubMsgResult::Err("out of gas".to_owned()));
+
+    assert!(parse_instantiate_reply(failed).is_err());
+}
+
+#[test]
+fn factory_children_registered_from_instantiate_replies() {
+    let mut storage = MockStorage::default();
+
+    let instantiate_msg = |label: &str| WasmMsg::Instantiate {
+        admin: None,
+        code_id: 1,
+        msg: Binary::default(),
+        funds: vec![],
+        label: label.to_owned(),
+    };
+
+    for label in ["a", "b"] {
+        factory::instantiate_child(&mut storage, instantiate_msg(label), &label, |idx| idx)
+            .unwrap();
+    }
+
+    let (idx, msg) =
+        factory::instantiate_child(&mut storage, instantiate_msg("c"), &"c", |idx| idx).unwrap();
+
+    assert_eq!(idx, 2);
+    assert_eq!(factory::child_count(&storage), 3);
+
+    let not_instantiate = WasmMsg::ClearAdmin {
+        contract_addr: "child".to_owned(),
+    };
+
+    assert!(factory::instantiate_child(&mut storage, not_instantiate, &"d", |idx| idx).is_err());
+
+    // the child index is recorded as the reply payload
+    assert_eq!(take_reply_payload::<u32>(&mut storage, msg.id), Some(2));
+
+    let address = "neutron1child";
+
+    let reply = testing::instantiate_reply(msg.id, address);
+
+    // children that were never instantiated are rejected
+    assert!(matches!(
+        factory::register_child::<String>(&mut storage, 5, reply.clone()),
+        Err(FactoryError::UnexpectedReply { .. })
+    ));
+
+    let child: Child<String> = factory::register_child(&mut storage, 1, reply.clone()).unwrap();
+
+    assert_eq!(child.address.as_deref(), Some(address));
+
+    assert!(matches!(
+        factory::register_child::<String>(&mut storage, 1, reply),
+        Err(FactoryError::UnexpectedReply { .. })
+    ));
+
+    let children: Vec<Child<String>> = factory::children(&storage, Some(0), 10);
+
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0], child);
+    assert_eq!(children[1].params, "c");
+    assert_eq!(children[1].address, None);
+}
+
+#[test]
+fn ica_idx_from_canonical_port_id_only() {
+    assert_eq!(
+        ica_idx_from_port_id("icacontroller-contract.3", "contract"),
+        Some(3)
+    );
+
+    assert_eq!(
+        ica_idx_from_port_id("icacontroller-contract.03", "contract"),
+        None
+    );
+
+    assert_eq!(
+        ica_idx_from_port_id("icacontroller-contract.+3", "contract"),
+        None
+    );
+
+    assert_eq!(
+        ica_idx_from_port_id("icacontroller-other.3", "contract"),
+        None
+    );
+}
+
+#[test]
+fn coin_arithmetic() {
+    let mut coins = vec![coin(10, "untrn")];
+
+    add_coins(&mut coins, &[coin(5, "untrn"), coin(3, "uatom")]);
+
+    assert_eq!(coins, vec![coin(15, "untrn"), coin(3, "uatom")]);
+
+    assert_eq!(
+        coins_shortfall(&coins, &[coin(20, "untrn"), coin(3, "uatom")]),
+        vec![coin(5, "untrn")]
+    );
+
+    assert_eq!(subtract_coins(&coins, &[coin(4, "uatom")]), None);
+
+    assert_eq!(
+        subtract_coins(&coins, &[coin(3, "uatom")]),
+        Some(vec![coin(15, "untrn")])
+    );
+}
+
+#[test]
+fn multi_coin_icq_deposit() {
+    let required = [coin(10, "untrn"), coin(2, "uatom")];
+
+    assert_eq!(
+        check_icq_deposit(&[], &required),
+        Err(IcqDepositError::Missing)
+    );
+
+    assert_eq!(
+        check_icq_deposit(&[coin(10, "uosmo")], &required),
+        Err(IcqDepositError::IncorrectAsset)
+    );
+
+    assert_eq!(
+        check_icq_deposit(&[coin(10, "untrn")], &required),
+        Err(IcqDepositError::Insufficient)
+    );
+
+    assert_eq!(
+        check_icq_deposit(
+            &[coin(12, "untrn"), coin(1, "uosmo"), coin(2, "uatom")],
+            &required
+        ),
+        Ok(vec![coin(12, "untrn"), coin(2, "uatom")])
+    );
+
+    assert_eq!(
+        foreign_deposit_coins(
+            &[coin(12, "untrn"), coin(1, "uosmo"), coin(2, "uatom")],
+            &required
+        ),
+        vec![coin(1, "uosmo")]
+    );
+
+    assert!(foreign_deposit_coins(&[coin(12, "untrn")], &required).is_empty());
+}
+
+#[test]
+fn ibc_hooks_sender_bound_to_channel_and_sender() {
+    let sender = ibc_hooks_sender("channel-0", "cosmos1ica", "neutron");
+
+    assert!(sender.starts_with("neutron1"));
+    assert_eq!(
+        sender,
+        ibc_hooks_sender("channel-0", "cosmos1ica", "neutron")
+    );
+    assert_ne!(
+        sender,
+        ibc_hooks_sender("channel-1", "cosmos1ica", "neutron")
+    );
+    assert_ne!(
+        sender,
+        ibc_hooks_sender("channel-0", "cosmos1other", "neutron")
+    );
+
+    // the vector of ibc-hooks' `TestDeriveIntermediateSender`
+    assert_eq!(
+        ibc_hooks_sender(
+            "channel-0",
+            "cosmos1tfejvgp5yzd8ypvn9t0e2uv2kcjf2laa8upya8",
+            "osmo"
+        ),
+        "osmo1sguz3gtyl2tjsdulwxmtprd68xtd43yyep6g5c554utz642sr8rqcgw0q6"
+    );
+}
+
+#[test]
+fn pfm_receiver_is_an_account_of_the_hop() {
+    use bech32::FromBase32;
+
+    let receiver = pfm_receiver("channel-0", "cosmos1ica", "osmo");
+
+    let (prefix, data, _) = bech32::decode(&receiver).unwrap();
+
+    assert_eq!(prefix, "osmo");
+    assert_eq!(Vec::<u8>::from_base32(&data).unwrap().len(), 20);
+    assert_ne!(receiver, pfm_receiver("channel-1", "cosmos1ica", "osmo"));
+    assert_ne!(receiver, pfm_receiver("channel-0", "cosmos1other", "osmo"));
+}
+
+#[test]
+fn ics20_voucher_denom_hashes_the_trace() {
+    // ATOM on osmosis
+    assert_eq!(
+        ics20_voucher_denom("transfer/channel-0", "uatom"),
+        "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+    );
+}
+
+#[test]
+fn forwarded_transfer_memo_nests_each_hop() {
+    let hop = |receiver: &str, channel: &str| ForwardHop {
+        receiver: receiver.to_owned(),
+        channel: channel.to_owned(),
+    };
+
+    let msg = make_ibc_transfer_via_hops_with_hook_msg(
+        "channel-0".to_owned(),
+        coin(10, "uatom"),
+        "cosmos1ica".to_owned(),
+        0,
+        &[hop("osmo1hop", "channel-1"), hop("juno1hop", "channel-2")],
+        cosmwasm_std::Addr::unchecked("neutron1contract"),
+        "hook",
+        &MemoLimits::default(),
+    )
+    .unwrap();
+
+    let encoded = String::from_utf8_lossy(&msg.value);
+
+    // the first hop receives the transfer, each forward memo carries the next one as a JSON string
+    assert!(encoded.contains("osmo1hop"));
+    assert!(encoded.contains(
+        r#"{"forward":{"receiver":"juno1hop","port":"transfer","channel":"channel-1","next":"{\"forward\":{\"receiver\":\"neutron1contract\",\"port\":\"transfer\",\"channel\":\"channel-2\",\"next\":\"{\\\"wasm\\\":{\\\"contract\\\":\\\"neutron1contract\\\",\\\"msg\\\":\\\"hook\\\"}}\"}}"}}"#
+    ));
+}
+
+#[test]
+fn oversized_built_memos_rejected() {
+    let hop = ForwardHop {
+        receiver: "osmo1hop".to_owned(),
+        channel: "channel-1".to_owned(),
+    };
+
+    let transfer = |limits: MemoLimits| {
+        make_ibc_transfer_via_hops_with_hook_msg(
+            "channel-0".to_owned(),
+            coin(10, "uatom"),
+            "cosmos1ica".to_owned(),
+            0,
+            &[hop.clone()],
+            cosmwasm_std::Addr::unchecked("neutron1contract"),
+            "hook",
+            &limits,
+        )
+    };
+
+    // {"wasm":{"contract":"neutron1contract","msg":"hook"}}
+    let hook_memo_len = 53;
+
+    assert!(transfer(MemoLimits {
+        max_hook_memo_len: hook_memo_len,
+        max_memo_len: 1024,
+    })
+    .is_ok());
+
+    assert_eq!(
+        transfer(MemoLimits {
+            max_hook_memo_len: hook_memo_len - 1,
+            max_memo_len: 1024,
+        })
+        .unwrap_err(),
+        ValidationError::MemoTooLong {
+            len: hook_memo_len,
+            max_len: hook_memo_len - 1,
+        }
+    );
+
+    // the forward memo nests the escaped hook memo
+    assert!(matches!(
+        transfer(MemoLimits {
+            max_hook_memo_len: hook_memo_len,
+            max_memo_len: hook_memo_len,
+        }),
+        Err(ValidationError::MemoTooLong { .. })
+    ));
+
+    assert_eq!(validate_transfer_memo("line\nbreak"), Ok(()));
+
+    assert_eq!(
+        validate_transfer_memo("bell\u{7}"),
+        Err(ValidationError::InvalidMemo("contains control characters"))
+    );
+}
+
+/// A custom query type wrapping neutron's queries, as a multitest app would
+#[cw_serde]
+enum AppQuery {
+    Neutron(NeutronQuery),
+}
+
+impl CustomQuery for AppQuery {}
+
+impl From<NeutronQuery> for AppQuery {
+    fn from(query: NeutronQuery) -> Self {
+        Self::Neutron(query)
+    }
+}
+
+#[test]
+fn neutron_queries_through_wrapping_custom_query() {
+    #[cw_serde]
+    struct MinIbcFeeResponse {
+        min_fee: IbcFee,
+    }
+
+    let min_fee = IbcFee {
+        recv_fee: vec![],
+        ack_fee: vec![coin(1000, "untrn")],
+        timeout_fee: vec![coin(1000, "untrn")],
+    };
+
+    let res = to_binary(&MinIbcFeeResponse {
+        min_fee: min_fee.clone(),
+    })
+    .unwrap();
+
+    let querier = MockQuerier::<AppQuery>::new(&[]).with_custom_handler(move |query| match query {
+        AppQuery::Neutron(NeutronQuery::MinIbcFee {}) => {
+            SystemResult::Ok(ContractResult::Ok(res.clone()))
+        }
+        _ => unimplemented!("unexpected query: {query:?}"),
+    });
+
+    let deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier,
+        custom_query_type: PhantomData,
+    };
+
+    assert_eq!(deps.as_ref().min_ibc_fee().unwrap(), min_fee);
+}
+
+#[test]
+fn unknown_connection_rejected() {
+    let deps = cosmwasm_std::testing::mock_dependencies();
+
+    assert!(matches!(
+        common::ibc::ensure_connection_open(deps.as_ref(), "connection-0"),
+        Err(common::ibc::IbcStateError::ConnectionNotFound { .. })
+    ));
+}
+
+/// Answers every IBC channel query with a channel in `state`
+struct ChannelQuerier {
+    state: &'static str,
+}
+
+impl cosmwasm_std::Querier for ChannelQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> cosmwasm_std::QuerierResult {
+        let request: cosmwasm_std::QueryRequest<cosmwasm_std::Empty> =
+            cosmwasm_std::from_slice(bin_request).unwrap();
+
+        let cosmwasm_std::QueryRequest::Stargate { path, .. } = request else {
+            unimplemented!("unexpected query: {request:?}");
+        };
+
+        assert_eq!(path, "/ibc.core.channel.v1.Query/Channel");
+
+        let res = format!(r#"{{"channel":{{"state":"{}"}}}}"#, self.state);
+
+        SystemResult::Ok(ContractResult::Ok(res.into_bytes().into()))
+    }
+}
+
+#[test]
+fn channel_not_open_rejected() {
+    let (storage, api) = (MockStorage::default(), MockApi::default());
+
+    let ensure_channel_open = |state| {
+        let querier = ChannelQuerier { state };
+
+        let deps = cosmwasm_std::Deps::<cosmwasm_std::Empty> {
+            storage: &storage,
+            api: &api,
+            querier: cosmwasm_std::QuerierWrapper::new(&querier),
+        };
+
+        common::ibc::ensure_channel_open(deps, "transfer", "channel-0")
+    };
+
+    assert!(ensure_channel_open(common::ibc::STATE_OPEN).is_ok());
+
+    for state in ["STATE_INIT", "STATE_TRYOPEN", "STATE_CLOSED"] {
+        assert!(matches!(
+            ensure_channel_open(state),
+            Err(common::ibc::IbcStateError::ChannelNotOpen { state: s, .. }) if s == state
+        ));
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum CodedError {
+    #[error("[E0900] unit")]
+    Unit,
+    #[error("[E0901] tuple {0}")]
+    Tuple(u32),
+    #[error("[E0902] struct {field}")]
+    Struct { field: u32 },
+}
+
+common::error_codes!(CodedError {
+    Unit = 900,
+    Tuple = 901,
+    Struct = 902,
+});
+
+#[test]
+fn error_codes_of_every_variant_kind() {
+    assert_eq!(CodedError::Unit.code(), 900);
+    assert_eq!(CodedError::Tuple(1).code(), 901);
+    assert_eq!(CodedError::Struct { field: 1 }.code(), 902);
+
+    let catalogue = CodedError::catalogue();
+
+    assert_eq!(catalogue.len(), 3);
+    assert_eq!(catalogue[2].code, 902);
+    assert_eq!(catalogue[2].name, "Struct");
+}
+
+#[test]
+fn icq_deposit_override_charged_if_params_unavailable() {
+    // the mock querier answers no stargate queries, as if the params query path had moved
+    let deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::<NeutronQuery>::new(&[]),
+        custom_query_type: PhantomData,
+    };
+
+    let deposit = vec![coin(1_000_000, "untrn")];
+
+    assert_eq!(
+        icq_deposit_fee_or(deps.as_ref(), Some(deposit.clone())).unwrap(),
+        deposit
+    );
+
+    assert!(icq_deposit_fee_or(deps.as_ref(), None).is_err());
+
+    assert_eq!(validate_icq_deposit(&deposit), Ok(()));
+
+    for (deposit, reason) in [
+        (vec![], "must hold at least one coin"),
+        (vec![coin(0, "untrn")], "coin amounts must be non-zero"),
+        (
+            vec![coin(1, "untrn"), coin(2, "untrn")],
+            "denoms must be distinct",
+        ),
+    ] {
+        assert_eq!(
+            validate_icq_deposit(&deposit),
+            Err(ValidationError::InvalidIcqDeposit(reason))
+        );
+    }
+}
+
+#[test]
+fn execute_receipts_read_from_reply_data() {
+    #[cw_serde]
+    enum ExecuteMsg {
+        TransferFunds { memo: Option<String> },
+        ClaimRewards {},
+    }
+
+    assert_eq!(
+        action_name(&ExecuteMsg::TransferFunds { memo: None }).unwrap(),
+        "transfer_funds"
+    );
+    assert_eq!(
+        action_name(&ExecuteMsg::ClaimRewards {}).unwrap(),
+        "claim_rewards"
+    );
+
+    let events = [
+        Event::new("fees_deposited"),
+        Event::new("transfer_requested").add_attribute("ica_idx", "3"),
+    ];
+
+    let receipt = ExecuteReceipt::from_events("transfer_funds", &events);
+
+    assert_eq!(receipt.ica_idx, Some(3));
+    assert_eq!(receipt.seq_num, None);
+
+    let reply = testing::execute_reply(1, &to_vec(&receipt).unwrap());
+
+    assert_eq!(parse_execute_receipt(reply).unwrap(), receipt);
+}
+
+fn test_ibc_fee() -> IbcFee {
+    IbcFee {
+        recv_fee: vec![],
+        ack_fee: vec![coin(1_000, "untrn")],
+        timeout_fee: vec![coin(1_000, "untrn")],
+    }
+}
+
+/// Assert `msg` serializes to the `golden` JSON as sent to the chain, the order of the fields aside
+fn assert_serialized_as(msg: &NeutronMsg, golden: &str) {
+    let serialized: serde_json::Value = serde_json::from_slice(&to_vec(msg).unwrap()).unwrap();
+
+    let golden: serde_json::Value = serde_json::from_str(golden).unwrap();
+
+    assert_eq!(serialized, golden);
+}
+
+#[test]
+fn ibc_transfers_built_from_config() {
+    let config = TransferConfig {
+        source_channel: "channel-0".to_owned(),
+        sender: "contract".to_owned(),
+    };
+
+    let by_height = msg_builders::ibc_transfer(
+        &config,
+        "ica",
+        coin(10, "untrn"),
+        TimeoutPolicy::Height {
+            revision_number: 2,
+            revision_height: 500,
+        },
+        Some("memo".to_owned()),
+        test_ibc_fee(),
+    );
+
+    let golden = r#"{"ibc_transfer":{
+        "source_port":"transfer",
+        "source_channel":"channel-0",
+        "sender":"contract",
+        "receiver":"ica",
+        "token":{"denom":"untrn","amount":"10"},
+        "timeout_height":{"revision_number":2,"revision_height":500},
+        "timeout_timestamp":0,
+        "memo":"memo",
+        "fee":{
+            "recv_fee":[],
+            "ack_fee":[{"denom":"untrn","amount":"1000"}],
+            "timeout_fee":[{"denom":"untrn","amount":"1000"}]
+        }
+    }}"#;
+
+    assert_serialized_as(&by_height, golden);
+
+    let by_timestamp = msg_builders::ibc_transfer(
+        &config,
+        "ica",
+        coin(10, "untrn"),
+        TimeoutPolicy::Timestamp(1_700_000_000_000_000_000),
+        None,
+        test_ibc_fee(),
+    );
+
+    let golden = r#"{"ibc_transfer":{
+        "source_port":"transfer",
+        "source_channel":"channel-0",
+        "sender":"contract",
+        "receiver":"ica",
+        "token":{"denom":"untrn","amount":"10"},
+        "timeout_height":{"revision_number":null,"revision_height":null},
+        "timeout_timestamp":1700000000000000000,
+        "memo":"",
+        "fee":{
+            "recv_fee":[],
+            "ack_fee":[{"denom":"untrn","amount":"1000"}],
+            "timeout_fee":[{"denom":"untrn","amount":"1000"}]
+        }
+    }}"#;
+
+    assert_serialized_as(&by_timestamp, golden);
+}
+
+#[test]
+fn ica_txs_built_from_config() {
+    let config = IcaTxConfig {
+        connection_id: "connection-0".to_owned(),
+        timeout_seconds: 600,
+    };
+
+    let msg = ProtobufAny {
+        type_url: "/cosmos.bank.v1beta1.MsgSend".to_owned(),
+        value: Binary::from(b"send".as_slice()),
+    };
+
+    let submit_tx = msg_builders::submit_tx(&config, "3", vec![msg], test_ibc_fee());
+
+    let golden = r#"{"submit_tx":{
+        "connection_id":"connection-0",
+        "interchain_account_id":"3",
+        "msgs":[{"type_url":"/cosmos.bank.v1beta1.MsgSend","value":"c2VuZA=="}],
+        "memo":"",
+        "timeout":600,
+        "fee":{
+            "recv_fee":[],
+            "ack_fee":[{"denom":"untrn","amount":"1000"}],
+            "timeout_fee":[{"denom":"untrn","amount":"1000"}]
+        }
+    }}"#;
+
+    assert_serialized_as(&submit_tx, golden);
+}
+
+#[test]
+fn staking_msgs_encoded_as_cosmos_sdk_msgs() {
+    let delegate = msg_builders::staking_msg(
+        msg_builders::MSG_DELEGATE_TYPE_URL,
+        "cosmos1".to_owned(),
+        "cosmosvaloper".to_owned(),
+        coin(5, "uatom"),
+    );
+
+    assert_eq!(delegate.type_url, "/cosmos.staking.v1beta1.MsgDelegate");
+
+    // delegator, validator & amount at fields 1, 2 & 3
+    assert_eq!(
+        delegate.value,
+        Binary::from_base64("Cgdjb3Ntb3MxEg1jb3Ntb3N2YWxvcGVyGgoKBXVhdG9tEgE1").unwrap()
+    );
+
+    let undelegate = msg_builders::staking_msg(
+        msg_builders::MSG_UNDELEGATE_TYPE_URL,
+        "cosmos1".to_owned(),
+        "cosmosvaloper".to_owned(),
+        coin(5, "uatom"),
+    );
+
+    assert_eq!(undelegate.type_url, "/cosmos.staking.v1beta1.MsgUndelegate");
+    assert_eq!(undelegate.value, delegate.value);
+}
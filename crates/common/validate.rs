@@ -0,0 +1,265 @@
+//! Offline validation of instantiation parameters, catching malformed configuration before gas is spent.
+
+use cosmwasm_std::Coin;
+
+/// Bounds of the ICQ update period in blocks
+pub const MIN_ICQ_UPDATE_PERIOD: u64 = 1;
+pub const MAX_ICQ_UPDATE_PERIOD: u64 = 100_000;
+
+/// Maximum length in bytes of a user supplied IBC transfer memo
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// The maximum length in bytes of an ICS-20 transfer memo accepted by ibc-go
+pub const MAX_IBC_MEMO_LEN: usize = 32_768;
+
+/// Maximum length in bytes of a user supplied label & note
+pub const MAX_LABEL_LEN: usize = 64;
+pub const MAX_NOTE_LEN: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("invalid denom {denom:?}: {reason}")]
+    InvalidDenom { denom: String, reason: &'static str },
+    #[error("invalid connection id {0:?}, expected connection-<n>")]
+    InvalidConnectionId(String),
+    #[error("invalid channel id {0:?}, expected channel-<n>")]
+    InvalidChannelId(String),
+    #[error("icq update period of {0} blocks is not between {MIN_ICQ_UPDATE_PERIOD} and {MAX_ICQ_UPDATE_PERIOD}")]
+    IcqUpdatePeriodOutOfBounds(u64),
+    #[error("invalid memo: {0}")]
+    InvalidMemo(&'static str),
+    #[error("invalid label: {0}")]
+    InvalidLabel(&'static str),
+    #[error("label of {0} bytes exceeds the limit of {MAX_LABEL_LEN} bytes")]
+    LabelTooLong(usize),
+    #[error("invalid note: {0}")]
+    InvalidNote(&'static str),
+    #[error("note of {0} bytes exceeds the limit of {MAX_NOTE_LEN} bytes")]
+    NoteTooLong(usize),
+    #[error("invalid remote address {0:?}, expected a bech32 address")]
+    InvalidRemoteAddress(String),
+    #[error("invalid bech32 prefix {0:?}, expected 1 to 83 lower case alphanumeric characters")]
+    InvalidBech32Prefix(String),
+    #[error("memo of {len} bytes exceeds the limit of {max_len} bytes")]
+    MemoTooLong { len: usize, max_len: usize },
+    #[error("invalid icq deposit: {0}")]
+    InvalidIcqDeposit(&'static str),
+}
+
+/// Checks the denom against the cosmos-sdk denom format & the `ibc/<hash>` format of ICS-20 denoms
+pub fn validate_denom(denom: &str) -> Result<(), ValidationError> {
+    let invalid = |reason| ValidationError::InvalidDenom {
+        denom: denom.to_owned(),
+        reason,
+    };
+
+    if !(3..=128).contains(&denom.len()) {
+        return Err(invalid("must be between 3 and 128 characters"));
+    }
+
+    if !denom.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(invalid("must start with a letter"));
+    }
+
+    if !denom
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c))
+    {
+        return Err(invalid("contains invalid characters"));
+    }
+
+    if let Some(hash) = denom.strip_prefix("ibc/") {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid("ibc denoms must be ibc/<sha256 hex hash>"));
+        }
+    }
+
+    Ok(())
+}
+
+fn has_numeric_suffix(id: &str, prefix: &str) -> bool {
+    id.strip_prefix(prefix)
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+pub fn validate_connection_id(connection_id: &str) -> Result<(), ValidationError> {
+    if !has_numeric_suffix(connection_id, "connection-") {
+        return Err(ValidationError::InvalidConnectionId(
+            connection_id.to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn validate_channel_id(channel_id: &str) -> Result<(), ValidationError> {
+    if !has_numeric_suffix(channel_id, "channel-") {
+        return Err(ValidationError::InvalidChannelId(channel_id.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Checks a user supplied IBC transfer memo is plain text, JSON object memos are executed by middleware
+/// on the receiving chain (IBC hooks, packet forwarding) so are never passed through
+pub fn validate_transfer_memo(memo: &str) -> Result<(), ValidationError> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(ValidationError::MemoTooLong {
+            len: memo.len(),
+            max_len: MAX_MEMO_LEN,
+        });
+    }
+
+    if memo.trim_start().starts_with('{') {
+        return Err(ValidationError::InvalidMemo(
+            "JSON objects are reserved for IBC middleware",
+        ));
+    }
+
+    validate_memo_chars(memo)
+}
+
+/// Checks a memo has no control characters other than newlines & tabs, which some chains reject
+pub fn validate_memo_chars(memo: &str) -> Result<(), ValidationError> {
+    if memo
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\t'))
+    {
+        return Err(ValidationError::InvalidMemo("contains control characters"));
+    }
+
+    Ok(())
+}
+
+/// The byte size limits of the memos built for IBC hooks & packet-forward-middleware, an oversized memo is only
+/// rejected by the remote chain once the fee of the tx carrying it has been spent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoLimits {
+    /// The limit of the IBC hook memo executed on arrival of the transfer
+    pub max_hook_memo_len: usize,
+    /// The limit of the memo of the submitted transfer, nesting the memo of every forwarded transfer
+    pub max_memo_len: usize,
+}
+
+impl Default for MemoLimits {
+    fn default() -> Self {
+        Self {
+            max_hook_memo_len: MAX_IBC_MEMO_LEN,
+            max_memo_len: MAX_IBC_MEMO_LEN,
+        }
+    }
+}
+
+/// Checks a built memo is within `max_len` bytes & has no control characters
+pub fn validate_built_memo(memo: &str, max_len: usize) -> Result<(), ValidationError> {
+    if memo.len() > max_len {
+        return Err(ValidationError::MemoTooLong {
+            len: memo.len(),
+            max_len,
+        });
+    }
+
+    validate_memo_chars(memo)
+}
+
+/// Checks a user supplied label is a single line of at most `MAX_LABEL_LEN` bytes
+pub fn validate_label(label: &str) -> Result<(), ValidationError> {
+    if label.trim().is_empty() {
+        return Err(ValidationError::InvalidLabel("must not be blank"));
+    }
+
+    if label.len() > MAX_LABEL_LEN {
+        return Err(ValidationError::LabelTooLong(label.len()));
+    }
+
+    if label.chars().any(char::is_control) {
+        return Err(ValidationError::InvalidLabel("contains control characters"));
+    }
+
+    Ok(())
+}
+
+/// Checks a user supplied note is at most `MAX_NOTE_LEN` bytes, line breaks are allowed
+pub fn validate_note(note: &str) -> Result<(), ValidationError> {
+    if note.len() > MAX_NOTE_LEN {
+        return Err(ValidationError::NoteTooLong(note.len()));
+    }
+
+    if note.chars().any(|c| c.is_control() && c != '\n') {
+        return Err(ValidationError::InvalidNote("contains control characters"));
+    }
+
+    Ok(())
+}
+
+/// Checks the shape of a bech32 address on a remote chain, its prefix & checksum are not known locally
+pub fn validate_remote_address(address: &str) -> Result<(), ValidationError> {
+    let is_bech32 = (8..=90).contains(&address.len())
+        && address.rsplit_once('1').is_some_and(|(hrp, data)| {
+            !hrp.is_empty()
+                && data.len() >= 6
+                && hrp
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && data
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && !data.contains(['1', 'b', 'i', 'o'])
+        });
+
+    if !is_bech32 {
+        return Err(ValidationError::InvalidRemoteAddress(address.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Checks a remote chain's bech32 address prefix, that addresses can be encoded under
+pub fn validate_bech32_prefix(prefix: &str) -> Result<(), ValidationError> {
+    let is_prefix = (1..=83).contains(&prefix.len())
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+
+    if !is_prefix {
+        return Err(ValidationError::InvalidBech32Prefix(prefix.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Checks an ICQ deposit given in place of the interchainqueries params holds coins of distinct valid denoms,
+/// none of them zero
+pub fn validate_icq_deposit(coins: &[Coin]) -> Result<(), ValidationError> {
+    if coins.is_empty() {
+        return Err(ValidationError::InvalidIcqDeposit(
+            "must hold at least one coin",
+        ));
+    }
+
+    for (idx, coin) in coins.iter().enumerate() {
+        validate_denom(&coin.denom)?;
+
+        if coin.amount.is_zero() {
+            return Err(ValidationError::InvalidIcqDeposit(
+                "coin amounts must be non-zero",
+            ));
+        }
+
+        if coins[..idx].iter().any(|c| c.denom == coin.denom) {
+            return Err(ValidationError::InvalidIcqDeposit(
+                "denoms must be distinct",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_icq_update_period(period: u64) -> Result<(), ValidationError> {
+    if !(MIN_ICQ_UPDATE_PERIOD..=MAX_ICQ_UPDATE_PERIOD).contains(&period) {
+        return Err(ValidationError::IcqUpdatePeriodOutOfBounds(period));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::{env, fs, path::PathBuf};
+
+#[allow(dead_code)]
+mod localnet {
+    include!("localnet.rs");
+}
+
+use localnet::{REMOTE_DENOM, TRANSFER_CHANNEL, TRANSFER_PORT};
+
+fn main() {
+    println!("cargo:rerun-if-changed=localnet.rs");
+
+    // ICS-20 vouchers are denominated by the hash of the denom trace
+    let trace = format!("{TRANSFER_PORT}/{TRANSFER_CHANNEL}/{REMOTE_DENOM}");
+
+    let hash = hex::encode_upper(hmac_sha256::Hash::hash(trace.as_bytes()));
+
+    let derived = format!(
+        "/// The ICS-20 denom of `REMOTE_DENOM` once transferred to neutron over `TRANSFER_CHANNEL`\n\
+         pub const HOST_IBC_DENOM: &str = \"ibc/{hash}\";\n"
+    );
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("set by cargo"));
+
+    fs::write(out_dir.join("derived.rs"), derived).expect("write derived constants");
+}
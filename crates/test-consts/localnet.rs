@@ -0,0 +1,23 @@
+// The layout of the local network started by `cargo x start-local`,
+// update these when the localnet configuration changes and everything else is derived.
+
+/// The connection between neutron & gaia established by hermes
+pub const CONNECTION_ID: &str = "connection-0";
+
+/// The ICS-20 transfer port
+pub const TRANSFER_PORT: &str = "transfer";
+
+/// The ICS-20 channel between neutron & gaia, the same id on both ends
+pub const TRANSFER_CHANNEL: &str = "channel-0";
+
+/// The staking denom of gaia
+pub const REMOTE_DENOM: &str = "uatom";
+
+/// The genesis validator of gaia
+pub const REMOTE_VALIDATOR: &str = "cosmosvaloper18hl5c9xn5dze2g50uaw0l2mr02ew57zk0auktn";
+
+/// The mnemonics of the funded keys of the localnet, `demowallet1` & `demowallet2`, present on both chains
+pub const KEY_MNEMONICS: [&str; 2] = [
+    "banner spread envelope side kite person disagree path silver will brother under couch edit food venture squirrel civil budget number acquire point work mass",
+    "veteran try aware erosion drink dance decade comic dawn museum release episode original list ability owner size tuition surface ceiling depth seminar capable only",
+];
@@ -0,0 +1,5 @@
+//! Constants describing the local network used by tests, see `localnet.rs`.
+
+include!("localnet.rs");
+
+include!(concat!(env!("OUT_DIR"), "/derived.rs"));
@@ -1,17 +1,28 @@
-use std::{sync::OnceLock, time::SystemTime};
+pub mod script;
 
-use ::multiple_ica_icq::msgs::{IcaLastBalance, IcaLastDelegation, IcaLastDelegationResponse};
+use std::{
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
+use ::multiple_ica_icq::msgs::{IcaLastBalance, IcaLastDelegation};
 use anyhow::Result;
-use serde::Serialize;
-use xshell::Shell;
+use client::{Conn, RoundtripClient};
+use cosmrs::crypto::secp256k1::SigningKey;
+use serde::{Deserialize, Serialize};
+use test_consts::{
+    CONNECTION_ID, HOST_IBC_DENOM, KEY_MNEMONICS, REMOTE_DENOM, REMOTE_VALIDATOR, TRANSFER_CHANNEL,
+};
+use xshell::{cmd, Shell};
 
 use cosmwasm_std::Coin;
 use cosmwasm_xtask::{
-    execute, instantiate,
     key::Key,
     network::{gas::Price as GasPrice, neutron::local::GAIA_CHAIN_ID, Instance},
-    query, store, wait_for_blocks, Initialize, Network, NeutronLocalnet,
+    query, wait_for_blocks, Initialize, Network, NeutronLocalnet,
 };
+use script::{execute, instantiate, store};
 
 pub struct Ctx {
     pub sh: Shell,
@@ -59,9 +70,232 @@ pub fn setup() -> Result<Ctx> {
         }
     }
 
+    if std::env::var("E2E_RECORD").is_ok() {
+        script::start_recording();
+    }
+
     Ok(Ctx { sh, network })
 }
 
+/// Save the txs recorded during `test_case` as `$E2E_RECORD/{test_case}.json`, if recording
+pub fn save_recording(test_case: &str) -> Result<()> {
+    let Some(script) = script::finish_recording() else {
+        return Ok(());
+    };
+
+    let mut path = PathBuf::from(std::env::var("E2E_RECORD")?);
+
+    std::fs::create_dir_all(&path)?;
+
+    path.push(format!("{test_case}.json"));
+
+    eprintln!(
+        "saving {} recorded steps to {}",
+        script.steps.len(),
+        path.display()
+    );
+
+    script.save(&path)
+}
+
+/// How often to poll the node when waiting for a height
+const HEIGHT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The tendermint RPC endpoint of the neutron node
+pub fn rpc_url(sh: &Shell, network: &dyn Network) -> Result<String> {
+    Ok(network
+        .node_uri(sh)?
+        .to_string()
+        .replace("tcp://", "http://"))
+}
+
+/// Query the latest block height of the neutron node
+pub fn current_height(sh: &Shell, network: &dyn Network) -> Result<u64> {
+    #[derive(Deserialize)]
+    struct SyncInfo {
+        latest_block_height: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Status {
+        sync_info: SyncInfo,
+    }
+
+    #[derive(Deserialize)]
+    struct StatusResponse {
+        result: Status,
+    }
+
+    let rpc_url = rpc_url(sh, network)?;
+
+    let status = cmd!(sh, "curl -s {rpc_url}/status").quiet().read()?;
+
+    let res: StatusResponse = cosmwasm_std::from_slice(status.as_bytes())?;
+
+    Ok(res.result.sync_info.latest_block_height.parse()?)
+}
+
+/// Block until the neutron node has reached `height`, returning the height actually reached
+pub fn wait_until_height(sh: &Shell, network: &dyn Network, height: u64) -> Result<u64> {
+    loop {
+        let current = current_height(sh, network)?;
+
+        if current >= height {
+            return Ok(current);
+        }
+
+        std::thread::sleep(HEIGHT_POLL_INTERVAL);
+    }
+}
+
+/// Query the neutron node over ABCI at the gRPC `path` with the protobuf encoded `request`, None if nothing was found
+pub fn abci_query(
+    sh: &Shell,
+    network: &dyn Network,
+    path: &str,
+    request: &[u8],
+) -> Result<Option<cosmwasm_std::Binary>> {
+    #[derive(Deserialize)]
+    struct AbciResponse {
+        value: Option<cosmwasm_std::Binary>,
+    }
+
+    #[derive(Deserialize)]
+    struct AbciQuery {
+        response: AbciResponse,
+    }
+
+    #[derive(Deserialize)]
+    struct AbciQueryResponse {
+        result: AbciQuery,
+    }
+
+    let rpc_url = rpc_url(sh, network)?;
+
+    let data: String = request.iter().map(|b| format!("{b:02x}")).collect();
+
+    let url = format!("{rpc_url}/abci_query?path=%22{path}%22&data=0x{data}");
+
+    let res = cmd!(sh, "curl -s {url}").quiet().read()?;
+
+    let res: AbciQueryResponse = cosmwasm_std::from_slice(res.as_bytes())?;
+
+    Ok(res.result.response.value)
+}
+
+/// The ids of the failed sudo calls of `contract` recorded by the contractmanager module, queried over ABCI
+pub fn contract_failure_ids(sh: &Shell, network: &dyn Network, contract: &str) -> Result<Vec<u64>> {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryFailuresRequest {
+        #[prost(string, tag = "1")]
+        address: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Failure {
+        #[prost(string, tag = "1")]
+        address: String,
+        #[prost(uint64, tag = "2")]
+        id: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryFailuresResponse {
+        #[prost(message, repeated, tag = "1")]
+        failures: Vec<Failure>,
+    }
+
+    let request = QueryFailuresRequest {
+        address: contract.to_owned(),
+    };
+
+    let Some(value) = abci_query(
+        sh,
+        network,
+        "/neutron.contractmanager.Query/AddressFailures",
+        &request.encode_to_vec(),
+    )?
+    else {
+        return Ok(vec![]);
+    };
+
+    let failures = QueryFailuresResponse::decode(value.as_slice())?;
+
+    Ok(failures.failures.into_iter().map(|f| f.id).collect())
+}
+
+/// The sequence number the next packet sent on `port_id`/`channel_id` will carry, queried over ABCI
+pub fn next_sequence_send(
+    sh: &Shell,
+    network: &dyn Network,
+    port_id: &str,
+    channel_id: &str,
+) -> Result<u64> {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryNextSequenceSendRequest {
+        #[prost(string, tag = "1")]
+        port_id: String,
+        #[prost(string, tag = "2")]
+        channel_id: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryNextSequenceSendResponse {
+        #[prost(uint64, tag = "1")]
+        next_sequence_send: u64,
+    }
+
+    let request = QueryNextSequenceSendRequest {
+        port_id: port_id.to_owned(),
+        channel_id: channel_id.to_owned(),
+    };
+
+    let value = abci_query(
+        sh,
+        network,
+        "/ibc.core.channel.v1.Query/NextSequenceSend",
+        &request.encode_to_vec(),
+    )?
+    .ok_or_else(|| anyhow::anyhow!("channel {port_id}/{channel_id} not found"))?;
+
+    Ok(QueryNextSequenceSendResponse::decode(value.as_slice())?.next_sequence_send)
+}
+
+/// An RPC connection to the neutron node signing with the localnet key `key`, whose mnemonic is `mnemonic`
+pub fn rpc_conn(sh: &Shell, network: &dyn Network, key: &Key, mnemonic: &str) -> Result<Conn> {
+    let mnemonic = bip32::Mnemonic::new(mnemonic, bip32::Language::English)?;
+
+    let signing_key = SigningKey::derive_from_path(
+        mnemonic.to_seed("").as_bytes(),
+        &"m/44'/118'/0'/0/0".parse()?,
+    )
+    .map_err(|err| anyhow::anyhow!("{err:?}"))?;
+
+    let conn = Conn::connect(
+        &rpc_url(sh, network)?,
+        signing_key,
+        client::GasPrice::new(0.025, "untrn"),
+    )?;
+
+    anyhow::ensure!(
+        conn.address() == key.address(),
+        "the mnemonic is not the one of {key}"
+    );
+
+    Ok(conn)
+}
+
+/// Block until `n` more blocks have been produced, returning the height reached
+pub fn wait_blocks(sh: &Shell, network: &dyn Network, n: u64) -> Result<u64> {
+    let start = current_height(sh, network)?;
+
+    wait_until_height(sh, network, start + n)
+}
+
 macro_rules! test_contract {
     ($f:ident) => {
         mod $f {
@@ -73,6 +307,8 @@ macro_rules! test_contract {
 
                 super::$f(&ctx.sh, &ctx.network, key)?;
 
+                super::save_recording(stringify!($f))?;
+
                 Ok(())
             }
         }
@@ -92,6 +328,8 @@ macro_rules! test_contract {
 
                 super::$f(&ctx.sh, &ctx.network, key)?;
 
+                super::save_recording(stringify!($f))?;
+
                 Ok(())
             }
         }
@@ -109,7 +347,10 @@ pub fn label(prefix: &str) -> String {
 
 pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
     use ::multiple_ica_icq::msgs::{
-        IcaLastBalanceResponse, IcaMetadataResponse, InstantiateMsg, QueryMsg,
+        AllLastBalancesResponse, AllLastDelegationsResponse, IcaLastUnbonding,
+        IcaLastUnbondingResponse, IcaMetadataResponse, IcaPendingRewards,
+        IcaPendingRewardsResponse, IcaRegistrationStatus, IcaStatus, InstantiateMsg, QueryMsg,
+        RegistrationStatus, RegistrationStatusResponse,
     };
 
     let contract_path = "artifacts/multiple_ica_icq.wasm";
@@ -121,15 +362,21 @@ pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<
     let code_id = store(contract_path).send(sh, network, key)?;
 
     let init_msg = InstantiateMsg {
-        connection_id: "connection-0".to_owned(),
+        connection_id: CONNECTION_ID.to_owned(),
         ica_set_size,
         icq_update_period: 6,
-        balance_icq_denom: "uatom".to_owned(),
-        delegations_icq_validator: "cosmosvaloper18hl5c9xn5dze2g50uaw0l2mr02ew57zk0auktn"
-            .to_owned(),
+        balance_icq_denom: REMOTE_DENOM.to_owned(),
+        additional_balance_icq_denoms: None,
+        delegations_icq_validators: vec![REMOTE_VALIDATOR.to_owned()],
+        icq_deposit_override: None,
+        icq_refund_address: None,
+        ica_owners: None,
     };
 
-    let deposit = 1_000_000 * u128::from(ica_set_size) * 2;
+    let deposit = 1_000_000 * u128::from(ica_set_size * ::multiple_ica_icq::ICQS_PER_ICA);
+
+    // catch malformed parameters before spending gas on a failing instantiate
+    init_msg.validate()?;
 
     eprintln!(
         "instantiating contract code {code_id} with {deposit}untrn & params: {}",
@@ -137,33 +384,33 @@ pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<
     );
 
     let contract = instantiate(code_id, &label("multiple_ica_icq"), init_msg)
-        // 2 ICQ deposits per ICA
+        // an ICQ deposit per ICQ of each ICA
         .amount(deposit, "untrn")
         .send(sh, network, key)?;
 
     eprintln!("waiting for ICAs and ICQs to be registered...");
 
-    let mut ica_idx = 0;
-
     let mut block_count = 0;
 
     loop {
-        let ica_metadata_res: IcaMetadataResponse =
-            query(sh, network, &contract, &QueryMsg::IcaMetadata { ica_idx })?;
+        let RegistrationStatusResponse { icas } =
+            query(sh, network, &contract, &QueryMsg::RegistrationStatus {})?;
 
-        if let Some(metadata) = ica_metadata_res.metadata {
-            eprintln!(
-                "multiple_ica_icq: ICA {ica_idx} registered: {}",
-                pretty(&metadata)
-            );
+        for IcaRegistrationStatus { ica_idx, status } in &icas {
+            if let RegistrationStatus::Failed { reason } = status {
+                anyhow::bail!("multiple_ica_icq: ICA {ica_idx} registration failed: {reason}");
+            }
+        }
 
-            ica_idx += 1;
+        let registered = icas
+            .iter()
+            .filter(|ica| ica.status == RegistrationStatus::IcqRegistered)
+            .count();
 
-            if ica_idx == ica_set_size {
-                break;
-            }
+        eprintln!("multiple_ica_icq: {registered}/{ica_set_size} ICAs registered with their ICQs");
 
-            continue;
+        if registered == icas.len() {
+            break;
         }
 
         eprintln!("waiting for another block...");
@@ -173,7 +420,24 @@ pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<
         block_count += 1;
     }
 
-    eprintln!("all {ica_set_size} ICAs with 2 ICQs each registered in {block_count} blocks");
+    for ica_idx in 0..ica_set_size {
+        let IcaMetadataResponse { metadata } =
+            query(sh, network, &contract, &QueryMsg::IcaMetadata { ica_idx })?;
+
+        assert!(metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.status == IcaStatus::Active));
+
+        eprintln!(
+            "multiple_ica_icq: ICA {ica_idx} registered: {}",
+            pretty(&metadata)
+        );
+    }
+
+    eprintln!(
+        "all {ica_set_size} ICAs with {} ICQs each registered in {block_count} blocks",
+        ::multiple_ica_icq::ICQS_PER_ICA
+    );
 
     eprintln!("waiting for first balance ICQ results to be posted...");
 
@@ -182,32 +446,44 @@ pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<
     let mut block_count = 0;
 
     loop {
-        if let IcaLastBalanceResponse {
-            last_balance:
-                Some(IcaLastBalance {
-                    balance,
-                    address,
-                    last_submitted_result_local_height,
-                }),
-        } = query(
+        // every ICA from the first without a result is queried in one go, walking them in order
+        let AllLastBalancesResponse { balances } = query(
             sh,
             network,
             &contract,
-            &QueryMsg::IcaLastBalance { ica_idx },
-        )? {
-            let balance_msg = balance
-                .as_ref()
-                .map_or_else(|| "empty balance".to_owned(), Coin::to_string);
+            &QueryMsg::AllLastBalances {
+                start_after: ica_idx.checked_sub(1),
+                limit: Some(ica_set_size),
+            },
+        )?;
+
+        for entry in balances {
+            let Some(IcaLastBalance {
+                balances,
+                address,
+                last_submitted_result_local_height,
+            }) = entry.last_balance
+            else {
+                break;
+            };
+
+            let balance_msg = if balances.is_empty() {
+                "empty balance".to_owned()
+            } else {
+                balances
+                    .iter()
+                    .map(Coin::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
 
             eprintln!("multiple_ica_icq: ICA {ica_idx} {address} last balance: {balance_msg} updated at height {last_submitted_result_local_height}");
 
             ica_idx += 1;
+        }
 
-            if ica_idx == 10 {
-                break;
-            }
-
-            continue;
+        if ica_idx == ica_set_size {
+            break;
         }
 
         eprintln!("waiting for another block...");
@@ -226,31 +502,38 @@ pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<
     let mut block_count = 0;
 
     loop {
-        if let IcaLastDelegationResponse {
-            last_delegation:
-                Some(IcaLastDelegation {
-                    delegation,
-                    last_submitted_result_local_height,
-                }),
-        } = query(
+        let AllLastDelegationsResponse { delegations } = query(
             sh,
             network,
             &contract,
-            &QueryMsg::IcaLastDelegation { ica_idx },
-        )? {
-            let delegation_msg = delegation
-                .as_ref()
-                .map_or_else(|| "not yet delegated".to_owned(), pretty);
+            &QueryMsg::AllLastDelegations {
+                start_after: ica_idx.checked_sub(1),
+                limit: Some(ica_set_size),
+            },
+        )?;
 
-            eprintln!("multiple_ica_icq: ICA {ica_idx} last delegation: {delegation_msg} updated at height {last_submitted_result_local_height}");
+        for entry in delegations {
+            let Some(IcaLastDelegation {
+                delegations,
+                last_submitted_result_local_height,
+            }) = entry.last_delegation
+            else {
+                break;
+            };
 
-            ica_idx += 1;
+            let delegations_msg = if delegations.is_empty() {
+                "not yet delegated".to_owned()
+            } else {
+                pretty(&delegations)
+            };
 
-            if ica_idx == 10 {
-                break;
-            }
+            eprintln!("multiple_ica_icq: ICA {ica_idx} last delegations: {delegations_msg} updated at height {last_submitted_result_local_height}");
 
-            continue;
+            ica_idx += 1;
+        }
+
+        if ica_idx == ica_set_size {
+            break;
         }
 
         eprintln!("waiting for another block...");
@@ -262,135 +545,650 @@ pub fn multiple_ica_icq(sh: &Shell, network: &dyn Network, key: &Key) -> Result<
 
     eprintln!("all {ica_set_size} delegation ICQs have results after {block_count} blocks");
 
-    Ok(())
-}
+    eprintln!("waiting for first unbonding delegations ICQ results to be posted");
 
-test_contract!(multiple_ica_icq);
+    let mut block_count = 0;
 
-pub fn ibc_transfer_atom_to_neutron(Ctx { sh, network }: &Ctx, key: &Key) -> Result<()> {
-    let chain_id = GAIA_CHAIN_ID.to_owned().into();
+    for ica_idx in 0..ica_set_size {
+        let last_unbonding = loop {
+            let IcaLastUnbondingResponse { last_unbonding, .. } = query(
+                sh,
+                network,
+                &contract,
+                &QueryMsg::IcaLastUnbonding { ica_idx },
+            )?;
+
+            if let Some(last_unbonding) = last_unbonding {
+                break last_unbonding;
+            }
 
-    let node_uri = network.gaiad.node_uri();
+            eprintln!("waiting for another block...");
 
-    let gas = GasPrice::new(0.02, "uatom").units(200_000);
+            wait_for_blocks(sh, network)?;
 
-    network
-        .gaiad
-        .cli(sh)
-        .tx(key, &chain_id, &node_uri)
-        .ibc_transfer("channel-0", key.address(), 10_000_000_000, "uatom")
-        .execute(&gas)?;
+            block_count += 1;
+        };
+
+        let IcaLastUnbonding {
+            unbondings,
+            last_submitted_result_local_height,
+        } = last_unbonding;
+
+        let unbondings_msg = if unbondings.is_empty() {
+            "not unbonding".to_owned()
+        } else {
+            pretty(&unbondings)
+        };
+
+        eprintln!("multiple_ica_icq: ICA {ica_idx} last unbonding delegations: {unbondings_msg} updated at height {last_submitted_result_local_height}");
+    }
+
+    eprintln!(
+        "all {ica_set_size} unbonding delegations ICQs have results after {block_count} blocks"
+    );
+
+    eprintln!("waiting for first rewards ICQ results to be posted");
+
+    let mut block_count = 0;
+
+    for ica_idx in 0..ica_set_size {
+        let pending_rewards = loop {
+            let IcaPendingRewardsResponse {
+                pending_rewards, ..
+            } = query(
+                sh,
+                network,
+                &contract,
+                &QueryMsg::IcaPendingRewards { ica_idx },
+            )?;
+
+            if let Some(pending_rewards) = pending_rewards {
+                break pending_rewards;
+            }
+
+            eprintln!("waiting for another block...");
+
+            wait_for_blocks(sh, network)?;
+
+            block_count += 1;
+        };
+
+        let IcaPendingRewards {
+            rewards,
+            last_submitted_result_local_height,
+        } = pending_rewards;
+
+        let rewards_msg = if rewards.is_empty() {
+            "no rewards".to_owned()
+        } else {
+            pretty(&rewards)
+        };
+
+        eprintln!("multiple_ica_icq: ICA {ica_idx} pending rewards: {rewards_msg} updated at height {last_submitted_result_local_height}");
+    }
+
+    eprintln!("all {ica_set_size} rewards ICQs have results after {block_count} blocks");
 
     Ok(())
 }
 
-pub fn ibc_transfer_roundtrip(sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
-    use ::ibc_transfer_roundtrip::msgs::{
-        ExecuteMsg, IcaLastBalance, IcaLastBalanceResponse, IcaMetadataResponse,
-        IcaTxStatusResponse, InstantiateMsg, QueryMsg,
+test_contract!(multiple_ica_icq);
+
+pub fn icq_update_cadence(sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
+    use ::multiple_ica_icq::msgs::{
+        IcaLastBalanceResponse, IcaMetadataResponse, IcaStatus, InstantiateMsg, QueryMsg,
     };
 
-    let contract_path = "artifacts/ibc_transfer_roundtrip.wasm";
+    // number of consecutive ICQ updates to observe
+    const UPDATES: usize = 4;
+
+    // acceptable deviation from the update period in blocks, accounting for relayer latency
+    const TOLERANCE: u64 = 3;
+
+    let icq_update_period = 6;
+
+    let contract_path = "artifacts/multiple_ica_icq.wasm";
 
     eprintln!("storing contract: {contract_path}");
 
     let code_id = store(contract_path).send(sh, network, key)?;
 
-    let ibc_atom_denom = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
-
     let init_msg = InstantiateMsg {
-        connection_id: "connection-0".to_owned(),
-        ibc_transfer_channel: "channel-0".to_owned(),
-        icq_update_period: 6,
-        remote_denom: "uatom".to_owned(),
-        host_ibc_denom: ibc_atom_denom.to_owned(),
+        connection_id: CONNECTION_ID.to_owned(),
+        ica_set_size: 1,
+        icq_update_period,
+        balance_icq_denom: REMOTE_DENOM.to_owned(),
+        additional_balance_icq_denoms: None,
+        delegations_icq_validators: vec![REMOTE_VALIDATOR.to_owned()],
+        icq_deposit_override: None,
+        icq_refund_address: None,
+        ica_owners: None,
     };
 
-    eprintln!(
-        "instantiating contract code {code_id} with params: {}",
-        pretty(&init_msg)
-    );
-
-    let contract =
-        instantiate(code_id, &label("ibc_transfer_roundtrip"), init_msg).send(sh, network, key)?;
-
-    eprintln!("instantiated contract with address: {contract}");
-
-    eprintln!("setting up an ICA for {key}");
+    init_msg.validate()?;
 
-    execute(&contract, ExecuteMsg::SetupIca {})
-        .amount(1_000_000, "untrn")
+    let contract = instantiate(code_id, &label("icq_update_cadence"), init_msg)
+        // an ICQ deposit per ICQ of the single ICA
+        .amount(
+            1_000_000 * u128::from(::multiple_ica_icq::ICQS_PER_ICA),
+            "untrn",
+        )
         .send(sh, network, key)?;
 
-    let mut block_count = 0;
+    eprintln!("waiting for the ICA and ICQs to be registered...");
 
     loop {
-        let ica_metadata_res: IcaMetadataResponse = query(
+        let res: IcaMetadataResponse = query(
             sh,
             network,
             &contract,
-            &QueryMsg::IcaMetadata {
-                owner: key.address().to_owned(),
-            },
+            &QueryMsg::IcaMetadata { ica_idx: 0 },
         )?;
 
-        if let Some(metadata) = ica_metadata_res.metadata {
-            if let Some((address, balance_icq)) = metadata.address.zip(metadata.balance_icq_id) {
-                eprintln!(
-                    "ICA {} registeration with address {address} and balance ICQ {balance_icq} took {block_count} blocks",
-                    metadata.ica_idx
-                );
-                break;
-            }
+        if res
+            .metadata
+            .is_some_and(|metadata| metadata.status == IcaStatus::Active)
+        {
+            break;
         }
 
-        eprintln!("waiting for another block...");
+        wait_blocks(sh, network, 1)?;
+    }
 
-        wait_for_blocks(sh, network)?;
+    eprintln!("recording the heights of {UPDATES} consecutive balance ICQ updates...");
 
-        block_count += 1;
+    let mut update_heights: Vec<u64> = vec![];
+
+    while update_heights.len() < UPDATES {
+        if let IcaLastBalanceResponse {
+            last_balance: Some(last_balance),
+            ..
+        } = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcaLastBalance { ica_idx: 0 },
+        )? {
+            let height = last_balance.last_submitted_result_local_height;
+
+            if update_heights.last() != Some(&height) {
+                eprintln!("balance ICQ updated at height {height}");
+
+                update_heights.push(height);
+            }
+        }
+
+        wait_blocks(sh, network, 1)?;
     }
 
-    let node_uri = network.node_uri(sh)?;
+    for interval in update_heights.windows(2).map(|w| w[1] - w[0]) {
+        assert!(
+            interval.abs_diff(icq_update_period) <= TOLERANCE,
+            "ICQ update interval of {interval} blocks is not within {TOLERANCE} blocks of the {icq_update_period} block update period"
+        );
+    }
 
-    eprintln!("waiting for IBC ATOM to be sent");
+    Ok(())
+}
 
-    let original_ibc_atom_balance = loop {
-        let balance = network
-            .cli(sh)?
-            .query(&node_uri)
-            .balance(key.address(), ibc_atom_denom)?;
+test_contract!(icq_update_cadence);
 
-        if balance >= 1_000_000_000 {
-            break balance;
-        }
+/// The deposit charged per ICQ by the interchainqueries module, in untrn
+pub fn icq_deposit(sh: &Shell, network: &dyn Network) -> Result<u128> {
+    use prost::Message;
 
-        eprintln!("waiting for another block...");
+    #[derive(Clone, PartialEq, Message)]
+    struct ProtoCoin {
+        #[prost(string, tag = "1")]
+        denom: String,
+        #[prost(string, tag = "2")]
+        amount: String,
+    }
 
-        wait_for_blocks(sh, network)?;
+    #[derive(Clone, PartialEq, Message)]
+    struct Params {
+        #[prost(uint64, tag = "1")]
+        query_submit_timeout: u64,
+        #[prost(message, repeated, tag = "2")]
+        query_deposit: Vec<ProtoCoin>,
+    }
 
-        block_count += 1;
-    };
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryParamsResponse {
+        #[prost(message, optional, tag = "1")]
+        params: Option<Params>,
+    }
 
-    eprintln!("{key} starting off with {original_ibc_atom_balance} IBC ATOM");
+    let value = abci_query(sh, network, "/neutron.interchainqueries.Query/Params", &[])?
+        .ok_or_else(|| anyhow::anyhow!("the interchainqueries params are queryable"))?;
 
-    eprintln!("transferring IBC ATOM to ICA");
+    let params = QueryParamsResponse::decode(value.as_slice())?
+        .params
+        .ok_or_else(|| anyhow::anyhow!("the interchainqueries params are set"))?;
 
-    execute(&contract, ExecuteMsg::TransferFunds {})
-        .amount(2000, "untrn")
-        .amount(1_000_000_000, ibc_atom_denom)
-        .send(sh, network, key)?;
+    let deposit = params
+        .query_deposit
+        .iter()
+        .find(|coin| coin.denom == "untrn")
+        .ok_or_else(|| anyhow::anyhow!("the ICQ deposit is charged in untrn"))?;
 
-    let mut block_count = 0;
+    Ok(deposit.amount.parse()?)
+}
 
-    loop {
-        if let IcaLastBalanceResponse {
-            last_balance:
-                Some(IcaLastBalance {
+/// Whether the interchainqueries module has the ICQ `query_id` registered
+pub fn icq_registered(sh: &Shell, network: &dyn Network, query_id: u64) -> Result<bool> {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct QueryRegisteredQueryRequest {
+        #[prost(uint64, tag = "1")]
+        query_id: u64,
+    }
+
+    let request = QueryRegisteredQueryRequest { query_id };
+
+    let value = abci_query(
+        sh,
+        network,
+        "/neutron.interchainqueries.Query/RegisteredQuery",
+        &request.encode_to_vec(),
+    )?;
+
+    Ok(value.is_some())
+}
+
+pub fn icq_deposit_accounting(sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
+    use ::ibc_transfer_roundtrip::msgs::{
+        ExecuteMsg, FeeBalanceResponse, IcaLastBalanceResponse, IcaMetadata, IcaMetadataResponse,
+        IcaStatus, IcqInfoResponse, InstantiateMsg, QueryMsg,
+    };
+
+    // blocks to wait for an ICA to be registered along with its balance ICQ
+    const REGISTRATION_WAIT_BLOCKS: u64 = 60;
+
+    // blocks to wait for the first balance ICQ result, which the teardown requires to show the ICA drained
+    const BALANCE_WAIT_BLOCKS: u64 = 30;
+
+    // the untrn attached to `RegisterTransfersQuery` on top of the ICQ deposit, credited to the owner's fee balance
+    const FEE_CREDIT: u128 = 5_000;
+
+    let icq_deposit = icq_deposit(sh, network)?;
+
+    eprintln!("the interchainqueries module charges a {icq_deposit}untrn deposit per ICQ");
+
+    let contract_path = "artifacts/ibc_transfer_roundtrip.wasm";
+
+    eprintln!("storing contract: {contract_path}");
+
+    let code_id = store(contract_path).send(sh, network, key)?;
+
+    let init_msg = InstantiateMsg {
+        connection_id: CONNECTION_ID.to_owned(),
+        ibc_transfer_channel: TRANSFER_CHANNEL.to_owned(),
+        icq_update_period: 6,
+        remote_denom: REMOTE_DENOM.to_owned(),
+        host_ibc_denom: HOST_IBC_DENOM.to_owned(),
+        additional_transfer_denoms: None,
+        admin: None,
+        timeout_seconds: None,
+        timeout_height: None,
+        timeout_revision_number: None,
+        transfer_limits: None,
+        max_memo_len: None,
+        max_hook_memo_len: None,
+        hash_retention_blocks: None,
+        icq_deposit_override: None,
+    };
+
+    init_msg.validate()?;
+
+    let contract =
+        instantiate(code_id, &label("icq_deposit_accounting"), init_msg).send(sh, network, key)?;
+
+    let owner = key.address().to_owned();
+
+    let node_uri = network.node_uri(sh)?;
+
+    let untrn_balance = |address: &str| -> Result<u128> {
+        let balance = network
+            .cli(sh)?
+            .query(&node_uri)
+            .balance(address, "untrn")?;
+
+        Ok(balance)
+    };
+
+    let metadata = || -> Result<Option<IcaMetadata>> {
+        let res: IcaMetadataResponse = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcaMetadata {
+                owner: owner.clone(),
+            },
+        )?;
+
+        Ok(res.metadata)
+    };
+
+    let fee_balance = || -> Result<u128> {
+        let res: FeeBalanceResponse = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::FeeBalance {
+                owner: owner.clone(),
+            },
+        )?;
+
+        Ok(res
+            .balance
+            .iter()
+            .find(|coin| coin.denom == "untrn")
+            .map_or(0, |coin| coin.amount.u128()))
+    };
+
+    // the contract holds the owner's fee balance & the setup deposit of an ICA until its balance ICQ is registered,
+    // every other untrn attached is held as an ICQ deposit by the interchainqueries module or refunded
+    let assert_held = |step: &str| -> Result<()> {
+        let setup_deposit = match metadata()? {
+            Some(metadata) if metadata.balance_icq_id.is_none() => icq_deposit,
+            _ => 0,
+        };
+
+        let held = untrn_balance(&contract)?;
+
+        let fees = fee_balance()?;
+
+        eprintln!(
+            "{step}: {held}untrn held, {fees}untrn of fees, {setup_deposit}untrn of setup deposit"
+        );
+
+        assert_eq!(
+            held,
+            fees + setup_deposit,
+            "{step}: the untrn held by the contract is not accounted for"
+        );
+
+        Ok(())
+    };
+
+    // the ICQs of the owner's ICA, each holding exactly the deposit charged for it
+    let registered_icq_ids = || -> Result<Vec<u64>> {
+        let res: IcqInfoResponse = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcqInfo {
+                owner: owner.clone(),
+            },
+        )?;
+
+        let icqs: Vec<_> = [res.balance, res.delegations, res.transfers]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        for icq in &icqs {
+            assert_eq!(
+                icq.deposit,
+                vec![Coin::new(icq_deposit, "untrn")],
+                "ICQ {} holds another deposit than the one charged",
+                icq.id
+            );
+        }
+
+        Ok(icqs.iter().map(|icq| icq.id).collect())
+    };
+
+    for cycle in 0..2 {
+        eprintln!("cycle {cycle}: setting up an ICA for {key}");
+
+        execute(&contract, ExecuteMsg::SetupIca {})
+            .amount(icq_deposit, "untrn")
+            .send(sh, network, key)?;
+
+        assert_held(&format!("cycle {cycle}: ICA setup"))?;
+
+        let mut waited = 0;
+
+        while !metadata()?.is_some_and(|metadata| {
+            metadata.status == IcaStatus::Active && metadata.balance_icq_id.is_some()
+        }) {
+            assert!(
+                waited < REGISTRATION_WAIT_BLOCKS,
+                "cycle {cycle}: the ICA was not registered after {REGISTRATION_WAIT_BLOCKS} blocks"
+            );
+
+            wait_blocks(sh, network, 1)?;
+
+            waited += 1;
+        }
+
+        eprintln!("cycle {cycle}: the ICA & its balance ICQ were registered after {waited} blocks");
+
+        assert_held(&format!("cycle {cycle}: balance ICQ registered"))?;
+
+        assert_eq!(registered_icq_ids()?.len(), 1);
+
+        execute(&contract, ExecuteMsg::RegisterTransfersQuery {})
+            .amount(icq_deposit + FEE_CREDIT, "untrn")
+            .send(sh, network, key)?;
+
+        assert_held(&format!("cycle {cycle}: transfers ICQ registered"))?;
+
+        let icq_ids = registered_icq_ids()?;
+
+        assert_eq!(icq_ids.len(), 2);
+
+        let mut waited = 0;
+
+        loop {
+            let res: IcaLastBalanceResponse = query(
+                sh,
+                network,
+                &contract,
+                &QueryMsg::IcaLastBalance {
+                    owner: owner.clone(),
+                },
+            )?;
+
+            if res.last_balance.is_some() {
+                break;
+            }
+
+            assert!(
+                waited < BALANCE_WAIT_BLOCKS,
+                "cycle {cycle}: the balance ICQ had no result after {BALANCE_WAIT_BLOCKS} blocks"
+            );
+
+            wait_blocks(sh, network, 1)?;
+
+            waited += 1;
+        }
+
+        let held = untrn_balance(&contract)?;
+
+        execute(&contract, ExecuteMsg::TeardownIca {}).send(sh, network, key)?;
+
+        // the deposits returned by the interchainqueries module are forwarded to the owner in full
+        assert_eq!(
+            untrn_balance(&contract)?,
+            held,
+            "cycle {cycle}: the refunded ICQ deposits were not forwarded to the owner"
+        );
+
+        for icq_id in icq_ids {
+            assert!(
+                !icq_registered(sh, network, icq_id)?,
+                "cycle {cycle}: ICQ {icq_id} is still registered after the teardown"
+            );
+        }
+
+        assert_held(&format!("cycle {cycle}: ICA torn down"))?;
+    }
+
+    assert_eq!(fee_balance()?, 2 * FEE_CREDIT);
+
+    execute(&contract, ExecuteMsg::WithdrawFees {}).send(sh, network, key)?;
+
+    assert_eq!(
+        untrn_balance(&contract)?,
+        0,
+        "the contract holds untrn once every fee is withdrawn"
+    );
+
+    Ok(())
+}
+
+test_contract!(icq_deposit_accounting);
+
+pub fn ibc_transfer_atom_to_neutron(Ctx { sh, network }: &Ctx, key: &Key) -> Result<()> {
+    let chain_id = GAIA_CHAIN_ID.to_owned().into();
+
+    let node_uri = network.gaiad.node_uri();
+
+    let gas = GasPrice::new(0.02, REMOTE_DENOM).units(200_000);
+
+    network
+        .gaiad
+        .cli(sh)
+        .tx(key, &chain_id, &node_uri)
+        .ibc_transfer(
+            TRANSFER_CHANNEL,
+            key.address(),
+            10_000_000_000,
+            REMOTE_DENOM,
+        )
+        .execute(&gas)?;
+
+    Ok(())
+}
+
+pub fn ibc_transfer_roundtrip(sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
+    use ::ibc_transfer_roundtrip::msgs::{
+        ExecuteMsg, IcaLastBalance, IcaLastBalanceResponse, IcaMetadataResponse,
+        IcaTxStatusResponse, InstantiateMsg, QueryMsg,
+    };
+
+    let contract_path = "artifacts/ibc_transfer_roundtrip.wasm";
+
+    eprintln!("storing contract: {contract_path}");
+
+    let code_id = store(contract_path).send(sh, network, key)?;
+
+    let init_msg = InstantiateMsg {
+        connection_id: CONNECTION_ID.to_owned(),
+        ibc_transfer_channel: TRANSFER_CHANNEL.to_owned(),
+        icq_update_period: 6,
+        remote_denom: REMOTE_DENOM.to_owned(),
+        host_ibc_denom: HOST_IBC_DENOM.to_owned(),
+        additional_transfer_denoms: None,
+        admin: None,
+        timeout_seconds: None,
+        timeout_height: None,
+        timeout_revision_number: None,
+        transfer_limits: None,
+        max_memo_len: None,
+        max_hook_memo_len: None,
+        hash_retention_blocks: None,
+        icq_deposit_override: None,
+    };
+
+    init_msg.validate()?;
+
+    eprintln!(
+        "instantiating contract code {code_id} with params: {}",
+        pretty(&init_msg)
+    );
+
+    let contract =
+        instantiate(code_id, &label("ibc_transfer_roundtrip"), init_msg).send(sh, network, key)?;
+
+    eprintln!("instantiated contract with address: {contract}");
+
+    eprintln!("setting up an ICA for {key}");
+
+    execute(&contract, ExecuteMsg::SetupIca {})
+        .amount(1_000_000, "untrn")
+        .send(sh, network, key)?;
+
+    let mut block_count = 0;
+
+    loop {
+        let ica_metadata_res: IcaMetadataResponse = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcaMetadata {
+                owner: key.address().to_owned(),
+            },
+        )?;
+
+        if let Some(metadata) = ica_metadata_res.metadata {
+            if let Some((address, balance_icq)) = metadata.address.zip(metadata.balance_icq_id) {
+                eprintln!(
+                    "ICA {} registeration with address {address} and balance ICQ {balance_icq} took {block_count} blocks",
+                    metadata.ica_idx
+                );
+                break;
+            }
+        }
+
+        eprintln!("waiting for another block...");
+
+        wait_for_blocks(sh, network)?;
+
+        block_count += 1;
+    }
+
+    let node_uri = network.node_uri(sh)?;
+
+    eprintln!("waiting for IBC ATOM to be sent");
+
+    let original_ibc_atom_balance = loop {
+        let balance = network
+            .cli(sh)?
+            .query(&node_uri)
+            .balance(key.address(), HOST_IBC_DENOM)?;
+
+        if balance >= 1_000_000_000 {
+            break balance;
+        }
+
+        eprintln!("waiting for another block...");
+
+        wait_for_blocks(sh, network)?;
+
+        block_count += 1;
+    };
+
+    eprintln!("{key} starting off with {original_ibc_atom_balance} IBC ATOM");
+
+    eprintln!("transferring IBC ATOM to ICA");
+
+    execute(
+        &contract,
+        ExecuteMsg::TransferFunds {
+            memo: None,
+            owner: None,
+        },
+    )
+    .amount(2000, "untrn")
+    .amount(1_000_000_000, HOST_IBC_DENOM)
+    .send(sh, network, key)?;
+
+    let mut block_count = 0;
+
+    loop {
+        if let IcaLastBalanceResponse {
+            last_balance:
+                Some(IcaLastBalance {
                     balance: Some(balance),
                     address,
                     last_submitted_result_local_height,
                 }),
+            ..
         } = query(
             sh,
             network,
@@ -416,7 +1214,7 @@ pub fn ibc_transfer_roundtrip(sh: &Shell, network: &dyn Network, key: &Key) -> R
     let current_ibc_atom_balance = network
         .cli(sh)?
         .query(&node_uri)
-        .balance(key.address(), ibc_atom_denom)?;
+        .balance(key.address(), HOST_IBC_DENOM)?;
 
     assert_eq!(
         current_ibc_atom_balance,
@@ -425,9 +1223,15 @@ pub fn ibc_transfer_roundtrip(sh: &Shell, network: &dyn Network, key: &Key) -> R
 
     eprintln!("retrieving ATOM from ICA");
 
-    execute(&contract, ExecuteMsg::RetrieveFunds {})
-        .amount(2000, "untrn")
-        .send(sh, network, key)?;
+    execute(
+        &contract,
+        ExecuteMsg::RetrieveFunds {
+            amount: None,
+            owner: None,
+        },
+    )
+    .amount(2000, "untrn")
+    .send(sh, network, key)?;
 
     let mut block_count = 0;
 
@@ -459,7 +1263,7 @@ pub fn ibc_transfer_roundtrip(sh: &Shell, network: &dyn Network, key: &Key) -> R
     let current_ibc_atom_balance = network
         .cli(sh)?
         .query(&node_uri)
-        .balance(key.address(), ibc_atom_denom)?;
+        .balance(key.address(), HOST_IBC_DENOM)?;
 
     assert_eq!(current_ibc_atom_balance, original_ibc_atom_balance);
 
@@ -472,3 +1276,508 @@ test_contract! {
         ibc_transfer_atom_to_neutron
     ]
 }
+
+/// Two owners interleave transfers & retrievals through their ICAs without waiting for the previous txs to settle, so
+/// that the txs of both ICAs are in flight at once on the shared transfer channel & on their ordered ICA channels.
+/// Each round, the transfer & the retrieval of both owners are broadcast over RPC without waiting for their inclusion,
+/// & the test asserts they were all included in the same block. These txs are not part of an `E2E_RECORD` recording.
+/// The contract does not limit the txs in flight per ICA, the test asserts they did overlap.
+/// A clogged ordered channel would time its txs out & close, & a tx correlated to the wrong ICA would show up in the
+/// other owner's history or out of step with the seq nums of the ICA's own channel.
+pub fn concurrent_owner_roundtrips(
+    sh: &Shell,
+    network: &Instance<NeutronLocalnet>,
+    key: &Key,
+) -> Result<()> {
+    use ::ibc_transfer_roundtrip::msgs::{
+        ExecuteMsg, IcaLastBalance, IcaLastBalanceResponse, IcaMetadataResponse,
+        IcaTxHistoryResponse, IcaTxStatusResponse, InstantiateMsg, PendingTxsResponse, QueryMsg,
+        TransferDirection, TxOutcome,
+    };
+
+    // the interleaved transfer & retrieval rounds
+    const ROUNDS: u128 = 3;
+
+    // the IBC ATOM moved by each transfer & retrieval
+    const AMOUNT: u128 = 100_000_000;
+
+    // enough for every retrieval to be covered by the last known ICA balance, however late the balance ICQ is
+    const FUNDING: u128 = (ROUNDS + 1) * AMOUNT;
+
+    // the gas limit of the txs broadcast together, which cannot be simulated before the previous ones are included
+    const ROUND_TX_GAS: u64 = 1_000_000;
+
+    let other_key = network
+        .keys
+        .get(1)
+        .ok_or_else(|| anyhow::anyhow!("the local network has a second key"))?;
+
+    let owners = [key, other_key];
+
+    eprintln!("sending IBC ATOM to {other_key}");
+
+    let chain_id = GAIA_CHAIN_ID.to_owned().into();
+
+    let gaia_node_uri = network.gaiad.node_uri();
+
+    network
+        .gaiad
+        .cli(sh)
+        .tx(key, &chain_id, &gaia_node_uri)
+        .ibc_transfer(
+            TRANSFER_CHANNEL,
+            other_key.address(),
+            10_000_000_000,
+            REMOTE_DENOM,
+        )
+        .execute(&GasPrice::new(0.02, REMOTE_DENOM).units(200_000))?;
+
+    let contract_path = "artifacts/ibc_transfer_roundtrip.wasm";
+
+    eprintln!("storing contract: {contract_path}");
+
+    let code_id = store(contract_path).send(sh, network, key)?;
+
+    let init_msg = InstantiateMsg {
+        connection_id: CONNECTION_ID.to_owned(),
+        ibc_transfer_channel: TRANSFER_CHANNEL.to_owned(),
+        icq_update_period: 6,
+        remote_denom: REMOTE_DENOM.to_owned(),
+        host_ibc_denom: HOST_IBC_DENOM.to_owned(),
+        additional_transfer_denoms: None,
+        admin: None,
+        timeout_seconds: None,
+        timeout_height: None,
+        timeout_revision_number: None,
+        transfer_limits: None,
+        max_memo_len: None,
+        max_hook_memo_len: None,
+        hash_retention_blocks: None,
+        icq_deposit_override: None,
+    };
+
+    init_msg.validate()?;
+
+    let contract = instantiate(code_id, &label("concurrent_owner_roundtrips"), init_msg)
+        .send(sh, network, key)?;
+
+    for owner in owners {
+        eprintln!("setting up an ICA for {owner}");
+
+        execute(&contract, ExecuteMsg::SetupIca {})
+            .amount(1_000_000, "untrn")
+            .send(sh, network, owner)?;
+    }
+
+    let node_uri = network.node_uri(sh)?;
+
+    let ibc_atom_balance = |owner: &Key| -> Result<u128> {
+        let balance = network
+            .cli(sh)?
+            .query(&node_uri)
+            .balance(owner.address(), HOST_IBC_DENOM)?;
+
+        Ok(balance)
+    };
+
+    let mut original_balances = vec![];
+
+    for owner in owners {
+        eprintln!(
+            "waiting for the ICA of {owner} to be registered & for its IBC ATOM to arrive..."
+        );
+
+        loop {
+            let res: IcaMetadataResponse = query(
+                sh,
+                network,
+                &contract,
+                &QueryMsg::IcaMetadata {
+                    owner: owner.address().to_owned(),
+                },
+            )?;
+
+            let registered = res
+                .metadata
+                .is_some_and(|metadata| metadata.balance_icq_id.is_some());
+
+            let balance = ibc_atom_balance(owner)?;
+
+            if registered && balance >= FUNDING + ROUNDS * AMOUNT {
+                original_balances.push(balance);
+
+                break;
+            }
+
+            wait_for_blocks(sh, network)?;
+        }
+    }
+
+    let transfer = |owner: &Key, amount: u128| {
+        execute(
+            &contract,
+            ExecuteMsg::TransferFunds {
+                memo: None,
+                owner: None,
+            },
+        )
+        .amount(2000, "untrn")
+        .amount(amount, HOST_IBC_DENOM)
+        .send(sh, network, owner)
+    };
+
+    for owner in owners {
+        eprintln!("funding the ICA of {owner} with {FUNDING} IBC ATOM");
+
+        transfer(owner, FUNDING)?;
+    }
+
+    for owner in owners {
+        eprintln!("waiting for the balance ICQ of {owner} to report the funding...");
+
+        loop {
+            if let IcaLastBalanceResponse {
+                last_balance:
+                    Some(IcaLastBalance {
+                        balance: Some(balance),
+                        ..
+                    }),
+                ..
+            } = query(
+                sh,
+                network,
+                &contract,
+                &QueryMsg::IcaLastBalance {
+                    owner: owner.address().to_owned(),
+                },
+            )? {
+                if balance.amount.u128() >= FUNDING {
+                    break;
+                }
+            }
+
+            wait_for_blocks(sh, network)?;
+        }
+    }
+
+    let pending_txs = |owner: &Key| -> Result<usize> {
+        let PendingTxsResponse { pending_txs } = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::PendingTxs {
+                owner: owner.address().to_owned(),
+            },
+        )?;
+
+        Ok(pending_txs.len())
+    };
+
+    // the most txs of an owner seen in flight at once
+    let mut max_in_flight = 0;
+
+    let conns = owners
+        .into_iter()
+        .zip(KEY_MNEMONICS)
+        .map(|(owner, mnemonic)| rpc_conn(sh, network, owner, mnemonic))
+        .collect::<Result<Vec<_>>>()?;
+
+    let roundtrip = RoundtripClient::new(&contract);
+
+    // no waiting between rounds: each round is issued while the txs of the previous rounds may still be in flight
+    for round in 0..ROUNDS {
+        eprintln!("round {round}: broadcasting the transfers & retrievals of both owners for the same block");
+
+        // right after a block is committed, leaving the whole block time for the txs to reach the mempool
+        wait_for_blocks(sh, network)?;
+
+        let mut round_txs = vec![];
+
+        for conn in &conns {
+            let transfer = roundtrip.execute_msg(
+                conn,
+                &ExecuteMsg::TransferFunds {
+                    memo: None,
+                    owner: None,
+                },
+                &[Coin::new(2000, "untrn"), Coin::new(AMOUNT, HOST_IBC_DENOM)],
+            )?;
+
+            let retrieve = roundtrip.execute_msg(
+                conn,
+                &ExecuteMsg::RetrieveFunds {
+                    amount: Some(AMOUNT.into()),
+                    owner: None,
+                },
+                &[Coin::new(2000, "untrn")],
+            )?;
+
+            let hashes = conn.broadcast(vec![vec![transfer], vec![retrieve]], ROUND_TX_GAS)?;
+
+            round_txs.extend(hashes.into_iter().map(|hash| (conn, hash)));
+        }
+
+        let heights = round_txs
+            .into_iter()
+            .map(|(conn, hash)| conn.wait_for_tx(hash))
+            .collect::<Result<Vec<u64>>>()?;
+
+        assert!(
+            heights.iter().all(|height| *height == heights[0]),
+            "the txs of round {round} were included at heights {heights:?} rather than in the same block"
+        );
+
+        for owner in owners {
+            max_in_flight = max_in_flight.max(pending_txs(owner)?);
+        }
+    }
+
+    eprintln!("at most {max_in_flight} txs of an owner were in flight at once");
+
+    assert!(
+        max_in_flight > 1,
+        "the txs of an owner were relayed before the next was issued, they never overlapped"
+    );
+
+    let mut block_count = 0;
+
+    for owner in owners {
+        eprintln!("waiting for the txs of {owner} to settle...");
+
+        loop {
+            if pending_txs(owner)? == 0 {
+                break;
+            }
+
+            wait_for_blocks(sh, network)?;
+
+            block_count += 1;
+        }
+    }
+
+    eprintln!("all txs settled after {block_count} blocks");
+
+    let mut transfer_seq_nums = vec![];
+
+    for (owner, original_balance) in owners.into_iter().zip(original_balances) {
+        let res: IcaMetadataResponse = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcaMetadata {
+                owner: owner.address().to_owned(),
+            },
+        )?;
+
+        let metadata = res.metadata.expect("the ica of the owner is setup");
+
+        let channel = metadata
+            .channel
+            .expect("the ica channel of the owner is open");
+
+        assert_eq!(
+            metadata.status,
+            ::ibc_transfer_roundtrip::msgs::IcaStatus::Active,
+            "the ICA channel of {owner} was closed"
+        );
+
+        let IcaTxStatusResponse {
+            status: Some(status),
+        } = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcaTxStatus {
+                owner: owner.address().to_owned(),
+            },
+        )?
+        else {
+            panic!("{owner} issued txs");
+        };
+
+        eprintln!("tx status of {owner}: {}", pretty(&status));
+
+        let issued = u32::try_from(1 + 2 * ROUNDS)?;
+
+        assert_eq!(status.issued, issued);
+        assert_eq!(status.success, issued, "every tx of {owner} succeeded");
+        assert_eq!(status.timeout, 0, "no tx of {owner} timed out");
+
+        let IcaTxHistoryResponse { history } = query(
+            sh,
+            network,
+            &contract,
+            &QueryMsg::IcaTxHistory {
+                owner: owner.address().to_owned(),
+                start_after: None,
+                limit: Some(issued),
+            },
+        )?;
+
+        // every tx of the owner is correlated to their ICA by its sequence number, & to no other
+        assert_eq!(history.len(), usize::try_from(issued)?);
+
+        for entry in &history {
+            assert_eq!(entry.outcome, TxOutcome::Success);
+
+            if entry.direction == TransferDirection::ToIca {
+                assert!(
+                    !transfer_seq_nums.contains(&entry.seq_num),
+                    "transfer {} of {owner} was also correlated to another ICA",
+                    entry.seq_num
+                );
+
+                transfer_seq_nums.push(entry.seq_num);
+            }
+        }
+
+        let retrievals: Vec<_> = history
+            .iter()
+            .filter(|entry| entry.direction == TransferDirection::FromIca)
+            .collect();
+
+        let retrieved = retrievals
+            .iter()
+            .map(|entry| entry.coin.amount.u128())
+            .sum::<u128>();
+
+        assert_eq!(retrieved, ROUNDS * AMOUNT);
+
+        // the retrievals are the txs of the ICA, each correlated by the seq num it was sent with on the ICA's own channel
+        let next_seq_num = next_sequence_send(sh, network, &channel.port_id, &channel.channel_id)?;
+
+        let mut retrieval_seq_nums: Vec<u64> =
+            retrievals.iter().map(|entry| entry.seq_num).collect();
+
+        retrieval_seq_nums.sort_unstable();
+
+        let channel_seq_nums: Vec<u64> =
+            (next_seq_num - u64::try_from(ROUNDS)?..next_seq_num).collect();
+
+        assert_eq!(
+            retrieval_seq_nums, channel_seq_nums,
+            "the retrievals of {owner} are out of step with the seq nums of its channel {}",
+            channel.channel_id
+        );
+
+        // only the funding is left in the ICA, every other transfer came back
+        assert_eq!(ibc_atom_balance(owner)?, original_balance - FUNDING);
+    }
+
+    Ok(())
+}
+
+test_contract! {
+    test_case: concurrent_owner_roundtrips,
+    prerequisites: [
+        ibc_transfer_atom_to_neutron
+    ]
+}
+
+pub fn failing_callbacks(sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
+    use ::failing_callbacks::msgs::{
+        CallbacksResponse, ExecuteMsg, FailureMode, IcaResponse, IcaStatus, InstantiateMsg,
+        QueryMsg,
+    };
+
+    // blocks to wait for an ICA tx callback to be relayed
+    const CALLBACK_WAIT_BLOCKS: u64 = 60;
+
+    let contract_path = "artifacts/failing_callbacks.wasm";
+
+    eprintln!("storing contract: {contract_path}");
+
+    let code_id = store(contract_path).send(sh, network, key)?;
+
+    let init_msg = InstantiateMsg {
+        connection_id: CONNECTION_ID.to_owned(),
+    };
+
+    init_msg.validate()?;
+
+    let contract =
+        instantiate(code_id, &label("failing_callbacks"), init_msg).send(sh, network, key)?;
+
+    eprintln!("waiting for the ICA to be registered...");
+
+    loop {
+        let res: IcaResponse = query(sh, network, &contract, &QueryMsg::Ica {})?;
+
+        if res.status == IcaStatus::Active {
+            break;
+        }
+
+        wait_blocks(sh, network, 1)?;
+    }
+
+    // the callback of each tx fails in a different way, then succeeds once resubmitted
+    for (handled, mode) in [(1, FailureMode::Error), (2, FailureMode::OutOfGas)] {
+        eprintln!("failing the next callback with {mode:?}");
+
+        execute(&contract, ExecuteMsg::SetFailureMode { mode }).send(sh, network, key)?;
+
+        execute(
+            &contract,
+            ExecuteMsg::SendTx {
+                coin: Coin::new(1, REMOTE_DENOM),
+            },
+        )
+        .amount(2000, "untrn")
+        .send(sh, network, key)?;
+
+        let mut waited = 0;
+
+        let failure_id = loop {
+            if let Some(failure_id) = contract_failure_ids(sh, network, &contract)?.pop() {
+                break failure_id;
+            }
+
+            assert!(
+                waited < CALLBACK_WAIT_BLOCKS,
+                "no failure recorded after {CALLBACK_WAIT_BLOCKS} blocks"
+            );
+
+            wait_blocks(sh, network, 1)?;
+
+            waited += 1;
+        };
+
+        eprintln!("the contractmanager recorded failure {failure_id} after {waited} blocks");
+
+        let callbacks: CallbacksResponse = query(sh, network, &contract, &QueryMsg::Callbacks {})?;
+
+        assert_eq!(
+            callbacks.responses + callbacks.errors + callbacks.timeouts,
+            handled - 1,
+            "a failed callback must not change the contract state"
+        );
+
+        execute(
+            &contract,
+            ExecuteMsg::SetFailureMode {
+                mode: FailureMode::None,
+            },
+        )
+        .send(sh, network, key)?;
+
+        execute(&contract, ExecuteMsg::ResubmitFailure { failure_id }).send(sh, network, key)?;
+
+        let callbacks: CallbacksResponse = query(sh, network, &contract, &QueryMsg::Callbacks {})?;
+
+        eprintln!(
+            "callbacks handled after resubmitting: {}",
+            pretty(&callbacks)
+        );
+
+        assert_eq!(
+            callbacks.responses + callbacks.errors + callbacks.timeouts,
+            handled
+        );
+
+        assert!(!contract_failure_ids(sh, network, &contract)?.contains(&failure_id));
+    }
+
+    Ok(())
+}
+
+test_contract!(failing_callbacks);
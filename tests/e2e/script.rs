@@ -0,0 +1,454 @@
+//! Recording of the store, instantiate & execute txs issued during an e2e run into a JSON script, & their replay.
+//!
+//! Code ids & contract addresses differ between networks, so steps refer to the step that produced them by index.
+//! Within msgs, the addresses of the recorded contracts & signers are replaced with `$contract:{step}` & `$signer:{idx}`
+//! placeholders, substituted back with the replay's own addresses. Addresses on the remote chain, such as ICAs, are not remapped.
+//!
+//! Each step also records how many blocks passed since the previous one, which the replay waits for before issuing it,
+//! so the ICA, IBC & ICQ traffic of a step settles before the next step depends on it.
+
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use cosmwasm_std::Coin;
+use cosmwasm_xtask::{key::Key, Network};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use xshell::Shell;
+
+use crate::{current_height, wait_until_height};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Step {
+    Store {
+        path: String,
+        /// Index of the signer in [`Script::signers`]
+        #[serde(default)]
+        signer: usize,
+        /// Blocks produced between the previous step & this one while recording
+        #[serde(default)]
+        settle_blocks: u64,
+    },
+    Instantiate {
+        /// Index of the store step of the code
+        code: usize,
+        label: String,
+        msg: Value,
+        funds: Vec<Coin>,
+        #[serde(default)]
+        signer: usize,
+        #[serde(default)]
+        settle_blocks: u64,
+    },
+    Execute {
+        /// Index of the instantiate step of the contract
+        contract: usize,
+        msg: Value,
+        funds: Vec<Coin>,
+        #[serde(default)]
+        signer: usize,
+        #[serde(default)]
+        settle_blocks: u64,
+    },
+}
+
+impl Step {
+    fn signer(&self) -> usize {
+        match self {
+            Self::Store { signer, .. }
+            | Self::Instantiate { signer, .. }
+            | Self::Execute { signer, .. } => *signer,
+        }
+    }
+
+    fn settle_blocks(&self) -> u64 {
+        match self {
+            Self::Store { settle_blocks, .. }
+            | Self::Instantiate { settle_blocks, .. }
+            | Self::Execute { settle_blocks, .. } => *settle_blocks,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Script {
+    /// The addresses of the keys which signed the recorded steps, in order of first use
+    #[serde(default)]
+    pub signers: Vec<String>,
+    pub steps: Vec<Step>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Recording {
+    script: Script,
+    code_steps: HashMap<u64, usize>,
+    contract_steps: HashMap<String, usize>,
+    last_height: Option<u64>,
+}
+
+impl Recording {
+    /// The index of `key` in the script's signers, adding it on first use
+    fn signer(&mut self, key: &Key) -> usize {
+        let signers = &mut self.script.signers;
+
+        if let Some(idx) = signers.iter().position(|s| s == key.address()) {
+            return idx;
+        }
+
+        signers.push(key.address().to_owned());
+
+        signers.len() - 1
+    }
+
+    /// The blocks produced since the previous recorded step
+    fn settle_blocks(&mut self, height: u64) -> u64 {
+        let blocks = self
+            .last_height
+            .map_or(0, |last| height.saturating_sub(last));
+
+        self.last_height = Some(height);
+
+        blocks
+    }
+
+    /// Replace the addresses of the recorded contracts & signers within `msg` with placeholders
+    fn placeholders(&self, msg: Value) -> Value {
+        let contracts = self
+            .contract_steps
+            .iter()
+            .map(|(address, step)| (address.clone(), format!("$contract:{step}")));
+
+        let signers = self
+            .script
+            .signers
+            .iter()
+            .enumerate()
+            .map(|(idx, address)| (address.clone(), format!("$signer:{idx}")));
+
+        remap(msg, &contracts.chain(signers).collect::<Vec<_>>())
+    }
+}
+
+/// Replace every occurrence of the `from` of each pair with its `to` in the strings & keys of `value`
+fn remap(value: Value, pairs: &[(String, String)]) -> Value {
+    let remap_str = |s: String| {
+        pairs
+            .iter()
+            .fold(s, |s, (from, to)| s.replace(from.as_str(), to))
+    };
+
+    match value {
+        Value::String(s) => Value::String(remap_str(s)),
+        Value::Array(values) => Value::Array(values.into_iter().map(|v| remap(v, pairs)).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (remap_str(k), remap(v, pairs)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Record every tx sent through this module from now on, discarding any unfinished recording
+pub fn start_recording() {
+    *RECORDING.lock().unwrap() = Some(Recording::default());
+}
+
+/// Stop recording, returning the recorded script if a recording was started
+pub fn finish_recording() -> Option<Script> {
+    RECORDING
+        .lock()
+        .unwrap()
+        .take()
+        .map(|recording| recording.script)
+}
+
+fn record(
+    sh: &Shell,
+    network: &dyn Network,
+    f: impl FnOnce(&mut Recording, u64) -> Result<()>,
+) -> Result<()> {
+    match RECORDING.lock().unwrap().as_mut() {
+        Some(recording) => {
+            let height = current_height(sh, network)?;
+
+            let settle_blocks = recording.settle_blocks(height);
+
+            f(recording, settle_blocks)
+        }
+        None => Ok(()),
+    }
+}
+
+pub struct Store {
+    path: String,
+}
+
+#[must_use]
+pub fn store(path: &str) -> Store {
+    Store {
+        path: path.to_owned(),
+    }
+}
+
+impl Store {
+    pub fn send(self, sh: &Shell, network: &dyn Network, key: &Key) -> Result<u64> {
+        let code_id = cosmwasm_xtask::store(&self.path).send(sh, network, key)?;
+
+        record(sh, network, |recording, settle_blocks| {
+            let signer = recording.signer(key);
+
+            let steps = &mut recording.script.steps;
+
+            recording.code_steps.insert(code_id, steps.len());
+
+            steps.push(Step::Store {
+                path: self.path,
+                signer,
+                settle_blocks,
+            });
+
+            Ok(())
+        })?;
+
+        Ok(code_id)
+    }
+}
+
+pub struct Instantiate {
+    code_id: u64,
+    label: String,
+    msg: Value,
+    funds: Vec<Coin>,
+}
+
+#[must_use]
+pub fn instantiate(code_id: u64, label: &str, msg: impl Serialize) -> Instantiate {
+    Instantiate {
+        code_id,
+        label: label.to_owned(),
+        msg: serde_json::to_value(msg).expect("msgs serialize to json"),
+        funds: vec![],
+    }
+}
+
+impl Instantiate {
+    #[must_use]
+    pub fn amount(mut self, amount: u128, denom: &str) -> Self {
+        self.funds.push(Coin::new(amount, denom));
+        self
+    }
+
+    pub fn send(self, sh: &Shell, network: &dyn Network, key: &Key) -> Result<String> {
+        let mut tx = cosmwasm_xtask::instantiate(self.code_id, &self.label, &self.msg);
+
+        for coin in &self.funds {
+            tx = tx.amount(coin.amount.u128(), &coin.denom);
+        }
+
+        let contract = tx.send(sh, network, key)?.to_string();
+
+        record(sh, network, |recording, settle_blocks| {
+            let code = *recording
+                .code_steps
+                .get(&self.code_id)
+                .ok_or_else(|| anyhow!("code {} was not stored while recording", self.code_id))?;
+
+            let signer = recording.signer(key);
+
+            let msg = recording.placeholders(self.msg);
+
+            let steps = &mut recording.script.steps;
+
+            recording
+                .contract_steps
+                .insert(contract.clone(), steps.len());
+
+            steps.push(Step::Instantiate {
+                code,
+                label: self.label,
+                msg,
+                funds: self.funds,
+                signer,
+                settle_blocks,
+            });
+
+            Ok(())
+        })?;
+
+        Ok(contract)
+    }
+}
+
+pub struct Execute {
+    contract: String,
+    msg: Value,
+    funds: Vec<Coin>,
+}
+
+#[must_use]
+pub fn execute(contract: &str, msg: impl Serialize) -> Execute {
+    Execute {
+        contract: contract.to_owned(),
+        msg: serde_json::to_value(msg).expect("msgs serialize to json"),
+        funds: vec![],
+    }
+}
+
+impl Execute {
+    #[must_use]
+    pub fn amount(mut self, amount: u128, denom: &str) -> Self {
+        self.funds.push(Coin::new(amount, denom));
+        self
+    }
+
+    pub fn send(self, sh: &Shell, network: &dyn Network, key: &Key) -> Result<()> {
+        let mut tx = cosmwasm_xtask::execute(&self.contract, &self.msg);
+
+        for coin in &self.funds {
+            tx = tx.amount(coin.amount.u128(), &coin.denom);
+        }
+
+        tx.send(sh, network, key)?;
+
+        record(sh, network, |recording, settle_blocks| {
+            let contract = *recording
+                .contract_steps
+                .get(&self.contract)
+                .ok_or_else(|| anyhow!("{} was not instantiated while recording", self.contract))?;
+
+            let signer = recording.signer(key);
+
+            let msg = recording.placeholders(self.msg);
+
+            recording.script.steps.push(Step::Execute {
+                contract,
+                msg,
+                funds: self.funds,
+                signer,
+                settle_blocks,
+            });
+
+            Ok(())
+        })
+    }
+}
+
+/// Re-issue the steps of `script` in order, the `n`th recorded signer being replaced with `keys[n]`.
+///
+/// Before each step, wait for as many blocks as passed before it while recording.
+pub fn replay(sh: &Shell, network: &dyn Network, keys: &[Key], script: &Script) -> Result<()> {
+    if keys.len() < script.signers.len() {
+        anyhow::bail!(
+            "the script was signed by {} keys but only {} are available",
+            script.signers.len(),
+            keys.len()
+        );
+    }
+
+    let mut code_ids: HashMap<usize, u64> = HashMap::new();
+
+    let mut contracts: HashMap<usize, String> = HashMap::new();
+
+    let signers: Vec<(String, String)> = keys
+        .iter()
+        .enumerate()
+        .map(|(idx, key)| (format!("$signer:{idx}"), key.address().to_owned()))
+        .collect();
+
+    let mut last_height: Option<u64> = None;
+
+    for (idx, step) in script.steps.iter().enumerate() {
+        let missing = |what: &str, step: usize| {
+            anyhow!("step {idx} refers to {what} step {step}, which did not precede it")
+        };
+
+        if let Some(last) = last_height {
+            wait_until_height(sh, network, last + step.settle_blocks())?;
+        }
+
+        eprintln!("replaying step {idx}: {}", serde_json::to_string(step)?);
+
+        let key = keys
+            .get(step.signer())
+            .ok_or_else(|| anyhow!("step {idx} was signed by unknown signer {}", step.signer()))?;
+
+        // placeholders of later steps' contracts cannot appear, so map every contract instantiated so far
+        let mut pairs: Vec<(String, String)> = contracts
+            .iter()
+            .map(|(step, address)| (format!("$contract:{step}"), address.clone()))
+            .collect();
+
+        pairs.extend(signers.iter().cloned());
+
+        // longest placeholders first, so that `$contract:1` does not replace the prefix of `$contract:12`
+        pairs.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+
+        match step {
+            Step::Store { path, .. } => {
+                let code_id = store(path).send(sh, network, key)?;
+
+                code_ids.insert(idx, code_id);
+            }
+
+            Step::Instantiate {
+                code,
+                label,
+                msg,
+                funds,
+                ..
+            } => {
+                let code_id = *code_ids.get(code).ok_or_else(|| missing("store", *code))?;
+
+                let tx = Instantiate {
+                    code_id,
+                    label: label.clone(),
+                    msg: remap(msg.clone(), &pairs),
+                    funds: funds.clone(),
+                };
+
+                contracts.insert(idx, tx.send(sh, network, key)?);
+            }
+
+            Step::Execute {
+                contract,
+                msg,
+                funds,
+                ..
+            } => {
+                let contract = contracts
+                    .get(contract)
+                    .ok_or_else(|| missing("instantiate", *contract))?;
+
+                let tx = Execute {
+                    contract: contract.clone(),
+                    msg: remap(msg.clone(), &pairs),
+                    funds: funds.clone(),
+                };
+
+                tx.send(sh, network, key)?;
+            }
+        }
+
+        last_height = Some(current_height(sh, network)?);
+    }
+
+    Ok(())
+}
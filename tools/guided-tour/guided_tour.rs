@@ -0,0 +1,487 @@
+//! A guided tour of the `ibc-transfer-roundtrip` example on a local network: each step of a roundtrip of IBC ATOM
+//! through an ICA is explained, the messages sent are printed & the state the contract serves is diffed before &
+//! after the step settles. The contract is driven through the same `client` crate offered to integrators.
+//!
+//! ```text
+//! cargo run -p guided-tour
+//! cargo run -p guided-tour -- --no-pause --amount 5000000
+//! ```
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use clap::Parser;
+use client::{Conn, Contract, GasPrice, Roundtrip, RoundtripClient};
+use cosmrs::crypto::secp256k1::SigningKey;
+use cosmwasm_std::Coin;
+use cosmwasm_xtask::{
+    key::Key,
+    network::{gas::Price as GasPrice, neutron::local::GAIA_CHAIN_ID, Instance},
+    wait_for_blocks, Initialize, Network, NeutronLocalnet, StartLocal,
+};
+use ibc_transfer_roundtrip::msgs::{
+    ExecuteMsg, IcaLastBalanceResponse, IcaMetadataResponse, IcaTxStatus, IcaTxStatusResponse,
+    InstantiateMsg,
+};
+use serde::Serialize;
+use serde_json::Value;
+use test_consts::{CONNECTION_ID, HOST_IBC_DENOM, KEY_MNEMONICS, REMOTE_DENOM, TRANSFER_CHANNEL};
+use xshell::Shell;
+
+/// The untrn attached to the ICA registration
+const SETUP_ICA_FEE: u128 = 1_000_000;
+
+/// The untrn attached to pay the IBC fee of transfers & retrievals
+const TX_FEE: u128 = 2_000;
+
+/// The IBC ATOM sent from gaia to the tour's key, so it has something to transfer
+const FUNDING_AMOUNT: u128 = 10_000_000_000;
+
+#[derive(Parser)]
+#[command(about = "walk through a roundtrip of IBC ATOM through an ICA, step by step")]
+struct Args {
+    #[arg(long, help = "run every step without waiting for enter to be pressed")]
+    no_pause: bool,
+    #[arg(long, default_value_t = 1_000_000, help = "the IBC ATOM to roundtrip")]
+    amount: u128,
+}
+
+struct Tour {
+    pause: bool,
+    step: usize,
+}
+
+impl Tour {
+    /// Introduce the next step, waiting for enter to be pressed unless the tour runs unattended
+    fn step(&mut self, title: &str, explanation: &str) -> Result<()> {
+        self.step += 1;
+
+        println!("\n━━ step {}: {title}\n", self.step);
+
+        for line in explanation.lines() {
+            println!("   {line}");
+        }
+
+        if self.pause {
+            print!("\n   press enter to continue ");
+
+            io::stdout().flush()?;
+
+            io::stdin().lock().read_line(&mut String::new())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Print `msg` as the JSON the contract receives
+fn show_msg<T: Serialize>(label: &str, msg: &T, funds: &[Coin]) -> Result<()> {
+    println!("\n   {label}:\n");
+
+    for line in serde_json::to_string_pretty(msg)?.lines() {
+        println!("     {line}");
+    }
+
+    if !funds.is_empty() {
+        let funds: Vec<String> = funds.iter().map(ToString::to_string).collect();
+
+        println!("\n   with funds: {}", funds.join(", "));
+    }
+
+    Ok(())
+}
+
+/// The state of the tour's ICA as served by the contract, along with the key's IBC ATOM balance
+#[derive(Serialize)]
+struct Snapshot {
+    ibc_atom_balance: u128,
+    metadata: IcaMetadataResponse,
+    last_balance: IcaLastBalanceResponse,
+    tx_status: IcaTxStatusResponse,
+}
+
+fn snapshot(
+    sh: &Shell,
+    network: &Instance<NeutronLocalnet>,
+    conn: &Conn,
+    roundtrip: &RoundtripClient,
+) -> Result<Snapshot> {
+    let owner = conn.address();
+
+    let node_uri = network.node_uri(sh)?;
+
+    let ibc_atom_balance = network
+        .cli(sh)?
+        .query(&node_uri)
+        .balance(owner, HOST_IBC_DENOM)?;
+
+    Ok(Snapshot {
+        ibc_atom_balance,
+        metadata: roundtrip.ica_metadata(conn, owner)?,
+        last_balance: roundtrip.ica_last_balance(conn, owner)?,
+        tx_status: roundtrip.ica_tx_status(conn, owner)?,
+    })
+}
+
+/// Collect the fields differing between `before` & `after` as `path: before -> after` lines
+fn diff(path: &str, before: &Value, after: &Value, changes: &mut Vec<String>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+
+            keys.sort();
+
+            keys.dedup();
+
+            for key in keys {
+                let before = before.get(key).unwrap_or(&Value::Null);
+
+                let after = after.get(key).unwrap_or(&Value::Null);
+
+                diff(&format!("{path}.{key}"), before, after, changes);
+            }
+        }
+        (before, after) if before != after => {
+            changes.push(format!("{path}: {before} -> {after}"));
+        }
+        _ => {}
+    }
+}
+
+fn show_diff(before: &Snapshot, after: &Snapshot) -> Result<()> {
+    let mut changes = vec![];
+
+    diff(
+        "state",
+        &serde_json::to_value(before)?,
+        &serde_json::to_value(after)?,
+        &mut changes,
+    );
+
+    println!("\n   state changes:\n");
+
+    if changes.is_empty() {
+        println!("     none");
+    }
+
+    for change in changes {
+        println!("     {change}");
+    }
+
+    Ok(())
+}
+
+/// Poll `ready` once per block until it yields a value
+fn wait_until<T>(
+    sh: &Shell,
+    network: &dyn Network,
+    what: &str,
+    mut ready: impl FnMut() -> Result<Option<T>>,
+) -> Result<T> {
+    let mut block_count = 0;
+
+    loop {
+        if let Some(value) = ready()? {
+            println!("\n   {what} after {block_count} blocks");
+
+            return Ok(value);
+        }
+
+        wait_for_blocks(sh, network)?;
+
+        block_count += 1;
+    }
+}
+
+fn fund_key(sh: &Shell, network: &Instance<NeutronLocalnet>, key: &Key) -> Result<()> {
+    let chain_id = GAIA_CHAIN_ID.to_owned().into();
+
+    let node_uri = network.gaiad.node_uri();
+
+    let gas = GasPrice::new(0.02, REMOTE_DENOM).units(200_000);
+
+    network
+        .gaiad
+        .cli(sh)
+        .tx(key, &chain_id, &node_uri)
+        .ibc_transfer(
+            TRANSFER_CHANNEL,
+            key.address(),
+            FUNDING_AMOUNT,
+            REMOTE_DENOM,
+        )
+        .execute(&gas)?;
+
+    Ok(())
+}
+
+/// The signing key of the default account derived from `mnemonic`
+fn signing_key(mnemonic: &str) -> Result<SigningKey> {
+    let mnemonic = bip32::Mnemonic::new(mnemonic, bip32::Language::English)?;
+
+    let path = "m/44'/118'/0'/0/0".parse()?;
+
+    SigningKey::derive_from_path(mnemonic.to_seed("").as_bytes(), &path)
+        .map_err(|err| anyhow::anyhow!("{err:?}"))
+}
+
+fn label() -> Result<String> {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    Ok(format!("guided_tour:{timestamp}"))
+}
+
+#[allow(clippy::too_many_lines)]
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut tour = Tour {
+        pause: !args.no_pause,
+        step: 0,
+    };
+
+    let sh = Shell::new()?;
+
+    sh.change_dir(PathBuf::from(format!("{}/../../", env!("CARGO_MANIFEST_DIR"))).canonicalize()?);
+
+    tour.step(
+        "start the local network",
+        "A neutron chain & a gaia chain are started, connected by hermes relaying IBC packets & by the ICQ\n\
+         relayer submitting the results of interchain queries. A network already running is reused.",
+    )?;
+
+    let network = NeutronLocalnet::initialize(&sh)?;
+
+    // the handle stops the network it started once the tour ends
+    let _handle = if wait_for_blocks(&sh, &network).is_ok() {
+        println!("\n   reusing the running local network");
+
+        None
+    } else {
+        let handle = network.start_local(&sh)?;
+
+        wait_for_blocks(&sh, &network)?;
+
+        Some(handle)
+    };
+
+    let key = network.keys.first().expect("the localnet has funded keys");
+
+    let rpc_url = network
+        .node_uri(&sh)?
+        .to_string()
+        .replace("tcp://", "http://");
+
+    let conn = Conn::connect(
+        &rpc_url,
+        signing_key(KEY_MNEMONICS[0])?,
+        GasPrice::new(0.025, "untrn"),
+    )?;
+
+    anyhow::ensure!(
+        conn.address() == key.address(),
+        "the tour signs with the first key of the localnet, {}",
+        key.address()
+    );
+
+    tour.step(
+        "build & store the contract",
+        "The contracts are compiled to optimized wasm, then ibc-transfer-roundtrip is stored on neutron.",
+    )?;
+
+    cosmwasm_xtask::ops::dist_workspace(&sh)?;
+
+    let code_id = RoundtripClient::store(&conn, sh.read_binary_file(Roundtrip::ARTIFACT)?)?;
+
+    println!("\n   stored as code {code_id}");
+
+    tour.step(
+        "instantiate the contract",
+        "The contract is configured with the connection its ICAs are registered over, the transfer channel\n\
+         IBC ATOM travels over & the denoms of ATOM on either side of it.",
+    )?;
+
+    let init_msg = InstantiateMsg {
+        connection_id: CONNECTION_ID.to_owned(),
+        ibc_transfer_channel: TRANSFER_CHANNEL.to_owned(),
+        icq_update_period: 6,
+        remote_denom: REMOTE_DENOM.to_owned(),
+        host_ibc_denom: HOST_IBC_DENOM.to_owned(),
+        additional_transfer_denoms: None,
+        admin: None,
+        timeout_seconds: None,
+        timeout_height: None,
+        timeout_revision_number: None,
+        transfer_limits: None,
+        max_memo_len: None,
+        max_hook_memo_len: None,
+        hash_retention_blocks: None,
+        icq_deposit_override: None,
+    };
+
+    init_msg.validate()?;
+
+    show_msg("instantiate msg", &init_msg, &[])?;
+
+    let roundtrip = RoundtripClient::instantiate(&conn, code_id, &label()?, &init_msg, &[])?;
+
+    println!("\n   instantiated at {}", roundtrip.address());
+
+    tour.step(
+        "fund the tour's key with IBC ATOM",
+        "ATOM is sent from gaia to the tour's key on neutron over the transfer channel, arriving as the\n\
+         IBC ATOM voucher the contract transfers.",
+    )?;
+
+    let before = snapshot(&sh, &network, &conn, &roundtrip)?;
+
+    fund_key(&sh, &network, key)?;
+
+    wait_until(&sh, &network, "IBC ATOM received", || {
+        let balance = snapshot(&sh, &network, &conn, &roundtrip)?.ibc_atom_balance;
+
+        Ok((balance >= before.ibc_atom_balance + FUNDING_AMOUNT).then_some(()))
+    })?;
+
+    show_diff(&before, &snapshot(&sh, &network, &conn, &roundtrip)?)?;
+
+    tour.step(
+        "set up an ICA",
+        "The contract registers an interchain account on gaia for the tour's key. Once the ICA channel is open\n\
+         the contract registers a balance ICQ, keeping it informed of the ICA's balance.",
+    )?;
+
+    let before = snapshot(&sh, &network, &conn, &roundtrip)?;
+
+    let msg = ExecuteMsg::SetupIca {};
+
+    let funds = [Coin::new(SETUP_ICA_FEE, "untrn")];
+
+    show_msg("execute msg", &msg, &funds)?;
+
+    roundtrip.execute(&conn, &msg, &funds)?;
+
+    wait_until(&sh, &network, "ICA & balance ICQ registered", || {
+        let IcaMetadataResponse { metadata } = roundtrip.ica_metadata(&conn, key.address())?;
+
+        Ok(metadata
+            .filter(|metadata| metadata.address.is_some() && metadata.balance_icq_id.is_some()))
+    })?;
+
+    show_diff(&before, &snapshot(&sh, &network, &conn, &roundtrip)?)?;
+
+    tour.step(
+        "transfer IBC ATOM to the ICA",
+        "The attached IBC ATOM is sent back over the transfer channel to the ICA, untrn pays the IBC fee of the\n\
+         transfer. The contract learns the outcome from the acknowledgement relayed back to it.",
+    )?;
+
+    let before = snapshot(&sh, &network, &conn, &roundtrip)?;
+
+    let settled = |status: &IcaTxStatus| status.success + status.error + status.timeout;
+
+    let settled_before = before.tx_status.status.as_ref().map_or(0, settled);
+
+    let msg = ExecuteMsg::TransferFunds {
+        memo: None,
+        owner: None,
+    };
+
+    let funds = [
+        Coin::new(TX_FEE, "untrn"),
+        Coin::new(args.amount, HOST_IBC_DENOM),
+    ];
+
+    show_msg("execute msg", &msg, &funds)?;
+
+    roundtrip.execute(&conn, &msg, &funds)?;
+
+    let after = wait_until(&sh, &network, "transfer outcome received", || {
+        let snapshot = snapshot(&sh, &network, &conn, &roundtrip)?;
+
+        let settled_now = snapshot.tx_status.status.as_ref().map_or(0, settled);
+
+        Ok((settled_now > settled_before).then_some(snapshot))
+    })?;
+
+    show_diff(&before, &after)?;
+
+    let successes = |snapshot: &Snapshot| {
+        snapshot
+            .tx_status
+            .status
+            .as_ref()
+            .map_or(0, |status| status.success)
+    };
+
+    anyhow::ensure!(
+        successes(&after) > successes(&before),
+        "the transfer to the ICA failed or timed out, see the state changes above"
+    );
+
+    tour.step(
+        "observe the balance ICQ",
+        "The ICQ relayer submits the ICA's balance on gaia, proven against gaia's light client, the contract\n\
+         serving it as the ICA's last balance.",
+    )?;
+
+    let before = snapshot(&sh, &network, &conn, &roundtrip)?;
+
+    wait_until(&sh, &network, "ICA balance reported by the ICQ", || {
+        let IcaLastBalanceResponse { last_balance, .. } =
+            roundtrip.ica_last_balance(&conn, key.address())?;
+
+        // the balance reported before the transfer arrived is not enough to retrieve it
+        Ok(last_balance
+            .and_then(|last_balance| last_balance.balance)
+            .filter(|balance| balance.amount.u128() >= args.amount))
+    })?;
+
+    show_diff(&before, &snapshot(&sh, &network, &conn, &roundtrip)?)?;
+
+    tour.step(
+        "retrieve the ATOM from the ICA",
+        "The contract submits an ICA tx making the ICA transfer its ATOM back to neutron. The transfer carries\n\
+         an IBC hook executing the contract once received, completing the roundtrip.",
+    )?;
+
+    let before = snapshot(&sh, &network, &conn, &roundtrip)?;
+
+    let roundtrips_before = before
+        .tx_status
+        .status
+        .as_ref()
+        .map_or(0, |status| status.roundtrips);
+
+    let msg = ExecuteMsg::RetrieveFunds {
+        amount: None,
+        owner: None,
+    };
+
+    let funds = [Coin::new(TX_FEE, "untrn")];
+
+    show_msg("execute msg", &msg, &funds)?;
+
+    roundtrip.execute(&conn, &msg, &funds)?;
+
+    wait_until(&sh, &network, "roundtrip completed", || {
+        let IcaTxStatusResponse { status } = roundtrip.ica_tx_status(&conn, key.address())?;
+
+        Ok(status.filter(|status| status.roundtrips > roundtrips_before))
+    })?;
+
+    show_diff(&before, &snapshot(&sh, &network, &conn, &roundtrip)?)?;
+
+    println!("\nthe IBC ATOM made it to the ICA & back, the tour is over");
+
+    Ok(())
+}
@@ -1,9 +1,14 @@
 use std::{env, path::PathBuf};
 
+use e2e::script::{self, Script};
+
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use cosmwasm_xtask::{network::Clean, Initialize, IntoForeground, NeutronLocalnet, StartLocal};
+use cosmwasm_xtask::{
+    network::Clean, wait_for_blocks, Initialize, IntoForeground, NeutronLocalnet, StartLocal,
+};
 use xshell::{cmd, Shell};
+use xtask::{icq_relayer, new_contract};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -27,12 +32,102 @@ enum Command {
     CleanLocalAll,
     #[command(subcommand, about = "testing tasks")]
     Test(Test),
+    #[command(
+        about = "reset the local network state, then replay a script recorded by an e2e run"
+    )]
+    Replay { script: PathBuf },
+    #[command(subcommand, about = "icq relayer tasks")]
+    IcqRelayer(IcqRelayer),
+    #[command(
+        about = "scaffold a new example contract in contracts/<name>, wired into the workspace & the e2e tests"
+    )]
+    NewContract { name: String },
 }
 
 #[derive(Subcommand)]
 enum Test {
     #[command(about = "start a local node then run e2e tests")]
-    E2e { args: Option<String> },
+    E2e {
+        args: Option<String>,
+        #[arg(
+            long,
+            help = "record the store, instantiate & execute txs of each test case into a JSON script in this directory"
+        )]
+        record: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "reset the local network state before each e2e test case, so no case sees the ICAs, channels or balances left by another"
+        )]
+        fresh_per_test: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IcqRelayer {
+    #[command(
+        about = "update the icq relayer settings applied when the local network starts, then print them"
+    )]
+    Config {
+        #[arg(long, help = "the most query results queued for submission at once")]
+        queue_capacity: Option<u32>,
+        #[arg(
+            long,
+            help = "the fewest blocks between two submissions of a KV query's result"
+        )]
+        submit_period: Option<u64>,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "only relay the results of these comma separated query ids"
+        )]
+        allowed_query_ids: Option<Vec<u64>>,
+        #[arg(
+            long,
+            help = "reset every setting to the relayer's default before applying the others"
+        )]
+        reset: bool,
+    },
+}
+
+/// Run each e2e test case matching `args` against a local network reset to its initial state
+fn test_e2e_fresh_per_test(
+    sh: &Shell,
+    args: Option<String>,
+    record: Option<PathBuf>,
+) -> Result<()> {
+    // build once rather than in every test process
+    cosmwasm_xtask::ops::dist_workspace(sh)?;
+
+    let listing = cmd!(sh, "cargo t -p e2e {args...} -- --list --format terse").read()?;
+
+    let test_cases: Vec<&str> = listing
+        .lines()
+        .filter_map(|line| line.strip_suffix(": test"))
+        .collect();
+
+    for test_case in test_cases {
+        eprintln!("running {test_case} on a fresh local network");
+
+        NeutronLocalnet::clean_state(sh)?;
+
+        let _handle = NeutronLocalnet::initialize(sh)?.start_local(sh)?;
+
+        wait_for_blocks(sh, &NeutronLocalnet::initialize(sh)?)?;
+
+        let mut test = cmd!(
+            sh,
+            "cargo t -p e2e {test_case} -- --exact --nocapture --test-threads 1"
+        )
+        .env("E2E_NO_DIST", "1");
+
+        if let Some(dir) = &record {
+            test = test.env("E2E_RECORD", dir);
+        }
+
+        test.run()?;
+    }
+
+    Ok(())
 }
 
 pub fn main() -> Result<()> {
@@ -44,6 +139,12 @@ pub fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    // resolve paths relative to the invocation directory before changing to the workspace root
+    let replay_script = match &cli.command {
+        Command::Replay { script } => Some(Script::load(script)?),
+        _ => None,
+    };
+
     let sh = Shell::new()?;
 
     let workspace_root =
@@ -51,6 +152,14 @@ pub fn main() -> Result<()> {
 
     sh.change_dir(workspace_root);
 
+    // the relayer spawned by the local network inherits the env of this process
+    if matches!(
+        cli.command,
+        Command::StartLocal | Command::Test(_) | Command::Replay { .. }
+    ) {
+        icq_relayer::export_config(&sh)?;
+    }
+
     match cli.command {
         Command::Dist => cosmwasm_xtask::ops::dist_workspace(&sh)?,
         Command::StartLocal => {
@@ -61,11 +170,77 @@ pub fn main() -> Result<()> {
         Command::CleanLocalState => NeutronLocalnet::clean_state(&sh)?,
         Command::CleanLocalAll => NeutronLocalnet::clean_all(&sh)?,
         Command::Test(cmd) => match cmd {
-            Test::E2e { args } => {
-                let _handle = NeutronLocalnet::initialize(&sh)?.start_local(&sh)?;
-                cmd!(sh, "cargo t {args...} -- --nocapture --test-threads 1").run()?;
+            Test::E2e {
+                args,
+                record,
+                fresh_per_test,
+            } => {
+                // the tests run from their package directory, so pass an absolute path
+                let record = match record {
+                    Some(dir) => {
+                        std::fs::create_dir_all(&dir)?;
+                        Some(dir.canonicalize()?)
+                    }
+                    None => None,
+                };
+
+                if fresh_per_test {
+                    test_e2e_fresh_per_test(&sh, args, record)?;
+                } else {
+                    let _handle = NeutronLocalnet::initialize(&sh)?.start_local(&sh)?;
+
+                    let mut test = cmd!(sh, "cargo t {args...} -- --nocapture --test-threads 1");
+
+                    if let Some(dir) = record {
+                        test = test.env("E2E_RECORD", dir);
+                    }
+
+                    test.run()?;
+                }
             }
         },
+        Command::Replay { .. } => {
+            let script = replay_script.expect("loaded above");
+
+            NeutronLocalnet::clean_state(&sh)?;
+
+            let _handle = NeutronLocalnet::initialize(&sh)?.start_local(&sh)?;
+
+            let network = NeutronLocalnet::initialize(&sh)?;
+
+            wait_for_blocks(&sh, &network)?;
+
+            cosmwasm_xtask::ops::dist_workspace(&sh)?;
+
+            script::replay(&sh, &network, &network.keys, &script)?;
+        }
+        Command::IcqRelayer(IcqRelayer::Config {
+            queue_capacity,
+            submit_period,
+            allowed_query_ids,
+            reset,
+        }) => {
+            let mut config = if reset {
+                icq_relayer::Config::default()
+            } else {
+                icq_relayer::Config::load(&sh)?
+            };
+
+            config.queue_capacity = queue_capacity.or(config.queue_capacity);
+
+            config.submit_period = submit_period.or(config.submit_period);
+
+            config.allowed_query_ids = allowed_query_ids.or(config.allowed_query_ids);
+
+            config.save(&sh)?;
+
+            eprintln!("icq relayer config written to {}", icq_relayer::CONFIG_PATH);
+
+            for (var, value) in config.env_vars() {
+                println!("{var}={value}");
+            }
+        }
+        Command::NewContract { name } => new_contract::new_contract(&sh, &name)?,
     }
 
     Ok(())
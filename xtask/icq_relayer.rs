@@ -0,0 +1,188 @@
+//! Tuning of the ICQ relayer started with the local network.
+//!
+//! The settings are kept in an env file under the workspace `target` directory, regenerated by
+//! `cargo x icq-relayer config`, & exported before the local network starts so the relayer it spawns picks them up.
+//! Unset settings are left to the relayer's defaults.
+
+use std::{fmt::Write, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use xshell::Shell;
+
+/// The env file holding the relayer settings, relative to the workspace root
+pub const CONFIG_PATH: &str = "target/icq-relayer.env";
+
+const QUEUE_CAPACITY_VAR: &str = "RELAYER_QUERIES_TASK_QUEUE_CAPACITY";
+const SUBMIT_PERIOD_VAR: &str = "RELAYER_MIN_KV_UPDATE_PERIOD";
+const ALLOWED_QUERY_IDS_VAR: &str = "RELAYER_REGISTRY_QUERY_IDS";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The most query results the relayer queues for submission at once
+    pub queue_capacity: Option<u32>,
+    /// The fewest blocks between two submissions of a KV query's result
+    pub submit_period: Option<u64>,
+    /// Only relay the results of these queries, all queries are relayed if unset
+    pub allowed_query_ids: Option<Vec<u64>>,
+}
+
+impl Config {
+    /// Load the settings from the config file, defaulting every setting if there is none
+    pub fn load(sh: &Shell) -> Result<Self> {
+        let path = config_path(sh);
+
+        if !sh.path_exists(&path) {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&sh.read_file(&path)?).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Regenerate the config file from the settings
+    pub fn save(&self, sh: &Shell) -> Result<()> {
+        let path = config_path(sh);
+
+        if let Some(dir) = path.parent() {
+            sh.create_dir(dir)?;
+        }
+
+        sh.write_file(path, self.to_env_file())?;
+
+        Ok(())
+    }
+
+    /// The relayer env vars of the set settings
+    #[must_use]
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = vec![];
+
+        if let Some(queue_capacity) = self.queue_capacity {
+            vars.push((QUEUE_CAPACITY_VAR, queue_capacity.to_string()));
+        }
+
+        if let Some(submit_period) = self.submit_period {
+            vars.push((SUBMIT_PERIOD_VAR, submit_period.to_string()));
+        }
+
+        if let Some(ids) = &self.allowed_query_ids {
+            let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+
+            vars.push((ALLOWED_QUERY_IDS_VAR, ids.join(",")));
+        }
+
+        vars
+    }
+
+    /// Export the relayer env vars to the current process, to be inherited by the relayer the local network spawns
+    pub fn export(&self) {
+        for (var, value) in self.env_vars() {
+            std::env::set_var(var, value);
+        }
+    }
+
+    fn to_env_file(&self) -> String {
+        let mut file =
+            String::from("# generated by `cargo x icq-relayer config`, edits are overwritten\n");
+
+        for (var, value) in self.env_vars() {
+            writeln!(file, "{var}={value}").expect("writing to a string never fails");
+        }
+
+        file
+    }
+
+    fn parse(env_file: &str) -> Result<Self> {
+        let mut config = Self::default();
+
+        let lines = env_file
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        for line in lines {
+            let (var, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected VAR=value, got '{line}'"))?;
+
+            match var {
+                QUEUE_CAPACITY_VAR => config.queue_capacity = Some(value.parse()?),
+                SUBMIT_PERIOD_VAR => config.submit_period = Some(value.parse()?),
+                ALLOWED_QUERY_IDS_VAR => {
+                    let ids = value
+                        .split(',')
+                        .filter(|id| !id.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()?;
+
+                    config.allowed_query_ids = Some(ids);
+                }
+                _ => return Err(anyhow!("unknown relayer setting {var}")),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn config_path(sh: &Shell) -> PathBuf {
+    sh.current_dir().join(CONFIG_PATH)
+}
+
+/// Export the configured relayer settings, must be called before the local network starts
+pub fn export_config(sh: &Shell) -> Result<()> {
+    Config::load(sh)?.export();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Config;
+
+    #[test]
+    fn env_file_roundtrips() {
+        let config = Config {
+            queue_capacity: Some(50),
+            submit_period: Some(2),
+            allowed_query_ids: Some(vec![1, 2, 3]),
+        };
+
+        let env_file = config.to_env_file();
+
+        assert!(env_file.contains("RELAYER_QUERIES_TASK_QUEUE_CAPACITY=50\n"));
+        assert!(env_file.contains("RELAYER_MIN_KV_UPDATE_PERIOD=2\n"));
+        assert!(env_file.contains("RELAYER_REGISTRY_QUERY_IDS=1,2,3\n"));
+
+        assert_eq!(Config::parse(&env_file).unwrap(), config);
+    }
+
+    #[test]
+    fn unset_settings_parsed_as_defaults() {
+        let config = Config::parse("# a comment\n\nRELAYER_MIN_KV_UPDATE_PERIOD=5\n").unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                submit_period: Some(5),
+                ..Config::default()
+            }
+        );
+
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn empty_allowed_query_ids_parsed() {
+        let config = Config::parse("RELAYER_REGISTRY_QUERY_IDS=").unwrap();
+
+        assert_eq!(config.allowed_query_ids, Some(vec![]));
+    }
+
+    #[test]
+    fn invalid_env_files_rejected() {
+        assert!(Config::parse("RELAYER_QUERIES_TASK_QUEUE_CAPACITY").is_err());
+        assert!(Config::parse("RELAYER_QUERIES_TASK_QUEUE_CAPACITY=many").is_err());
+        assert!(Config::parse("RELAYER_REGISTRY_QUERY_IDS=1,x").is_err());
+        assert!(Config::parse("RELAYER_UNKNOWN=1").is_err());
+    }
+}
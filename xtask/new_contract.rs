@@ -0,0 +1,145 @@
+//! Scaffolding of a new example contract, wired into the workspace & the e2e tests.
+//!
+//! The contract is generated from the templates in `templates/new-contract`, with its own hundred of error codes
+//! following the highest hundred taken by the existing contracts.
+
+use anyhow::{bail, Result};
+use xshell::Shell;
+
+const CARGO_TOML: &str = include_str!("templates/new-contract/Cargo.toml.tmpl");
+const CONTRACT_RS: &str = include_str!("templates/new-contract/contract.rs.tmpl");
+const MSGS_RS: &str = include_str!("templates/new-contract/msgs.rs.tmpl");
+const STATE_RS: &str = include_str!("templates/new-contract/state.rs.tmpl");
+const SCHEMA_RS: &str = include_str!("templates/new-contract/schema.rs.tmpl");
+const E2E_RS: &str = include_str!("templates/new-contract/e2e.rs.tmpl");
+
+/// Contract names are kebab-case package names, e.g. `ica-swap`
+pub fn validate_name(name: &str) -> Result<()> {
+    let is_kebab_case = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.split('-').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        });
+
+    if !is_kebab_case {
+        bail!("invalid contract name {name:?}, expected a kebab-case name such as ica-swap");
+    }
+
+    Ok(())
+}
+
+/// The first error code of the hundred following the highest hundred of error codes taken by `contracts`
+#[must_use]
+pub fn next_error_code_base(contracts: &[String]) -> u32 {
+    let highest_hundred = contracts
+        .iter()
+        .flat_map(|contract| contract.split("[E").skip(1))
+        .filter_map(|code| code.get(..4)?.parse::<u32>().ok())
+        .map(|code| code / 100)
+        .max()
+        .unwrap_or_default();
+
+    (highest_hundred + 1) * 100
+}
+
+/// Scaffold the contract `name` in `contracts/<name>`, add it to the workspace dependencies & add an e2e test stub
+pub fn new_contract(sh: &Shell, name: &str) -> Result<()> {
+    validate_name(name)?;
+
+    let dir = format!("contracts/{name}");
+
+    if sh.path_exists(&dir) {
+        bail!("{dir} already exists");
+    }
+
+    let crate_name = name.replace('-', "_");
+
+    let mut package_names = vec![];
+
+    let mut contracts = vec![];
+
+    for path in sh.read_dir("contracts")? {
+        let contract = path.join("contract.rs");
+
+        if sh.path_exists(&contract) {
+            contracts.push(sh.read_file(contract)?);
+        }
+
+        if let Some(package) = path.file_name().and_then(|name| name.to_str()) {
+            package_names.push(package.to_owned());
+        }
+    }
+
+    let code_base = next_error_code_base(&contracts);
+
+    let render = |template: &str| {
+        template
+            .replace("__NAME__", name)
+            .replace("__CRATE__", &crate_name)
+            .replace("__UNAUTHORIZED_PADDED__", &format!("{code_base:04}"))
+            .replace("__UNAUTHORIZED__", &code_base.to_string())
+    };
+
+    for (file, template) in [
+        ("Cargo.toml", CARGO_TOML),
+        ("contract.rs", CONTRACT_RS),
+        ("msgs.rs", MSGS_RS),
+        ("state.rs", STATE_RS),
+        ("schema.rs", SCHEMA_RS),
+    ] {
+        sh.write_file(format!("{dir}/{file}"), render(template))?;
+    }
+
+    let workspace_manifest = insert_after_last(
+        &sh.read_file("Cargo.toml")?,
+        |line| line.contains("path = \"contracts/"),
+        &format!("{name} = {{ path = \"{dir}\" }}"),
+    )?;
+
+    sh.write_file("Cargo.toml", workspace_manifest)?;
+
+    let e2e_manifest = insert_after_last(
+        &sh.read_file("tests/e2e/Cargo.toml")?,
+        |line| {
+            package_names
+                .iter()
+                .any(|package| line.starts_with(&format!("{package}.workspace")))
+        },
+        &format!("{name}.workspace = true"),
+    )?;
+
+    sh.write_file("tests/e2e/Cargo.toml", e2e_manifest)?;
+
+    let mut e2e = sh.read_file("tests/e2e/e2e.rs")?;
+
+    e2e.push_str(&render(E2E_RS));
+
+    sh.write_file("tests/e2e/e2e.rs", e2e)?;
+
+    eprintln!("scaffolded {dir} with error codes from E{code_base:04} & an e2e test: cargo x test e2e {crate_name}");
+
+    Ok(())
+}
+
+/// Insert `new_line` after the last line of `file` matching `predicate`
+fn insert_after_last(
+    file: &str,
+    predicate: impl Fn(&str) -> bool,
+    new_line: &str,
+) -> Result<String> {
+    let mut lines: Vec<&str> = file.lines().collect();
+
+    let Some(idx) = lines.iter().rposition(|line| predicate(line)) else {
+        bail!("no line to insert {new_line:?} after");
+    };
+
+    lines.insert(idx + 1, new_line);
+
+    let mut file = lines.join("\n");
+
+    file.push('\n');
+
+    Ok(file)
+}
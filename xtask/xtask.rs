@@ -1 +1,2 @@
-// add tasks as needed
\ No newline at end of file
+pub mod icq_relayer;
+pub mod new_contract;